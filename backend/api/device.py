@@ -1,15 +1,24 @@
 """ESP32 Device Connection API.
 
-Handles device discovery, connection management, and emergency recovery.
+Handles device discovery, connection management, pairing, and emergency recovery.
 """
 
 import asyncio
+import hashlib
 import ipaddress
 import logging
+import math
+import re
+import secrets
 import socket
+import ssl
+import time
 from datetime import datetime
 
-from fastapi import APIRouter, HTTPException
+from api.api_keys import APIKeyCreate, create_api_key, get_api_key_by_id, validate_api_key
+from config import settings
+from db import get_db
+from fastapi import APIRouter, Depends, HTTPException
 from pydantic import BaseModel
 
 logger = logging.getLogger(__name__)
@@ -30,6 +39,17 @@ def _is_private_ip(ip: str) -> bool:
 router = APIRouter(prefix="/device", tags=["device"])
 
 
+async def require_control_key(api_key: dict = Depends(validate_api_key)) -> dict:
+    """Require an API key with control permission that is not the display's own key.
+
+    Guards the calls that change who holds the display's key or which server
+    it trusts, so neither a lesser key nor the display itself can take them over.
+    """
+    if not api_key["can_control"] or api_key["id"] == await get_device_key_id():
+        raise HTTPException(status_code=403, detail="This needs an API key with control permission")
+    return api_key
+
+
 class DeviceInfo(BaseModel):
     """Information about a discovered or connected device."""
 
@@ -208,8 +228,8 @@ async def reboot_device():
     if not is_display_connected():
         raise HTTPException(status_code=400, detail="No device connected")
 
-    queue_display_command("reboot")
-    return {"success": True, "message": "Reboot command queued"}
+    command_id = queue_display_command("reboot")
+    return {"success": True, "message": "Reboot command queued", "command_id": command_id}
 
 
 @router.post("/update")
@@ -220,8 +240,34 @@ async def update_device():
     if not is_display_connected():
         raise HTTPException(status_code=400, detail="No device connected")
 
-    queue_display_command("update")
-    return {"success": True, "message": "Update command queued"}
+    command_id = queue_display_command("update")
+    return {"success": True, "message": "Update command queued", "command_id": command_id}
+
+
+@router.post("/server-url")
+async def set_device_server_url(url: str = "", _api_key: dict = Depends(require_control_key)):
+    """Set the backend URL stored on the device.
+
+    Needs an API key with control permission (not the display's own key), as
+    the URL decides which server the display trusts.
+
+    Args:
+        url: http(s) URL of the backend, e.g. "http://spoolbuddy.local:3000".
+            An empty value clears the stored URL so the device falls back to mDNS discovery.
+    """
+    from main import is_display_connected, queue_display_command
+
+    if not is_display_connected():
+        raise HTTPException(status_code=400, detail="No device connected")
+
+    url = url.strip().rstrip("/")
+    if url and not url.startswith(("http://", "https://")):
+        raise HTTPException(status_code=400, detail="URL must start with http:// or https://")
+
+    command_id = queue_display_command("set_server_url", url=url)
+    if url:
+        return {"success": True, "message": f"Server URL command queued ({url})", "command_id": command_id}
+    return {"success": True, "message": "Server URL clear command queued", "command_id": command_id}
 
 
 @router.post("/factory-reset")
@@ -245,8 +291,8 @@ async def scale_tare():
     if not is_display_connected():
         raise HTTPException(status_code=400, detail="No device connected")
 
-    queue_display_command("scale_tare")
-    return {"success": True, "message": "Tare command queued"}
+    command_id = queue_display_command("scale_tare")
+    return {"success": True, "message": "Tare command queued", "command_id": command_id}
 
 
 @router.post("/scale/calibrate")
@@ -262,8 +308,12 @@ async def scale_calibrate(known_weight: float):
         raise HTTPException(status_code=400, detail="No device connected")
 
     # Queue calibrate command with weight parameter
-    queue_display_command(f"scale_calibrate:{known_weight:.1f}")
-    return {"success": True, "message": f"Calibrate command queued (known weight: {known_weight}g)"}
+    command_id = queue_display_command("scale_calibrate", known_weight=known_weight)
+    return {
+        "success": True,
+        "message": f"Calibrate command queued (known weight: {known_weight}g)",
+        "command_id": command_id,
+    }
 
 
 @router.post("/scale/reset")
@@ -274,8 +324,341 @@ async def scale_reset():
     if not is_display_connected():
         raise HTTPException(status_code=400, detail="No device connected")
 
-    queue_display_command("scale_reset")
-    return {"success": True, "message": "Scale calibration reset command queued"}
+    command_id = queue_display_command("scale_reset")
+    return {"success": True, "message": "Scale calibration reset command queued", "command_id": command_id}
+
+
+SCALE_SAMPLE_RATES = (10, 20, 40, 80, 320)
+SCALE_GAINS = (1, 2, 4, 8, 16, 32, 64, 128)
+SCALE_LDO_MV = tuple(range(2400, 4501, 300))
+SCALE_CHANNELS = (1, 2)
+SCALE_MAX_WINDOW = 50
+SCALE_TEMP_SOURCES = ("none", "internal", "ams")
+
+
+@router.post("/scale/config")
+async def scale_config(
+    sample_rate: int | None = None,
+    gain: int | None = None,
+    ldo_mv: int | None = None,
+    channel: int | None = None,
+    median_window: int | None = None,
+    average_window: int | None = None,
+    ema_alpha: float | None = None,
+    snap_threshold_g: float | None = None,
+    stability_window: int | None = None,
+    stability_std_dev_g: float | None = None,
+    auto_zero: bool | None = None,
+    auto_zero_band_g: float | None = None,
+    auto_zero_rate_g: float | None = None,
+    temp_source: str | None = None,
+    zero_g_per_c: float | None = None,
+    span_ppm_per_c: float | None = None,
+):
+    """Change the scale ADC, filter, stability, zero tracking and drift compensation settings.
+
+    Omitted settings are kept; with none given the command only reads back the
+    active settings. The command result carries the active settings. The
+    display keeps them across restarts.
+
+    Args:
+        sample_rate: Samples per second (10, 20, 40, 80 or 320)
+        gain: PGA gain (1-128, powers of two)
+        ldo_mv: LDO voltage in millivolts (2400-4500 in 300 mV steps)
+        channel: Input channel (1 or 2)
+        median_window: Median window for spike rejection (1-50 readings, 1 = off)
+        average_window: Moving average window (1-50 readings, 1 = off)
+        ema_alpha: EMA weight of a new reading (0-1, 1 = off)
+        snap_threshold_g: Change in grams on which the EMA jumps to the new reading
+        stability_window: Readings the stability is judged over (2-50)
+        stability_std_dev_g: Largest standard deviation in grams of a stable weight
+        auto_zero: Track the zero while the empty scale is stable
+        auto_zero_band_g: Largest weight taken as an empty scale (0-50 g)
+        auto_zero_rate_g: Largest zero correction per reading (grams, up to the band)
+        temp_source: Temperature for drift compensation ("none", "internal" or "ams")
+        zero_g_per_c: Zero drift in grams per °C
+        span_ppm_per_c: Span drift in ppm per °C (below 10000 either way)
+    """
+    from main import is_display_connected, queue_display_command
+
+    allowed = {
+        "sample_rate": lambda v: v in SCALE_SAMPLE_RATES,
+        "gain": lambda v: v in SCALE_GAINS,
+        "ldo_mv": lambda v: v in SCALE_LDO_MV,
+        "channel": lambda v: v in SCALE_CHANNELS,
+        "median_window": lambda v: 1 <= v <= SCALE_MAX_WINDOW,
+        "average_window": lambda v: 1 <= v <= SCALE_MAX_WINDOW,
+        "ema_alpha": lambda v: 0 < v <= 1,
+        "snap_threshold_g": lambda v: v > 0,
+        "stability_window": lambda v: 2 <= v <= SCALE_MAX_WINDOW,
+        "stability_std_dev_g": lambda v: v > 0,
+        "auto_zero": lambda v: True,
+        "auto_zero_band_g": lambda v: 0 < v <= 50,
+        "auto_zero_rate_g": lambda v: v > 0,
+        "temp_source": lambda v: v in SCALE_TEMP_SOURCES,
+        "zero_g_per_c": math.isfinite,
+        "span_ppm_per_c": lambda v: abs(v) < 10000,
+    }
+    params = {
+        "sample_rate": sample_rate,
+        "gain": gain,
+        "ldo_mv": ldo_mv,
+        "channel": channel,
+        "median_window": median_window,
+        "average_window": average_window,
+        "ema_alpha": ema_alpha,
+        "snap_threshold_g": snap_threshold_g,
+        "stability_window": stability_window,
+        "stability_std_dev_g": stability_std_dev_g,
+        "auto_zero": auto_zero,
+        "auto_zero_band_g": auto_zero_band_g,
+        "auto_zero_rate_g": auto_zero_rate_g,
+        "temp_source": temp_source,
+        "zero_g_per_c": zero_g_per_c,
+        "span_ppm_per_c": span_ppm_per_c,
+    }
+    params = {name: value for name, value in params.items() if value is not None}
+    for name, value in params.items():
+        if not allowed[name](value):
+            raise HTTPException(status_code=400, detail=f"Invalid {name}: {value}")
+
+    if not is_display_connected():
+        raise HTTPException(status_code=400, detail="No device connected")
+
+    command_id = queue_display_command("scale_config", **params)
+    return {"success": True, "message": "Scale config command queued", "command_id": command_id}
+
+
+@router.get("/commands/{command_id}")
+async def get_device_command(command_id: str):
+    """Get the status of a queued device command.
+
+    Status is one of: pending (not yet delivered), sent (delivered, no result yet),
+    ok, error or rejected (reported by the device).
+    """
+    from main import get_display_command_result
+
+    result = get_display_command_result(command_id)
+    if result is None:
+        raise HTTPException(status_code=404, detail="Command not found")
+    return result
+
+
+# === Pairing and device API key ===
+#
+# The display obtains its API key by pairing: it requests a code, shows it on
+# screen and polls until the user enters the code in the web UI. Once a
+# display is paired, the /api/display/ endpoints require its key.
+
+DEVICE_KEY_SETTING = "spoolbuddy-device-api-key-id"
+PAIRING_TIMEOUT_SEC = 300
+PAIRING_MAX_PENDING = 8
+
+# pairing_id -> {"device_id", "code", "created", "key"}; key is set on approval
+_pairings: dict[str, dict] = {}
+# command_id -> (new_key_id, old_key_id) for rotations awaiting the display's result
+_key_rotations: dict[str, tuple[int, int]] = {}
+
+
+def _prune_pairings():
+    """Drop expired pairing requests."""
+    now = time.time()
+    for pairing_id in [p for p, v in _pairings.items() if now - v["created"] > PAIRING_TIMEOUT_SEC]:
+        del _pairings[pairing_id]
+
+
+async def get_device_key_id() -> int | None:
+    """ID of the API key held by the paired display, if any."""
+    db = await get_db()
+    value = await db.get_setting(DEVICE_KEY_SETTING)
+    return int(value) if value else None
+
+
+async def _create_device_key():
+    return await create_api_key(
+        APIKeyCreate(name="SpoolBuddy Display", can_read=True, can_write=True, can_control=True)
+    )
+
+
+async def _revoke_key(key_id: int):
+    db = await get_db()
+    await db.conn.execute("DELETE FROM api_keys WHERE id = ?", (key_id,))
+    await db.conn.commit()
+
+
+@router.post("/pair")
+async def start_pairing(device_id: str = ""):
+    """Start pairing (called by the display).
+
+    Returns the id the display polls with and a 6-digit code it shows on screen.
+    """
+    _prune_pairings()
+    device_id = device_id[:32]
+
+    # A new request from the same display replaces its previous one
+    if device_id:
+        for pairing_id in [p for p, v in _pairings.items() if v["device_id"] == device_id]:
+            del _pairings[pairing_id]
+    while len(_pairings) >= PAIRING_MAX_PENDING:
+        del _pairings[next(iter(_pairings))]
+
+    codes = {p["code"] for p in _pairings.values()}
+    code = f"{secrets.randbelow(1_000_000):06d}"
+    while code in codes:
+        code = f"{secrets.randbelow(1_000_000):06d}"
+
+    pairing_id = secrets.token_urlsafe(16)
+    _pairings[pairing_id] = {"device_id": device_id, "code": code, "created": time.time(), "key": None}
+    logger.info(f"Display {device_id or '(unknown)'} requested pairing")
+    return {"pairing_id": pairing_id, "code": code, "expires_in": PAIRING_TIMEOUT_SEC}
+
+
+@router.post("/pair/approve")
+async def approve_pairing(code: str, _api_key: dict = Depends(require_control_key)):
+    """Approve a pairing request using the code shown on the display.
+
+    Needs an API key with control permission (not the display's own key).
+    Creates the display's API key. A previously paired display's key is revoked.
+    """
+    _prune_pairings()
+    code = code.strip()
+    pairing = next((p for p in _pairings.values() if p["code"] == code and p["key"] is None), None)
+    if pairing is None:
+        raise HTTPException(status_code=404, detail="Unknown or expired pairing code")
+
+    old_key_id = await get_device_key_id()
+    created = await _create_device_key()
+    db = await get_db()
+    await db.set_setting(DEVICE_KEY_SETTING, str(created.id))
+    if old_key_id is not None:
+        await _revoke_key(old_key_id)
+
+    pairing["key"] = created.key
+    logger.info(f"Display {pairing['device_id'] or '(unknown)'} paired (key {created.key_prefix})")
+    return {"success": True, "message": "Display paired", "key_prefix": created.key_prefix}
+
+
+@router.get("/pair/{pairing_id}")
+async def get_pairing_status(pairing_id: str):
+    """Get the state of a pairing request (polled by the display).
+
+    Once approved, the response carries the API key until the request expires.
+    """
+    _prune_pairings()
+    pairing = _pairings.get(pairing_id)
+    if pairing is None:
+        raise HTTPException(status_code=404, detail="Pairing request not found")
+    if pairing["key"] is None:
+        return {"status": "pending"}
+    return {"status": "approved", "key": pairing["key"]}
+
+
+@router.get("/auth")
+async def get_device_auth():
+    """Get the display pairing state."""
+    _prune_pairings()
+    key = None
+    key_id = await get_device_key_id()
+    if key_id is not None:
+        try:
+            key = await get_api_key_by_id(key_id)
+        except HTTPException:
+            key = None  # Revoked from the API keys list
+
+    return {
+        "paired": key is not None,
+        "key_prefix": key.key_prefix if key else None,
+        "last_used": key.last_used if key else None,
+        "pending_pairings": sum(1 for p in _pairings.values() if p["key"] is None),
+    }
+
+
+@router.delete("/auth")
+async def unpair_device(_api_key: dict = Depends(require_control_key)):
+    """Unpair the display by revoking its API key.
+
+    Needs an API key with control permission (not the display's own key).
+    The display will request a new pairing code.
+    """
+    key_id = await get_device_key_id()
+    if key_id is None:
+        raise HTTPException(status_code=400, detail="Display is not paired")
+
+    await _revoke_key(key_id)
+    db = await get_db()
+    await db.delete_setting(DEVICE_KEY_SETTING)
+    return {"success": True, "message": "Display unpaired"}
+
+
+@router.post("/rotate-key")
+async def rotate_device_key(_api_key: dict = Depends(require_control_key)):
+    """Replace the display's API key.
+
+    Needs an API key with control permission (not the display's own key).
+    The new key is pushed with a rotate_api_key command; the old key is revoked
+    once the display reports that it stored the new one.
+    """
+    from main import is_display_connected, queue_display_command
+
+    if not is_display_connected():
+        raise HTTPException(status_code=400, detail="No device connected")
+
+    old_key_id = await get_device_key_id()
+    if old_key_id is None:
+        raise HTTPException(status_code=400, detail="Display is not paired")
+
+    created = await _create_device_key()
+    command_id = queue_display_command("rotate_api_key", key=created.key)
+    _key_rotations[command_id] = (created.id, old_key_id)
+    return {"success": True, "message": "Key rotation command queued", "command_id": command_id}
+
+
+async def complete_key_rotation(command_id: str, status: str):
+    """Finish a key rotation once the display reported the command result."""
+    rotation = _key_rotations.pop(command_id, None)
+    if rotation is None:
+        return
+
+    new_key_id, old_key_id = rotation
+    if status == "ok":
+        db = await get_db()
+        await db.set_setting(DEVICE_KEY_SETTING, str(new_key_id))
+        await _revoke_key(old_key_id)
+        logger.info("Display API key rotated")
+    else:
+        await _revoke_key(new_key_id)
+        logger.warning(f"Display API key rotation {status}, keeping the old key")
+
+
+PEM_CERTIFICATE_RE = re.compile(r"-----BEGIN CERTIFICATE-----.+?-----END CERTIFICATE-----", re.DOTALL)
+
+
+@router.get("/tls-certificate")
+async def get_tls_certificate():
+    """Get the certificate the display pins for HTTPS (trust on first use).
+
+    Only the certificate blocks of the configured file are returned, never a
+    private key. The fingerprint is the SHA-256 of the first certificate (DER).
+    """
+    path = settings.tls_pin_certfile or settings.ssl_certfile
+    if path is None:
+        raise HTTPException(status_code=404, detail="HTTPS is not configured")
+
+    try:
+        pem = path.read_text()
+    except OSError as e:
+        logger.warning(f"Failed to read TLS certificate {path}: {e}")
+        raise HTTPException(status_code=500, detail="Certificate not readable") from e
+
+    certificates = PEM_CERTIFICATE_RE.findall(pem)
+    if not certificates:
+        raise HTTPException(status_code=500, detail="No certificate found")
+
+    der = ssl.PEM_cert_to_DER_cert(certificates[0])
+    return {
+        "certificate": "\n".join(certificates) + "\n",
+        "fingerprint": hashlib.sha256(der).hexdigest(),
+    }
 
 
 class RecoveryInfo(BaseModel):
@@ -79,16 +79,23 @@ def set_printer_manager(manager):
 @router.get("", response_model=list[PrinterWithStatus])
 async def list_printers():
     """Get all printers with connection status and live state."""
-    db = await get_db()
-    printers = await db.get_printers()
-
-    # Get connection statuses
     statuses = _printer_manager.get_connection_statuses() if _printer_manager else {}
     if statuses:
         logger.info(f"Printer connection statuses: {statuses}")
     else:
         logger.info(f"No printer connections active (_printer_manager={_printer_manager is not None})")
 
+    return await get_printers_with_status()
+
+
+async def get_printers_with_status() -> list[PrinterWithStatus]:
+    """Build the printer list with live state (shared by the REST route and the display event stream)."""
+    db = await get_db()
+    printers = await db.get_printers()
+
+    # Get connection statuses
+    statuses = _printer_manager.get_connection_statuses() if _printer_manager else {}
+
     result = []
     for printer in printers:
         connected = statuses.get(printer.serial, False)
@@ -98,6 +105,7 @@ async def list_printers():
         mc_remaining_time = None
         cover_url = None
         ams_units = []
+        vt_tray = None
         tray_now = None
         tray_now_left = None
         tray_now_right = None
@@ -115,6 +123,7 @@ async def list_printers():
                 subtask_name = state.subtask_name
                 mc_remaining_time = state.mc_remaining_time
                 ams_units = state.ams_units
+                vt_tray = state.vt_tray
                 tray_now = state.tray_now
                 tray_now_left = state.tray_now_left
                 tray_now_right = state.tray_now_right
@@ -136,6 +145,7 @@ async def list_printers():
                 mc_remaining_time=mc_remaining_time,
                 cover_url=cover_url,
                 ams_units=ams_units,
+                vt_tray=vt_tray,
                 tray_now=tray_now,
                 tray_now_left=tray_now_left,
                 tray_now_right=tray_now_right,
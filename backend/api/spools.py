@@ -1,5 +1,10 @@
+import hashlib
+import json
+
 from db import get_db
-from fastapi import APIRouter, HTTPException, Query
+from fastapi import APIRouter, HTTPException, Query, Request, Response
+from fastapi.encoders import jsonable_encoder
+from fastapi.responses import JSONResponse
 from models import Spool, SpoolCreate, SpoolUpdate
 from pydantic import BaseModel
 
@@ -66,6 +71,28 @@ async def list_untagged_spools():
     return await db.get_untagged_spools()
 
 
+@router.get("/by-tag", response_model=Spool)
+async def get_spool_by_tag(tag_id: str, request: Request):
+    """Get the (non-archived) spool linked to an NFC tag.
+
+    Used by the display on every scan. The ETag is the spool's version (a hash
+    of its contents, so it changes with every update, not only once per second
+    like updated_at); a display revalidating its cached copy with If-None-Match
+    gets 304 while it is current.
+    """
+    db = await get_db()
+    spool = await db.get_spool_by_tag(tag_id)
+    if not spool:
+        raise HTTPException(status_code=404, detail="Spool not found")
+
+    content = jsonable_encoder(spool)
+    version = hashlib.sha256(json.dumps(content, sort_keys=True).encode()).hexdigest()[:16]
+    etag = f'"{version}"'
+    if request.headers.get("if-none-match") == etag:
+        return Response(status_code=304, headers={"ETag": etag})
+    return JSONResponse(content=content, headers={"ETag": etag})
+
+
 @router.get("/{spool_id}", response_model=Spool)
 async def get_spool(spool_id: str):
     """Get a single spool."""
@@ -16,6 +16,13 @@ class Settings(BaseSettings):
     host: str = "0.0.0.0"  # nosec B104
     port: int = 3000
 
+    # HTTPS (serve TLS directly; both files PEM)
+    ssl_certfile: Path | None = None
+    ssl_keyfile: Path | None = None
+    # Certificate the display pins on first pairing (defaults to ssl_certfile;
+    # set to a CA certificate to pin the issuer instead of the server certificate)
+    tls_pin_certfile: Path | None = None
+
     # Database
     database_path: Path = Path("spoolbuddy.db")
 
@@ -4,6 +4,8 @@ import json
 import logging
 import socket
 import time
+import uuid
+from collections import OrderedDict
 from contextlib import asynccontextmanager
 from pathlib import Path
 
@@ -21,16 +23,19 @@ from api import (
     tags_router,
     updates_router,
 )
+from api.api_keys import validate_api_key
 from api.cloud import router as cloud_router
+from api.device import complete_key_rotation, get_device_key_id
 from api.printers import set_printer_manager
 from api.settings import router as settings_router
 from api.support import init_debug_logging
 from config import settings
 from db import get_db
-from fastapi import FastAPI, WebSocket, WebSocketDisconnect
+from fastapi import FastAPI, HTTPException, Request, Response, WebSocket, WebSocketDisconnect
 from fastapi.middleware.cors import CORSMiddleware
+from fastapi.responses import JSONResponse, StreamingResponse
 from fastapi.staticfiles import StaticFiles
-from models import PrinterState
+from models import DeviceCommandResult, PrinterState
 from mqtt import PrinterManager
 from tags import TagDecoder
 from usage_tracker import UsageTracker, estimate_weight_from_percent
@@ -140,8 +145,19 @@ _mdns_service: ServiceInfo | None = None
 _display_last_seen: float = 0
 _display_connected: bool = False
 DISPLAY_TIMEOUT_SEC = 10  # Consider disconnected after 10s of no requests
-# Pending commands for display (checked on heartbeat)
-_display_pending_command: str | None = None
+# Pending commands for display (delivered on heartbeat, oldest first)
+_display_pending_commands: list[dict] = []
+# Results reported by the display, keyed by command id (most recent last)
+_display_command_results: dict[str, dict] = {}
+DISPLAY_COMMAND_RESULTS_MAX = 50
+# Server-Sent Events subscribers (display push updates), one queue per stream
+_display_event_queues: set[asyncio.Queue] = set()
+# Last printer JSON sent on the event stream, keyed by serial (for delta detection)
+_display_printer_snapshots: dict[str, str] = {}
+_display_delta_pending: bool = False
+DISPLAY_EVENT_QUEUE_MAX = 32
+DISPLAY_EVENT_KEEPALIVE_SEC = 15
+DISPLAY_DELTA_DEBOUNCE_SEC = 0.2
 # Device firmware version (reported by device in heartbeat)
 _display_firmware_version: str | None = None
 # Device reports update is available
@@ -318,19 +334,112 @@ def is_display_connected() -> bool:
     return (time.time() - _display_last_seen) < DISPLAY_TIMEOUT_SEC
 
 
-def queue_display_command(command: str):
-    """Queue a command for the display to execute on next heartbeat."""
-    global _display_pending_command
-    _display_pending_command = command
-    logger.info(f"Queued display command: {command}")
+def queue_display_command(command: str, **params) -> str:
+    """Queue a command for the display to execute on next heartbeat.
 
+    Returns the command id the device echoes back when reporting the result.
+    """
+    command_id = uuid.uuid4().hex[:12]
+    _display_pending_commands.append({"id": command_id, "type": command, **params})
+    _display_command_results[command_id] = {"id": command_id, "type": command, "status": "pending"}
+    while len(_display_command_results) > DISPLAY_COMMAND_RESULTS_MAX:
+        del _display_command_results[next(iter(_display_command_results))]
+    logger.info(f"Queued display command {command_id}: {command} {_redact_command(params) or ''}")
+    return command_id
+
+
+def _redact_command(cmd: dict) -> dict:
+    """Copy of a command (or its parameters) that is safe to log."""
+    return {k: ("***" if k == "key" else v) for k, v in cmd.items()}
+
+
+def pop_display_commands() -> list[dict]:
+    """Get and clear all pending display commands, marking them as sent."""
+    commands = list(_display_pending_commands)
+    _display_pending_commands.clear()
+    for cmd in commands:
+        result = _display_command_results.get(cmd["id"])
+        if result and result["status"] == "pending":
+            result["status"] = "sent"
+    return commands
+
+
+def get_display_command_result(command_id: str) -> dict | None:
+    """Get the current state of a display command (pending, sent, ok, error, rejected)."""
+    return _display_command_results.get(command_id)
+
+
+def _legacy_command_string(cmd: dict) -> str:
+    """Format a command the way firmware before the typed protocol expects it."""
+    if cmd["type"] == "scale_calibrate":
+        return f"scale_calibrate:{cmd['known_weight']:.1f}"
+    if cmd["type"] == "set_server_url":
+        return f"set_server_url:{cmd['url']}"
+    return cmd["type"]
+
+
+def format_sse(event: str, data) -> str:
+    """Format a Server-Sent Events frame."""
+    return f"event: {event}\ndata: {json.dumps(data, separators=(',', ':'))}\n\n"
+
+
+def publish_display_event(event: str, data) -> None:
+    """Queue an event for every display event stream.
+
+    A subscriber that falls too far behind is dropped; the device reconnects
+    and gets a fresh snapshot.
+    """
+    frame = format_sse(event, data)
+    for queue in list(_display_event_queues):
+        try:
+            queue.put_nowait(frame)
+        except asyncio.QueueFull:
+            logger.warning("Display event stream lagging, dropping subscriber")
+            _display_event_queues.discard(queue)
 
-def pop_display_command() -> str | None:
-    """Get and clear the pending display command."""
-    global _display_pending_command
-    cmd = _display_pending_command
-    _display_pending_command = None
-    return cmd
+
+async def publish_printer_deltas():
+    """Send printers whose display-relevant state changed since the last event."""
+    from api.printers import get_printers_with_status
+
+    printers = await get_printers_with_status()
+    current = {p.serial: json.dumps(p.model_dump(mode="json"), sort_keys=True) for p in printers}
+
+    if set(current) != set(_display_printer_snapshots):
+        # Printer added or removed - resend the whole list
+        _display_printer_snapshots.clear()
+        _display_printer_snapshots.update(current)
+        publish_display_event("printers", [p.model_dump(mode="json") for p in printers])
+        return
+
+    for printer in printers:
+        if _display_printer_snapshots.get(printer.serial) != current[printer.serial]:
+            _display_printer_snapshots[printer.serial] = current[printer.serial]
+            publish_display_event("printer", printer.model_dump(mode="json"))
+
+
+def schedule_printer_deltas():
+    """Coalesce bursts of printer updates into one delta pass."""
+    global _display_delta_pending
+
+    if not _display_event_queues or _display_delta_pending:
+        return
+
+    async def run():
+        global _display_delta_pending
+        await asyncio.sleep(DISPLAY_DELTA_DEBOUNCE_SEC)
+        _display_delta_pending = False
+        try:
+            await publish_printer_deltas()
+        except Exception as e:
+            logger.warning(f"Failed to publish printer deltas: {e}")
+
+    try:
+        loop = asyncio.get_running_loop()
+        _display_delta_pending = True
+        loop.create_task(run())
+    except RuntimeError:
+        pass  # No running loop
 
 
 async def udp_log_listener():
@@ -505,6 +614,8 @@ def on_printer_state_update(serial: str, state: PrinterState):
     except RuntimeError:
         pass  # No running loop
 
+    schedule_printer_deltas()
+
 
 def on_printer_connect(serial: str):
     """Handle printer connection from MQTT."""
@@ -523,6 +634,8 @@ def on_printer_connect(serial: str):
     except RuntimeError:
         pass  # No running loop
 
+    schedule_printer_deltas()
+
 
 def on_printer_disconnect(serial: str):
     """Handle printer disconnection from MQTT."""
@@ -544,6 +657,8 @@ def on_printer_disconnect(serial: str):
     except RuntimeError:
         pass  # No running loop
 
+    schedule_printer_deltas()
+
 
 def on_nozzle_count_update(serial: str, nozzle_count: int):
     """Handle nozzle count detection from MQTT (auto-detect dual-nozzle printers)."""
@@ -696,7 +811,7 @@ async def lifespan(app: FastAPI):
             "SpoolBuddy._spbuddy-srv._tcp.local.",
             addresses=[socket.inet_aton(local_ip)],
             port=settings.port,
-            properties={"version": "0.1.0", "api": "/api"},
+            properties={"version": "0.1.0", "api": "/api", "scheme": "https" if settings.ssl_certfile else "http"},
         )
         await _zeroconf.async_register_service(_mdns_service)
         logger.info(f"mDNS service registered: {local_ip}:{settings.port} (_spbuddy-srv._tcp)")
@@ -754,6 +869,95 @@ app.add_middleware(
     allow_headers=["*"],
 )
 
+# Idempotency for mutations replayed from the display's offline queue.
+# The device sends an Idempotency-Key header; a repeated key returns the stored
+# response instead of applying the change twice.
+# The cache is in memory only: a request that was applied just before a backend
+# restart, but whose response the display never got, is applied again when the
+# display replays it. Weight syncs, tag links and tray assignments set absolute
+# state, so that only duplicates added spools.
+IDEMPOTENCY_CACHE_MAX = 500
+_idempotency_cache: OrderedDict[str, tuple[int, bytes, str | None]] = OrderedDict()
+
+
+@app.middleware("http")
+async def idempotency_middleware(request: Request, call_next):
+    key = request.headers.get("idempotency-key")
+    if not key or request.method not in ("POST", "PUT", "PATCH"):
+        return await call_next(request)
+
+    cache_key = f"{request.method} {request.url.path} {key}"
+    cached = _idempotency_cache.get(cache_key)
+    if cached:
+        status_code, body, media_type = cached
+        logger.info(f"Idempotent replay of {cache_key}")
+        return Response(
+            content=body, status_code=status_code, media_type=media_type, headers={"Idempotent-Replayed": "true"}
+        )
+
+    response = await call_next(request)
+    body = b"".join([chunk async for chunk in response.body_iterator])
+
+    # Server errors are retried by the device, so don't remember them
+    if response.status_code < 500:
+        _idempotency_cache[cache_key] = (response.status_code, body, response.media_type)
+        while len(_idempotency_cache) > IDEMPOTENCY_CACHE_MAX:
+            _idempotency_cache.popitem(last=False)
+
+    queued_at = request.headers.get("x-queued-at")
+    if queued_at:
+        logger.info(f"Replayed {request.method} {request.url.path} queued at {queued_at} -> {response.status_code}")
+
+    headers = {k: v for k, v in response.headers.items() if k.lower() != "content-length"}
+    return Response(content=body, status_code=response.status_code, headers=headers, media_type=response.media_type)
+
+
+# Device API key authentication.
+# A request carrying an X-API-Key header needs a valid, enabled key (401) with
+# read permission, or write permission for anything but GET/HEAD (403). Once a
+# display is paired, the /api/display/ endpoints require its key.
+# Other requests without a key are let through on purpose: the web UI has no
+# login and stays trusted on the local network as before keys existed.
+# Approving a display pairing, unpairing the display, rotating its key and
+# setting its server URL are the exception and need a control key other than
+# the display's own (see require_control_key in api/device.py). Starting a pairing and polling it stay
+# open so a display can obtain a key.
+PAIRING_PATH = "/api/device/pair"
+
+
+def is_device_auth_exempt(method: str, path: str) -> bool:
+    """Whether a request is one of the open pairing calls of the display."""
+    if method == "POST":
+        return path == PAIRING_PATH
+    if method == "GET" and path.startswith(PAIRING_PATH + "/"):
+        return "/" not in path[len(PAIRING_PATH) + 1 :]
+    return False
+
+
+@app.middleware("http")
+async def device_auth_middleware(request: Request, call_next):
+    path = request.url.path
+    if is_device_auth_exempt(request.method, path) or request.method == "OPTIONS":
+        return await call_next(request)
+
+    key = request.headers.get("x-api-key")
+    if not key:
+        if path.startswith("/api/display/") and await get_device_key_id() is not None:
+            return JSONResponse(status_code=401, content={"detail": "Display API key required"})
+        return await call_next(request)
+
+    try:
+        api_key = await validate_api_key(key, None)
+    except HTTPException as e:
+        return JSONResponse(status_code=e.status_code, content={"detail": e.detail})
+
+    permission = "read" if request.method in ("GET", "HEAD") else "write"
+    if not api_key[f"can_{permission}"]:
+        return JSONResponse(status_code=403, content={"detail": f"API key does not have '{permission}' permission"})
+
+    return await call_next(request)
+
+
 # API routes
 app.include_router(spools_router, prefix="/api")
 app.include_router(printers_router, prefix="/api")
@@ -826,13 +1030,70 @@ async def display_heartbeat(
     if wifi_rssi is not None:
         _device_wifi_rssi = wifi_rssi
 
-    cmd = pop_display_command()
-    if cmd:
-        logger.info(f"Sending command to display: {cmd}")
-        return {"ok": True, "command": cmd}
+    commands = pop_display_commands()
+    if commands:
+        logger.info(f"Sending commands to display: {[_redact_command(c) for c in commands]}")
+        # "command" keeps older firmware working (it only understands one string command)
+        return {"ok": True, "commands": commands, "command": _legacy_command_string(commands[0])}
+    return {"ok": True}
+
+
+@app.post("/api/display/command-result")
+async def display_command_result(result: DeviceCommandResult):
+    """Receive the outcome of a command executed by the display."""
+    update_display_heartbeat()
+
+    entry = _display_command_results.get(result.id, {"id": result.id, "type": None})
+    entry.update(status=result.status, error=result.error, data=result.data)
+    _display_command_results[result.id] = entry
+
+    if result.status == "ok":
+        logger.info(f"Display command {result.id} ({entry['type']}) succeeded: {result.data}")
+    else:
+        logger.warning(f"Display command {result.id} ({entry['type']}) {result.status}: {result.error}")
+
+    if entry["type"] == "rotate_api_key":
+        await complete_key_rotation(result.id, result.status)
+
+    await broadcast_message({"type": "device_command_result", **entry})
     return {"ok": True}
 
 
+@app.get("/api/display/events")
+async def display_events(request: Request):
+    """Server-Sent Events stream of printer/AMS updates for the display.
+
+    Starts with a full `printers` snapshot, then sends a `printer` event for
+    each printer whose state changed. Comment lines keep the connection alive.
+    """
+    from api.printers import get_printers_with_status
+
+    queue: asyncio.Queue = asyncio.Queue(maxsize=DISPLAY_EVENT_QUEUE_MAX)
+    _display_event_queues.add(queue)
+    logger.info(f"Display event stream opened ({len(_display_event_queues)} subscribers)")
+
+    printers = await get_printers_with_status()
+    for p in printers:
+        _display_printer_snapshots[p.serial] = json.dumps(p.model_dump(mode="json"), sort_keys=True)
+    snapshot = format_sse("printers", [p.model_dump(mode="json") for p in printers])
+
+    async def stream():
+        try:
+            yield snapshot
+            while queue in _display_event_queues:
+                if await request.is_disconnected():
+                    break
+                try:
+                    yield await asyncio.wait_for(queue.get(), timeout=DISPLAY_EVENT_KEEPALIVE_SEC)
+                except asyncio.TimeoutError:
+                    yield ": keepalive\n\n"
+        finally:
+            _display_event_queues.discard(queue)
+            logger.info("Display event stream closed")
+
+    return StreamingResponse(stream(), media_type="text/event-stream", headers={"Cache-Control": "no-cache"})
+
+
 def get_display_firmware_version() -> str | None:
     """Get the last reported firmware version from the display."""
     return _display_firmware_version
@@ -874,6 +1135,8 @@ async def update_device_state(
     weight: float | None = None,
     stable: bool | None = None,
     tag_id: str | None = None,
+    # Spool identity: the tray UID shared by both tags of a Bambu spool, else tag_id
+    spool_id: str | None = None,
     tag_vendor: str | None = None,
     tag_material: str | None = None,
     tag_subtype: str | None = None,
@@ -881,6 +1144,17 @@ async def update_device_state(
     tag_color_rgba: int | None = None,
     tag_weight: int | None = None,
     tag_type: str | None = None,
+    # Bambu Lab tag details (only sent for tags that carry them)
+    tag_diameter: float | None = None,
+    tag_drying_temp: int | None = None,
+    tag_drying_time: int | None = None,
+    tag_bed_temp: int | None = None,
+    tag_hotend_min: int | None = None,
+    tag_hotend_max: int | None = None,
+    tag_spool_width: float | None = None,
+    tag_length: int | None = None,
+    tag_production_date: str | None = None,
+    tag_tray_uid: str | None = None,
     # WiFi status from device
     wifi_state: int | None = None,
     wifi_ssid: str | None = None,
@@ -907,6 +1181,7 @@ async def update_device_state(
     if tag_id and tag_vendor:
         tag_data = {
             "uid": tag_id,
+            "spool_id": spool_id or tag_id,
             "tag_type": tag_type or "bambulab",
             "vendor": tag_vendor or "",
             "material": tag_material or "",
@@ -915,6 +1190,19 @@ async def update_device_state(
             "color_rgba": tag_color_rgba or 0,
             "spool_weight": tag_weight or 0,
         }
+        details = {
+            "filament_diameter": tag_diameter,
+            "drying_temp": tag_drying_temp,
+            "drying_time": tag_drying_time,
+            "bed_temp": tag_bed_temp,
+            "hotend_min_temp": tag_hotend_min,
+            "hotend_max_temp": tag_hotend_max,
+            "spool_width": tag_spool_width,
+            "filament_length": tag_length,
+            "production_date": tag_production_date,
+            "tray_uid": tag_tray_uid,
+        }
+        tag_data.update({key: value for key, value in details.items() if value is not None})
         logger.info(f"Received decoded tag data from device: {tag_vendor} {tag_material}")
 
     # Build message - only include tag_id if it was actually provided in the request
@@ -927,6 +1215,7 @@ async def update_device_state(
     # (device sends tag_id when reporting tag status, omits it for weight-only updates)
     if tag_id is not None or tag_vendor is not None:
         message["tag_id"] = tag_id
+        message["spool_id"] = spool_id or tag_id
         message["tag_data"] = tag_data
 
     await handle_device_state(message)
@@ -1440,4 +1729,6 @@ if __name__ == "__main__":
         host=settings.host,
         port=settings.port,
         reload=True,
+        ssl_certfile=str(settings.ssl_certfile) if settings.ssl_certfile else None,
+        ssl_keyfile=str(settings.ssl_keyfile) if settings.ssl_keyfile else None,
     )
@@ -135,6 +135,7 @@ class PrinterWithStatus(BaseModel):
     stg_cur_name: str | None = None  # Human-readable stage name (e.g., "Auto bed leveling")
     # AMS state
     ams_units: list[AmsUnit] = []
+    vt_tray: AmsTray | None = None  # External spool holder
     tray_now: int | None = None  # Active tray (single nozzle)
     tray_now_left: int | None = None  # Active tray left nozzle (dual)
     tray_now_right: int | None = None  # Active tray right nozzle (dual)
@@ -202,6 +203,18 @@ class SetCalibrationRequest(BaseModel):
     nozzle_temp_max: int = 230  # Max nozzle temp for extrusion_cali_set
 
 
+# ============ Display Commands ============
+
+
+class DeviceCommandResult(BaseModel):
+    """Outcome of a command executed by the display, reported by the device."""
+
+    id: str
+    status: str  # ok, error, rejected
+    error: str | None = None
+    data: dict = {}
+
+
 # ============ WebSocket Messages ============
 
 
@@ -99,6 +99,7 @@ async def async_client(test_db, mock_printer_manager) -> AsyncGenerator[AsyncCli
         patch("api.cloud.get_db", override_get_db),
         patch("api.colors.get_db", override_get_db),
         patch("api.api_keys.get_db", override_get_db),
+        patch("api.device.get_db", override_get_db),
         patch("api.catalog.get_db", override_get_db),
         patch("api.settings.get_db", override_get_db),
         patch("api.support.get_db", override_get_db),
@@ -95,6 +95,27 @@ class TestPrintersAPI:
         assert len(printers) == 1
         assert printers[0]["name"] == "New Name"
 
+    async def test_list_printers_includes_external_spool(
+        self, async_client, sample_printer_data, mock_printer_manager
+    ):
+        """Test that the external spool holder is reported for connected printers."""
+        from models import AmsTray, PrinterState
+
+        await async_client.post("/api/printers", json=sample_printer_data)
+        serial = sample_printer_data["serial"]
+        mock_printer_manager.get_connection_statuses.return_value = {serial: True}
+        mock_printer_manager.get_state.return_value = PrinterState(
+            vt_tray=AmsTray(ams_id=255, tray_id=0, tray_type="PETG", tray_color="FF0000FF", remain=40)
+        )
+
+        response = await async_client.get("/api/printers")
+        assert response.status_code == 200
+
+        vt_tray = response.json()[0]["vt_tray"]
+        assert vt_tray["ams_id"] == 255
+        assert vt_tray["tray_type"] == "PETG"
+        assert vt_tray["remain"] == 40
+
 
 class TestPrintersDatabase:
     """Test printer database operations directly."""
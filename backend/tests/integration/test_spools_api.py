@@ -51,6 +51,43 @@ class TestSpoolsAPI:
         response = await async_client.get("/api/spools/nonexistent-id")
         assert response.status_code == 404
 
+    async def test_get_spool_by_tag(self, async_client, sample_spool_data):
+        """Test looking up a spool by NFC tag (base64 UID)."""
+        create_response = await async_client.post("/api/spools", json={**sample_spool_data, "tag_id": "BA+/9w=="})
+        spool_id = create_response.json()["id"]
+
+        response = await async_client.get("/api/spools/by-tag", params={"tag_id": "BA+/9w=="})
+        assert response.status_code == 200
+        assert response.json()["id"] == spool_id
+        assert response.headers["etag"]
+
+    async def test_get_spool_by_tag_not_found(self, async_client):
+        """Test looking up a tag that isn't linked to a spool."""
+        response = await async_client.get("/api/spools/by-tag", params={"tag_id": "unknown=="})
+        assert response.status_code == 404
+
+    async def test_get_spool_by_tag_etag(self, async_client, sample_spool_data):
+        """Test revalidation: 304 while unchanged, new ETag after an update."""
+        create_response = await async_client.post("/api/spools", json={**sample_spool_data, "tag_id": "ETAG01=="})
+        spool_id = create_response.json()["id"]
+
+        response = await async_client.get("/api/spools/by-tag", params={"tag_id": "ETAG01=="})
+        etag = response.headers["etag"]
+
+        response = await async_client.get(
+            "/api/spools/by-tag", params={"tag_id": "ETAG01=="}, headers={"If-None-Match": etag}
+        )
+        assert response.status_code == 304
+
+        update = {**sample_spool_data, "tag_id": "ETAG01==", "weight_current": 500}
+        await async_client.put(f"/api/spools/{spool_id}", json=update)
+        response = await async_client.get(
+            "/api/spools/by-tag", params={"tag_id": "ETAG01=="}, headers={"If-None-Match": etag}
+        )
+        assert response.status_code == 200
+        assert response.json()["weight_current"] == 500
+        assert response.headers["etag"] != etag
+
     async def test_update_spool(self, async_client, sample_spool_data):
         """Test updating a spool."""
         # Create a spool first
@@ -105,6 +142,41 @@ class TestSpoolsAPI:
         assert len(data) == 3
 
 
+class TestIdempotentReplay:
+    """Test replay of queued device mutations with Idempotency-Key."""
+
+    @pytest.fixture(autouse=True)
+    def _clear_cache(self):
+        import main
+
+        main._idempotency_cache.clear()
+        yield
+        main._idempotency_cache.clear()
+
+    async def test_replayed_create_is_applied_once(self, async_client):
+        """Test the same key creates only one spool and returns the same response."""
+        headers = {"Idempotency-Key": "sb-0011223344-7", "X-Queued-At": "1760000000"}
+
+        first = await async_client.post("/api/spools", json={"material": "PLA"}, headers=headers)
+        second = await async_client.post("/api/spools", json={"material": "PLA"}, headers=headers)
+
+        assert first.status_code == 201
+        assert second.status_code == 201
+        assert second.json()["id"] == first.json()["id"]
+        assert second.headers.get("Idempotent-Replayed") == "true"
+
+        response = await async_client.get("/api/spools")
+        assert len(response.json()) == 1
+
+    async def test_different_keys_are_applied(self, async_client):
+        """Test distinct keys are treated as distinct mutations."""
+        await async_client.post("/api/spools", json={"material": "PLA"}, headers={"Idempotency-Key": "k1"})
+        await async_client.post("/api/spools", json={"material": "PLA"}, headers={"Idempotency-Key": "k2"})
+
+        response = await async_client.get("/api/spools")
+        assert len(response.json()) == 2
+
+
 class TestSpoolsDatabase:
     """Test spool database operations directly."""
 
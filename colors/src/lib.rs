@@ -0,0 +1,395 @@
+//! SpoolBuddy filament color names.
+//!
+//! Names an RGBA color after the perceptually nearest entry of a built-in
+//! palette: Bambu Lab's official filament color names first, then generic
+//! CSS-like names. Distances are CIEDE2000 in CIELAB (sRGB, D65 white).
+//! Translucent colors (alpha below [`OPAQUE_ALPHA`]) are named
+//! "Translucent <name>"; alpha 0 is taken as "not specified" (opaque).
+//!
+//! Kept apart from the UI crate so the firmware can name tag colors without
+//! pulling in the embedded-graphics screens.
+
+use core::fmt;
+
+/// A named palette color (0xRRGGBB)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NamedColor {
+    pub name: &'static str,
+    pub rgb: u32,
+}
+
+const fn named(name: &'static str, rgb: u32) -> NamedColor {
+    NamedColor { name, rgb }
+}
+
+/// Bambu Lab filament colors (PLA Basic, PLA Matte, PETG and ABS lines)
+pub const BAMBU_COLORS: &[NamedColor] = &[
+    named("Jade White", 0xFFFFFF),
+    named("Black", 0x000000),
+    named("Beige", 0xF7E6DE),
+    named("Light Gray", 0xD1D3D5),
+    named("Gray", 0x8E9089),
+    named("Silver", 0xA6A9AA),
+    named("Dark Gray", 0x545454),
+    named("Blue Grey", 0x5B6579),
+    named("Bambu Green", 0x00AE42),
+    named("Mistletoe Green", 0x3F8E43),
+    named("Bright Green", 0xBECF00),
+    named("Turquoise", 0x00B1B7),
+    named("Cyan", 0x0086D6),
+    named("Cobalt Blue", 0x0056B8),
+    named("Blue", 0x0A2989),
+    named("Purple", 0x5E43B7),
+    named("Indigo Purple", 0x482960),
+    named("Magenta", 0xEC008C),
+    named("Pink", 0xF55A74),
+    named("Red", 0xC12E1F),
+    named("Maroon Red", 0x9D2235),
+    named("Orange", 0xFF6A13),
+    named("Pumpkin Orange", 0xFF9016),
+    named("Sunflower Yellow", 0xFEC600),
+    named("Yellow", 0xF4EE2A),
+    named("Gold", 0xE4BD68),
+    named("Bronze", 0x847D48),
+    named("Brown", 0x9D432C),
+    named("Cocoa Brown", 0x6F5034),
+    named("Bone White", 0xCBC6B8),
+    named("Latte Brown", 0xD3B7A7),
+    named("Desert Tan", 0xE8DBB7),
+    named("Ash Gray", 0x9B9EA0),
+    named("Nardo Gray", 0x757575),
+    named("Dark Blue", 0x042F56),
+    named("Marine Blue", 0x0078BF),
+    named("Sky Blue", 0x56B7E6),
+    named("Ice Blue", 0xA3D8E1),
+    named("Lilac Purple", 0xAE96D4),
+    named("Sakura Pink", 0xE8AFCF),
+    named("Scarlet Red", 0xDE4343),
+    named("Dark Red", 0xBB3D43),
+    named("Mandarin Orange", 0xF99963),
+    named("Lemon Yellow", 0xF7D959),
+    named("Grass Green", 0x61C680),
+    named("Apple Green", 0xC2E189),
+    named("Dark Green", 0x68724D),
+    named("Dark Brown", 0x7D6556),
+    named("Caramel", 0xAE835B),
+    named("Terracotta", 0xB15533),
+    named("Plum", 0x950051),
+];
+
+/// Generic color names for colors far from any Bambu Lab color
+pub const GENERIC_COLORS: &[NamedColor] = &[
+    named("White", 0xFFFFFF),
+    named("Black", 0x000000),
+    named("Gray", 0x808080),
+    named("Red", 0xFF0000),
+    named("Crimson", 0xDC143C),
+    named("Coral", 0xFF7F50),
+    named("Salmon", 0xFA8072),
+    named("Orange", 0xFFA500),
+    named("Yellow", 0xFFFF00),
+    named("Khaki", 0xF0E68C),
+    named("Olive", 0x808000),
+    named("Lime", 0x00FF00),
+    named("Green", 0x008000),
+    named("Mint", 0x98FF98),
+    named("Teal", 0x008080),
+    named("Aqua", 0x00FFFF),
+    named("Navy", 0x000080),
+    named("Royal Blue", 0x4169E1),
+    named("Blue", 0x0000FF),
+    named("Lavender", 0xE6E6FA),
+    named("Violet", 0xEE82EE),
+    named("Purple", 0x800080),
+    named("Fuchsia", 0xFF00FF),
+    named("Hot Pink", 0xFF69B4),
+    named("Pink", 0xFFC0CB),
+    named("Maroon", 0x800000),
+    named("Chocolate", 0xD2691E),
+    named("Brown", 0x8B4513),
+    named("Tan", 0xD2B48C),
+    named("Cream", 0xFFFDD0),
+];
+
+/// Alpha from which a color counts as opaque
+pub const OPAQUE_ALPHA: u8 = 0xF0;
+
+/// Nearest palette color to an RGBA color
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorMatch {
+    pub color: NamedColor,
+    /// Alpha below [`OPAQUE_ALPHA`] (and not 0)
+    pub translucent: bool,
+    /// CIEDE2000 distance to the palette color
+    pub delta_e: f32,
+}
+
+impl fmt::Display for ColorMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.translucent {
+            write!(f, "Translucent {}", self.color.name)
+        } else {
+            f.write_str(self.color.name)
+        }
+    }
+}
+
+/// Find the palette color nearest to `rgba` (0xRRGGBBAA).
+/// On a tie the Bambu Lab name wins.
+pub fn nearest_color(rgba: u32) -> ColorMatch {
+    let lab = Lab::from_rgb(rgba >> 8);
+    let alpha = (rgba & 0xFF) as u8;
+    let mut best = ColorMatch {
+        color: BAMBU_COLORS[0],
+        translucent: alpha != 0 && alpha < OPAQUE_ALPHA,
+        delta_e: f32::MAX,
+    };
+
+    for color in BAMBU_COLORS.iter().chain(GENERIC_COLORS) {
+        let delta_e = ciede2000(lab, Lab::from_rgb(color.rgb));
+        if delta_e < best.delta_e {
+            best.color = *color;
+            best.delta_e = delta_e;
+        }
+    }
+    best
+}
+
+/// Color name for `rgba` (0xRRGGBBAA), e.g. "Jade White" or "Translucent Red"
+pub fn color_name(rgba: u32) -> heapless::String<32> {
+    let mut name = heapless::String::new();
+    // The longest name with the prefix fits
+    let _ = fmt::write(&mut name, format_args!("{}", nearest_color(rgba)));
+    name
+}
+
+/// CIELAB color
+#[derive(Debug, Clone, Copy)]
+struct Lab {
+    l: f32,
+    a: f32,
+    b: f32,
+}
+
+impl Lab {
+    /// From 0xRRGGBB sRGB
+    fn from_rgb(rgb: u32) -> Self {
+        let linear = |shift: u32| {
+            let c = ((rgb >> shift) & 0xFF) as f32 / 255.0;
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        let (r, g, b) = (linear(16), linear(8), linear(0));
+
+        // XYZ relative to the D65 white point
+        let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+        let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+        let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+
+        let f = |t: f32| {
+            if t > 216.0 / 24389.0 {
+                t.cbrt()
+            } else {
+                (24389.0 / 27.0 * t + 16.0) / 116.0
+            }
+        };
+        let (fx, fy, fz) = (f(x), f(y), f(z));
+        Self { l: 116.0 * fy - 16.0, a: 500.0 * (fx - fy), b: 200.0 * (fy - fz) }
+    }
+}
+
+/// CIEDE2000 color difference (kL = kC = kH = 1)
+fn ciede2000(lab1: Lab, lab2: Lab) -> f32 {
+    const POW25_7: f32 = 6_103_515_625.0; // 25^7
+
+    let c1 = lab1.a.hypot(lab1.b);
+    let c2 = lab2.a.hypot(lab2.b);
+    let c_bar7 = ((c1 + c2) / 2.0).powi(7);
+    let g = 0.5 * (1.0 - (c_bar7 / (c_bar7 + POW25_7)).sqrt());
+
+    let a1 = (1.0 + g) * lab1.a;
+    let a2 = (1.0 + g) * lab2.a;
+    let c1 = a1.hypot(lab1.b);
+    let c2 = a2.hypot(lab2.b);
+    let hue = |b: f32, a: f32| {
+        if a == 0.0 && b == 0.0 {
+            0.0
+        } else {
+            b.atan2(a).to_degrees().rem_euclid(360.0)
+        }
+    };
+    let h1 = hue(lab1.b, a1);
+    let h2 = hue(lab2.b, a2);
+    let achromatic = c1 * c2 == 0.0;
+
+    let delta_l = lab2.l - lab1.l;
+    let delta_c = c2 - c1;
+    let delta_h = if achromatic {
+        0.0
+    } else if h2 - h1 > 180.0 {
+        h2 - h1 - 360.0
+    } else if h2 - h1 < -180.0 {
+        h2 - h1 + 360.0
+    } else {
+        h2 - h1
+    };
+    let delta_hue = 2.0 * (c1 * c2).sqrt() * (delta_h / 2.0).to_radians().sin();
+
+    let l_mean = (lab1.l + lab2.l) / 2.0;
+    let c_mean = (c1 + c2) / 2.0;
+    let h_mean = if achromatic {
+        h1 + h2
+    } else if (h1 - h2).abs() <= 180.0 {
+        (h1 + h2) / 2.0
+    } else if h1 + h2 < 360.0 {
+        (h1 + h2 + 360.0) / 2.0
+    } else {
+        (h1 + h2 - 360.0) / 2.0
+    };
+
+    let cos_deg = |deg: f32| deg.to_radians().cos();
+    let t = 1.0 - 0.17 * cos_deg(h_mean - 30.0) + 0.24 * cos_deg(2.0 * h_mean) + 0.32 * cos_deg(3.0 * h_mean + 6.0)
+        - 0.20 * cos_deg(4.0 * h_mean - 63.0);
+    let delta_theta = 30.0 * (-((h_mean - 275.0) / 25.0).powi(2)).exp();
+    let c_mean7 = c_mean.powi(7);
+    let r_c = 2.0 * (c_mean7 / (c_mean7 + POW25_7)).sqrt();
+    let l_offset2 = (l_mean - 50.0).powi(2);
+    let s_l = 1.0 + 0.015 * l_offset2 / (20.0 + l_offset2).sqrt();
+    let s_c = 1.0 + 0.045 * c_mean;
+    let s_h = 1.0 + 0.015 * c_mean * t;
+    let r_t = -(2.0 * delta_theta).to_radians().sin() * r_c;
+
+    let (dl, dc, dh) = (delta_l / s_l, delta_c / s_c, delta_hue / s_h);
+    (dl * dl + dc * dc + dh * dh + r_t * dc * dh).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lab(l: f32, a: f32, b: f32) -> Lab {
+        Lab { l, a, b }
+    }
+
+    /// Test data of Sharma, Wu and Dalal, "The CIEDE2000 Color-Difference
+    /// Formula: Implementation Notes, Supplementary Test Data, and
+    /// Mathematical Observations" (2005), Table 1
+    const SHARMA_PAIRS: [([f32; 3], [f32; 3], f32); 34] = [
+        ([50.0000, 2.6772, -79.7751], [50.0000, 0.0000, -82.7485], 2.0425),
+        ([50.0000, 3.1571, -77.2803], [50.0000, 0.0000, -82.7485], 2.8615),
+        ([50.0000, 2.8361, -74.0200], [50.0000, 0.0000, -82.7485], 3.4412),
+        ([50.0000, -1.3802, -84.2814], [50.0000, 0.0000, -82.7485], 1.0000),
+        ([50.0000, -1.1848, -84.8006], [50.0000, 0.0000, -82.7485], 1.0000),
+        ([50.0000, -0.9009, -85.5211], [50.0000, 0.0000, -82.7485], 1.0000),
+        ([50.0000, 0.0000, 0.0000], [50.0000, -1.0000, 2.0000], 2.3669),
+        ([50.0000, -1.0000, 2.0000], [50.0000, 0.0000, 0.0000], 2.3669),
+        ([50.0000, 2.4900, -0.0010], [50.0000, -2.4900, 0.0009], 7.1792),
+        ([50.0000, 2.4900, -0.0010], [50.0000, -2.4900, 0.0010], 7.1792),
+        ([50.0000, 2.4900, -0.0010], [50.0000, -2.4900, 0.0011], 7.2195),
+        ([50.0000, 2.4900, -0.0010], [50.0000, -2.4900, 0.0012], 7.2195),
+        ([50.0000, -0.0010, 2.4900], [50.0000, 0.0009, -2.4900], 4.8045),
+        ([50.0000, -0.0010, 2.4900], [50.0000, 0.0010, -2.4900], 4.8045),
+        ([50.0000, -0.0010, 2.4900], [50.0000, 0.0011, -2.4900], 4.7461),
+        ([50.0000, 2.5000, 0.0000], [50.0000, 0.0000, -2.5000], 4.3065),
+        ([50.0000, 2.5000, 0.0000], [73.0000, 25.0000, -18.0000], 27.1492),
+        ([50.0000, 2.5000, 0.0000], [61.0000, -5.0000, 29.0000], 22.8977),
+        ([50.0000, 2.5000, 0.0000], [56.0000, -27.0000, -3.0000], 31.9030),
+        ([50.0000, 2.5000, 0.0000], [58.0000, 24.0000, 15.0000], 19.4535),
+        ([50.0000, 2.5000, 0.0000], [50.0000, 3.1736, 0.5854], 1.0000),
+        ([50.0000, 2.5000, 0.0000], [50.0000, 3.2972, 0.0000], 1.0000),
+        ([50.0000, 2.5000, 0.0000], [50.0000, 1.8634, 0.5757], 1.0000),
+        ([50.0000, 2.5000, 0.0000], [50.0000, 3.2592, 0.3350], 1.0000),
+        ([60.2574, -34.0099, 36.2677], [60.4626, -34.1751, 39.4387], 1.2644),
+        ([63.0109, -31.0961, -5.8663], [62.8187, -29.7946, -4.0864], 1.2630),
+        ([61.2901, 3.7196, -5.3901], [61.4292, 2.2480, -4.9620], 1.8731),
+        ([35.0831, -44.1164, 3.7933], [35.0232, -40.0716, 1.5901], 1.8645),
+        ([22.7233, 20.0904, -46.6940], [23.0331, 14.9730, -42.5619], 2.0373),
+        ([36.4612, 47.8580, 18.3852], [36.2715, 50.5065, 21.2231], 1.4146),
+        ([90.8027, -2.0831, 1.4410], [91.1528, -1.6435, 0.0447], 1.4441),
+        ([90.9257, -0.5406, -0.9208], [88.6381, -0.8985, -0.7239], 1.5381),
+        ([6.7747, -0.2908, -2.4247], [5.8714, -0.0985, -2.2286], 0.6377),
+        ([2.0776, 0.0795, -1.1350], [0.9033, -0.0636, -0.5514], 0.9082),
+    ];
+
+    #[test]
+    fn ciede2000_matches_sharma_test_data() {
+        for (i, (c1, c2, expected)) in SHARMA_PAIRS.iter().enumerate() {
+            let lab1 = lab(c1[0], c1[1], c1[2]);
+            let lab2 = lab(c2[0], c2[1], c2[2]);
+            let forward = ciede2000(lab1, lab2);
+            let backward = ciede2000(lab2, lab1);
+            assert!((forward - expected).abs() < 1e-3, "pair {}: {} != {}", i + 1, forward, expected);
+            assert!((backward - expected).abs() < 1e-3, "pair {} reversed: {} != {}", i + 1, backward, expected);
+        }
+    }
+
+    #[test]
+    fn lab_of_reference_colors() {
+        let white = Lab::from_rgb(0xFFFFFF);
+        assert!((white.l - 100.0).abs() < 0.05 && white.a.abs() < 0.05 && white.b.abs() < 0.05, "{:?}", white);
+        let black = Lab::from_rgb(0x000000);
+        assert!(black.l.abs() < 0.01, "{:?}", black);
+        // sRGB red: L 53.24, a 80.09, b 67.20
+        let red = Lab::from_rgb(0xFF0000);
+        assert!((red.l - 53.24).abs() < 0.05 && (red.a - 80.09).abs() < 0.1 && (red.b - 67.20).abs() < 0.1, "{:?}", red);
+    }
+
+    #[test]
+    fn palette_colors_name_themselves() {
+        for color in BAMBU_COLORS {
+            assert_eq!(nearest_color((color.rgb << 8) | 0xFF).color, *color);
+        }
+        // Shared values: the Bambu Lab name wins
+        assert_eq!(color_name(0xFFFFFFFF), "Jade White");
+        assert_eq!(color_name(0x000000FF), "Black");
+    }
+
+    #[test]
+    fn greys_are_not_tinted() {
+        // Neutral greys and slightly warm/cool ones must not come out as a
+        // tinted name (Blue Grey, Bronze, Beige...)
+        for rgb in [0x202020, 0x404040, 0x5A5A5A, 0x707070, 0x808080, 0x8C8C8C, 0x9E9E9E, 0xB4B4B4, 0xC8C8C8, 0x8A8D90] {
+            let name = color_name((rgb << 8) | 0xFF);
+            assert!(
+                name.contains("Gray") || name == "Silver" || name == "Black",
+                "{:06X} named {}",
+                rgb,
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn oranges_are_not_red_or_brown() {
+        for rgb in [0xFF6A13, 0xFF7F00, 0xFF8C00, 0xFFA500, 0xF57C00, 0xFF9016, 0xE86A17] {
+            let name = color_name((rgb << 8) | 0xFF);
+            assert!(name.contains("Orange"), "{:06X} named {}", rgb, name);
+        }
+    }
+
+    #[test]
+    fn alpha_handling() {
+        // Alpha 0 means not specified: opaque
+        assert_eq!(color_name(0xC12E1F00), "Red");
+        assert!(!nearest_color(0xC12E1F00).translucent);
+        assert_eq!(color_name(0xC12E1FFF), "Red");
+        assert_eq!(color_name(0xC12E1F80), "Translucent Red");
+        assert_eq!(color_name(0xC12E1F01), "Translucent Red");
+        assert!(nearest_color((0xC12E1F << 8) | (OPAQUE_ALPHA as u32 - 1)).translucent);
+        assert!(!nearest_color((0xC12E1F << 8) | OPAQUE_ALPHA as u32).translucent);
+        // Alpha does not change the match itself
+        assert_eq!(nearest_color(0x0056B840).color, nearest_color(0x0056B8FF).color);
+    }
+
+    #[test]
+    fn translucent_names_fit() {
+        for color in BAMBU_COLORS.iter().chain(GENERIC_COLORS) {
+            let rgba = (color.rgb << 8) | 0x80;
+            let name = color_name(rgba);
+            assert_eq!(name.strip_prefix("Translucent "), Some(nearest_color(rgba).color.name));
+        }
+    }
+}
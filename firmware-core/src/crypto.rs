@@ -0,0 +1,169 @@
+//! SHA-256, HMAC-SHA256, HKDF-SHA256 and base64
+//!
+//! On the device these go through mbedTLS: it is linked for ESP-TLS anyway
+//! (and drives the SHA accelerator), so certificate fingerprints and key
+//! derivation use it as well. The few functions needed are declared here
+//! directly. On the host the RustCrypto crates stand in, so tag key
+//! derivation and pinning are tested against the same code paths.
+
+pub use backend::{base64_decode, hmac_sha256, sha256};
+
+#[cfg(target_os = "espidf")]
+mod backend {
+    use log::warn;
+    use std::ffi::{c_char, c_int, c_uchar, c_void};
+
+    extern "C" {
+        fn mbedtls_sha256(input: *const c_uchar, ilen: usize, output: *mut c_uchar, is224: c_int) -> c_int;
+        fn mbedtls_md_info_from_string(md_name: *const c_char) -> *const c_void;
+        fn mbedtls_md_hmac(
+            md_info: *const c_void,
+            key: *const c_uchar,
+            keylen: usize,
+            input: *const c_uchar,
+            ilen: usize,
+            output: *mut c_uchar,
+        ) -> c_int;
+        fn mbedtls_base64_decode(
+            dst: *mut c_uchar,
+            dlen: usize,
+            olen: *mut usize,
+            src: *const c_uchar,
+            slen: usize,
+        ) -> c_int;
+    }
+
+    /// SHA-256 of `data`
+    pub fn sha256(data: &[u8]) -> [u8; 32] {
+        let mut out = [0u8; 32];
+        let ret = unsafe { mbedtls_sha256(data.as_ptr(), data.len(), out.as_mut_ptr(), 0) };
+        if ret != 0 {
+            warn!("mbedtls_sha256 failed: -0x{:04x}", -ret);
+        }
+        out
+    }
+
+    /// HMAC-SHA256 (RFC 2104)
+    pub fn hmac_sha256(key: &[u8], data: &[u8]) -> [u8; 32] {
+        let mut out = [0u8; 32];
+        let ret = unsafe {
+            let md_info = mbedtls_md_info_from_string(c"SHA256".as_ptr());
+            if md_info.is_null() {
+                -1
+            } else {
+                mbedtls_md_hmac(md_info, key.as_ptr(), key.len(), data.as_ptr(), data.len(), out.as_mut_ptr())
+            }
+        };
+        if ret != 0 {
+            warn!("mbedtls_md_hmac failed: -0x{:04x}", -ret);
+        }
+        out
+    }
+
+    /// Standard base64 decoding (line breaks allowed, as in PEM)
+    pub fn base64_decode(input: &str) -> Option<Vec<u8>> {
+        let src = input.trim().as_bytes();
+        let mut out = vec![0u8; src.len() * 3 / 4 + 3];
+        let mut len = 0usize;
+        let ret = unsafe { mbedtls_base64_decode(out.as_mut_ptr(), out.len(), &mut len, src.as_ptr(), src.len()) };
+        if ret != 0 || len == 0 {
+            return None;
+        }
+        out.truncate(len);
+        Some(out)
+    }
+}
+
+#[cfg(not(target_os = "espidf"))]
+mod backend {
+    use base64::Engine;
+    use hmac::{Hmac, Mac};
+    use sha2::{Digest, Sha256};
+
+    /// SHA-256 of `data`
+    pub fn sha256(data: &[u8]) -> [u8; 32] {
+        Sha256::digest(data).into()
+    }
+
+    /// HMAC-SHA256 (RFC 2104)
+    pub fn hmac_sha256(key: &[u8], data: &[u8]) -> [u8; 32] {
+        let mut mac = <Hmac<Sha256>>::new_from_slice(key).expect("HMAC takes keys of any length");
+        mac.update(data);
+        mac.finalize().into_bytes().into()
+    }
+
+    /// Standard base64 decoding (line breaks allowed, as in PEM)
+    pub fn base64_decode(input: &str) -> Option<Vec<u8>> {
+        let src: Vec<u8> = input.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+        base64::engine::general_purpose::STANDARD.decode(src).ok().filter(|out| !out.is_empty())
+    }
+}
+
+/// HKDF-SHA256 (RFC 5869): extract with `salt`, then expand `info` into `okm`
+/// (at most 255 * 32 bytes)
+pub fn hkdf_sha256(salt: &[u8], ikm: &[u8], info: &[u8], okm: &mut [u8]) {
+    let prk = hmac_sha256(salt, ikm);
+
+    // T(i) = HMAC(PRK, T(i-1) | info | i)
+    let mut t: Vec<u8> = Vec::with_capacity(32 + info.len() + 1);
+    for (i, chunk) in okm.chunks_mut(32).enumerate() {
+        t.extend_from_slice(info);
+        t.push(i as u8 + 1);
+        let block = hmac_sha256(&prk, &t);
+        chunk.copy_from_slice(&block[..chunk.len()]);
+        t.clear();
+        t.extend_from_slice(&block);
+    }
+}
+
+/// Lowercase hex encoding (fingerprints)
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap()).collect()
+    }
+
+    #[test]
+    fn sha256_of_abc() {
+        assert_eq!(
+            to_hex(&sha256(b"abc")),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    /// RFC 4231 test case 2
+    #[test]
+    fn hmac_sha256_rfc4231() {
+        assert_eq!(
+            to_hex(&hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    /// RFC 5869 test case 1
+    #[test]
+    fn hkdf_sha256_rfc5869() {
+        let ikm = [0x0bu8; 22];
+        let salt = from_hex("000102030405060708090a0b0c");
+        let info = from_hex("f0f1f2f3f4f5f6f7f8f9");
+        let mut okm = [0u8; 42];
+        hkdf_sha256(&salt, &ikm, &info, &mut okm);
+        assert_eq!(
+            to_hex(&okm),
+            "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865"
+        );
+    }
+
+    #[test]
+    fn base64_with_line_breaks() {
+        assert_eq!(base64_decode("SGVsbG8s\r\nIFdvcmxk\n").as_deref(), Some(&b"Hello, World"[..]));
+        assert_eq!(base64_decode("not base64!"), None);
+        assert_eq!(base64_decode(""), None);
+    }
+}
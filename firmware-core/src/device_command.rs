@@ -0,0 +1,364 @@
+//! Typed device commands from the backend: parsing and results
+//!
+//! The heartbeat response carries a `commands` list; each entry has an `id`
+//! and a `type` plus type-specific parameters. Entries without an id, of an
+//! unknown type or with bad parameters are turned into a rejection result
+//! right away. The firmware (`device_commands`) runs the valid commands and
+//! posts every result back to `/api/display/command-result`.
+
+use serde::{Deserialize, Serialize};
+
+/// API key received from the backend
+/// Debug output only shows the prefix so keys don't end up in logs.
+#[derive(Clone, PartialEq, Deserialize)]
+#[serde(transparent)]
+pub struct ApiKey(pub String);
+
+impl ApiKey {
+    /// First 8 characters, as listed in the web UI
+    pub fn prefix(&self) -> &str {
+        self.0.get(..8).unwrap_or("")
+    }
+}
+
+impl std::fmt::Debug for ApiKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ApiKey({}...)", self.prefix())
+    }
+}
+
+/// Command payload, tagged by `type`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CommandKind {
+    Update,
+    Reboot,
+    ScaleTare,
+    ScaleCalibrate { known_weight: f32 },
+    ScaleReset,
+    /// Change scale settings (omitted ones are kept); reports the active ones
+    ScaleConfig(ScaleSettings),
+    SetServerUrl {
+        #[serde(default)]
+        url: String,
+    },
+    /// Replace the device API key (the backend revokes the old one on success)
+    RotateApiKey { key: ApiKey },
+}
+
+/// Settings of a `scale_config` command, each optional
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ScaleSettings {
+    // ADC (samples per second, gain factor, LDO millivolts, channel number)
+    pub sample_rate: Option<i32>,
+    pub gain: Option<i32>,
+    pub ldo_mv: Option<i32>,
+    pub channel: Option<i32>,
+    // Filter pipeline and stability (see `scale::filter::FilterConfig`)
+    pub median_window: Option<usize>,
+    pub average_window: Option<usize>,
+    pub ema_alpha: Option<f32>,
+    pub snap_threshold_g: Option<f32>,
+    pub stability_window: Option<usize>,
+    pub stability_std_dev_g: Option<f32>,
+    // Zero tracking and drift compensation (see `scale::drift::DriftConfig`)
+    pub auto_zero: Option<bool>,
+    pub auto_zero_band_g: Option<f32>,
+    pub auto_zero_rate_g: Option<f32>,
+    /// "none", "internal" or "ams"
+    pub temp_source: Option<String>,
+    pub zero_g_per_c: Option<f32>,
+    pub span_ppm_per_c: Option<f32>,
+}
+
+impl ScaleSettings {
+    pub fn has_adc(&self) -> bool {
+        self.sample_rate.is_some() || self.gain.is_some() || self.ldo_mv.is_some() || self.channel.is_some()
+    }
+
+    pub fn has_filter(&self) -> bool {
+        self.median_window.is_some()
+            || self.average_window.is_some()
+            || self.ema_alpha.is_some()
+            || self.snap_threshold_g.is_some()
+            || self.stability_window.is_some()
+            || self.stability_std_dev_g.is_some()
+    }
+
+    pub fn has_drift(&self) -> bool {
+        self.auto_zero.is_some()
+            || self.auto_zero_band_g.is_some()
+            || self.auto_zero_rate_g.is_some()
+            || self.temp_source.is_some()
+            || self.zero_g_per_c.is_some()
+            || self.span_ppm_per_c.is_some()
+    }
+}
+
+/// Command received from the backend
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceCommand {
+    pub id: String,
+    pub kind: CommandKind,
+}
+
+/// Outcome of a command, reported back to the backend
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CommandResult {
+    pub id: String,
+    /// "ok", "error" or "rejected"
+    pub status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub data: serde_json::Map<String, serde_json::Value>,
+}
+
+impl CommandResult {
+    pub fn ok(id: &str) -> Self {
+        Self { id: id.to_string(), status: "ok", error: None, data: serde_json::Map::new() }
+    }
+
+    pub fn error(id: &str, error: String) -> Self {
+        Self { id: id.to_string(), status: "error", error: Some(error), data: serde_json::Map::new() }
+    }
+
+    pub fn rejected(id: &str, error: String) -> Self {
+        Self { id: id.to_string(), status: "rejected", error: Some(error), data: serde_json::Map::new() }
+    }
+
+    pub fn with(mut self, key: &str, value: impl Into<serde_json::Value>) -> Self {
+        self.data.insert(key.to_string(), value.into());
+        self
+    }
+}
+
+/// Heartbeat response body
+#[derive(Debug, Deserialize)]
+struct HeartbeatResponse {
+    #[serde(default)]
+    commands: Vec<serde_json::Value>,
+}
+
+/// Parse a single command object
+/// Unknown types and bad parameters are turned into a rejection result.
+pub fn parse_command(value: serde_json::Value) -> Result<DeviceCommand, CommandResult> {
+    let id = value.get("id").and_then(|v| v.as_str()).unwrap_or("").to_string();
+    let cmd_type = value.get("type").and_then(|v| v.as_str()).unwrap_or("").to_string();
+
+    if id.is_empty() {
+        return Err(CommandResult::rejected("", format!("Command '{}' has no id", cmd_type)));
+    }
+
+    match serde_json::from_value::<CommandKind>(value) {
+        Ok(kind) => Ok(DeviceCommand { id, kind }),
+        Err(e) => {
+            let known = matches!(
+                cmd_type.as_str(),
+                "update" | "reboot" | "scale_tare" | "scale_calibrate" | "scale_reset" | "scale_config"
+                    | "set_server_url" | "rotate_api_key"
+            );
+            let error = if known {
+                format!("Invalid parameters for '{}': {}", cmd_type, e)
+            } else {
+                format!("Unknown command '{}'", cmd_type)
+            };
+            Err(CommandResult::rejected(&id, error))
+        }
+    }
+}
+
+/// Parse the command list from a heartbeat response
+/// Returns the valid commands and a rejection result for each invalid one.
+pub fn parse_heartbeat(body: &str) -> Result<(Vec<DeviceCommand>, Vec<CommandResult>), String> {
+    let response: HeartbeatResponse =
+        serde_json::from_str(body).map_err(|e| format!("Invalid heartbeat response: {}", e))?;
+
+    let mut commands = Vec::new();
+    let mut rejected = Vec::new();
+    for value in response.commands {
+        match parse_command(value) {
+            Ok(cmd) => commands.push(cmd),
+            Err(result) => rejected.push(result),
+        }
+    }
+    Ok((commands, rejected))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn parse(value: serde_json::Value) -> DeviceCommand {
+        parse_command(value).expect("valid command")
+    }
+
+    fn rejection(value: serde_json::Value) -> CommandResult {
+        parse_command(value).expect_err("rejected command")
+    }
+
+    #[test]
+    fn parses_each_command_kind() {
+        let simple = [
+            ("update", CommandKind::Update),
+            ("reboot", CommandKind::Reboot),
+            ("scale_tare", CommandKind::ScaleTare),
+            ("scale_reset", CommandKind::ScaleReset),
+        ];
+        for (cmd_type, kind) in simple {
+            let cmd = parse(json!({ "id": "c1", "type": cmd_type }));
+            assert_eq!(cmd, DeviceCommand { id: "c1".to_string(), kind });
+        }
+
+        let cmd = parse(json!({ "id": "c2", "type": "scale_calibrate", "known_weight": 500.0 }));
+        assert_eq!(cmd.kind, CommandKind::ScaleCalibrate { known_weight: 500.0 });
+
+        let cmd = parse(json!({ "id": "c3", "type": "set_server_url", "url": "http://10.0.0.2:3000" }));
+        assert_eq!(cmd.kind, CommandKind::SetServerUrl { url: "http://10.0.0.2:3000".to_string() });
+
+        // A missing url is left to the firmware to reject as invalid
+        let cmd = parse(json!({ "id": "c4", "type": "set_server_url" }));
+        assert_eq!(cmd.kind, CommandKind::SetServerUrl { url: String::new() });
+
+        let cmd = parse(json!({ "id": "c5", "type": "rotate_api_key", "key": "sb_0123456789abcdef" }));
+        assert_eq!(cmd.kind, CommandKind::RotateApiKey { key: ApiKey("sb_0123456789abcdef".to_string()) });
+    }
+
+    #[test]
+    fn parses_scale_config() {
+        let cmd = parse(json!({
+            "id": "c1",
+            "type": "scale_config",
+            "sample_rate": 80,
+            "median_window": 7,
+            "temp_source": "ams",
+        }));
+        let CommandKind::ScaleConfig(settings) = cmd.kind else {
+            panic!("not a scale_config command: {:?}", cmd.kind);
+        };
+        assert_eq!(settings.sample_rate, Some(80));
+        assert_eq!(settings.median_window, Some(7));
+        assert_eq!(settings.temp_source.as_deref(), Some("ams"));
+        assert_eq!(settings.gain, None);
+        assert!(settings.has_adc());
+        assert!(settings.has_filter());
+        assert!(settings.has_drift());
+
+        // Without settings it only reports the active ones
+        let cmd = parse(json!({ "id": "c2", "type": "scale_config" }));
+        let CommandKind::ScaleConfig(settings) = cmd.kind else {
+            panic!("not a scale_config command: {:?}", cmd.kind);
+        };
+        assert_eq!(settings, ScaleSettings::default());
+        assert!(!settings.has_adc() && !settings.has_filter() && !settings.has_drift());
+    }
+
+    #[test]
+    fn rejects_missing_id() {
+        let result = rejection(json!({ "type": "reboot" }));
+        assert_eq!(result.id, "");
+        assert_eq!(result.status, "rejected");
+        assert_eq!(result.error.as_deref(), Some("Command 'reboot' has no id"));
+
+        let result = rejection(json!({ "id": "", "type": "scale_tare" }));
+        assert_eq!(result.error.as_deref(), Some("Command 'scale_tare' has no id"));
+    }
+
+    #[test]
+    fn rejects_unknown_type() {
+        let result = rejection(json!({ "id": "c1", "type": "self_destruct" }));
+        assert_eq!(result.id, "c1");
+        assert_eq!(result.status, "rejected");
+        assert_eq!(result.error.as_deref(), Some("Unknown command 'self_destruct'"));
+
+        let result = rejection(json!({ "id": "c2" }));
+        assert_eq!(result.error.as_deref(), Some("Unknown command ''"));
+    }
+
+    #[test]
+    fn rejects_bad_params() {
+        let cases = [
+            json!({ "id": "c1", "type": "scale_calibrate" }),
+            json!({ "id": "c1", "type": "scale_calibrate", "known_weight": "heavy" }),
+            json!({ "id": "c1", "type": "scale_config", "median_window": -3 }),
+            json!({ "id": "c1", "type": "scale_config", "auto_zero": "yes" }),
+            json!({ "id": "c1", "type": "rotate_api_key" }),
+        ];
+        for value in cases {
+            let cmd_type = value["type"].as_str().unwrap().to_string();
+            let result = rejection(value);
+            assert_eq!(result.id, "c1");
+            assert_eq!(result.status, "rejected");
+            let error = result.error.unwrap();
+            assert!(
+                error.starts_with(&format!("Invalid parameters for '{}': ", cmd_type)),
+                "unexpected error: {}",
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn heartbeat_splits_valid_and_rejected() {
+        let body = r#"{
+            "status": "ok",
+            "commands": [
+                { "id": "a", "type": "scale_tare" },
+                { "id": "b", "type": "format_disk" },
+                { "type": "reboot" },
+                { "id": "c", "type": "scale_calibrate", "known_weight": 100 }
+            ]
+        }"#;
+        let (commands, rejected) = parse_heartbeat(body).unwrap();
+        assert_eq!(
+            commands,
+            vec![
+                DeviceCommand { id: "a".to_string(), kind: CommandKind::ScaleTare },
+                DeviceCommand { id: "c".to_string(), kind: CommandKind::ScaleCalibrate { known_weight: 100.0 } },
+            ]
+        );
+        let ids: Vec<&str> = rejected.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["b", ""]);
+    }
+
+    #[test]
+    fn heartbeat_without_commands() {
+        let (commands, rejected) = parse_heartbeat(r#"{"status": "ok"}"#).unwrap();
+        assert!(commands.is_empty() && rejected.is_empty());
+
+        let (commands, rejected) = parse_heartbeat(r#"{"commands": []}"#).unwrap();
+        assert!(commands.is_empty() && rejected.is_empty());
+    }
+
+    #[test]
+    fn heartbeat_invalid_json() {
+        let error = parse_heartbeat("<html>502 Bad Gateway</html>").unwrap_err();
+        assert!(error.starts_with("Invalid heartbeat response: "), "unexpected error: {}", error);
+
+        assert!(parse_heartbeat(r#"{"commands": "reboot"}"#).is_err());
+    }
+
+    #[test]
+    fn result_serialization() {
+        let result = CommandResult::ok("c1").with("cal_factor", 0.5);
+        assert_eq!(
+            serde_json::to_value(&result).unwrap(),
+            json!({ "id": "c1", "status": "ok", "data": { "cal_factor": 0.5 } })
+        );
+
+        let result = CommandResult::error("c2", "Scale not initialized".to_string());
+        assert_eq!(
+            serde_json::to_value(&result).unwrap(),
+            json!({ "id": "c2", "status": "error", "error": "Scale not initialized", "data": {} })
+        );
+    }
+
+    #[test]
+    fn api_key_debug_hides_key() {
+        let key = ApiKey("sb_0123456789abcdef".to_string());
+        assert_eq!(key.prefix(), "sb_01234");
+        assert_eq!(format!("{:?}", key), "ApiKey(sb_01234...)");
+        assert_eq!(ApiKey("short".to_string()).prefix(), "");
+    }
+}
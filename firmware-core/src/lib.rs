@@ -0,0 +1,26 @@
+//! Hardware-independent parts of the SpoolBuddy firmware.
+//!
+//! NFC tag formats and reader protocols, the Pico bridge protocol, scale
+//! filtering/calibration/drift and the NAU7802 driver (on any embedded-hal
+//! I2C bus), backend command parsing, mDNS discovery, CRC and crypto. Kept apart from the firmware
+//! crate, which only builds for the ESP32-S3, so all of it can be tested on
+//! the host with `cargo test`. The firmware re-exports these modules under
+//! their old paths.
+
+/// CRC-16 for bridge frames and NVS blobs
+pub mod crc;
+
+/// SHA-256, HMAC/HKDF and base64 for certificate fingerprints and tag keys
+pub mod crypto;
+
+/// Typed commands from the backend heartbeat
+pub mod device_command;
+
+/// mDNS / DNS-SD discovery of the backend server
+pub mod mdns;
+
+/// NFC tags, readers and the Pico bridge protocol
+pub mod nfc;
+
+/// Weight processing and the NAU7802 driver
+pub mod scale;
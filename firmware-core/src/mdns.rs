@@ -0,0 +1,548 @@
+//! mDNS / DNS-SD discovery of the SpoolBuddy backend
+//!
+//! Sends a one-shot DNS-SD browse query (RFC 6762 §5.1) to 224.0.0.251:5353
+//! from an ephemeral port, collects PTR/SRV/TXT/A records from the replies
+//! and resolves them into service instances.
+//!
+//! The packet building and parsing functions are pure and have no ESP-IDF
+//! dependencies, so they can be exercised on the host with captured packets.
+
+use log::{info, warn};
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+use std::time::{Duration, Instant};
+
+/// Service types browsed for the backend.
+/// The backend registers "_spbuddy-srv" because DNS-SD service names are
+/// limited to 15 characters; the long form is kept for older servers.
+pub const SERVICE_TYPES: [&str; 2] = ["_spbuddy-srv._tcp.local", "_spoolbuddy-server._tcp.local"];
+
+/// mDNS multicast group and port
+const MDNS_ADDR: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 251);
+const MDNS_PORT: u16 = 5353;
+
+/// Default backend port if a responder has no usable SRV record
+pub const DEFAULT_PORT: u16 = 3000;
+
+/// Maximum number of compression pointers followed while reading a name
+const MAX_NAME_JUMPS: usize = 16;
+
+/// DNS record types
+pub mod rtype {
+    pub const A: u16 = 1;
+    pub const PTR: u16 = 12;
+    pub const TXT: u16 = 16;
+    pub const SRV: u16 = 33;
+}
+
+/// DNS class IN
+const CLASS_IN: u16 = 1;
+
+/// Decoded resource record data
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordData {
+    A([u8; 4]),
+    Ptr(String),
+    Srv { priority: u16, weight: u16, port: u16, target: String },
+    Txt(Vec<String>),
+    Other(u16),
+}
+
+/// Resource record from an answer, authority or additional section
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResourceRecord {
+    pub name: String,
+    pub ttl: u32,
+    pub data: RecordData,
+}
+
+/// Resolved DNS-SD service instance
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ServiceInstance {
+    /// Full instance name (e.g. "SpoolBuddy._spbuddy-srv._tcp.local")
+    pub instance: String,
+    /// SRV target host (e.g. "spoolbuddy.local")
+    pub host: String,
+    /// IPv4 address from A record, if known
+    pub ip: Option<[u8; 4]>,
+    pub port: u16,
+    pub priority: u16,
+    pub weight: u16,
+    /// TXT key/value pairs
+    pub txt: Vec<(String, String)>,
+}
+
+impl ServiceInstance {
+    /// Look up a TXT value by key (case-insensitive, per RFC 6763 §6.4)
+    pub fn txt_value(&self, key: &str) -> Option<&str> {
+        self.txt
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Base URL for this instance, e.g. "http://192.168.1.10:3000"
+    /// Without an A record the SRV target hostname is used (resolved per
+    /// request); None if neither is known.
+    pub fn url(&self) -> Option<String> {
+        let scheme = match self.txt_value("scheme") {
+            Some("https") => "https",
+            _ => "http",
+        };
+        match self.ip {
+            Some(ip) => Some(format!("{}://{}.{}.{}.{}:{}", scheme, ip[0], ip[1], ip[2], ip[3], self.port)),
+            None if !self.host.is_empty() => Some(format!("{}://{}:{}", scheme, self.host, self.port)),
+            None => None,
+        }
+    }
+}
+
+// =============================================================================
+// Packet building
+// =============================================================================
+
+/// Append a dotted name as DNS labels
+fn write_name(buf: &mut Vec<u8>, name: &str) {
+    for label in name.trim_end_matches('.').split('.') {
+        if label.is_empty() {
+            continue;
+        }
+        let bytes = label.as_bytes();
+        let len = bytes.len().min(63);
+        buf.push(len as u8);
+        buf.extend_from_slice(&bytes[..len]);
+    }
+    buf.push(0);
+}
+
+/// Build a query packet with one question per name
+pub fn build_query(id: u16, names: &[&str], qtype: u16) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(12 + names.len() * 32);
+    buf.extend_from_slice(&id.to_be_bytes());
+    buf.extend_from_slice(&0u16.to_be_bytes()); // flags: standard query
+    buf.extend_from_slice(&(names.len() as u16).to_be_bytes()); // QDCOUNT
+    buf.extend_from_slice(&[0, 0, 0, 0, 0, 0]); // AN/NS/AR counts
+
+    for name in names {
+        write_name(&mut buf, name);
+        buf.extend_from_slice(&qtype.to_be_bytes());
+        buf.extend_from_slice(&CLASS_IN.to_be_bytes());
+    }
+    buf
+}
+
+// =============================================================================
+// Packet parsing
+// =============================================================================
+
+fn read_u16(data: &[u8], pos: usize) -> Result<u16, &'static str> {
+    match data.get(pos..pos + 2) {
+        Some(b) => Ok(u16::from_be_bytes([b[0], b[1]])),
+        None => Err("Truncated packet"),
+    }
+}
+
+fn read_u32(data: &[u8], pos: usize) -> Result<u32, &'static str> {
+    match data.get(pos..pos + 4) {
+        Some(b) => Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]])),
+        None => Err("Truncated packet"),
+    }
+}
+
+/// Read a (possibly compressed) name starting at `pos`
+/// Returns the dotted name and the position just after it in the original stream
+fn read_name(data: &[u8], mut pos: usize) -> Result<(String, usize), &'static str> {
+    let mut name = String::new();
+    let mut end: Option<usize> = None;
+    let mut jumps = 0;
+
+    loop {
+        let len = *data.get(pos).ok_or("Truncated name")? as usize;
+        match len & 0xC0 {
+            0x00 => {
+                if len == 0 {
+                    pos += 1;
+                    break;
+                }
+                let label = data.get(pos + 1..pos + 1 + len).ok_or("Truncated label")?;
+                if !name.is_empty() {
+                    name.push('.');
+                }
+                name.push_str(&String::from_utf8_lossy(label));
+                pos += 1 + len;
+            }
+            0xC0 => {
+                let lo = *data.get(pos + 1).ok_or("Truncated pointer")? as usize;
+                if end.is_none() {
+                    end = Some(pos + 2);
+                }
+                jumps += 1;
+                if jumps > MAX_NAME_JUMPS {
+                    return Err("Name compression loop");
+                }
+                pos = ((len & 0x3F) << 8) | lo;
+            }
+            _ => return Err("Unsupported label type"),
+        }
+    }
+
+    Ok((name, end.unwrap_or(pos)))
+}
+
+/// Decode TXT rdata into its character strings
+fn parse_txt(rdata: &[u8]) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut pos = 0;
+    while pos < rdata.len() {
+        let len = rdata[pos] as usize;
+        pos += 1;
+        let end = (pos + len).min(rdata.len());
+        if len > 0 {
+            out.push(String::from_utf8_lossy(&rdata[pos..end]).into_owned());
+        }
+        pos = end;
+    }
+    out
+}
+
+/// Parse a DNS message and return every record in the answer, authority
+/// and additional sections. Question entries are skipped.
+pub fn parse_packet(data: &[u8]) -> Result<Vec<ResourceRecord>, &'static str> {
+    if data.len() < 12 {
+        return Err("Packet too short");
+    }
+
+    let flags = read_u16(data, 2)?;
+    if flags & 0x8000 == 0 {
+        return Err("Not a response");
+    }
+
+    let qdcount = read_u16(data, 4)? as usize;
+    let rrcount = read_u16(data, 6)? as usize + read_u16(data, 8)? as usize + read_u16(data, 10)? as usize;
+
+    let mut pos = 12;
+    for _ in 0..qdcount {
+        let (_, next) = read_name(data, pos)?;
+        pos = next + 4; // QTYPE + QCLASS
+    }
+
+    let mut records = Vec::with_capacity(rrcount);
+    for _ in 0..rrcount {
+        let (name, next) = read_name(data, pos)?;
+        let rr_type = read_u16(data, next)?;
+        let ttl = read_u32(data, next + 4)?;
+        let rdlen = read_u16(data, next + 8)? as usize;
+        let rdata_start = next + 10;
+        let rdata = data.get(rdata_start..rdata_start + rdlen).ok_or("Truncated rdata")?;
+
+        let rdata_parsed = match rr_type {
+            rtype::A if rdlen == 4 => RecordData::A([rdata[0], rdata[1], rdata[2], rdata[3]]),
+            rtype::PTR => RecordData::Ptr(read_name(data, rdata_start)?.0),
+            rtype::SRV if rdlen >= 7 => RecordData::Srv {
+                priority: read_u16(data, rdata_start)?,
+                weight: read_u16(data, rdata_start + 2)?,
+                port: read_u16(data, rdata_start + 4)?,
+                target: read_name(data, rdata_start + 6)?.0,
+            },
+            rtype::TXT => RecordData::Txt(parse_txt(rdata)),
+            other => RecordData::Other(other),
+        };
+
+        records.push(ResourceRecord { name, ttl, data: rdata_parsed });
+        pos = rdata_start + rdlen;
+    }
+
+    Ok(records)
+}
+
+// =============================================================================
+// Resolution
+// =============================================================================
+
+/// Resolve service instances of `service_type` from a set of records.
+/// Records may come from several packets. Instances with TTL 0 (goodbye
+/// packets) are dropped.
+pub fn resolve_instances(records: &[ResourceRecord], service_type: &str) -> Vec<ServiceInstance> {
+    let mut instances: Vec<ServiceInstance> = Vec::new();
+
+    for rr in records {
+        if let RecordData::Ptr(target) = &rr.data {
+            if rr.name.eq_ignore_ascii_case(service_type)
+                && rr.ttl > 0
+                && !instances.iter().any(|i| i.instance.eq_ignore_ascii_case(target))
+            {
+                instances.push(ServiceInstance {
+                    instance: target.clone(),
+                    port: DEFAULT_PORT,
+                    ..Default::default()
+                });
+            }
+        }
+    }
+
+    for inst in instances.iter_mut() {
+        for rr in records.iter().filter(|rr| rr.name.eq_ignore_ascii_case(&inst.instance)) {
+            match &rr.data {
+                RecordData::Srv { priority, weight, port, target } => {
+                    inst.priority = *priority;
+                    inst.weight = *weight;
+                    inst.port = *port;
+                    inst.host = target.clone();
+                }
+                RecordData::Txt(strings) => {
+                    inst.txt = strings
+                        .iter()
+                        .map(|s| match s.split_once('=') {
+                            Some((k, v)) => (k.to_string(), v.to_string()),
+                            None => (s.clone(), String::new()),
+                        })
+                        .collect();
+                }
+                _ => {}
+            }
+        }
+
+        if !inst.host.is_empty() {
+            inst.ip = records.iter().find_map(|rr| match rr.data {
+                RecordData::A(ip) if rr.ttl > 0 && rr.name.eq_ignore_ascii_case(&inst.host) => Some(ip),
+                _ => None,
+            });
+        }
+    }
+
+    instances
+}
+
+/// Choose one instance among several responders.
+///
+/// Only instances with an address or hostname are considered. An instance
+/// matching `preferred_url` (the server used last) wins; otherwise the
+/// lowest SRV priority, then highest weight, then instance name is used so
+/// the choice is stable across reboots.
+pub fn choose_instance<'a>(
+    instances: &'a [ServiceInstance],
+    preferred_url: Option<&str>,
+) -> Option<&'a ServiceInstance> {
+    let mut candidates: Vec<&ServiceInstance> = instances.iter().filter(|i| i.url().is_some()).collect();
+
+    if let Some(preferred) = preferred_url {
+        let preferred = preferred.trim_end_matches('/');
+        if let Some(inst) = candidates.iter().find(|i| i.url().as_deref() == Some(preferred)) {
+            return Some(*inst);
+        }
+    }
+
+    candidates.sort_by(|a, b| {
+        a.priority
+            .cmp(&b.priority)
+            .then(b.weight.cmp(&a.weight))
+            .then(a.instance.cmp(&b.instance))
+    });
+    candidates.first().copied()
+}
+
+// =============================================================================
+// Network
+// =============================================================================
+
+/// Browse for backend instances on the local network
+/// Blocks for up to `timeout`. Returns every instance with an address or
+/// hostname.
+pub fn discover(timeout: Duration) -> Result<Vec<ServiceInstance>, String> {
+    let socket = UdpSocket::bind("0.0.0.0:0").map_err(|e| format!("Bind failed: {:?}", e))?;
+    socket
+        .set_read_timeout(Some(Duration::from_millis(250)))
+        .map_err(|e| format!("Set timeout failed: {:?}", e))?;
+
+    let dest: SocketAddr = (MDNS_ADDR, MDNS_PORT).into();
+    let query = build_query(0, &SERVICE_TYPES, rtype::PTR);
+    socket.send_to(&query, dest).map_err(|e| format!("Send failed: {:?}", e))?;
+    info!("mDNS browse sent for {:?}", SERVICE_TYPES);
+
+    let mut records: Vec<ResourceRecord> = Vec::new();
+    let mut buf = [0u8; 1500];
+    let deadline = Instant::now() + timeout;
+    let mut address_query_sent = false;
+
+    while Instant::now() < deadline {
+        match socket.recv_from(&mut buf) {
+            Ok((len, addr)) => match parse_packet(&buf[..len]) {
+                Ok(rrs) => records.extend(rrs),
+                Err(e) => warn!("mDNS: bad packet from {}: {}", addr, e),
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock || e.kind() == std::io::ErrorKind::TimedOut => {}
+            Err(e) => return Err(format!("Receive failed: {:?}", e)),
+        }
+
+        // Responders usually include SRV/A as additional records, but if only
+        // the SRV target came back, ask for its address once.
+        if !address_query_sent {
+            let unresolved: Vec<String> = SERVICE_TYPES
+                .iter()
+                .flat_map(|t| resolve_instances(&records, t))
+                .filter(|i| i.ip.is_none() && !i.host.is_empty())
+                .map(|i| i.host)
+                .collect();
+            if !unresolved.is_empty() {
+                let names: Vec<&str> = unresolved.iter().map(|s| s.as_str()).collect();
+                let _ = socket.send_to(&build_query(0, &names, rtype::A), dest);
+                address_query_sent = true;
+            }
+        }
+    }
+
+    let mut instances: Vec<ServiceInstance> = Vec::new();
+    for service_type in SERVICE_TYPES {
+        for inst in resolve_instances(&records, service_type) {
+            if inst.url().is_some() && !instances.iter().any(|i| i.url() == inst.url()) {
+                instances.push(inst);
+            }
+        }
+    }
+
+    info!("mDNS discovery found {} instance(s)", instances.len());
+    Ok(instances)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Responses as the backend's responder (python-zeroconf) sends them:
+    // cache-flush bit on unique records, names compressed against earlier ones.
+
+    // PTR answer with SRV, TXT, A and AAAA additionals
+    const RESPONSE_FULL: &[u8] = &[
+        0x00, 0x00, 0x84, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x04, 0x0c, 0x5f, 0x73, 0x70,
+        0x62, 0x75, 0x64, 0x64, 0x79, 0x2d, 0x73, 0x72, 0x76, 0x04, 0x5f, 0x74, 0x63, 0x70, 0x05, 0x6c,
+        0x6f, 0x63, 0x61, 0x6c, 0x00, 0x00, 0x0c, 0x00, 0x01, 0x00, 0x00, 0x11, 0x94, 0x00, 0x0d, 0x0a,
+        0x53, 0x70, 0x6f, 0x6f, 0x6c, 0x42, 0x75, 0x64, 0x64, 0x79, 0xc0, 0x0c, 0xc0, 0x2f, 0x00, 0x21,
+        0x80, 0x01, 0x00, 0x00, 0x00, 0x78, 0x00, 0x1a, 0x00, 0x00, 0x00, 0x00, 0x0b, 0xb8, 0x11, 0x73,
+        0x70, 0x6f, 0x6f, 0x6c, 0x62, 0x75, 0x64, 0x64, 0x79, 0x2d, 0x73, 0x65, 0x72, 0x76, 0x65, 0x72,
+        0xc0, 0x1e, 0xc0, 0x2f, 0x00, 0x10, 0x80, 0x01, 0x00, 0x00, 0x11, 0x94, 0x00, 0x21, 0x0d, 0x76,
+        0x65, 0x72, 0x73, 0x69, 0x6f, 0x6e, 0x3d, 0x30, 0x2e, 0x33, 0x2e, 0x31, 0x0b, 0x73, 0x63, 0x68,
+        0x65, 0x6d, 0x65, 0x3d, 0x68, 0x74, 0x74, 0x70, 0x06, 0x70, 0x61, 0x74, 0x68, 0x3d, 0x2f, 0xc0,
+        0x4e, 0x00, 0x01, 0x80, 0x01, 0x00, 0x00, 0x00, 0x78, 0x00, 0x04, 0xc0, 0xa8, 0x01, 0x2a, 0xc0,
+        0x4e, 0x00, 0x1c, 0x80, 0x01, 0x00, 0x00, 0x00, 0x78, 0x00, 0x10, 0xfe, 0x80, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01,
+    ];
+
+    // PTR answer with SRV and TXT only (https on port 8443)
+    const RESPONSE_NO_ADDRESS: &[u8] = &[
+        0x00, 0x00, 0x84, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x0c, 0x5f, 0x73, 0x70,
+        0x62, 0x75, 0x64, 0x64, 0x79, 0x2d, 0x73, 0x72, 0x76, 0x04, 0x5f, 0x74, 0x63, 0x70, 0x05, 0x6c,
+        0x6f, 0x63, 0x61, 0x6c, 0x00, 0x00, 0x0c, 0x00, 0x01, 0x00, 0x00, 0x11, 0x94, 0x00, 0x0d, 0x0a,
+        0x53, 0x70, 0x6f, 0x6f, 0x6c, 0x42, 0x75, 0x64, 0x64, 0x79, 0xc0, 0x0c, 0xc0, 0x2f, 0x00, 0x21,
+        0x80, 0x01, 0x00, 0x00, 0x00, 0x78, 0x00, 0x14, 0x00, 0x00, 0x00, 0x00, 0x20, 0xfb, 0x0b, 0x77,
+        0x6f, 0x72, 0x6b, 0x73, 0x68, 0x6f, 0x70, 0x2d, 0x70, 0x69, 0xc0, 0x1e, 0xc0, 0x2f, 0x00, 0x10,
+        0x80, 0x01, 0x00, 0x00, 0x11, 0x94, 0x00, 0x0d, 0x0c, 0x73, 0x63, 0x68, 0x65, 0x6d, 0x65, 0x3d,
+        0x68, 0x74, 0x74, 0x70, 0x73,
+    ];
+
+    // Answer to the follow-up A query for the SRV target
+    const RESPONSE_ADDRESS: &[u8] = &[
+        0x00, 0x00, 0x84, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x0b, 0x77, 0x6f, 0x72,
+        0x6b, 0x73, 0x68, 0x6f, 0x70, 0x2d, 0x70, 0x69, 0x05, 0x6c, 0x6f, 0x63, 0x61, 0x6c, 0x00, 0x00,
+        0x01, 0x80, 0x01, 0x00, 0x00, 0x00, 0x78, 0x00, 0x04, 0x0a, 0x00, 0x00, 0x07,
+    ];
+
+    // Goodbye: the PTR with TTL 0
+    const RESPONSE_GOODBYE: &[u8] = &[
+        0x00, 0x00, 0x84, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x0c, 0x5f, 0x73, 0x70,
+        0x62, 0x75, 0x64, 0x64, 0x79, 0x2d, 0x73, 0x72, 0x76, 0x04, 0x5f, 0x74, 0x63, 0x70, 0x05, 0x6c,
+        0x6f, 0x63, 0x61, 0x6c, 0x00, 0x00, 0x0c, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0d, 0x0a,
+        0x53, 0x70, 0x6f, 0x6f, 0x6c, 0x42, 0x75, 0x64, 0x64, 0x79, 0xc0, 0x0c,
+    ];
+
+    const SERVICE: &str = "_spbuddy-srv._tcp.local";
+    const INSTANCE: &str = "SpoolBuddy._spbuddy-srv._tcp.local";
+
+    #[test]
+    fn parses_all_record_types() {
+        let records = parse_packet(RESPONSE_FULL).unwrap();
+        assert_eq!(records.len(), 5);
+        assert_eq!(records[0], ResourceRecord { name: SERVICE.into(), ttl: 4500, data: RecordData::Ptr(INSTANCE.into()) });
+        assert_eq!(
+            records[1].data,
+            RecordData::Srv { priority: 0, weight: 0, port: 3000, target: "spoolbuddy-server.local".into() }
+        );
+        assert_eq!(
+            records[2].data,
+            RecordData::Txt(vec!["version=0.3.1".into(), "scheme=http".into(), "path=/".into()])
+        );
+        assert_eq!(records[3].name, "spoolbuddy-server.local");
+        assert_eq!(records[3].data, RecordData::A([192, 168, 1, 42]));
+        assert_eq!(records[4].data, RecordData::Other(28));
+    }
+
+    #[test]
+    fn resolves_instance_with_address() {
+        let records = parse_packet(RESPONSE_FULL).unwrap();
+        let instances = resolve_instances(&records, SERVICE);
+        assert_eq!(instances.len(), 1);
+        let inst = &instances[0];
+        assert_eq!(inst.host, "spoolbuddy-server.local");
+        assert_eq!(inst.ip, Some([192, 168, 1, 42]));
+        assert_eq!(inst.txt_value("VERSION"), Some("0.3.1"));
+        assert_eq!(inst.url().as_deref(), Some("http://192.168.1.42:3000"));
+    }
+
+    #[test]
+    fn falls_back_to_hostname_without_address() {
+        let records = parse_packet(RESPONSE_NO_ADDRESS).unwrap();
+        let instances = resolve_instances(&records, SERVICE);
+        assert_eq!(instances[0].ip, None);
+        assert_eq!(instances[0].url().as_deref(), Some("https://workshop-pi.local:8443"));
+        assert!(choose_instance(&instances, None).is_some());
+    }
+
+    #[test]
+    fn address_from_a_later_packet() {
+        let mut records = parse_packet(RESPONSE_NO_ADDRESS).unwrap();
+        records.extend(parse_packet(RESPONSE_ADDRESS).unwrap());
+        let instances = resolve_instances(&records, SERVICE);
+        assert_eq!(instances[0].url().as_deref(), Some("https://10.0.0.7:8443"));
+    }
+
+    #[test]
+    fn goodbye_drops_instance() {
+        let records = parse_packet(RESPONSE_GOODBYE).unwrap();
+        assert!(resolve_instances(&records, SERVICE).is_empty());
+    }
+
+    #[test]
+    fn prefers_previous_server_then_priority() {
+        let server = |name: &str, ip: u8, priority: u16| ServiceInstance {
+            instance: name.into(),
+            ip: Some([192, 168, 1, ip]),
+            port: 3000,
+            priority,
+            ..Default::default()
+        };
+        let instances = [server("b", 2, 10), server("a", 1, 20), server("c", 3, 10)];
+        assert_eq!(choose_instance(&instances, None).unwrap().instance, "b");
+        assert_eq!(choose_instance(&instances, Some("http://192.168.1.1:3000/")).unwrap().instance, "a");
+        assert_eq!(choose_instance(&[], None), None);
+    }
+
+    #[test]
+    fn rejects_malformed_packets() {
+        assert_eq!(parse_packet(&RESPONSE_FULL[..8]), Err("Packet too short"));
+        assert!(parse_packet(&RESPONSE_FULL[..RESPONSE_FULL.len() - 3]).is_err());
+
+        // Query, not a response
+        assert_eq!(parse_packet(&build_query(0, &[SERVICE], rtype::PTR)), Err("Not a response"));
+
+        // Answer name pointing at itself
+        let mut looped = vec![0, 0, 0x84, 0, 0, 0, 0, 1, 0, 0, 0, 0];
+        looped.extend_from_slice(&[0xC0, 12, 0, 1, 0, 1, 0, 0, 0, 120, 0, 4, 1, 2, 3, 4]);
+        assert_eq!(parse_packet(&looped), Err("Name compression loop"));
+    }
+
+    #[test]
+    fn builds_browse_query() {
+        let query = build_query(0, &[SERVICE], rtype::PTR);
+        assert_eq!(&query[..12], &[0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(&query[12..25], b"\x0c_spbuddy-srv");
+        assert_eq!(&query[query.len() - 4..], &[0, 12, 0, 1]);
+    }
+}
@@ -0,0 +1,516 @@
+//! Emulator of the Pico end of the NFC bridge
+//!
+//! Implements [`BridgeTransport`] like the I2C bus does, and answers the way
+//! pico-nfc-bridge.ino does: BUSY until a command's processing time has
+//! passed on a virtual clock (advanced by `delay_ms`), then the response
+//! frame until the next command. Tags can be placed and removed, and faults
+//! injected (slow processing, corrupted reads, old unframed firmware), so the
+//! `i2c_bridge` driver can be exercised on the host without hardware.
+
+use super::bridge_protocol::{
+    self, BridgeTransport, Request, CAP_WRITE_TAG, FRAME_OVERHEAD, FRAME_SOF, FRAME_STATE_BAD_FRAME,
+    FRAME_STATE_BUSY, FRAME_STATE_READY, PROTOCOL_VERSION,
+};
+use super::i2c_bridge::{TAG_TYPE_MIFARE_1K, TAG_TYPE_NTAG};
+
+const CMD_GET_STATUS: u8 = 0x00;
+const CMD_GET_VERSION: u8 = 0x01;
+const CMD_SCAN_TAG: u8 = 0x10;
+const CMD_READ_TAG_DATA: u8 = 0x20;
+const CMD_WRITE_TAG: u8 = 0x30;
+
+/// CMD_WRITE_TAG status codes (as on the Pico)
+const WRITE_OK: u8 = 0;
+const WRITE_NO_TAG: u8 = 1;
+const WRITE_FAILED: u8 = 2;
+const WRITE_NOT_NTAG: u8 = 3;
+const WRITE_READ_ONLY: u8 = 4;
+const WRITE_OUT_OF_RANGE: u8 = 5;
+const WRITE_BAD_REQUEST: u8 = 6;
+const WRITE_NOT_NDEF: u8 = 7;
+
+/// NTAG pages returned by CMD_READ_TAG_DATA (pages 4-39)
+const NTAG_READ_PAGES: usize = 36;
+
+/// A tag on the emulated reader
+#[derive(Debug, Clone)]
+pub enum EmulatedTag {
+    /// NTAG with its full memory from page 0 (CC in page 3)
+    Ntag { uid: Vec<u8>, memory: Vec<u8> },
+    /// MIFARE Classic with the Bambu blocks back to back (only blocks 1, 2, 4
+    /// and 5 emulate an older bridge)
+    Mifare { uid: Vec<u8>, blocks: Vec<u8> },
+}
+
+impl EmulatedTag {
+    /// Blank, NDEF-formatted NTAG213 (144 byte data area)
+    pub fn ntag213(uid: &[u8]) -> Self {
+        Self::blank_ntag(uid, 45, 0x12)
+    }
+
+    /// Blank, NDEF-formatted NTAG215 (496 byte data area)
+    pub fn ntag215(uid: &[u8]) -> Self {
+        Self::blank_ntag(uid, 135, 0x3E)
+    }
+
+    fn blank_ntag(uid: &[u8], pages: usize, cc_size: u8) -> Self {
+        let mut memory = vec![0u8; pages * 4];
+        memory[12..16].copy_from_slice(&[0xE1, 0x10, cc_size, 0x00]);
+        // Empty NDEF message
+        memory[16..19].copy_from_slice(&[0x03, 0x00, 0xFE]);
+        Self::Ntag { uid: uid.to_vec(), memory }
+    }
+
+    /// Set the CC access byte to read-only
+    pub fn read_only(mut self) -> Self {
+        if let Self::Ntag { memory, .. } = &mut self {
+            memory[15] = 0x0F;
+        }
+        self
+    }
+
+    fn uid(&self) -> &[u8] {
+        match self {
+            Self::Ntag { uid, .. } | Self::Mifare { uid, .. } => uid,
+        }
+    }
+}
+
+/// Emulated Pico NFC bridge
+pub struct PicoEmulator {
+    /// Firmware version reported by CMD_GET_VERSION
+    pub version: (u8, u8),
+    /// Capability bits reported by CMD_GET_VERSION
+    pub capabilities: u8,
+    /// Protocol version reported by CMD_GET_VERSION (frames always use the current one)
+    pub protocol: u8,
+    /// Answer like firmware from before the framed protocol
+    pub unframed_only: bool,
+    /// Processing time per command
+    pub scan_ms: u32,
+    pub read_ms: u32,
+    pub write_ms: u32,
+    /// Corrupt this many upcoming full-frame reads (a flipped payload bit)
+    pub corrupt_reads: usize,
+    /// Tag currently on the reader
+    pub tag: Option<EmulatedTag>,
+
+    now_ms: u32,
+    pending: Option<(Request, u32)>,
+    frame: Vec<u8>,
+    unframed_response: Vec<u8>,
+    commands: usize,
+}
+
+impl Default for PicoEmulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PicoEmulator {
+    pub fn new() -> Self {
+        Self {
+            version: (2, 0),
+            capabilities: CAP_WRITE_TAG,
+            protocol: PROTOCOL_VERSION,
+            unframed_only: false,
+            scan_ms: 120,
+            read_ms: 300,
+            write_ms: 40,
+            corrupt_reads: 0,
+            tag: None,
+            now_ms: 0,
+            pending: None,
+            frame: bridge_protocol::encode_response(0, FRAME_STATE_READY, &[]),
+            unframed_response: Vec::new(),
+            commands: 0,
+        }
+    }
+
+    /// Virtual time passed in `delay_ms` calls
+    pub fn now_ms(&self) -> u32 {
+        self.now_ms
+    }
+
+    /// Number of commands received
+    pub fn commands(&self) -> usize {
+        self.commands
+    }
+
+    /// NTAG memory of the tag on the reader
+    pub fn ntag_memory(&self) -> Option<&[u8]> {
+        match &self.tag {
+            Some(EmulatedTag::Ntag { memory, .. }) => Some(memory),
+            _ => None,
+        }
+    }
+
+    fn processing_ms(&self, cmd: u8) -> u32 {
+        match cmd {
+            CMD_SCAN_TAG => self.scan_ms,
+            CMD_READ_TAG_DATA => self.read_ms,
+            CMD_WRITE_TAG => self.write_ms,
+            _ => 1,
+        }
+    }
+
+    /// Finish the pending command once its processing time has passed
+    fn update(&mut self) {
+        let due = matches!(self.pending, Some((_, ready_at)) if self.now_ms >= ready_at);
+        if due {
+            let (request, _) = self.pending.take().unwrap();
+            let payload = self.process(request.cmd, &request.payload);
+            self.frame = bridge_protocol::encode_response(request.seq, FRAME_STATE_READY, &payload);
+        }
+    }
+
+    fn process(&mut self, cmd: u8, payload: &[u8]) -> Vec<u8> {
+        match cmd {
+            CMD_GET_STATUS => vec![0, self.tag.is_some() as u8],
+            CMD_GET_VERSION => {
+                vec![0, self.version.0, self.version.1, self.protocol, self.capabilities]
+            }
+            CMD_SCAN_TAG => match &self.tag {
+                Some(tag) => {
+                    let mut resp = vec![0, tag.uid().len() as u8];
+                    resp.extend_from_slice(tag.uid());
+                    resp
+                }
+                None => vec![1],
+            },
+            CMD_READ_TAG_DATA => self.read_tag_data(),
+            CMD_WRITE_TAG => self.write_tag(payload),
+            _ => vec![0xFF],
+        }
+    }
+
+    fn read_tag_data(&self) -> Vec<u8> {
+        let (tag_type, uid, data) = match &self.tag {
+            Some(EmulatedTag::Ntag { uid, memory }) => {
+                let mut data = memory.get(16..).unwrap_or(&[]).to_vec();
+                data.resize(NTAG_READ_PAGES * 4, 0);
+                (TAG_TYPE_NTAG, uid, data)
+            }
+            Some(EmulatedTag::Mifare { uid, blocks }) => (TAG_TYPE_MIFARE_1K, uid, blocks.clone()),
+            None => return vec![1],
+        };
+        let mut resp = vec![0, tag_type, uid.len() as u8];
+        resp.extend_from_slice(uid);
+        resp.extend_from_slice(&data);
+        resp
+    }
+
+    /// Same checks and order as handleWriteTag() on the Pico
+    fn write_tag(&mut self, payload: &[u8]) -> Vec<u8> {
+        let memory = match &mut self.tag {
+            Some(EmulatedTag::Ntag { memory, .. }) => memory,
+            Some(_) => return vec![WRITE_NOT_NTAG, 0, 0],
+            None => return vec![WRITE_NO_TAG, 0, 0],
+        };
+
+        let (start_page, page_count) = match payload {
+            [start, count, ..] if payload.len() == 2 + *count as usize * 4 => (*start as usize, *count as usize),
+            _ => return vec![WRITE_BAD_REQUEST, 0, 0],
+        };
+
+        let cc = [memory[12], memory[13], memory[14], memory[15]];
+        let status = if cc[0] != 0xE1 {
+            WRITE_NOT_NDEF
+        } else if cc[3] & 0x0F != 0 {
+            WRITE_READ_ONLY
+        } else {
+            let last_page = 4 + cc[2] as usize * 8 / 4;
+            if page_count > 0 && (start_page < 4 || start_page + page_count > last_page) {
+                WRITE_OUT_OF_RANGE
+            } else if (start_page + page_count) * 4 > memory.len() {
+                WRITE_FAILED
+            } else {
+                memory[start_page * 4..(start_page + page_count) * 4].copy_from_slice(&payload[2..]);
+                WRITE_OK
+            }
+        };
+        vec![status, cc[2], cc[3]]
+    }
+}
+
+impl BridgeTransport for PicoEmulator {
+    fn write(&mut self, data: &[u8]) -> Result<(), &'static str> {
+        self.commands += 1;
+
+        if self.unframed_only {
+            // Old firmware: the command byte comes first, no frames at all
+            self.unframed_response = match data.first() {
+                Some(&CMD_GET_VERSION) => vec![0, self.version.0, self.version.1],
+                Some(&cmd) if cmd != FRAME_SOF => self.process(cmd, data.get(2..).unwrap_or(&[])),
+                _ => vec![0xFF],
+            };
+            return Ok(());
+        }
+
+        if data.first() != Some(&FRAME_SOF) {
+            return Err("Emulator only speaks the framed protocol");
+        }
+        match bridge_protocol::decode_request(data) {
+            Ok(request) => {
+                let ready_at = self.now_ms + self.processing_ms(request.cmd);
+                self.frame = bridge_protocol::encode_response(request.seq, FRAME_STATE_BUSY, &[]);
+                self.pending = Some((request, ready_at));
+            }
+            Err(_) => {
+                let seq = data.get(3).copied().unwrap_or(0);
+                self.pending = None;
+                self.frame = bridge_protocol::encode_response(seq, FRAME_STATE_BAD_FRAME, &[]);
+            }
+        }
+        Ok(())
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<(), &'static str> {
+        if self.unframed_only {
+            // Unread bytes come back as 0xFF, and the response is consumed
+            buf.fill(0xFF);
+            let n = buf.len().min(self.unframed_response.len());
+            buf[..n].copy_from_slice(&self.unframed_response[..n]);
+            self.unframed_response.clear();
+            return Ok(());
+        }
+
+        self.update();
+        buf.fill(0xFF);
+        let n = buf.len().min(self.frame.len());
+        buf[..n].copy_from_slice(&self.frame[..n]);
+
+        // Corrupt full reads of a ready frame (header polls stay clean)
+        if self.corrupt_reads > 0 && self.pending.is_none() && buf.len() > FRAME_OVERHEAD {
+            self.corrupt_reads -= 1;
+            buf[FRAME_OVERHEAD - 2] ^= 0x01;
+        }
+        Ok(())
+    }
+
+    fn delay_ms(&mut self, ms: u32) {
+        self.now_ms += ms;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nfc::bridge_protocol::{decode_response, poll_response, send_request, transact, FRAME_HEADER_LEN};
+    use crate::nfc::i2c_bridge::{self, NfcBridgeState, SCAN_TIMEOUT_MS};
+
+    const UID: [u8; 7] = [0x04, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66];
+
+    /// Read the frame the emulator currently answers with
+    fn current_frame(emu: &mut PicoEmulator, len: usize) -> Vec<u8> {
+        let mut buf = vec![0u8; FRAME_OVERHEAD + len];
+        emu.read(&mut buf).unwrap();
+        buf
+    }
+
+    #[test]
+    fn request_framing() {
+        let frame = bridge_protocol::encode_request(CMD_WRITE_TAG, 7, &[4, 1, 0xDE, 0xAD, 0xBE, 0xEF]).unwrap();
+        assert_eq!(frame[..FRAME_HEADER_LEN], [FRAME_SOF, PROTOCOL_VERSION, CMD_WRITE_TAG, 7, 6]);
+        assert_eq!(frame.len(), FRAME_OVERHEAD + 6);
+
+        let request = bridge_protocol::decode_request(&frame).unwrap();
+        assert_eq!(request, Request { cmd: CMD_WRITE_TAG, seq: 7, payload: vec![4, 1, 0xDE, 0xAD, 0xBE, 0xEF] });
+
+        let mut bad_crc = frame.clone();
+        *bad_crc.last_mut().unwrap() ^= 0x01;
+        assert_eq!(bridge_protocol::decode_request(&bad_crc), Err("CRC mismatch"));
+        assert_eq!(bridge_protocol::decode_request(&frame[..frame.len() - 1]), Err("Request length mismatch"));
+
+        let too_long = [0u8; bridge_protocol::MAX_REQUEST_PAYLOAD + 1];
+        assert!(bridge_protocol::encode_request(CMD_WRITE_TAG, 0, &too_long).is_err());
+    }
+
+    #[test]
+    fn response_framing() {
+        let frame = bridge_protocol::encode_response(3, FRAME_STATE_READY, &[0, 1]);
+        assert_eq!(decode_response(&frame, 3), Ok(&[0u8, 1][..]));
+        assert_eq!(decode_response(&frame[..frame.len() - 1], 3), Err("Response truncated"));
+
+        let mut corrupted = frame.clone();
+        corrupted[FRAME_HEADER_LEN] ^= 0x80;
+        assert_eq!(decode_response(&corrupted, 3), Err("CRC mismatch"));
+
+        let busy = bridge_protocol::encode_response(3, FRAME_STATE_BUSY, &[]);
+        assert_eq!(decode_response(&busy, 3), Err("Bridge busy"));
+    }
+
+    #[test]
+    fn rejects_unframed_and_corrupted_requests() {
+        let mut emu = PicoEmulator::new();
+        assert!(emu.write(&[CMD_GET_VERSION]).is_err());
+
+        let mut frame = bridge_protocol::encode_request(CMD_GET_STATUS, 9, &[]).unwrap();
+        *frame.last_mut().unwrap() ^= 0x01;
+        emu.write(&frame).unwrap();
+        assert_eq!(poll_response(&mut emu, 9), Err("Bridge rejected the request frame"));
+    }
+
+    #[test]
+    fn response_echoes_sequence() {
+        let mut emu = PicoEmulator::new();
+        emu.tag = Some(EmulatedTag::ntag213(&UID));
+
+        send_request(&mut emu, CMD_GET_STATUS, 0x42, &[]).unwrap();
+        emu.delay_ms(1);
+        let frame = current_frame(&mut emu, 2);
+        assert_eq!(frame[2], 0x42);
+        assert_eq!(decode_response(&frame, 0x42), Ok(&[0u8, 1][..]));
+        assert_eq!(decode_response(&frame, 0x41), Err("Response sequence mismatch"));
+
+        // A poll for another command never takes this response
+        assert_eq!(poll_response(&mut emu, 0x41), Ok(None));
+        assert_eq!(poll_response(&mut emu, 0x42), Ok(Some(vec![0, 1])));
+    }
+
+    #[test]
+    fn busy_until_processing_done() {
+        let mut emu = PicoEmulator::new();
+        emu.tag = Some(EmulatedTag::ntag213(&UID));
+
+        send_request(&mut emu, CMD_SCAN_TAG, 1, &[]).unwrap();
+        let frame = current_frame(&mut emu, 0);
+        assert_eq!(frame[3], FRAME_STATE_BUSY);
+        assert_eq!(poll_response(&mut emu, 1), Ok(None));
+
+        emu.delay_ms(emu.scan_ms - 1);
+        assert_eq!(poll_response(&mut emu, 1), Ok(None));
+        emu.delay_ms(1);
+        let resp = poll_response(&mut emu, 1).unwrap().unwrap();
+        assert_eq!(resp[..2], [0, UID.len() as u8]);
+        assert_eq!(resp[2..], UID);
+
+        // The response stays until the next command
+        assert_eq!(poll_response(&mut emu, 1).unwrap(), Some(resp));
+    }
+
+    #[test]
+    fn transact_polls_until_ready() {
+        let mut emu = PicoEmulator::new();
+        emu.tag = Some(EmulatedTag::ntag213(&UID));
+
+        let resp = transact(&mut emu, CMD_SCAN_TAG, 2, &[], SCAN_TIMEOUT_MS).unwrap();
+        assert_eq!(resp[0], 0);
+        // Done within one poll interval of the processing time, not the timeout
+        assert!((emu.scan_ms..emu.scan_ms + 10).contains(&emu.now_ms()), "{} ms", emu.now_ms());
+
+        emu.scan_ms = SCAN_TIMEOUT_MS + 100;
+        assert_eq!(transact(&mut emu, CMD_SCAN_TAG, 3, &[], SCAN_TIMEOUT_MS), Err("Bridge response timeout"));
+    }
+
+    #[test]
+    fn rereads_after_crc_error() {
+        let mut emu = PicoEmulator::new();
+        emu.corrupt_reads = 2;
+        assert_eq!(transact(&mut emu, CMD_GET_STATUS, 4, &[], 100), Ok(vec![0, 0]));
+        assert_eq!(emu.corrupt_reads, 0);
+        assert_eq!(emu.commands(), 1);
+
+        emu.corrupt_reads = 3;
+        assert_eq!(transact(&mut emu, CMD_GET_STATUS, 5, &[], 100), Err("CRC mismatch"));
+    }
+
+    #[test]
+    fn negotiates_version() {
+        let mut emu = PicoEmulator::new();
+        emu.version = (2, 3);
+        let mut state = NfcBridgeState::new();
+        i2c_bridge::init_bridge(&mut emu, &mut state).unwrap();
+        assert!(state.initialized);
+        assert_eq!(state.firmware_version, (2, 3));
+        assert_eq!(state.capabilities, CAP_WRITE_TAG);
+        assert!(i2c_bridge::can_write(&state).is_ok());
+
+        // Framed firmware without tag writing
+        let mut emu = PicoEmulator::new();
+        emu.capabilities = 0;
+        let mut state = NfcBridgeState::new();
+        i2c_bridge::init_bridge(&mut emu, &mut state).unwrap();
+        assert_eq!(i2c_bridge::can_write(&state), Err("Bridge firmware cannot write tags"));
+    }
+
+    #[test]
+    fn refuses_other_protocol_versions() {
+        let mut emu = PicoEmulator::new();
+        emu.protocol = PROTOCOL_VERSION + 1;
+        let mut state = NfcBridgeState::new();
+        assert_eq!(i2c_bridge::init_bridge(&mut emu, &mut state), Err("Unsupported bridge protocol"));
+        assert!(!state.initialized);
+    }
+
+    #[test]
+    fn detects_unframed_firmware() {
+        let mut emu = PicoEmulator::new();
+        emu.unframed_only = true;
+        let mut state = NfcBridgeState::new();
+        assert_eq!(i2c_bridge::init_bridge(&mut emu, &mut state), Err("Pico bridge firmware too old"));
+        assert!(!state.initialized);
+    }
+
+    #[test]
+    fn scan_read_and_write_ntag() {
+        let mut emu = PicoEmulator::new();
+        let mut state = NfcBridgeState::new();
+        i2c_bridge::init_bridge(&mut emu, &mut state).unwrap();
+
+        let seq = i2c_bridge::start_scan(&mut emu).unwrap();
+        let resp = transact_pending(&mut emu, seq);
+        assert!(!i2c_bridge::finish_scan(&mut state, seq, &resp));
+
+        emu.tag = Some(EmulatedTag::ntag215(&UID));
+        let seq = i2c_bridge::start_scan(&mut emu).unwrap();
+        let resp = transact_pending(&mut emu, seq);
+        assert!(i2c_bridge::finish_scan(&mut state, seq, &resp));
+        assert_eq!(state.tag_uid[..state.tag_uid_len as usize], UID);
+
+        let seq = i2c_bridge::start_write(&mut emu, 4, &[0x03, 0x02, 0xD0, 0x00, 0xFE]).unwrap();
+        let resp = transact_pending(&mut emu, seq);
+        assert_eq!(i2c_bridge::finish_write(seq, &resp), Ok((0x3E, 0x00)));
+        assert_eq!(emu.ntag_memory().unwrap()[16..24], [0x03, 0x02, 0xD0, 0x00, 0xFE, 0, 0, 0]);
+
+        let seq = i2c_bridge::start_read(&mut emu).unwrap();
+        let resp = transact_pending(&mut emu, seq);
+        let data = i2c_bridge::finish_read(seq, &resp).unwrap();
+        assert_eq!(data.tag_type, TAG_TYPE_NTAG);
+        assert_eq!(data.uid, UID);
+        assert_eq!(data.data.len(), NTAG_READ_PAGES * 4);
+        assert_eq!(data.data[..5], [0x03, 0x02, 0xD0, 0x00, 0xFE]);
+    }
+
+    #[test]
+    fn write_errors() {
+        let mut emu = PicoEmulator::new();
+        emu.tag = Some(EmulatedTag::ntag213(&UID).read_only());
+        let seq = i2c_bridge::start_write(&mut emu, 4, &[0; 4]).unwrap();
+        let resp = transact_pending(&mut emu, seq);
+        assert_eq!(i2c_bridge::finish_write(seq, &resp), Err("Tag is read-only"));
+
+        // NTAG213 data area ends at page 39
+        emu.tag = Some(EmulatedTag::ntag213(&UID));
+        let seq = i2c_bridge::start_write(&mut emu, 39, &[0; 8]).unwrap();
+        let resp = transact_pending(&mut emu, seq);
+        assert_eq!(i2c_bridge::finish_write(seq, &resp), Err("Data does not fit on tag"));
+
+        emu.tag = Some(EmulatedTag::Mifare { uid: UID[..4].to_vec(), blocks: vec![0; 64] });
+        let seq = i2c_bridge::start_write(&mut emu, 4, &[0; 4]).unwrap();
+        let resp = transact_pending(&mut emu, seq);
+        assert_eq!(i2c_bridge::finish_write(seq, &resp), Err("Not an NTAG tag"));
+    }
+
+    /// Poll the response of a command sent with one of the `start_*` calls
+    fn transact_pending(emu: &mut PicoEmulator, seq: u8) -> Vec<u8> {
+        for _ in 0..500 {
+            if let Some(resp) = poll_response(emu, seq).unwrap() {
+                return resp;
+            }
+            emu.delay_ms(10);
+        }
+        panic!("no response for #{}", seq);
+    }
+}
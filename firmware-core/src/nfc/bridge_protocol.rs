@@ -0,0 +1,211 @@
+//! Framed I2C protocol between the ESP32 and the Pico NFC bridge (version 2)
+//!
+//! Request:  [SOF, version, cmd, seq, len, payload[len], crc_hi, crc_lo]
+//! Response: [SOF, version, seq, state, len, payload[len], crc_hi, crc_lo]
+//!
+//! The CRC is CRC-16/CCITT-FALSE over everything before it. The Pico answers
+//! any read with a BUSY frame (echoing the request's seq) until the command
+//! is done, then with the full response until the next command arrives. So
+//! the ESP32 polls the 5-byte header and reads the frame as soon as it is
+//! ready, and can simply re-read a frame that failed its CRC. Sending and
+//! polling are separate steps, so a caller can release the bus in between.
+//!
+//! Nothing here touches the hardware: the I2C bus is a [`BridgeTransport`],
+//! which the Pico emulator also implements.
+
+use crate::crc::crc16;
+
+/// Start of frame marker (never a valid unframed command byte)
+pub const FRAME_SOF: u8 = 0xB5;
+
+/// Protocol version sent in every frame
+pub const PROTOCOL_VERSION: u8 = 2;
+
+/// SOF, version, cmd/seq, seq/state, len
+pub const FRAME_HEADER_LEN: usize = 5;
+
+/// Header plus CRC
+pub const FRAME_OVERHEAD: usize = FRAME_HEADER_LEN + 2;
+
+/// Largest request payload (the Pico command buffer is 64 bytes)
+pub const MAX_REQUEST_PAYLOAD: usize = 64 - FRAME_OVERHEAD;
+
+/// Largest response payload (the Pico response buffer is 200 bytes)
+pub const MAX_RESPONSE_PAYLOAD: usize = 200;
+
+/// Response states
+pub const FRAME_STATE_READY: u8 = 0;
+pub const FRAME_STATE_BUSY: u8 = 1;
+pub const FRAME_STATE_BAD_FRAME: u8 = 2;
+
+/// Capability bits reported by CMD_GET_VERSION
+pub const CAP_WRITE_TAG: u8 = 0x01;
+
+/// How often the header is polled while the Pico is busy
+const POLL_INTERVAL_MS: u32 = 10;
+
+/// Full-frame reads retried after a CRC error
+const CRC_RETRIES: usize = 2;
+
+/// Byte transport to the bridge (the I2C bus, or the emulator in tests)
+pub trait BridgeTransport {
+    fn write(&mut self, data: &[u8]) -> Result<(), &'static str>;
+    fn read(&mut self, buf: &mut [u8]) -> Result<(), &'static str>;
+    fn delay_ms(&mut self, ms: u32);
+}
+
+/// Build a request frame
+pub fn encode_request(cmd: u8, seq: u8, payload: &[u8]) -> Result<Vec<u8>, &'static str> {
+    if payload.len() > MAX_REQUEST_PAYLOAD {
+        return Err("Request payload too large");
+    }
+    let mut frame = Vec::with_capacity(FRAME_OVERHEAD + payload.len());
+    frame.extend_from_slice(&[FRAME_SOF, PROTOCOL_VERSION, cmd, seq, payload.len() as u8]);
+    frame.extend_from_slice(payload);
+    let crc = crc16(&frame);
+    frame.extend_from_slice(&crc.to_be_bytes());
+    Ok(frame)
+}
+
+/// Build a response frame (the Pico side; used by the emulator)
+pub fn encode_response(seq: u8, state: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(FRAME_OVERHEAD + payload.len());
+    frame.extend_from_slice(&[FRAME_SOF, PROTOCOL_VERSION, seq, state, payload.len() as u8]);
+    frame.extend_from_slice(payload);
+    let crc = crc16(&frame);
+    frame.extend_from_slice(&crc.to_be_bytes());
+    frame
+}
+
+/// A validated request frame (the Pico side; used by the emulator)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    pub cmd: u8,
+    pub seq: u8,
+    pub payload: Vec<u8>,
+}
+
+/// Check and split a request frame
+pub fn decode_request(frame: &[u8]) -> Result<Request, &'static str> {
+    if frame.len() < FRAME_OVERHEAD || frame[0] != FRAME_SOF {
+        return Err("Not a request frame");
+    }
+    if frame[1] != PROTOCOL_VERSION {
+        return Err("Unsupported protocol version");
+    }
+    let len = frame[4] as usize;
+    if frame.len() != FRAME_OVERHEAD + len {
+        return Err("Request length mismatch");
+    }
+    check_crc(&frame[..FRAME_HEADER_LEN + len], &frame[FRAME_HEADER_LEN + len..])?;
+    Ok(Request {
+        cmd: frame[2],
+        seq: frame[3],
+        payload: frame[FRAME_HEADER_LEN..FRAME_HEADER_LEN + len].to_vec(),
+    })
+}
+
+/// Response frame header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResponseHeader {
+    pub seq: u8,
+    pub state: u8,
+    pub len: usize,
+}
+
+/// Parse a response header (SOF and version checked, CRC not yet)
+pub fn parse_response_header(header: &[u8]) -> Result<ResponseHeader, &'static str> {
+    if header.len() < FRAME_HEADER_LEN || header[0] != FRAME_SOF {
+        return Err("No response frame");
+    }
+    if header[1] != PROTOCOL_VERSION {
+        return Err("Unsupported protocol version");
+    }
+    Ok(ResponseHeader { seq: header[2], state: header[3], len: header[4] as usize })
+}
+
+/// Check a full response frame for `seq` and return its payload
+pub fn decode_response(frame: &[u8], seq: u8) -> Result<&[u8], &'static str> {
+    let header = parse_response_header(frame)?;
+    let end = FRAME_HEADER_LEN + header.len;
+    if frame.len() < end + 2 {
+        return Err("Response truncated");
+    }
+    check_crc(&frame[..end], &frame[end..end + 2])?;
+    if header.seq != seq {
+        return Err("Response sequence mismatch");
+    }
+    match header.state {
+        FRAME_STATE_READY => Ok(&frame[FRAME_HEADER_LEN..end]),
+        FRAME_STATE_BUSY => Err("Bridge busy"),
+        FRAME_STATE_BAD_FRAME => Err("Bridge rejected the request frame"),
+        _ => Err("Unknown response state"),
+    }
+}
+
+fn check_crc(data: &[u8], crc: &[u8]) -> Result<(), &'static str> {
+    if crc.len() != 2 || crc16(data).to_be_bytes() != [crc[0], crc[1]] {
+        return Err("CRC mismatch");
+    }
+    Ok(())
+}
+
+/// Send a request frame without waiting for the response
+pub fn send_request<T: BridgeTransport>(bus: &mut T, cmd: u8, seq: u8, payload: &[u8]) -> Result<(), &'static str> {
+    let request = encode_request(cmd, seq, payload)?;
+    bus.write(&request)
+}
+
+/// Check once whether the response for `seq` is ready, and read it if so.
+/// Returns `Ok(None)` while the Pico is still busy (or still answering an
+/// earlier command, or the header was garbled).
+pub fn poll_response<T: BridgeTransport>(bus: &mut T, seq: u8) -> Result<Option<Vec<u8>>, &'static str> {
+    let mut header = [0u8; FRAME_HEADER_LEN];
+    bus.read(&mut header)?;
+    let len = match parse_response_header(&header) {
+        Ok(h) if h.seq == seq => match h.state {
+            FRAME_STATE_READY if h.len <= MAX_RESPONSE_PAYLOAD => h.len,
+            FRAME_STATE_READY => return Err("Response too long"),
+            FRAME_STATE_BAD_FRAME => return Err("Bridge rejected the request frame"),
+            _ => return Ok(None),
+        },
+        _ => return Ok(None),
+    };
+
+    // Re-read the whole frame after a CRC error (the Pico keeps it until the next command)
+    let mut frame = vec![0u8; FRAME_OVERHEAD + len];
+    let mut last_err = "CRC mismatch";
+    for _ in 0..=CRC_RETRIES {
+        bus.read(&mut frame)?;
+        match decode_response(&frame, seq) {
+            Ok(payload) => return Ok(Some(payload.to_vec())),
+            Err(e) => last_err = e,
+        }
+    }
+    Err(last_err)
+}
+
+/// Send a command and wait for its response payload.
+/// Blocks on `delay_ms` between polls, so only for short commands during
+/// init; the main loop polls with [`poll_response`] instead.
+pub fn transact<T: BridgeTransport>(
+    bus: &mut T,
+    cmd: u8,
+    seq: u8,
+    payload: &[u8],
+    timeout_ms: u32,
+) -> Result<Vec<u8>, &'static str> {
+    send_request(bus, cmd, seq, payload)?;
+
+    let mut waited = 0;
+    loop {
+        if let Some(payload) = poll_response(bus, seq)? {
+            return Ok(payload);
+        }
+        if waited >= timeout_ms {
+            return Err("Bridge response timeout");
+        }
+        bus.delay_ms(POLL_INTERVAL_MS);
+        waited += POLL_INTERVAL_MS;
+    }
+}
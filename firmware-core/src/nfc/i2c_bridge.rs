@@ -0,0 +1,463 @@
+//! NFC I2C Bridge Driver
+//!
+//! Communicates with the Pico NFC bridge over I2C.
+//! The Pico handles PN5180 SPI communication and exposes a simple I2C interface.
+//!
+//! I2C Protocol (framed and CRC-checked, see `bridge_protocol`):
+//! - Address: 0x55
+//! - Commands:
+//!   - 0x00: Get status (returns 2 bytes: status, tag_present)
+//!   - 0x01: Get version (returns: status, major, minor, protocol_version, capabilities)
+//!   - 0x10: Scan tag (returns: status, uid_len, uid[0..uid_len])
+//!   - 0x20: Read tag data (returns: status, tag_type, uid_len, uid, block_data...)
+//!   - 0x30: Write NTAG pages (sends: start_page, page_count, data; returns: status, cc_size, cc_access)
+//!
+//! Tag commands are split into `start_*` (send) and `finish_*` (apply the
+//! response) so the caller can release the shared bus while the Pico works.
+
+use super::bridge_protocol::{self, BridgeTransport, CAP_WRITE_TAG, PROTOCOL_VERSION};
+use super::mifare_classic::BAMBU_BLOCKS;
+use log::{debug, info, warn};
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// I2C address of the Pico NFC bridge
+pub const PICO_NFC_ADDR: u8 = 0x55;
+
+/// Sequence counter for log correlation
+static CMD_SEQ: AtomicU8 = AtomicU8::new(0);
+
+fn next_seq() -> u8 {
+    CMD_SEQ.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Commands
+#[allow(dead_code)]
+const CMD_GET_STATUS: u8 = 0x00;
+const CMD_GET_VERSION: u8 = 0x01;
+const CMD_SCAN_TAG: u8 = 0x10;
+const CMD_READ_TAG_DATA: u8 = 0x20;
+const CMD_WRITE_TAG: u8 = 0x30;
+
+/// Response timeouts (the Pico reports ready as soon as it is done)
+const VERSION_TIMEOUT_MS: u32 = 200;
+/// A hard reset of the PN5180 can take 300-500ms, plus a background scan in progress
+pub const SCAN_TIMEOUT_MS: u32 = 1500;
+/// MIFARE authentication + block reads
+pub const READ_TIMEOUT_MS: u32 = 2000;
+/// Each page write takes a few ms of EEPROM programming on the tag
+pub const WRITE_TIMEOUT_MS: u32 = 1000;
+
+/// NTAG pages per write command (the Pico command buffer is 64 bytes)
+pub const WRITE_CHUNK_PAGES: usize = 12;
+
+/// Tag types (matches Pico definitions)
+#[allow(dead_code)]
+pub const TAG_TYPE_UNKNOWN: u8 = 0;
+pub const TAG_TYPE_NTAG: u8 = 1;
+pub const TAG_TYPE_MIFARE_1K: u8 = 2;
+pub const TAG_TYPE_MIFARE_4K: u8 = 3;
+
+/// Decoded tag data from Bambu/NTAG tags
+#[derive(Debug, Clone, Default)]
+pub struct DecodedTagInfo {
+    pub vendor: String,
+    pub material: String,
+    pub material_subtype: String,
+    pub color_name: String,
+    pub color_rgba: u32,
+    pub spool_weight: i32,
+    pub tag_type_name: String,
+    // Bambu Lab only, zero or empty when the tag does not carry them
+    /// Filament diameter (mm)
+    pub filament_diameter: f32,
+    /// Drying temperature (°C) and time (hours)
+    pub drying_temp: i32,
+    pub drying_time: i32,
+    /// Bed temperature (°C)
+    pub bed_temp: i32,
+    /// Hotend temperature range (°C)
+    pub hotend_min_temp: i32,
+    pub hotend_max_temp: i32,
+    /// Spool width (mm)
+    pub spool_width: f32,
+    /// Filament length (m)
+    pub filament_length: i32,
+    /// Production date as stored on the tag, e.g. "2024_03_18_13_38"
+    pub production_date: String,
+    /// Tray UID (hex), the same on both tags of a spool
+    pub tray_uid: String,
+}
+
+/// NFC Bridge state
+pub struct NfcBridgeState {
+    pub initialized: bool,
+    pub firmware_version: (u8, u8),  // major, minor
+    pub capabilities: u8,            // CAP_* bits from the bridge
+    pub tag_uid: [u8; 10],
+    pub tag_uid_len: u8,
+}
+
+impl NfcBridgeState {
+    pub fn new() -> Self {
+        Self {
+            initialized: false,
+            firmware_version: (0, 0),
+            capabilities: 0,
+            tag_uid: [0; 10],
+            tag_uid_len: 0,
+        }
+    }
+}
+
+impl Default for NfcBridgeState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Version information reported by the bridge
+#[derive(Debug, Clone, Copy)]
+pub struct BridgeVersion {
+    pub major: u8,
+    pub minor: u8,
+    pub protocol: u8,
+    pub capabilities: u8,
+}
+
+/// Initialize the NFC I2C bridge
+pub fn init_bridge<T: BridgeTransport>(bus: &mut T, state: &mut NfcBridgeState) -> Result<(), &'static str> {
+    info!("=== NFC I2C BRIDGE INIT ===");
+    info!("  Pico address: 0x{:02X}", PICO_NFC_ADDR);
+
+    // Check if Pico is present
+    let mut buf = [0u8; 1];
+    if bus.read(&mut buf).is_err() {
+        warn!("  Pico NFC bridge not found at 0x{:02X}", PICO_NFC_ADDR);
+        return Err("Pico not found");
+    }
+    info!("  Pico NFC bridge detected");
+
+    // Get version and negotiate capabilities
+    let version = match get_version(bus) {
+        Ok(version) => version,
+        Err(e) => {
+            warn!("  Failed to get version: {}", e);
+            if probe_unframed_version(bus) {
+                warn!("  Pico firmware predates protocol v{} - please update the bridge", PROTOCOL_VERSION);
+                return Err("Pico bridge firmware too old");
+            }
+            return Err(e);
+        }
+    };
+    info!("  Pico firmware: {}.{} (protocol v{}, caps 0x{:02X})",
+          version.major, version.minor, version.protocol, version.capabilities);
+    if version.protocol != PROTOCOL_VERSION {
+        warn!("  Unsupported bridge protocol v{}", version.protocol);
+        return Err("Unsupported bridge protocol");
+    }
+    state.firmware_version = (version.major, version.minor);
+    state.capabilities = version.capabilities;
+
+    state.initialized = true;
+    info!("=== NFC I2C BRIDGE READY ===");
+    Ok(())
+}
+
+/// Get Pico firmware version, protocol version and capabilities
+pub fn get_version<T: BridgeTransport>(bus: &mut T) -> Result<BridgeVersion, &'static str> {
+    let seq = next_seq();
+    let resp = bridge_protocol::transact(bus, CMD_GET_VERSION, seq, &[], VERSION_TIMEOUT_MS)?;
+
+    // Response: [status, major, minor, protocol_version, capabilities]
+    match resp[..] {
+        [0, major, minor, protocol, capabilities, ..] => Ok(BridgeVersion { major, minor, protocol, capabilities }),
+        [0, ..] => Err("Version response too short"),
+        _ => Err("Command failed"),
+    }
+}
+
+/// Check for a bridge that only speaks the old unframed protocol
+fn probe_unframed_version<T: BridgeTransport>(bus: &mut T) -> bool {
+    if bus.write(&[CMD_GET_VERSION]).is_err() {
+        return false;
+    }
+    bus.delay_ms(10);
+    let mut resp = [0xFFu8; 3];
+    bus.read(&mut resp).is_ok() && resp[0] == 0
+}
+
+/// Send a command without waiting for the response; returns its seq.
+/// The response is picked up with `bridge_protocol::poll_response`.
+fn send_cmd<T: BridgeTransport>(bus: &mut T, cmd: u8, name: &str, payload: &[u8]) -> Result<u8, &'static str> {
+    let seq = next_seq();
+    info!("[#{}] TX: {}", seq, name);
+    bridge_protocol::send_request(bus, cmd, seq, payload)
+        .inspect_err(|e| warn!("[#{}] {} failed: {}", seq, name, e))?;
+    Ok(seq)
+}
+
+/// Start a tag scan
+pub fn start_scan<T: BridgeTransport>(bus: &mut T) -> Result<u8, &'static str> {
+    send_cmd(bus, CMD_SCAN_TAG, "SCAN_TAG", &[])
+}
+
+/// Apply a SCAN_TAG response; returns whether a tag is present
+pub fn finish_scan(state: &mut NfcBridgeState, seq: u8, resp: &[u8]) -> bool {
+    // Response: [status, uid_len, uid...]
+    let status = resp.first().copied().unwrap_or(0xFF);
+    if status != 0 {
+        // No tag or error
+        info!("[#{}] No tag (status={})", seq, status);
+        state.tag_uid_len = 0;
+        return false;
+    }
+
+    let uid_len = resp.get(1).copied().unwrap_or(0);
+    match resp.get(2..2 + uid_len as usize) {
+        Some(uid) if uid_len > 0 && uid_len <= 10 => {
+            state.tag_uid_len = uid_len;
+            state.tag_uid[..uid.len()].copy_from_slice(uid);
+
+            // Tag detected - no sensitive data logged
+            debug!("[#{}] Tag detected", seq);
+            true
+        }
+        _ => {
+            debug!("[#{}] No valid tag", seq);
+            state.tag_uid_len = 0;
+            false
+        }
+    }
+}
+
+/// Start reading the tag data
+/// Response format:
+/// [0] = status (0 = success, 1 = no tag, 2 = read error, 3 = unknown type)
+/// [1] = tag_type
+/// [2] = uid_len
+/// [3..3+uid_len] = uid
+/// For MIFARE: the BAMBU_BLOCKS (144 bytes, 64 from older bridges)
+/// For NTAG: pages 4-39 (144 bytes, NTAG213 user memory)
+pub fn start_read<T: BridgeTransport>(bus: &mut T) -> Result<u8, &'static str> {
+    send_cmd(bus, CMD_READ_TAG_DATA, "READ_TAG_DATA", &[])
+}
+
+/// Tag data from a READ_TAG_DATA response
+#[derive(Debug, Clone)]
+pub struct BridgeTagData {
+    pub tag_type: u8,
+    pub uid: Vec<u8>,
+    /// MIFARE Bambu blocks or NTAG pages 4-39
+    pub data: Vec<u8>,
+}
+
+/// Parse a READ_TAG_DATA response
+pub fn finish_read(seq: u8, resp: &[u8]) -> Result<BridgeTagData, &'static str> {
+    let resp = pad_read_response(resp);
+
+    let status = resp[0];
+    if status != 0 {
+        warn!("[#{}] Read failed, status: {}", seq, status);
+        return Err(match status {
+            1 => "No tag present",
+            3 => "Unknown tag type",
+            _ => "Tag read failed",
+        });
+    }
+
+    let tag_type = resp[1];
+    debug!("[#{}] Tag read success", seq);
+
+    let uid_len = resp[2] as usize;
+    let uid = resp.get(3..3 + uid_len).unwrap_or(&[]).to_vec();
+    Ok(BridgeTagData { tag_type, uid, data: tag_data(&resp).to_vec() })
+}
+
+/// Error responses are just the status byte
+fn pad_read_response(resp: &[u8]) -> Vec<u8> {
+    let mut resp = resp.to_vec();
+    if resp.len() < 3 {
+        resp.resize(3, 0);
+    }
+    resp
+}
+
+/// Tag data after the status, type and UID of a READ_TAG_DATA response
+fn tag_data(resp: &[u8]) -> &[u8] {
+    let data_offset = 3 + resp[2] as usize;
+    resp.get(data_offset..).unwrap_or(&[])
+}
+
+/// Check that the bridge firmware supports writing tags
+pub fn can_write(state: &NfcBridgeState) -> Result<(), &'static str> {
+    if state.capabilities & CAP_WRITE_TAG == 0 {
+        return Err("Bridge firmware cannot write tags");
+    }
+    Ok(())
+}
+
+/// Start writing NTAG pages from `start_page` (the chunk is padded to whole
+/// pages, at most WRITE_CHUNK_PAGES). An empty chunk only checks the tag.
+pub fn start_write<T: BridgeTransport>(bus: &mut T, start_page: u8, chunk: &[u8]) -> Result<u8, &'static str> {
+    let page_count = chunk.len().div_ceil(4);
+
+    // Payload: [start_page, page_count, data (page_count * 4 bytes)]
+    let mut payload = Vec::with_capacity(2 + page_count * 4);
+    payload.extend_from_slice(&[start_page, page_count as u8]);
+    payload.extend_from_slice(chunk);
+    payload.resize(2 + page_count * 4, 0);
+
+    debug!("WRITE_TAG page={} count={}", start_page, page_count);
+    send_cmd(bus, CMD_WRITE_TAG, "WRITE_TAG", &payload)
+}
+
+/// Check a WRITE_TAG response.
+/// Returns the capability container size and access bytes.
+pub fn finish_write(seq: u8, resp: &[u8]) -> Result<(u8, u8), &'static str> {
+    // Response: [status, cc_size, cc_access]
+    match resp[..] {
+        [0, cc_size, cc_access, ..] => Ok((cc_size, cc_access)),
+        [0, ..] => Err("Write response too short"),
+        [status, ..] => {
+            warn!("[#{}] Write failed, status: {}", seq, status);
+            Err(match status {
+                1 => "No tag present",
+                3 => "Not an NTAG tag",
+                4 => "Tag is read-only",
+                5 => "Data does not fit on tag",
+                6 => "Invalid write request",
+                7 => "Tag is not NDEF formatted",
+                // A NAK on an unlocked tag usually means locked pages
+                _ => "Write failed (tag locked or moved away?)",
+            })
+        }
+        [] => Err("Empty write response"),
+    }
+}
+
+/// Decode Bambu Lab tag data from raw blocks (BAMBU_BLOCKS, back to back).
+/// Older bridges only send blocks 1, 2, 4 and 5; the fields of the other
+/// blocks are then left empty.
+pub fn decode_bambu_tag(block_data: &[u8]) -> DecodedTagInfo {
+    // Block layout (each 16 bytes, numbers little-endian):
+    // Block 1: Material variant ID (0-7), Material ID (8-15)
+    // Block 2: Filament type (e.g., "PLA")
+    // Block 4: Detailed type (e.g., "PLA Basic")
+    // Block 5: Color RGBA (0-3), Spool weight (4-5), Diameter (8-11, f32)
+    // Block 6: Drying temp (0-1), Drying hours (2-3), Bed temp type (4-5),
+    //          Bed temp (6-7), Hotend max (8-9), Hotend min (10-11)
+    // Block 9: Tray UID
+    // Block 10: Spool width in 1/100 mm (4-5)
+    // Block 12: Production date (e.g., "2024_03_18_13_38")
+    // Block 14: Filament length in m (4-5)
+
+    if block_data.len() < 64 {
+        warn!("Insufficient block data: {} bytes", block_data.len());
+        return DecodedTagInfo {
+            tag_type_name: "Bambu Lab".to_string(),
+            ..Default::default()
+        };
+    }
+
+    let block1 = &block_data[0..16];
+    let block2 = &block_data[16..32];
+    let block4 = &block_data[32..48];
+    let block5 = &block_data[48..64];
+
+    // Extract material ID (block 1, bytes 8-15)
+    let material_id = extract_cstring(&block1[8..16]);
+
+    // Extract filament type (block 2)
+    let filament_type = extract_cstring(block2);
+
+    // Extract detailed type (block 4)
+    let detailed_type = extract_cstring(block4);
+
+    // Extract color RGBA (block 5, bytes 0-3)
+    let color_rgba = u32::from_be_bytes([block5[0], block5[1], block5[2], block5[3]]);
+
+    // Extract spool weight (block 5, bytes 4-5, little-endian)
+    let spool_weight = i16::from_le_bytes([block5[4], block5[5]]) as i32;
+
+    // Derive subtype from detailed_type
+    let material_subtype = if detailed_type.starts_with(&format!("Bambu {} ", filament_type)) {
+        detailed_type.strip_prefix(&format!("Bambu {} ", filament_type))
+            .unwrap_or("")
+            .to_string()
+    } else if detailed_type.starts_with(&filament_type) {
+        detailed_type.strip_prefix(&filament_type)
+            .map(|s| s.trim())
+            .unwrap_or("")
+            .to_string()
+    } else {
+        detailed_type.clone()
+    };
+
+    info!("Decoded Bambu tag: material_id={}, type={}, detailed={}, color=0x{:08X}, weight={}g",
+          material_id, filament_type, detailed_type, color_rgba, spool_weight);
+
+    let mut info = DecodedTagInfo {
+        vendor: "Bambu".to_string(),
+        material: filament_type,
+        material_subtype,
+        color_name: format_color_name(color_rgba),
+        color_rgba,
+        spool_weight,
+        tag_type_name: "Bambu Lab".to_string(),
+        ..Default::default()
+    };
+
+    // Extract filament diameter (block 5, bytes 8-11, f32)
+    let diameter = f32::from_le_bytes([block5[8], block5[9], block5[10], block5[11]]);
+    if diameter.is_finite() && diameter > 0.0 && diameter < 10.0 {
+        info.filament_diameter = diameter;
+    }
+
+    // The remaining blocks are missing from older bridges
+    let block = |number: u8| {
+        let index = BAMBU_BLOCKS.iter().position(|&b| b == number)?;
+        block_data.get(index * 16..(index + 1) * 16)
+    };
+    let u16_at = |block: &[u8], offset: usize| u16::from_le_bytes([block[offset], block[offset + 1]]) as i32;
+
+    if let Some(block6) = block(6) {
+        info.drying_temp = u16_at(block6, 0);
+        info.drying_time = u16_at(block6, 2);
+        info.bed_temp = u16_at(block6, 6);
+        info.hotend_max_temp = u16_at(block6, 8);
+        info.hotend_min_temp = u16_at(block6, 10);
+    }
+    if let Some(block9) = block(9).filter(|b| b.iter().any(|&x| x != 0)) {
+        info.tray_uid = block9.iter().map(|b| format!("{:02X}", b)).collect();
+    }
+    if let Some(block10) = block(10) {
+        info.spool_width = u16_at(block10, 4) as f32 / 100.0;
+    }
+    if let Some(block12) = block(12) {
+        info.production_date = extract_cstring(block12);
+    }
+    if let Some(block14) = block(14) {
+        info.filament_length = u16_at(block14, 4);
+    }
+
+    if !info.tray_uid.is_empty() {
+        info!("  diameter={:.2}mm, hotend={}-{}C, bed={}C, drying={}C/{}h, width={:.1}mm, length={}m, date={}, tray={}",
+              info.filament_diameter, info.hotend_min_temp, info.hotend_max_temp, info.bed_temp,
+              info.drying_temp, info.drying_time, info.spool_width, info.filament_length,
+              info.production_date, info.tray_uid);
+    }
+
+    info
+}
+
+/// Extract null-terminated string from bytes
+fn extract_cstring(data: &[u8]) -> String {
+    let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+    String::from_utf8_lossy(&data[..end]).to_string()
+}
+
+/// Format color RGBA as a name: the perceptually nearest filament color,
+/// "Translucent" for translucent colors (see `spoolbuddy_colors`)
+pub fn format_color_name(rgba: u32) -> String {
+    spoolbuddy_colors::color_name(rgba).to_string()
+}
@@ -0,0 +1,330 @@
+//! ISO/IEC 14443-3 type A activation: REQA/WUPA, anticollision, SELECT, HLTA
+//!
+//! A card's UID is 4, 7 or 10 bytes, fetched in one to three cascade levels.
+//! Each level returns 40 bits (4 UID bytes + BCC); when more levels follow,
+//! the first byte is the cascade tag 0x88 and the SAK of that level's SELECT
+//! has the cascade bit set. With several cards in the field, anticollision
+//! walks the UID bit by bit: at each collision it picks the cards with a 1
+//! and asks again with the longer known prefix, until one card is left.
+//!
+//! Chip independent: frames go through a [`Transceiver`], which the PN5180
+//! implements (and its mock, on the host).
+
+use super::pn5180_protocol::Pn5180Error;
+use log::{debug, warn};
+
+/// Short frames (7 bits)
+pub const REQA: u8 = 0x26;
+pub const WUPA: u8 = 0x52;
+
+/// SEL codes for cascade levels 1-3
+pub const SEL_CASCADE_LEVELS: [u8; 3] = [0x93, 0x95, 0x97];
+
+/// NVB of a SELECT (all 7 bytes: SEL, NVB, 4 UID bytes, BCC)
+pub const NVB_SELECT: u8 = 0x70;
+
+/// First UID byte of a cascade level that is not the last
+pub const CASCADE_TAG: u8 = 0x88;
+
+/// SAK bit: UID not complete, continue with the next cascade level
+pub const SAK_CASCADE: u8 = 0x04;
+
+/// HLTA (sent with CRC)
+pub const HLTA: [u8; 2] = [0x50, 0x00];
+
+/// UID bits per cascade level (4 UID bytes + BCC)
+const CASCADE_LEVEL_BITS: usize = 40;
+
+/// A received frame. Bits are LSB first from bit 0 of `data`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RxFrame {
+    pub data: Vec<u8>,
+    /// Number of valid bits
+    pub bits: usize,
+    /// Position of the first bit where answering cards differed
+    pub collision: Option<usize>,
+}
+
+/// One RF exchange with the cards in the field
+pub trait Transceiver {
+    /// Send `tx` (`tx_last_bits` valid bits in the last byte, 0 = all 8),
+    /// with CRC_A appended and checked if `crc`. Returns None if no card answered.
+    fn transceive(&mut self, tx: &[u8], tx_last_bits: u8, crc: bool) -> Result<Option<RxFrame>, Pn5180Error>;
+}
+
+/// ISO 14443A card info
+#[derive(Debug, Clone)]
+pub struct Iso14443aCard {
+    /// UID (4, 7, or 10 bytes)
+    pub uid: [u8; 10],
+    /// UID length (4, 7, or 10)
+    pub uid_len: u8,
+    /// ATQA (2 bytes)
+    pub atqa: [u8; 2],
+    /// SAK byte
+    pub sak: u8,
+}
+
+impl Iso14443aCard {
+    /// UID bytes
+    pub fn uid(&self) -> &[u8] {
+        &self.uid[..self.uid_len as usize]
+    }
+
+    /// Check if this is an NTAG (based on SAK)
+    pub fn is_ntag(&self) -> bool {
+        self.sak == 0x00
+    }
+
+    /// Check if this is a MIFARE Classic 1K (based on SAK)
+    pub fn is_mifare_classic_1k(&self) -> bool {
+        self.sak == 0x08
+    }
+
+    /// Check if this is a MIFARE Classic 4K (based on SAK)
+    pub fn is_mifare_classic_4k(&self) -> bool {
+        self.sak == 0x18
+    }
+}
+
+/// CRC_A (ISO/IEC 14443-3): CRC-16 with reflected poly 0x8408, init 0x6363, LSB first
+pub fn crc_a(data: &[u8]) -> [u8; 2] {
+    let mut crc: u16 = 0x6363;
+    for &byte in data {
+        let mut b = byte ^ (crc & 0xFF) as u8;
+        b ^= b << 4;
+        crc = (crc >> 8) ^ ((b as u16) << 8) ^ ((b as u16) << 3) ^ ((b as u16) >> 4);
+    }
+    crc.to_le_bytes()
+}
+
+/// Block check character of a cascade level (XOR of the 4 UID bytes)
+pub fn bcc(uid: &[u8]) -> u8 {
+    uid.iter().fold(0, |acc, b| acc ^ b)
+}
+
+/// Wake a card with REQA (idle cards) or WUPA (idle and halted cards), then
+/// run anticollision and SELECT on every cascade level.
+/// Returns None if no card answered or it left the field part way.
+pub fn activate<T: Transceiver>(pcd: &mut T, request: u8) -> Result<Option<Iso14443aCard>, Pn5180Error> {
+    let atqa = match pcd.transceive(&[request], 7, false)? {
+        Some(rx) if rx.bits >= 16 => [rx.data[0], rx.data[1]],
+        _ => return Ok(None),
+    };
+    debug!("ATQA: {:02X} {:02X}", atqa[0], atqa[1]);
+
+    let mut uid = [0u8; 10];
+    let mut uid_len = 0;
+
+    for (level, &sel) in SEL_CASCADE_LEVELS.iter().enumerate() {
+        let Some(uid_cl) = anticollision(pcd, sel)? else {
+            return Ok(None);
+        };
+        let Some(sak) = select(pcd, sel, &uid_cl)? else {
+            return Ok(None);
+        };
+        debug!("Cascade level {}: SAK {:02X}", level + 1, sak);
+
+        if sak & SAK_CASCADE == 0 {
+            uid[uid_len..uid_len + 4].copy_from_slice(&uid_cl[..4]);
+            uid_len += 4;
+            return Ok(Some(Iso14443aCard { uid, uid_len: uid_len as u8, atqa, sak }));
+        }
+
+        // UID continues on the next level, after the cascade tag
+        if uid_cl[0] != CASCADE_TAG {
+            warn!("Cascade bit set without cascade tag");
+            return Err(Pn5180Error::InvalidResponse);
+        }
+        uid[uid_len..uid_len + 3].copy_from_slice(&uid_cl[1..4]);
+        uid_len += 3;
+    }
+
+    warn!("UID longer than three cascade levels");
+    Err(Pn5180Error::InvalidResponse)
+}
+
+/// Put the selected card into HALT (it then only answers WUPA)
+pub fn halt<T: Transceiver>(pcd: &mut T) -> Result<(), Pn5180Error> {
+    // A halted card stays silent; any answer is a NAK
+    match pcd.transceive(&HLTA, 0, true)? {
+        None => Ok(()),
+        Some(_) => Err(Pn5180Error::InvalidResponse),
+    }
+}
+
+/// Anticollision loop for one cascade level; returns its 4 UID bytes and BCC
+fn anticollision<T: Transceiver>(pcd: &mut T, sel: u8) -> Result<Option<[u8; 5]>, Pn5180Error> {
+    let mut uid_cl = [0u8; 5];
+    let mut known: usize = 0;
+
+    loop {
+        // NVB: bytes (SEL and NVB included) and extra bits sent
+        let nvb = (((2 + known / 8) << 4) | (known % 8)) as u8;
+        let mut tx = vec![sel, nvb];
+        tx.extend_from_slice(&uid_cl[..known.div_ceil(8)]);
+
+        let Some(rx) = pcd.transceive(&tx, (known % 8) as u8, false)? else {
+            return Ok(None);
+        };
+
+        // The answer carries the UID bits from `known` on, up to any collision
+        let valid = rx.collision.unwrap_or(rx.bits).min(rx.bits).min(CASCADE_LEVEL_BITS - known);
+        for i in 0..valid {
+            set_bit(&mut uid_cl, known + i, get_bit(&rx.data, i));
+        }
+
+        match rx.collision {
+            Some(pos) if known + pos < CASCADE_LEVEL_BITS && pos <= rx.bits => {
+                // Carry on with the cards that have a 1 here
+                debug!("Collision at bit {}", known + pos);
+                set_bit(&mut uid_cl, known + pos, true);
+                known += pos + 1;
+                if known == CASCADE_LEVEL_BITS {
+                    break;
+                }
+            }
+            _ => {
+                known += valid;
+                if known < CASCADE_LEVEL_BITS {
+                    warn!("Anticollision answer too short ({} of {} bits)", known, CASCADE_LEVEL_BITS);
+                    return Err(Pn5180Error::InvalidResponse);
+                }
+                break;
+            }
+        }
+    }
+
+    if bcc(&uid_cl[..4]) != uid_cl[4] {
+        warn!("UID BCC mismatch");
+        return Err(Pn5180Error::InvalidResponse);
+    }
+    Ok(Some(uid_cl))
+}
+
+/// SELECT a cascade level; returns the SAK
+fn select<T: Transceiver>(pcd: &mut T, sel: u8, uid_cl: &[u8; 5]) -> Result<Option<u8>, Pn5180Error> {
+    let mut tx = vec![sel, NVB_SELECT];
+    tx.extend_from_slice(uid_cl);
+    match pcd.transceive(&tx, 0, true)? {
+        Some(rx) if rx.bits >= 8 && rx.collision.is_none() => Ok(Some(rx.data[0])),
+        Some(_) => Err(Pn5180Error::InvalidResponse),
+        None => Ok(None),
+    }
+}
+
+fn get_bit(data: &[u8], bit: usize) -> bool {
+    data.get(bit / 8).is_some_and(|b| b & (1 << (bit % 8)) != 0)
+}
+
+fn set_bit(data: &mut [u8], bit: usize, value: bool) {
+    if value {
+        data[bit / 8] |= 1 << (bit % 8);
+    } else {
+        data[bit / 8] &= !(1 << (bit % 8));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nfc::pn5180_mock::{MockCard, MockPn5180};
+
+    fn field(cards: Vec<MockCard>) -> MockPn5180 {
+        let mut pcd = MockPn5180::new();
+        pcd.cards = cards;
+        pcd
+    }
+
+    #[test]
+    fn crc_a_and_bcc() {
+        // HLTA as sent on air: 50 00 57 CD
+        assert_eq!(crc_a(&HLTA), [0x57, 0xCD]);
+        assert_eq!(bcc(&[0x88, 0x04, 0x11, 0x22]), 0x88 ^ 0x04 ^ 0x11 ^ 0x22);
+    }
+
+    #[test]
+    fn activates_4_byte_uid() {
+        let uid = [0xDE, 0xAD, 0xBE, 0xEF];
+        let mut pcd = field(vec![MockCard::mifare_classic_1k(&uid)]);
+        let card = activate(&mut pcd, REQA).unwrap().unwrap();
+        assert_eq!(card.uid(), uid);
+        assert_eq!(card.atqa, [0x04, 0x00]);
+        assert!(card.is_mifare_classic_1k());
+    }
+
+    #[test]
+    fn activates_7_byte_uid() {
+        let uid = [0x04, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66];
+        let mut pcd = field(vec![MockCard::ntag(&uid)]);
+        let card = activate(&mut pcd, REQA).unwrap().unwrap();
+        assert_eq!(card.uid(), uid);
+        assert_eq!(card.atqa, [0x44, 0x00]);
+        assert!(card.is_ntag());
+    }
+
+    #[test]
+    fn activates_10_byte_uid() {
+        let uid = [0x08, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09];
+        let mut pcd = field(vec![MockCard::new(&uid, 0x20)]);
+        let card = activate(&mut pcd, REQA).unwrap().unwrap();
+        assert_eq!(card.uid(), uid);
+        assert_eq!(card.uid_len, 10);
+        assert_eq!(card.atqa, [0x84, 0x00]);
+        assert_eq!(card.sak, 0x20);
+    }
+
+    #[test]
+    fn no_card() {
+        let mut pcd = MockPn5180::new();
+        assert!(activate(&mut pcd, REQA).unwrap().is_none());
+        assert!(activate(&mut pcd, WUPA).unwrap().is_none());
+    }
+
+    #[test]
+    fn resolves_three_card_collision() {
+        // First differing bits (LSB first): bit 7 picks the 0x92 card, then
+        // bit 16 picks 0x57 over 0x56
+        let uids = [[0x12, 0x34, 0x56, 0x78], [0x12, 0x34, 0x57, 0x78], [0x92, 0x34, 0x56, 0x78]];
+        let mut pcd = field(uids.iter().map(MockCard::mifare_classic_1k).collect());
+
+        let mut order = Vec::new();
+        while let Some(card) = activate(&mut pcd, REQA).unwrap() {
+            order.push(card.uid().to_vec());
+            halt(&mut pcd).unwrap();
+            assert!(order.len() <= 3, "a halted card answered REQA");
+        }
+        assert_eq!(order, vec![uids[2].to_vec(), uids[1].to_vec(), uids[0].to_vec()]);
+        assert!(pcd.cards.iter().all(MockCard::is_halted));
+    }
+
+    #[test]
+    fn resolves_collision_across_uid_lengths() {
+        // Level 1 of the 7-byte card starts with the cascade tag (0x88), so
+        // the 4-byte card (0x01) wins bit 0; the other waits for the next REQA
+        let short = [0x01, 0x02, 0x03, 0x04];
+        let long = [0x04, 0xA0, 0xB0, 0xC0, 0xD0, 0xE0, 0xF0];
+        let mut pcd = field(vec![MockCard::mifare_classic_1k(&short), MockCard::ntag(&long)]);
+
+        let card = activate(&mut pcd, REQA).unwrap().unwrap();
+        assert_eq!(card.uid(), short);
+        halt(&mut pcd).unwrap();
+        let card = activate(&mut pcd, REQA).unwrap().unwrap();
+        assert_eq!(card.uid(), long);
+    }
+
+    #[test]
+    fn halted_card_ignores_reqa() {
+        let uid = [0x04, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66];
+        let mut pcd = field(vec![MockCard::ntag(&uid)]);
+
+        activate(&mut pcd, REQA).unwrap().unwrap();
+        halt(&mut pcd).unwrap();
+        assert!(pcd.cards[0].is_halted());
+        assert!(activate(&mut pcd, REQA).unwrap().is_none());
+
+        // WUPA wakes it again
+        let card = activate(&mut pcd, WUPA).unwrap().unwrap();
+        assert_eq!(card.uid(), uid);
+    }
+}
@@ -0,0 +1,267 @@
+//! MIFARE Classic: sector keys, Crypto1 authentication and block reads
+//!
+//! A 1K card has 16 sectors of 4 blocks (16 bytes each); the last block of a
+//! sector is its trailer with key A, access bits and key B. A sector has to be
+//! authenticated before its blocks can be read. Crypto1 itself runs on the
+//! PN5180 (MIFARE_AUTHENTICATE, after which the chip en/decrypts every
+//! exchange), so reading a block is a plain READ on the [`Transceiver`].
+//!
+//! Bambu Lab spools carry a 1K card whose sector keys are derived from the
+//! UID with HKDF-SHA256 ([`BambuKeys`]).
+
+use super::iso14443a::{Iso14443aCard, Transceiver};
+use super::pn5180_protocol::{self, Pn5180Error, Pn5180Io};
+use crate::crypto::hkdf_sha256;
+use log::{debug, warn};
+
+/// MIFARE authentication key type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MifareKeyType {
+    KeyA,
+    KeyB,
+}
+
+impl MifareKeyType {
+    /// AUTH command code sent to the card
+    pub fn auth_code(self) -> u8 {
+        match self {
+            MifareKeyType::KeyA => 0x60,
+            MifareKeyType::KeyB => 0x61,
+        }
+    }
+}
+
+/// READ command (one 16 byte block)
+const READ: u8 = 0x30;
+
+/// Block size in bytes
+pub const BLOCK_SIZE: usize = 16;
+
+/// Sectors on a 1K card
+pub const SECTORS_1K: usize = 16;
+
+/// Transport key of blank cards
+pub const DEFAULT_KEY: [u8; 6] = [0xFF; 6];
+
+/// Sector holding `block` (4K cards have 16-block sectors from block 128 on)
+pub fn sector_of(block: u8) -> u8 {
+    if block < 128 {
+        block / 4
+    } else {
+        32 + (block - 128) / 16
+    }
+}
+
+/// UID bytes Crypto1 is keyed with: the whole 4 byte UID, or the last
+/// cascade level (last 4 bytes) of a 7 byte UID
+pub fn crypto_uid(uid: &[u8]) -> Option<[u8; 4]> {
+    match uid.len() {
+        4 | 7 => {
+            let last = &uid[uid.len() - 4..];
+            Some([last[0], last[1], last[2], last[3]])
+        }
+        _ => None,
+    }
+}
+
+/// Supplies the key for each sector of a card
+pub trait MifareKeyProvider {
+    /// Key type and key to authenticate `sector` of the card with `uid`
+    fn key(&self, uid: &[u8], sector: u8) -> (MifareKeyType, [u8; 6]);
+}
+
+/// Key A 0xFF..FF on every sector (blank cards)
+pub struct DefaultKeys;
+
+impl MifareKeyProvider for DefaultKeys {
+    fn key(&self, _uid: &[u8], _sector: u8) -> (MifareKeyType, [u8; 6]) {
+        (MifareKeyType::KeyA, DEFAULT_KEY)
+    }
+}
+
+/// Bambu Lab key derivation: HKDF-SHA256 of the UID with this salt, expanded
+/// to 16 six-byte keys (one per sector) with info "RFID-A\0" or "RFID-B\0"
+const BAMBU_KEY_SALT: [u8; 16] = [
+    0x9a, 0x75, 0x9c, 0xf2, 0xc4, 0xf7, 0xca, 0xff, 0x22, 0x2c, 0xb9, 0x76, 0x9b, 0x41, 0xbc, 0x96,
+];
+
+/// Blocks holding the Bambu Lab filament data, as read by the Pico bridge:
+/// material IDs, type, detailed type, color and weight (1, 2, 4, 5),
+/// temperatures and drying (6), tray UID (9), spool width (10), production
+/// date (12) and length (14)
+pub const BAMBU_BLOCKS: [u8; 9] = [1, 2, 4, 5, 6, 9, 10, 12, 14];
+
+/// All sector keys of a Bambu Lab tag
+pub fn bambu_keys(uid: &[u8], key_type: MifareKeyType) -> [[u8; 6]; SECTORS_1K] {
+    let info: &[u8] = match key_type {
+        MifareKeyType::KeyA => b"RFID-A\0",
+        MifareKeyType::KeyB => b"RFID-B\0",
+    };
+    let mut okm = [0u8; 6 * SECTORS_1K];
+    hkdf_sha256(&BAMBU_KEY_SALT, uid, info, &mut okm);
+
+    let mut keys = [[0u8; 6]; SECTORS_1K];
+    for (key, chunk) in keys.iter_mut().zip(okm.chunks_exact(6)) {
+        key.copy_from_slice(chunk);
+    }
+    keys
+}
+
+/// Key A of each sector, derived from the UID
+pub struct BambuKeys;
+
+impl MifareKeyProvider for BambuKeys {
+    fn key(&self, uid: &[u8], sector: u8) -> (MifareKeyType, [u8; 6]) {
+        let keys = bambu_keys(uid, MifareKeyType::KeyA);
+        (MifareKeyType::KeyA, keys[sector as usize % SECTORS_1K])
+    }
+}
+
+/// Read one block of the authenticated sector
+pub fn read_block<T: Transceiver>(pcd: &mut T, block: u8) -> Result<[u8; BLOCK_SIZE], Pn5180Error> {
+    // A NAK (4 bits, no CRC) shows up as a CRC error
+    match pcd.transceive(&[READ, block], 0, true)? {
+        Some(rx) if rx.bits >= BLOCK_SIZE * 8 && rx.collision.is_none() => {
+            let mut data = [0u8; BLOCK_SIZE];
+            data.copy_from_slice(&rx.data[..BLOCK_SIZE]);
+            Ok(data)
+        }
+        Some(rx) => {
+            warn!("Block {} read: {} bits", block, rx.bits);
+            Err(Pn5180Error::ReadFailed)
+        }
+        None => Err(Pn5180Error::NoCard),
+    }
+}
+
+/// Read `blocks` from a selected card, authenticating each sector once with
+/// the key from `keys`. Returns the blocks back to back.
+pub fn read_blocks<T: Pn5180Io>(
+    io: &mut T,
+    card: &Iso14443aCard,
+    keys: &dyn MifareKeyProvider,
+    blocks: &[u8],
+) -> Result<Vec<u8>, Pn5180Error> {
+    let mut data = Vec::with_capacity(blocks.len() * BLOCK_SIZE);
+    let mut authenticated = None;
+
+    for &block in blocks {
+        let sector = sector_of(block);
+        if authenticated != Some(sector) {
+            let (key_type, key) = keys.key(card.uid(), sector);
+            pn5180_protocol::mifare_authenticate(io, block, key_type, &key, card.uid())?;
+            debug!("Sector {} authenticated", sector);
+            authenticated = Some(sector);
+        }
+        data.extend_from_slice(&read_block(io, block)?);
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(hex: &str) -> [u8; 6] {
+        let mut key = [0u8; 6];
+        for (i, byte) in key.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).unwrap();
+        }
+        key
+    }
+
+    // Reference: Python hmac/hashlib HKDF-SHA256 with the salt above, 96 bytes
+    // of output split into 16 keys
+    const UID: [u8; 4] = [0xDE, 0xAD, 0xBE, 0xEF];
+    const KEYS_A: [&str; SECTORS_1K] = [
+        "045c6dc690e9", "daf05c224715", "141899c0b498", "375533c16de8",
+        "ea75fd5c2ec2", "f6ac7fd01b75", "e3d94b7c914d", "3fec6971dd78",
+        "5b57effc5d7a", "1b31535effe7", "4c9bbd4ee19f", "8a5cd3180c93",
+        "33be1598f79e", "1a43690778fa", "c192e145b713", "46cf8b20c176",
+    ];
+    const KEYS_B: [&str; SECTORS_1K] = [
+        "2572900ffe08", "a0a461505bbe", "1a1415ccac6d", "05d87aad4f96",
+        "e907660920e4", "78ee65620a37", "1dafa963073b", "471c0a81b035",
+        "21b9dd53ac15", "2a726d66c4d3", "c99855527f83", "f7d20d2a68c1",
+        "55529bae6dee", "1aaa02023f1e", "f00438a9988b", "9ce9e52ce49d",
+    ];
+
+    #[test]
+    fn bambu_keys_match_reference() {
+        let keys_a = bambu_keys(&UID, MifareKeyType::KeyA);
+        let keys_b = bambu_keys(&UID, MifareKeyType::KeyB);
+        for sector in 0..SECTORS_1K {
+            assert_eq!(keys_a[sector], key(KEYS_A[sector]), "key A, sector {}", sector);
+            assert_eq!(keys_b[sector], key(KEYS_B[sector]), "key B, sector {}", sector);
+        }
+        assert_eq!(BambuKeys.key(&UID, 5), (MifareKeyType::KeyA, key(KEYS_A[5])));
+    }
+
+    #[test]
+    fn sectors_and_crypto_uid() {
+        assert_eq!(sector_of(0), 0);
+        assert_eq!(sector_of(7), 1);
+        assert_eq!(sector_of(127), 31);
+        assert_eq!(sector_of(128), 32);
+        assert_eq!(sector_of(255), 39);
+        assert_eq!(crypto_uid(&UID), Some(UID));
+        assert_eq!(crypto_uid(&[0x04, 0x01, 0x02, 0xDE, 0xAD, 0xBE, 0xEF]), Some(UID));
+        assert_eq!(crypto_uid(&[0; 10]), None);
+    }
+
+    mod mock {
+        use super::super::*;
+        use super::UID;
+        use crate::nfc::iso14443a::{activate, WUPA};
+        use crate::nfc::pn5180_mock::{MockCard, MockPn5180};
+
+        /// A Bambu-keyed card in the field, each BAMBU_BLOCKS block filled with its number
+        fn bambu_field() -> MockPn5180 {
+            let mut card = MockCard::mifare_classic_1k(&UID);
+            card.keys_a = bambu_keys(&UID, MifareKeyType::KeyA).to_vec();
+            card.keys_b = bambu_keys(&UID, MifareKeyType::KeyB).to_vec();
+            for block in BAMBU_BLOCKS {
+                card.blocks[block as usize] = [block; BLOCK_SIZE];
+            }
+            let mut pcd = MockPn5180::new();
+            pcd.cards.push(card);
+            pcd
+        }
+
+        #[test]
+        fn reads_with_correct_keys() {
+            let mut pcd = bambu_field();
+            let card = activate(&mut pcd, WUPA).unwrap().unwrap();
+            let data = read_blocks(&mut pcd, &card, &BambuKeys, &BAMBU_BLOCKS).unwrap();
+            assert_eq!(data.len(), BAMBU_BLOCKS.len() * BLOCK_SIZE);
+            for (chunk, block) in data.chunks_exact(BLOCK_SIZE).zip(BAMBU_BLOCKS) {
+                assert_eq!(chunk, [block; BLOCK_SIZE]);
+            }
+        }
+
+        #[test]
+        fn fails_with_wrong_keys() {
+            let mut pcd = bambu_field();
+            let card = activate(&mut pcd, WUPA).unwrap().unwrap();
+            let result = read_blocks(&mut pcd, &card, &DefaultKeys, &BAMBU_BLOCKS);
+            assert!(matches!(result, Err(Pn5180Error::AuthFailed)), "{:?}", result);
+        }
+
+        #[test]
+        fn fails_on_key_of_another_sector() {
+            // Keys of sector 0 for every sector: block 1 reads, block 4 does not
+            struct FirstSectorKey;
+            impl MifareKeyProvider for FirstSectorKey {
+                fn key(&self, uid: &[u8], _sector: u8) -> (MifareKeyType, [u8; 6]) {
+                    (MifareKeyType::KeyA, bambu_keys(uid, MifareKeyType::KeyA)[0])
+                }
+            }
+
+            let mut pcd = bambu_field();
+            let card = activate(&mut pcd, WUPA).unwrap().unwrap();
+            assert_eq!(read_blocks(&mut pcd, &card, &FirstSectorKey, &[1, 2]).unwrap().len(), 2 * BLOCK_SIZE);
+            let result = read_blocks(&mut pcd, &card, &FirstSectorKey, &[4]);
+            assert!(matches!(result, Err(Pn5180Error::AuthFailed)), "{:?}", result);
+        }
+    }
+}
@@ -0,0 +1,146 @@
+//! In-memory [`NfcReader`] for running the tag poller and manager without
+//! hardware
+//!
+//! Tags are placed on and taken off the reader by the host code. Each
+//! operation can be made to return `Pending` a few times first (like the Pico
+//! bridge does), and the next operation can be made to fail.
+
+use super::mifare_classic::BLOCK_SIZE;
+use super::ntag::PAGE_SIZE;
+use super::reader::{DetectedTag, NfcError, NfcReader, NfcResult, TagKind};
+
+/// A tag for the mock reader
+#[derive(Debug, Clone)]
+pub struct MockTag {
+    pub uid: Vec<u8>,
+    pub kind: TagKind,
+    /// NTAG pages or MIFARE Classic blocks, back to back
+    pub memory: Vec<u8>,
+}
+
+impl MockTag {
+    /// Blank NDEF formatted NTAG213 (45 pages, 144 bytes of user memory)
+    pub fn ntag213(uid: &[u8]) -> Self {
+        let mut memory = vec![0u8; 45 * PAGE_SIZE];
+        memory[3 * PAGE_SIZE..4 * PAGE_SIZE].copy_from_slice(&[0xE1, 0x10, 0x12, 0x00]);
+        memory[4 * PAGE_SIZE..5 * PAGE_SIZE].copy_from_slice(&[0x03, 0x00, 0xFE, 0x00]);
+        Self { uid: uid.to_vec(), kind: TagKind::Ntag, memory }
+    }
+
+    /// MIFARE Classic 1K with the given blocks (block number, data)
+    pub fn mifare_classic_1k(uid: &[u8], blocks: &[(u8, [u8; BLOCK_SIZE])]) -> Self {
+        let mut memory = vec![0u8; 64 * BLOCK_SIZE];
+        for (block, data) in blocks {
+            let start = *block as usize * BLOCK_SIZE;
+            memory[start..start + BLOCK_SIZE].copy_from_slice(data);
+        }
+        Self { uid: uid.to_vec(), kind: TagKind::MifareClassic1k, memory }
+    }
+
+    /// Set the CC write access nibble of an NTAG to read-only
+    pub fn read_only(mut self) -> Self {
+        self.memory[3 * PAGE_SIZE + 3] = 0x0F;
+        self
+    }
+}
+
+/// Reader whose field holds at most one [`MockTag`]
+#[derive(Debug, Default)]
+pub struct MockReader {
+    pub tag: Option<MockTag>,
+    /// `Pending` results returned before each operation completes
+    pub latency: u32,
+    /// Whether `write_pages` is supported
+    pub writable: bool,
+    /// Error returned by the next operation
+    pub fail_next: Option<&'static str>,
+    /// `Pending` results returned so far for the operation in progress
+    polls: u32,
+}
+
+impl MockReader {
+    pub fn new() -> Self {
+        Self { writable: true, ..Default::default() }
+    }
+
+    /// Count down the latency of an operation, then apply an injected failure
+    fn complete(&mut self) -> NfcResult<()> {
+        if self.polls < self.latency {
+            self.polls += 1;
+            return Err(NfcError::Pending);
+        }
+        self.polls = 0;
+        match self.fail_next.take() {
+            Some(e) => Err(NfcError::Failed(e)),
+            None => Ok(()),
+        }
+    }
+
+    /// The tag on the reader, if it is `tag`
+    fn tag_mut(&mut self, tag: &DetectedTag) -> NfcResult<&mut MockTag> {
+        match self.tag.as_mut() {
+            Some(t) if t.uid == tag.uid => Ok(t),
+            Some(_) => Err(NfcError::Failed("A different tag is on the reader")),
+            None => Err(NfcError::Failed("No tag present")),
+        }
+    }
+}
+
+impl NfcReader for MockReader {
+    fn name(&self) -> &'static str {
+        "Mock"
+    }
+
+    fn firmware_version(&self) -> (u8, u8, u8) {
+        (1, 0, 0)
+    }
+
+    fn can_write(&self) -> bool {
+        self.writable
+    }
+
+    fn detect(&mut self, _now_ms: u64) -> NfcResult<Option<DetectedTag>> {
+        self.complete()?;
+        Ok(self.tag.as_ref().map(|t| DetectedTag { uid: t.uid.clone(), kind: t.kind }))
+    }
+
+    fn read_blocks(&mut self, tag: &DetectedTag, blocks: &[u8], _now_ms: u64) -> NfcResult<Vec<u8>> {
+        self.complete()?;
+        let t = self.tag_mut(tag)?;
+        if !t.kind.is_mifare_classic() {
+            return Err(NfcError::Failed("Tag read failed"));
+        }
+        let mut data = Vec::with_capacity(blocks.len() * BLOCK_SIZE);
+        for &block in blocks {
+            let start = block as usize * BLOCK_SIZE;
+            data.extend_from_slice(t.memory.get(start..start + BLOCK_SIZE).ok_or("Tag read failed")?);
+        }
+        Ok(data)
+    }
+
+    fn read_pages(&mut self, tag: &DetectedTag, start_page: u8, count: u8, _now_ms: u64) -> NfcResult<Vec<u8>> {
+        self.complete()?;
+        let t = self.tag_mut(tag)?;
+        if t.kind != TagKind::Ntag {
+            return Err(NfcError::Failed("Tag read failed"));
+        }
+        let start = (start_page as usize * PAGE_SIZE).min(t.memory.len());
+        let end = (start + count as usize * PAGE_SIZE).min(t.memory.len());
+        Ok(t.memory[start..end].to_vec())
+    }
+
+    fn write_pages(&mut self, tag: &DetectedTag, start_page: u8, data: &[u8], _now_ms: u64) -> NfcResult<()> {
+        self.complete()?;
+        let t = self.tag_mut(tag)?;
+        let read_only = t.memory[3 * PAGE_SIZE + 3] & 0x0F != 0;
+        if t.kind != TagKind::Ntag || read_only || start_page < 4 {
+            return Err(NfcError::Failed("Write failed (tag locked or moved away?)"));
+        }
+        let start = start_page as usize * PAGE_SIZE;
+        let end = start + data.len().div_ceil(PAGE_SIZE) * PAGE_SIZE;
+        let area = t.memory.get_mut(start..end).ok_or("Write failed (tag locked or moved away?)")?;
+        area.fill(0);
+        area[..data.len()].copy_from_slice(data);
+        Ok(())
+    }
+}
@@ -0,0 +1,58 @@
+//! NFC tags, readers and the Pico bridge protocol.
+//!
+//! Everything but the PN5180 SPI/GPIO binding, which stays in the firmware
+//! (`pn5180`). Readers work through the `reader::NfcReader` trait, the bridge
+//! driver through `bridge_protocol::BridgeTransport` and the PN5180 protocol
+//! through `pn5180_protocol::Pn5180Io`, so the host tests run them against the
+//! test doubles below.
+
+/// PN5180 command frames, registers and RF exchanges (hardware independent)
+pub mod pn5180_protocol;
+
+/// ISO14443A activation: anticollision over all cascade levels, SELECT, HLTA
+pub mod iso14443a;
+
+/// MIFARE Classic authentication, block reads and per-tag keys (Bambu Lab)
+pub mod mifare_classic;
+
+/// NTAG21x page reads and writes, capability container
+pub mod ntag;
+
+/// Host-side mock of a PN5180 with ISO14443A cards in its field
+#[cfg(any(test, feature = "pn5180-mock"))]
+pub mod pn5180_mock;
+
+/// Common interface of the Pico bridge and PN5180 readers
+pub mod reader;
+
+/// NfcReader on the Pico bridge
+pub mod pico_reader;
+
+/// NfcReader on a PN5180 over SPI
+pub mod pn5180_reader;
+
+/// In-memory NfcReader for host-side tests of the poller and manager
+#[cfg(any(test, feature = "mock-reader"))]
+pub mod mock_reader;
+
+/// I2C bridge to Pico for NFC (recommended - more reliable than direct SPI)
+pub mod i2c_bridge;
+
+/// Framed, CRC-checked I2C protocol spoken with the Pico bridge
+pub mod bridge_protocol;
+
+/// Non-blocking scan/read/write state machine over any NfcReader
+pub mod tag_poller;
+
+/// Host-side emulator of the Pico bridge, for running the driver without hardware
+#[cfg(any(test, feature = "bridge-emulator"))]
+pub mod bridge_emulator;
+
+/// NDEF TLV/record parsing for NTAG tags
+pub mod ndef;
+
+/// Spool data formats carried in NDEF (OpenSpool, OpenPrintTag, OpenTag3D, SpoolEase)
+pub mod tag_formats;
+
+/// Chip UIDs seen with each tray UID (both tags of a Bambu Lab spool)
+pub mod spool_identity;
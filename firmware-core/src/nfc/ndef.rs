@@ -0,0 +1,237 @@
+//! NDEF parsing for NFC Forum Type 2 tags (NTAG21x)
+//!
+//! The user memory of a Type 2 tag (from page 4) is a sequence of TLV blocks.
+//! The NDEF Message TLV holds one or more NDEF records, each with a TNF, a
+//! type, an optional ID and a payload. Records borrow from the tag dump, so
+//! nothing is copied until a format decoder picks out its fields.
+//!
+//! The encoder side builds the same layout for writing: a single MIME record
+//! in an NDEF Message TLV, closed by a Terminator TLV.
+
+/// TLV block types
+const TLV_NULL: u8 = 0x00;
+const TLV_NDEF_MESSAGE: u8 = 0x03;
+const TLV_TERMINATOR: u8 = 0xFE;
+
+/// NTAG page size
+pub const PAGE_SIZE: usize = 4;
+
+/// Record header flags
+const FLAG_MB: u8 = 0x80;
+const FLAG_ME: u8 = 0x40;
+const FLAG_CF: u8 = 0x20;
+const FLAG_SR: u8 = 0x10;
+const FLAG_IL: u8 = 0x08;
+const TNF_MASK: u8 = 0x07;
+
+/// Type Name Format values
+pub const TNF_WELL_KNOWN: u8 = 0x01;
+pub const TNF_MIME: u8 = 0x02;
+pub const TNF_ABSOLUTE_URI: u8 = 0x03;
+
+/// URI identifier codes (NFC Forum URI RTD, prefix byte of a "U" record)
+const URI_PREFIXES: [&str; 36] = [
+    "",
+    "http://www.",
+    "https://www.",
+    "http://",
+    "https://",
+    "tel:",
+    "mailto:",
+    "ftp://anonymous:anonymous@",
+    "ftp://ftp.",
+    "ftps://",
+    "sftp://",
+    "smb://",
+    "nfs://",
+    "ftp://",
+    "dav://",
+    "news:",
+    "telnet://",
+    "imap:",
+    "rtsp://",
+    "urn:",
+    "pop:",
+    "sip:",
+    "sips:",
+    "tftp:",
+    "btspp://",
+    "btl2cap://",
+    "btgoep://",
+    "tcpobex://",
+    "irdaobex://",
+    "file://",
+    "urn:epc:id:",
+    "urn:epc:tag:",
+    "urn:epc:pat:",
+    "urn:epc:raw:",
+    "urn:epc:",
+    "urn:nfc:",
+];
+
+/// A single NDEF record
+#[derive(Debug, Clone, Copy)]
+pub struct NdefRecord<'a> {
+    pub tnf: u8,
+    pub record_type: &'a [u8],
+    #[allow(dead_code)]
+    pub id: &'a [u8],
+    pub payload: &'a [u8],
+}
+
+impl NdefRecord<'_> {
+    /// Check for a MIME record of the given type (case-insensitive, parameters ignored)
+    pub fn is_mime(&self, mime_type: &str) -> bool {
+        if self.tnf != TNF_MIME {
+            return false;
+        }
+        let record_type = std::str::from_utf8(self.record_type).unwrap_or("");
+        let base = record_type.split(';').next().unwrap_or("").trim();
+        base.eq_ignore_ascii_case(mime_type)
+    }
+
+    /// URI carried by a well-known "U" record or an absolute-URI record
+    pub fn uri(&self) -> Option<String> {
+        match self.tnf {
+            TNF_WELL_KNOWN if self.record_type == b"U" => expand_uri(self.payload),
+            TNF_ABSOLUTE_URI => std::str::from_utf8(self.record_type).ok().map(str::to_string),
+            _ => None,
+        }
+    }
+}
+
+/// Find the NDEF message in a Type 2 tag memory dump (starting at page 4)
+pub fn find_message(data: &[u8]) -> Result<&[u8], &'static str> {
+    let mut pos = 0;
+
+    while pos < data.len() {
+        let tlv_type = data[pos];
+        match tlv_type {
+            TLV_NULL => {
+                pos += 1;
+                continue;
+            }
+            TLV_TERMINATOR => break,
+            _ => {}
+        }
+
+        // Length: one byte, or 0xFF followed by a big-endian u16
+        let (len, value_start) = match data.get(pos + 1) {
+            Some(0xFF) => {
+                let hi = *data.get(pos + 2).ok_or("TLV length truncated")?;
+                let lo = *data.get(pos + 3).ok_or("TLV length truncated")?;
+                (u16::from_be_bytes([hi, lo]) as usize, pos + 4)
+            }
+            Some(&len) => (len as usize, pos + 2),
+            None => return Err("TLV length truncated"),
+        };
+
+        let value_end = value_start + len;
+        if tlv_type == TLV_NDEF_MESSAGE {
+            if value_end > data.len() {
+                return Err("NDEF message truncated");
+            }
+            return Ok(&data[value_start..value_end]);
+        }
+        pos = value_end;
+    }
+
+    Err("No NDEF message")
+}
+
+/// Split an NDEF message into its records
+pub fn parse_records(message: &[u8]) -> Result<Vec<NdefRecord<'_>>, &'static str> {
+    let mut records = Vec::new();
+    let mut pos = 0;
+
+    while pos < message.len() {
+        let header = message[pos];
+        if header & FLAG_CF != 0 {
+            return Err("Chunked NDEF records not supported");
+        }
+        pos += 1;
+
+        let type_len = *message.get(pos).ok_or("Record header truncated")? as usize;
+        pos += 1;
+
+        let payload_len = if header & FLAG_SR != 0 {
+            let len = *message.get(pos).ok_or("Record header truncated")? as usize;
+            pos += 1;
+            len
+        } else {
+            let bytes = message.get(pos..pos + 4).ok_or("Record header truncated")?;
+            pos += 4;
+            u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize
+        };
+
+        let id_len = if header & FLAG_IL != 0 {
+            let len = *message.get(pos).ok_or("Record header truncated")? as usize;
+            pos += 1;
+            len
+        } else {
+            0
+        };
+
+        let record_type = take(message, &mut pos, type_len)?;
+        let id = take(message, &mut pos, id_len)?;
+        let payload = take(message, &mut pos, payload_len)?;
+
+        records.push(NdefRecord { tnf: header & TNF_MASK, record_type, id, payload });
+    }
+
+    Ok(records)
+}
+
+fn take<'a>(data: &'a [u8], pos: &mut usize, len: usize) -> Result<&'a [u8], &'static str> {
+    let end = pos.checked_add(len).ok_or("Record truncated")?;
+    let slice = data.get(*pos..end).ok_or("Record truncated")?;
+    *pos = end;
+    Ok(slice)
+}
+
+/// Expand a URI record payload (prefix code + rest of the URI)
+pub fn expand_uri(payload: &[u8]) -> Option<String> {
+    let (&code, rest) = payload.split_first()?;
+    let prefix = URI_PREFIXES.get(code as usize).copied().unwrap_or("");
+    let rest = std::str::from_utf8(rest).ok()?;
+    Some(format!("{}{}", prefix, rest))
+}
+
+/// Encode a single-record NDEF message holding a MIME record
+pub fn mime_record(mime_type: &str, payload: &[u8]) -> Vec<u8> {
+    let short = payload.len() <= u8::MAX as usize;
+    let mut header = FLAG_MB | FLAG_ME | TNF_MIME;
+    if short {
+        header |= FLAG_SR;
+    }
+
+    let mut out = Vec::with_capacity(payload.len() + mime_type.len() + 6);
+    out.push(header);
+    out.push(mime_type.len() as u8);
+    if short {
+        out.push(payload.len() as u8);
+    } else {
+        out.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    }
+    out.extend_from_slice(mime_type.as_bytes());
+    out.extend_from_slice(payload);
+    out
+}
+
+/// Wrap an NDEF message for Type 2 tag memory (from page 4), padded to whole pages
+pub fn wrap_message(message: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(message.len() + 8);
+    out.push(TLV_NDEF_MESSAGE);
+    if message.len() < 0xFF {
+        out.push(message.len() as u8);
+    } else {
+        out.push(0xFF);
+        out.extend_from_slice(&(message.len() as u16).to_be_bytes());
+    }
+    out.extend_from_slice(message);
+    out.push(TLV_TERMINATOR);
+
+    let padded_len = out.len().div_ceil(PAGE_SIZE) * PAGE_SIZE;
+    out.resize(padded_len, 0);
+    out
+}
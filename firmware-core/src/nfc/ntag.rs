@@ -0,0 +1,93 @@
+//! NTAG21x memory access over ISO14443A
+//!
+//! Memory is organised in 4 byte pages: UID and lock bytes in pages 0-2, the
+//! capability container (CC) in page 3, user memory (NDEF) from page 4.
+//! READ returns 4 pages at a time; WRITE programs a single page and is
+//! acknowledged with a 4 bit ACK (no CRC).
+
+use super::iso14443a::{crc_a, Transceiver};
+use super::pn5180_protocol::Pn5180Error;
+use log::warn;
+
+/// Capability container page
+pub const NTAG_CC_PAGE: u8 = 3;
+
+/// First user memory page
+pub const NTAG_FIRST_DATA_PAGE: u8 = 4;
+
+/// User memory pages read for the spool data (NTAG213: pages 4-39, 144 bytes)
+pub const NTAG_DATA_PAGES: u8 = 36;
+
+/// Page size in bytes
+pub const PAGE_SIZE: usize = 4;
+
+const READ: u8 = 0x30;
+const WRITE: u8 = 0xA2;
+
+/// 4 bit ACK of a WRITE
+const ACK: u8 = 0x0A;
+
+/// NDEF magic number in CC byte 0
+const CC_NDEF_MAGIC: u8 = 0xE1;
+
+/// What the capability container says about the tag
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CapabilityContainer {
+    /// NDEF data area size in bytes
+    pub data_size: usize,
+    /// Write access nibble is 0x0 (0xF = read-only)
+    pub writable: bool,
+}
+
+/// Parse the CC page
+pub fn parse_cc(cc: &[u8]) -> Result<CapabilityContainer, &'static str> {
+    match cc {
+        [CC_NDEF_MAGIC, _version, size, access, ..] => Ok(CapabilityContainer {
+            data_size: *size as usize * 8,
+            // Low nibble is write access (0x0 = writable, 0xF = read-only)
+            writable: access & 0x0F == 0x00,
+        }),
+        [_, _, _, _, ..] => Err("Tag is not NDEF formatted"),
+        _ => Err("Capability container too short"),
+    }
+}
+
+/// Read `count` pages from `start_page` of a selected NTAG
+pub fn read_pages<T: Transceiver>(pcd: &mut T, start_page: u8, count: u8) -> Result<Vec<u8>, Pn5180Error> {
+    let mut data = Vec::with_capacity(count as usize * PAGE_SIZE);
+    let mut page = start_page;
+
+    while data.len() < count as usize * PAGE_SIZE {
+        match pcd.transceive(&[READ, page], 0, true)? {
+            Some(rx) if rx.bits >= 16 * 8 && rx.collision.is_none() => {
+                let wanted = count as usize * PAGE_SIZE - data.len();
+                data.extend_from_slice(&rx.data[..wanted.min(16)]);
+            }
+            Some(rx) => {
+                warn!("NTAG page {} read: {} bits", page, rx.bits);
+                return Err(Pn5180Error::ReadFailed);
+            }
+            None => return Err(Pn5180Error::NoCard),
+        }
+        page = page.checked_add(4).ok_or(Pn5180Error::ReadFailed)?;
+    }
+    Ok(data)
+}
+
+/// Write one page of a selected NTAG
+pub fn write_page<T: Transceiver>(pcd: &mut T, page: u8, data: &[u8; PAGE_SIZE]) -> Result<(), Pn5180Error> {
+    // The ACK carries no CRC, so the CRC is appended here rather than by the PCD
+    let mut tx = vec![WRITE, page];
+    tx.extend_from_slice(data);
+    let crc = crc_a(&tx);
+    tx.extend_from_slice(&crc);
+
+    match pcd.transceive(&tx, 0, false)? {
+        Some(rx) if rx.bits == 4 && rx.data[0] & 0x0F == ACK => Ok(()),
+        Some(rx) => {
+            warn!("NTAG page {} write: NAK {:X}", page, rx.data.first().copied().unwrap_or(0) & 0x0F);
+            Err(Pn5180Error::WriteFailed)
+        }
+        None => Err(Pn5180Error::NoCard),
+    }
+}
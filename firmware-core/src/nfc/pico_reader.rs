@@ -0,0 +1,250 @@
+//! [`NfcReader`] on the Pico bridge
+//!
+//! The Pico talks to the tag while the shared I2C bus is released: a call
+//! sends the command and returns `Pending`, and later calls with the same
+//! arguments poll once for the response. Only one command is outstanding at a
+//! time; a call for a different operation abandons it.
+//!
+//! The bridge has no raw page or block access. SCAN_TAG only returns the UID,
+//! so `detect` follows a new tag up with READ_TAG_DATA to learn its type, and
+//! keeps the data (the MIFARE Bambu blocks or NTAG pages 4-39) for the reads
+//! that follow. Older bridge firmware returns only Bambu blocks 1, 2, 4, 5. The capability container is reported by an empty WRITE_TAG.
+
+use super::bridge_protocol::{self, BridgeTransport};
+use super::i2c_bridge::{
+    self, BridgeTagData, NfcBridgeState, READ_TIMEOUT_MS, SCAN_TIMEOUT_MS, TAG_TYPE_MIFARE_1K,
+    TAG_TYPE_MIFARE_4K, TAG_TYPE_NTAG, WRITE_CHUNK_PAGES, WRITE_TIMEOUT_MS,
+};
+use super::mifare_classic::BAMBU_BLOCKS;
+use super::ntag::{NTAG_CC_PAGE, NTAG_FIRST_DATA_PAGE, PAGE_SIZE};
+use super::reader::{DetectedTag, NfcError, NfcReader, NfcResult, TagKind};
+use log::warn;
+
+/// Bridge command whose response is outstanding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Scan,
+    Read,
+    /// WRITE_TAG of `page_count` pages from `start_page` (0 pages = check only)
+    Write { start_page: u8, page_count: u8 },
+}
+
+impl Op {
+    fn timeout_ms(self) -> u32 {
+        match self {
+            Op::Scan => SCAN_TIMEOUT_MS,
+            Op::Read => READ_TIMEOUT_MS,
+            Op::Write { .. } => WRITE_TIMEOUT_MS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct InFlight {
+    op: Op,
+    seq: u8,
+    sent_ms: u64,
+}
+
+/// The Pico bridge behind a [`BridgeTransport`]
+pub struct PicoReader<T: BridgeTransport> {
+    bus: T,
+    state: NfcBridgeState,
+    in_flight: Option<InFlight>,
+    /// READ_TAG_DATA of the tag on the reader
+    cached: Option<BridgeTagData>,
+    /// Pages of the current `write_pages` call already written
+    written_pages: usize,
+}
+
+impl<T: BridgeTransport> PicoReader<T> {
+    /// Probe the bridge and negotiate the protocol version
+    pub fn init(mut bus: T) -> Result<Self, &'static str> {
+        let mut state = NfcBridgeState::new();
+        i2c_bridge::init_bridge(&mut bus, &mut state)?;
+        Ok(Self { bus, state, in_flight: None, cached: None, written_pages: 0 })
+    }
+
+    /// Send the command for `op`, or poll once for its response
+    fn run(
+        &mut self,
+        op: Op,
+        now_ms: u64,
+        send: impl FnOnce(&mut T) -> Result<u8, &'static str>,
+    ) -> NfcResult<(u8, Vec<u8>)> {
+        let cmd = match self.in_flight {
+            Some(cmd) if cmd.op == op => cmd,
+            _ => {
+                // Whatever else was outstanding is abandoned
+                self.in_flight = None;
+                let seq = send(&mut self.bus)?;
+                self.in_flight = Some(InFlight { op, seq, sent_ms: now_ms });
+                return Err(NfcError::Pending);
+            }
+        };
+
+        match bridge_protocol::poll_response(&mut self.bus, cmd.seq) {
+            Ok(Some(resp)) => {
+                self.in_flight = None;
+                Ok((cmd.seq, resp))
+            }
+            Ok(None) if now_ms.saturating_sub(cmd.sent_ms) < op.timeout_ms() as u64 => Err(NfcError::Pending),
+            Ok(None) => {
+                self.in_flight = None;
+                Err(NfcError::Failed("Bridge response timeout"))
+            }
+            Err(e) => {
+                self.in_flight = None;
+                Err(NfcError::Failed(e))
+            }
+        }
+    }
+
+    /// READ_TAG_DATA, keeping the result for later reads
+    fn read_tag(&mut self, now_ms: u64) -> NfcResult<BridgeTagData> {
+        let (seq, resp) = self.run(Op::Read, now_ms, i2c_bridge::start_read)?;
+        let data = i2c_bridge::finish_read(seq, &resp)?;
+        self.cached = Some(data.clone());
+        Ok(data)
+    }
+
+    /// Data of `tag`, read if not cached
+    fn tag_data(&mut self, tag: &DetectedTag, now_ms: u64) -> NfcResult<BridgeTagData> {
+        if let Some(data) = self.cached.as_ref().filter(|data| data.uid == tag.uid) {
+            return Ok(data.clone());
+        }
+        let data = self.read_tag(now_ms)?;
+        if data.uid != tag.uid {
+            return Err(NfcError::Failed("A different tag is on the reader"));
+        }
+        Ok(data)
+    }
+
+    /// UID from the last SCAN_TAG
+    fn scanned_uid(&self) -> Vec<u8> {
+        self.state.tag_uid[..self.state.tag_uid_len as usize].to_vec()
+    }
+
+    /// Read a newly scanned tag to learn its type
+    fn identify(&mut self, now_ms: u64) -> NfcResult<Option<DetectedTag>> {
+        match self.read_tag(now_ms) {
+            Ok(data) => Ok(Some(DetectedTag { kind: tag_kind(data.tag_type), uid: data.uid })),
+            Err(NfcError::Pending) => Err(NfcError::Pending),
+            Err(NfcError::Failed(e)) => {
+                // Not cached, so the next scan tries again
+                warn!("Tag data read error: {}", e);
+                Ok(Some(DetectedTag { uid: self.scanned_uid(), kind: TagKind::Unknown }))
+            }
+        }
+    }
+}
+
+impl<T: BridgeTransport> NfcReader for PicoReader<T> {
+    fn name(&self) -> &'static str {
+        "Pico bridge"
+    }
+
+    fn firmware_version(&self) -> (u8, u8, u8) {
+        let (major, minor) = self.state.firmware_version;
+        (major, minor, 0)
+    }
+
+    fn can_write(&self) -> bool {
+        i2c_bridge::can_write(&self.state).is_ok()
+    }
+
+    fn detect(&mut self, now_ms: u64) -> NfcResult<Option<DetectedTag>> {
+        // Still reading the tag the last scan found
+        if matches!(self.in_flight, Some(InFlight { op: Op::Read, .. })) {
+            return self.identify(now_ms);
+        }
+
+        let (seq, resp) = self.run(Op::Scan, now_ms, i2c_bridge::start_scan)?;
+        if !i2c_bridge::finish_scan(&mut self.state, seq, &resp) {
+            self.cached = None;
+            return Ok(None);
+        }
+
+        let uid = self.scanned_uid();
+        match &self.cached {
+            Some(data) if data.uid == uid => Ok(Some(DetectedTag { uid, kind: tag_kind(data.tag_type) })),
+            _ => {
+                self.cached = None;
+                self.identify(now_ms)
+            }
+        }
+    }
+
+    fn read_blocks(&mut self, tag: &DetectedTag, blocks: &[u8], now_ms: u64) -> NfcResult<Vec<u8>> {
+        if blocks != BAMBU_BLOCKS {
+            return Err(NfcError::Failed("The Pico bridge only reads the Bambu Lab blocks"));
+        }
+        let data = self.tag_data(tag, now_ms)?;
+        if !tag_kind(data.tag_type).is_mifare_classic() {
+            return Err(NfcError::Failed("Not a MIFARE Classic tag"));
+        }
+        Ok(data.data)
+    }
+
+    fn read_pages(&mut self, tag: &DetectedTag, start_page: u8, count: u8, now_ms: u64) -> NfcResult<Vec<u8>> {
+        if start_page == NTAG_CC_PAGE && count == 1 {
+            // An empty write checks the tag and reports the CC size and access bytes
+            let op = Op::Write { start_page: NTAG_FIRST_DATA_PAGE, page_count: 0 };
+            let (seq, resp) = self.run(op, now_ms, |bus| i2c_bridge::start_write(bus, NTAG_FIRST_DATA_PAGE, &[]))?;
+            let (cc_size, cc_access) = i2c_bridge::finish_write(seq, &resp)?;
+            return Ok(vec![0xE1, 0x10, cc_size, cc_access]);
+        }
+        if start_page != NTAG_FIRST_DATA_PAGE {
+            return Err(NfcError::Failed("The Pico bridge only reads from page 4"));
+        }
+
+        let data = self.tag_data(tag, now_ms)?;
+        if data.tag_type != TAG_TYPE_NTAG {
+            return Err(NfcError::Failed("Not an NTAG tag"));
+        }
+        let mut memory = data.data;
+        memory.truncate(count as usize * PAGE_SIZE);
+        Ok(memory)
+    }
+
+    fn write_pages(&mut self, _tag: &DetectedTag, start_page: u8, data: &[u8], now_ms: u64) -> NfcResult<()> {
+        // Progress only carries over while one of our chunks is outstanding
+        if !matches!(self.in_flight, Some(InFlight { op: Op::Write { .. }, .. })) {
+            self.written_pages = 0;
+        }
+        self.cached = None;
+
+        let page_count = data.len().div_ceil(PAGE_SIZE);
+        while self.written_pages < page_count {
+            let first = self.written_pages;
+            let pages = (page_count - first).min(WRITE_CHUNK_PAGES);
+            let chunk = &data[first * PAGE_SIZE..data.len().min((first + pages) * PAGE_SIZE)];
+            let page = u8::try_from(start_page as usize + first).map_err(|_| "Write out of range")?;
+
+            let op = Op::Write { start_page: page, page_count: pages as u8 };
+            let result = self
+                .run(op, now_ms, |bus| i2c_bridge::start_write(bus, page, chunk))
+                .and_then(|(seq, resp)| i2c_bridge::finish_write(seq, &resp).map_err(NfcError::from));
+            match result {
+                Ok(_) => self.written_pages += pages,
+                Err(NfcError::Pending) => return Err(NfcError::Pending),
+                Err(e) => {
+                    self.written_pages = 0;
+                    return Err(e);
+                }
+            }
+        }
+        self.written_pages = 0;
+        Ok(())
+    }
+}
+
+/// Tag kind from the bridge's TAG_TYPE_*
+fn tag_kind(tag_type: u8) -> TagKind {
+    match tag_type {
+        TAG_TYPE_NTAG => TagKind::Ntag,
+        TAG_TYPE_MIFARE_1K => TagKind::MifareClassic1k,
+        TAG_TYPE_MIFARE_4K => TagKind::MifareClassic4k,
+        _ => TagKind::Unknown,
+    }
+}
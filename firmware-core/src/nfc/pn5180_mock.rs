@@ -0,0 +1,448 @@
+//! Mock PN5180 with ISO14443A cards in its field
+//!
+//! Implements [`Pn5180Io`] like the SPI driver does, decoding the command
+//! frames the way the chip would: register writes and masks, READ_REGISTER,
+//! SEND_DATA in the Transceive state, READ_DATA. The RF side is modelled
+//! with cards that follow the ISO14443-3A state machine (idle, ready per
+//! cascade level, active, halt); when several cards answer, their bits are
+//! combined and the first differing bit is reported as a collision in
+//! RX_STATUS, as on real hardware. MIFARE Classic cards check the key on
+//! MIFARE_AUTHENTICATE and then serve READs of the authenticated sector
+//! (Crypto1 is not simulated; the PN5180 hides it from the host anyway).
+//! NTAG cards serve READ and WRITE on their page memory.
+//! Lets the anticollision and MIFARE code run on the host.
+
+use super::iso14443a::{bcc, crc_a, CASCADE_TAG, HLTA, NVB_SELECT, REQA, SAK_CASCADE, SEL_CASCADE_LEVELS, WUPA};
+use super::mifare_classic::{crypto_uid, sector_of, BLOCK_SIZE, DEFAULT_KEY, SECTORS_1K};
+use super::ntag::{NTAG_CC_PAGE, NTAG_FIRST_DATA_PAGE, PAGE_SIZE};
+use super::pn5180_protocol::{
+    commands, registers, Pn5180Error, Pn5180Io, CRC_ENABLE, IRQ_RX, MFC_AUTH_FAILED, MFC_AUTH_OK, MFC_AUTH_TIMEOUT,
+    RX_COLLISION_DETECTED, RX_COLL_POS_SHIFT, RX_DATA_INTEGRITY_ERROR, RX_NUM_LAST_BITS_SHIFT,
+    SYSTEM_CONFIG_COMMAND_MASK, SYSTEM_CONFIG_COMMAND_TRANSCEIVE, SYSTEM_CONFIG_MFC_CRYPTO_ON,
+};
+
+/// READ (MIFARE Classic block, or 4 NTAG pages) and its NAK (4 bits)
+const READ: u8 = 0x30;
+const NAK: u8 = 0x04;
+
+/// NTAG WRITE (one page) and its ACK (4 bits)
+const NTAG_WRITE: u8 = 0xA2;
+const NTAG_ACK: u8 = 0x0A;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CardState {
+    Idle,
+    /// Anticollision/SELECT on this cascade level (0-2)
+    Ready(usize),
+    Active,
+    Halt,
+}
+
+/// A card in the mock field
+#[derive(Debug, Clone)]
+pub struct MockCard {
+    pub uid: Vec<u8>,
+    pub atqa: [u8; 2],
+    /// SAK once the UID is complete
+    pub sak: u8,
+    /// MIFARE Classic memory (empty for other cards)
+    pub blocks: Vec<[u8; BLOCK_SIZE]>,
+    /// MIFARE Classic key A and key B of each sector
+    pub keys_a: Vec<[u8; 6]>,
+    pub keys_b: Vec<[u8; 6]>,
+    /// NTAG memory from page 0 (empty for other cards)
+    pub pages: Vec<[u8; PAGE_SIZE]>,
+    state: CardState,
+    /// Sector authenticated with Crypto1
+    authenticated: Option<u8>,
+}
+
+impl MockCard {
+    /// Card with a 4, 7 or 10 byte UID (ATQA UID size bits set to match)
+    pub fn new(uid: &[u8], sak: u8) -> Self {
+        let uid_size = match uid.len() {
+            4 => 0x00,
+            7 => 0x40,
+            10 => 0x80,
+            n => panic!("invalid UID length {}", n),
+        };
+        Self {
+            uid: uid.to_vec(),
+            atqa: [uid_size | 0x04, 0x00],
+            sak,
+            blocks: Vec::new(),
+            keys_a: Vec::new(),
+            keys_b: Vec::new(),
+            pages: Vec::new(),
+            state: CardState::Idle,
+            authenticated: None,
+        }
+    }
+
+    /// Blank, NDEF-formatted NTAG213 (7 byte UID, SAK 0x00, 45 pages)
+    pub fn ntag(uid: &[u8; 7]) -> Self {
+        let mut card = Self::new(uid, 0x00);
+        card.pages = vec![[0; PAGE_SIZE]; 45];
+        card.pages[0] = [uid[0], uid[1], uid[2], bcc(&[CASCADE_TAG, uid[0], uid[1], uid[2]])];
+        card.pages[1] = [uid[3], uid[4], uid[5], uid[6]];
+        card.pages[2][0] = bcc(&uid[3..7]);
+        card.pages[NTAG_CC_PAGE as usize] = [0xE1, 0x10, 0x12, 0x00];
+        // Empty NDEF message
+        card.pages[NTAG_FIRST_DATA_PAGE as usize] = [0x03, 0x00, 0xFE, 0x00];
+        card
+    }
+
+    /// Set the NTAG CC access byte to read-only
+    pub fn read_only(mut self) -> Self {
+        if let Some(cc) = self.pages.get_mut(NTAG_CC_PAGE as usize) {
+            cc[3] = 0x0F;
+        }
+        self
+    }
+
+    /// NTAG user memory from page 4
+    pub fn ntag_memory(&self) -> Vec<u8> {
+        self.pages.iter().skip(NTAG_FIRST_DATA_PAGE as usize).flatten().copied().collect()
+    }
+
+    /// Blank MIFARE Classic 1K (4 byte UID, SAK 0x08, transport keys)
+    pub fn mifare_classic_1k(uid: &[u8; 4]) -> Self {
+        let mut card = Self::new(uid, 0x08);
+        card.blocks = vec![[0; BLOCK_SIZE]; SECTORS_1K * 4];
+        card.keys_a = vec![DEFAULT_KEY; SECTORS_1K];
+        card.keys_b = vec![DEFAULT_KEY; SECTORS_1K];
+        card
+    }
+
+    /// Whether the card is halted
+    pub fn is_halted(&self) -> bool {
+        self.state == CardState::Halt
+    }
+
+    /// Levels needed for the UID
+    fn levels(&self) -> usize {
+        match self.uid.len() {
+            4 => 1,
+            7 => 2,
+            _ => 3,
+        }
+    }
+
+    /// The 40 bits of a cascade level: 4 UID bytes (after the cascade tag if more follow) and BCC
+    fn cascade_level(&self, level: usize) -> [u8; 5] {
+        let mut uid_cl = [0u8; 5];
+        if level + 1 < self.levels() {
+            uid_cl[0] = CASCADE_TAG;
+            uid_cl[1..4].copy_from_slice(&self.uid[level * 3..level * 3 + 3]);
+        } else {
+            uid_cl[..4].copy_from_slice(&self.uid[level * 3..level * 3 + 4]);
+        }
+        uid_cl[4] = bcc(&uid_cl[..4]);
+        uid_cl
+    }
+
+    /// MIFARE_AUTHENTICATE against this card; returns the PN5180 status byte
+    fn authenticate(&mut self, key_type: u8, block: u8, key: &[u8], uid: [u8; 4]) -> Option<u8> {
+        if self.state != CardState::Active || crypto_uid(&self.uid) != Some(uid) {
+            return None;
+        }
+        let sector = sector_of(block) as usize;
+        let expected = match key_type {
+            0x60 => self.keys_a.get(sector),
+            0x61 => self.keys_b.get(sector),
+            _ => None,
+        };
+        if expected.is_some_and(|k| k[..] == *key) {
+            self.authenticated = Some(sector as u8);
+            Some(MFC_AUTH_OK)
+        } else {
+            // A failed authentication leaves the card idle
+            self.state = CardState::Idle;
+            self.authenticated = None;
+            Some(MFC_AUTH_FAILED)
+        }
+    }
+
+    /// 16 bytes for a READ: a MIFARE Classic block of the authenticated
+    /// sector, or 4 NTAG pages (rolling over to page 0 at the end)
+    fn read(&self, addr: u8) -> Option<[u8; BLOCK_SIZE]> {
+        if !self.blocks.is_empty() {
+            let block = self.blocks.get(addr as usize)?;
+            return (self.authenticated == Some(sector_of(addr))).then_some(*block);
+        }
+        if (addr as usize) >= self.pages.len() {
+            return None;
+        }
+        let mut data = [0u8; BLOCK_SIZE];
+        for (i, chunk) in data.chunks_exact_mut(PAGE_SIZE).enumerate() {
+            chunk.copy_from_slice(&self.pages[(addr as usize + i) % self.pages.len()]);
+        }
+        Some(data)
+    }
+
+    /// NTAG WRITE of a user memory page, if the CC allows it
+    fn write_page(&mut self, page: u8, data: &[u8]) -> bool {
+        let writable = self.pages.get(NTAG_CC_PAGE as usize).is_some_and(|cc| cc[3] & 0x0F == 0);
+        match self.pages.get_mut(page as usize) {
+            Some(target) if writable && page >= NTAG_FIRST_DATA_PAGE => {
+                target.copy_from_slice(data);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Handle a frame as received over the air (`crypto`: PN5180 Crypto1 on);
+    /// returns the answer bits (LSB first)
+    fn receive(&mut self, frame: &[u8], last_bits: u8, crypto: bool) -> Option<Vec<bool>> {
+        // Without the cipher on both ends the card only sees noise
+        if self.authenticated.is_some() != crypto {
+            self.state = CardState::Idle;
+            self.authenticated = None;
+            return None;
+        }
+
+        // Short frame: REQA/WUPA
+        if frame.len() == 1 && last_bits == 7 {
+            let wakes = match frame[0] {
+                REQA => self.state == CardState::Idle,
+                WUPA => matches!(self.state, CardState::Idle | CardState::Halt),
+                _ => false,
+            };
+            if wakes {
+                self.state = CardState::Ready(0);
+                return Some(to_bits(&self.atqa, 16));
+            }
+            if self.state != CardState::Halt {
+                self.state = CardState::Idle;
+                self.authenticated = None;
+            }
+            return None;
+        }
+
+        match self.state {
+            CardState::Ready(level) => {
+                if frame.len() < 2 || frame[0] != SEL_CASCADE_LEVELS[level] {
+                    self.state = CardState::Idle;
+                    return None;
+                }
+                let uid_cl = self.cascade_level(level);
+
+                // SELECT: all 40 bits with CRC_A
+                if frame[1] == NVB_SELECT {
+                    if frame.len() != 9 || !crc_ok(frame) || frame[2..7] != uid_cl {
+                        self.state = CardState::Idle;
+                        return None;
+                    }
+                    let sak = if level + 1 < self.levels() {
+                        self.state = CardState::Ready(level + 1);
+                        SAK_CASCADE
+                    } else {
+                        self.state = CardState::Active;
+                        self.sak
+                    };
+                    let mut answer = vec![sak];
+                    answer.extend_from_slice(&crc_a(&[sak]));
+                    return Some(to_bits(&answer, 24));
+                }
+
+                // ANTICOLLISION: answer the rest of the level if the known bits match
+                let known = ((frame[1] >> 4) as usize).saturating_sub(2) * 8 + (frame[1] & 0x07) as usize;
+                if known >= 40 || (0..known).any(|i| get_bit(&frame[2..], i) != get_bit(&uid_cl, i)) {
+                    return None;
+                }
+                Some((known..40).map(|i| get_bit(&uid_cl, i)).collect())
+            }
+            CardState::Active => {
+                if frame.len() == 4 && frame[0] == READ && crc_ok(frame) {
+                    if let Some(data) = self.read(frame[1]) {
+                        let mut answer = data.to_vec();
+                        answer.extend_from_slice(&crc_a(&data));
+                        return Some(to_bits(&answer, answer.len() * 8));
+                    }
+                    self.state = CardState::Idle;
+                    self.authenticated = None;
+                    return Some(to_bits(&[NAK], 4));
+                }
+                if frame.len() == 8 && frame[0] == NTAG_WRITE && crc_ok(frame) {
+                    if self.write_page(frame[1], &frame[2..6]) {
+                        return Some(to_bits(&[NTAG_ACK], 4));
+                    }
+                    self.state = CardState::Idle;
+                    return Some(to_bits(&[NAK], 4));
+                }
+                self.state = if frame.len() == 4 && frame[..2] == HLTA && crc_ok(frame) {
+                    CardState::Halt
+                } else {
+                    CardState::Idle
+                };
+                self.authenticated = None;
+                None
+            }
+            CardState::Idle | CardState::Halt => None,
+        }
+    }
+}
+
+/// Mock PN5180 (registers, RX buffer) with cards in its field
+pub struct MockPn5180 {
+    /// Cards in the field
+    pub cards: Vec<MockCard>,
+    registers: [u32; 0x30],
+    rx_buffer: Vec<u8>,
+    now_ms: u32,
+    rf_exchanges: usize,
+}
+
+impl Default for MockPn5180 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockPn5180 {
+    pub fn new() -> Self {
+        Self { cards: Vec::new(), registers: [0; 0x30], rx_buffer: Vec::new(), now_ms: 0, rf_exchanges: 0 }
+    }
+
+    /// Virtual time passed in `delay_ms` calls
+    pub fn now_ms(&self) -> u32 {
+        self.now_ms
+    }
+
+    /// Number of SEND_DATA frames sent over the air
+    pub fn rf_exchanges(&self) -> usize {
+        self.rf_exchanges
+    }
+
+    fn register_mut(&mut self, reg: u8) -> Result<&mut u32, Pn5180Error> {
+        self.registers.get_mut(reg as usize).ok_or(Pn5180Error::InvalidResponse)
+    }
+
+    /// Run one RF exchange from SEND_DATA
+    fn send_data(&mut self, last_bits: u8, data: &[u8]) {
+        let system_config = self.registers[registers::SYSTEM_CONFIG as usize];
+        if system_config & SYSTEM_CONFIG_COMMAND_MASK != SYSTEM_CONFIG_COMMAND_TRANSCEIVE {
+            return;
+        }
+        self.rf_exchanges += 1;
+
+        let mut frame = data.to_vec();
+        if self.registers[registers::CRC_TX_CONFIG as usize] & CRC_ENABLE != 0 {
+            frame.extend_from_slice(&crc_a(data));
+        }
+        let crypto = system_config & SYSTEM_CONFIG_MFC_CRYPTO_ON != 0;
+        let answers: Vec<Vec<bool>> =
+            self.cards.iter_mut().filter_map(|card| card.receive(&frame, last_bits, crypto)).collect();
+
+        self.rx_buffer.clear();
+        self.registers[registers::RX_STATUS as usize] = 0;
+        let Some(len) = answers.iter().map(Vec::len).max() else {
+            return;
+        };
+
+        // Overlapping answers: a 1 wins, and the first differing bit is the collision
+        let bits: Vec<bool> = (0..len).map(|i| answers.iter().any(|a| a.get(i) == Some(&true))).collect();
+        let collision = (0..len).find(|&i| answers.iter().any(|a| a.get(i) != Some(&bits[i])));
+
+        let mut bits = bits;
+        let mut integrity_error = false;
+        if self.registers[registers::CRC_RX_CONFIG as usize] & CRC_ENABLE != 0 && collision.is_none() {
+            // Checked and stripped by the receiver; shorter frames (a NAK) fail the check
+            if len >= 24 {
+                bits.truncate(len - 16);
+            } else {
+                integrity_error = true;
+            }
+        }
+
+        self.rx_buffer = vec![0u8; bits.len().div_ceil(8)];
+        for (i, &bit) in bits.iter().enumerate() {
+            if bit {
+                self.rx_buffer[i / 8] |= 1 << (i % 8);
+            }
+        }
+        let mut rx_status = self.rx_buffer.len() as u32 | (((bits.len() % 8) as u32) << RX_NUM_LAST_BITS_SHIFT);
+        if let Some(pos) = collision {
+            rx_status |= RX_COLLISION_DETECTED | ((pos as u32) << RX_COLL_POS_SHIFT);
+        }
+        if integrity_error {
+            rx_status |= RX_DATA_INTEGRITY_ERROR;
+        }
+        self.registers[registers::RX_STATUS as usize] = rx_status;
+        self.registers[registers::IRQ_STATUS as usize] |= IRQ_RX;
+    }
+}
+
+impl Pn5180Io for MockPn5180 {
+    fn command(&mut self, cmd: &[u8]) -> Result<(), Pn5180Error> {
+        let value = |cmd: &[u8]| -> Result<u32, Pn5180Error> {
+            match cmd.get(2..6) {
+                Some(b) => Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+                None => Err(Pn5180Error::InvalidResponse),
+            }
+        };
+        match cmd.first().copied() {
+            Some(commands::WRITE_REGISTER) if cmd[1] == registers::IRQ_CLEAR => {
+                let clear = value(cmd)?;
+                *self.register_mut(registers::IRQ_STATUS)? &= !clear;
+            }
+            Some(commands::WRITE_REGISTER) => *self.register_mut(cmd[1])? = value(cmd)?,
+            Some(commands::WRITE_REGISTER_OR_MASK) => *self.register_mut(cmd[1])? |= value(cmd)?,
+            Some(commands::WRITE_REGISTER_AND_MASK) => *self.register_mut(cmd[1])? &= value(cmd)?,
+            Some(commands::SEND_DATA) if cmd.len() >= 3 => self.send_data(cmd[1], &cmd[2..]),
+            Some(commands::RF_ON) | Some(commands::RF_OFF) | Some(commands::LOAD_RF_CONFIG) => {}
+            _ => return Err(Pn5180Error::InvalidResponse),
+        }
+        Ok(())
+    }
+
+    fn command_read(&mut self, cmd: &[u8], response: &mut [u8]) -> Result<(), Pn5180Error> {
+        response.fill(0);
+        match cmd {
+            [commands::READ_REGISTER, reg] => {
+                let value = *self.register_mut(*reg)?;
+                let n = response.len().min(4);
+                response[..n].copy_from_slice(&value.to_le_bytes()[..n]);
+            }
+            [commands::READ_DATA, 0x00] => {
+                let n = response.len().min(self.rx_buffer.len());
+                response[..n].copy_from_slice(&self.rx_buffer[..n]);
+            }
+            [commands::MIFARE_AUTHENTICATE, key @ .., key_type, block, u0, u1, u2, u3] if key.len() == 6 => {
+                let uid = [*u0, *u1, *u2, *u3];
+                let status = self
+                    .cards
+                    .iter_mut()
+                    .find_map(|card| card.authenticate(*key_type, *block, key, uid))
+                    .unwrap_or(MFC_AUTH_TIMEOUT);
+                if status == MFC_AUTH_OK {
+                    self.registers[registers::SYSTEM_CONFIG as usize] |= SYSTEM_CONFIG_MFC_CRYPTO_ON;
+                }
+                if let Some(first) = response.first_mut() {
+                    *first = status;
+                }
+            }
+            [commands::READ_EEPROM, ..] => {}
+            _ => return Err(Pn5180Error::InvalidResponse),
+        }
+        Ok(())
+    }
+
+    fn delay_ms(&mut self, ms: u32) {
+        self.now_ms += ms;
+    }
+}
+
+fn crc_ok(frame: &[u8]) -> bool {
+    let (data, crc) = frame.split_at(frame.len() - 2);
+    crc_a(data) == [crc[0], crc[1]]
+}
+
+fn to_bits(bytes: &[u8], bits: usize) -> Vec<bool> {
+    (0..bits).map(|i| get_bit(bytes, i)).collect()
+}
+
+fn get_bit(data: &[u8], bit: usize) -> bool {
+    data.get(bit / 8).is_some_and(|b| b & (1 << (bit % 8)) != 0)
+}
@@ -0,0 +1,222 @@
+//! PN5180 host interface: SPI command frames, registers and RF exchanges
+//!
+//! Everything the host does with the PN5180 is a command frame on SPI
+//! ([CMD_BYTE] [PAYLOAD...]), optionally followed by a second frame that
+//! clocks out the response once BUSY is low. [`Pn5180Io`] is that frame
+//! level: the ESP32 driver implements it with NSS/BUSY handling, the mock
+//! PN5180 implements it on the host.
+//!
+//! On top of the frames, every `Pn5180Io` is an ISO14443A [`Transceiver`]:
+//! one RF exchange is SEND_DATA in the Transceive command state, waiting for
+//! the RX IRQ, then RX_STATUS and READ_DATA. Nothing here touches the ESP32
+//! hardware.
+
+use super::iso14443a::{RxFrame, Transceiver};
+use super::mifare_classic::{self, MifareKeyType};
+
+/// PN5180 command codes
+pub mod commands {
+    pub const WRITE_REGISTER: u8 = 0x00;
+    pub const WRITE_REGISTER_OR_MASK: u8 = 0x01;
+    pub const WRITE_REGISTER_AND_MASK: u8 = 0x02;
+    pub const READ_REGISTER: u8 = 0x04;
+    pub const WRITE_EEPROM: u8 = 0x06;
+    pub const READ_EEPROM: u8 = 0x07;
+    pub const SEND_DATA: u8 = 0x09;
+    pub const READ_DATA: u8 = 0x0A;
+    pub const SWITCH_MODE: u8 = 0x0B;
+    pub const MIFARE_AUTHENTICATE: u8 = 0x0C;
+    pub const EPC_INVENTORY: u8 = 0x0D;
+    pub const EPC_RESUME_INVENTORY: u8 = 0x0E;
+    pub const EPC_RETRIEVE_INVENTORY_RESULT_SIZE: u8 = 0x0F;
+    pub const EPC_RETRIEVE_INVENTORY_RESULT: u8 = 0x10;
+    pub const LOAD_RF_CONFIG: u8 = 0x11;
+    pub const UPDATE_RF_CONFIG: u8 = 0x12;
+    pub const RETRIEVE_RF_CONFIG_SIZE: u8 = 0x13;
+    pub const RETRIEVE_RF_CONFIG: u8 = 0x14;
+    pub const RF_ON: u8 = 0x16;
+    pub const RF_OFF: u8 = 0x17;
+}
+
+/// PN5180 register addresses
+pub mod registers {
+    pub const SYSTEM_CONFIG: u8 = 0x00;
+    pub const IRQ_ENABLE: u8 = 0x01;
+    pub const IRQ_STATUS: u8 = 0x02;
+    pub const IRQ_CLEAR: u8 = 0x03;
+    pub const TRANSCEIVE_CONTROL: u8 = 0x04;
+    pub const TIMER1_CONFIG: u8 = 0x0F;
+    pub const TIMER1_RELOAD: u8 = 0x10;
+    pub const TIMER1_VALUE: u8 = 0x11;
+    pub const CRC_RX_CONFIG: u8 = 0x12;
+    pub const RX_STATUS: u8 = 0x13;
+    pub const CRC_TX_CONFIG: u8 = 0x19;
+    pub const RF_STATUS: u8 = 0x1D;
+}
+
+/// SYSTEM_CONFIG: command field (bits 2:0) and MIFARE Crypto1 enable
+pub const SYSTEM_CONFIG_COMMAND_MASK: u32 = 0x07;
+pub const SYSTEM_CONFIG_COMMAND_TRANSCEIVE: u32 = 0x03;
+pub const SYSTEM_CONFIG_MFC_CRYPTO_ON: u32 = 1 << 6;
+
+/// IRQ_STATUS: a frame was received
+pub const IRQ_RX: u32 = 1 << 0;
+
+/// CRC_RX_CONFIG / CRC_TX_CONFIG: CRC enable
+pub const CRC_ENABLE: u32 = 0x01;
+
+/// RX_STATUS fields
+pub const RX_NUM_BYTES_MASK: u32 = 0x1FF;
+pub const RX_NUM_LAST_BITS_SHIFT: u32 = 13;
+pub const RX_DATA_INTEGRITY_ERROR: u32 = 1 << 16;
+pub const RX_COLLISION_DETECTED: u32 = 1 << 18;
+pub const RX_COLL_POS_SHIFT: u32 = 19;
+
+/// MIFARE_AUTHENTICATE result
+pub const MFC_AUTH_OK: u8 = 0x00;
+pub const MFC_AUTH_FAILED: u8 = 0x01;
+pub const MFC_AUTH_TIMEOUT: u8 = 0x02;
+
+/// How long to wait for a card to answer (ISO14443A answers within a few ms)
+const RX_TIMEOUT_MS: u32 = 5;
+
+/// Largest frame the PN5180 RX buffer holds
+const MAX_RX_BYTES: usize = 508;
+
+/// PN5180 errors
+#[derive(Debug, Clone, Copy)]
+pub enum Pn5180Error {
+    SpiError,
+    GpioError,
+    Timeout,
+    NoCard,
+    AuthFailed,
+    ReadFailed,
+    WriteFailed,
+    InvalidResponse,
+}
+
+/// Command frame transport to the PN5180 (the SPI bus, or the mock in tests)
+pub trait Pn5180Io {
+    /// Send one command frame
+    fn command(&mut self, cmd: &[u8]) -> Result<(), Pn5180Error>;
+    /// Send a command frame, then clock out `response.len()` bytes
+    fn command_read(&mut self, cmd: &[u8], response: &mut [u8]) -> Result<(), Pn5180Error>;
+    fn delay_ms(&mut self, ms: u32);
+}
+
+/// Write register (32-bit)
+pub fn write_register<T: Pn5180Io>(io: &mut T, reg: u8, value: u32) -> Result<(), Pn5180Error> {
+    let bytes = value.to_le_bytes();
+    io.command(&[commands::WRITE_REGISTER, reg, bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+/// Set register bits
+pub fn write_register_or_mask<T: Pn5180Io>(io: &mut T, reg: u8, mask: u32) -> Result<(), Pn5180Error> {
+    let bytes = mask.to_le_bytes();
+    io.command(&[commands::WRITE_REGISTER_OR_MASK, reg, bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+/// Keep only the register bits in `mask`
+pub fn write_register_and_mask<T: Pn5180Io>(io: &mut T, reg: u8, mask: u32) -> Result<(), Pn5180Error> {
+    let bytes = mask.to_le_bytes();
+    io.command(&[commands::WRITE_REGISTER_AND_MASK, reg, bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+/// Read register (32-bit)
+pub fn read_register<T: Pn5180Io>(io: &mut T, reg: u8) -> Result<u32, Pn5180Error> {
+    let mut response = [0u8; 4];
+    io.command_read(&[commands::READ_REGISTER, reg], &mut response)?;
+    Ok(u32::from_le_bytes(response))
+}
+
+/// Switch MIFARE Crypto1 off (a card left authenticated would not answer in plain)
+pub fn crypto1_off<T: Pn5180Io>(io: &mut T) -> Result<(), Pn5180Error> {
+    write_register_and_mask(io, registers::SYSTEM_CONFIG, !SYSTEM_CONFIG_MFC_CRYPTO_ON)
+}
+
+/// Authenticate the MIFARE Classic sector holding `block` with Crypto1.
+/// On success the PN5180 sets MFC_CRYPTO_ON and en/decrypts what follows.
+pub fn mifare_authenticate<T: Pn5180Io>(
+    io: &mut T,
+    block: u8,
+    key_type: MifareKeyType,
+    key: &[u8; 6],
+    uid: &[u8],
+) -> Result<(), Pn5180Error> {
+    let uid = mifare_classic::crypto_uid(uid).ok_or(Pn5180Error::InvalidResponse)?;
+
+    // [cmd, key (6), key type, block, UID (4)] -> 1 status byte
+    let mut cmd = [0u8; 13];
+    cmd[0] = commands::MIFARE_AUTHENTICATE;
+    cmd[1..7].copy_from_slice(key);
+    cmd[7] = key_type.auth_code();
+    cmd[8] = block;
+    cmd[9..13].copy_from_slice(&uid);
+
+    let mut status = [0u8; 1];
+    io.command_read(&cmd, &mut status)?;
+    match status[0] {
+        MFC_AUTH_OK => Ok(()),
+        MFC_AUTH_FAILED => Err(Pn5180Error::AuthFailed),
+        MFC_AUTH_TIMEOUT => Err(Pn5180Error::Timeout),
+        _ => Err(Pn5180Error::InvalidResponse),
+    }
+}
+
+/// Every PN5180 frame transport can run ISO14443A exchanges
+impl<T: Pn5180Io> Transceiver for T {
+    fn transceive(&mut self, tx: &[u8], tx_last_bits: u8, crc: bool) -> Result<Option<RxFrame>, Pn5180Error> {
+        // CRC is appended on TX and checked and stripped on RX by the PN5180
+        if crc {
+            write_register_or_mask(self, registers::CRC_TX_CONFIG, CRC_ENABLE)?;
+            write_register_or_mask(self, registers::CRC_RX_CONFIG, CRC_ENABLE)?;
+        } else {
+            write_register_and_mask(self, registers::CRC_TX_CONFIG, !CRC_ENABLE)?;
+            write_register_and_mask(self, registers::CRC_RX_CONFIG, !CRC_ENABLE)?;
+        }
+        write_register(self, registers::IRQ_CLEAR, 0x000F_FFFF)?;
+
+        // Idle, then Transceive: the next SEND_DATA goes out and the receiver arms itself
+        write_register_and_mask(self, registers::SYSTEM_CONFIG, !SYSTEM_CONFIG_COMMAND_MASK)?;
+        write_register_or_mask(self, registers::SYSTEM_CONFIG, SYSTEM_CONFIG_COMMAND_TRANSCEIVE)?;
+
+        // SEND_DATA: [cmd, valid bits in last byte (0 = all 8), data...]
+        let mut frame = Vec::with_capacity(2 + tx.len());
+        frame.extend_from_slice(&[commands::SEND_DATA, tx_last_bits & 0x07]);
+        frame.extend_from_slice(tx);
+        self.command(&frame)?;
+
+        // Wait for the answer; none within the timeout means no card (or a halted one)
+        let mut waited = 0;
+        while read_register(self, registers::IRQ_STATUS)? & IRQ_RX == 0 {
+            if waited >= RX_TIMEOUT_MS {
+                return Ok(None);
+            }
+            self.delay_ms(1);
+            waited += 1;
+        }
+
+        let rx_status = read_register(self, registers::RX_STATUS)?;
+        let bytes = (rx_status & RX_NUM_BYTES_MASK) as usize;
+        // All ones is a floating MISO, not a frame
+        if rx_status == 0xFFFF_FFFF || bytes > MAX_RX_BYTES {
+            return Err(Pn5180Error::SpiError);
+        }
+        if bytes == 0 {
+            return Ok(None);
+        }
+        if crc && rx_status & RX_DATA_INTEGRITY_ERROR != 0 {
+            return Err(Pn5180Error::ReadFailed);
+        }
+
+        let last_bits = ((rx_status >> RX_NUM_LAST_BITS_SHIFT) & 0x07) as usize;
+        let bits = if last_bits == 0 { bytes * 8 } else { (bytes - 1) * 8 + last_bits };
+        let collision = (rx_status & RX_COLLISION_DETECTED != 0)
+            .then_some(((rx_status >> RX_COLL_POS_SHIFT) & 0x7F) as usize);
+
+        let mut data = vec![0u8; bytes];
+        self.command_read(&[commands::READ_DATA, 0x00], &mut data)?;
+        Ok(Some(RxFrame { data, bits, collision }))
+    }
+}
@@ -0,0 +1,109 @@
+//! [`NfcReader`] on a PN5180 driven directly over SPI
+//!
+//! Every operation completes in one call (a few ms of SPI). The tag is halted
+//! after each operation and woken again with WUPA by the next one, so the
+//! periodic `detect` sees the same tag again instead of a card that is still
+//! selected (and would ignore the request).
+
+use super::iso14443a::{self, Iso14443aCard};
+use super::mifare_classic::{self, BambuKeys};
+use super::ntag::{self, PAGE_SIZE};
+use super::pn5180_protocol::{self, Pn5180Error, Pn5180Io};
+use super::reader::{DetectedTag, NfcError, NfcReader, NfcResult, TagKind};
+
+/// A PN5180 with its RF field on and ISO14443A configuration loaded
+pub struct Pn5180Reader<IO: Pn5180Io> {
+    io: IO,
+    firmware_version: (u8, u8, u8),
+}
+
+impl<IO: Pn5180Io> Pn5180Reader<IO> {
+    pub fn new(io: IO, firmware_version: (u8, u8, u8)) -> Self {
+        Self { io, firmware_version }
+    }
+
+    /// Wake and select any tag in the field
+    fn activate(&mut self) -> Result<Option<Iso14443aCard>, Pn5180Error> {
+        // A tag left authenticated would not answer in plain
+        pn5180_protocol::crypto1_off(&mut self.io)?;
+        iso14443a::activate(&mut self.io, iso14443a::WUPA)
+    }
+
+    /// Select `tag` again and run `op` on it, halting it afterwards
+    fn with_tag<R>(
+        &mut self,
+        tag: &DetectedTag,
+        op: impl FnOnce(&mut IO, &Iso14443aCard) -> Result<R, Pn5180Error>,
+    ) -> NfcResult<R> {
+        let card = match self.activate() {
+            Ok(Some(card)) => card,
+            Ok(None) => return Err(NfcError::Failed("No tag present")),
+            Err(e) => return Err(failed(e)),
+        };
+        if card.uid() != tag.uid.as_slice() {
+            let _ = iso14443a::halt(&mut self.io);
+            return Err(NfcError::Failed("A different tag is on the reader"));
+        }
+
+        let result = op(&mut self.io, &card);
+        // A tag that NAKed is idle already and stays silent
+        let _ = iso14443a::halt(&mut self.io);
+        result.map_err(failed)
+    }
+}
+
+impl<IO: Pn5180Io> NfcReader for Pn5180Reader<IO> {
+    fn name(&self) -> &'static str {
+        "PN5180"
+    }
+
+    fn firmware_version(&self) -> (u8, u8, u8) {
+        self.firmware_version
+    }
+
+    fn can_write(&self) -> bool {
+        true
+    }
+
+    fn detect(&mut self, _now_ms: u64) -> NfcResult<Option<DetectedTag>> {
+        let card = self.activate().map_err(failed)?;
+        Ok(card.map(|card| {
+            let _ = iso14443a::halt(&mut self.io);
+            DetectedTag { uid: card.uid().to_vec(), kind: TagKind::from_sak(card.sak) }
+        }))
+    }
+
+    fn read_blocks(&mut self, tag: &DetectedTag, blocks: &[u8], _now_ms: u64) -> NfcResult<Vec<u8>> {
+        // The MIFARE Classic tags read for spool data are Bambu Lab's
+        self.with_tag(tag, |io, card| mifare_classic::read_blocks(io, card, &BambuKeys, blocks))
+    }
+
+    fn read_pages(&mut self, tag: &DetectedTag, start_page: u8, count: u8, _now_ms: u64) -> NfcResult<Vec<u8>> {
+        self.with_tag(tag, |io, _| ntag::read_pages(io, start_page, count))
+    }
+
+    fn write_pages(&mut self, tag: &DetectedTag, start_page: u8, data: &[u8], _now_ms: u64) -> NfcResult<()> {
+        self.with_tag(tag, |io, _| {
+            for (i, chunk) in data.chunks(PAGE_SIZE).enumerate() {
+                let mut page_data = [0u8; PAGE_SIZE];
+                page_data[..chunk.len()].copy_from_slice(chunk);
+                let page = u8::try_from(start_page as usize + i).map_err(|_| Pn5180Error::WriteFailed)?;
+                ntag::write_page(io, page, &page_data)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+/// User-facing message for a PN5180 error
+fn failed(e: Pn5180Error) -> NfcError {
+    NfcError::Failed(match e {
+        Pn5180Error::SpiError | Pn5180Error::GpioError => "PN5180 communication error",
+        Pn5180Error::Timeout => "Tag did not answer",
+        Pn5180Error::NoCard => "Tag removed",
+        Pn5180Error::AuthFailed => "Tag authentication failed",
+        Pn5180Error::ReadFailed => "Tag read failed",
+        Pn5180Error::WriteFailed => "Write failed (tag locked or moved away?)",
+        Pn5180Error::InvalidResponse => "Invalid response from tag",
+    })
+}
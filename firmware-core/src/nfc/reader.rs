@@ -0,0 +1,98 @@
+//! Common interface of the NFC readers
+//!
+//! Tags are read either through the Pico bridge on the shared I2C bus
+//! (`pico_reader`) or by a PN5180 on SPI (`pn5180_reader`). The tag poller
+//! and the manager only see [`NfcReader`], so the rest of the firmware does
+//! not care which one was found at boot.
+//!
+//! The Pico runs each command while the bus is released, so its operations
+//! span several main-loop ticks: they return [`NfcError::Pending`] and the
+//! caller repeats the same call on a later tick until it completes. The
+//! PN5180 completes every call right away.
+
+/// Kind of tag, as far as the spool data goes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagKind {
+    /// NTAG21x (NDEF: OpenSpool, OpenPrintTag, OpenTag3D, SpoolEase)
+    Ntag,
+    /// MIFARE Classic 1K (Bambu Lab)
+    MifareClassic1k,
+    /// MIFARE Classic 4K
+    MifareClassic4k,
+    /// Anything else, or not identified yet
+    Unknown,
+}
+
+impl TagKind {
+    /// Identify a tag from its SAK
+    pub fn from_sak(sak: u8) -> Self {
+        match sak {
+            0x00 => TagKind::Ntag,
+            0x08 => TagKind::MifareClassic1k,
+            0x18 => TagKind::MifareClassic4k,
+            _ => TagKind::Unknown,
+        }
+    }
+
+    pub fn is_mifare_classic(self) -> bool {
+        matches!(self, TagKind::MifareClassic1k | TagKind::MifareClassic4k)
+    }
+}
+
+/// A tag found by `detect`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetectedTag {
+    /// UID (4, 7 or 10 bytes)
+    pub uid: Vec<u8>,
+    pub kind: TagKind,
+}
+
+impl DetectedTag {
+    /// UID as "04:A1:B2:..." (tag id used by the backend)
+    pub fn uid_hex(&self) -> String {
+        self.uid.iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(":")
+    }
+}
+
+/// Why a reader operation did not return a result
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NfcError {
+    /// Still running: call again with the same arguments
+    Pending,
+    /// Failed (shown to the user for writes)
+    Failed(&'static str),
+}
+
+impl From<&'static str> for NfcError {
+    fn from(message: &'static str) -> Self {
+        NfcError::Failed(message)
+    }
+}
+
+pub type NfcResult<T> = Result<T, NfcError>;
+
+/// An NFC reader back end. `now_ms` is a monotonic clock for timeouts.
+pub trait NfcReader {
+    /// Reader name for logs
+    fn name(&self) -> &'static str;
+
+    /// Firmware version (major, minor, patch)
+    fn firmware_version(&self) -> (u8, u8, u8);
+
+    /// Whether the reader can write tags
+    fn can_write(&self) -> bool;
+
+    /// Look for a tag in the field
+    fn detect(&mut self, now_ms: u64) -> NfcResult<Option<DetectedTag>>;
+
+    /// Read MIFARE Classic blocks, back to back (sectors are authenticated
+    /// with the tag's derived keys). May return only a leading part of them.
+    fn read_blocks(&mut self, tag: &DetectedTag, blocks: &[u8], now_ms: u64) -> NfcResult<Vec<u8>>;
+
+    /// Read `count` NTAG pages from `start_page`. May return fewer bytes when
+    /// the reader cannot read that far (the Pico bridge stops at page 39).
+    fn read_pages(&mut self, tag: &DetectedTag, start_page: u8, count: u8, now_ms: u64) -> NfcResult<Vec<u8>>;
+
+    /// Write NTAG pages from `start_page` (`data` is padded to whole pages)
+    fn write_pages(&mut self, tag: &DetectedTag, start_page: u8, data: &[u8], now_ms: u64) -> NfcResult<()>;
+}
@@ -0,0 +1,186 @@
+//! Spool identity registry: chip UIDs seen with each tray UID
+//!
+//! Bambu Lab spools carry a tag on each side. The chip UIDs differ, but both
+//! tags hold the same tray UID, so the tray UID identifies the spool. The
+//! registry remembers the chip UIDs of recently seen spools; the firmware
+//! (`spool_identity`) persists it to NVS as JSON.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Spools remembered (least recently seen are dropped beyond this)
+pub const MAX_SPOOLS: usize = 32;
+
+/// Chip UIDs remembered per spool (a spool has two tags)
+pub const MAX_TAGS_PER_SPOOL: usize = 2;
+
+/// Tags seen with one tray UID
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpoolTags {
+    pub tray_uid: String,
+    /// Chip UIDs ("XX:XX:XX:XX"), most recently seen last
+    pub tag_uids: Vec<String>,
+}
+
+/// Persisted spool list, least recently seen first
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Registry {
+    pub spools: VecDeque<SpoolTags>,
+}
+
+impl Registry {
+    /// Record that the tag `tag_uid` holds `tray_uid`.
+    /// Returns true if the mapping was new.
+    pub fn record(&mut self, tag_uid: &str, tray_uid: &str) -> bool {
+        let known = self.spool_of(tag_uid).is_some_and(|s| s.tray_uid == tray_uid);
+
+        // A chip UID belongs to one spool only
+        for spool in self.spools.iter_mut().filter(|s| s.tray_uid != tray_uid) {
+            spool.tag_uids.retain(|uid| uid != tag_uid);
+        }
+        self.spools.retain(|s| !s.tag_uids.is_empty());
+
+        let mut spool = match self.spools.iter().position(|s| s.tray_uid == tray_uid) {
+            Some(index) => self.spools.remove(index).unwrap(),
+            None => SpoolTags { tray_uid: tray_uid.to_string(), tag_uids: Vec::new() },
+        };
+        spool.tag_uids.retain(|uid| uid != tag_uid);
+        spool.tag_uids.push(tag_uid.to_string());
+        if spool.tag_uids.len() > MAX_TAGS_PER_SPOOL {
+            spool.tag_uids.remove(0);
+        }
+
+        while self.spools.len() >= MAX_SPOOLS {
+            self.spools.pop_front();
+        }
+        self.spools.push_back(spool);
+        !known
+    }
+
+    /// Spool a chip UID was last seen on
+    pub fn spool_of(&self, tag_uid: &str) -> Option<&SpoolTags> {
+        self.spools.iter().find(|s| s.tag_uids.iter().any(|uid| uid == tag_uid))
+    }
+
+    /// Spool identity of a tag: its tray UID if known, else the chip UID
+    pub fn spool_id(&self, tag_uid: &str) -> String {
+        self.spool_of(tag_uid)
+            .map(|spool| spool.tray_uid.clone())
+            .unwrap_or_else(|| tag_uid.to_string())
+    }
+
+    /// Chip UIDs of the spool a tag belongs to: the tag itself, then the
+    /// others most recently seen first
+    pub fn spool_tag_uids(&self, tag_uid: &str) -> Vec<String> {
+        let mut uids = vec![tag_uid.to_string()];
+        if let Some(spool) = self.spool_of(tag_uid) {
+            uids.extend(spool.tag_uids.iter().rev().filter(|uid| *uid != tag_uid).cloned());
+        }
+        uids
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tray_uids(registry: &Registry) -> Vec<&str> {
+        registry.spools.iter().map(|s| s.tray_uid.as_str()).collect()
+    }
+
+    #[test]
+    fn record_both_sides_of_a_spool() {
+        let mut registry = Registry::default();
+        assert!(registry.record("04:A1", "TRAY1"));
+        assert!(!registry.record("04:A1", "TRAY1"));
+        assert!(registry.record("04:B2", "TRAY1"));
+
+        assert_eq!(registry.spools.len(), 1);
+        assert_eq!(registry.spool_id("04:A1"), "TRAY1");
+        assert_eq!(registry.spool_id("04:B2"), "TRAY1");
+        assert_eq!(registry.spool_id("04:C3"), "04:C3");
+    }
+
+    #[test]
+    fn chip_uid_moves_to_another_tray() {
+        let mut registry = Registry::default();
+        registry.record("04:A1", "TRAY1");
+        registry.record("04:B2", "TRAY1");
+        registry.record("04:C3", "TRAY2");
+
+        // Rewritten tag: it now belongs to TRAY2 only
+        assert!(registry.record("04:A1", "TRAY2"));
+        assert_eq!(registry.spool_id("04:A1"), "TRAY2");
+        assert_eq!(registry.spool_of("04:B2").unwrap().tag_uids, ["04:B2"]);
+        assert_eq!(registry.spool_of("04:C3").unwrap().tag_uids, ["04:C3", "04:A1"]);
+
+        // A spool left without tags is dropped
+        assert!(registry.record("04:B2", "TRAY2"));
+        assert_eq!(tray_uids(&registry), ["TRAY2"]);
+        assert_eq!(registry.spool_id("04:C3"), "04:C3");
+    }
+
+    #[test]
+    fn keeps_two_tags_per_spool() {
+        let mut registry = Registry::default();
+        registry.record("04:A1", "TRAY1");
+        registry.record("04:B2", "TRAY1");
+        registry.record("04:C3", "TRAY1");
+
+        // The least recently seen tag is forgotten
+        assert_eq!(registry.spool_of("04:B2").unwrap().tag_uids, ["04:B2", "04:C3"]);
+        assert_eq!(registry.spool_id("04:A1"), "04:A1");
+        assert!(registry.record("04:A1", "TRAY1"));
+
+        // Seeing a known tag again makes it the most recent one
+        registry.record("04:C3", "TRAY1");
+        assert_eq!(registry.spool_of("04:C3").unwrap().tag_uids, ["04:A1", "04:C3"]);
+    }
+
+    #[test]
+    fn evicts_least_recently_seen_spool() {
+        let mut registry = Registry::default();
+        for i in 0..MAX_SPOOLS {
+            registry.record(&format!("04:{:02X}", i), &format!("TRAY{}", i));
+        }
+        assert_eq!(registry.spools.len(), MAX_SPOOLS);
+
+        // Seeing TRAY0 again keeps it, TRAY1 is now the oldest
+        assert!(!registry.record("04:00", "TRAY0"));
+        assert!(registry.record("04:FF", "NEW"));
+
+        assert_eq!(registry.spools.len(), MAX_SPOOLS);
+        assert_eq!(registry.spool_id("04:01"), "04:01");
+        assert_eq!(registry.spool_id("04:00"), "TRAY0");
+        assert_eq!(registry.spools.front().unwrap().tray_uid, "TRAY2");
+        assert_eq!(registry.spools.back().unwrap().tray_uid, "NEW");
+    }
+
+    #[test]
+    fn spool_tag_uids_order() {
+        let mut registry = Registry::default();
+        assert_eq!(registry.spool_tag_uids("04:A1"), ["04:A1"]);
+
+        registry.record("04:A1", "TRAY1");
+        registry.record("04:B2", "TRAY1");
+
+        // The tag itself first, then the spool's other tag
+        assert_eq!(registry.spool_tag_uids("04:A1"), ["04:A1", "04:B2"]);
+        assert_eq!(registry.spool_tag_uids("04:B2"), ["04:B2", "04:A1"]);
+
+        // Unknown tags only list themselves
+        assert_eq!(registry.spool_tag_uids("04:C3"), ["04:C3"]);
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let mut registry = Registry::default();
+        registry.record("04:A1", "TRAY1");
+        registry.record("04:B2", "TRAY2");
+
+        let json = serde_json::to_vec(&registry).unwrap();
+        let restored: Registry = serde_json::from_slice(&json).unwrap();
+        assert_eq!(restored, registry);
+        assert_eq!(tray_uids(&restored), ["TRAY1", "TRAY2"]);
+    }
+}
@@ -0,0 +1,873 @@
+//! Spool data formats stored in NDEF on NTAG tags
+//!
+//! Mirrors the backend's `tags/` decoders so third-party tagged spools show up
+//! on the display without a backend round trip:
+//! - OpenSpool: JSON in an `application/json` record
+//! - OpenPrintTag: CBOR in an `application/vnd.openprinttag` record
+//! - OpenTag3D: fixed binary layout in an `application/opentag3d` record
+//! - SpoolEase: query parameters of an `info.filament3d.org` URI record
+//!
+//! OpenSpool and OpenPrintTag can also be encoded for writing to a tag.
+
+use super::i2c_bridge::{format_color_name, DecodedTagInfo};
+use super::ndef::{self, NdefRecord};
+use serde::{Deserialize, Serialize};
+
+const OPENSPOOL_MIME: &str = "application/json";
+const OPENPRINTTAG_MIME: &str = "application/vnd.openprinttag";
+const OPENTAG3D_MIME: &str = "application/opentag3d";
+const SPOOLEASE_HOST: &str = "info.filament3d.org";
+
+/// Decode the spool data in an NTAG memory dump (pages 4 onwards)
+pub fn decode_ntag(data: &[u8]) -> Result<DecodedTagInfo, &'static str> {
+    let message = ndef::find_message(data)?;
+    let records = ndef::parse_records(message)?;
+    records.iter().find_map(decode_record).ok_or("No known spool format")
+}
+
+/// Formats the display can write to NTAG tags
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagWriteFormat {
+    OpenSpool,
+    OpenPrintTag,
+}
+
+impl TagWriteFormat {
+    /// Map the C format constant (`TAG_WRITE_FORMAT_*` in ui_internal.h)
+    pub fn from_c(value: i32) -> Option<Self> {
+        match value {
+            0 => Some(Self::OpenSpool),
+            1 => Some(Self::OpenPrintTag),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::OpenSpool => "OpenSpool",
+            Self::OpenPrintTag => "OpenPrintTag",
+        }
+    }
+}
+
+/// Encode spool data as NTAG memory contents (pages 4 onwards)
+pub fn encode_ntag(format: TagWriteFormat, info: &DecodedTagInfo) -> Vec<u8> {
+    let record = match format {
+        TagWriteFormat::OpenSpool => ndef::mime_record(OPENSPOOL_MIME, &encode_openspool(info)),
+        TagWriteFormat::OpenPrintTag => ndef::mime_record(OPENPRINTTAG_MIME, &encode_openprinttag(info)),
+    };
+    ndef::wrap_message(&record)
+}
+
+/// Decode a single NDEF record, if it is one of the known spool formats
+pub fn decode_record(record: &NdefRecord<'_>) -> Option<DecodedTagInfo> {
+    if record.is_mime(OPENPRINTTAG_MIME) {
+        return decode_openprinttag(record.payload);
+    }
+    if record.is_mime(OPENSPOOL_MIME) {
+        return decode_openspool(record.payload);
+    }
+    if record.is_mime(OPENTAG3D_MIME) {
+        return decode_opentag3d(record.payload);
+    }
+    record.uri().and_then(|uri| decode_spoolease(&uri))
+}
+
+/// Fill the color name from the RGBA value when the tag has none
+fn with_color_fallback(mut info: DecodedTagInfo) -> DecodedTagInfo {
+    if info.color_name.is_empty() && info.color_rgba != 0 {
+        info.color_name = format_color_name(info.color_rgba);
+    }
+    info
+}
+
+/// Parse "RRGGBB" or "RRGGBBAA" (optionally prefixed with '#') to 0xRRGGBBAA
+fn parse_hex_rgba(hex: &str) -> Option<u32> {
+    let hex = hex.trim().trim_start_matches('#');
+    // from_str_radix alone would take a leading sign
+    if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let value = u32::from_str_radix(hex, 16).ok()?;
+    match hex.len() {
+        6 => Some((value << 8) | 0xFF),
+        8 => Some(value),
+        _ => None,
+    }
+}
+
+// ============================================================================
+// OpenSpool
+// ============================================================================
+
+/// OpenSpool JSON payload: {"protocol": "openspool", "type": "PLA", ...}
+#[derive(Debug, Deserialize)]
+struct OpenSpoolPayload {
+    protocol: String,
+    #[serde(rename = "type", default)]
+    material_type: Option<String>,
+    #[serde(default)]
+    color_hex: Option<String>,
+    #[serde(default)]
+    brand: Option<String>,
+}
+
+/// OpenSpool JSON as written (same fields and order as the backend's encoder)
+#[derive(Debug, Serialize)]
+struct OpenSpoolOut<'a> {
+    protocol: &'static str,
+    version: &'static str,
+    #[serde(rename = "type", skip_serializing_if = "str::is_empty")]
+    material_type: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    color_hex: Option<String>,
+    #[serde(skip_serializing_if = "str::is_empty")]
+    brand: &'a str,
+}
+
+fn encode_openspool(info: &DecodedTagInfo) -> Vec<u8> {
+    let out = OpenSpoolOut {
+        protocol: "openspool",
+        version: "1.0",
+        material_type: &info.material,
+        // OpenSpool colors are RGB without alpha
+        color_hex: (info.color_rgba != 0).then(|| format!("{:06X}", info.color_rgba >> 8)),
+        brand: &info.vendor,
+    };
+    serde_json::to_vec(&out).unwrap_or_default()
+}
+
+fn decode_openspool(payload: &[u8]) -> Option<DecodedTagInfo> {
+    let data: OpenSpoolPayload = serde_json::from_slice(payload).ok()?;
+    if data.protocol != "openspool" {
+        return None;
+    }
+
+    Some(with_color_fallback(DecodedTagInfo {
+        vendor: data.brand.unwrap_or_default(),
+        material: data.material_type.unwrap_or_default(),
+        color_rgba: data.color_hex.as_deref().and_then(parse_hex_rgba).unwrap_or(0),
+        tag_type_name: "OpenSpool".to_string(),
+        ..Default::default()
+    }))
+}
+
+// ============================================================================
+// OpenPrintTag
+// ============================================================================
+
+/// Material type enum of the main region (key 9)
+const OPENPRINTTAG_MATERIALS: [&str; 40] = [
+    "PLA", "PETG", "TPU", "ABS", "ASA", "PC", "PCTG", "PP", "PA6", "PA11",
+    "PA12", "PA66", "CPE", "TPE", "HIPS", "PHA", "PET", "PEI", "PBT", "PVB",
+    "PVA", "PEKK", "PEEK", "BVOH", "TPC", "PPS", "PPSU", "PVC", "PEBA", "PVDF",
+    "PPA", "PCL", "PES", "PMMA", "POM", "PPE", "PS", "PSU", "TPI", "SBS",
+];
+
+/// Main region keys
+const OPT_META_MAIN_OFFSET: u64 = 0;
+const OPT_MATERIAL_TYPE: u64 = 9;
+const OPT_MATERIAL_NAME: u64 = 10;
+const OPT_BRAND_NAME: u64 = 11;
+const OPT_NOMINAL_WEIGHT: u64 = 16;
+const OPT_PRIMARY_COLOR: u64 = 19;
+const OPT_MATERIAL_ABBREVIATION: u64 = 52;
+
+fn decode_openprinttag(payload: &[u8]) -> Option<DecodedTagInfo> {
+    // The payload starts with the meta region; key 0 points at the main region.
+    // Without it, the main region directly follows the meta region (or the
+    // first map already is the main region).
+    let mut reader = Cbor::new(payload);
+    let first = reader.map()?;
+    let main = match first.uint(OPT_META_MAIN_OFFSET) {
+        Some(offset) if offset > 0 => Cbor::new(payload.get(offset as usize..)?).map()?,
+        _ if first.get(OPT_MATERIAL_TYPE).is_none() && first.get(OPT_MATERIAL_NAME).is_none() => {
+            reader.map().unwrap_or(first)
+        }
+        _ => first,
+    };
+
+    let material = main
+        .uint(OPT_MATERIAL_TYPE)
+        .and_then(|idx| OPENPRINTTAG_MATERIALS.get(idx as usize))
+        .map(|m| m.to_string())
+        .or_else(|| main.text(OPT_MATERIAL_ABBREVIATION).map(str::to_string))
+        .unwrap_or_default();
+
+    // The material name usually reads "PLA Galaxy Black"; the color is what is
+    // left without the material type
+    let color_name = main
+        .text(OPT_MATERIAL_NAME)
+        .map(|name| {
+            name.split_whitespace()
+                .filter(|word| material.is_empty() || !word.eq_ignore_ascii_case(&material))
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .unwrap_or_default();
+
+    let color_rgba = match main.bytes(OPT_PRIMARY_COLOR) {
+        Some(&[r, g, b]) => u32::from_be_bytes([r, g, b, 0xFF]),
+        Some(&[r, g, b, a]) => u32::from_be_bytes([r, g, b, a]),
+        _ => 0,
+    };
+
+    Some(with_color_fallback(DecodedTagInfo {
+        vendor: main.text(OPT_BRAND_NAME).unwrap_or("").to_string(),
+        material,
+        color_name,
+        color_rgba,
+        spool_weight: main.uint(OPT_NOMINAL_WEIGHT).map_or(0, |w| w.min(i32::MAX as u64) as i32),
+        tag_type_name: "OpenPrintTag".to_string(),
+        ..Default::default()
+    }))
+}
+
+fn encode_openprinttag(info: &DecodedTagInfo) -> Vec<u8> {
+    let material_idx = OPENPRINTTAG_MATERIALS
+        .iter()
+        .position(|m| m.eq_ignore_ascii_case(info.material.trim()));
+    // Decoders take the color name back out of "<material> <color>"
+    let material_name = format!("{} {}", info.material.trim(), info.color_name.trim());
+    let material_name = material_name.trim();
+
+    let mut main = Vec::new();
+    let mut entries = 0u64;
+    if let Some(idx) = material_idx {
+        cbor_uint(&mut main, 0, OPT_MATERIAL_TYPE);
+        cbor_uint(&mut main, 0, idx as u64);
+        entries += 1;
+    }
+    if !material_name.is_empty() {
+        cbor_uint(&mut main, 0, OPT_MATERIAL_NAME);
+        cbor_text(&mut main, material_name);
+        entries += 1;
+    }
+    if !info.vendor.is_empty() {
+        cbor_uint(&mut main, 0, OPT_BRAND_NAME);
+        cbor_text(&mut main, &info.vendor);
+        entries += 1;
+    }
+    if info.spool_weight > 0 {
+        cbor_uint(&mut main, 0, OPT_NOMINAL_WEIGHT);
+        cbor_uint(&mut main, 0, info.spool_weight as u64);
+        entries += 1;
+    }
+    if info.color_rgba != 0 {
+        cbor_uint(&mut main, 0, OPT_PRIMARY_COLOR);
+        cbor_uint(&mut main, 2, 4);
+        main.extend_from_slice(&info.color_rgba.to_be_bytes());
+        entries += 1;
+    }
+    if material_idx.is_none() && !info.material.trim().is_empty() {
+        cbor_uint(&mut main, 0, OPT_MATERIAL_ABBREVIATION);
+        cbor_text(&mut main, info.material.trim());
+        entries += 1;
+    }
+
+    // Meta region {0: offset of the main region}, which follows right after it
+    let mut out = Vec::with_capacity(main.len() + 8);
+    cbor_uint(&mut out, 5, 1);
+    cbor_uint(&mut out, 0, OPT_META_MAIN_OFFSET);
+    cbor_uint(&mut out, 0, 3);
+    cbor_uint(&mut out, 5, entries);
+    out.extend_from_slice(&main);
+    out
+}
+
+/// Write a CBOR item header (major type and argument)
+fn cbor_uint(out: &mut Vec<u8>, major: u8, value: u64) {
+    let major = major << 5;
+    match value {
+        0..=23 => out.push(major | value as u8),
+        24..=0xFF => out.extend_from_slice(&[major | 24, value as u8]),
+        0x100..=0xFFFF => {
+            out.push(major | 25);
+            out.extend_from_slice(&(value as u16).to_be_bytes());
+        }
+        0x1_0000..=0xFFFF_FFFF => {
+            out.push(major | 26);
+            out.extend_from_slice(&(value as u32).to_be_bytes());
+        }
+        _ => {
+            out.push(major | 27);
+            out.extend_from_slice(&value.to_be_bytes());
+        }
+    }
+}
+
+fn cbor_text(out: &mut Vec<u8>, text: &str) {
+    cbor_uint(out, 3, text.len() as u64);
+    out.extend_from_slice(text.as_bytes());
+}
+
+/// Nesting limit when skipping CBOR containers
+const CBOR_MAX_DEPTH: u8 = 8;
+
+/// CBOR data item, as far as the decoders need it
+#[derive(Debug, Clone, Copy)]
+enum CborValue<'a> {
+    Uint(u64),
+    Bytes(&'a [u8]),
+    Text(&'a str),
+    /// Negative integers, floats, simple values and containers
+    Other,
+}
+
+/// CBOR map with unsigned integer keys (other keys are dropped)
+struct CborMap<'a>(Vec<(u64, CborValue<'a>)>);
+
+impl<'a> CborMap<'a> {
+    fn get(&self, key: u64) -> Option<CborValue<'a>> {
+        self.0.iter().find(|(k, _)| *k == key).map(|(_, v)| *v)
+    }
+
+    fn uint(&self, key: u64) -> Option<u64> {
+        match self.get(key)? {
+            CborValue::Uint(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    fn text(&self, key: u64) -> Option<&'a str> {
+        match self.get(key)? {
+            CborValue::Text(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn bytes(&self, key: u64) -> Option<&'a [u8]> {
+        match self.get(key)? {
+            CborValue::Bytes(b) => Some(b),
+            _ => None,
+        }
+    }
+}
+
+/// Minimal CBOR (RFC 8949) reader
+struct Cbor<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cbor<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn byte(&mut self) -> Option<u8> {
+        let b = *self.data.get(self.pos)?;
+        self.pos += 1;
+        Some(b)
+    }
+
+    fn slice(&mut self, len: u64) -> Option<&'a [u8]> {
+        let end = self.pos.checked_add(usize::try_from(len).ok()?)?;
+        let slice = self.data.get(self.pos..end)?;
+        self.pos = end;
+        Some(slice)
+    }
+
+    fn at_break(&self) -> bool {
+        self.data.get(self.pos) == Some(&0xFF)
+    }
+
+    /// Read an item header: (major type, argument), argument None for indefinite length
+    fn header(&mut self) -> Option<(u8, Option<u64>)> {
+        let initial = self.byte()?;
+        let major = initial >> 5;
+        let arg = match initial & 0x1F {
+            n @ 0..=23 => Some(n as u64),
+            24 => Some(self.byte()? as u64),
+            25 => Some(u16::from_be_bytes(self.slice(2)?.try_into().ok()?) as u64),
+            26 => Some(u32::from_be_bytes(self.slice(4)?.try_into().ok()?) as u64),
+            27 => Some(u64::from_be_bytes(self.slice(8)?.try_into().ok()?)),
+            31 if major >= 2 && major != 6 => None,
+            _ => return None,
+        };
+        Some((major, arg))
+    }
+
+    /// Read the next item, skipping over the contents of containers
+    fn item(&mut self, depth: u8) -> Option<CborValue<'a>> {
+        if depth > CBOR_MAX_DEPTH {
+            return None;
+        }
+        let (major, arg) = self.header()?;
+        match (major, arg) {
+            (0, Some(v)) => Some(CborValue::Uint(v)),
+            (1, Some(_)) => Some(CborValue::Other),
+            (2, Some(len)) => Some(CborValue::Bytes(self.slice(len)?)),
+            (3, Some(len)) => std::str::from_utf8(self.slice(len)?).ok().map(CborValue::Text),
+            (2 | 3, None) => {
+                // Indefinite-length string: definite chunks until break
+                while !self.at_break() {
+                    self.item(depth + 1)?;
+                }
+                self.pos += 1;
+                Some(CborValue::Other)
+            }
+            (4 | 5, len) => {
+                let per_entry = if major == 5 { 2 } else { 1 };
+                match len {
+                    Some(len) => {
+                        for _ in 0..len.checked_mul(per_entry)? {
+                            self.item(depth + 1)?;
+                        }
+                    }
+                    None => {
+                        while !self.at_break() {
+                            self.item(depth + 1)?;
+                        }
+                        self.pos += 1;
+                    }
+                }
+                Some(CborValue::Other)
+            }
+            // Tagged item: the tag does not change the value for our purposes
+            (6, Some(_)) => self.item(depth + 1),
+            (7, Some(_)) => Some(CborValue::Other),
+            _ => None,
+        }
+    }
+
+    /// Read a map, keeping entries with unsigned integer keys
+    fn map(&mut self) -> Option<CborMap<'a>> {
+        let (major, len) = self.header()?;
+        if major != 5 {
+            return None;
+        }
+
+        let mut entries = Vec::new();
+        let mut remaining = len;
+        loop {
+            match remaining {
+                Some(0) => break,
+                Some(ref mut n) => *n -= 1,
+                None if self.at_break() => {
+                    self.pos += 1;
+                    break;
+                }
+                None => {}
+            }
+            let key = self.item(1)?;
+            let value = self.item(1)?;
+            if let CborValue::Uint(key) = key {
+                entries.push((key, value));
+            }
+        }
+        Some(CborMap(entries))
+    }
+}
+
+// ============================================================================
+// OpenTag3D
+// ============================================================================
+
+/// Core region layout
+const OT3D_MATERIAL: (usize, usize) = (0x02, 5);
+const OT3D_MODIFIERS: (usize, usize) = (0x07, 5);
+const OT3D_MANUFACTURER: (usize, usize) = (0x1B, 16);
+const OT3D_COLOR_NAME: (usize, usize) = (0x2B, 32);
+const OT3D_COLOR_PRIMARY: usize = 0x4B;
+const OT3D_WEIGHT: usize = 0x5E;
+const OT3D_CORE_SIZE: usize = 0x66;
+
+fn decode_opentag3d(payload: &[u8]) -> Option<DecodedTagInfo> {
+    if payload.len() < OT3D_CORE_SIZE {
+        return None;
+    }
+
+    let field = |(offset, len): (usize, usize)| {
+        let raw = &payload[offset..offset + len];
+        let end = raw.iter().position(|&b| b == 0).unwrap_or(len);
+        std::str::from_utf8(&raw[..end]).map(|s| s.trim().to_string()).unwrap_or_default()
+    };
+
+    let color = &payload[OT3D_COLOR_PRIMARY..OT3D_COLOR_PRIMARY + 4];
+    let weight = u16::from_be_bytes([payload[OT3D_WEIGHT], payload[OT3D_WEIGHT + 1]]);
+
+    Some(with_color_fallback(DecodedTagInfo {
+        vendor: field(OT3D_MANUFACTURER),
+        material: field(OT3D_MATERIAL),
+        material_subtype: field(OT3D_MODIFIERS),
+        color_name: field(OT3D_COLOR_NAME),
+        color_rgba: u32::from_be_bytes([color[0], color[1], color[2], color[3]]),
+        spool_weight: weight as i32,
+        tag_type_name: "OpenTag3D".to_string(),
+        ..Default::default()
+    }))
+}
+
+// ============================================================================
+// SpoolEase
+// ============================================================================
+
+/// Decode a SpoolEase URL (https://info.filament3d.org/V2/?TG=...&M=PLA&...)
+fn decode_spoolease(url: &str) -> Option<DecodedTagInfo> {
+    if !url.contains(SPOOLEASE_HOST) {
+        return None;
+    }
+    let (_, query) = url.split_once('?')?;
+
+    let mut info = DecodedTagInfo {
+        tag_type_name: "SpoolEase".to_string(),
+        ..Default::default()
+    };
+
+    for pair in query.split('&') {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let value = percent_decode(value);
+        match key {
+            "B" => info.vendor = value,
+            "M" => info.material = value,
+            "MS" => info.material_subtype = value,
+            "CN" => info.color_name = value,
+            "CC" => info.color_rgba = parse_hex_rgba(&value).unwrap_or(0),
+            "WL" => info.spool_weight = value.parse().unwrap_or(0),
+            _ => {}
+        }
+    }
+
+    Some(with_color_fallback(info))
+}
+
+/// Decode a URL query value ('+' is a space)
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' => {
+                let hex = bytes.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok());
+                match hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                    Some(b) => {
+                        out.push(b);
+                        i += 2;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            b => out.push(b),
+        }
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // NTAG215 user memory (page 4 onwards) as written by the respective apps,
+    // trailing zero pages dropped. Most start with a Lock Control TLV.
+
+    // OpenSpool: Elegoo PETG, color_hex 1A2B3C
+    const OPENSPOOL: &[u8] = &[
+        0x01, 0x03, 0xa0, 0x0c, 0x34, 0x03, 0x91, 0xd2, 0x10, 0x7e, 0x61, 0x70, 0x70, 0x6c, 0x69, 0x63,
+        0x61, 0x74, 0x69, 0x6f, 0x6e, 0x2f, 0x6a, 0x73, 0x6f, 0x6e, 0x7b, 0x22, 0x70, 0x72, 0x6f, 0x74,
+        0x6f, 0x63, 0x6f, 0x6c, 0x22, 0x3a, 0x22, 0x6f, 0x70, 0x65, 0x6e, 0x73, 0x70, 0x6f, 0x6f, 0x6c,
+        0x22, 0x2c, 0x22, 0x76, 0x65, 0x72, 0x73, 0x69, 0x6f, 0x6e, 0x22, 0x3a, 0x22, 0x31, 0x2e, 0x30,
+        0x22, 0x2c, 0x22, 0x74, 0x79, 0x70, 0x65, 0x22, 0x3a, 0x22, 0x50, 0x45, 0x54, 0x47, 0x22, 0x2c,
+        0x22, 0x63, 0x6f, 0x6c, 0x6f, 0x72, 0x5f, 0x68, 0x65, 0x78, 0x22, 0x3a, 0x22, 0x31, 0x41, 0x32,
+        0x42, 0x33, 0x43, 0x22, 0x2c, 0x22, 0x62, 0x72, 0x61, 0x6e, 0x64, 0x22, 0x3a, 0x22, 0x45, 0x6c,
+        0x65, 0x67, 0x6f, 0x6f, 0x22, 0x2c, 0x22, 0x6d, 0x69, 0x6e, 0x5f, 0x74, 0x65, 0x6d, 0x70, 0x22,
+        0x3a, 0x22, 0x32, 0x33, 0x30, 0x22, 0x2c, 0x22, 0x6d, 0x61, 0x78, 0x5f, 0x74, 0x65, 0x6d, 0x70,
+        0x22, 0x3a, 0x22, 0x32, 0x35, 0x30, 0x22, 0x7d, 0xfe,
+    ];
+
+    // OpenPrintTag: meta region {0: 3}, main region right after it
+    const OPENPRINTTAG: &[u8] = &[
+        0x01, 0x03, 0xa0, 0x0c, 0x34, 0x03, 0x54, 0xd2, 0x1c, 0x35, 0x61, 0x70, 0x70, 0x6c, 0x69, 0x63,
+        0x61, 0x74, 0x69, 0x6f, 0x6e, 0x2f, 0x76, 0x6e, 0x64, 0x2e, 0x6f, 0x70, 0x65, 0x6e, 0x70, 0x72,
+        0x69, 0x6e, 0x74, 0x74, 0x61, 0x67, 0xa1, 0x00, 0x03, 0xa7, 0x09, 0x00, 0x0a, 0x70, 0x50, 0x4c,
+        0x41, 0x20, 0x47, 0x61, 0x6c, 0x61, 0x78, 0x79, 0x20, 0x42, 0x6c, 0x61, 0x63, 0x6b, 0x0b, 0x69,
+        0x50, 0x72, 0x75, 0x73, 0x61, 0x6d, 0x65, 0x6e, 0x74, 0x10, 0x19, 0x03, 0xe8, 0x12, 0x18, 0xc1,
+        0x13, 0x43, 0x20, 0x20, 0x20, 0x18, 0x34, 0x63, 0x50, 0x4c, 0x41, 0xfe,
+    ];
+
+    // OpenPrintTag: meta region without a main region offset ({2: 5})
+    const OPENPRINTTAG_NO_OFFSET: &[u8] = &[
+        0x01, 0x03, 0xa0, 0x0c, 0x34, 0x03, 0x54, 0xd2, 0x1c, 0x35, 0x61, 0x70, 0x70, 0x6c, 0x69, 0x63,
+        0x61, 0x74, 0x69, 0x6f, 0x6e, 0x2f, 0x76, 0x6e, 0x64, 0x2e, 0x6f, 0x70, 0x65, 0x6e, 0x70, 0x72,
+        0x69, 0x6e, 0x74, 0x74, 0x61, 0x67, 0xa1, 0x02, 0x05, 0xa7, 0x09, 0x00, 0x0a, 0x70, 0x50, 0x4c,
+        0x41, 0x20, 0x47, 0x61, 0x6c, 0x61, 0x78, 0x79, 0x20, 0x42, 0x6c, 0x61, 0x63, 0x6b, 0x0b, 0x69,
+        0x50, 0x72, 0x75, 0x73, 0x61, 0x6d, 0x65, 0x6e, 0x74, 0x10, 0x19, 0x03, 0xe8, 0x12, 0x18, 0xc1,
+        0x13, 0x43, 0x20, 0x20, 0x20, 0x18, 0x34, 0x63, 0x50, 0x4c, 0x41, 0xfe,
+    ];
+
+    // OpenPrintTag in a long record behind a 3-byte TLV length; the main
+    // region uses an indefinite-length map, an indefinite array, a tagged
+    // string, a negative integer and a float
+    const OPENPRINTTAG_INDEFINITE: &[u8] = &[
+        0x03, 0xff, 0x00, 0x53, 0xc2, 0x1c, 0x00, 0x00, 0x00, 0x31, 0x61, 0x70, 0x70, 0x6c, 0x69, 0x63,
+        0x61, 0x74, 0x69, 0x6f, 0x6e, 0x2f, 0x76, 0x6e, 0x64, 0x2e, 0x6f, 0x70, 0x65, 0x6e, 0x70, 0x72,
+        0x69, 0x6e, 0x74, 0x74, 0x61, 0x67, 0xa1, 0x00, 0x03, 0xbf, 0x01, 0x9f, 0x01, 0x02, 0xff, 0x09,
+        0x01, 0x0a, 0x6a, 0x50, 0x45, 0x54, 0x47, 0x20, 0x47, 0x72, 0x65, 0x65, 0x6e, 0x0b, 0xc0, 0x64,
+        0x41, 0x43, 0x4d, 0x45, 0x03, 0x20, 0x04, 0xfb, 0x3f, 0xf8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x13, 0x44, 0x01, 0x02, 0x03, 0x04, 0xff, 0xfe,
+    ];
+
+    // OpenTag3D core region: Polymaker PLA CF "Jet Black", 750 g
+    const OPENTAG3D: &[u8] = &[
+        0x03, 0x7e, 0xd2, 0x15, 0x66, 0x61, 0x70, 0x70, 0x6c, 0x69, 0x63, 0x61, 0x74, 0x69, 0x6f, 0x6e,
+        0x2f, 0x6f, 0x70, 0x65, 0x6e, 0x74, 0x61, 0x67, 0x33, 0x64, 0x00, 0x14, 0x50, 0x4c, 0x41, 0x00,
+        0x00, 0x43, 0x46, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x50, 0x6f, 0x6c, 0x79, 0x6d, 0x61, 0x6b, 0x65, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x4a, 0x65, 0x74, 0x20, 0x42, 0x6c, 0x61, 0x63, 0x6b, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x10, 0x10, 0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0xee, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0xfe,
+    ];
+
+    // SpoolEase URI record (https:// prefix code)
+    const SPOOLEASE: &[u8] = &[
+        0x01, 0x03, 0xa0, 0x0c, 0x34, 0x03, 0x68, 0xd1, 0x01, 0x64, 0x55, 0x04, 0x69, 0x6e, 0x66, 0x6f,
+        0x2e, 0x66, 0x69, 0x6c, 0x61, 0x6d, 0x65, 0x6e, 0x74, 0x33, 0x64, 0x2e, 0x6f, 0x72, 0x67, 0x2f,
+        0x56, 0x32, 0x2f, 0x3f, 0x54, 0x47, 0x3d, 0x42, 0x41, 0x62, 0x43, 0x26, 0x49, 0x44, 0x3d, 0x34,
+        0x32, 0x26, 0x4d, 0x3d, 0x50, 0x4c, 0x41, 0x26, 0x4d, 0x53, 0x3d, 0x53, 0x69, 0x6c, 0x6b, 0x25,
+        0x32, 0x42, 0x26, 0x43, 0x43, 0x3d, 0x46, 0x46, 0x38, 0x38, 0x30, 0x30, 0x46, 0x46, 0x26, 0x43,
+        0x4e, 0x3d, 0x53, 0x75, 0x6e, 0x73, 0x65, 0x74, 0x2b, 0x4f, 0x72, 0x61, 0x6e, 0x67, 0x65, 0x26,
+        0x42, 0x3d, 0x53, 0x75, 0x6e, 0x6c, 0x75, 0x26, 0x57, 0x4c, 0x3d, 0x31, 0x30, 0x30, 0x30, 0xfe,
+    ];
+
+    // Text record "hello" followed by the OpenSpool record
+    const TEXT_THEN_OPENSPOOL: &[u8] = &[
+        0x01, 0x03, 0xa0, 0x0c, 0x34, 0x03, 0x9d, 0x91, 0x01, 0x08, 0x54, 0x02, 0x65, 0x6e, 0x68, 0x65,
+        0x6c, 0x6c, 0x6f, 0x52, 0x10, 0x7e, 0x61, 0x70, 0x70, 0x6c, 0x69, 0x63, 0x61, 0x74, 0x69, 0x6f,
+        0x6e, 0x2f, 0x6a, 0x73, 0x6f, 0x6e, 0x7b, 0x22, 0x70, 0x72, 0x6f, 0x74, 0x6f, 0x63, 0x6f, 0x6c,
+        0x22, 0x3a, 0x22, 0x6f, 0x70, 0x65, 0x6e, 0x73, 0x70, 0x6f, 0x6f, 0x6c, 0x22, 0x2c, 0x22, 0x76,
+        0x65, 0x72, 0x73, 0x69, 0x6f, 0x6e, 0x22, 0x3a, 0x22, 0x31, 0x2e, 0x30, 0x22, 0x2c, 0x22, 0x74,
+        0x79, 0x70, 0x65, 0x22, 0x3a, 0x22, 0x50, 0x45, 0x54, 0x47, 0x22, 0x2c, 0x22, 0x63, 0x6f, 0x6c,
+        0x6f, 0x72, 0x5f, 0x68, 0x65, 0x78, 0x22, 0x3a, 0x22, 0x31, 0x41, 0x32, 0x42, 0x33, 0x43, 0x22,
+        0x2c, 0x22, 0x62, 0x72, 0x61, 0x6e, 0x64, 0x22, 0x3a, 0x22, 0x45, 0x6c, 0x65, 0x67, 0x6f, 0x6f,
+        0x22, 0x2c, 0x22, 0x6d, 0x69, 0x6e, 0x5f, 0x74, 0x65, 0x6d, 0x70, 0x22, 0x3a, 0x22, 0x32, 0x33,
+        0x30, 0x22, 0x2c, 0x22, 0x6d, 0x61, 0x78, 0x5f, 0x74, 0x65, 0x6d, 0x70, 0x22, 0x3a, 0x22, 0x32,
+        0x35, 0x30, 0x22, 0x7d, 0xfe,
+    ];
+
+    // OpenSpool dump cut off inside the record
+    const TRUNCATED: &[u8] = &[
+        0x01, 0x03, 0xa0, 0x0c, 0x34, 0x03, 0x91, 0xd2, 0x10, 0x7e, 0x61, 0x70, 0x70, 0x6c, 0x69, 0x63,
+        0x61, 0x74, 0x69, 0x6f, 0x6e, 0x2f, 0x6a, 0x73, 0x6f, 0x6e, 0x7b, 0x22, 0x70, 0x72, 0x6f, 0x74,
+        0x6f, 0x63, 0x6f, 0x6c, 0x22, 0x3a, 0x22, 0x6f,
+    ];
+
+    fn mime_dump(mime_type: &str, payload: &[u8]) -> Vec<u8> {
+        ndef::wrap_message(&ndef::mime_record(mime_type, payload))
+    }
+
+    #[test]
+    fn decodes_openspool() {
+        let info = decode_ntag(OPENSPOOL).unwrap();
+        assert_eq!(info.tag_type_name, "OpenSpool");
+        assert_eq!(info.vendor, "Elegoo");
+        assert_eq!(info.material, "PETG");
+        assert_eq!(info.color_rgba, 0x1A2B3CFF);
+        assert_eq!(info.color_name, format_color_name(0x1A2B3CFF));
+    }
+
+    #[test]
+    fn decodes_openprinttag_with_main_region_offset() {
+        let info = decode_ntag(OPENPRINTTAG).unwrap();
+        assert_eq!(info.tag_type_name, "OpenPrintTag");
+        assert_eq!(info.vendor, "Prusament");
+        assert_eq!(info.material, "PLA");
+        assert_eq!(info.color_name, "Galaxy Black");
+        assert_eq!(info.color_rgba, 0x202020FF);
+        assert_eq!(info.spool_weight, 1000);
+    }
+
+    #[test]
+    fn decodes_openprinttag_without_main_region_offset() {
+        let info = decode_ntag(OPENPRINTTAG_NO_OFFSET).unwrap();
+        assert_eq!(info.vendor, "Prusament");
+        assert_eq!(info.material, "PLA");
+        assert_eq!(info.color_name, "Galaxy Black");
+        assert_eq!(info.spool_weight, 1000);
+    }
+
+    #[test]
+    fn decodes_openprinttag_main_region_only() {
+        let main = [0xA2, 0x09, 0x03, 0x0B, 0x64, b'A', b'C', b'M', b'E'];
+        let info = decode_ntag(&mime_dump(OPENPRINTTAG_MIME, &main)).unwrap();
+        assert_eq!(info.vendor, "ACME");
+        assert_eq!(info.material, "ABS");
+    }
+
+    #[test]
+    fn decodes_openprinttag_indefinite_lengths() {
+        let info = decode_ntag(OPENPRINTTAG_INDEFINITE).unwrap();
+        assert_eq!(info.vendor, "ACME");
+        assert_eq!(info.material, "PETG");
+        assert_eq!(info.color_name, "Green");
+        assert_eq!(info.color_rgba, 0x01020304);
+    }
+
+    #[test]
+    fn decodes_opentag3d() {
+        let info = decode_ntag(OPENTAG3D).unwrap();
+        assert_eq!(info.tag_type_name, "OpenTag3D");
+        assert_eq!(info.vendor, "Polymake");
+        assert_eq!(info.material, "PLA");
+        assert_eq!(info.material_subtype, "CF");
+        assert_eq!(info.color_name, "Jet Black");
+        assert_eq!(info.color_rgba, 0x101010FF);
+        assert_eq!(info.spool_weight, 750);
+    }
+
+    #[test]
+    fn decodes_spoolease() {
+        let info = decode_ntag(SPOOLEASE).unwrap();
+        assert_eq!(info.tag_type_name, "SpoolEase");
+        assert_eq!(info.vendor, "Sunlu");
+        assert_eq!(info.material, "PLA");
+        assert_eq!(info.material_subtype, "Silk+");
+        assert_eq!(info.color_name, "Sunset Orange");
+        assert_eq!(info.color_rgba, 0xFF8800FF);
+        assert_eq!(info.spool_weight, 1000);
+    }
+
+    #[test]
+    fn skips_unknown_records() {
+        let info = decode_ntag(TEXT_THEN_OPENSPOOL).unwrap();
+        assert_eq!(info.tag_type_name, "OpenSpool");
+        assert_eq!(info.vendor, "Elegoo");
+    }
+
+    #[test]
+    fn rejects_malformed_tlv() {
+        assert_eq!(decode_ntag(TRUNCATED).unwrap_err(), "NDEF message truncated");
+        assert_eq!(decode_ntag(&[0u8; 64]).unwrap_err(), "No NDEF message");
+        assert_eq!(decode_ntag(&[0x01, 0x03, 0xA0, 0x0C, 0x34, 0x03]).unwrap_err(), "TLV length truncated");
+        assert_eq!(decode_ntag(&[0x03, 0xFF, 0x01]).unwrap_err(), "TLV length truncated");
+        assert_eq!(decode_ntag(&[0x03, 0x00, 0xFE]).unwrap_err(), "No known spool format");
+    }
+
+    #[test]
+    fn rejects_malformed_records() {
+        // Payload length beyond the message
+        assert_eq!(decode_ntag(&[0x03, 0x05, 0xD2, 0x01, 0x09, b'x', 0x00, 0xFE]).unwrap_err(), "Record truncated");
+        // Long record with its length cut off
+        assert_eq!(decode_ntag(&[0x03, 0x04, 0xC2, 0x01, 0x00, 0x00, 0xFE]).unwrap_err(), "Record header truncated");
+        // Chunked record
+        assert_eq!(
+            decode_ntag(&[0x03, 0x04, 0xB2, 0x01, 0x00, b'x', 0xFE]).unwrap_err(),
+            "Chunked NDEF records not supported"
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_cbor() {
+        let cases: [&[u8]; 7] = [
+            // Empty payload
+            &[],
+            // Not a map
+            &[0x83, 0x01, 0x02, 0x03],
+            // Map announcing more entries than present
+            &[0xA2, 0x09, 0x00],
+            // Text value running past the payload
+            &[0xA1, 0x0A, 0x6A, b'P', b'L', b'A'],
+            // Invalid UTF-8 text
+            &[0xA1, 0x0A, 0x62, 0xC3, 0x28],
+            // Reserved additional information
+            &[0xA1, 0x09, 0x1C],
+            // Main region offset beyond the payload
+            &[0xA1, 0x00, 0x18, 0x40],
+        ];
+        for payload in cases {
+            assert!(decode_ntag(&mime_dump(OPENPRINTTAG_MIME, payload)).is_err(), "{:02X?}", payload);
+        }
+
+        // Nesting deeper than the limit
+        let mut nested = vec![0xA1, 0x01];
+        nested.extend([0x81; 16]);
+        nested.push(0x00);
+        assert!(decode_ntag(&mime_dump(OPENPRINTTAG_MIME, &nested)).is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_openspool_and_opentag3d() {
+        assert!(decode_ntag(&mime_dump(OPENSPOOL_MIME, b"{\"protocol\":\"openspool\"")).is_err());
+        assert!(decode_ntag(&mime_dump(OPENSPOOL_MIME, b"{\"protocol\":\"other\",\"type\":\"PLA\"}")).is_err());
+        assert!(decode_ntag(&mime_dump(OPENTAG3D_MIME, &[0u8; OT3D_CORE_SIZE - 1])).is_err());
+    }
+
+    #[test]
+    fn parses_hex_colors() {
+        assert_eq!(parse_hex_rgba("1a2b3c"), Some(0x1A2B3CFF));
+        assert_eq!(parse_hex_rgba("#1A2B3C80"), Some(0x1A2B3C80));
+        assert_eq!(parse_hex_rgba(" FF8800 "), Some(0xFF8800FF));
+        assert_eq!(parse_hex_rgba("+FFFFF"), None);
+        assert_eq!(parse_hex_rgba("-FFFFF"), None);
+        assert_eq!(parse_hex_rgba("+FFFFFFF"), None);
+        assert_eq!(parse_hex_rgba("12345G"), None);
+        assert_eq!(parse_hex_rgba("FFF"), None);
+        assert_eq!(parse_hex_rgba(""), None);
+    }
+
+    fn sample() -> DecodedTagInfo {
+        DecodedTagInfo {
+            vendor: "Polymaker".to_string(),
+            material: "PETG".to_string(),
+            color_name: "Galaxy Black".to_string(),
+            color_rgba: 0x112233FF,
+            spool_weight: 1000,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn openspool_round_trip() {
+        let data = encode_ntag(TagWriteFormat::OpenSpool, &sample());
+        assert_eq!(data.len() % ndef::PAGE_SIZE, 0);
+
+        // OpenSpool has no color name or weight; the name comes from the color
+        let info = decode_ntag(&data).unwrap();
+        assert_eq!(info.tag_type_name, "OpenSpool");
+        assert_eq!(info.vendor, "Polymaker");
+        assert_eq!(info.material, "PETG");
+        assert_eq!(info.color_rgba, 0x112233FF);
+        assert_eq!(info.color_name, format_color_name(0x112233FF));
+    }
+
+    #[test]
+    fn openprinttag_round_trip() {
+        let data = encode_ntag(TagWriteFormat::OpenPrintTag, &sample());
+        assert_eq!(data.len() % ndef::PAGE_SIZE, 0);
+
+        let info = decode_ntag(&data).unwrap();
+        assert_eq!(info.tag_type_name, "OpenPrintTag");
+        assert_eq!(info.vendor, "Polymaker");
+        assert_eq!(info.material, "PETG");
+        assert_eq!(info.color_name, "Galaxy Black");
+        assert_eq!(info.color_rgba, 0x112233FF);
+        assert_eq!(info.spool_weight, 1000);
+    }
+
+    #[test]
+    fn openprinttag_round_trip_unlisted_material() {
+        let info = DecodedTagInfo { vendor: "Generic".to_string(), material: "PLA-CF".to_string(), ..Default::default() };
+        let decoded = decode_ntag(&encode_ntag(TagWriteFormat::OpenPrintTag, &info)).unwrap();
+        assert_eq!(decoded.vendor, "Generic");
+        assert_eq!(decoded.material, "PLA-CF");
+        assert_eq!(decoded.color_rgba, 0);
+        assert_eq!(decoded.spool_weight, 0);
+    }
+
+    #[test]
+    fn round_trip_long_record() {
+        // Over 255 bytes: long record header and 3-byte TLV length
+        let info = DecodedTagInfo { vendor: "V".repeat(300), ..sample() };
+        for format in [TagWriteFormat::OpenSpool, TagWriteFormat::OpenPrintTag] {
+            let data = encode_ntag(format, &info);
+            assert_eq!(data[..2], [0x03, 0xFF]);
+            assert_eq!(decode_ntag(&data).unwrap().vendor, info.vendor, "{}", format.name());
+        }
+    }
+}
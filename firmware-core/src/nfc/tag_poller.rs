@@ -0,0 +1,670 @@
+//! Non-blocking tag polling over any [`NfcReader`]
+//!
+//! An explicit state machine advanced one step per main-loop tick. A reader
+//! operation either completes or returns `Pending` and is repeated on the next
+//! tick, so with the Pico bridge the shared I2C bus is only held for a single
+//! short transfer and the scale and UI keep running while the Pico talks to
+//! the tag:
+//!
+//! Idle -> Scanning -> Reading -> Present
+//! Present -> Scanning (rescan) -> ... -> Removed -> Idle
+//!
+//! A tag write queued by the UI runs from Present: check the capability
+//! container, write the pages, and read them back to verify.
+
+use super::i2c_bridge::{self, DecodedTagInfo};
+use super::mifare_classic::BAMBU_BLOCKS;
+use super::ntag::{self, NTAG_CC_PAGE, NTAG_DATA_PAGES, NTAG_FIRST_DATA_PAGE, PAGE_SIZE};
+use super::reader::{DetectedTag, NfcError, NfcReader, TagKind};
+use super::tag_formats;
+use log::{info, warn};
+
+/// Time between scans, with or without a tag on the reader
+pub const SCAN_INTERVAL_MS: u64 = 500;
+
+/// Something the caller has to act on (backend updates, UI state)
+#[derive(Debug, Clone)]
+pub enum NfcEvent {
+    /// A tag (or a different tag) was placed on the reader
+    TagDetected { tag: DetectedTag },
+    /// The tag data was read and decoded
+    TagDecoded { uid_hex: String, info: DecodedTagInfo },
+    /// The tag was taken off the reader
+    TagRemoved,
+    /// A queued write finished; on success, what was read back from the tag
+    WriteFinished { uid_hex: String, result: Result<DecodedTagInfo, String> },
+}
+
+/// A tag write in progress: the encoded NTAG data and the next step
+#[derive(Debug, Clone)]
+struct WriteJob {
+    tag: DetectedTag,
+    data: Vec<u8>,
+    step: WriteStep,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WriteStep {
+    /// Read the CC: is the tag writable, and how big is it
+    Check,
+    /// Write the data from the first user page
+    Write,
+    /// Read the data back to compare with what was written
+    Verify,
+}
+
+/// Outcome of running a write step once
+enum StepOutcome {
+    Pending,
+    Next(WriteStep),
+    Verified(DecodedTagInfo),
+    Failed(String),
+}
+
+impl From<NfcError> for StepOutcome {
+    fn from(e: NfcError) -> Self {
+        match e {
+            NfcError::Pending => StepOutcome::Pending,
+            NfcError::Failed(message) => StepOutcome::Failed(message.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Phase {
+    /// No tag; next scan due at `next_scan_ms`
+    Idle { next_scan_ms: u64 },
+    /// Waiting for the reader to finish looking for a tag
+    Scanning,
+    /// Waiting for the reader to finish reading the present tag
+    Reading,
+    /// Tag on the reader; rescanned at `next_scan_ms` to notice removal
+    Present { next_scan_ms: u64 },
+    /// The last scan found no tag where there was one
+    Removed,
+    /// A write step in progress
+    Writing(WriteJob),
+}
+
+/// Tag presence and write state machine
+pub struct TagPoller {
+    phase: Phase,
+    /// The tag on the reader (None when no tag)
+    present: Option<DetectedTag>,
+    /// Tag data read (or given up on) for the present tag
+    data_read: bool,
+    /// Write queued by the UI, started from Present
+    queued_write: Option<WriteJob>,
+}
+
+impl Default for TagPoller {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TagPoller {
+    pub const fn new() -> Self {
+        Self {
+            phase: Phase::Idle { next_scan_ms: 0 },
+            present: None,
+            data_read: false,
+            queued_write: None,
+        }
+    }
+
+    /// Queue writing `data` (NTAG user memory from page 4) to `tag`. It starts
+    /// once the current operation is done; the result comes back as
+    /// `NfcEvent::WriteFinished`.
+    pub fn queue_write(&mut self, tag: DetectedTag, data: Vec<u8>) {
+        self.queued_write = Some(WriteJob { tag, data, step: WriteStep::Check });
+    }
+
+    /// Drop a queued write that has not started yet
+    pub fn cancel_write(&mut self) {
+        self.queued_write = None;
+    }
+
+    /// The tag on the reader
+    pub fn present(&self) -> Option<&DetectedTag> {
+        self.present.as_ref()
+    }
+
+    /// Advance one step. Runs or polls at most one reader operation (a write
+    /// moves on to its next step within the tick), never waits.
+    pub fn step<R: NfcReader + ?Sized>(&mut self, reader: &mut R, now_ms: u64) -> Vec<NfcEvent> {
+        let mut events = Vec::new();
+        let phase = std::mem::replace(&mut self.phase, Phase::Removed);
+
+        self.phase = match phase {
+            Phase::Idle { next_scan_ms } => {
+                if let Some(job) = self.queued_write.take() {
+                    events.push(removed_before_writing(job));
+                }
+                if now_ms >= next_scan_ms {
+                    self.scan(reader, now_ms, &mut events)
+                } else {
+                    Phase::Idle { next_scan_ms }
+                }
+            }
+
+            Phase::Present { next_scan_ms } => {
+                if let Some(job) = self.queued_write.take() {
+                    self.start_write_job(reader, job, now_ms, &mut events)
+                } else if now_ms >= next_scan_ms {
+                    self.scan(reader, now_ms, &mut events)
+                } else {
+                    Phase::Present { next_scan_ms }
+                }
+            }
+
+            Phase::Scanning => self.scan(reader, now_ms, &mut events),
+
+            Phase::Reading => self.read_data(reader, now_ms, &mut events),
+
+            Phase::Removed => {
+                info!("NFC TAG REMOVED");
+                self.present = None;
+                self.data_read = false;
+                if let Some(job) = self.queued_write.take() {
+                    events.push(removed_before_writing(job));
+                }
+                events.push(NfcEvent::TagRemoved);
+                Phase::Idle { next_scan_ms: now_ms + SCAN_INTERVAL_MS }
+            }
+
+            Phase::Writing(job) => self.write(reader, job, now_ms, &mut events),
+        };
+
+        events
+    }
+
+    /// Look for a tag; on a reader error stay put and retry after the interval
+    fn scan<R: NfcReader + ?Sized>(&mut self, reader: &mut R, now_ms: u64, events: &mut Vec<NfcEvent>) -> Phase {
+        match reader.detect(now_ms) {
+            Ok(Some(tag)) => self.tag_found(reader, tag, now_ms, events),
+            Ok(None) if self.present.is_some() => Phase::Removed,
+            Ok(None) => Phase::Idle { next_scan_ms: now_ms + SCAN_INTERVAL_MS },
+            Err(NfcError::Pending) => Phase::Scanning,
+            Err(NfcError::Failed(e)) => {
+                warn!("NFC scan error: {}", e);
+                self.settle(now_ms)
+            }
+        }
+    }
+
+    /// A scan found a tag: report it if it is new, and read it if not done yet
+    fn tag_found<R: NfcReader + ?Sized>(
+        &mut self,
+        reader: &mut R,
+        tag: DetectedTag,
+        now_ms: u64,
+        events: &mut Vec<NfcEvent>,
+    ) -> Phase {
+        if self.present.as_ref().map(|present| &present.uid) != Some(&tag.uid) {
+            // Log detection without full UID (security: avoid logging sensitive tag identifiers)
+            info!("NFC TAG DETECTED");
+            self.data_read = false;
+            events.push(NfcEvent::TagDetected { tag: tag.clone() });
+        }
+        // The kind may only be known once the reader has read the tag
+        self.present = Some(tag);
+
+        if self.data_read {
+            return Phase::Present { next_scan_ms: now_ms + SCAN_INTERVAL_MS };
+        }
+        self.read_data(reader, now_ms, events)
+    }
+
+    /// Read and decode the spool data of the present tag
+    fn read_data<R: NfcReader + ?Sized>(&mut self, reader: &mut R, now_ms: u64, events: &mut Vec<NfcEvent>) -> Phase {
+        let Some(tag) = self.present.clone() else {
+            return self.settle(now_ms);
+        };
+        let result = match tag.kind {
+            TagKind::Ntag => reader
+                .read_pages(&tag, NTAG_FIRST_DATA_PAGE, NTAG_DATA_PAGES, now_ms)
+                .map(|memory| decode_ntag(&memory)),
+            TagKind::MifareClassic1k | TagKind::MifareClassic4k => reader
+                .read_blocks(&tag, &BAMBU_BLOCKS, now_ms)
+                .map(|blocks| i2c_bridge::decode_bambu_tag(&blocks)),
+            // Nothing to read yet, retried after the next scan
+            TagKind::Unknown => return self.settle(now_ms),
+        };
+
+        match result {
+            Ok(info) => {
+                self.data_read = true;
+                events.push(NfcEvent::TagDecoded { uid_hex: tag.uid_hex(), info });
+                self.settle(now_ms)
+            }
+            Err(NfcError::Pending) => Phase::Reading,
+            Err(NfcError::Failed(e)) => {
+                warn!("Tag data read error: {}", e);
+                self.data_read = true; // Don't keep retrying on error
+                self.settle(now_ms)
+            }
+        }
+    }
+
+    /// Back to waiting for the next scan, keeping what is known about the tag
+    fn settle(&self, now_ms: u64) -> Phase {
+        let next_scan_ms = now_ms + SCAN_INTERVAL_MS;
+        if self.present.is_some() {
+            Phase::Present { next_scan_ms }
+        } else {
+            Phase::Idle { next_scan_ms }
+        }
+    }
+
+    fn start_write_job<R: NfcReader + ?Sized>(
+        &mut self,
+        reader: &mut R,
+        job: WriteJob,
+        now_ms: u64,
+        events: &mut Vec<NfcEvent>,
+    ) -> Phase {
+        if self.present.as_ref().map(|present| &present.uid) != Some(&job.tag.uid) {
+            let message = "A different tag is on the reader".to_string();
+            return self.write_failed(&job.tag, message, now_ms, events);
+        }
+        if !reader.can_write() {
+            let message = format!("{} cannot write tags", reader.name());
+            return self.write_failed(&job.tag, message, now_ms, events);
+        }
+        self.write(reader, job, now_ms, events)
+    }
+
+    /// Run the job's steps until one is pending or the job is done
+    fn write<R: NfcReader + ?Sized>(
+        &mut self,
+        reader: &mut R,
+        mut job: WriteJob,
+        now_ms: u64,
+        events: &mut Vec<NfcEvent>,
+    ) -> Phase {
+        loop {
+            match run_write_step(reader, &job, now_ms) {
+                StepOutcome::Pending => return Phase::Writing(job),
+                StepOutcome::Next(step) => job.step = step,
+                StepOutcome::Verified(info) => {
+                    info!("{} tag written and verified", info.tag_type_name);
+                    self.data_read = true;
+                    let uid_hex = job.tag.uid_hex();
+                    events.push(NfcEvent::WriteFinished { uid_hex, result: Ok(info) });
+                    return Phase::Present { next_scan_ms: now_ms + SCAN_INTERVAL_MS };
+                }
+                StepOutcome::Failed(message) => return self.write_failed(&job.tag, message, now_ms, events),
+            }
+        }
+    }
+
+    fn write_failed(&mut self, tag: &DetectedTag, message: String, now_ms: u64, events: &mut Vec<NfcEvent>) -> Phase {
+        warn!("Tag write failed: {}", message);
+        events.push(NfcEvent::WriteFinished { uid_hex: tag.uid_hex(), result: Err(message) });
+        // Rescan and re-read right away: the tag may have moved, or hold a partial write
+        self.data_read = false;
+        if self.present.is_some() {
+            Phase::Present { next_scan_ms: now_ms }
+        } else {
+            Phase::Idle { next_scan_ms: now_ms }
+        }
+    }
+}
+
+fn removed_before_writing(job: WriteJob) -> NfcEvent {
+    NfcEvent::WriteFinished { uid_hex: job.tag.uid_hex(), result: Err("Tag removed before writing".to_string()) }
+}
+
+/// Run the job's current step once
+fn run_write_step<R: NfcReader + ?Sized>(reader: &mut R, job: &WriteJob, now_ms: u64) -> StepOutcome {
+    match job.step {
+        WriteStep::Check => match reader.read_pages(&job.tag, NTAG_CC_PAGE, 1, now_ms) {
+            Ok(cc) => match check_capacity(&cc, job.data.len()) {
+                Ok(()) => StepOutcome::Next(WriteStep::Write),
+                Err(e) => StepOutcome::Failed(e),
+            },
+            Err(e) => e.into(),
+        },
+        WriteStep::Write => match reader.write_pages(&job.tag, NTAG_FIRST_DATA_PAGE, &job.data, now_ms) {
+            Ok(()) => StepOutcome::Next(WriteStep::Verify),
+            Err(e) => e.into(),
+        },
+        WriteStep::Verify => {
+            // Read at least the usual spool data area, so it decodes as a whole
+            let pages = job.data.len().div_ceil(PAGE_SIZE).max(NTAG_DATA_PAGES as usize);
+            let count = u8::try_from(pages).unwrap_or(u8::MAX);
+            match reader.read_pages(&job.tag, NTAG_FIRST_DATA_PAGE, count, now_ms) {
+                Ok(read_back) => match verify(&read_back, &job.data) {
+                    Ok(info) => StepOutcome::Verified(info),
+                    Err(e) => StepOutcome::Failed(e),
+                },
+                Err(e) => e.into(),
+            }
+        }
+    }
+}
+
+/// Check the CC page: the tag must be writable and big enough for `len` bytes
+fn check_capacity(cc: &[u8], len: usize) -> Result<(), String> {
+    let cc = ntag::parse_cc(cc)?;
+    if !cc.writable {
+        return Err("Tag is read-only".to_string());
+    }
+    if len > cc.data_size {
+        return Err(format!("Tag too small: {} bytes needed, {} available", len, cc.data_size));
+    }
+    Ok(())
+}
+
+/// Compare the read-back memory with what was written and decode it
+fn verify(read_back: &[u8], data: &[u8]) -> Result<DecodedTagInfo, String> {
+    // The Pico bridge returns the first 144 bytes of user memory, which
+    // covers everything the encoders produce for a normal spool record
+    let compare_len = data.len().min(read_back.len());
+    if read_back[..compare_len] != data[..compare_len] {
+        return Err("Verify failed: tag content differs after write".to_string());
+    }
+    tag_formats::decode_ntag(read_back).map_err(|e| format!("Verify failed: {}", e))
+}
+
+/// Decode NTAG user memory, falling back to a plain "NTAG" for tags without
+/// spool data
+fn decode_ntag(memory: &[u8]) -> DecodedTagInfo {
+    match tag_formats::decode_ntag(memory) {
+        Ok(decoded) => {
+            info!("Decoded {} tag: vendor={}, material={}, color=0x{:08X}, weight={}g",
+                  decoded.tag_type_name, decoded.vendor, decoded.material,
+                  decoded.color_rgba, decoded.spool_weight);
+            decoded
+        }
+        Err(e) => {
+            info!("NTAG not decoded: {}", e);
+            DecodedTagInfo {
+                tag_type_name: "NTAG".to_string(),
+                ..Default::default()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nfc::mock_reader::{MockReader, MockTag};
+    use crate::nfc::tag_formats::{encode_ntag, TagWriteFormat};
+
+    const UID: [u8; 7] = [0x04, 0xA1, 0xB2, 0xC3, 0xD4, 0xE5, 0xF6];
+    const OTHER_UID: [u8; 7] = [0x04, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66];
+
+    fn spool_data() -> Vec<u8> {
+        let info = DecodedTagInfo {
+            vendor: "Elegoo".to_string(),
+            material: "PETG".to_string(),
+            color_rgba: 0x1A2B3CFF,
+            ..Default::default()
+        };
+        encode_ntag(TagWriteFormat::OpenSpool, &info)
+    }
+
+    fn detected(uid: &[u8]) -> DetectedTag {
+        DetectedTag { uid: uid.to_vec(), kind: TagKind::Ntag }
+    }
+
+    fn reader_with(tag: MockTag) -> MockReader {
+        let mut reader = MockReader::new();
+        reader.tag = Some(tag);
+        reader
+    }
+
+    /// A poller with the blank NTAG213 on `reader` detected and read
+    fn present_poller(reader: &mut MockReader) -> TagPoller {
+        let mut poller = TagPoller::new();
+        let events = poller.step(reader, 0);
+        assert!(matches!(events.as_slice(), [NfcEvent::TagDetected { .. }, NfcEvent::TagDecoded { .. }]));
+        poller
+    }
+
+    /// Step every millisecond from `*now` until something happens (at most 10 s)
+    fn next_events(poller: &mut TagPoller, reader: &mut MockReader, now: &mut u64) -> Vec<NfcEvent> {
+        for _ in 0..10_000 {
+            *now += 1;
+            let events = poller.step(reader, *now);
+            if !events.is_empty() {
+                return events;
+            }
+        }
+        panic!("no event within 10 s");
+    }
+
+    fn write_result(events: &[NfcEvent]) -> &Result<DecodedTagInfo, String> {
+        match events {
+            [NfcEvent::WriteFinished { result, .. }, ..] => result,
+            other => panic!("expected WriteFinished, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn tag_placed_read_and_removed() {
+        let mut reader = MockReader::new();
+        reader.latency = 1;
+        let mut poller = TagPoller::new();
+
+        // Nothing on the reader: scan pending, then back to idle
+        assert!(poller.step(&mut reader, 0).is_empty());
+        assert!(matches!(poller.phase, Phase::Scanning));
+        assert!(poller.step(&mut reader, 1).is_empty());
+        assert!(matches!(poller.phase, Phase::Idle { next_scan_ms: 501 }));
+        assert!(poller.step(&mut reader, 100).is_empty());
+
+        // Tag placed: detected on the next scan, then read
+        reader.tag = Some(MockTag::ntag213(&UID));
+        let tag_data = spool_data();
+        reader.tag.as_mut().unwrap().memory[16..16 + tag_data.len()].copy_from_slice(&tag_data);
+        assert!(poller.step(&mut reader, 501).is_empty());
+        assert!(matches!(poller.phase, Phase::Scanning));
+        let events = poller.step(&mut reader, 502);
+        assert!(matches!(events.as_slice(), [NfcEvent::TagDetected { tag }] if tag.uid == UID));
+        assert!(matches!(poller.phase, Phase::Reading));
+        let events = poller.step(&mut reader, 503);
+        match events.as_slice() {
+            [NfcEvent::TagDecoded { uid_hex, info }] => {
+                assert_eq!(uid_hex, "04:A1:B2:C3:D4:E5:F6");
+                assert_eq!(info.vendor, "Elegoo");
+                assert_eq!(info.material, "PETG");
+            }
+            other => panic!("expected TagDecoded, got {:?}", other),
+        }
+        assert!(matches!(poller.phase, Phase::Present { next_scan_ms: 1003 }));
+        assert_eq!(poller.present().map(|t| t.uid.as_slice()), Some(&UID[..]));
+
+        // Rescans of the same tag report nothing and don't read it again
+        let mut now = 503;
+        for _ in 0..3 {
+            now += SCAN_INTERVAL_MS;
+            assert!(poller.step(&mut reader, now).is_empty());
+            assert!(poller.step(&mut reader, now + 1).is_empty());
+            assert!(matches!(poller.phase, Phase::Present { .. }));
+            now += 1;
+        }
+
+        // Tag taken off: the next scan notices, then it is reported
+        reader.tag = None;
+        now += SCAN_INTERVAL_MS;
+        assert!(poller.step(&mut reader, now).is_empty());
+        assert!(poller.step(&mut reader, now + 1).is_empty());
+        assert!(matches!(poller.phase, Phase::Removed));
+        let events = poller.step(&mut reader, now + 2);
+        assert!(matches!(events.as_slice(), [NfcEvent::TagRemoved]));
+        assert!(matches!(poller.phase, Phase::Idle { .. }));
+        assert!(poller.present().is_none());
+    }
+
+    #[test]
+    fn pending_operations_are_retried() {
+        let mut reader = reader_with(MockTag::ntag213(&UID));
+        reader.latency = 3;
+        let mut poller = TagPoller::new();
+
+        // Detection and the read each take 3 pending polls
+        let mut events = Vec::new();
+        for now in 0..6 {
+            events.extend(poller.step(&mut reader, now));
+        }
+        assert!(matches!(poller.phase, Phase::Reading));
+        events.extend(poller.step(&mut reader, 6));
+        match events.as_slice() {
+            [NfcEvent::TagDetected { .. }, NfcEvent::TagDecoded { info, .. }] => {
+                // Blank tag: plain NTAG
+                assert_eq!(info.tag_type_name, "NTAG");
+                assert!(info.vendor.is_empty());
+            }
+            other => panic!("expected TagDetected and TagDecoded, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn failed_read_is_not_retried() {
+        // MIFARE Classic without the Bambu blocks: the read fails
+        let broken = MockTag { uid: UID.to_vec(), kind: TagKind::MifareClassic1k, memory: Vec::new() };
+        let mut reader = reader_with(broken);
+        let mut poller = TagPoller::new();
+
+        let events = poller.step(&mut reader, 0);
+        assert!(matches!(events.as_slice(), [NfcEvent::TagDetected { .. }]));
+        assert!(matches!(poller.phase, Phase::Present { .. }));
+
+        // Readable now, but the tag isn't read again while it stays on the reader
+        reader.tag = Some(MockTag::mifare_classic_1k(&UID, &[(2, *b"PLA\0\0\0\0\0\0\0\0\0\0\0\0\0")]));
+        for scan in 1..5 {
+            assert!(poller.step(&mut reader, scan * SCAN_INTERVAL_MS).is_empty());
+            assert!(matches!(poller.phase, Phase::Present { .. }));
+        }
+
+        // A scan error leaves the tag in place and is retried after the interval
+        reader.fail_next = Some("RF error");
+        assert!(poller.step(&mut reader, 5 * SCAN_INTERVAL_MS).is_empty());
+        assert!(matches!(poller.phase, Phase::Present { next_scan_ms } if next_scan_ms == 6 * SCAN_INTERVAL_MS));
+        assert!(poller.present().is_some());
+    }
+
+    #[test]
+    fn write_from_present_is_verified() {
+        let mut reader = reader_with(MockTag::ntag213(&UID));
+        let mut poller = present_poller(&mut reader);
+        reader.latency = 1;
+
+        let data = spool_data();
+        poller.queue_write(detected(&UID), data.clone());
+        let mut now = 0;
+        let events = next_events(&mut poller, &mut reader, &mut now);
+
+        let info = write_result(&events).as_ref().expect("write succeeds");
+        assert_eq!(info.tag_type_name, "OpenSpool");
+        assert_eq!(info.vendor, "Elegoo");
+        assert_eq!(&reader.tag.as_ref().unwrap().memory[16..16 + data.len()], data.as_slice());
+        assert!(matches!(poller.phase, Phase::Present { .. }));
+
+        // The written data counts as read: the next scan reports nothing
+        reader.latency = 0;
+        assert!(poller.step(&mut reader, now + SCAN_INTERVAL_MS).is_empty());
+        assert!(matches!(poller.phase, Phase::Present { .. }));
+    }
+
+    #[test]
+    fn write_to_a_different_tag_fails() {
+        let mut reader = reader_with(MockTag::ntag213(&UID));
+        let mut poller = present_poller(&mut reader);
+
+        poller.queue_write(detected(&OTHER_UID), spool_data());
+        let events = poller.step(&mut reader, 1);
+        match events.as_slice() {
+            [NfcEvent::WriteFinished { uid_hex, result: Err(e) }] => {
+                assert_eq!(uid_hex, "04:11:22:33:44:55:66");
+                assert_eq!(e, "A different tag is on the reader");
+            }
+            other => panic!("expected a failed write, got {:?}", other),
+        }
+        // Nothing was written, and the present tag is read again right away
+        assert!(reader.tag.as_ref().unwrap().memory[16..].starts_with(&[0x03, 0x00, 0xFE]));
+        let events = poller.step(&mut reader, 1);
+        assert!(matches!(events.as_slice(), [NfcEvent::TagDecoded { .. }]));
+    }
+
+    #[test]
+    fn write_after_tag_removed_fails() {
+        let mut reader = reader_with(MockTag::ntag213(&UID));
+        let mut poller = present_poller(&mut reader);
+
+        // Taken off and noticed by the next scan, then the write is queued
+        reader.tag = None;
+        assert!(poller.step(&mut reader, SCAN_INTERVAL_MS).is_empty());
+        assert!(matches!(poller.phase, Phase::Removed));
+        poller.queue_write(detected(&UID), spool_data());
+
+        let events = poller.step(&mut reader, SCAN_INTERVAL_MS + 1);
+        match events.as_slice() {
+            [NfcEvent::WriteFinished { result: Err(e), .. }, NfcEvent::TagRemoved] => {
+                assert_eq!(e, "Tag removed before writing");
+            }
+            other => panic!("expected a failed write and TagRemoved, got {:?}", other),
+        }
+
+        // Queued while idle: fails on the next step as well
+        poller.queue_write(detected(&UID), spool_data());
+        let events = poller.step(&mut reader, SCAN_INTERVAL_MS + 2);
+        assert_eq!(write_result(&events).as_ref().unwrap_err(), "Tag removed before writing");
+    }
+
+    #[test]
+    fn write_rejected_by_capability_container() {
+        // NTAG213 holds 144 bytes
+        let mut reader = reader_with(MockTag::ntag213(&UID));
+        let mut poller = present_poller(&mut reader);
+        poller.queue_write(detected(&UID), vec![0xAA; 200]);
+        let events = poller.step(&mut reader, 1);
+        assert_eq!(
+            write_result(&events).as_ref().unwrap_err(),
+            "Tag too small: 200 bytes needed, 144 available"
+        );
+
+        // Read-only tag
+        let mut reader = reader_with(MockTag::ntag213(&UID).read_only());
+        let mut poller = present_poller(&mut reader);
+        poller.queue_write(detected(&UID), spool_data());
+        let events = poller.step(&mut reader, 1);
+        assert_eq!(write_result(&events).as_ref().unwrap_err(), "Tag is read-only");
+        assert!(reader.tag.as_ref().unwrap().memory[16..].starts_with(&[0x03, 0x00, 0xFE]));
+
+        // Reader that can't write at all
+        let mut reader = reader_with(MockTag::ntag213(&UID));
+        reader.writable = false;
+        let mut poller = present_poller(&mut reader);
+        poller.queue_write(detected(&UID), spool_data());
+        let events = poller.step(&mut reader, 1);
+        assert_eq!(write_result(&events).as_ref().unwrap_err(), "Mock cannot write tags");
+    }
+
+    #[test]
+    fn write_verify_mismatch_fails() {
+        let mut reader = reader_with(MockTag::ntag213(&UID));
+        let mut poller = present_poller(&mut reader);
+        reader.latency = 1;
+
+        poller.queue_write(detected(&UID), spool_data());
+        let mut now = 0;
+        while !matches!(&poller.phase, Phase::Writing(job) if job.step == WriteStep::Verify) {
+            now += 1;
+            assert!(poller.step(&mut reader, now).is_empty());
+            assert!(now < 10, "write never reached verify");
+        }
+
+        // The tag content changes between writing and reading back
+        reader.tag.as_mut().unwrap().memory[20] ^= 0xFF;
+        let events = next_events(&mut poller, &mut reader, &mut now);
+        assert_eq!(
+            write_result(&events).as_ref().unwrap_err(),
+            "Verify failed: tag content differs after write"
+        );
+    }
+}
@@ -0,0 +1,310 @@
+//! Scale calibration curve and its persisted format
+//!
+//! The weight is a function of the tared reading `d = raw - zero_offset`.
+//! Reference weights are fitted by least squares through the origin, either
+//! linear (`g = d / cal_factor`) or quadratic
+//! (`g = d / cal_factor + quadratic·d²`) for load cells that are not linear
+//! over their range. The residuals of the reference weights show how well the
+//! curve fits.
+//!
+//! Calibration blob (little-endian, 16 bytes):
+//! ```text
+//! [version=2, reserved, zero_offset i32, cal_factor f32, quadratic f32, crc16]
+//! ```
+//! The CRC is CRC-16/CCITT-FALSE over the bytes before it. The original
+//! 8-byte blob (zero_offset i32, cal_factor ×1000 as i32) is still read.
+
+use crate::crc::crc16;
+
+/// Current calibration blob version
+pub const BLOB_VERSION: u8 = 2;
+
+/// Calibration blob length
+pub const BLOB_LEN: usize = 16;
+
+/// Length of the original (unversioned) calibration blob
+const LEGACY_BLOB_LEN: usize = 8;
+
+/// Reference weights per calibration
+pub const MAX_POINTS: usize = 8;
+
+/// Smallest reading (above zero) of the heaviest reference weight.
+/// A 797g weight gives ~195,000 with a typical 5kg load cell.
+const MIN_DELTA: i32 = 10000;
+
+/// Plausible linear calibration factors (raw units per gram)
+const CAL_FACTOR_RANGE: core::ops::RangeInclusive<f32> = 10.0..=2000.0;
+
+/// Scale calibration data
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Calibration {
+    /// Zero offset (tare)
+    pub zero_offset: i32,
+    /// Calibration factor (raw units per gram)
+    pub cal_factor: f32,
+    /// Quadratic term (grams per raw unit squared), 0 when linear
+    pub quadratic: f32,
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Self {
+            zero_offset: 0,
+            // Default calibration factor - needs actual calibration
+            cal_factor: 1000.0,
+            quadratic: 0.0,
+        }
+    }
+}
+
+impl Calibration {
+    /// Weight in grams of a raw reading
+    pub fn grams(&self, raw: i32) -> f32 {
+        let d = (raw - self.zero_offset) as f32;
+        d / self.cal_factor + self.quadratic * d * d
+    }
+
+    /// Serialize to the current blob format
+    pub fn to_blob(self) -> [u8; BLOB_LEN] {
+        let mut buf = [0u8; BLOB_LEN];
+        buf[0] = BLOB_VERSION;
+        buf[2..6].copy_from_slice(&self.zero_offset.to_le_bytes());
+        buf[6..10].copy_from_slice(&self.cal_factor.to_le_bytes());
+        buf[10..14].copy_from_slice(&self.quadratic.to_le_bytes());
+        let crc = crc16(&buf[..BLOB_LEN - 2]);
+        buf[BLOB_LEN - 2..].copy_from_slice(&crc.to_le_bytes());
+        buf
+    }
+
+    /// Parse a calibration blob, current or original format.
+    /// Returns the calibration and whether it needs saving in the current format.
+    pub fn from_blob(blob: &[u8]) -> Result<(Self, bool), &'static str> {
+        if blob.len() == LEGACY_BLOB_LEN {
+            let word = |i: usize| i32::from_le_bytes([blob[i], blob[i + 1], blob[i + 2], blob[i + 3]]);
+            let cal = Self { zero_offset: word(0), cal_factor: word(4) as f32 / 1000.0, quadratic: 0.0 };
+            return Ok((cal.validated()?, true));
+        }
+
+        if blob.first() != Some(&BLOB_VERSION) {
+            return Err("Unsupported calibration version");
+        }
+        if blob.len() != BLOB_LEN {
+            return Err("Calibration blob has the wrong length");
+        }
+        let crc = u16::from_le_bytes([blob[BLOB_LEN - 2], blob[BLOB_LEN - 1]]);
+        if crc != crc16(&blob[..BLOB_LEN - 2]) {
+            return Err("Calibration checksum mismatch");
+        }
+
+        let word = |i: usize| [blob[i], blob[i + 1], blob[i + 2], blob[i + 3]];
+        let cal = Self {
+            zero_offset: i32::from_le_bytes(word(2)),
+            cal_factor: f32::from_le_bytes(word(6)),
+            quadratic: f32::from_le_bytes(word(10)),
+        };
+        Ok((cal.validated()?, false))
+    }
+
+    fn validated(self) -> Result<Self, &'static str> {
+        if !self.cal_factor.is_finite() || self.cal_factor <= 0.0 || !self.quadratic.is_finite() {
+            return Err("Invalid calibration values");
+        }
+        Ok(self)
+    }
+}
+
+/// A reference weight and its tared reading
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CalPoint {
+    /// Averaged raw reading minus the zero offset
+    pub delta: i32,
+    /// Known weight in grams
+    pub grams: f32,
+}
+
+/// Shape of the fitted curve
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CurveKind {
+    Linear,
+    Quadratic,
+}
+
+impl CurveKind {
+    /// Quadratic from three reference weights on (with two it would fit
+    /// exactly, leaving no residual to judge it by)
+    pub fn for_points(count: usize) -> Self {
+        if count >= 3 {
+            CurveKind::Quadratic
+        } else {
+            CurveKind::Linear
+        }
+    }
+}
+
+/// How well a fitted curve matches the reference weights
+#[derive(Debug, Clone, PartialEq)]
+pub struct FitReport {
+    pub kind: CurveKind,
+    /// Fitted minus known weight per point, in grams
+    pub residuals: Vec<f32>,
+    /// Largest absolute residual
+    pub max_error: f32,
+    /// Root mean square of the residuals
+    pub rms_error: f32,
+}
+
+/// Fit the calibration curve through zero and the reference weights.
+/// Sets `cal_factor` and `quadratic` of `calibration`, keeping its zero offset.
+pub fn fit(calibration: &mut Calibration, points: &[CalPoint], kind: CurveKind) -> Result<FitReport, &'static str> {
+    if points.is_empty() {
+        return Err("No calibration points");
+    }
+    if points.iter().any(|p| p.delta <= 0 || p.grams.is_nan() || p.grams <= 0.0) {
+        return Err("Reference weights must read above zero");
+    }
+    let max_delta = points.iter().map(|p| p.delta).max().unwrap_or(0);
+    if max_delta < MIN_DELTA {
+        return Err("No significant weight detected");
+    }
+
+    // Normalized readings keep the sums well conditioned
+    let scale = max_delta as f64;
+    let (mut sx2, mut sx3, mut sx4, mut sxg, mut sx2g) = (0.0, 0.0, 0.0, 0.0, 0.0);
+    for p in points {
+        let (x, g) = (p.delta as f64 / scale, p.grams as f64);
+        sx2 += x * x;
+        sx3 += x * x * x;
+        sx4 += x * x * x * x;
+        sxg += x * g;
+        sx2g += x * x * g;
+    }
+
+    let (a, b) = match kind {
+        CurveKind::Linear => (sxg / sx2, 0.0),
+        CurveKind::Quadratic => {
+            let det = sx2 * sx4 - sx3 * sx3;
+            if det.abs() <= 1e-9 * sx2 * sx4 {
+                return Err("A quadratic fit needs two different reference weights");
+            }
+            ((sxg * sx4 - sx2g * sx3) / det, (sx2 * sx2g - sx3 * sxg) / det)
+        }
+    };
+
+    // Back to raw units: g = a·(d/s) + b·(d/s)²
+    let cal_factor = (scale / a) as f32;
+    let quadratic = (b / (scale * scale)) as f32;
+    if !CAL_FACTOR_RANGE.contains(&cal_factor) {
+        return Err("Calibration factor out of range (10-2000)");
+    }
+    // Weight must keep rising with the reading, up to twice the heaviest point
+    if a + 4.0 * b <= 0.0 {
+        return Err("Fitted curve is not increasing");
+    }
+
+    calibration.cal_factor = cal_factor;
+    calibration.quadratic = quadratic;
+
+    let residuals: Vec<f32> = points
+        .iter()
+        .map(|p| calibration.grams(calibration.zero_offset + p.delta) - p.grams)
+        .collect();
+    let max_error = residuals.iter().fold(0.0f32, |max, r| max.max(r.abs()));
+    let rms_error = (residuals.iter().map(|r| r * r).sum::<f32>() / residuals.len() as f32).sqrt();
+
+    Ok(FitReport { kind, residuals, max_error, rms_error })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blob_round_trip() {
+        let cal = Calibration { zero_offset: -84_213, cal_factor: 428.6, quadratic: -1.5e-9 };
+        let blob = cal.to_blob();
+        assert_eq!(blob[0], BLOB_VERSION);
+        assert_eq!(Calibration::from_blob(&blob), Ok((cal, false)));
+    }
+
+    #[test]
+    fn legacy_blob_migrates() {
+        let mut blob = [0u8; LEGACY_BLOB_LEN];
+        blob[..4].copy_from_slice(&84_213i32.to_le_bytes());
+        blob[4..].copy_from_slice(&428_600i32.to_le_bytes());
+
+        let (cal, migrate) = Calibration::from_blob(&blob).unwrap();
+        assert!(migrate);
+        assert_eq!(cal, Calibration { zero_offset: 84_213, cal_factor: 428.6, quadratic: 0.0 });
+        // Saved again in the current format
+        assert_eq!(Calibration::from_blob(&cal.to_blob()), Ok((cal, false)));
+    }
+
+    #[test]
+    fn rejects_bad_blobs() {
+        let blob = Calibration::default().to_blob();
+
+        let mut corrupt = blob;
+        corrupt[3] ^= 0x01;
+        assert_eq!(Calibration::from_blob(&corrupt), Err("Calibration checksum mismatch"));
+
+        let mut version = blob;
+        version[0] = BLOB_VERSION + 1;
+        assert_eq!(Calibration::from_blob(&version), Err("Unsupported calibration version"));
+
+        assert_eq!(Calibration::from_blob(&blob[..12]), Err("Calibration blob has the wrong length"));
+        assert_eq!(Calibration::from_blob(&[0u8; LEGACY_BLOB_LEN]), Err("Invalid calibration values"));
+    }
+
+    #[test]
+    fn linear_fit() {
+        let mut cal = Calibration { zero_offset: 1000, ..Calibration::default() };
+        let points = [CalPoint { delta: 122_500, grams: 500.0 }];
+        let report = fit(&mut cal, &points, CurveKind::Linear).unwrap();
+        assert!((cal.cal_factor - 245.0).abs() < 1e-3);
+        assert_eq!(cal.quadratic, 0.0);
+        assert_eq!(cal.zero_offset, 1000);
+        assert!(report.max_error < 1e-3);
+    }
+
+    #[test]
+    fn fit_rejects_unusable_points() {
+        let mut cal = Calibration::default();
+        assert!(fit(&mut cal, &[], CurveKind::Linear).is_err());
+        assert!(fit(&mut cal, &[CalPoint { delta: -5000, grams: 100.0 }], CurveKind::Linear).is_err());
+        assert!(fit(&mut cal, &[CalPoint { delta: 10, grams: 100.0 }], CurveKind::Linear).is_err());
+        let same = [CalPoint { delta: 24_500, grams: 100.0 }; 3];
+        assert!(fit(&mut cal, &same, CurveKind::Quadratic).is_err());
+        assert_eq!(cal, Calibration::default());
+    }
+
+    /// Multi-point calibration of a non-linear load cell on the simulated chip
+    #[test]
+    fn quadratic_fit_of_simulated_cell() {
+        use crate::scale::nau7802::{self, Nau7802State};
+        use crate::scale::nau7802_sim::{LoadCell, LoadProfile, SimDelay, SimulatedNau7802};
+
+        let cell = LoadCell { nonlinearity: -0.004, noise_raw: 0, ..LoadCell::default() };
+        let mut sim = SimulatedNau7802::new(cell, LoadProfile::constant(0.0));
+        let mut delay = SimDelay::default();
+        let mut state = Nau7802State::new();
+        nau7802::init(&mut sim, &mut delay, &mut state).unwrap();
+        nau7802::tare(&mut sim, &mut delay, &mut state).unwrap();
+
+        let mut points = Vec::new();
+        for grams in [500.0, 1000.0, 2000.0] {
+            sim.set_profile(LoadProfile::constant(grams));
+            points.push(nau7802::measure_point(&mut sim, &mut delay, &mut state, grams).unwrap());
+        }
+
+        let mut linear = state.calibration;
+        let linear_report = fit(&mut linear, &points, CurveKind::Linear).unwrap();
+        let mut quadratic = state.calibration;
+        let report = fit(&mut quadratic, &points, CurveKind::for_points(points.len())).unwrap();
+        assert_eq!(report.kind, CurveKind::Quadratic);
+        assert!(report.max_error < 0.5, "{report:?}");
+        assert!(linear_report.max_error > 5.0, "{linear_report:?}");
+        // Between the reference weights
+        let delta = (1500.0 * cell.raw_per_gram + 1500.0 * 1500.0 * cell.nonlinearity) as i32;
+        assert!((quadratic.grams(quadratic.zero_offset + delta) - 1500.0).abs() < 0.5);
+    }
+}
@@ -0,0 +1,448 @@
+//! Zero tracking and temperature drift compensation
+//!
+//! Auto-zero: while the filtered weight sits stable within a small band
+//! around zero, the zero offset is walked towards the reading by a few
+//! hundredths of a gram per reading. Slow creep (the workshop warming up)
+//! is absorbed; anything put on the scale is far outside the band.
+//!
+//! Temperature compensation corrects the calibrated weight by the change in
+//! temperature since the reference (the temperature at the last tare, or the
+//! first one known after start-up): a zero drift in grams per °C and a span
+//! drift in ppm per °C. The temperature comes from the NAU7802's internal
+//! sensor or from the AMS (via the backend). Only differences to the
+//! reference matter, so an uncalibrated sensor offset cancels out.
+//!
+//! Like `scale::filter`, this works on grams only and runs on the host.
+//!
+//! Settings blob (little-endian, 24 bytes):
+//! ```text
+//! [version=1, auto_zero, band f32, rate f32, hold u16, temp_source,
+//!  reserved, zero_g_per_c f32, span_ppm_per_c f32, crc16]
+//! ```
+//! The CRC is CRC-16/CCITT-FALSE over the bytes before it.
+
+use crate::crc::crc16;
+
+/// Settings blob version
+const DRIFT_BLOB_VERSION: u8 = 1;
+
+/// Settings blob length
+pub const DRIFT_BLOB_LEN: usize = 24;
+
+/// Where the temperature for drift compensation comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TempSource {
+    /// No compensation
+    None = 0,
+    /// NAU7802 internal temperature sensor
+    Internal = 1,
+    /// AMS temperature reported by the backend
+    Ams = 2,
+}
+
+impl TempSource {
+    /// From the C interface value
+    pub fn from_i32(value: i32) -> Option<Self> {
+        match value {
+            0 => Some(TempSource::None),
+            1 => Some(TempSource::Internal),
+            2 => Some(TempSource::Ams),
+            _ => None,
+        }
+    }
+
+    /// Name in backend commands
+    pub fn name(self) -> &'static str {
+        match self {
+            TempSource::None => "none",
+            TempSource::Internal => "internal",
+            TempSource::Ams => "ams",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "none" => Some(TempSource::None),
+            "internal" => Some(TempSource::Internal),
+            "ams" => Some(TempSource::Ams),
+            _ => None,
+        }
+    }
+}
+
+/// Zero tracking and drift compensation settings (adjustable at runtime)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DriftConfig {
+    /// Track the zero automatically
+    pub auto_zero: bool,
+    /// Largest weight (grams, either sign) that is taken as an empty platform
+    pub auto_zero_band_g: f32,
+    /// Largest zero correction per reading (grams)
+    pub auto_zero_rate_g: f32,
+    /// Stable readings within the band before tracking starts
+    pub auto_zero_hold: usize,
+    pub temp_source: TempSource,
+    /// Zero drift (grams per °C)
+    pub zero_g_per_c: f32,
+    /// Span drift (ppm of the weight per °C)
+    pub span_ppm_per_c: f32,
+}
+
+impl Default for DriftConfig {
+    fn default() -> Self {
+        Self {
+            auto_zero: true,
+            auto_zero_band_g: 2.0,
+            // 0.2 g/s at 10 SPS
+            auto_zero_rate_g: 0.02,
+            // ~3 seconds at 10 SPS
+            auto_zero_hold: 30,
+            temp_source: TempSource::None,
+            zero_g_per_c: 0.0,
+            span_ppm_per_c: 0.0,
+        }
+    }
+}
+
+impl DriftConfig {
+    /// Check the settings are usable
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.auto_zero_band_g.is_nan() || self.auto_zero_band_g <= 0.0 || self.auto_zero_band_g > 50.0 {
+            return Err("Auto-zero band must be in (0, 50] grams");
+        }
+        if self.auto_zero_rate_g.is_nan() || self.auto_zero_rate_g <= 0.0 || self.auto_zero_rate_g > self.auto_zero_band_g {
+            return Err("Auto-zero rate must be positive and within the band");
+        }
+        if !self.zero_g_per_c.is_finite() || !self.span_ppm_per_c.is_finite() {
+            return Err("Invalid drift coefficients");
+        }
+        if self.span_ppm_per_c.abs() >= 10_000.0 {
+            return Err("Span drift out of range");
+        }
+        Ok(())
+    }
+
+    /// Serialize to the blob format
+    pub fn to_blob(self) -> [u8; DRIFT_BLOB_LEN] {
+        let mut buf = [0u8; DRIFT_BLOB_LEN];
+        buf[0] = DRIFT_BLOB_VERSION;
+        buf[1] = self.auto_zero as u8;
+        buf[2..6].copy_from_slice(&self.auto_zero_band_g.to_le_bytes());
+        buf[6..10].copy_from_slice(&self.auto_zero_rate_g.to_le_bytes());
+        let hold = u16::try_from(self.auto_zero_hold).unwrap_or(u16::MAX);
+        buf[10..12].copy_from_slice(&hold.to_le_bytes());
+        buf[12] = self.temp_source as u8;
+        buf[14..18].copy_from_slice(&self.zero_g_per_c.to_le_bytes());
+        buf[18..22].copy_from_slice(&self.span_ppm_per_c.to_le_bytes());
+        let crc = crc16(&buf[..DRIFT_BLOB_LEN - 2]);
+        buf[DRIFT_BLOB_LEN - 2..].copy_from_slice(&crc.to_le_bytes());
+        buf
+    }
+
+    /// Parse a settings blob
+    pub fn from_blob(blob: &[u8]) -> Result<Self, &'static str> {
+        if blob.first() != Some(&DRIFT_BLOB_VERSION) {
+            return Err("Unsupported drift settings version");
+        }
+        if blob.len() != DRIFT_BLOB_LEN {
+            return Err("Drift settings blob has the wrong length");
+        }
+        let crc = u16::from_le_bytes([blob[DRIFT_BLOB_LEN - 2], blob[DRIFT_BLOB_LEN - 1]]);
+        if crc != crc16(&blob[..DRIFT_BLOB_LEN - 2]) {
+            return Err("Drift settings checksum mismatch");
+        }
+
+        let float = |i: usize| f32::from_le_bytes([blob[i], blob[i + 1], blob[i + 2], blob[i + 3]]);
+        let config = Self {
+            auto_zero: blob[1] != 0,
+            auto_zero_band_g: float(2),
+            auto_zero_rate_g: float(6),
+            auto_zero_hold: u16::from_le_bytes([blob[10], blob[11]]) as usize,
+            temp_source: TempSource::from_i32(blob[12] as i32).ok_or("Invalid temperature source")?,
+            zero_g_per_c: float(14),
+            span_ppm_per_c: float(18),
+        };
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+/// Zero tracking and temperature compensation state
+pub struct DriftCompensator {
+    config: DriftConfig,
+    /// Stable readings within the band so far
+    held: usize,
+    /// Correction (raw units) not yet applied to the integer zero offset
+    pending_raw: f32,
+    /// Zero correction since the last tare (grams)
+    tracked_g: f32,
+    temperature_c: Option<f32>,
+    reference_c: Option<f32>,
+    /// Temperature correction of the last reading (grams)
+    correction_g: f32,
+}
+
+impl DriftCompensator {
+    pub fn new(config: DriftConfig) -> Self {
+        Self {
+            config,
+            held: 0,
+            pending_raw: 0.0,
+            tracked_g: 0.0,
+            temperature_c: None,
+            reference_c: None,
+            correction_g: 0.0,
+        }
+    }
+
+    pub fn config(&self) -> DriftConfig {
+        self.config
+    }
+
+    /// Switch to new settings; a change of temperature source forgets the
+    /// temperature and its reference
+    pub fn set_config(&mut self, config: DriftConfig) {
+        if config.temp_source != self.config.temp_source {
+            self.temperature_c = None;
+            self.reference_c = None;
+            self.correction_g = 0.0;
+        }
+        self.config = config;
+        self.held = 0;
+    }
+
+    /// New temperature reading; the first one becomes the reference
+    pub fn set_temperature(&mut self, temperature_c: f32) {
+        if !temperature_c.is_finite() {
+            return;
+        }
+        self.temperature_c = Some(temperature_c);
+        self.reference_c.get_or_insert(temperature_c);
+    }
+
+    /// Forget the zero tracking and take the current temperature as the
+    /// reference (after a tare)
+    pub fn reset(&mut self) {
+        self.held = 0;
+        self.pending_raw = 0.0;
+        self.tracked_g = 0.0;
+        self.reference_c = self.temperature_c;
+        self.correction_g = 0.0;
+    }
+
+    /// Temperature compensated weight of a calibrated reading
+    pub fn compensate(&mut self, grams: f32) -> f32 {
+        let delta_c = match (self.config.temp_source, self.temperature_c, self.reference_c) {
+            (TempSource::None, _, _) => None,
+            (_, Some(t), Some(reference)) => Some(t - reference),
+            _ => None,
+        };
+        let Some(delta_c) = delta_c else {
+            self.correction_g = 0.0;
+            return grams;
+        };
+
+        let span = 1.0 + self.config.span_ppm_per_c * 1e-6 * delta_c;
+        let corrected = (grams - self.config.zero_g_per_c * delta_c) / span;
+        self.correction_g = corrected - grams;
+        corrected
+    }
+
+    /// Zero tracking for the next filtered weight.
+    /// Returns the raw units to add to the zero offset (0 while not tracking).
+    pub fn track_zero(&mut self, weight_g: f32, stable: bool, cal_factor: f32) -> i32 {
+        if !self.config.auto_zero || !stable || weight_g.abs() > self.config.auto_zero_band_g {
+            self.held = 0;
+            return 0;
+        }
+        if self.held < self.config.auto_zero_hold {
+            self.held += 1;
+            return 0;
+        }
+
+        let step_g = weight_g.clamp(-self.config.auto_zero_rate_g, self.config.auto_zero_rate_g);
+        self.pending_raw += step_g * cal_factor;
+        let shift = self.pending_raw.trunc();
+        self.pending_raw -= shift;
+        self.tracked_g += shift / cal_factor;
+        shift as i32
+    }
+
+    /// Current temperature, if known
+    pub fn temperature(&self) -> Option<f32> {
+        self.temperature_c
+    }
+
+    /// Temperature the correction is relative to
+    pub fn reference_temperature(&self) -> Option<f32> {
+        self.reference_c
+    }
+
+    /// Temperature correction added to the last reading (grams)
+    pub fn correction_g(&self) -> f32 {
+        self.correction_g
+    }
+
+    /// Zero moved by auto-zero since the last tare (grams)
+    pub fn tracked_g(&self) -> f32 {
+        self.tracked_g
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Raw units per gram
+    const CAL_FACTOR: f32 = 10.0;
+
+    fn tracking(hold: usize) -> DriftCompensator {
+        DriftCompensator::new(DriftConfig {
+            auto_zero_band_g: 2.0,
+            auto_zero_rate_g: 0.25,
+            auto_zero_hold: hold,
+            ..Default::default()
+        })
+    }
+
+    fn compensating(zero_g_per_c: f32, span_ppm_per_c: f32) -> DriftCompensator {
+        DriftCompensator::new(DriftConfig {
+            temp_source: TempSource::Internal,
+            zero_g_per_c,
+            span_ppm_per_c,
+            ..Default::default()
+        })
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-3, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn zero_tracking_waits_for_the_hold_count() {
+        let mut drift = tracking(3);
+        for _ in 0..3 {
+            assert_eq!(drift.track_zero(1.0, true, CAL_FACTOR), 0);
+        }
+        assert_ne!(drift.track_zero(1.0, true, CAL_FACTOR), 0);
+
+        // An unstable reading starts the count over
+        let mut drift = tracking(3);
+        for _ in 0..2 {
+            drift.track_zero(1.0, true, CAL_FACTOR);
+        }
+        assert_eq!(drift.track_zero(1.0, false, CAL_FACTOR), 0);
+        for _ in 0..3 {
+            assert_eq!(drift.track_zero(1.0, true, CAL_FACTOR), 0);
+        }
+        assert_ne!(drift.track_zero(1.0, true, CAL_FACTOR), 0);
+    }
+
+    #[test]
+    fn zero_tracking_only_within_the_band() {
+        let mut drift = tracking(0);
+        assert_eq!(drift.track_zero(2.5, true, CAL_FACTOR), 0);
+        assert_eq!(drift.track_zero(-2.5, true, CAL_FACTOR), 0);
+        assert_ne!(drift.track_zero(-2.0, true, CAL_FACTOR), 0);
+
+        let mut off = DriftCompensator::new(DriftConfig { auto_zero: false, auto_zero_hold: 0, ..Default::default() });
+        assert_eq!(off.track_zero(0.5, true, CAL_FACTOR), 0);
+        assert_eq!(off.tracked_g(), 0.0);
+    }
+
+    #[test]
+    fn zero_tracking_rate_is_clamped_and_fractions_carry() {
+        // 1 g off: clamped to 0.25 g = 2.5 raw per reading
+        let mut drift = tracking(0);
+        let shifts: Vec<i32> = (0..4).map(|_| drift.track_zero(1.0, true, CAL_FACTOR)).collect();
+        assert_eq!(shifts, [2, 3, 2, 3]);
+        assert_close(drift.tracked_g(), 1.0);
+
+        // Below the rate the whole reading is taken: -1.25 raw per reading
+        let mut drift = tracking(0);
+        let shifts: Vec<i32> = (0..4).map(|_| drift.track_zero(-0.125, true, CAL_FACTOR)).collect();
+        assert_eq!(shifts, [-1, -1, -1, -2]);
+        assert_close(drift.tracked_g(), -0.5);
+
+        // A tare drops the carried fraction
+        let mut drift = tracking(0);
+        assert_eq!(drift.track_zero(1.0, true, CAL_FACTOR), 2);
+        drift.reset();
+        assert_eq!(drift.tracked_g(), 0.0);
+        assert_eq!(drift.track_zero(1.0, true, CAL_FACTOR), 2);
+    }
+
+    #[test]
+    fn no_compensation_without_source_or_temperature() {
+        let mut drift = DriftCompensator::new(DriftConfig { zero_g_per_c: 1.0, ..Default::default() });
+        drift.set_temperature(20.0);
+        drift.set_temperature(30.0);
+        assert_eq!(drift.compensate(100.0), 100.0);
+
+        let mut drift = compensating(1.0, 0.0);
+        assert_eq!(drift.compensate(100.0), 100.0);
+        assert_eq!(drift.correction_g(), 0.0);
+    }
+
+    #[test]
+    fn compensates_zero_drift() {
+        let mut drift = compensating(0.1, 0.0);
+        drift.set_temperature(20.0);
+        assert_eq!(drift.compensate(100.0), 100.0);
+
+        // 10 °C warmer reads 1 g more
+        drift.set_temperature(30.0);
+        assert_close(drift.compensate(101.0), 100.0);
+        assert_close(drift.correction_g(), -1.0);
+        assert_eq!(drift.reference_temperature(), Some(20.0));
+
+        // After a tare the current temperature is the reference
+        drift.reset();
+        assert_eq!(drift.reference_temperature(), Some(30.0));
+        assert_eq!(drift.compensate(101.0), 101.0);
+    }
+
+    #[test]
+    fn compensates_span_drift() {
+        // 1000 ppm/°C: 1% heavier at +10 °C, on top of the zero drift
+        let mut drift = compensating(0.1, 1000.0);
+        drift.set_temperature(20.0);
+        drift.set_temperature(30.0);
+        assert_close(drift.compensate(102.0), 100.0);
+        assert_close(drift.compensate(1.0 + 1010.0), 1000.0);
+
+        // 10 °C colder: 100 g reads 1% and 1 g lighter
+        drift.set_temperature(10.0);
+        assert_close(drift.compensate(98.0), 100.0);
+    }
+
+    #[test]
+    fn blob_round_trip() {
+        let config = DriftConfig {
+            auto_zero: false,
+            auto_zero_band_g: 1.5,
+            auto_zero_rate_g: 0.05,
+            auto_zero_hold: 50,
+            temp_source: TempSource::Ams,
+            zero_g_per_c: -0.08,
+            span_ppm_per_c: 120.0,
+        };
+        assert_eq!(DriftConfig::from_blob(&config.to_blob()), Ok(config));
+
+        let mut corrupt = config.to_blob();
+        corrupt[3] ^= 0x01;
+        assert_eq!(DriftConfig::from_blob(&corrupt), Err("Drift settings checksum mismatch"));
+        assert_eq!(DriftConfig::from_blob(&corrupt[..20]), Err("Drift settings blob has the wrong length"));
+        assert_eq!(DriftConfig::from_blob(&[0u8; DRIFT_BLOB_LEN]), Err("Unsupported drift settings version"));
+    }
+
+    #[test]
+    fn changing_the_source_forgets_the_temperature() {
+        let mut drift = compensating(0.1, 0.0);
+        drift.set_temperature(20.0);
+        drift.set_temperature(30.0);
+        drift.set_config(DriftConfig { temp_source: TempSource::Ams, ..drift.config() });
+        assert_eq!(drift.temperature(), None);
+        assert_eq!(drift.compensate(101.0), 101.0);
+    }
+}
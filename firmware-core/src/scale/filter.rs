@@ -0,0 +1,472 @@
+//! Weight filtering and stability detection
+//!
+//! Calibrated readings pass through a pipeline of [`FilterStage`]s: median
+//! spike rejection, a moving average and an adaptive EMA that snaps to large
+//! steps, each optional. Stability is judged separately, from the standard
+//! deviation of the last readings before filtering, so a swinging spool or a
+//! bumped table keeps the weight unstable even while the smoothed value looks
+//! calm.
+//!
+//! Everything here works on grams only, so it runs on the host as well (see
+//! `scale::trace` for replaying recorded readings).
+//!
+//! Settings blob (little-endian, 18 bytes):
+//! ```text
+//! [version=1, median_window, average_window, stability_window,
+//!  ema_alpha f32, snap_threshold_g f32, stability_std_dev_g f32, crc16]
+//! ```
+//! The CRC is CRC-16/CCITT-FALSE over the bytes before it.
+
+use crate::crc::crc16;
+use std::collections::VecDeque;
+
+/// Settings blob version
+const FILTER_BLOB_VERSION: u8 = 1;
+
+/// Settings blob length
+pub const FILTER_BLOB_LEN: usize = 18;
+
+/// Longest median, average or stability window
+pub const MAX_WINDOW: usize = 50;
+
+/// Marker of a logged raw reading (trace logging, see `scale::trace`)
+pub const TRACE_MARKER: &str = "SCALE_TRACE";
+
+/// One step of the filter pipeline
+pub trait FilterStage: Send {
+    /// Filter the next reading
+    fn update(&mut self, grams: f32) -> f32;
+    /// Forget the history and continue from `grams`
+    fn reset(&mut self, grams: f32);
+}
+
+/// Push a reading into a window of at most `size` readings
+fn push_window(window: &mut VecDeque<f32>, size: usize, grams: f32) {
+    if window.len() >= size {
+        window.pop_front();
+    }
+    window.push_back(grams);
+}
+
+/// Median of the last readings (rejects single-reading spikes)
+pub struct MedianFilter {
+    size: usize,
+    window: VecDeque<f32>,
+}
+
+impl MedianFilter {
+    pub fn new(size: usize) -> Self {
+        Self { size: size.max(1), window: VecDeque::with_capacity(size) }
+    }
+}
+
+impl FilterStage for MedianFilter {
+    fn update(&mut self, grams: f32) -> f32 {
+        push_window(&mut self.window, self.size, grams);
+        let mut sorted: Vec<f32> = self.window.iter().copied().collect();
+        sorted.sort_by(f32::total_cmp);
+        // Middle reading, or the mean of the middle two
+        let len = sorted.len();
+        (sorted[(len - 1) / 2] + sorted[len / 2]) / 2.0
+    }
+
+    fn reset(&mut self, grams: f32) {
+        self.window.clear();
+        self.window.push_back(grams);
+    }
+}
+
+/// Mean of the last readings
+pub struct MovingAverage {
+    size: usize,
+    window: VecDeque<f32>,
+}
+
+impl MovingAverage {
+    pub fn new(size: usize) -> Self {
+        Self { size: size.max(1), window: VecDeque::with_capacity(size) }
+    }
+}
+
+impl FilterStage for MovingAverage {
+    fn update(&mut self, grams: f32) -> f32 {
+        push_window(&mut self.window, self.size, grams);
+        self.window.iter().sum::<f32>() / self.window.len() as f32
+    }
+
+    fn reset(&mut self, grams: f32) {
+        self.window.clear();
+        self.window.push_back(grams);
+    }
+}
+
+/// Exponential moving average that jumps to the reading on a large step
+/// (a spool put on or taken off) instead of creeping towards it
+pub struct AdaptiveEma {
+    alpha: f32,
+    snap_threshold_g: f32,
+    value: Option<f32>,
+}
+
+impl AdaptiveEma {
+    pub fn new(alpha: f32, snap_threshold_g: f32) -> Self {
+        Self { alpha, snap_threshold_g, value: None }
+    }
+}
+
+impl FilterStage for AdaptiveEma {
+    fn update(&mut self, grams: f32) -> f32 {
+        let value = match self.value {
+            Some(value) if (grams - value).abs() <= self.snap_threshold_g => value + self.alpha * (grams - value),
+            _ => grams,
+        };
+        self.value = Some(value);
+        value
+    }
+
+    fn reset(&mut self, grams: f32) {
+        self.value = Some(grams);
+    }
+}
+
+/// Filter and stability settings (adjustable at runtime)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FilterConfig {
+    /// Median window for spike rejection (1 = off)
+    pub median_window: usize,
+    /// Moving average window (1 = off)
+    pub average_window: usize,
+    /// EMA weight of a new reading (1 = off)
+    pub ema_alpha: f32,
+    /// Change (grams) on which the EMA jumps to the new reading
+    pub snap_threshold_g: f32,
+    /// Readings the stability is judged over
+    pub stability_window: usize,
+    /// Largest standard deviation (grams) of a stable window
+    pub stability_std_dev_g: f32,
+}
+
+impl Default for FilterConfig {
+    fn default() -> Self {
+        Self {
+            median_window: 3,
+            average_window: 1,
+            // Moderate filtering - balance between smoothness and response
+            ema_alpha: 0.25,
+            snap_threshold_g: 50.0,
+            // ~1 second at 10 SPS
+            stability_window: 10,
+            stability_std_dev_g: 3.0,
+        }
+    }
+}
+
+impl FilterConfig {
+    /// Check the settings are usable
+    pub fn validate(&self) -> Result<(), &'static str> {
+        let windows = 1..=MAX_WINDOW;
+        if !windows.contains(&self.median_window) || !windows.contains(&self.average_window) {
+            return Err("Filter window out of range");
+        }
+        if !(2..=MAX_WINDOW).contains(&self.stability_window) {
+            return Err("Stability window out of range");
+        }
+        if self.ema_alpha.is_nan() || self.ema_alpha <= 0.0 || self.ema_alpha > 1.0 {
+            return Err("EMA alpha must be in (0, 1]");
+        }
+        if self.snap_threshold_g.is_nan() || self.snap_threshold_g <= 0.0 {
+            return Err("Snap threshold must be positive");
+        }
+        if self.stability_std_dev_g.is_nan() || self.stability_std_dev_g <= 0.0 {
+            return Err("Stability threshold must be positive");
+        }
+        Ok(())
+    }
+
+    /// Serialize to the blob format
+    pub fn to_blob(self) -> [u8; FILTER_BLOB_LEN] {
+        let window = |size: usize| u8::try_from(size).unwrap_or(u8::MAX);
+        let mut buf = [0u8; FILTER_BLOB_LEN];
+        buf[0] = FILTER_BLOB_VERSION;
+        buf[1] = window(self.median_window);
+        buf[2] = window(self.average_window);
+        buf[3] = window(self.stability_window);
+        buf[4..8].copy_from_slice(&self.ema_alpha.to_le_bytes());
+        buf[8..12].copy_from_slice(&self.snap_threshold_g.to_le_bytes());
+        buf[12..16].copy_from_slice(&self.stability_std_dev_g.to_le_bytes());
+        let crc = crc16(&buf[..FILTER_BLOB_LEN - 2]);
+        buf[FILTER_BLOB_LEN - 2..].copy_from_slice(&crc.to_le_bytes());
+        buf
+    }
+
+    /// Parse a settings blob
+    pub fn from_blob(blob: &[u8]) -> Result<Self, &'static str> {
+        if blob.first() != Some(&FILTER_BLOB_VERSION) {
+            return Err("Unsupported filter settings version");
+        }
+        if blob.len() != FILTER_BLOB_LEN {
+            return Err("Filter settings blob has the wrong length");
+        }
+        let crc = u16::from_le_bytes([blob[FILTER_BLOB_LEN - 2], blob[FILTER_BLOB_LEN - 1]]);
+        if crc != crc16(&blob[..FILTER_BLOB_LEN - 2]) {
+            return Err("Filter settings checksum mismatch");
+        }
+
+        let float = |i: usize| f32::from_le_bytes([blob[i], blob[i + 1], blob[i + 2], blob[i + 3]]);
+        let config = Self {
+            median_window: blob[1] as usize,
+            average_window: blob[2] as usize,
+            ema_alpha: float(4),
+            snap_threshold_g: float(8),
+            stability_window: blob[3] as usize,
+            stability_std_dev_g: float(12),
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Filter stages for these settings (disabled ones left out)
+    pub fn stages(&self) -> Vec<Box<dyn FilterStage>> {
+        let mut stages: Vec<Box<dyn FilterStage>> = Vec::new();
+        if self.median_window > 1 {
+            stages.push(Box::new(MedianFilter::new(self.median_window)));
+        }
+        if self.average_window > 1 {
+            stages.push(Box::new(MovingAverage::new(self.average_window)));
+        }
+        if self.ema_alpha < 1.0 {
+            stages.push(Box::new(AdaptiveEma::new(self.ema_alpha, self.snap_threshold_g)));
+        }
+        stages
+    }
+}
+
+/// Stability from the standard deviation over a window of readings
+pub struct StabilityDetector {
+    size: usize,
+    max_std_dev_g: f32,
+    window: VecDeque<f32>,
+    std_dev: f32,
+}
+
+impl StabilityDetector {
+    pub fn new(size: usize, max_std_dev_g: f32) -> Self {
+        Self { size: size.max(2), max_std_dev_g, window: VecDeque::with_capacity(size), std_dev: f32::INFINITY }
+    }
+
+    /// Add a reading; stable once the window is full and its spread is small
+    pub fn update(&mut self, grams: f32) -> bool {
+        push_window(&mut self.window, self.size, grams);
+        if self.window.len() < self.size {
+            self.std_dev = f32::INFINITY;
+            return false;
+        }
+
+        let n = self.window.len() as f32;
+        let mean = self.window.iter().sum::<f32>() / n;
+        let variance = self.window.iter().map(|g| (g - mean) * (g - mean)).sum::<f32>() / n;
+        self.std_dev = variance.sqrt();
+        self.std_dev <= self.max_std_dev_g
+    }
+
+    /// Standard deviation of the window (infinite until it is full)
+    pub fn std_dev(&self) -> f32 {
+        self.std_dev
+    }
+
+    pub fn reset(&mut self) {
+        self.window.clear();
+        self.std_dev = f32::INFINITY;
+    }
+}
+
+/// Output of [`WeightFilter::update`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FilteredWeight {
+    pub weight: f32,
+    pub stable: bool,
+    /// Standard deviation of the stability window (grams)
+    pub std_dev: f32,
+}
+
+/// Filter pipeline plus stability detection
+pub struct WeightFilter {
+    config: FilterConfig,
+    stages: Vec<Box<dyn FilterStage>>,
+    stability: StabilityDetector,
+    weight: f32,
+}
+
+impl WeightFilter {
+    pub fn new(config: FilterConfig) -> Self {
+        Self::with_stages(config, config.stages())
+    }
+
+    /// Custom pipeline; `config` only supplies the stability settings
+    pub fn with_stages(config: FilterConfig, stages: Vec<Box<dyn FilterStage>>) -> Self {
+        Self {
+            config,
+            stages,
+            stability: StabilityDetector::new(config.stability_window, config.stability_std_dev_g),
+            weight: 0.0,
+        }
+    }
+
+    pub fn config(&self) -> FilterConfig {
+        self.config
+    }
+
+    /// Switch to new settings, continuing from the current weight
+    pub fn set_config(&mut self, config: FilterConfig) {
+        let weight = self.weight;
+        *self = Self::new(config);
+        self.reset(weight);
+    }
+
+    /// Filter the next calibrated reading
+    pub fn update(&mut self, grams: f32) -> FilteredWeight {
+        let stable = self.stability.update(grams);
+        self.weight = self.stages.iter_mut().fold(grams, |value, stage| stage.update(value));
+        FilteredWeight { weight: self.weight, stable, std_dev: self.stability.std_dev() }
+    }
+
+    /// Continue from `grams` (after tare or calibration); unstable until the
+    /// stability window has filled again
+    pub fn reset(&mut self, grams: f32) {
+        for stage in &mut self.stages {
+            stage.reset(grams);
+        }
+        self.stability.reset();
+        self.weight = grams;
+    }
+
+    pub fn weight(&self) -> f32 {
+        self.weight
+    }
+
+    pub fn std_dev(&self) -> f32 {
+        self.stability.std_dev()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(stage: &mut dyn FilterStage, readings: &[f32]) -> Vec<f32> {
+        readings.iter().map(|&g| stage.update(g)).collect()
+    }
+
+    #[test]
+    fn median_rejects_spikes_with_odd_window() {
+        let mut median = MedianFilter::new(3);
+        assert_eq!(run(&mut median, &[10.0, 10.0, 100.0, 10.0, 12.0]), [10.0, 10.0, 10.0, 10.0, 12.0]);
+    }
+
+    #[test]
+    fn median_of_even_window_is_mean_of_middle_two() {
+        let mut median = MedianFilter::new(4);
+        assert_eq!(run(&mut median, &[1.0, 3.0, 10.0, 2.0]), [1.0, 2.0, 3.0, 2.5]);
+        // Full window slides: [3, 10, 2, 4]
+        assert_eq!(median.update(4.0), 3.5);
+
+        median.reset(7.0);
+        assert_eq!(median.update(9.0), 8.0);
+    }
+
+    #[test]
+    fn moving_average_over_window() {
+        let mut average = MovingAverage::new(3);
+        assert_eq!(run(&mut average, &[3.0, 6.0, 9.0, 12.0]), [3.0, 4.5, 6.0, 9.0]);
+
+        average.reset(0.0);
+        assert_eq!(average.update(6.0), 3.0);
+    }
+
+    #[test]
+    fn ema_smooths_small_changes_and_snaps_to_steps() {
+        let mut ema = AdaptiveEma::new(0.5, 10.0);
+        // The first reading is taken as is
+        assert_eq!(ema.update(100.0), 100.0);
+        assert_eq!(ema.update(104.0), 102.0);
+        // Above the threshold: jump
+        assert_eq!(ema.update(150.0), 150.0);
+        // At the threshold: still smoothed
+        assert_eq!(ema.update(160.0), 155.0);
+        assert_eq!(ema.update(149.0), 152.0);
+
+        ema.reset(0.0);
+        assert_eq!(ema.update(4.0), 2.0);
+    }
+
+    #[test]
+    fn stability_at_std_dev_threshold() {
+        let mut stability = StabilityDetector::new(4, 1.0);
+        // Not stable until the window is full
+        for g in [10.0, 12.0, 10.0] {
+            assert!(!stability.update(g));
+            assert_eq!(stability.std_dev(), f32::INFINITY);
+        }
+        // Mean 11, standard deviation exactly 1
+        assert!(stability.update(12.0));
+        assert_eq!(stability.std_dev(), 1.0);
+        // [12, 10, 12, 13]: spread above the threshold
+        assert!(!stability.update(13.0));
+        assert!(stability.std_dev() > 1.0);
+
+        stability.reset();
+        assert!(!stability.update(11.0));
+        assert_eq!(stability.std_dev(), f32::INFINITY);
+    }
+
+    #[test]
+    fn weight_filter_runs_enabled_stages() {
+        let config = FilterConfig { median_window: 1, average_window: 1, ema_alpha: 1.0, ..Default::default() };
+        assert!(config.stages().is_empty());
+        assert_eq!(FilterConfig::default().stages().len(), 2);
+
+        // No stages: the reading passes through, stable once the window is full
+        let mut filter = WeightFilter::new(FilterConfig { stability_window: 2, ..config });
+        assert_eq!(filter.update(50.0), FilteredWeight { weight: 50.0, stable: false, std_dev: f32::INFINITY });
+        assert_eq!(filter.update(50.0), FilteredWeight { weight: 50.0, stable: true, std_dev: 0.0 });
+    }
+
+    #[test]
+    fn validates_settings() {
+        assert_eq!(FilterConfig::default().validate(), Ok(()));
+
+        let invalid = [
+            (FilterConfig { median_window: 0, ..Default::default() }, "Filter window out of range"),
+            (FilterConfig { average_window: MAX_WINDOW + 1, ..Default::default() }, "Filter window out of range"),
+            (FilterConfig { stability_window: 1, ..Default::default() }, "Stability window out of range"),
+            (FilterConfig { ema_alpha: 0.0, ..Default::default() }, "EMA alpha must be in (0, 1]"),
+            (FilterConfig { ema_alpha: 1.5, ..Default::default() }, "EMA alpha must be in (0, 1]"),
+            (FilterConfig { ema_alpha: f32::NAN, ..Default::default() }, "EMA alpha must be in (0, 1]"),
+            (FilterConfig { snap_threshold_g: 0.0, ..Default::default() }, "Snap threshold must be positive"),
+            (FilterConfig { stability_std_dev_g: -1.0, ..Default::default() }, "Stability threshold must be positive"),
+        ];
+        for (config, error) in invalid {
+            assert_eq!(config.validate(), Err(error), "{:?}", config);
+        }
+        assert_eq!(FilterConfig { ema_alpha: 1.0, ..Default::default() }.validate(), Ok(()));
+    }
+
+    #[test]
+    fn blob_round_trip() {
+        let config = FilterConfig {
+            median_window: 5,
+            average_window: 4,
+            ema_alpha: 0.4,
+            snap_threshold_g: 25.0,
+            stability_window: 20,
+            stability_std_dev_g: 1.5,
+        };
+        assert_eq!(FilterConfig::from_blob(&config.to_blob()), Ok(config));
+
+        let mut corrupt = config.to_blob();
+        corrupt[1] = 7;
+        assert_eq!(FilterConfig::from_blob(&corrupt), Err("Filter settings checksum mismatch"));
+        assert_eq!(FilterConfig::from_blob(&corrupt[..10]), Err("Filter settings blob has the wrong length"));
+        assert_eq!(FilterConfig::from_blob(&[0u8; FILTER_BLOB_LEN]), Err("Unsupported filter settings version"));
+    }
+}
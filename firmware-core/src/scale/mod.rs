@@ -0,0 +1,24 @@
+//! Weight processing and the NAU7802 driver.
+//!
+//! The driver works on any embedded-hal I2C bus; the firmware passes its
+//! shared ESP-IDF bus, the host tests `nau7802_sim::SimulatedNau7802`.
+
+/// Calibration curve fitting and its NVS format
+pub mod calibration;
+
+/// Auto-zero tracking and temperature drift compensation
+pub mod drift;
+
+/// Weight filter pipeline and stability detection
+pub mod filter;
+
+/// NAU7802 driver
+pub mod nau7802;
+
+/// Host-side simulated NAU7802 with a scripted load profile
+#[cfg(any(test, feature = "nau7802-sim"))]
+pub mod nau7802_sim;
+
+/// Host-side replay of recorded raw readings through the weight filter
+#[cfg(any(test, feature = "scale-trace"))]
+pub mod trace;
@@ -0,0 +1,927 @@
+//! NAU7802 24-bit ADC Scale Driver
+//!
+//! SparkFun Qwiic Scale uses the NAU7802 chip for high-precision weight measurement.
+//!
+//! I2C Address: 0x2A
+//!
+//! CrowPanel Advance 7.0" I2C-OUT Connector:
+//! ```text
+//! ┌──────┬──────┬──────┬──────┐
+//! │ Pin1 │ Pin2 │ Pin3 │ Pin4 │
+//! │ 3V3  │ SDA  │ SCL  │ GND  │
+//! │      │ IO19 │ IO20 │      │
+//! └──────┴──────┴──────┴──────┘
+//! ```
+//!
+//! Pinout (SparkFun Qwiic Scale):
+//!   MCU Side:
+//!     - GND: Ground
+//!     - 3V3: 3.3V power
+//!     - SDA: I2C Data (IO19)
+//!     - SCL: I2C Clock (IO20)
+//!     - INT: Interrupt (optional)
+//!     - AVDD: Analog VDD (connect to 3V3)
+//!
+//!   Load Cell Side (green terminal):
+//!     - RED: Excitation+ (E+)
+//!     - BLK: Excitation- (E-)
+//!     - WHT: Signal- (A-)
+//!     - GRN: Signal+ (A+)
+//!
+//! The driver works on any `embedded_hal::i2c::I2c` bus: the ESP-IDF
+//! `I2cDriver` on the device, `nau7802_sim::SimulatedNau7802` on the host.
+//! Waits go through an `embedded_hal::delay::DelayNs` (`FreeRtos` on the
+//! device), so host tests don't sleep.
+
+use embedded_hal::delay::DelayNs;
+use embedded_hal::i2c::I2c;
+use log::{info, warn};
+
+use super::calibration::{self, CurveKind};
+use super::drift::{DriftCompensator, DriftConfig, TempSource};
+use super::filter::{FilterConfig, FilteredWeight, WeightFilter, TRACE_MARKER};
+use crate::crc::crc16;
+pub use super::calibration::{CalPoint, Calibration};
+
+/// NAU7802 I2C address
+pub const NAU7802_ADDR: u8 = 0x2A;
+
+/// NAU7802 Register addresses
+#[allow(dead_code)]
+mod reg {
+    pub const PU_CTRL: u8 = 0x00;      // Power-up control
+    pub const CTRL1: u8 = 0x01;        // Control 1
+    pub const CTRL2: u8 = 0x02;        // Control 2
+    pub const OCAL1_B2: u8 = 0x03;     // Offset calibration
+    pub const OCAL1_B1: u8 = 0x04;
+    pub const OCAL1_B0: u8 = 0x05;
+    pub const GCAL1_B3: u8 = 0x06;     // Gain calibration
+    pub const GCAL1_B2: u8 = 0x07;
+    pub const GCAL1_B1: u8 = 0x08;
+    pub const GCAL1_B0: u8 = 0x09;
+    pub const I2C_CTRL: u8 = 0x11;     // I2C control
+    pub const ADCO_B2: u8 = 0x12;      // ADC output (MSB)
+    pub const ADCO_B1: u8 = 0x13;      // ADC output
+    pub const ADCO_B0: u8 = 0x14;      // ADC output (LSB)
+    pub const ADC: u8 = 0x15;          // ADC control
+    pub const PGA: u8 = 0x1B;          // PGA control
+    pub const PWR_CTRL: u8 = 0x1C;     // Power control
+    pub const REVISION: u8 = 0x1F;     // Revision ID
+}
+
+/// PU_CTRL register bits
+#[allow(dead_code)]
+mod pu_ctrl {
+    pub const RR: u8 = 0x01;           // Register reset
+    pub const PUD: u8 = 0x02;          // Power up digital
+    pub const PUA: u8 = 0x04;          // Power up analog
+    pub const PUR: u8 = 0x08;          // Power up ready (read-only)
+    pub const CS: u8 = 0x10;           // Cycle start
+    pub const CR: u8 = 0x20;           // Cycle ready (read-only)
+    pub const OSCS: u8 = 0x40;         // Oscillator select
+    pub const AVDDS: u8 = 0x80;        // AVDD source select
+}
+
+/// CTRL2 register bits
+mod ctrl2 {
+    pub const CALMOD: u8 = 0x03;       // Calibration mode
+    pub const CALS: u8 = 0x04;         // Start calibration (cleared when done)
+    pub const CAL_ERR: u8 = 0x08;      // Calibration error (read-only)
+    pub const CRS: u8 = 0x70;          // Conversion rate
+    pub const CHS: u8 = 0x80;          // Channel select
+}
+
+/// I2C_CTRL register bits
+mod i2c_ctrl {
+    pub const TS: u8 = 0x02;           // Temperature sensor to PGA input
+}
+
+/// Readings between internal temperature measurements (~60 s at 10 SPS)
+const TEMP_INTERVAL_READINGS: u32 = 600;
+
+/// Conversions discarded after switching the ADC input
+const SETTLE_READINGS: u8 = 2;
+
+/// Internal temperature sensor, typical datasheet figures at gain 1
+/// (not calibrated per chip; drift compensation only uses differences)
+const TEMP_MV_AT_25C: f32 = 360.0;
+const TEMP_MV_PER_C: f32 = 0.109;
+
+/// ADC full scale at gain 1 (±VREF/2 with the 3.3V LDO)
+const FULL_SCALE_MV: f32 = 1650.0;
+
+/// What the ADC is converting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdcInput {
+    /// Load cell
+    Weight,
+    /// Internal temperature sensor, `discard` conversions to go
+    Temperature { discard: u8 },
+    /// Back on the load cell, `discard` conversions to go
+    Settling { discard: u8 },
+}
+
+/// Sample rates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(dead_code)]
+pub enum SampleRate {
+    Sps10 = 0,
+    Sps20 = 1,
+    Sps40 = 2,
+    Sps80 = 3,
+    Sps320 = 7,
+}
+
+impl SampleRate {
+    const ALL: [SampleRate; 5] = [SampleRate::Sps10, SampleRate::Sps20, SampleRate::Sps40, SampleRate::Sps80, SampleRate::Sps320];
+
+    /// Samples per second
+    pub fn sps(self) -> u16 {
+        match self {
+            SampleRate::Sps10 => 10,
+            SampleRate::Sps20 => 20,
+            SampleRate::Sps40 => 40,
+            SampleRate::Sps80 => 80,
+            SampleRate::Sps320 => 320,
+        }
+    }
+
+    pub fn from_sps(sps: u16) -> Option<Self> {
+        Self::ALL.into_iter().find(|rate| rate.sps() == sps)
+    }
+
+    fn from_bits(bits: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|rate| *rate as u8 == bits)
+    }
+}
+
+/// PGA Gain settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(dead_code)]
+pub enum Gain {
+    X1 = 0,
+    X2 = 1,
+    X4 = 2,
+    X8 = 3,
+    X16 = 4,
+    X32 = 5,
+    X64 = 6,
+    X128 = 7,
+}
+
+impl Gain {
+    const ALL: [Gain; 8] = [Gain::X1, Gain::X2, Gain::X4, Gain::X8, Gain::X16, Gain::X32, Gain::X64, Gain::X128];
+
+    /// Amplification factor (1-128)
+    pub fn factor(self) -> u8 {
+        1 << self as u8
+    }
+
+    pub fn from_factor(factor: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|gain| gain.factor() == factor)
+    }
+
+    fn from_bits(bits: u8) -> Option<Self> {
+        Self::ALL.get(bits as usize).copied()
+    }
+}
+
+/// LDO Voltage settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(dead_code)]
+pub enum LdoVoltage {
+    V2_4 = 0b111,
+    V2_7 = 0b110,
+    V3_0 = 0b101,
+    V3_3 = 0b100,
+    V3_6 = 0b011,
+    V3_9 = 0b010,
+    V4_2 = 0b001,
+    V4_5 = 0b000,
+}
+
+impl LdoVoltage {
+    const ALL: [LdoVoltage; 8] = [
+        LdoVoltage::V2_4,
+        LdoVoltage::V2_7,
+        LdoVoltage::V3_0,
+        LdoVoltage::V3_3,
+        LdoVoltage::V3_6,
+        LdoVoltage::V3_9,
+        LdoVoltage::V4_2,
+        LdoVoltage::V4_5,
+    ];
+
+    /// Output voltage in millivolts (2400-4500)
+    pub fn millivolts(self) -> u16 {
+        4500 - 300 * self as u16
+    }
+
+    pub fn from_millivolts(millivolts: u16) -> Option<Self> {
+        Self::ALL.into_iter().find(|ldo| ldo.millivolts() == millivolts)
+    }
+
+    fn from_bits(bits: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|ldo| *ldo as u8 == bits)
+    }
+}
+
+/// Input channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    Ch1 = 0,
+    Ch2 = 1,
+}
+
+impl Channel {
+    /// Channel number (1 or 2)
+    pub fn number(self) -> u8 {
+        self as u8 + 1
+    }
+
+    pub fn from_number(number: u8) -> Option<Self> {
+        match number {
+            1 => Some(Channel::Ch1),
+            2 => Some(Channel::Ch2),
+            _ => None,
+        }
+    }
+}
+
+/// AFE calibration modes (CALMOD)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(dead_code)]
+pub enum AfeCalibration {
+    /// Internal offset (inputs shorted inside the chip)
+    InternalOffset = 0b00,
+    /// System offset (the load cell as connected)
+    SystemOffset = 0b10,
+    /// System gain (full-scale signal on the inputs)
+    SystemGain = 0b11,
+}
+
+/// ADC configuration blob version
+const ADC_BLOB_VERSION: u8 = 1;
+
+/// ADC configuration blob length
+pub const ADC_BLOB_LEN: usize = 8;
+
+/// ADC settings (persisted by the scale manager)
+///
+/// Blob: `[version=1, rate bits, gain bits, LDO bits, channel, reserved, crc16 LE]`
+/// with the CRC-16/CCITT-FALSE of the bytes before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AdcConfig {
+    pub sample_rate: SampleRate,
+    pub gain: Gain,
+    pub ldo: LdoVoltage,
+    pub channel: Channel,
+}
+
+impl Default for AdcConfig {
+    fn default() -> Self {
+        Self {
+            // 10 SPS for stable readings - chip does internal averaging
+            sample_rate: SampleRate::Sps10,
+            // 128x for load cells
+            gain: Gain::X128,
+            ldo: LdoVoltage::V3_3,
+            channel: Channel::Ch1,
+        }
+    }
+}
+
+impl AdcConfig {
+    /// Serialize to the blob format
+    pub fn to_blob(self) -> [u8; ADC_BLOB_LEN] {
+        let mut buf = [0u8; ADC_BLOB_LEN];
+        buf[0] = ADC_BLOB_VERSION;
+        buf[1] = self.sample_rate as u8;
+        buf[2] = self.gain as u8;
+        buf[3] = self.ldo as u8;
+        buf[4] = self.channel as u8;
+        let crc = crc16(&buf[..ADC_BLOB_LEN - 2]);
+        buf[ADC_BLOB_LEN - 2..].copy_from_slice(&crc.to_le_bytes());
+        buf
+    }
+
+    /// Parse an ADC configuration blob
+    pub fn from_blob(blob: &[u8]) -> Result<Self, &'static str> {
+        if blob.first() != Some(&ADC_BLOB_VERSION) {
+            return Err("Unsupported ADC configuration version");
+        }
+        if blob.len() != ADC_BLOB_LEN {
+            return Err("ADC configuration blob has the wrong length");
+        }
+        let crc = u16::from_le_bytes([blob[ADC_BLOB_LEN - 2], blob[ADC_BLOB_LEN - 1]]);
+        if crc != crc16(&blob[..ADC_BLOB_LEN - 2]) {
+            return Err("ADC configuration checksum mismatch");
+        }
+
+        Ok(Self {
+            sample_rate: SampleRate::from_bits(blob[1]).ok_or("Invalid sample rate")?,
+            gain: Gain::from_bits(blob[2]).ok_or("Invalid gain")?,
+            ldo: LdoVoltage::from_bits(blob[3]).ok_or("Invalid LDO voltage")?,
+            channel: match blob[4] {
+                0 => Channel::Ch1,
+                1 => Channel::Ch2,
+                _ => return Err("Invalid channel"),
+            },
+        })
+    }
+}
+
+/// NAU7802 Scale driver state
+pub struct Nau7802State {
+    /// Calibration data
+    pub calibration: Calibration,
+    /// ADC settings (applied by `init`)
+    pub adc_config: AdcConfig,
+    /// Whether the scale has been initialized
+    pub initialized: bool,
+    /// Last raw reading
+    pub last_raw: i32,
+    /// Filtered weight in grams
+    pub weight_grams: f32,
+    /// Filter pipeline and stability detection
+    pub filter: WeightFilter,
+    /// Weight stability flag
+    pub stable: bool,
+    /// Log every raw reading for recording filter traces
+    pub trace_logging: bool,
+    /// Zero tracking and temperature compensation
+    pub drift: DriftCompensator,
+    /// Current ADC input
+    pub adc_input: AdcInput,
+    /// Weight readings since the last internal temperature measurement
+    pub readings_since_temp: u32,
+}
+
+impl Nau7802State {
+    /// Create a new scale state
+    pub fn new() -> Self {
+        Self {
+            calibration: Calibration::default(),
+            adc_config: AdcConfig::default(),
+            initialized: false,
+            last_raw: 0,
+            weight_grams: 0.0,
+            filter: WeightFilter::new(FilterConfig::default()),
+            stable: false,
+            trace_logging: false,
+            drift: DriftCompensator::new(DriftConfig::default()),
+            adc_input: AdcInput::Weight,
+            readings_since_temp: TEMP_INTERVAL_READINGS,
+        }
+    }
+
+    /// Restart filtering from `weight_grams` (after tare or calibration)
+    pub fn reset_weight(&mut self, weight_grams: f32) {
+        self.filter.reset(weight_grams);
+        self.weight_grams = weight_grams;
+        self.stable = false;
+    }
+}
+
+impl Default for Nau7802State {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Initialize the NAU7802
+pub fn init<I: I2c, D: DelayNs>(i2c: &mut I, delay: &mut D, state: &mut Nau7802State) -> Result<(), Nau7802Error> {
+    info!("Initializing NAU7802 scale at 0x{:02X}", NAU7802_ADDR);
+
+    // Check if device is present
+    let revision = read_reg(i2c, reg::REVISION)?;
+    info!("  NAU7802 revision: 0x{:02X}", revision);
+
+    // Reset the device
+    write_reg(i2c, reg::PU_CTRL, pu_ctrl::RR)?;
+    delay.delay_ms(10);
+    write_reg(i2c, reg::PU_CTRL, 0x00)?;
+
+    // Power up digital and analog
+    write_reg(i2c, reg::PU_CTRL, pu_ctrl::PUD | pu_ctrl::PUA)?;
+
+    // Wait for power-up ready
+    let mut timeout = 100;
+    loop {
+        let status = read_reg(i2c, reg::PU_CTRL)?;
+        if (status & pu_ctrl::PUR) != 0 {
+            info!("  NAU7802 powered up");
+            break;
+        }
+        timeout -= 1;
+        if timeout == 0 {
+            warn!("  NAU7802 power-up timeout");
+            return Err(Nau7802Error::Timeout);
+        }
+        delay.delay_ms(1);
+    }
+
+    // Configure sample rate, gain, LDO and channel
+    let config = state.adc_config;
+    write_adc_config(i2c, &config)?;
+    info!("  ADC: {} SPS, gain {}x, LDO {}mV, channel {}",
+          config.sample_rate.sps(), config.gain.factor(), config.ldo.millivolts(), config.channel.number());
+
+    // Enable internal LDO
+    let ctrl1 = read_reg(i2c, reg::CTRL1)?;
+    write_reg(i2c, reg::CTRL1, ctrl1 | 0x80)?; // VLDO bit
+
+    // Start conversion cycle
+    let pu_ctrl_val = read_reg(i2c, reg::PU_CTRL)?;
+    write_reg(i2c, reg::PU_CTRL, pu_ctrl_val | pu_ctrl::CS)?;
+
+    // Null the AFE offset for these settings
+    calibrate_afe(i2c, delay, AfeCalibration::InternalOffset)?;
+
+    state.initialized = true;
+    info!("  NAU7802 initialization complete");
+
+    Ok(())
+}
+
+/// Set sample rate
+pub fn set_sample_rate<I: I2c>(i2c: &mut I, rate: SampleRate) -> Result<(), Nau7802Error> {
+    let ctrl2 = read_reg(i2c, reg::CTRL2)?;
+    let new_ctrl2 = (ctrl2 & !ctrl2::CRS) | ((rate as u8) << 4);
+    write_reg(i2c, reg::CTRL2, new_ctrl2)
+}
+
+/// Select the input channel
+pub fn set_channel<I: I2c>(i2c: &mut I, channel: Channel) -> Result<(), Nau7802Error> {
+    let ctrl2 = read_reg(i2c, reg::CTRL2)?;
+    let new_ctrl2 = match channel {
+        Channel::Ch1 => ctrl2 & !ctrl2::CHS,
+        Channel::Ch2 => ctrl2 | ctrl2::CHS,
+    };
+    write_reg(i2c, reg::CTRL2, new_ctrl2)
+}
+
+/// Write sample rate, gain, LDO and channel
+fn write_adc_config<I: I2c>(i2c: &mut I, config: &AdcConfig) -> Result<(), Nau7802Error> {
+    set_sample_rate(i2c, config.sample_rate)?;
+    set_gain(i2c, config.gain)?;
+    set_ldo(i2c, config.ldo)?;
+    set_channel(i2c, config.channel)
+}
+
+/// Run the chip's offset or gain calibration (CALS/CALMOD) and wait for it.
+/// Takes a few conversions; readings before it completes are discarded.
+pub fn calibrate_afe<I: I2c, D: DelayNs>(i2c: &mut I, delay: &mut D, mode: AfeCalibration) -> Result<(), Nau7802Error> {
+    let ctrl2 = read_reg(i2c, reg::CTRL2)?;
+    write_reg(i2c, reg::CTRL2, (ctrl2 & !ctrl2::CALMOD) | mode as u8 | ctrl2::CALS)?;
+
+    // Up to 2 seconds (a handful of conversions at 10 SPS)
+    for _ in 0..200 {
+        delay.delay_ms(10);
+        let status = read_reg(i2c, reg::CTRL2)?;
+        if (status & ctrl2::CALS) == 0 {
+            if (status & ctrl2::CAL_ERR) != 0 {
+                warn!("  NAU7802 {:?} calibration failed", mode);
+                return Err(Nau7802Error::CalibrationFailed);
+            }
+            info!("  NAU7802 {:?} calibration complete", mode);
+            return Ok(());
+        }
+    }
+    warn!("  NAU7802 {:?} calibration timeout", mode);
+    Err(Nau7802Error::Timeout)
+}
+
+/// Switch to new ADC settings and recalibrate the AFE.
+/// A gain change rescales the weight calibration to match (re-tare for best
+/// accuracy); a channel change leaves it as it is. If the settings can't be
+/// applied, the old ones are restored.
+pub fn apply_adc_config<I: I2c, D: DelayNs>(i2c: &mut I, delay: &mut D, state: &mut Nau7802State, config: AdcConfig) -> Result<(), Nau7802Error> {
+    let old = state.adc_config;
+    select_weight_input(i2c, state)?;
+    if let Err(e) = write_adc_config(i2c, &config).and_then(|()| calibrate_afe(i2c, delay, AfeCalibration::InternalOffset)) {
+        warn!("  ADC settings not applied ({:?}), restoring the previous ones", e);
+        if let Err(e) = write_adc_config(i2c, &old).and_then(|()| calibrate_afe(i2c, delay, AfeCalibration::InternalOffset)) {
+            warn!("  Restoring the previous ADC settings failed: {:?}", e);
+        }
+        state.adc_input = AdcInput::Settling { discard: SETTLE_READINGS };
+        return Err(e);
+    }
+    state.adc_config = config;
+
+    if config.gain != old.gain {
+        // Readings scale with the gain
+        let ratio = config.gain.factor() as f32 / old.gain.factor() as f32;
+        let cal = &mut state.calibration;
+        cal.zero_offset = (cal.zero_offset as f32 * ratio) as i32;
+        cal.cal_factor *= ratio;
+        cal.quadratic /= ratio * ratio;
+        info!("  Calibration rescaled for gain {}x: zero_offset={}, cal_factor={}",
+              config.gain.factor(), cal.zero_offset, cal.cal_factor);
+    }
+    if config.channel != old.channel {
+        warn!("  Input channel changed: tare and calibrate the scale again");
+    }
+
+    state.adc_input = AdcInput::Settling { discard: SETTLE_READINGS };
+    let weight = state.weight_grams;
+    state.reset_weight(weight);
+    Ok(())
+}
+
+/// Set PGA gain
+pub fn set_gain<I: I2c>(i2c: &mut I, gain: Gain) -> Result<(), Nau7802Error> {
+    let ctrl1 = read_reg(i2c, reg::CTRL1)?;
+    let new_ctrl1 = (ctrl1 & 0xF8) | (gain as u8);
+    write_reg(i2c, reg::CTRL1, new_ctrl1)
+}
+
+/// Set LDO voltage
+pub fn set_ldo<I: I2c>(i2c: &mut I, voltage: LdoVoltage) -> Result<(), Nau7802Error> {
+    let ctrl1 = read_reg(i2c, reg::CTRL1)?;
+    let new_ctrl1 = (ctrl1 & 0xC7) | ((voltage as u8) << 3);
+    write_reg(i2c, reg::CTRL1, new_ctrl1)
+}
+
+/// Check if data is ready
+pub fn data_ready<I: I2c>(i2c: &mut I) -> Result<bool, Nau7802Error> {
+    let status = read_reg(i2c, reg::PU_CTRL)?;
+    Ok((status & pu_ctrl::CR) != 0)
+}
+
+/// Read raw ADC value (24-bit signed)
+pub fn read_raw<I: I2c>(i2c: &mut I, state: &mut Nau7802State) -> Result<i32, Nau7802Error> {
+    // Read 3 bytes of ADC data
+    let b2 = read_reg(i2c, reg::ADCO_B2)? as i32;
+    let b1 = read_reg(i2c, reg::ADCO_B1)? as i32;
+    let b0 = read_reg(i2c, reg::ADCO_B0)? as i32;
+
+    // Combine into 24-bit value
+    let mut raw = (b2 << 16) | (b1 << 8) | b0;
+
+    // Sign extend from 24-bit to 32-bit
+    if (raw & 0x800000) != 0 {
+        raw |= 0xFF000000u32 as i32;
+    }
+
+    state.last_raw = raw;
+    Ok(raw)
+}
+
+/// Read weight in grams (with filtering and stability detection)
+pub fn read_weight<I: I2c>(i2c: &mut I, state: &mut Nau7802State) -> Result<f32, Nau7802Error> {
+    if !state.initialized {
+        return Err(Nau7802Error::NotInitialized);
+    }
+
+    // Check if data is ready
+    if !data_ready(i2c)? {
+        return Ok(state.weight_grams); // Return last value
+    }
+
+    let raw = read_raw(i2c, state)?;
+
+    match state.adc_input {
+        AdcInput::Weight => {}
+        AdcInput::Temperature { discard } => {
+            if discard > 0 {
+                state.adc_input = AdcInput::Temperature { discard: discard - 1 };
+            } else {
+                if state.drift.config().temp_source == TempSource::Internal {
+                    state.drift.set_temperature(internal_temperature_c(raw));
+                }
+                select_weight_input(i2c, state)?;
+            }
+            return Ok(state.weight_grams);
+        }
+        AdcInput::Settling { discard } => {
+            state.adc_input = match discard {
+                0 | 1 => AdcInput::Weight,
+                _ => AdcInput::Settling { discard: discard - 1 },
+            };
+            return Ok(state.weight_grams);
+        }
+    }
+
+    if state.trace_logging {
+        info!("{} {}", TRACE_MARKER, raw);
+    }
+
+    process_weight(state, raw);
+
+    // Measure the temperature now and then
+    if state.drift.config().temp_source == TempSource::Internal {
+        state.readings_since_temp += 1;
+        if state.readings_since_temp >= TEMP_INTERVAL_READINGS {
+            state.readings_since_temp = 0;
+            select_temperature_input(i2c, state)?;
+        }
+    }
+
+    Ok(state.weight_grams)
+}
+
+/// Run a raw weight reading through the pipeline: calibration, drift
+/// compensation, the weight filter and zero tracking (`scale::trace` replays
+/// recorded readings through this as well)
+pub fn process_weight(state: &mut Nau7802State, raw: i32) -> FilteredWeight {
+    // Convert to grams using calibration, compensate drift, then filter
+    let grams = state.drift.compensate(state.calibration.grams(raw));
+    let filtered = state.filter.update(grams);
+    state.weight_grams = filtered.weight;
+    state.stable = filtered.stable;
+
+    // Walk the zero towards a stable empty reading
+    let shift = state.drift.track_zero(filtered.weight, filtered.stable, state.calibration.cal_factor);
+    state.calibration.zero_offset += shift;
+
+    filtered
+}
+
+/// Switch the ADC to the internal temperature sensor (gain 1)
+fn select_temperature_input<I: I2c>(i2c: &mut I, state: &mut Nau7802State) -> Result<(), Nau7802Error> {
+    set_gain(i2c, Gain::X1)?;
+    let ctrl = read_reg(i2c, reg::I2C_CTRL)?;
+    write_reg(i2c, reg::I2C_CTRL, ctrl | i2c_ctrl::TS)?;
+    state.adc_input = AdcInput::Temperature { discard: SETTLE_READINGS };
+    Ok(())
+}
+
+/// Switch the ADC back to the load cell (configured gain)
+pub fn select_weight_input<I: I2c>(i2c: &mut I, state: &mut Nau7802State) -> Result<(), Nau7802Error> {
+    if state.adc_input == AdcInput::Weight {
+        return Ok(());
+    }
+    let ctrl = read_reg(i2c, reg::I2C_CTRL)?;
+    write_reg(i2c, reg::I2C_CTRL, ctrl & !i2c_ctrl::TS)?;
+    set_gain(i2c, state.adc_config.gain)?;
+    state.adc_input = AdcInput::Settling { discard: SETTLE_READINGS };
+    Ok(())
+}
+
+/// Temperature (°C) of an internal sensor reading at gain 1
+pub fn internal_temperature_c(raw: i32) -> f32 {
+    let mv = raw as f32 * FULL_SCALE_MV / (1 << 23) as f32;
+    25.0 + (mv - TEMP_MV_AT_25C) / TEMP_MV_PER_C
+}
+
+/// Average raw reading of a settled scale (trimmed mean of 30 samples)
+pub fn read_average<I: I2c, D: DelayNs>(i2c: &mut I, delay: &mut D, state: &mut Nau7802State) -> Result<i32, Nau7802Error> {
+    // Not in the middle of a temperature measurement
+    select_weight_input(i2c, state)?;
+    state.adc_input = AdcInput::Weight;
+
+    // Wait for scale to settle before sampling
+    info!("  Waiting for scale to settle (1 second)...");
+    delay.delay_ms(1000);
+
+    // Take samples for averaging (reduced to avoid watchdog)
+    let samples = 30;
+    let mut readings = [0i32; 30];
+
+    for reading in readings.iter_mut() {
+        // Wait for data ready
+        while !data_ready(i2c)? {
+            delay.delay_ms(10);
+        }
+        *reading = read_raw(i2c, state)?;
+    }
+
+    info!("  Raw readings: min={}, max={}, range={}",
+          readings.iter().min().unwrap(),
+          readings.iter().max().unwrap(),
+          readings.iter().max().unwrap() - readings.iter().min().unwrap());
+
+    // Sort readings for trimmed mean (discard highest and lowest 5 values)
+    readings.sort();
+    let trim = 5;
+    let trimmed = &readings[trim..samples - trim];
+
+    // Calculate average of trimmed values
+    let sum: i64 = trimmed.iter().map(|&x| x as i64).sum();
+    let count = trimmed.len() as i64;
+    let avg_raw = (sum / count) as i32;
+    info!("  Average raw value (trimmed): {} (from {} middle samples)", avg_raw, count);
+
+    // Sanity check: range shouldn't be too extreme
+    let range = readings.iter().max().unwrap() - readings.iter().min().unwrap();
+    if range > 100000 {
+        warn!("  Warning: readings are very noisy (range={}), result may be inaccurate", range);
+    }
+
+    Ok(avg_raw)
+}
+
+/// Tare the scale (set current weight as zero)
+pub fn tare<I: I2c, D: DelayNs>(i2c: &mut I, delay: &mut D, state: &mut Nau7802State) -> Result<(), Nau7802Error> {
+    info!("=== SCALE TARE START ===");
+    info!("  Current zero_offset: {}", state.calibration.zero_offset);
+    info!("  Current cal_factor: {}", state.calibration.cal_factor);
+
+    // Null the AFE offset first; the zero is measured after it
+    select_weight_input(i2c, state)?;
+    calibrate_afe(i2c, delay, AfeCalibration::InternalOffset)?;
+
+    let new_zero_offset = read_average(i2c, delay, state)?;
+    info!("  NEW zero_offset: {}", new_zero_offset);
+
+    state.calibration.zero_offset = new_zero_offset;
+
+    // Reset filtered state; drift is relative to this zero from now on
+    state.drift.reset();
+    state.reset_weight(0.0);
+
+    info!("=== TARE COMPLETE ===");
+    info!("  Final zero_offset: {}", state.calibration.zero_offset);
+    info!("  Final cal_factor: {}", state.calibration.cal_factor);
+    Ok(())
+}
+
+/// Measure a known weight on the (tared) scale as a calibration point
+pub fn measure_point<I: I2c, D: DelayNs>(i2c: &mut I, delay: &mut D, state: &mut Nau7802State, known_weight_grams: f32) -> Result<CalPoint, Nau7802Error> {
+    info!("=== SCALE CALIBRATION POINT ===");
+    info!("  Known weight: {} grams", known_weight_grams);
+    info!("  Current zero_offset: {}", state.calibration.zero_offset);
+
+    let avg_raw = read_average(i2c, delay, state)?;
+    let delta = avg_raw - state.calibration.zero_offset;
+    info!("  Delta from zero: {} (avg_raw {} - zero_offset {})",
+          delta, avg_raw, state.calibration.zero_offset);
+
+    // Delta must be positive: the weight has to raise the readings
+    if delta <= 0 {
+        warn!("  Calibration point FAILED: delta ({}) - weight did not increase readings!", delta);
+        warn!("  This usually means: load cell wiring issue, defective load cell, not mounted correctly, or no tare");
+        return Err(Nau7802Error::CalibrationFailed);
+    }
+
+    Ok(CalPoint { delta, grams: known_weight_grams })
+}
+
+/// Calibrate with a known weight (single point, linear)
+pub fn calibrate<I: I2c, D: DelayNs>(i2c: &mut I, delay: &mut D, state: &mut Nau7802State, known_weight_grams: f32) -> Result<(), Nau7802Error> {
+    let point = measure_point(i2c, delay, state, known_weight_grams)?;
+
+    let mut cal = state.calibration;
+    if let Err(e) = calibration::fit(&mut cal, &[point], CurveKind::Linear) {
+        warn!("  Calibration FAILED: {} (delta {})", e, point.delta);
+        return Err(Nau7802Error::CalibrationFailed);
+    }
+    apply_calibration(state, cal, known_weight_grams);
+    Ok(())
+}
+
+/// Switch to a new calibration, resetting the filtered weight to `weight_grams`
+pub fn apply_calibration(state: &mut Nau7802State, calibration: Calibration, weight_grams: f32) {
+    state.calibration = calibration;
+
+    // Reset filtered state
+    state.reset_weight(weight_grams);
+
+    info!("=== CALIBRATION COMPLETE ===");
+    info!("  Final zero_offset: {}", state.calibration.zero_offset);
+    info!("  Final cal_factor: {}", state.calibration.cal_factor);
+    info!("  Final quadratic: {:e}", state.calibration.quadratic);
+}
+
+// --- Private helpers ---
+
+fn read_reg<I: I2c>(i2c: &mut I, reg: u8) -> Result<u8, Nau7802Error> {
+    let mut buf = [0u8; 1];
+    i2c.write_read(NAU7802_ADDR, &[reg], &mut buf)
+        .map_err(|_| Nau7802Error::I2cError)?;
+    Ok(buf[0])
+}
+
+fn write_reg<I: I2c>(i2c: &mut I, reg: u8, value: u8) -> Result<(), Nau7802Error> {
+    i2c.write(NAU7802_ADDR, &[reg, value])
+        .map_err(|_| Nau7802Error::I2cError)?;
+    Ok(())
+}
+
+/// NAU7802 error types
+#[derive(Debug)]
+pub enum Nau7802Error {
+    I2cError,
+    NotInitialized,
+    Timeout,
+    CalibrationFailed,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scale::nau7802_sim::{LoadCell, LoadProfile, SimDelay, SimulatedNau7802};
+
+    /// Initialized driver on a simulated chip with the default load cell
+    fn scale(profile: LoadProfile) -> (SimulatedNau7802, SimDelay, Nau7802State) {
+        let mut sim = SimulatedNau7802::new(LoadCell::default(), profile);
+        let mut delay = SimDelay::default();
+        let mut state = Nau7802State::new();
+        init(&mut sim, &mut delay, &mut state).unwrap();
+        (sim, delay, state)
+    }
+
+    /// Read until the weight has been stable for a while
+    fn settled_weight(sim: &mut SimulatedNau7802, state: &mut Nau7802State) -> f32 {
+        for _ in 0..30 {
+            read_weight(sim, state).unwrap();
+        }
+        assert!(state.stable, "not stable: {} g", state.weight_grams);
+        state.weight_grams
+    }
+
+    #[test]
+    fn init_calibrates_afe() {
+        let (sim, delay, state) = scale(LoadProfile::constant(0.0));
+        assert!(state.initialized);
+        assert!(sim.afe_calibrated());
+        assert_eq!(sim.gain(), state.adc_config.gain.factor());
+        // Waits are asked for, not slept
+        assert!(delay.elapsed_ns >= 10_000_000);
+    }
+
+    #[test]
+    fn tare_zeroes_empty_platform() {
+        let (mut sim, mut delay, mut state) = scale(LoadProfile::constant(0.0));
+        tare(&mut sim, &mut delay, &mut state).unwrap();
+
+        let cell = LoadCell::default();
+        assert!((state.calibration.zero_offset - cell.zero_raw).abs() <= cell.noise_raw);
+        // The settle time before averaging
+        assert!(delay.elapsed_ns >= 1_000_000_000);
+        assert!(settled_weight(&mut sim, &mut state).abs() < 0.5);
+    }
+
+    #[test]
+    fn single_point_calibration() {
+        let (mut sim, mut delay, mut state) = scale(LoadProfile::constant(0.0));
+        tare(&mut sim, &mut delay, &mut state).unwrap();
+
+        sim.set_profile(LoadProfile::constant(1000.0));
+        calibrate(&mut sim, &mut delay, &mut state, 1000.0).unwrap();
+        let raw_per_gram = LoadCell::default().raw_per_gram;
+        assert!((state.calibration.cal_factor - raw_per_gram).abs() < raw_per_gram * 0.001);
+        assert_eq!(state.calibration.quadratic, 0.0);
+        assert_eq!(state.weight_grams, 1000.0);
+    }
+
+    #[test]
+    fn reads_500g() {
+        let (mut sim, mut delay, mut state) = scale(LoadProfile::constant(0.0));
+        tare(&mut sim, &mut delay, &mut state).unwrap();
+        sim.set_profile(LoadProfile::constant(1000.0));
+        calibrate(&mut sim, &mut delay, &mut state, 1000.0).unwrap();
+
+        sim.set_profile(LoadProfile::constant(500.0));
+        let weight = settled_weight(&mut sim, &mut state);
+        assert!((weight - 500.0).abs() < 0.5, "{weight} g");
+    }
+
+    #[test]
+    fn calibration_without_weight_fails() {
+        let (mut sim, mut delay, mut state) = scale(LoadProfile::constant(0.0));
+        tare(&mut sim, &mut delay, &mut state).unwrap();
+        let before = state.calibration;
+
+        let result = calibrate(&mut sim, &mut delay, &mut state, 1000.0);
+        assert!(matches!(result, Err(Nau7802Error::CalibrationFailed)));
+        assert_eq!(state.calibration, before);
+    }
+
+    #[test]
+    fn disconnected_chip_is_an_i2c_error() {
+        let mut sim = SimulatedNau7802::new(LoadCell::default(), LoadProfile::constant(0.0));
+        sim.set_connected(false);
+        let mut delay = SimDelay::default();
+        let mut state = Nau7802State::new();
+        assert!(matches!(init(&mut sim, &mut delay, &mut state), Err(Nau7802Error::I2cError)));
+        assert!(!state.initialized);
+        assert!(matches!(read_weight(&mut sim, &mut state), Err(Nau7802Error::NotInitialized)));
+
+        let (mut sim, mut delay, mut state) = scale(LoadProfile::constant(0.0));
+        let zero_offset = state.calibration.zero_offset;
+        sim.set_connected(false);
+        assert!(matches!(read_weight(&mut sim, &mut state), Err(Nau7802Error::I2cError)));
+        assert!(matches!(tare(&mut sim, &mut delay, &mut state), Err(Nau7802Error::I2cError)));
+        assert_eq!(state.calibration.zero_offset, zero_offset);
+    }
+
+    #[test]
+    fn failed_adc_config_restores_previous() {
+        let (mut sim, mut delay, mut state) = scale(LoadProfile::constant(0.0));
+        let old = state.adc_config;
+        let config = AdcConfig { gain: Gain::X64, ..old };
+
+        sim.fail_calibration(true);
+        let result = apply_adc_config(&mut sim, &mut delay, &mut state, config);
+        assert!(matches!(result, Err(Nau7802Error::CalibrationFailed)));
+        assert_eq!(state.adc_config, old);
+        assert_eq!(sim.gain(), old.gain.factor());
+
+        sim.fail_calibration(false);
+        apply_adc_config(&mut sim, &mut delay, &mut state, config).unwrap();
+        assert_eq!(state.adc_config, config);
+        assert_eq!(sim.gain(), 64);
+    }
+}
@@ -0,0 +1,338 @@
+//! Simulated NAU7802 on an `embedded_hal::i2c::I2c` bus
+//!
+//! A register file that behaves like the chip towards the driver: reset and
+//! power-up (PU_CTRL), conversion rate, gain, LDO and channel (CTRL1/CTRL2),
+//! AFE calibration (CALS/CALMOD, with an injectable CAL_ERR), the internal
+//! temperature sensor (I2C_CTRL TS) and the 24-bit ADC output. Every poll of
+//! PU_CTRL after the previous result was read completes a conversion, so the
+//! driver never waits on the simulated clock.
+//!
+//! Conversions follow a scripted load profile (grams per conversion) through
+//! a load cell model with zero offset, sensitivity, non-linearity and
+//! deterministic noise. Until an internal offset calibration runs, the AFE
+//! adds its own offset. Lets the driver, tare and calibration run on the host.
+
+use embedded_hal::delay::DelayNs;
+use embedded_hal::i2c::{ErrorKind, ErrorType, NoAcknowledgeSource, Operation, SevenBitAddress};
+
+use super::nau7802::NAU7802_ADDR;
+
+/// Revision ID reported in REVISION (0x1F)
+pub const SIM_REVISION: u8 = 0x0F;
+
+const PU_CTRL: u8 = 0x00;
+const CTRL1: u8 = 0x01;
+const CTRL2: u8 = 0x02;
+const I2C_CTRL: u8 = 0x11;
+const ADCO_B2: u8 = 0x12;
+const ADCO_B0: u8 = 0x14;
+const REVISION: u8 = 0x1F;
+
+const RR: u8 = 0x01;
+const PUD: u8 = 0x02;
+const PUA: u8 = 0x04;
+const PUR: u8 = 0x08;
+const CS: u8 = 0x10;
+const CR: u8 = 0x20;
+const CALMOD: u8 = 0x03;
+const CALS: u8 = 0x04;
+const CAL_ERR: u8 = 0x08;
+const CHS: u8 = 0x80;
+const TS: u8 = 0x02;
+
+/// Typical internal temperature sensor output (see `nau7802`)
+const TEMP_MV_AT_25C: f32 = 360.0;
+const TEMP_MV_PER_C: f32 = 0.109;
+
+/// Delay for the driver that only adds up the time asked for (the simulated
+/// chip never needs waiting for)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SimDelay {
+    pub elapsed_ns: u64,
+}
+
+impl DelayNs for SimDelay {
+    fn delay_ns(&mut self, ns: u32) {
+        self.elapsed_ns += ns as u64;
+    }
+}
+
+/// Load on the platform over time
+#[derive(Debug, Clone, Default)]
+pub struct LoadProfile {
+    /// (conversions, grams) steps in order; the last load stays on
+    steps: Vec<(usize, f32)>,
+}
+
+impl LoadProfile {
+    /// The same load throughout
+    pub fn constant(grams: f32) -> Self {
+        Self { steps: vec![(1, grams)] }
+    }
+
+    /// Put `grams` on for the next `conversions` conversions
+    pub fn then(mut self, conversions: usize, grams: f32) -> Self {
+        self.steps.push((conversions, grams));
+        self
+    }
+
+    /// Load during conversion `index`
+    pub fn load_at(&self, index: usize) -> f32 {
+        let mut start = 0;
+        for &(conversions, grams) in &self.steps {
+            if index < start + conversions {
+                return grams;
+            }
+            start += conversions;
+        }
+        self.steps.last().map_or(0.0, |&(_, grams)| grams)
+    }
+}
+
+/// Load cell as seen at gain 128
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoadCell {
+    /// Reading of the empty platform
+    pub zero_raw: i32,
+    /// Raw units per gram
+    pub raw_per_gram: f32,
+    /// Raw units per gram squared (0 = linear)
+    pub nonlinearity: f32,
+    /// Peak noise in raw units
+    pub noise_raw: i32,
+}
+
+impl Default for LoadCell {
+    fn default() -> Self {
+        // A 5kg cell on the SparkFun Qwiic Scale
+        Self { zero_raw: 84_000, raw_per_gram: 245.0, nonlinearity: 0.0, noise_raw: 60 }
+    }
+}
+
+/// Bus error of the simulated chip
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimError {
+    /// Nothing answers at the address (or the chip is disconnected)
+    NoAcknowledge,
+}
+
+impl embedded_hal::i2c::Error for SimError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            SimError::NoAcknowledge => ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address),
+        }
+    }
+}
+
+/// Simulated NAU7802
+pub struct SimulatedNau7802 {
+    regs: [u8; 0x20],
+    /// Register pointer (set by the first byte of a write)
+    pointer: u8,
+    cell: LoadCell,
+    profile: LoadProfile,
+    /// Offset the AFE adds until an internal offset calibration
+    afe_offset_raw: i32,
+    afe_calibrated: bool,
+    temperature_c: f32,
+    conversions: usize,
+    connected: bool,
+    fail_calibration: bool,
+}
+
+impl SimulatedNau7802 {
+    pub fn new(cell: LoadCell, profile: LoadProfile) -> Self {
+        let mut sim = Self {
+            regs: [0; 0x20],
+            pointer: 0,
+            cell,
+            profile,
+            afe_offset_raw: 1_500,
+            afe_calibrated: false,
+            temperature_c: 25.0,
+            conversions: 0,
+            connected: true,
+            fail_calibration: false,
+        };
+        sim.reset_registers();
+        sim
+    }
+
+    /// Replace the load profile, continuing from the current conversion
+    pub fn set_profile(&mut self, profile: LoadProfile) {
+        self.profile = LoadProfile { steps: vec![(self.conversions, 0.0)] };
+        self.profile.steps.extend(profile.steps);
+    }
+
+    /// Unplug (or plug in) the chip: bus transfers fail while unplugged
+    pub fn set_connected(&mut self, connected: bool) {
+        self.connected = connected;
+    }
+
+    /// Make AFE calibrations end with CAL_ERR
+    pub fn fail_calibration(&mut self, fail: bool) {
+        self.fail_calibration = fail;
+    }
+
+    pub fn set_temperature(&mut self, temperature_c: f32) {
+        self.temperature_c = temperature_c;
+    }
+
+    /// Offset the AFE adds before its internal offset calibration
+    pub fn set_afe_offset(&mut self, raw: i32) {
+        self.afe_offset_raw = raw;
+    }
+
+    /// Whether an internal offset calibration has run since the last reset
+    pub fn afe_calibrated(&self) -> bool {
+        self.afe_calibrated
+    }
+
+    /// Conversions completed so far
+    pub fn conversions(&self) -> usize {
+        self.conversions
+    }
+
+    pub fn register(&self, reg: u8) -> u8 {
+        self.regs[reg as usize & 0x1F]
+    }
+
+    /// PGA gain set in CTRL1
+    pub fn gain(&self) -> u8 {
+        1 << (self.regs[CTRL1 as usize] & 0x07)
+    }
+
+    /// Conversion rate bits (CRS) set in CTRL2
+    pub fn rate_bits(&self) -> u8 {
+        (self.regs[CTRL2 as usize] >> 4) & 0x07
+    }
+
+    fn reset_registers(&mut self) {
+        self.regs = [0; 0x20];
+        self.regs[REVISION as usize] = SIM_REVISION;
+        self.afe_calibrated = false;
+    }
+
+    fn write_register(&mut self, reg: u8, value: u8) {
+        match reg {
+            PU_CTRL => {
+                if value & RR != 0 {
+                    self.reset_registers();
+                    self.regs[PU_CTRL as usize] = RR;
+                    return;
+                }
+                // Power-up ready as soon as digital and analog are up
+                let powered = value & (PUD | PUA) == PUD | PUA;
+                let status = self.regs[PU_CTRL as usize] & CR;
+                self.regs[PU_CTRL as usize] = (value & !(PUR | CR)) | if powered { PUR } else { 0 } | status;
+            }
+            CTRL2 => {
+                self.regs[CTRL2 as usize] = value & !CAL_ERR;
+                if value & CALS != 0 {
+                    self.run_calibration(value & CALMOD);
+                }
+            }
+            ADCO_B2..=ADCO_B0 | REVISION => {} // Read-only
+            _ => self.regs[reg as usize & 0x1F] = value,
+        }
+    }
+
+    fn read_register(&mut self, reg: u8) -> u8 {
+        match reg {
+            PU_CTRL => {
+                let pu = self.regs[PU_CTRL as usize];
+                if pu & CS != 0 && pu & PUR != 0 && pu & CR == 0 {
+                    self.convert();
+                }
+                self.regs[PU_CTRL as usize]
+            }
+            ADCO_B0 => {
+                // Reading the last byte frees the output for the next conversion
+                self.regs[PU_CTRL as usize] &= !CR;
+                self.regs[ADCO_B0 as usize]
+            }
+            _ => self.regs[reg as usize & 0x1F],
+        }
+    }
+
+    /// Offset calibration completes immediately; gain calibration is accepted
+    /// but changes nothing
+    fn run_calibration(&mut self, mode: u8) {
+        let ctrl2 = &mut self.regs[CTRL2 as usize];
+        *ctrl2 &= !CALS;
+        if self.fail_calibration {
+            *ctrl2 |= CAL_ERR;
+        } else if mode == 0b00 {
+            self.afe_calibrated = true;
+        }
+    }
+
+    /// Complete a conversion into ADCO
+    fn convert(&mut self) {
+        let gain = self.gain() as f32;
+        let code = if self.regs[I2C_CTRL as usize] & TS != 0 {
+            let mv = TEMP_MV_AT_25C + TEMP_MV_PER_C * (self.temperature_c - 25.0);
+            (mv / 1650.0 * (1 << 23) as f32 * gain) as i64
+        } else {
+            let signal = if self.regs[CTRL2 as usize] & CHS == 0 {
+                let grams = self.profile.load_at(self.conversions);
+                let cell = &self.cell;
+                cell.zero_raw as f32 + grams * cell.raw_per_gram + grams * grams * cell.nonlinearity
+            } else {
+                0.0 // Nothing on channel 2
+            };
+            let afe = if self.afe_calibrated { 0 } else { self.afe_offset_raw };
+            (signal * gain / 128.0) as i64 + afe as i64 + self.noise()
+        };
+
+        let code = code.clamp(-(1 << 23), (1 << 23) - 1) as i32;
+        let bytes = code.to_be_bytes();
+        self.regs[ADCO_B2 as usize..=ADCO_B0 as usize].copy_from_slice(&bytes[1..]);
+        self.regs[PU_CTRL as usize] |= CR;
+        self.conversions += 1;
+    }
+
+    /// Deterministic noise in [-noise_raw, noise_raw]
+    fn noise(&self) -> i64 {
+        let span = self.cell.noise_raw as i64;
+        if span == 0 {
+            return 0;
+        }
+        let x = (self.conversions as u64).wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407) >> 33;
+        (x % (2 * span as u64 + 1)) as i64 - span
+    }
+}
+
+impl ErrorType for SimulatedNau7802 {
+    type Error = SimError;
+}
+
+impl embedded_hal::i2c::I2c<SevenBitAddress> for SimulatedNau7802 {
+    fn transaction(&mut self, address: SevenBitAddress, operations: &mut [Operation<'_>]) -> Result<(), SimError> {
+        if address != NAU7802_ADDR || !self.connected {
+            return Err(SimError::NoAcknowledge);
+        }
+
+        for operation in operations {
+            match operation {
+                Operation::Write(bytes) => {
+                    let Some((&reg, values)) = bytes.split_first() else {
+                        continue;
+                    };
+                    self.pointer = reg;
+                    for &value in values {
+                        self.write_register(self.pointer, value);
+                        self.pointer = self.pointer.wrapping_add(1);
+                    }
+                }
+                Operation::Read(buf) => {
+                    for byte in buf.iter_mut() {
+                        *byte = self.read_register(self.pointer);
+                        self.pointer = self.pointer.wrapping_add(1);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,103 @@
+//! Replay of recorded raw scale readings through the weight pipeline
+//!
+//! Traces are recorded on the device with `scale_set_trace_logging(true)`,
+//! which logs every raw reading as `SCALE_TRACE <raw>`. A captured log (serial
+//! or UDP logger) can be fed in as is: other lines are skipped. A plain list
+//! of raw values, one per line, works too.
+//!
+//! Replay runs the device's own pipeline (`nau7802::process_weight`): drift
+//! compensation, the weight filter, then zero tracking. A trace holds no
+//! temperatures, so temperature compensation has nothing to correct.
+
+use super::calibration::Calibration;
+use super::drift::{DriftCompensator, DriftConfig};
+use super::filter::{FilterConfig, WeightFilter, TRACE_MARKER};
+use super::nau7802::{self, Nau7802State};
+
+/// One replayed reading
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TraceSample {
+    pub raw: i32,
+    /// Calibrated, unfiltered weight
+    pub grams: f32,
+    /// Filtered weight
+    pub weight: f32,
+    pub stable: bool,
+    pub std_dev: f32,
+}
+
+/// Raw readings of a recorded trace
+pub fn parse(text: &str) -> Vec<i32> {
+    text.lines()
+        .filter_map(|line| {
+            let value = match line.find(TRACE_MARKER) {
+                Some(pos) => &line[pos + TRACE_MARKER.len()..],
+                None => line,
+            };
+            value.trim().parse().ok()
+        })
+        .collect()
+}
+
+/// Run raw readings through a fresh pipeline, as the device would
+pub fn replay(raw: &[i32], calibration: &Calibration, config: FilterConfig, drift: DriftConfig) -> Vec<TraceSample> {
+    let mut state = Nau7802State::new();
+    state.calibration = *calibration;
+    state.filter = WeightFilter::new(config);
+    state.drift = DriftCompensator::new(drift);
+    raw.iter()
+        .map(|&raw| {
+            let grams = state.calibration.grams(raw);
+            let out = nau7802::process_weight(&mut state, raw);
+            TraceSample { raw, grams, weight: out.weight, stable: out.stable, std_dev: out.std_dev }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SWING_BUMP: &str = include_str!("traces/swing_bump.log");
+    const SPOOL_G: f32 = 1000.0;
+
+    fn replay_swing_bump() -> Vec<TraceSample> {
+        let calibration = Calibration { zero_offset: 84213, cal_factor: 428.6, quadratic: 0.0 };
+        replay(&parse(SWING_BUMP), &calibration, FilterConfig::default(), DriftConfig::default())
+    }
+
+    #[test]
+    fn parses_log_lines() {
+        let raw = parse(SWING_BUMP);
+        assert_eq!(raw.len(), 260);
+        assert_eq!(raw[0], 84186);
+        assert_eq!(parse("123\n-45\nnot a reading\nI (1) x: SCALE_TRACE 678\n"), vec![123, -45, 678]);
+    }
+
+    #[test]
+    fn no_false_stable_while_swinging_or_bumped() {
+        let samples = replay_swing_bump();
+
+        // Spool swinging after being set down, table bumped
+        for range in [40..85, 135..150] {
+            for (i, sample) in samples[range.clone()].iter().enumerate() {
+                assert!(!sample.stable, "stable at reading {} ({:.1} g)", range.start + i, sample.weight);
+            }
+        }
+
+        // Whenever stable, the weight is the empty platform or the spool
+        for (i, sample) in samples.iter().enumerate().filter(|(_, s)| s.stable) {
+            let error = sample.weight.abs().min((sample.weight - SPOOL_G).abs());
+            assert!(error < 1.5, "stable at reading {i} with {:.1} g", sample.weight);
+        }
+    }
+
+    #[test]
+    fn settles_after_swing_bump_and_removal() {
+        let samples = replay_swing_bump();
+        for (i, expected) in [(39, 0.0), (120, SPOOL_G), (170, SPOOL_G), (259, 0.0)] {
+            assert!(samples[i].stable, "not stable at reading {i}");
+            assert!((samples[i].weight - expected).abs() < 1.0, "reading {i}: {:.1} g", samples[i].weight);
+        }
+    }
+}
@@ -14,8 +14,6 @@
 #ifdef ESP_PLATFORM
 #include "ui_internal.h"
 #include "esp_log.h"
-#include "freertos/FreeRTOS.h"
-#include "freertos/task.h"
 #else
 #include "backend_client.h"
 #define ESP_LOGI(tag, fmt, ...) printf("[%s] " fmt "\n", tag, ##__VA_ARGS__)
@@ -100,10 +98,22 @@ static lv_obj_t *g_success_overlay = NULL;
 static lv_obj_t *g_loading_spinner = NULL;
 static lv_obj_t *g_loading_label = NULL;
 static bool g_data_loaded = false;
-#ifdef ESP_PLATFORM
-static volatile bool g_data_fetch_complete = false;  // Set by task when HTTP done
-static TaskHandle_t g_fetch_task_handle = NULL;
-#endif
+
+// Backend request in flight (runs on the backend worker, polled by g_request_timer)
+typedef enum {
+    MODAL_REQUEST_NONE,
+    MODAL_REQUEST_PRESETS,
+    MODAL_REQUEST_K_PROFILES,
+    MODAL_REQUEST_PRESET_DETAIL,
+    MODAL_REQUEST_FILAMENT,
+    MODAL_REQUEST_CALIBRATION,
+    MODAL_REQUEST_REREAD,
+    MODAL_REQUEST_CLEAR,
+} ModalRequest;
+
+static ModalRequest g_request = MODAL_REQUEST_NONE;
+static lv_timer_t *g_request_timer = NULL;
+static bool g_colors_pending = false;  // Catalog color search in flight
 
 // Slot info
 static char g_printer_serial[32] = {0};
@@ -135,6 +145,18 @@ static lv_obj_t *g_color_name_label = NULL;  // Label showing selected color nam
 static EXT_RAM_BSS_ATTR ColorCatalogEntry g_catalog_colors[MAX_CATALOG_COLORS];
 static int g_catalog_color_count = 0;
 
+// Slot configuration being sent (fixed when Configure is pressed)
+static struct {
+    char setting_id[64];        // Selected preset
+    const char *material;
+    char tray_sub_brands[64];
+    char tray_color[24];
+    int temp_min;
+    int temp_max;
+    bool has_k_profile;
+    KProfileInfo k_profile;
+} g_configure;
+
 // Parsed preset info (for K-profile and color filtering)
 static char g_selected_brand[64] = {0};
 static char g_selected_material[32] = {0};
@@ -359,6 +381,7 @@ static void update_k_profile_dropdown(void);
 
 // Forward declarations for catalog colors
 static void refresh_catalog_colors(void);
+static void poll_catalog_colors(void);
 static void rebuild_colors_ui(void);
 
 // Check if K-profile matches brand and material (no extruder filtering - show all in dropdown)
@@ -702,246 +725,204 @@ static void toggle_extended_colors_handler(lv_event_t *e) {
     rebuild_colors_ui();
 }
 
-static void configure_handler(lv_event_t *e) {
-    (void)e;
+// Show an error below the options
+static void show_error(const char *msg) {
+    if (g_error_label) {
+        lv_label_set_text(g_error_label, msg);
+        lv_obj_remove_flag(g_error_label, LV_OBJ_FLAG_HIDDEN);
+    }
+}
 
-    if (g_selected_preset_idx < 0) {
-        if (g_error_label) {
-            lv_label_set_text(g_error_label, "Please select a filament profile");
-            lv_obj_remove_flag(g_error_label, LV_OBJ_FLAG_HIDDEN);
-        }
-        return;
+// Show a full screen result overlay, notify the caller and close after a delay
+static void show_result_overlay(const char *symbol, const char *message) {
+    if (g_modal && !g_success_overlay) {
+        g_success_overlay = lv_obj_create(g_modal);
+        lv_obj_set_size(g_success_overlay, 800, 480);
+        lv_obj_set_pos(g_success_overlay, -16, -16);  // Offset for modal padding
+        lv_obj_set_style_bg_color(g_success_overlay, lv_color_hex(0x1a1a1a), 0);
+        lv_obj_set_style_bg_opa(g_success_overlay, 250, 0);
+        lv_obj_set_style_radius(g_success_overlay, 0, 0);
+        lv_obj_clear_flag(g_success_overlay, LV_OBJ_FLAG_SCROLLABLE);
+
+        lv_obj_t *check = lv_label_create(g_success_overlay);
+        lv_label_set_text(check, symbol);
+        lv_obj_set_style_text_font(check, &lv_font_montserrat_28, 0);
+        lv_obj_set_style_text_color(check, lv_color_hex(0x32CD32), 0);
+        lv_obj_align(check, LV_ALIGN_CENTER, 0, -30);
+
+        lv_obj_t *msg = lv_label_create(g_success_overlay);
+        lv_label_set_text(msg, message);
+        lv_obj_set_style_text_font(msg, &lv_font_montserrat_20, 0);
+        lv_obj_set_style_text_color(msg, lv_color_hex(0xfafafa), 0);
+        lv_obj_align(msg, LV_ALIGN_CENTER, 0, 30);
     }
 
-    // Hide error
-    if (g_error_label) {
-        lv_obj_add_flag(g_error_label, LV_OBJ_FLAG_HIDDEN);
+    // Call success callback
+    if (g_on_success) {
+        g_on_success();
     }
 
-    SlicerPreset *preset = &g_presets[g_selected_preset_idx];
-    const char *material = parse_material(preset->name);
+    // Auto-close after delay
+    lv_timer_create(auto_close_timer_cb, 1500, NULL);
+}
 
-    // Get tray_info_idx and effective_setting_id
+// Send the slot filament (detail = user preset detail, NULL to use the preset itself)
+static void start_set_filament(const PresetDetail *detail) {
     char tray_info_idx[64] = {0};
     char effective_setting_id[64] = {0};
 
-    // For user presets, fetch detail to get filament_id or base_id
-    if (is_user_preset(preset->setting_id)) {
-        PresetDetail detail;
-        if (backend_get_preset_detail(preset->setting_id, &detail)) {
-            // Priority: filament_id first, then derive from base_id (matches frontend)
-            if (detail.has_filament_id) {
-                // Use filament_id directly for tray_info_idx
-                strncpy(tray_info_idx, detail.filament_id, sizeof(tray_info_idx) - 1);
-                strncpy(effective_setting_id, preset->setting_id, sizeof(effective_setting_id) - 1);
-                ESP_LOGI(TAG, "User preset %s -> filament_id=%s",
-                         preset->setting_id, detail.filament_id);
-            } else if (detail.has_base_id) {
-                // Derive tray_info_idx from base_id (e.g., GFSA00 -> GFA00)
-                convert_to_tray_info_idx(detail.base_id, tray_info_idx, sizeof(tray_info_idx));
-                strncpy(effective_setting_id, detail.base_id, sizeof(effective_setting_id) - 1);
-                ESP_LOGI(TAG, "User preset %s -> base_id=%s, tray_info_idx=%s",
-                         preset->setting_id, detail.base_id, tray_info_idx);
-            } else {
-                // Fallback - use preset setting_id
-                convert_to_tray_info_idx(preset->setting_id, tray_info_idx, sizeof(tray_info_idx));
-                strncpy(effective_setting_id, preset->setting_id, sizeof(effective_setting_id) - 1);
-            }
-        } else {
-            // Cloud lookup failed - fallback
-            convert_to_tray_info_idx(preset->setting_id, tray_info_idx, sizeof(tray_info_idx));
-            strncpy(effective_setting_id, preset->setting_id, sizeof(effective_setting_id) - 1);
-        }
+    // Priority: filament_id first, then derive from base_id (matches frontend)
+    if (detail && detail->has_filament_id) {
+        // Use filament_id directly for tray_info_idx
+        strncpy(tray_info_idx, detail->filament_id, sizeof(tray_info_idx) - 1);
+        strncpy(effective_setting_id, g_configure.setting_id, sizeof(effective_setting_id) - 1);
+        ESP_LOGI(TAG, "User preset %s -> filament_id=%s",
+                 g_configure.setting_id, detail->filament_id);
+    } else if (detail && detail->has_base_id) {
+        // Derive tray_info_idx from base_id (e.g., GFSA00 -> GFA00)
+        convert_to_tray_info_idx(detail->base_id, tray_info_idx, sizeof(tray_info_idx));
+        strncpy(effective_setting_id, detail->base_id, sizeof(effective_setting_id) - 1);
+        ESP_LOGI(TAG, "User preset %s -> base_id=%s, tray_info_idx=%s",
+                 g_configure.setting_id, detail->base_id, tray_info_idx);
     } else {
-        // Bambu preset - use directly
-        convert_to_tray_info_idx(preset->setting_id, tray_info_idx, sizeof(tray_info_idx));
-        strncpy(effective_setting_id, preset->setting_id, sizeof(effective_setting_id) - 1);
+        // Bambu preset, or no detail for a user preset - use the preset setting_id
+        convert_to_tray_info_idx(g_configure.setting_id, tray_info_idx, sizeof(tray_info_idx));
+        strncpy(effective_setting_id, g_configure.setting_id, sizeof(effective_setting_id) - 1);
     }
 
-    // Get color
-    const char *color_hex = g_selected_color_hex[0] ? g_selected_color_hex :
-                           (g_current_tray_color[0] ? g_current_tray_color : "FFFFFF");
-    char tray_color[24];
-    snprintf(tray_color, sizeof(tray_color), "%.8sFF", color_hex);  // Add alpha
-
-    // Get temp range
-    int temp_min, temp_max;
-    get_temp_range(material, &temp_min, &temp_max);
-
-    // Get preset name for tray_sub_brands (strip @ suffix)
-    char tray_sub_brands[64];
-    strncpy(tray_sub_brands, preset->name, sizeof(tray_sub_brands) - 1);
-    char *at_pos = strchr(tray_sub_brands, '@');
-    if (at_pos) *at_pos = '\0';
-    // Strip leading "# " if present
-    char *name_start = tray_sub_brands;
-    if (strncmp(name_start, "# ", 2) == 0) name_start += 2;
-
-    // Get selected K-profile (needed before setting filament to ensure tray_info_idx matches)
-    KProfileInfo *k_profile = (g_selected_k_idx >= 0) ? &g_k_profiles[g_selected_k_idx] : NULL;
-
     // IMPORTANT: If a K-profile is selected, use its filament_id as tray_info_idx
     // The printer requires tray_info_idx to match the K-profile's filament_id for calibration to apply
-    if (k_profile && k_profile->filament_id[0]) {
-        strncpy(tray_info_idx, k_profile->filament_id, sizeof(tray_info_idx) - 1);
+    if (g_configure.has_k_profile && g_configure.k_profile.filament_id[0]) {
+        strncpy(tray_info_idx, g_configure.k_profile.filament_id, sizeof(tray_info_idx) - 1);
         ESP_LOGI(TAG, "Using K-profile filament_id for tray_info_idx: %s", tray_info_idx);
     }
 
-    ESP_LOGI(TAG, "Configuring slot: preset=%s, setting_id=%s, tray_info_idx=%s, material=%s, tray_sub_brands=%s, color=%s",
-             preset->name, effective_setting_id, tray_info_idx, material, name_start, tray_color);
+    ESP_LOGI(TAG, "Configuring slot: setting_id=%s, tray_info_idx=%s, material=%s, tray_sub_brands=%s, color=%s",
+             effective_setting_id, tray_info_idx, g_configure.material,
+             g_configure.tray_sub_brands, g_configure.tray_color);
 
     // Set filament
-    bool success = backend_set_slot_filament(g_printer_serial, g_ams_id, g_tray_id,
-                                              tray_info_idx, effective_setting_id,
-                                              material, name_start,
-                                              tray_color, temp_min, temp_max);
-
-    if (!success) {
-        if (g_error_label) {
-            lv_label_set_text(g_error_label, "Failed to configure slot");
-            lv_obj_remove_flag(g_error_label, LV_OBJ_FLAG_HIDDEN);
-        }
+    if (backend_set_slot_filament_start(g_printer_serial, g_ams_id, g_tray_id,
+                                        tray_info_idx, effective_setting_id,
+                                        g_configure.material, g_configure.tray_sub_brands,
+                                        g_configure.tray_color, g_configure.temp_min,
+                                        g_configure.temp_max) != 0) {
+        show_error("Failed to configure slot");
         return;
     }
+    g_request = MODAL_REQUEST_FILAMENT;
+}
+
+// Send the slot calibration once the filament is set
+static void start_set_calibration(void) {
+    const KProfileInfo *k_profile = g_configure.has_k_profile ? &g_configure.k_profile : NULL;
 
-    // K-profile already retrieved above
     float k_value = 0.0f;
     if (k_profile && k_profile->k_value[0]) {
         k_value = atof(k_profile->k_value);
     }
 
-    ESP_LOGI(TAG, "Setting calibration: k_idx=%d, cali_idx=%d, filament_id='%s', setting_id='%s', k_value=%.4f, temp_max=%d",
-             g_selected_k_idx,
+    ESP_LOGI(TAG, "Setting calibration: cali_idx=%d, filament_id='%s', setting_id='%s', k_value=%.4f, temp_max=%d",
              (int)(k_profile ? k_profile->cali_idx : -1),
              k_profile ? k_profile->filament_id : "(none)",
              k_profile ? k_profile->setting_id : "(none)",
-             k_value, temp_max);
+             k_value, g_configure.temp_max);
+
+    if (backend_set_slot_calibration_start(g_printer_serial, g_ams_id, g_tray_id,
+                                           k_profile ? k_profile->cali_idx : -1,
+                                           k_profile ? k_profile->filament_id : "",
+                                           k_profile ? k_profile->setting_id : "",
+                                           "0.4", k_value, g_configure.temp_max) != 0) {
+        // The filament is set - the slot is configured without the calibration
+        show_result_overlay(LV_SYMBOL_OK, "Slot Configured!");
+        return;
+    }
+    g_request = MODAL_REQUEST_CALIBRATION;
+}
 
-    backend_set_slot_calibration(g_printer_serial, g_ams_id, g_tray_id,
-                                  k_profile ? k_profile->cali_idx : -1,
-                                  k_profile ? k_profile->filament_id : "",
-                                  k_profile ? k_profile->setting_id : "",
-                                  "0.4", k_value, temp_max);
+static void configure_handler(lv_event_t *e) {
+    (void)e;
 
-    // Show success overlay (full screen)
-    if (g_modal && !g_success_overlay) {
-        g_success_overlay = lv_obj_create(g_modal);
-        lv_obj_set_size(g_success_overlay, 800, 480);
-        lv_obj_set_pos(g_success_overlay, -16, -16);  // Offset for modal padding
-        lv_obj_set_style_bg_color(g_success_overlay, lv_color_hex(0x1a1a1a), 0);
-        lv_obj_set_style_bg_opa(g_success_overlay, 250, 0);
-        lv_obj_set_style_radius(g_success_overlay, 0, 0);
-        lv_obj_clear_flag(g_success_overlay, LV_OBJ_FLAG_SCROLLABLE);
+    if (g_request != MODAL_REQUEST_NONE) return;
 
-        lv_obj_t *check = lv_label_create(g_success_overlay);
-        lv_label_set_text(check, LV_SYMBOL_OK);
-        lv_obj_set_style_text_font(check, &lv_font_montserrat_28, 0);
-        lv_obj_set_style_text_color(check, lv_color_hex(0x32CD32), 0);
-        lv_obj_align(check, LV_ALIGN_CENTER, 0, -30);
+    if (g_selected_preset_idx < 0) {
+        show_error("Please select a filament profile");
+        return;
+    }
 
-        lv_obj_t *msg = lv_label_create(g_success_overlay);
-        lv_label_set_text(msg, "Slot Configured!");
-        lv_obj_set_style_text_font(msg, &lv_font_montserrat_20, 0);
-        lv_obj_set_style_text_color(msg, lv_color_hex(0xfafafa), 0);
-        lv_obj_align(msg, LV_ALIGN_CENTER, 0, 30);
+    // Hide error
+    if (g_error_label) {
+        lv_obj_add_flag(g_error_label, LV_OBJ_FLAG_HIDDEN);
     }
 
-    // Call success callback
-    if (g_on_success) {
-        g_on_success();
+    SlicerPreset *preset = &g_presets[g_selected_preset_idx];
+    memset(&g_configure, 0, sizeof(g_configure));
+    strncpy(g_configure.setting_id, preset->setting_id, sizeof(g_configure.setting_id) - 1);
+    g_configure.material = parse_material(preset->name);
+
+    // Get color
+    const char *color_hex = g_selected_color_hex[0] ? g_selected_color_hex :
+                           (g_current_tray_color[0] ? g_current_tray_color : "FFFFFF");
+    snprintf(g_configure.tray_color, sizeof(g_configure.tray_color), "%.8sFF", color_hex);  // Add alpha
+
+    // Get temp range
+    get_temp_range(g_configure.material, &g_configure.temp_min, &g_configure.temp_max);
+
+    // Get preset name for tray_sub_brands (strip @ suffix and leading "# ")
+    const char *name_start = preset->name;
+    if (strncmp(name_start, "# ", 2) == 0) name_start += 2;
+    strncpy(g_configure.tray_sub_brands, name_start, sizeof(g_configure.tray_sub_brands) - 1);
+    char *at_pos = strchr(g_configure.tray_sub_brands, '@');
+    if (at_pos) *at_pos = '\0';
+
+    // Get selected K-profile (needed before setting filament to ensure tray_info_idx matches)
+    if (g_selected_k_idx >= 0) {
+        g_configure.has_k_profile = true;
+        g_configure.k_profile = g_k_profiles[g_selected_k_idx];
     }
 
-    // Auto-close after delay
-    lv_timer_create(auto_close_timer_cb, 1500, NULL);
+    ESP_LOGI(TAG, "Configure pressed: preset=%s, k_idx=%d", preset->name, g_selected_k_idx);
+
+    // For user presets, fetch detail to get filament_id or base_id
+    if (is_user_preset(preset->setting_id) && backend_preset_detail_start(preset->setting_id) == 0) {
+        g_request = MODAL_REQUEST_PRESET_DETAIL;
+        return;
+    }
+
+    start_set_filament(NULL);
 }
 
 static void reread_handler(lv_event_t *e) {
     (void)e;
 
+    if (g_request != MODAL_REQUEST_NONE) return;
+
     ESP_LOGI(TAG, "Re-reading slot %s AMS %d tray %d", g_printer_serial, g_ams_id, g_tray_id);
 
     // Reset slot triggers RFID re-read
-    bool success = backend_reset_slot(g_printer_serial, g_ams_id, g_tray_id);
-
-    if (success) {
-        // Show success and close (full screen overlay)
-        if (g_modal && !g_success_overlay) {
-            g_success_overlay = lv_obj_create(g_modal);
-            lv_obj_set_size(g_success_overlay, 800, 480);
-            lv_obj_set_pos(g_success_overlay, -16, -16);  // Offset for modal padding
-            lv_obj_set_style_bg_color(g_success_overlay, lv_color_hex(0x1a1a1a), 0);
-            lv_obj_set_style_bg_opa(g_success_overlay, 250, 0);
-            lv_obj_set_style_radius(g_success_overlay, 0, 0);
-            lv_obj_clear_flag(g_success_overlay, LV_OBJ_FLAG_SCROLLABLE);
-
-            lv_obj_t *check = lv_label_create(g_success_overlay);
-            lv_label_set_text(check, LV_SYMBOL_REFRESH);
-            lv_obj_set_style_text_font(check, &lv_font_montserrat_28, 0);
-            lv_obj_set_style_text_color(check, lv_color_hex(0x32CD32), 0);
-            lv_obj_align(check, LV_ALIGN_CENTER, 0, -30);
-
-            lv_obj_t *msg = lv_label_create(g_success_overlay);
-            lv_label_set_text(msg, "Re-reading Slot...");
-            lv_obj_set_style_text_font(msg, &lv_font_montserrat_20, 0);
-            lv_obj_set_style_text_color(msg, lv_color_hex(0xfafafa), 0);
-            lv_obj_align(msg, LV_ALIGN_CENTER, 0, 30);
-        }
-
-        if (g_on_success) g_on_success();
-
-        lv_timer_create(auto_close_timer_cb, 1500, NULL);
-    } else {
-        if (g_error_label) {
-            lv_label_set_text(g_error_label, "Failed to re-read slot");
-            lv_obj_remove_flag(g_error_label, LV_OBJ_FLAG_HIDDEN);
-        }
+    if (backend_reset_slot_start(g_printer_serial, g_ams_id, g_tray_id) != 0) {
+        show_error("Failed to re-read slot");
+        return;
     }
+    g_request = MODAL_REQUEST_REREAD;
 }
 
 static void clear_handler(lv_event_t *e) {
     (void)e;
 
+    if (g_request != MODAL_REQUEST_NONE) return;
+
     ESP_LOGI(TAG, "Clearing slot %s AMS %d tray %d", g_printer_serial, g_ams_id, g_tray_id);
 
     // Clear slot by setting empty filament info (NOT reset which triggers re-read)
-    bool success = backend_set_slot_filament(g_printer_serial, g_ams_id, g_tray_id,
-                                              "", "",  // empty tray_info_idx and setting_id
-                                              "", "",  // empty tray_type and tray_sub_brands
-                                              "FFFFFFFF", 0, 0);  // white color, no temps
-
-    if (success) {
-        // Full screen success overlay
-        if (g_modal && !g_success_overlay) {
-            g_success_overlay = lv_obj_create(g_modal);
-            lv_obj_set_size(g_success_overlay, 800, 480);
-            lv_obj_set_pos(g_success_overlay, -16, -16);  // Offset for modal padding
-            lv_obj_set_style_bg_color(g_success_overlay, lv_color_hex(0x1a1a1a), 0);
-            lv_obj_set_style_bg_opa(g_success_overlay, 250, 0);
-            lv_obj_set_style_radius(g_success_overlay, 0, 0);
-            lv_obj_clear_flag(g_success_overlay, LV_OBJ_FLAG_SCROLLABLE);
-
-            lv_obj_t *check = lv_label_create(g_success_overlay);
-            lv_label_set_text(check, LV_SYMBOL_TRASH);
-            lv_obj_set_style_text_font(check, &lv_font_montserrat_28, 0);
-            lv_obj_set_style_text_color(check, lv_color_hex(0x32CD32), 0);
-            lv_obj_align(check, LV_ALIGN_CENTER, 0, -30);
-
-            lv_obj_t *msg = lv_label_create(g_success_overlay);
-            lv_label_set_text(msg, "Slot Cleared!");
-            lv_obj_set_style_text_font(msg, &lv_font_montserrat_20, 0);
-            lv_obj_set_style_text_color(msg, lv_color_hex(0xfafafa), 0);
-            lv_obj_align(msg, LV_ALIGN_CENTER, 0, 30);
-        }
-
-        if (g_on_success) g_on_success();
-
-        lv_timer_create(auto_close_timer_cb, 1500, NULL);
-    } else {
-        if (g_error_label) {
-            lv_label_set_text(g_error_label, "Failed to clear slot");
-            lv_obj_remove_flag(g_error_label, LV_OBJ_FLAG_HIDDEN);
-        }
+    if (backend_set_slot_filament_start(g_printer_serial, g_ams_id, g_tray_id,
+                                        "", "",  // empty tray_info_idx and setting_id
+                                        "", "",  // empty tray_type and tray_sub_brands
+                                        "FFFFFFFF", 0, 0) != 0) {  // white color, no temps
+        show_error("Failed to clear slot");
+        return;
     }
+    g_request = MODAL_REQUEST_CLEAR;
 }
 
 // =============================================================================
@@ -1020,24 +1001,30 @@ static void update_k_profile_dropdown(void) {
 // =============================================================================
 
 // Fetch catalog colors from backend based on brand/material
+// Quick colors are shown until the search is done (see poll_catalog_colors)
 static void refresh_catalog_colors(void) {
     g_catalog_color_count = 0;
+    g_colors_pending = false;
 
     // Only fetch if we have brand or material
-    if (!g_selected_brand[0] && !g_selected_material[0]) {
-        rebuild_colors_ui();
-        return;
+    if (g_selected_brand[0] || g_selected_material[0]) {
+        // Search for colors matching brand and/or material
+        g_colors_pending = backend_search_colors_start(g_selected_brand, g_selected_material) == 0;
     }
 
-    // Search for colors matching brand and/or material
-    g_catalog_color_count = backend_search_colors(
-        g_selected_brand[0] ? g_selected_brand : NULL,
-        g_selected_material[0] ? g_selected_material : NULL,
-        g_catalog_colors,
-        MAX_CATALOG_COLORS
-    );
+    rebuild_colors_ui();
+}
+
+// Show the catalog colors once the search is done
+static void poll_catalog_colors(void) {
+    if (!g_colors_pending) return;
+
+    int count = 0;
+    int state = backend_search_colors_poll(g_catalog_colors, MAX_CATALOG_COLORS, &count);
+    if (state == BACKEND_REQUEST_PENDING) return;
 
-    if (g_catalog_color_count < 0) g_catalog_color_count = 0;
+    g_colors_pending = false;
+    g_catalog_color_count = (state == BACKEND_REQUEST_DONE) ? count : 0;
 
     ESP_LOGI(TAG, "Found %d catalog colors for brand='%s' material='%s'",
              g_catalog_color_count, g_selected_brand, g_selected_material);
@@ -1287,10 +1274,8 @@ static void search_input_handler(lv_event_t *e) {
 // Public API
 // =============================================================================
 
-// Called via lv_async_call when data fetch is complete - runs in LVGL thread
-static void on_data_fetch_complete(void *user_data) {
-    (void)user_data;
-
+// Called once presets and K-profiles are loaded - builds the content
+static void on_data_fetch_complete(void) {
     if (!g_modal || !g_modal_open) return;
 
     ESP_LOGI(TAG, "Data fetch complete: %d presets, %d K-profiles", g_preset_count, g_k_profile_count);
@@ -1311,80 +1296,105 @@ static void on_data_fetch_complete(void *user_data) {
     build_modal_content();
 }
 
-#ifdef ESP_PLATFORM
-// FreeRTOS task to fetch data without blocking UI
-static void data_fetch_task(void *pvParameters) {
-    (void)pvParameters;
-
-    ESP_LOGI(TAG, "Data fetch task started");
-
-    // Fetch presets (blocking HTTP call - but in separate task)
-    g_preset_count = backend_get_slicer_presets(g_presets, MAX_PRESETS);
-    if (g_preset_count < 0) g_preset_count = 0;
-    ESP_LOGI(TAG, "Loaded %d presets", g_preset_count);
-
-    // Fetch K-profiles (blocking HTTP call - but in separate task)
-    g_k_profile_count = backend_get_k_profiles(g_printer_serial, "0.4", g_k_profiles, MAX_K_PROFILES);
-    if (g_k_profile_count < 0) g_k_profile_count = 0;
-    ESP_LOGI(TAG, "Loaded %d K-profiles", g_k_profile_count);
-
-    // Debug: log first few profiles
-    for (int i = 0; i < g_k_profile_count && i < 5; i++) {
-        ESP_LOGI(TAG, "K-profile[%d]: cali_idx=%d extruder=%d name='%s'",
-                 i, (int)g_k_profiles[i].cali_idx, (int)g_k_profiles[i].extruder_id,
-                 g_k_profiles[i].name);
+// Load K-profiles after the presets (builds the content if that can't start)
+static void start_k_profiles(void) {
+    if (backend_k_profiles_start(g_printer_serial, "0.4") != 0) {
+        g_k_profile_count = 0;
+        on_data_fetch_complete();
+        return;
     }
-
-    // Signal UI thread that data is ready
-    lv_async_call(on_data_fetch_complete, NULL);
-
-    g_fetch_task_handle = NULL;
-    vTaskDelete(NULL);
+    g_request = MODAL_REQUEST_K_PROFILES;
 }
-#endif
 
-// Timer callback to start async data loading
-static void load_data_timer_cb(lv_timer_t *t) {
-    lv_timer_delete(t);
+// Poll the backend request in flight (requests run on the backend worker)
+static void request_timer_cb(lv_timer_t *t) {
+    (void)t;
 
-    if (!g_modal || !g_modal_open) return;
+    if (!g_modal_open) return;
 
-    ESP_LOGI(TAG, "load_data_timer_cb: starting data fetch");
+    poll_catalog_colors();
 
-#ifdef ESP_PLATFORM
-    // Spawn FreeRTOS task to fetch data without blocking UI
-    if (g_fetch_task_handle == NULL) {
-        ESP_LOGI(TAG, "Creating fetch task...");
-        BaseType_t ret = xTaskCreate(
-            data_fetch_task,
-            "modal_fetch",
-            4096,  // Stack size
-            NULL,
-            5,     // Priority (lower than UI)
-            &g_fetch_task_handle
-        );
-        if (ret != pdPASS) {
-            ESP_LOGE(TAG, "Failed to create fetch task, ret=%d", (int)ret);
-            // Don't fallback to sync - just show empty data
-            g_preset_count = 0;
-            g_k_profile_count = 0;
-            on_data_fetch_complete(NULL);
-        } else {
-            ESP_LOGI(TAG, "Fetch task created successfully");
-        }
-    }
-#else
-    // Simulator: load synchronously (no threading issues)
-    g_preset_count = backend_get_slicer_presets(g_presets, MAX_PRESETS);
-    if (g_preset_count < 0) g_preset_count = 0;
-    ESP_LOGI(TAG, "Loaded %d presets", g_preset_count);
+    int state;
+    int count = 0;
+    PresetDetail detail;
 
-    g_k_profile_count = backend_get_k_profiles(g_printer_serial, "0.4", g_k_profiles, MAX_K_PROFILES);
-    if (g_k_profile_count < 0) g_k_profile_count = 0;
-    ESP_LOGI(TAG, "Loaded %d K-profiles", g_k_profile_count);
+    switch (g_request) {
+        case MODAL_REQUEST_NONE:
+            return;
 
-    on_data_fetch_complete(NULL);
-#endif
+        case MODAL_REQUEST_PRESETS:
+            state = backend_slicer_presets_poll(g_presets, MAX_PRESETS, &count);
+            if (state == BACKEND_REQUEST_PENDING) return;
+            g_request = MODAL_REQUEST_NONE;
+            g_preset_count = (state == BACKEND_REQUEST_DONE) ? count : 0;
+            ESP_LOGI(TAG, "Loaded %d presets", g_preset_count);
+            start_k_profiles();
+            break;
+
+        case MODAL_REQUEST_K_PROFILES:
+            state = backend_k_profiles_poll(g_k_profiles, MAX_K_PROFILES, &count);
+            if (state == BACKEND_REQUEST_PENDING) return;
+            g_request = MODAL_REQUEST_NONE;
+            g_k_profile_count = (state == BACKEND_REQUEST_DONE) ? count : 0;
+            ESP_LOGI(TAG, "Loaded %d K-profiles", g_k_profile_count);
+
+            // Debug: log first few profiles
+            for (int i = 0; i < g_k_profile_count && i < 5; i++) {
+                ESP_LOGI(TAG, "K-profile[%d]: cali_idx=%d extruder=%d name='%s'",
+                         i, (int)g_k_profiles[i].cali_idx, (int)g_k_profiles[i].extruder_id,
+                         g_k_profiles[i].name);
+            }
+            on_data_fetch_complete();
+            break;
+
+        case MODAL_REQUEST_PRESET_DETAIL:
+            state = backend_preset_detail_poll(&detail);
+            if (state == BACKEND_REQUEST_PENDING) return;
+            g_request = MODAL_REQUEST_NONE;
+            // Cloud lookup failed - fall back to the preset setting_id
+            start_set_filament(state == BACKEND_REQUEST_DONE ? &detail : NULL);
+            break;
+
+        case MODAL_REQUEST_FILAMENT:
+            state = backend_set_slot_filament_poll();
+            if (state == BACKEND_REQUEST_PENDING) return;
+            g_request = MODAL_REQUEST_NONE;
+            if (state == BACKEND_REQUEST_DONE) {
+                start_set_calibration();
+            } else {
+                show_error("Failed to configure slot");
+            }
+            break;
+
+        case MODAL_REQUEST_CALIBRATION:
+            // The slot is configured once the filament is set, calibration or not
+            if (backend_set_slot_calibration_poll() == BACKEND_REQUEST_PENDING) return;
+            g_request = MODAL_REQUEST_NONE;
+            show_result_overlay(LV_SYMBOL_OK, "Slot Configured!");
+            break;
+
+        case MODAL_REQUEST_REREAD:
+            state = backend_reset_slot_poll();
+            if (state == BACKEND_REQUEST_PENDING) return;
+            g_request = MODAL_REQUEST_NONE;
+            if (state == BACKEND_REQUEST_DONE) {
+                show_result_overlay(LV_SYMBOL_REFRESH, "Re-reading Slot...");
+            } else {
+                show_error("Failed to re-read slot");
+            }
+            break;
+
+        case MODAL_REQUEST_CLEAR:
+            state = backend_set_slot_filament_poll();
+            if (state == BACKEND_REQUEST_PENDING) return;
+            g_request = MODAL_REQUEST_NONE;
+            if (state == BACKEND_REQUEST_DONE) {
+                show_result_overlay(LV_SYMBOL_TRASH, "Slot Cleared!");
+            } else {
+                show_error("Failed to clear slot");
+            }
+            break;
+    }
 }
 
 void ui_ams_slot_modal_open(const char *printer_serial, int ams_id, int tray_id,
@@ -1510,8 +1520,15 @@ void ui_ams_slot_modal_open(const char *printer_serial, int ams_id, int tray_id,
     lv_obj_set_style_text_color(g_loading_label, lv_color_hex(0x888888), 0);
     lv_obj_align(g_loading_label, LV_ALIGN_CENTER, 0, 40);
 
-    // Start timer to load data (allows UI to render first, 100ms initial delay)
-    lv_timer_create(load_data_timer_cb, 100, NULL);
+    // Load presets, then K-profiles, on the backend worker
+    g_request = MODAL_REQUEST_NONE;
+    g_request_timer = lv_timer_create(request_timer_cb, 100, NULL);
+    if (backend_slicer_presets_start() == 0) {
+        g_request = MODAL_REQUEST_PRESETS;
+    } else {
+        g_preset_count = 0;
+        start_k_profiles();
+    }
 }
 
 // Build the full modal content after data is loaded
@@ -1749,6 +1766,14 @@ void ui_ams_slot_modal_close(void) {
 
     ESP_LOGI(TAG, "Closing AMS slot modal");
 
+    // A request still in flight is left to finish; its result is not used
+    if (g_request_timer) {
+        lv_timer_delete(g_request_timer);
+        g_request_timer = NULL;
+    }
+    g_request = MODAL_REQUEST_NONE;
+    g_colors_pending = false;
+
     if (g_modal) {
         lv_obj_delete(g_modal);
         g_modal = NULL;
@@ -727,6 +727,28 @@ static lv_obj_t* create_slot(lv_obj_t *parent, int x, int y, uint32_t rgba, bool
     return slot;
 }
 
+/**
+ * @brief Fill an external slot with the spool holder's tray from the backend
+ * @param match_id Only use the holder with the slot's id (dual-nozzle printers)
+ * Leaves the slot empty if the printer doesn't report the holder.
+ */
+static void fill_external_tray(AmsUnitCInfo *ext, bool match_id) {
+    int count = backend_get_external_count(selected_printer_index);
+    for (int i = 0; i < count; i++) {
+        AmsUnitCInfo real;
+        if (backend_get_external_unit(selected_printer_index, i, &real) != 0) {
+            continue;
+        }
+        if (match_id && real.id != ext->id) {
+            continue;
+        }
+        if (real.tray_count > 0) {
+            ext->trays[0] = real.trays[0];
+        }
+        return;
+    }
+}
+
 /**
  * @brief Create AMS container matching EEZ design exactly
  * @param tray_now Global active tray index (used to highlight active slot)
@@ -1148,8 +1170,10 @@ static void update_ams_display(void) {
             .temperature = -1,
             .extruder = 0,
             .tray_count = 1,
-            .trays = {{.tray_color = 0}}  // Empty
+            .kind = AMS_KIND_EXTERNAL,
+            .trays = {{.tray_color = 0}}  // Empty until filled from the backend
         };
+        fill_external_tray(&ext_info, is_dual_nozzle);
 
         if (!is_dual_nozzle) {
             // Single-nozzle: create one "Ext" slot on LEFT side, use active_tray_left
@@ -1172,8 +1196,10 @@ static void update_ams_display(void) {
                 .temperature = -1,
                 .extruder = 1,
                 .tray_count = 1,
-                .trays = {{.tray_color = 0}}  // Empty
+                .kind = AMS_KIND_EXTERNAL,
+                .trays = {{.tray_color = 0}}  // Empty until filled from the backend
             };
+            fill_external_tray(&ext_l_info, true);
             if (objects.main_screen_ams_left_nozzle && ams_widget_count_left < MAX_AMS_WIDGETS) {
                 lv_obj_t *ext_l = create_ams_container(objects.main_screen_ams_left_nozzle, &ext_l_info, active_tray_left);
                 lv_obj_set_pos(ext_l, left_1slot_x, ROW_BOTTOM_Y);
@@ -25,7 +25,12 @@ extern float scale_get_weight(void);
 extern bool scale_is_initialized(void);
 extern bool scale_is_stable(void);
 extern int32_t scale_tare(void);
-extern int32_t scale_calibrate(float known_weight);
+extern void scale_cal_clear_points(void);
+extern int32_t scale_cal_add_point(float known_weight);
+extern int32_t scale_cal_point_count(void);
+extern int32_t scale_cal_finish(void);
+extern bool scale_cal_is_quadratic(void);
+extern float scale_cal_get_max_error(void);
 
 // =============================================================================
 // Screen Objects (stored for updates)
@@ -461,7 +466,7 @@ static void cal_screen_tare_handler(lv_event_t *e) {
             lv_obj_set_style_text_color(scale_cal_status_text, lv_color_hex(COLOR_ACCENT_GREEN), LV_PART_MAIN);
         }
         if (scale_cal_status_subtitle) {
-            lv_label_set_text(scale_cal_status_subtitle, "Tare complete - ready for calibration");
+            lv_label_set_text(scale_cal_status_subtitle, "Tare complete - add reference weights");
             lv_obj_set_style_text_color(scale_cal_status_subtitle, lv_color_hex(COLOR_ACCENT_GREEN), LV_PART_MAIN);
         }
     } else {
@@ -485,89 +490,74 @@ static void cal_screen_tare_handler(lv_event_t *e) {
     scale_cal_timer_cb(NULL);
 }
 
-static void cal_screen_calibrate_handler(lv_event_t *e) {
+// Show a calibration result in the status card (accent color, tinted background)
+static void set_cal_status(uint32_t color, uint32_t tint, const char *text, const char *subtitle) {
+    if (scale_cal_status_card) {
+        lv_obj_set_style_bg_color(scale_cal_status_card, lv_color_hex(tint), LV_PART_MAIN);
+        lv_obj_set_style_border_color(scale_cal_status_card, lv_color_hex(color), LV_PART_MAIN);
+    }
+    if (scale_cal_status_icon) {
+        lv_obj_set_style_bg_color(scale_cal_status_icon, lv_color_hex(color), LV_PART_MAIN);
+    }
+    if (scale_cal_status_text) {
+        lv_label_set_text(scale_cal_status_text, text);
+        lv_obj_set_style_text_color(scale_cal_status_text, lv_color_hex(color), LV_PART_MAIN);
+    }
+    if (scale_cal_status_subtitle) {
+        lv_label_set_text(scale_cal_status_subtitle, subtitle);
+        lv_obj_set_style_text_color(scale_cal_status_subtitle, lv_color_hex(color), LV_PART_MAIN);
+    }
+}
+
+// Measure the entered weight as the next calibration point
+static void cal_screen_add_point_handler(lv_event_t *e) {
     (void)e;
-    if (scale_cal_weight_input) {
-        const char *text = lv_textarea_get_text(scale_cal_weight_input);
-        if (text && strlen(text) > 0) {
-            float known_weight = (float)atof(text);
-            if (known_weight > 0) {
-                // Show calibrating status (yellow)
-                if (scale_cal_status_card) {
-                    lv_obj_set_style_bg_color(scale_cal_status_card, lv_color_hex(0x33331a), LV_PART_MAIN);  // Yellow tint
-                    lv_obj_set_style_border_color(scale_cal_status_card, lv_color_hex(COLOR_ACCENT_YELLOW), LV_PART_MAIN);
-                }
-                if (scale_cal_status_icon) {
-                    lv_obj_set_style_bg_color(scale_cal_status_icon, lv_color_hex(COLOR_ACCENT_YELLOW), LV_PART_MAIN);
-                }
-                if (scale_cal_status_text) {
-                    lv_label_set_text(scale_cal_status_text, "Calibrating...");
-                    lv_obj_set_style_text_color(scale_cal_status_text, lv_color_hex(COLOR_ACCENT_YELLOW), LV_PART_MAIN);
-                }
-                if (scale_cal_status_subtitle) {
-                    lv_label_set_text(scale_cal_status_subtitle, "Please wait...");
-                }
-
-                int result = scale_calibrate(known_weight);
-                if (result == 0) {
-                    char msg[64];
-                    snprintf(msg, sizeof(msg), "Calibrated to %.0fg", known_weight);
-                    // Success - green
-                    if (scale_cal_status_card) {
-                        lv_obj_set_style_bg_color(scale_cal_status_card, lv_color_hex(0x1a3320), LV_PART_MAIN);
-                        lv_obj_set_style_border_color(scale_cal_status_card, lv_color_hex(COLOR_ACCENT_GREEN), LV_PART_MAIN);
-                    }
-                    if (scale_cal_status_icon) {
-                        lv_obj_set_style_bg_color(scale_cal_status_icon, lv_color_hex(COLOR_ACCENT_GREEN), LV_PART_MAIN);
-                    }
-                    if (scale_cal_status_text) {
-                        lv_label_set_text(scale_cal_status_text, "Scale Calibrated");
-                        lv_obj_set_style_text_color(scale_cal_status_text, lv_color_hex(COLOR_ACCENT_GREEN), LV_PART_MAIN);
-                    }
-                    if (scale_cal_status_subtitle) {
-                        lv_label_set_text(scale_cal_status_subtitle, msg);
-                        lv_obj_set_style_text_color(scale_cal_status_subtitle, lv_color_hex(COLOR_ACCENT_GREEN), LV_PART_MAIN);
-                    }
-                } else {
-                    // Failed - red
-                    if (scale_cal_status_card) {
-                        lv_obj_set_style_bg_color(scale_cal_status_card, lv_color_hex(0x331a1a), LV_PART_MAIN);
-                        lv_obj_set_style_border_color(scale_cal_status_card, lv_color_hex(COLOR_ACCENT_RED), LV_PART_MAIN);
-                    }
-                    if (scale_cal_status_icon) {
-                        lv_obj_set_style_bg_color(scale_cal_status_icon, lv_color_hex(COLOR_ACCENT_RED), LV_PART_MAIN);
-                    }
-                    if (scale_cal_status_text) {
-                        lv_label_set_text(scale_cal_status_text, "Calibration Failed");
-                        lv_obj_set_style_text_color(scale_cal_status_text, lv_color_hex(COLOR_ACCENT_RED), LV_PART_MAIN);
-                    }
-                    if (scale_cal_status_subtitle) {
-                        lv_label_set_text(scale_cal_status_subtitle, "Device not connected?");
-                        lv_obj_set_style_text_color(scale_cal_status_subtitle, lv_color_hex(COLOR_ACCENT_RED), LV_PART_MAIN);
-                    }
-                }
-                // Immediately update weight display
-                scale_cal_timer_cb(NULL);
-                return;
-            }
-        }
-        // Invalid weight entered - red
-        if (scale_cal_status_card) {
-            lv_obj_set_style_bg_color(scale_cal_status_card, lv_color_hex(0x331a1a), LV_PART_MAIN);
-            lv_obj_set_style_border_color(scale_cal_status_card, lv_color_hex(COLOR_ACCENT_RED), LV_PART_MAIN);
-        }
-        if (scale_cal_status_icon) {
-            lv_obj_set_style_bg_color(scale_cal_status_icon, lv_color_hex(COLOR_ACCENT_RED), LV_PART_MAIN);
-        }
-        if (scale_cal_status_text) {
-            lv_label_set_text(scale_cal_status_text, "Invalid Weight");
-            lv_obj_set_style_text_color(scale_cal_status_text, lv_color_hex(COLOR_ACCENT_RED), LV_PART_MAIN);
-        }
-        if (scale_cal_status_subtitle) {
-            lv_label_set_text(scale_cal_status_subtitle, "Please enter a weight > 0");
-            lv_obj_set_style_text_color(scale_cal_status_subtitle, lv_color_hex(COLOR_ACCENT_RED), LV_PART_MAIN);
-        }
+    if (!scale_cal_weight_input) return;
+
+    const char *text = lv_textarea_get_text(scale_cal_weight_input);
+    float known_weight = (text && strlen(text) > 0) ? (float)atof(text) : 0.0f;
+    if (known_weight <= 0) {
+        set_cal_status(COLOR_ACCENT_RED, 0x331a1a, "Invalid Weight", "Please enter a weight > 0");
+        return;
+    }
+
+    // Show measuring status (yellow)
+    set_cal_status(COLOR_ACCENT_YELLOW, 0x33331a, "Measuring...", "Please wait...");
+
+    int count = scale_cal_add_point(known_weight);
+    if (count > 0) {
+        char title[32];
+        char msg[64];
+        snprintf(title, sizeof(title), "Point %d Added", count);
+        snprintf(msg, sizeof(msg), "%.0fg measured - add another or press Finish", known_weight);
+        set_cal_status(COLOR_ACCENT_GREEN, 0x1a3320, title, msg);
+    } else {
+        set_cal_status(COLOR_ACCENT_RED, 0x331a1a, "Measurement Failed", "Tare first, then place the weight");
     }
+    // Immediately update weight display
+    scale_cal_timer_cb(NULL);
+}
+
+// Fit the calibration curve to the points added so far
+static void cal_screen_finish_handler(lv_event_t *e) {
+    (void)e;
+    int count = scale_cal_point_count();
+    if (count <= 0) {
+        set_cal_status(COLOR_ACCENT_RED, 0x331a1a, "No Points", "Add at least one reference weight");
+        return;
+    }
+
+    if (scale_cal_finish() == 0) {
+        char msg[64];
+        snprintf(msg, sizeof(msg), "%s fit, %d point%s, max error %.1fg",
+                 scale_cal_is_quadratic() ? "Quadratic" : "Linear", count, count == 1 ? "" : "s",
+                 scale_cal_get_max_error());
+        set_cal_status(COLOR_ACCENT_GREEN, 0x1a3320, "Scale Calibrated", msg);
+    } else {
+        set_cal_status(COLOR_ACCENT_RED, 0x331a1a, "Calibration Failed", "Check the weights and try again");
+    }
+    // Immediately update weight display
+    scale_cal_timer_cb(NULL);
 }
 
 static void cal_keyboard_handler(lv_event_t *e) {
@@ -711,8 +701,8 @@ void create_scale_calibration_screen(void) {
 
     // Step cards (reduced spacing)
     create_step_card(content, 1, "Remove all items from the scale and press \"Tare\"", 90);
-    create_step_card(content, 2, "Place a known weight on scale", 140);
-    create_step_card(content, 3, "Enter the exact weight and press \"Calibrate\"", 190);
+    create_step_card(content, 2, "Place a known weight, enter it and press \"Add Point\"", 140);
+    create_step_card(content, 3, "Repeat with heavier weights (3+ for a curve), then \"Finish\"", 190);
 
     // "CALIBRATION WEIGHT (GRAMS)" label
     lv_obj_t *weight_label = lv_label_create(content);
@@ -770,7 +760,7 @@ void create_scale_calibration_screen(void) {
 
     // Tare button (gray, left)
     lv_obj_t *tare_btn = lv_button_create(btn_container);
-    lv_obj_set_size(tare_btn, 370, 45);
+    lv_obj_set_size(tare_btn, 245, 45);
     lv_obj_align(tare_btn, LV_ALIGN_LEFT_MID, 0, 0);
     lv_obj_set_style_bg_color(tare_btn, lv_color_hex(0x555555), LV_PART_MAIN);
     lv_obj_set_style_bg_color(tare_btn, lv_color_hex(0x444444), LV_PART_MAIN | LV_STATE_PRESSED);
@@ -782,19 +772,33 @@ void create_scale_calibration_screen(void) {
     lv_obj_set_style_text_color(tare_label, lv_color_hex(COLOR_TEXT_PRIMARY), LV_PART_MAIN);
     lv_obj_center(tare_label);
 
-    // Calibrate button (green, right)
-    lv_obj_t *calibrate_btn = lv_button_create(btn_container);
-    lv_obj_set_size(calibrate_btn, 370, 45);
-    lv_obj_align(calibrate_btn, LV_ALIGN_RIGHT_MID, 0, 0);
-    lv_obj_set_style_bg_color(calibrate_btn, lv_color_hex(COLOR_ACCENT_GREEN), LV_PART_MAIN);
-    lv_obj_set_style_bg_color(calibrate_btn, lv_color_hex(0x00cc00), LV_PART_MAIN | LV_STATE_PRESSED);
-    lv_obj_add_event_cb(calibrate_btn, cal_screen_calibrate_handler, LV_EVENT_CLICKED, NULL);
-
-    lv_obj_t *cal_label = lv_label_create(calibrate_btn);
-    lv_label_set_text(cal_label, "Calibrate");
-    lv_obj_set_style_text_font(cal_label, &lv_font_montserrat_18, LV_PART_MAIN);
-    lv_obj_set_style_text_color(cal_label, lv_color_hex(0x000000), LV_PART_MAIN);
-    lv_obj_center(cal_label);
+    // Add Point button (blue, middle)
+    lv_obj_t *add_point_btn = lv_button_create(btn_container);
+    lv_obj_set_size(add_point_btn, 245, 45);
+    lv_obj_align(add_point_btn, LV_ALIGN_CENTER, 0, 0);
+    lv_obj_set_style_bg_color(add_point_btn, lv_color_hex(COLOR_ACCENT_BLUE), LV_PART_MAIN);
+    lv_obj_set_style_bg_color(add_point_btn, lv_color_hex(0x0066cc), LV_PART_MAIN | LV_STATE_PRESSED);
+    lv_obj_add_event_cb(add_point_btn, cal_screen_add_point_handler, LV_EVENT_CLICKED, NULL);
+
+    lv_obj_t *add_point_label = lv_label_create(add_point_btn);
+    lv_label_set_text(add_point_label, "Add Point");
+    lv_obj_set_style_text_font(add_point_label, &lv_font_montserrat_18, LV_PART_MAIN);
+    lv_obj_set_style_text_color(add_point_label, lv_color_hex(COLOR_TEXT_PRIMARY), LV_PART_MAIN);
+    lv_obj_center(add_point_label);
+
+    // Finish button (green, right)
+    lv_obj_t *finish_btn = lv_button_create(btn_container);
+    lv_obj_set_size(finish_btn, 245, 45);
+    lv_obj_align(finish_btn, LV_ALIGN_RIGHT_MID, 0, 0);
+    lv_obj_set_style_bg_color(finish_btn, lv_color_hex(COLOR_ACCENT_GREEN), LV_PART_MAIN);
+    lv_obj_set_style_bg_color(finish_btn, lv_color_hex(0x00cc00), LV_PART_MAIN | LV_STATE_PRESSED);
+    lv_obj_add_event_cb(finish_btn, cal_screen_finish_handler, LV_EVENT_CLICKED, NULL);
+
+    lv_obj_t *finish_label = lv_label_create(finish_btn);
+    lv_label_set_text(finish_label, "Finish");
+    lv_obj_set_style_text_font(finish_label, &lv_font_montserrat_18, LV_PART_MAIN);
+    lv_obj_set_style_text_color(finish_label, lv_color_hex(0x000000), LV_PART_MAIN);
+    lv_obj_center(finish_label);
 
     // Spacer at bottom to allow scrolling when keyboard is visible
     lv_obj_t *spacer = lv_obj_create(content);
@@ -811,6 +815,9 @@ void create_scale_calibration_screen(void) {
     lv_obj_add_event_cb(scale_cal_keyboard, cal_keyboard_handler, LV_EVENT_ALL, NULL);
     lv_obj_add_flag(scale_cal_keyboard, LV_OBJ_FLAG_HIDDEN);
 
+    // Each visit starts a new set of calibration points
+    scale_cal_clear_points();
+
     // Start timer for live weight updates
     scale_cal_timer = lv_timer_create(scale_cal_timer_cb, 200, NULL);
     scale_cal_timer_cb(NULL);  // Initial update
@@ -67,10 +67,10 @@ extern int printer_discover(PrinterDiscoveryResult *results, int max_results);
 
 // Backend connection status
 typedef struct {
-    int state;              // 0=Disconnected, 1=Discovering, 2=Connected, 3=Error
-    uint8_t server_ip[4];   // Server IP address (valid when state=2)
-    uint16_t server_port;   // Server port (valid when state=2)
-    uint8_t printer_count;  // Number of printers cached
+    int state;              // 0=Disconnected, 1=Discovering, 2=Connected, 3=Error, 4=Unauthorized, 5=CertificateMismatch
+    uint8_t server_ip[4];   // Server IP address (valid when state=2, 4 or 5)
+    uint16_t server_port;   // Server port (valid when state=2, 4 or 5)
+    uint8_t printer_count;  // Number of printers cached (saturates at 255)
 } BackendStatus;
 
 // Printer info from backend (must match Rust PrinterInfo struct exactly)
@@ -89,11 +89,22 @@ typedef struct {
     uint8_t _pad[3];            // 3 bytes padding
 } BackendPrinterInfo;
 
+// Text fields for backend_get_printer_text (full length, not cut like BackendPrinterInfo)
+#define PRINTER_TEXT_NAME          0
+#define PRINTER_TEXT_SERIAL        1
+#define PRINTER_TEXT_GCODE_STATE   2
+#define PRINTER_TEXT_SUBTASK_NAME  3
+#define PRINTER_TEXT_STAGE_NAME    4
+
 // Backend client functions (implemented in Rust)
 extern void backend_get_status(BackendStatus *status);
 extern int backend_get_printer(int index, BackendPrinterInfo *info);
+extern int backend_get_printer_text(int index, int field, char *buf, int buf_len);  // Returns full length like snprintf
 extern int backend_set_url(const char *url);
+extern int backend_get_server_url(char *buf, int buf_len);
 extern int backend_discover_server(void);
+extern int backend_get_outbox_count(void);
+extern int backend_get_pairing_code(char *buf, int buf_len);  // Returns length, 0 if not pairing
 extern int backend_is_connected(void);
 extern int backend_get_printer_count(void);
 extern int backend_has_cover(void);
@@ -110,14 +121,21 @@ typedef struct {
     uint8_t remain;         // 0-100 percentage
 } AmsTrayCInfo;
 
+// AMS unit kinds (AmsUnitCInfo.kind)
+#define AMS_KIND_AMS       0
+#define AMS_KIND_LITE      1
+#define AMS_KIND_HT        2
+#define AMS_KIND_EXTERNAL  3   // External spool holder (vt_tray)
+
 // AMS unit info from backend
 typedef struct {
-    int id;                 // AMS unit ID (0-3 for regular, 128-135 for HT)
+    int id;                 // AMS unit ID (0-3 for regular, 128-135 for HT, 254/255 external)
     int humidity;           // -1 if not available, otherwise 0-100%
     int16_t temperature;    // Celsius * 10, -1 if not available
     int8_t extruder;        // -1 if not available, 0=right, 1=left
-    uint8_t tray_count;     // Number of trays (1-4)
-    AmsTrayCInfo trays[4];  // Tray data
+    uint8_t tray_count;     // Number of trays (trays[] holds the first 4)
+    uint8_t kind;           // AMS_KIND_*
+    AmsTrayCInfo trays[4];  // Tray data (backend_get_ams_tray reads any tray)
 } AmsUnitCInfo;
 
 // AMS tray info with string color (for status_bar.c hex parsing)
@@ -128,8 +146,10 @@ typedef struct {
 } AmsTrayInfo;
 
 // AMS backend functions
-extern int backend_get_ams_count(int printer_index);
+extern int backend_get_ams_count(int printer_index);  // AMS units, without external holders
 extern int backend_get_ams_unit(int printer_index, int ams_index, AmsUnitCInfo *info);
+extern int backend_get_external_count(int printer_index);
+extern int backend_get_external_unit(int printer_index, int external_index, AmsUnitCInfo *info);
 extern int backend_get_ams_tray(int printer_index, int ams_index, int tray_index, AmsTrayInfo *info);
 extern int backend_get_tray_now(int printer_index);
 extern int backend_get_tray_now_left(int printer_index);
@@ -185,28 +205,67 @@ typedef struct {
 } SpoolKProfileC;
 
 // Assign result enum
-// 0 = Error, 1 = Configured, 2 = Staged, 3 = StagedReplace
+// 0 = Error, 1 = Configured, 2 = Staged, 3 = StagedReplace, 4 = Queued (server offline)
 typedef enum {
     ASSIGN_RESULT_ERROR = 0,
     ASSIGN_RESULT_CONFIGURED = 1,
     ASSIGN_RESULT_STAGED = 2,
     ASSIGN_RESULT_STAGED_REPLACE = 3,
+    ASSIGN_RESULT_QUEUED = 4,
 } AssignResult;
 
+// Spool lookup state (lookups run on the backend worker, never on the UI thread)
+typedef enum {
+    SPOOL_LOOKUP_NONE = -1,      // No lookup for this tag
+    SPOOL_LOOKUP_PENDING = 0,
+    SPOOL_LOOKUP_FOUND = 1,
+    SPOOL_LOOKUP_NOT_FOUND = 2,
+    SPOOL_LOOKUP_FAILED = 3,     // Backend unreachable or error
+} SpoolLookupState;
+
+// Start a fresh inventory lookup for a tag (0 = submitted, -1 = no server)
+extern int spool_lookup_start(const char *tag_id);
+// Get the lookup state for a tag (SpoolLookupState)
+extern int spool_lookup_poll(const char *tag_id);
+
+// State of a request started from the UI
+// Requests run on the backend worker: *_start returns 0 if submitted, -1 on
+// invalid arguments or if the worker isn't running, and *_poll returns the
+// state, filling its outputs once DONE. Starting a request again supersedes
+// the previous one of the same kind.
+typedef enum {
+    BACKEND_REQUEST_NONE = -1,   // Never started
+    BACKEND_REQUEST_PENDING = 0,
+    BACKEND_REQUEST_DONE = 1,
+    BACKEND_REQUEST_FAILED = 2,  // Backend unreachable or error
+} BackendRequestState;
+
 // Spool inventory functions
+// spool_get_by_tag/spool_exists_by_tag read the lookup result (false while pending)
 extern bool spool_get_by_tag(const char *tag_id, SpoolInfoC *info);
-extern bool spool_get_k_profile_for_printer(const char *spool_id, const char *printer_serial, SpoolKProfileC *profile);
-extern int backend_assign_spool_to_tray(const char *printer_serial, int ams_id, int tray_id, const char *spool_id);
-extern bool spool_sync_weight(const char *spool_id, int weight);
+
+// K-profile of a spool on a printer
+// When done, cali_idx is -1 and the strings are empty if the spool has none
+extern int spool_k_profile_start(const char *spool_id, const char *printer_serial);
+extern int spool_k_profile_poll(SpoolKProfileC *profile);
+
+// Assign a spool to an AMS tray (result is an AssignResult)
+extern int backend_assign_spool_to_tray_start(const char *printer_serial, int ams_id, int tray_id, const char *spool_id);
+extern int backend_assign_spool_to_tray_poll(int *result);
+
+// Sync spool weight to backend (done once synced or queued offline)
+extern int spool_sync_weight_start(const char *spool_id, int weight);
+extern int spool_sync_weight_poll(void);
 
 // Check if a spool with given tag_id exists in inventory
 extern bool spool_exists_by_tag(const char *tag_id);
 
-// Add a new spool to inventory
-extern bool spool_add_to_inventory(const char *tag_id, const char *vendor, const char *material,
-                                    const char *subtype, const char *color_name, uint32_t color_rgba,
-                                    int label_weight, int weight_current, const char *data_origin,
-                                    const char *tag_type, const char *slicer_filament);
+// Add a new spool to inventory (done once added or queued offline)
+extern int spool_add_to_inventory_start(const char *tag_id, const char *vendor, const char *material,
+                                        const char *subtype, const char *color_name, uint32_t color_rgba,
+                                        int label_weight, int weight_current, const char *data_origin,
+                                        const char *tag_type, const char *slicer_filament);
+extern int spool_add_to_inventory_poll(void);
 
 // Untagged spool info (for linking tags to existing spools)
 typedef struct {
@@ -220,15 +279,18 @@ typedef struct {
     bool valid;
 } UntaggedSpoolInfo;
 
-// Get list of spools without NFC tags assigned
-extern int spool_get_untagged_list(UntaggedSpoolInfo *spools, int max_count);
+// Get list of spools without NFC tags assigned (up to max_count, sets count)
+extern int spool_untagged_list_start(void);
+extern int spool_untagged_list_poll(UntaggedSpoolInfo *spools, int max_count, int *count);
 
-// Get count of spools without NFC tags
+// Get count of spools without NFC tags (from the last tag lookup, -1 if unknown)
 extern int spool_get_untagged_count(void);
 
 // Link an NFC tag to an existing spool
-// Returns: 0 = success, -1 = connection error, or HTTP status code (e.g., 409 = already assigned)
-extern int spool_link_tag(const char *spool_id, const char *tag_id, const char *tag_type);
+// Result: 0 = success, 1 = queued offline,
+// or HTTP status code (e.g., 409 = already assigned)
+extern int spool_link_tag_start(const char *spool_id, const char *tag_id, const char *tag_type);
+extern int spool_link_tag_poll(int *result);
 
 // =============================================================================
 // AMS Slot Configuration API (for Configure Slot modal)
@@ -270,40 +332,43 @@ typedef struct {
     char material[32];      // e.g., "PLA" (may be empty)
 } ColorCatalogEntry;
 
+// These run on the backend worker like the requests above (BackendRequestState)
+
 // Get slicer filament presets from Bambu Cloud
-// Returns number of presets found (up to max_count), -1 on error
-extern int backend_get_slicer_presets(SlicerPreset *presets, int max_count);
+// When done, fills up to max_count presets and sets count (0 if not available)
+extern int backend_slicer_presets_start(void);
+extern int backend_slicer_presets_poll(SlicerPreset *presets, int max_count, int *count);
 
 // Get detailed preset info including filament_id and base_id
-// Returns true on success, false on failure
-extern bool backend_get_preset_detail(const char *setting_id, PresetDetail *detail);
+extern int backend_preset_detail_start(const char *setting_id);
+extern int backend_preset_detail_poll(PresetDetail *detail);
 
 // Get K-profiles (calibration profiles) for a printer
-// Returns number of profiles found, -1 on error
-extern int backend_get_k_profiles(const char *printer_serial, const char *nozzle_diameter,
-                                   KProfileInfo *profiles, int max_count);
+// When done, fills up to max_count profiles and sets count
+extern int backend_k_profiles_start(const char *printer_serial, const char *nozzle_diameter);
+extern int backend_k_profiles_poll(KProfileInfo *profiles, int max_count, int *count);
 
 // Set filament in an AMS slot
-// Returns true on success
-extern bool backend_set_slot_filament(const char *printer_serial, int ams_id, int tray_id,
-                                       const char *tray_info_idx, const char *setting_id,
-                                       const char *tray_type, const char *tray_sub_brands,
-                                       const char *tray_color, int nozzle_temp_min, int nozzle_temp_max);
+extern int backend_set_slot_filament_start(const char *printer_serial, int ams_id, int tray_id,
+                                           const char *tray_info_idx, const char *setting_id,
+                                           const char *tray_type, const char *tray_sub_brands,
+                                           const char *tray_color, int nozzle_temp_min, int nozzle_temp_max);
+extern int backend_set_slot_filament_poll(void);
 
 // Set calibration (K-profile) for an AMS slot
-// Returns true on success
-extern bool backend_set_slot_calibration(const char *printer_serial, int ams_id, int tray_id,
-                                          int cali_idx, const char *filament_id, const char *setting_id,
-                                          const char *nozzle_diameter, float k_value, int nozzle_temp);
+extern int backend_set_slot_calibration_start(const char *printer_serial, int ams_id, int tray_id,
+                                              int cali_idx, const char *filament_id, const char *setting_id,
+                                              const char *nozzle_diameter, float k_value, int nozzle_temp);
+extern int backend_set_slot_calibration_poll(void);
 
 // Reset/clear an AMS slot (triggers RFID re-read)
-// Returns true on success
-extern bool backend_reset_slot(const char *printer_serial, int ams_id, int tray_id);
+extern int backend_reset_slot_start(const char *printer_serial, int ams_id, int tray_id);
+extern int backend_reset_slot_poll(void);
 
-// Search color catalog by manufacturer and/or material
-// Returns number of colors found (up to max_count), -1 on error
-extern int backend_search_colors(const char *manufacturer, const char *material,
-                                  ColorCatalogEntry *colors, int max_count);
+// Search color catalog by manufacturer and/or material (empty = any)
+// When done, fills up to max_count colors and sets count
+extern int backend_search_colors_start(const char *manufacturer, const char *material);
+extern int backend_search_colors_poll(ColorCatalogEntry *colors, int max_count, int *count);
 
 // =============================================================================
 // Programmatic Screen IDs (beyond EEZ-generated screens)
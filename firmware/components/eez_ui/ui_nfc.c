@@ -7,7 +7,7 @@
 #include "lvgl.h"
 #include "screens.h"
 
-// External FFI functions from Rust nfc_bridge_manager
+// External FFI functions from Rust nfc_manager
 extern bool nfc_is_initialized(void);
 extern bool nfc_tag_present(void);
 extern uint8_t nfc_get_uid_len(void);
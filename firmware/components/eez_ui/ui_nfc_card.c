@@ -14,16 +14,12 @@
 #include "ui_internal.h"
 // Type aliases for unified API
 typedef SpoolInfoC SpoolInfoLocal;
-typedef SpoolKProfileC SpoolKProfileLocal;
 #define spool_get_by_tag_local spool_get_by_tag
-#define spool_get_k_profile_local spool_get_k_profile_for_printer
 #else
 #include "backend_client.h"
 // Type aliases for unified API
 typedef SpoolInfo SpoolInfoLocal;
-typedef SpoolKProfile SpoolKProfileLocal;
 #define spool_get_by_tag_local spool_get_by_tag_full
-#define spool_get_k_profile_local spool_get_k_profile_for_printer_full
 #endif
 
 static const char *TAG = "ui_nfc_card";
@@ -38,6 +34,20 @@ extern const char* nfc_get_tag_vendor(void);
 extern const char* nfc_get_tag_material(void);
 extern const char* nfc_get_tag_color_name(void);
 extern uint32_t nfc_get_tag_color_rgba(void);
+extern const char* nfc_get_tag_type(void);
+
+// Tag writing (Rust FFI on ESP32, stub on simulator)
+// The write runs on the NFC poll; nfc_get_write_status reports the outcome
+#define TAG_WRITE_FORMAT_OPENSPOOL    0
+#define TAG_WRITE_FORMAT_OPENPRINTTAG 1
+#define TAG_WRITE_IDLE    0
+#define TAG_WRITE_PENDING 1
+#define TAG_WRITE_OK      2
+#define TAG_WRITE_FAILED  3
+extern int nfc_write_spool_tag(const char *tag_id, int format);
+extern int nfc_get_write_status(void);
+extern int nfc_get_write_message(char *buf, int buf_len);
+extern void nfc_clear_write_status(void);
 
 // External Rust FFI functions - Scale
 extern float scale_get_weight(void);
@@ -75,9 +85,21 @@ static lv_obj_t *link_popup = NULL;
 static UntaggedSpoolInfo untagged_spools[20];  // Cache of untagged spools
 static int untagged_spools_count = 0;
 
+// Backend requests in flight (run on the backend worker, polled in ui_nfc_card_update)
+static bool add_spool_pending = false;
+static bool link_tag_pending = false;
+static bool untagged_list_pending = false;
+static bool sync_weight_pending = false;
+static bool details_k_profile_pending = false;
+static char link_spool_name[72] = {0};  // "Brand Material" of the spool being linked
+
 // Tag details modal (read-only view)
 static lv_obj_t *details_modal = NULL;
 static char details_modal_spool_id[64] = {0};  // For sync button
+static char details_modal_tag_id[32] = {0};    // For write buttons
+static lv_obj_t *details_write_label = NULL;   // Tag write progress/result
+static lv_obj_t *details_k_row = NULL;         // K profile row (shown once looked up)
+static lv_obj_t *details_k_value = NULL;
 
 // Close handler for details modal
 static void details_modal_close_handler(lv_event_t *e) {
@@ -85,9 +107,71 @@ static void details_modal_close_handler(lv_event_t *e) {
     if (details_modal) {
         lv_obj_delete(details_modal);
         details_modal = NULL;
+        details_write_label = NULL;
+        details_k_row = NULL;
+        details_k_value = NULL;
+    }
+    details_k_profile_pending = false;
+}
+
+// Write tag button handler (user data = TAG_WRITE_FORMAT_*)
+static void write_tag_click_handler(lv_event_t *e) {
+    int format = (int)(intptr_t)lv_event_get_user_data(e);
+    if (details_modal_tag_id[0] == '\0' || !details_write_label) return;
+
+    ESP_LOGI(TAG, "Writing tag %s (format %d)", details_modal_tag_id, format);
+
+    char message[64] = {0};
+    if (nfc_write_spool_tag(details_modal_tag_id, format) == 0) {
+        lv_label_set_text(details_write_label, "Writing tag... keep the spool on the scale");
+        lv_obj_set_style_text_color(details_write_label, lv_color_hex(0xaaaaaa), 0);
+    } else {
+        nfc_get_write_message(message, sizeof(message));
+        lv_label_set_text(details_write_label, message[0] ? message : "Cannot write tag");
+        lv_obj_set_style_text_color(details_write_label, lv_color_hex(0xFF5252), 0);
+    }
+}
+
+// Show the result of a finished tag write in the details modal
+static void update_write_status(void) {
+    int status = nfc_get_write_status();
+    if (status != TAG_WRITE_OK && status != TAG_WRITE_FAILED) return;
+
+    char message[64] = {0};
+    nfc_get_write_message(message, sizeof(message));
+    nfc_clear_write_status();
+    ESP_LOGI(TAG, "Tag write finished: %s", message);
+
+    if (!details_write_label) return;
+    if (status == TAG_WRITE_OK) {
+        char text[80];
+        snprintf(text, sizeof(text), LV_SYMBOL_OK " %s", message);
+        lv_label_set_text(details_write_label, text);
+        lv_obj_set_style_text_color(details_write_label, lv_color_hex(0x4CAF50), 0);
+    } else {
+        char text[80];
+        snprintf(text, sizeof(text), LV_SYMBOL_WARNING " %s", message);
+        lv_label_set_text(details_write_label, text);
+        lv_obj_set_style_text_color(details_write_label, lv_color_hex(0xFF5252), 0);
     }
 }
 
+// Create a small write button in the details modal
+static void create_write_button(lv_obj_t *card, const char *text, int format, lv_align_t align) {
+    lv_obj_t *btn = lv_btn_create(card);
+    lv_obj_set_size(btn, 120, 36);
+    lv_obj_align(btn, align, 0, -5);
+    lv_obj_set_style_bg_color(btn, lv_color_hex(0x2D5A27), 0);
+    lv_obj_set_style_radius(btn, 18, 0);
+    lv_obj_add_event_cb(btn, write_tag_click_handler, LV_EVENT_CLICKED, (void *)(intptr_t)format);
+
+    lv_obj_t *label = lv_label_create(btn);
+    lv_label_set_text(label, text);
+    lv_obj_set_style_text_font(label, &lv_font_montserrat_12, 0);
+    lv_obj_set_style_text_color(label, lv_color_hex(0xFFFFFF), 0);
+    lv_obj_center(label);
+}
+
 // Sync weight button handler
 static void sync_weight_click_handler(lv_event_t *e) {
     (void)e;
@@ -98,18 +182,54 @@ static void sync_weight_click_handler(lv_event_t *e) {
     if (weight_int >= -20 && weight_int <= 20) weight_int = 0;
     if (weight_int < 0) weight_int = 0;
 
+    if (sync_weight_pending) return;
+
     ESP_LOGI(TAG, "Syncing weight %dg for spool %s", weight_int, details_modal_spool_id);
 
-    if (spool_sync_weight(details_modal_spool_id, weight_int)) {
+    if (spool_sync_weight_start(details_modal_spool_id, weight_int) == 0) {
+        sync_weight_pending = true;
+    } else {
+        ESP_LOGE(TAG, "Failed to sync weight");
+    }
+}
+
+// Refresh the details modal once the weight is synced
+static void update_sync_weight(void) {
+    if (!sync_weight_pending) return;
+
+    int state = spool_sync_weight_poll();
+    if (state == BACKEND_REQUEST_PENDING) return;
+    sync_weight_pending = false;
+
+    if (state == BACKEND_REQUEST_DONE) {
         ESP_LOGI(TAG, "Weight synced successfully");
-        // Close and reopen to refresh
-        details_modal_close_handler(NULL);
-        ui_nfc_card_show_details();
+        if (details_modal) {
+            // Close and reopen to refresh
+            details_modal_close_handler(NULL);
+            ui_nfc_card_show_details();
+        }
     } else {
         ESP_LOGE(TAG, "Failed to sync weight");
     }
 }
 
+// Show the spool's K profile in the details modal once looked up
+static void update_details_k_profile(void) {
+    if (!details_k_profile_pending) return;
+
+    SpoolKProfileC k_profile = {0};
+    int state = spool_k_profile_poll(&k_profile);
+    if (state == BACKEND_REQUEST_PENDING) return;
+    details_k_profile_pending = false;
+
+    if (state != BACKEND_REQUEST_DONE || !k_profile.name[0] || !details_k_row || !details_k_value) return;
+
+    char k_text[96];
+    snprintf(k_text, sizeof(k_text), "%.63s (k=%.15s)", k_profile.name, k_profile.k_value[0] ? k_profile.k_value : "-");
+    lv_label_set_text(details_k_value, k_text);
+    lv_obj_remove_flag(details_k_row, LV_OBJ_FLAG_HIDDEN);
+}
+
 // Show tag details modal (read-only, just Close button)
 void ui_nfc_card_show_details(void) {
     if (details_modal) return;  // Already open
@@ -276,17 +396,17 @@ void ui_nfc_card_show_details(void) {
         SpoolInfoLocal spool_info = {0};
         spool_get_by_tag_local((const char*)uid_str, &spool_info);
 
-        // Store spool ID for sync button
+        // Store spool ID for sync button, tag ID for write buttons
         strncpy(details_modal_spool_id, spool_info.id, sizeof(details_modal_spool_id) - 1);
+        strncpy(details_modal_tag_id, (const char*)uid_str, sizeof(details_modal_tag_id) - 1);
 
-        // Get K profile for selected printer
-        SpoolKProfileLocal k_profile = {0};
-        bool has_k_profile = false;
+        // Look up K profile for selected printer (row shown by update_details_k_profile)
+        details_k_profile_pending = false;
         int printer_idx = get_selected_printer_index();
         if (printer_idx >= 0 && spool_info.id[0]) {
             BackendPrinterInfo printer_info = {0};
             if (backend_get_printer(printer_idx, &printer_info) == 0) {
-                has_k_profile = spool_get_k_profile_local(spool_info.id, printer_info.serial, &k_profile);
+                details_k_profile_pending = spool_k_profile_start(spool_info.id, printer_info.serial) == 0;
             }
         }
 
@@ -387,9 +507,10 @@ void ui_nfc_card_show_details(void) {
         lv_obj_set_style_text_font(tag_val, &lv_font_montserrat_10, 0);
         lv_obj_set_style_text_color(tag_val, lv_color_hex(0x999999), 0);
 
-        // K profile info (if available)
-        if (has_k_profile && k_profile.name[0]) {
+        // K profile info (hidden until the lookup finds one)
+        if (details_k_profile_pending) {
             lv_obj_t *k_row = lv_obj_create(details_container);
+            lv_obj_add_flag(k_row, LV_OBJ_FLAG_HIDDEN);
             lv_obj_set_size(k_row, LV_SIZE_CONTENT, LV_SIZE_CONTENT);
             lv_obj_set_style_bg_opa(k_row, 0, 0);
             lv_obj_set_style_border_width(k_row, 0, 0);
@@ -403,12 +524,12 @@ void ui_nfc_card_show_details(void) {
             lv_obj_set_style_text_font(k_lbl, &lv_font_montserrat_10, 0);
             lv_obj_set_style_text_color(k_lbl, lv_color_hex(0x666666), 0);
             lv_obj_set_width(k_lbl, 60);
-            char k_text[96];
-            snprintf(k_text, sizeof(k_text), "%.63s (k=%.15s)", k_profile.name, k_profile.k_value[0] ? k_profile.k_value : "-");
             lv_obj_t *k_val = lv_label_create(k_row);
-            lv_label_set_text(k_val, k_text);
+            lv_label_set_text(k_val, "");
             lv_obj_set_style_text_font(k_val, &lv_font_montserrat_10, 0);
             lv_obj_set_style_text_color(k_val, lv_color_hex(0x4CAF50), 0);
+            details_k_row = k_row;
+            details_k_value = k_val;
         }
 
         #undef CREATE_DETAIL_ROW
@@ -566,6 +687,17 @@ void ui_nfc_card_show_details(void) {
         lv_obj_set_style_text_color(close_label, lv_color_hex(0xFFFFFF), 0);
         lv_obj_center(close_label);
 
+        // Write inventory data to the tag (Bambu tags are signed and read-only)
+        if (strcmp(nfc_get_tag_type(), "Bambu Lab") != 0) {
+            create_write_button(card, LV_SYMBOL_DOWNLOAD " OpenSpool", TAG_WRITE_FORMAT_OPENSPOOL, LV_ALIGN_BOTTOM_LEFT);
+            create_write_button(card, LV_SYMBOL_DOWNLOAD " OpenPrintTag", TAG_WRITE_FORMAT_OPENPRINTTAG, LV_ALIGN_BOTTOM_RIGHT);
+
+            details_write_label = lv_label_create(card);
+            lv_label_set_text(details_write_label, "");
+            lv_obj_set_style_text_font(details_write_label, &lv_font_montserrat_12, 0);
+            lv_obj_align(details_write_label, LV_ALIGN_BOTTOM_MID, 0, -48);
+        }
+
     } else {
         // Unknown tag - show tag ID and weight only
         lv_obj_t *tag_id_label = lv_label_create(card);
@@ -647,6 +779,8 @@ static void add_spool_click_handler(lv_event_t *e) {
     (void)e;
     ESP_LOGI(TAG, "Add Spool clicked");
 
+    if (add_spool_pending) return;
+
     // Get current weight
     float weight = scale_get_weight();
     bool scale_ok = scale_is_initialized();
@@ -655,7 +789,7 @@ static void add_spool_click_handler(lv_event_t *e) {
 
     // Add spool with minimal info - tag_id and weight only
     // User will configure details via frontend
-    bool success = spool_add_to_inventory(
+    int started = spool_add_to_inventory_start(
         (const char*)popup_tag_uid,  // tag_id
         "Unknown",                    // vendor
         "Unknown",                    // material
@@ -669,7 +803,23 @@ static void add_spool_click_handler(lv_event_t *e) {
         NULL                          // slicer_filament
     );
 
-    if (success) {
+    if (started == 0) {
+        add_spool_pending = true;
+    } else {
+        ESP_LOGE(TAG, "Failed to add spool");
+        show_success_overlay("Failed to add spool.\nPlease try again.");
+    }
+}
+
+// Show the add spool result once the backend answered
+static void update_add_spool(void) {
+    if (!add_spool_pending) return;
+
+    int state = spool_add_to_inventory_poll();
+    if (state == BACKEND_REQUEST_PENDING) return;
+    add_spool_pending = false;
+
+    if (state == BACKEND_REQUEST_DONE) {
         ESP_LOGI(TAG, "Spool added successfully");
         show_success_overlay("Spool Added!\nConfigure details in web UI.");
     } else {
@@ -775,6 +925,8 @@ static void link_popup_close_handler(lv_event_t *e) {
 static void spool_item_click_handler(lv_event_t *e) {
     int spool_index = (int)(intptr_t)lv_event_get_user_data(e);
 
+    if (link_tag_pending) return;
+
     if (spool_index < 0 || spool_index >= untagged_spools_count) {
         ESP_LOGE(TAG, "Invalid spool index: %d", spool_index);
         return;
@@ -784,9 +936,9 @@ static void spool_item_click_handler(lv_event_t *e) {
     ESP_LOGI(TAG, "Linking tag %s to spool %s (%s %s)",
              popup_tag_uid, spool->id, spool->brand, spool->material);
 
-    // Link the tag to this spool
-    // Returns: 0 = success, -1 = connection error, 409 = already assigned, other = server error
-    int result = spool_link_tag(spool->id, (const char*)popup_tag_uid, "generic");
+    // Link the tag to this spool (result shown by update_link_tag)
+    snprintf(link_spool_name, sizeof(link_spool_name), "%s %s", spool->brand, spool->material);
+    link_tag_pending = spool_link_tag_start(spool->id, (const char*)popup_tag_uid, "generic") == 0;
 
     // Close link popup
     if (link_popup) {
@@ -794,14 +946,32 @@ static void spool_item_click_handler(lv_event_t *e) {
         link_popup = NULL;
     }
 
-    if (result == 0) {
+    if (!link_tag_pending) {
+        show_success_overlay("Connection error.\nPlease try again.");
+    }
+}
+
+// Show the link result once the backend answered
+static void update_link_tag(void) {
+    if (!link_tag_pending) return;
+
+    // Result: 0 = success, 1 = queued offline, 409 = already assigned,
+    // other = server error
+    int result = 0;
+    int state = spool_link_tag_poll(&result);
+    if (state == BACKEND_REQUEST_PENDING) return;
+    link_tag_pending = false;
+
+    if (state != BACKEND_REQUEST_DONE) {
+        show_success_overlay("Connection error.\nPlease try again.");
+    } else if (result == 0) {
         char msg[128];
-        snprintf(msg, sizeof(msg), "Tag Linked!\n%s %s", spool->brand, spool->material);
+        snprintf(msg, sizeof(msg), "Tag Linked!\n%s", link_spool_name);
         show_success_overlay(msg);
+    } else if (result == 1) {
+        show_success_overlay("Tag link saved offline.\nWill sync when online.");
     } else if (result == 409) {
         show_success_overlay("Tag already assigned\nto another spool.");
-    } else if (result == -1) {
-        show_success_overlay("Connection error.\nPlease try again.");
     } else {
         char msg[64];
         snprintf(msg, sizeof(msg), "Server error (%d).\nPlease try again.", result);
@@ -809,11 +979,29 @@ static void spool_item_click_handler(lv_event_t *e) {
     }
 }
 
+static void build_link_spool_popup(void);
+
 static void show_link_spool_popup(void) {
-    if (link_popup) return;  // Already open
+    if (link_popup || untagged_list_pending) return;  // Already open or loading
+
+    // Fetch untagged spools (popup built by update_untagged_list)
+    if (spool_untagged_list_start() == 0) {
+        untagged_list_pending = true;
+    } else {
+        ESP_LOGE(TAG, "Failed to fetch untagged spools");
+    }
+}
+
+// Open the link popup once the untagged spools are fetched
+static void update_untagged_list(void) {
+    if (!untagged_list_pending) return;
 
-    // Fetch untagged spools
-    untagged_spools_count = spool_get_untagged_list(untagged_spools, 20);
+    int count = 0;
+    int state = spool_untagged_list_poll(untagged_spools, 20, &count);
+    if (state == BACKEND_REQUEST_PENDING) return;
+    untagged_list_pending = false;
+
+    untagged_spools_count = (state == BACKEND_REQUEST_DONE) ? count : 0;
     ESP_LOGI(TAG, "Found %d untagged spools", untagged_spools_count);
 
     if (untagged_spools_count == 0) {
@@ -821,6 +1009,13 @@ static void show_link_spool_popup(void) {
         return;
     }
 
+    // Tag popup closed while loading - nothing to link
+    if (!tag_popup || link_popup) return;
+
+    build_link_spool_popup();
+}
+
+static void build_link_spool_popup(void) {
     // Create modal overlay
     link_popup = lv_obj_create(lv_layer_top());
     lv_obj_set_size(link_popup, 800, 480);
@@ -1246,6 +1441,37 @@ static void update_popup_weight(void) {
     lv_label_set_text(popup_weight_label, weight_text);
 }
 
+#ifdef ESP_PLATFORM
+// Inventory lookup for the tag on the reader (runs on the backend worker)
+#define SPOOL_LOOKUP_TIMEOUT_MS 6000
+static char lookup_tag_uid[32] = {0};
+static uint32_t lookup_started = 0;
+
+// Check whether the inventory lookup for a tag has finished, starting it if needed.
+// Gives up waiting after SPOOL_LOOKUP_TIMEOUT_MS so the popup still opens offline.
+static bool spool_lookup_ready(const char *uid) {
+    if (strcmp(lookup_tag_uid, uid) != 0) {
+        strncpy(lookup_tag_uid, uid, sizeof(lookup_tag_uid) - 1);
+        lookup_tag_uid[sizeof(lookup_tag_uid) - 1] = '\0';
+        lookup_started = lv_tick_get();
+        if (spool_lookup_poll(uid) == SPOOL_LOOKUP_NONE) {
+            spool_lookup_start(uid);
+        }
+    }
+
+    if (spool_lookup_poll(uid) != SPOOL_LOOKUP_PENDING) {
+        return true;
+    }
+    return lv_tick_get() - lookup_started >= SPOOL_LOOKUP_TIMEOUT_MS;
+}
+#else
+// Simulator backend client answers lookups synchronously
+static bool spool_lookup_ready(const char *uid) {
+    (void)uid;
+    return true;
+}
+#endif
+
 void ui_nfc_card_init(void) {
     last_tag_present = false;
     // Don't reset configured_tag_id - it needs to persist across screen transitions
@@ -1327,7 +1553,7 @@ void ui_nfc_card_update(void) {
 
         if (!tag_popup) {
             // No popup open - check if we should open one
-            if (!is_suppressed) {
+            if (!is_suppressed && spool_lookup_ready((const char*)current_uid)) {
                 ESP_LOGI(TAG, "Opening popup for tag %s (dismissed=%s, configured=%s)",
                          current_uid, dismissed_tag_uid, configured_tag_id);
                 create_tag_popup();
@@ -1337,7 +1563,7 @@ void ui_nfc_card_update(void) {
             // Popup is open - check if we need to update for a different tag
             bool popup_is_different = (popup_tag_uid[0] != '\0') &&
                                       (strcmp((char*)current_uid, (char*)popup_tag_uid) != 0);
-            if (popup_is_different) {
+            if (popup_is_different && spool_lookup_ready((const char*)current_uid)) {
                 ESP_LOGI(TAG, "Different tag %s (popup was %s), recreating popup", current_uid, popup_tag_uid);
                 close_popup();
                 dismissed_tag_uid[0] = '\0';
@@ -1362,6 +1588,9 @@ void ui_nfc_card_update(void) {
                 dismissed_tag_uid[0] = '\0';
                 popup_user_closed = false;
                 memset(popup_tag_uid, 0, sizeof(popup_tag_uid));
+#ifdef ESP_PLATFORM
+                lookup_tag_uid[0] = '\0';  // Look the tag up again when it comes back
+#endif
                 tag_lost_time = 0;  // Reset timer
             }
         }
@@ -1369,5 +1598,12 @@ void ui_nfc_card_update(void) {
 
     last_tag_present = tag_present;
 
+    update_write_status();
+    update_sync_weight();
+    update_details_k_profile();
+    update_add_spool();
+    update_link_tag();
+    update_untagged_list();
+
     // Note: Scale and NFC status are now shown in the global status bar (ui_status_bar.c)
 }
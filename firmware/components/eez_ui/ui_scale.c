@@ -22,6 +22,12 @@ extern bool scale_is_stable(void);
 extern int32_t scale_tare(void);
 extern int32_t scale_calibrate(float known_weight_grams);
 extern int32_t scale_get_tare_offset(void);
+extern void scale_cal_clear_points(void);
+extern int32_t scale_cal_add_point(float known_weight_grams);
+extern int32_t scale_cal_point_count(void);
+extern int32_t scale_cal_finish(void);
+extern bool scale_cal_is_quadratic(void);
+extern float scale_cal_get_max_error(void);
 #else
 // Simulator: Scale functions that read from backend (which gets from ESP32 device)
 // Forward declare backend functions to avoid header conflicts
@@ -52,6 +58,29 @@ int32_t scale_calibrate(float known_weight_grams) {
 }
 int32_t scale_get_tare_offset(void) { return 0; }  // Tare offset is managed by ESP32
 
+// Multi-point calibration: the backend only relays single-point calibration,
+// so the points are counted here and Finish calibrates with the last weight
+// (still on the scale)
+static int sim_cal_point_count = 0;
+static float sim_cal_last_weight = 0.0f;
+
+void scale_cal_clear_points(void) {
+    sim_cal_point_count = 0;
+}
+int32_t scale_cal_add_point(float known_weight_grams) {
+    sim_cal_last_weight = known_weight_grams;
+    return ++sim_cal_point_count;
+}
+int32_t scale_cal_point_count(void) { return sim_cal_point_count; }
+int32_t scale_cal_finish(void) {
+    if (sim_cal_point_count == 0) return -1;
+    printf("[scale] Sending calibrate command to ESP32 (last of %d points: %.1f g)...\n",
+           sim_cal_point_count, sim_cal_last_weight);
+    return backend_scale_calibrate(sim_cal_last_weight);
+}
+bool scale_cal_is_quadratic(void) { return false; }
+float scale_cal_get_max_error(void) { return 0.0f; }
+
 // Simulator control functions (kept for compatibility, but now no-op)
 void sim_set_scale_weight(float weight) { (void)weight; }
 void sim_set_scale_initialized(bool initialized) { (void)initialized; }
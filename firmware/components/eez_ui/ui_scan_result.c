@@ -67,6 +67,12 @@ static int32_t captured_spool_weight = 0;  // Label weight in grams from NFC tag
 static char captured_slicer_filament[32] = {0};
 static bool captured_in_inventory = false;  // True if spool found in backend inventory
 
+// Backend requests in flight (polled in ui_scan_result_update)
+static bool k_profile_pending = false;
+static bool assign_pending = false;
+static int assign_ams_id = -1;      // Slot being assigned (selection may change meanwhile)
+static int assign_slot_index = -1;
+
 // Pre-set the tag ID before navigating to scan_result screen
 // This avoids race conditions where nfc_tag_present() might return false during screen transition
 void ui_scan_result_set_tag_id(const char *tag_id) {
@@ -491,10 +497,14 @@ static void populate_spool_panel(void) {
         lv_obj_set_style_image_recolor_opa(objects.scan_screen_main_panel_spool_panel_icon_spool_color, 255, 0);
     }
 
-    // K-profile: Look up from backend if spool is in inventory
-    bool k_profile_found = false;
-    SpoolKProfileC k_profile = {0};
+    // K-profile: "-" until the lookup below answers (see update_k_profile)
+    if (objects.scan_screen_main_panel_spool_panel_label_k_factor_value)
+        lv_label_set_text(objects.scan_screen_main_panel_spool_panel_label_k_factor_value, "-");
+    if (objects.scan_screen_main_panel_spool_panel_label_k_profile_value)
+        lv_label_set_text(objects.scan_screen_main_panel_spool_panel_label_k_profile_value, "-");
 
+    // Look up from backend if spool is in inventory
+    k_profile_pending = false;
     if (captured_in_inventory && captured_spool_id[0]) {
         // Get selected printer serial
         int printer_idx = get_selected_printer_index();
@@ -502,31 +512,35 @@ static void populate_spool_panel(void) {
             BackendPrinterInfo printer_info = {0};
             if (backend_get_printer(printer_idx, &printer_info) == 0 && printer_info.serial[0]) {
                 // Look up K-profile for this spool on this printer
-                k_profile_found = spool_get_k_profile_for_printer(captured_spool_id,
-                                                                   printer_info.serial, &k_profile);
-                ESP_LOGI("ui_scan_result", "K-profile lookup: spool=%s printer=%s found=%d",
-                         captured_spool_id, printer_info.serial, k_profile_found);
+                k_profile_pending = spool_k_profile_start(captured_spool_id, printer_info.serial) == 0;
+                ESP_LOGI("ui_scan_result", "K-profile lookup: spool=%s printer=%s started=%d",
+                         captured_spool_id, printer_info.serial, k_profile_pending);
             }
         }
     }
+}
+
+// Show the K-profile once the lookup answered
+static void update_k_profile(void) {
+    if (!k_profile_pending) return;
+
+    SpoolKProfileC k_profile = {0};
+    int state = spool_k_profile_poll(&k_profile);
+    if (state == BACKEND_REQUEST_PENDING) return;
+    k_profile_pending = false;
+
+    bool k_profile_found = (state == BACKEND_REQUEST_DONE);
+    ESP_LOGI("ui_scan_result", "K-profile lookup: found=%d", k_profile_found);
 
     // Set K factor value
-    if (objects.scan_screen_main_panel_spool_panel_label_k_factor_value) {
-        if (k_profile_found && k_profile.k_value[0]) {
-            lv_label_set_text(objects.scan_screen_main_panel_spool_panel_label_k_factor_value, (const char*)k_profile.k_value);
-            ESP_LOGI("ui_scan_result", "K factor: %s", k_profile.k_value);
-        } else {
-            lv_label_set_text(objects.scan_screen_main_panel_spool_panel_label_k_factor_value, "-");
-        }
+    if (objects.scan_screen_main_panel_spool_panel_label_k_factor_value && k_profile_found && k_profile.k_value[0]) {
+        lv_label_set_text(objects.scan_screen_main_panel_spool_panel_label_k_factor_value, (const char*)k_profile.k_value);
+        ESP_LOGI("ui_scan_result", "K factor: %s", k_profile.k_value);
     }
     // Set K profile name
-    if (objects.scan_screen_main_panel_spool_panel_label_k_profile_value) {
-        if (k_profile_found && k_profile.name[0]) {
-            lv_label_set_text(objects.scan_screen_main_panel_spool_panel_label_k_profile_value, (const char*)k_profile.name);
-            ESP_LOGI("ui_scan_result", "K profile: %s", k_profile.name);
-        } else {
-            lv_label_set_text(objects.scan_screen_main_panel_spool_panel_label_k_profile_value, "-");
-        }
+    if (objects.scan_screen_main_panel_spool_panel_label_k_profile_value && k_profile_found && k_profile.name[0]) {
+        lv_label_set_text(objects.scan_screen_main_panel_spool_panel_label_k_profile_value, (const char*)k_profile.name);
+        ESP_LOGI("ui_scan_result", "K profile: %s", k_profile.name);
     }
 }
 
@@ -680,7 +694,8 @@ void ui_scan_result_init(void) {
     int printer_idx = get_selected_printer_index();
     bool is_dual_nozzle = is_selected_printer_dual_nozzle();
 
-    // Reset selection state
+    // Reset selection state (a still-running assignment completes on the backend)
+    assign_pending = false;
     selected_ams_id = -1;
     selected_slot_index = -1;
     selected_slot_obj = NULL;
@@ -845,8 +860,13 @@ void ui_scan_result_init(void) {
     ESP_LOGI("ui_scan_result", "ui_scan_result_init complete");
 }
 
+static void update_assign_request(void);
+
 // Update scan result screen (called from ui_tick)
 void ui_scan_result_update(void) {
+    update_k_profile();
+    update_assign_request();
+
     // Update weight display with live weight from scale (integer, 0 for negatives)
     float weight = 0;
     bool scale_ok = scale_is_initialized();
@@ -967,7 +987,8 @@ static void show_assign_result_popup(int result, const char *ams_name, int slot_
     lv_obj_set_style_pad_all(card, 20, LV_PART_MAIN);
     lv_obj_clear_flag(card, LV_OBJ_FLAG_SCROLLABLE);
 
-    bool is_success = (result == ASSIGN_RESULT_CONFIGURED || result == ASSIGN_RESULT_STAGED || result == ASSIGN_RESULT_STAGED_REPLACE);
+    bool is_success = (result == ASSIGN_RESULT_CONFIGURED || result == ASSIGN_RESULT_STAGED ||
+                       result == ASSIGN_RESULT_STAGED_REPLACE || result == ASSIGN_RESULT_QUEUED);
     bool needs_insert = (result == ASSIGN_RESULT_STAGED || result == ASSIGN_RESULT_STAGED_REPLACE);
 
     // Border color based on result
@@ -985,6 +1006,8 @@ static void show_assign_result_popup(int result, const char *ams_name, int slot_
     lv_obj_t *title = lv_label_create(card);
     if (result == ASSIGN_RESULT_ERROR) {
         lv_label_set_text(title, "Configuration Failed");
+    } else if (result == ASSIGN_RESULT_QUEUED) {
+        lv_label_set_text(title, "Saved Offline");
     } else if (needs_insert) {
         lv_label_set_text(title, "Slot Configured");
     } else {
@@ -1021,7 +1044,10 @@ static void show_assign_result_popup(int result, const char *ams_name, int slot_
     // Action/status message
     lv_obj_t *action_label = lv_label_create(card);
     char action_text[128];
-    if (is_success && needs_insert) {
+    if (result == ASSIGN_RESULT_QUEUED) {
+        snprintf(action_text, sizeof(action_text), "Will be applied to %s\nwhen the server is back", slot_text);
+        lv_obj_set_style_text_color(action_label, lv_color_hex(0xFF9800), LV_PART_MAIN);
+    } else if (is_success && needs_insert) {
         snprintf(action_text, sizeof(action_text), "Please insert spool into\n%s", slot_text);
         lv_obj_set_style_text_color(action_label, lv_color_hex(0xFF9800), LV_PART_MAIN);
     } else if (is_success) {
@@ -1077,6 +1103,8 @@ static void assign_button_click_handler(lv_event_t *e) {
     (void)e;
 
     ESP_LOGI("ui_scan_result", "=== ASSIGN BUTTON CLICKED ===");
+
+    if (assign_pending) return;  // Previous assignment still running
     ESP_LOGI("ui_scan_result", "Assign: ams_id=%d, slot=%d, spool_id=%s, in_inventory=%d",
              selected_ams_id, selected_slot_index, captured_spool_id, captured_in_inventory);
 
@@ -1112,9 +1140,26 @@ static void assign_button_click_handler(lv_event_t *e) {
     ESP_LOGI("ui_scan_result", "Assigning spool %s to printer %s, AMS %d, tray %d",
              captured_spool_id, printer_info.serial, selected_ams_id, selected_slot_index);
 
-    // Call backend to assign spool to tray
-    int assign_result = backend_assign_spool_to_tray(printer_info.serial, selected_ams_id,
-                                                      selected_slot_index, captured_spool_id);
+    // Call backend to assign spool to tray (result shown by update_assign_request)
+    assign_ams_id = selected_ams_id;
+    assign_slot_index = selected_slot_index;
+    if (backend_assign_spool_to_tray_start(printer_info.serial, assign_ams_id,
+                                           assign_slot_index, captured_spool_id) == 0) {
+        assign_pending = true;
+    } else {
+        show_assign_result_popup(ASSIGN_RESULT_ERROR, get_ams_display_name(assign_ams_id), assign_slot_index + 1);
+    }
+}
+
+// Show the assignment result once the backend answered
+static void update_assign_request(void) {
+    if (!assign_pending) return;
+
+    int assign_result = ASSIGN_RESULT_ERROR;
+    int state = backend_assign_spool_to_tray_poll(&assign_result);
+    if (state == BACKEND_REQUEST_PENDING) return;
+    assign_pending = false;
+    if (state != BACKEND_REQUEST_DONE) assign_result = ASSIGN_RESULT_ERROR;
 
     ESP_LOGI("ui_scan_result", "Assign result: %d (0=error, 1=configured, 2=staged, 3=staged_replace)", assign_result);
 
@@ -1124,8 +1169,8 @@ static void assign_button_click_handler(lv_event_t *e) {
     }
 
     // Get AMS display name and slot number for popup
-    const char *ams_name = get_ams_display_name(selected_ams_id);
-    int slot_display = selected_slot_index + 1;  // 1-based for display
+    const char *ams_name = get_ams_display_name(assign_ams_id);
+    int slot_display = assign_slot_index + 1;  // 1-based for display
 
     // Show result popup (will auto-navigate back to main screen)
     show_assign_result_popup(assign_result, ams_name, slot_display);
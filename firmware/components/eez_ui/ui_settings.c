@@ -6,6 +6,7 @@
 
 #include "ui_internal.h"
 #include "screens.h"
+#include <stdio.h>
 #include <string.h>
 
 // =============================================================================
@@ -124,9 +125,21 @@ static void settings_detail_back_handler(lv_event_t *e) {
 
 static lv_obj_t *keyboard_settings_row = NULL;
 
-// Reset keyboard row pointer when screens are deleted
+// Backend server row and dialog (see below)
+static lv_obj_t *server_settings_row = NULL;
+static lv_obj_t *server_row_value = NULL;
+static lv_obj_t *server_dialog = NULL;
+static lv_obj_t *server_dialog_ta = NULL;
+static lv_obj_t *server_dialog_status = NULL;
+
+// Reset keyboard and server row pointers when screens are deleted
 void ui_settings_cleanup(void) {
     keyboard_settings_row = NULL;
+    server_settings_row = NULL;
+    server_row_value = NULL;
+    server_dialog = NULL;
+    server_dialog_ta = NULL;
+    server_dialog_status = NULL;
 }
 
 // Direct click handler for keyboard row (avoids label search issues)
@@ -198,6 +211,228 @@ static void add_keyboard_row_to_hardware_tab(void) {
     lv_obj_add_event_cb(row, keyboard_row_click_handler, LV_EVENT_CLICKED, NULL);
 }
 
+// =============================================================================
+// Backend Server Row and Dialog
+// =============================================================================
+// The server URL is saved in NVS by the firmware (backend_set_url). Without
+// one the firmware keeps looking for the server via mDNS; this is where it is
+// entered by hand on first boot, or cleared to go back to discovery.
+
+// Current server for the row and dialog: URL, or what the firmware is doing
+static void format_server_state(char *buf, size_t buf_len) {
+    BackendStatus status;
+    backend_get_status(&status);
+
+    char url[128];
+    int len = backend_get_server_url(url, sizeof(url));
+    if (len <= 0) {
+        snprintf(buf, buf_len, "%s", status.state == 1 ? "Searching..." : "Not found");
+        return;
+    }
+
+    const char *state = "";
+    switch (status.state) {
+        case 1: state = " (connecting)"; break;
+        case 4: state = " (not paired)"; break;
+        case 5: state = " (certificate changed)"; break;
+        case 2: break;
+        default: state = " (offline)"; break;
+    }
+    snprintf(buf, buf_len, "%s%s", url, state);
+}
+
+static void update_server_row(void) {
+    if (!server_row_value) return;
+    char text[160];
+    format_server_state(text, sizeof(text));
+    lv_label_set_text(server_row_value, text);
+}
+
+static void server_dialog_close(void) {
+    if (server_dialog) {
+        lv_obj_delete(server_dialog);
+    }
+    server_dialog = NULL;
+    server_dialog_ta = NULL;
+    server_dialog_status = NULL;
+    update_server_row();
+}
+
+static void server_cancel_handler(lv_event_t *e) {
+    (void)e;
+    server_dialog_close();
+}
+
+static void server_save_handler(lv_event_t *e) {
+    (void)e;
+    if (!server_dialog_ta) return;
+
+    const char *url = lv_textarea_get_text(server_dialog_ta);
+    if (backend_set_url(url) != 0) {
+        lv_label_set_text(server_dialog_status, "Invalid URL (e.g. http://192.168.1.10:3000)");
+        lv_obj_set_style_text_color(server_dialog_status, lv_color_hex(0xffff5555), LV_PART_MAIN);
+        return;
+    }
+    server_dialog_close();
+}
+
+// Clear the saved URL: the firmware goes back to mDNS discovery
+static void server_discover_handler(lv_event_t *e) {
+    (void)e;
+    backend_set_url("");
+    server_dialog_close();
+}
+
+static void server_keyboard_handler(lv_event_t *e) {
+    lv_event_code_t code = lv_event_get_code(e);
+    if (code == LV_EVENT_READY) {
+        server_save_handler(e);
+    } else if (code == LV_EVENT_CANCEL) {
+        server_dialog_close();
+    }
+}
+
+static lv_obj_t *create_server_dialog_button(lv_obj_t *parent, const char *text, uint32_t color,
+                                             int x, lv_event_cb_t handler) {
+    lv_obj_t *btn = lv_button_create(parent);
+    lv_obj_set_pos(btn, x, 170);
+    lv_obj_set_size(btn, 180, 44);
+    lv_obj_set_style_bg_color(btn, lv_color_hex(color), LV_PART_MAIN);
+    lv_obj_set_style_radius(btn, 8, LV_PART_MAIN);
+    lv_obj_add_event_cb(btn, handler, LV_EVENT_CLICKED, NULL);
+
+    lv_obj_t *label = lv_label_create(btn);
+    lv_label_set_text(label, text);
+    lv_obj_set_style_text_font(label, &lv_font_montserrat_16, LV_PART_MAIN);
+    lv_obj_set_style_text_color(label, lv_color_hex(color == 0xff00ff00 ? 0xff000000 : 0xffffffff), LV_PART_MAIN);
+    lv_obj_center(label);
+    return btn;
+}
+
+static void server_dialog_open(void) {
+    if (server_dialog) return;
+
+    // Full-screen dialog on the settings screen (deleted with it)
+    server_dialog = lv_obj_create(lv_scr_act());
+    lv_obj_set_size(server_dialog, 800, 480);
+    lv_obj_set_pos(server_dialog, 0, 0);
+    lv_obj_set_style_bg_color(server_dialog, lv_color_hex(0xff1a1a1a), LV_PART_MAIN);
+    lv_obj_set_style_bg_opa(server_dialog, 255, LV_PART_MAIN);
+    lv_obj_set_style_border_width(server_dialog, 0, LV_PART_MAIN);
+    lv_obj_set_style_radius(server_dialog, 0, LV_PART_MAIN);
+    lv_obj_set_style_pad_all(server_dialog, 16, LV_PART_MAIN);
+    lv_obj_clear_flag(server_dialog, LV_OBJ_FLAG_SCROLLABLE);
+
+    lv_obj_t *title = lv_label_create(server_dialog);
+    lv_obj_set_pos(title, 0, 0);
+    lv_label_set_text(title, "Backend Server");
+    lv_obj_set_style_text_font(title, &lv_font_montserrat_20, LV_PART_MAIN);
+    lv_obj_set_style_text_color(title, lv_color_hex(0xffffffff), LV_PART_MAIN);
+
+    server_dialog_status = lv_label_create(server_dialog);
+    lv_obj_set_pos(server_dialog_status, 0, 36);
+    lv_obj_set_width(server_dialog_status, 768);
+    char state[160];
+    format_server_state(state, sizeof(state));
+    lv_label_set_text(server_dialog_status, state);
+    lv_obj_set_style_text_font(server_dialog_status, &lv_font_montserrat_14, LV_PART_MAIN);
+    lv_obj_set_style_text_color(server_dialog_status, lv_color_hex(0xff888888), LV_PART_MAIN);
+
+    server_dialog_ta = lv_textarea_create(server_dialog);
+    lv_obj_set_pos(server_dialog_ta, 0, 70);
+    lv_obj_set_size(server_dialog_ta, 768, 50);
+    lv_textarea_set_one_line(server_dialog_ta, true);
+    lv_textarea_set_max_length(server_dialog_ta, 127);
+    lv_textarea_set_placeholder_text(server_dialog_ta, "http://192.168.1.10:3000");
+    char url[128];
+    if (backend_get_server_url(url, sizeof(url)) > 0) {
+        lv_textarea_set_text(server_dialog_ta, url);
+    }
+    lv_obj_set_style_text_font(server_dialog_ta, &lv_font_montserrat_18, LV_PART_MAIN);
+    lv_obj_set_style_bg_color(server_dialog_ta, lv_color_hex(0xff2d2d2d), LV_PART_MAIN);
+    lv_obj_set_style_text_color(server_dialog_ta, lv_color_hex(0xffffffff), LV_PART_MAIN);
+    lv_obj_set_style_border_color(server_dialog_ta, lv_color_hex(0xff00ff00), LV_PART_MAIN | LV_STATE_FOCUSED);
+    lv_obj_add_state(server_dialog_ta, LV_STATE_FOCUSED);
+
+    lv_obj_t *hint = lv_label_create(server_dialog);
+    lv_obj_set_pos(hint, 0, 130);
+    lv_label_set_text(hint, "Leave empty or press Discover to find the server automatically");
+    lv_obj_set_style_text_font(hint, &lv_font_montserrat_14, LV_PART_MAIN);
+    lv_obj_set_style_text_color(hint, lv_color_hex(0xff666666), LV_PART_MAIN);
+
+    create_server_dialog_button(server_dialog, "Cancel", 0xff3d3d3d, 0, server_cancel_handler);
+    create_server_dialog_button(server_dialog, "Discover", 0xff3d3d3d, 294, server_discover_handler);
+    create_server_dialog_button(server_dialog, "Save", 0xff00ff00, 588, server_save_handler);
+
+    // Keyboard always shown: entering the URL is the only thing to do here
+    lv_obj_t *keyboard = lv_keyboard_create(server_dialog);
+    lv_obj_set_size(keyboard, 768, 220);
+    lv_obj_align(keyboard, LV_ALIGN_BOTTOM_MID, 0, 0);
+    lv_keyboard_set_textarea(keyboard, server_dialog_ta);
+    lv_obj_add_event_cb(keyboard, server_keyboard_handler, LV_EVENT_ALL, NULL);
+    apply_keyboard_layout(keyboard);
+}
+
+static void server_row_click_handler(lv_event_t *e) {
+    (void)e;
+    server_dialog_open();
+}
+
+static void add_server_row_to_network_tab(void) {
+    if (!objects.settings_screen_tabs_network_content) return;
+    if (server_settings_row) return;  // Already added
+
+    // Row below WiFi (y10), same style
+    lv_obj_t *row = lv_obj_create(objects.settings_screen_tabs_network_content);
+    server_settings_row = row;
+    lv_obj_set_pos(row, 15, 70);
+    lv_obj_set_size(row, 770, 50);
+    lv_obj_set_style_pad_top(row, 0, LV_PART_MAIN);
+    lv_obj_set_style_pad_bottom(row, 0, LV_PART_MAIN);
+    lv_obj_clear_flag(row, LV_OBJ_FLAG_SCROLLABLE | LV_OBJ_FLAG_SCROLL_CHAIN_HOR |
+                      LV_OBJ_FLAG_SCROLL_CHAIN_VER | LV_OBJ_FLAG_SCROLL_ELASTIC |
+                      LV_OBJ_FLAG_SCROLL_MOMENTUM | LV_OBJ_FLAG_SCROLL_WITH_ARROW);
+    lv_obj_set_style_bg_color(row, lv_color_hex(0xff2d2d2d), LV_PART_MAIN);
+    lv_obj_set_style_bg_opa(row, 255, LV_PART_MAIN);
+    lv_obj_set_style_radius(row, 8, LV_PART_MAIN);
+    lv_obj_set_style_border_width(row, 0, LV_PART_MAIN);
+    lv_obj_set_style_pad_left(row, 15, LV_PART_MAIN);
+    lv_obj_set_style_pad_right(row, 15, LV_PART_MAIN);
+
+    lv_obj_t *icon = lv_label_create(row);
+    lv_obj_set_pos(icon, 5, 13);
+    lv_label_set_text(icon, LV_SYMBOL_DRIVE);
+    lv_obj_set_style_text_font(icon, &lv_font_montserrat_24, LV_PART_MAIN);
+    lv_obj_set_style_text_color(icon, lv_color_hex(0xff00ff00), LV_PART_MAIN);
+
+    lv_obj_t *label = lv_label_create(row);
+    lv_obj_set_pos(label, 45, 15);
+    lv_obj_set_size(label, 200, 20);
+    lv_label_set_text(label, "Backend Server");
+    lv_obj_set_style_text_color(label, lv_color_hex(0xffffffff), LV_PART_MAIN);
+    lv_obj_set_style_text_font(label, &lv_font_montserrat_16, LV_PART_MAIN);
+
+    server_row_value = lv_label_create(row);
+    lv_obj_set_pos(server_row_value, 255, 15);
+    lv_obj_set_size(server_row_value, 430, 20);
+    lv_label_set_long_mode(server_row_value, LV_LABEL_LONG_DOT);
+    lv_obj_set_style_text_align(server_row_value, LV_TEXT_ALIGN_RIGHT, LV_PART_MAIN);
+    lv_obj_set_style_text_color(server_row_value, lv_color_hex(0xff888888), LV_PART_MAIN);
+    lv_obj_set_style_text_font(server_row_value, &lv_font_montserrat_14, LV_PART_MAIN);
+    update_server_row();
+
+    lv_obj_t *arrow = lv_label_create(row);
+    lv_obj_set_pos(arrow, 710, 15);
+    lv_label_set_text(arrow, ">");
+    lv_obj_set_style_text_color(arrow, lv_color_hex(0xff666666), LV_PART_MAIN);
+    lv_obj_set_style_text_font(arrow, &lv_font_montserrat_18, LV_PART_MAIN);
+
+    lv_obj_add_flag(row, LV_OBJ_FLAG_CLICKABLE);
+    lv_obj_remove_flag(row, LV_OBJ_FLAG_SCROLL_ON_FOCUS);
+    lv_obj_set_style_bg_color(row, lv_color_hex(0xff3d3d3d), LV_PART_MAIN | LV_STATE_PRESSED);
+    lv_obj_add_event_cb(row, server_row_click_handler, LV_EVENT_CLICKED, NULL);
+}
+
 // =============================================================================
 // Wire Functions
 // =============================================================================
@@ -235,8 +470,9 @@ void wire_settings_buttons(void) {
     wire_content_rows(objects.settings_screen_tabs_hardware_content);
     wire_content_rows(objects.settings_screen_tabs_system_content);
 
-    // Add keyboard row to hardware tab (not in EEZ design)
+    // Add keyboard row to hardware tab and server row to network tab (not in EEZ design)
     add_keyboard_row_to_hardware_tab();
+    add_server_row_to_network_tab();
 
     // Initialize with first tab selected, hide others
     select_settings_tab(0);
@@ -334,6 +334,30 @@ void ui_status_bar_update(void) {
             lv_color_hex(connected ? COLOR_GREEN : COLOR_RED), 0);
     }
 
+#ifdef ESP_PLATFORM
+    // Show a refused server certificate or the pairing code while waiting
+    // for an API key, otherwise mutations waiting for the server (offline outbox)
+    if (backend_label) {
+        char server_text[32];
+        char pairing_code[12];
+        BackendStatus backend_status;
+        int queued = backend_get_outbox_count();
+        backend_get_status(&backend_status);
+        if (backend_status.state == 5) {
+            snprintf(server_text, sizeof(server_text), "Server cert changed");
+        } else if (backend_get_pairing_code(pairing_code, sizeof(pairing_code)) > 0) {
+            snprintf(server_text, sizeof(server_text), "Pair code %s", pairing_code);
+        } else if (queued > 0) {
+            snprintf(server_text, sizeof(server_text), "Server (%d queued)", queued);
+        } else {
+            snprintf(server_text, sizeof(server_text), "Server");
+        }
+        if (strcmp(lv_label_get_text(backend_label), server_text) != 0) {
+            lv_label_set_text(backend_label, server_text);
+        }
+    }
+#endif
+
     // =========================================================================
     // Update active tray badge and material
     // =========================================================================
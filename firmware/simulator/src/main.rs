@@ -14,6 +14,7 @@ use embedded_graphics::{
     text::{Alignment, Text},
 };
 use image::{DynamicImage, GenericImageView, ImageBuffer, Rgb};
+use spoolbuddy_ui::color_names::color_name;
 use std::path::Path;
 
 // Embedded icon data (loaded at startup)
@@ -202,6 +203,11 @@ fn light_theme() -> Theme {
     }
 }
 
+/// Filament color (0xRRGGBBAA) as RGB565
+fn filament_rgb565(rgba: u32) -> Rgb565 {
+    Rgb565::new((rgba >> 27) as u8 & 0x1F, (rgba >> 18) as u8 & 0x3F, (rgba >> 11) as u8 & 0x1F)
+}
+
 struct AppState {
     weight: f32,
     weight_stable: bool,
@@ -448,8 +454,9 @@ fn render_spool_info(fb: &mut Framebuffer, state: &AppState, theme: &Theme, _ico
     .into_styled(PrimitiveStyle::with_fill(theme.card_bg))
     .draw(fb);
 
-    // Color swatch (jade green) - larger
-    let swatch_color = Rgb565::new(0x02, 0x30, 0x10); // More muted jade
+    // Color swatch - larger
+    let spool_color = 0x10C080FF; // Muted jade green
+    let swatch_color = filament_rgb565(spool_color);
     let _ = RoundedRectangle::with_equal_corners(
         Rectangle::new(Point::new(card_x + 20, card_y + 20), Size::new(80, 80)),
         Size::new(12, 12),
@@ -462,7 +469,7 @@ fn render_spool_info(fb: &mut Framebuffer, state: &AppState, theme: &Theme, _ico
     let subtitle_style = MonoTextStyle::new(&FONT_6X10, theme.text_secondary);
 
     let _ = Text::new("Bambu Lab PLA Basic", Point::new(text_x, card_y + 36), title_style).draw(fb);
-    let _ = Text::new("Jade White", Point::new(text_x, card_y + 56), subtitle_style).draw(fb);
+    let _ = Text::new(&color_name(spool_color), Point::new(text_x, card_y + 56), subtitle_style).draw(fb);
 
     // Progress bar - smoother
     let progress_y = card_y + 72;
@@ -3,28 +3,39 @@
 //! Provides HTTP polling to the SpoolBuddy backend server for printer status.
 //! Uses mDNS to discover the server automatically.
 
-use esp_idf_svc::http::client::{Configuration as HttpConfig, EspHttpConnection};
+use esp_idf_svc::http::client::EspHttpConnection;
+use esp_idf_svc::nvs::{EspDefaultNvsPartition, EspNvs};
 use log::{info, warn};
 use serde::Deserialize;
 use std::ffi::{c_char, c_int};
 use std::sync::Mutex;
 use embedded_svc::http::client::Client as HttpClient;
 
-/// Maximum number of printers to cache (reduced for memory)
-const MAX_PRINTERS: usize = 4;
-
-/// Maximum number of AMS units per printer
-const MAX_AMS_UNITS: usize = 4;
+use crate::backend_worker::Job;
+use crate::device_auth;
+use crate::outbox::{self, Method, OutboxEntry};
+use crate::tls_pin;
 
 /// HTTP timeout in milliseconds
 const HTTP_TIMEOUT_MS: u64 = 5000;
 
+/// NVS namespace and key for the configured server URL
+const NVS_NAMESPACE: &str = "backend";
+const NVS_KEY_URL: &str = "url";
+
+/// Default backend port when an http URL has none
+const DEFAULT_HTTP_PORT: u16 = 3000;
+
 /// Backend connection state
 #[derive(Debug, Clone, PartialEq)]
 pub enum BackendState {
     Disconnected,
     Discovering,
     Connected { ip: [u8; 4], port: u16 },
+    /// Server reachable but the device API key was refused (HTTP 401/403)
+    Unauthorized { ip: [u8; 4], port: u16, status: u16 },
+    /// Server reachable but its TLS certificate is not the pinned one
+    CertificateMismatch { ip: [u8; 4], port: u16 },
     #[allow(dead_code)]
     Error(String),
 }
@@ -56,6 +67,8 @@ struct ApiAmsUnit {
 struct ApiPrinter {
     serial: String,
     name: Option<String>,
+    #[serde(default)]
+    model: Option<String>,
     ip_address: Option<String>,
     access_code: Option<String>,
     connected: bool,
@@ -68,6 +81,8 @@ struct ApiPrinter {
     stg_cur_name: Option<String>,  // Human-readable stage name
     #[serde(default)]
     ams_units: Vec<ApiAmsUnit>,
+    #[serde(default)]
+    vt_tray: Option<ApiAmsTray>,   // External spool holder
     tray_now: Option<i32>,
     tray_now_left: Option<i32>,
     tray_now_right: Option<i32>,
@@ -79,84 +94,90 @@ struct ApiPrinter {
 struct ApiTime {
     hour: u8,
     minute: u8,
+    #[serde(default)]
+    timestamp: Option<u64>,
+}
+
+/// Kind of AMS unit (C values in ui_internal.h)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AmsKind {
+    Ams = 0,
+    AmsLite = 1,
+    AmsHt = 2,
+    /// External spool holder (vt_tray)
+    External = 3,
+}
+
+impl AmsKind {
+    /// Kind of the unit with this id on a printer model
+    /// AMS HT units use ids 128-135, the external holder 254/255. A1 series
+    /// printers (model codes N1/N2S) only take the AMS Lite.
+    fn of_unit(id: i32, printer_model: Option<&str>) -> Self {
+        match id {
+            128..=135 => AmsKind::AmsHt,
+            254 | 255 => AmsKind::External,
+            _ => {
+                let model = printer_model.unwrap_or("").trim().to_ascii_uppercase();
+                if model.starts_with("A1") || model == "N1" || model == "N2S" {
+                    AmsKind::AmsLite
+                } else {
+                    AmsKind::Ams
+                }
+            }
+        }
+    }
 }
 
 /// Cached AMS tray info
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Default)]
 struct CachedAmsTray {
-    tray_type: [u8; 16],    // Material type
+    tray_type: String,      // Material type
     tray_color: u32,        // RGBA packed (0xRRGGBBAA)
     remain: u8,             // 0-100 percentage
 }
 
 /// Cached AMS unit info
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 struct CachedAmsUnit {
     id: i32,
+    kind: AmsKind,
     humidity: i32,          // -1 if not available
     temperature: i16,       // Celsius * 10, -1 if not available
     extruder: i8,           // -1 if not available, 0=right, 1=left
-    tray_count: u8,
-    trays: [CachedAmsTray; 4],
-}
-
-impl Default for CachedAmsUnit {
-    fn default() -> Self {
-        Self {
-            id: 0,
-            humidity: -1,
-            temperature: -1,
-            extruder: -1,
-            tray_count: 0,
-            trays: [CachedAmsTray::default(); 4],
-        }
-    }
+    trays: Vec<CachedAmsTray>,
 }
 
 /// Cached printer info (internal)
 #[derive(Debug, Clone)]
 struct CachedPrinter {
-    name: [u8; 32],
-    serial: [u8; 20],
-    ip_address: [u8; 20],
-    access_code: [u8; 16],
+    name: String,
+    serial: String,
+    ip_address: String,
+    access_code: String,
     connected: bool,
-    gcode_state: [u8; 16],
+    gcode_state: String,
     print_progress: u8,
-    subtask_name: [u8; 64],
+    subtask_name: String,
     remaining_time_min: u16,
     stg_cur: i8,            // Current stage number (-1 = idle)
-    stg_cur_name: [u8; 48], // Human-readable stage name
-    // AMS data
-    ams_unit_count: u8,
-    ams_units: [CachedAmsUnit; MAX_AMS_UNITS],
+    stg_cur_name: String,   // Human-readable stage name
+    /// AMS units in backend order, then the external spool holder
+    ams_units: Vec<CachedAmsUnit>,
     tray_now: i32,          // -1 if not available
     tray_now_left: i32,     // -1 if not available
     tray_now_right: i32,    // -1 if not available
     active_extruder: i32,   // -1 if not available, 0=right, 1=left
 }
 
-impl Default for CachedPrinter {
-    fn default() -> Self {
-        Self {
-            name: [0; 32],
-            serial: [0; 20],
-            ip_address: [0; 20],
-            access_code: [0; 16],
-            connected: false,
-            gcode_state: [0; 16],
-            print_progress: 0,
-            subtask_name: [0; 64],
-            remaining_time_min: 0,
-            stg_cur: -1,
-            stg_cur_name: [0; 48],
-            ams_unit_count: 0,
-            ams_units: [CachedAmsUnit::default(); MAX_AMS_UNITS],
-            tray_now: -1,
-            tray_now_left: -1,
-            tray_now_right: -1,
-            active_extruder: -1,
-        }
+impl CachedPrinter {
+    /// AMS units (AMS, AMS Lite, AMS HT), as indexed by the C interface
+    fn ams(&self) -> impl Iterator<Item = &CachedAmsUnit> {
+        self.ams_units.iter().filter(|u| u.kind != AmsKind::External)
+    }
+
+    /// External spool holders
+    fn external(&self) -> impl Iterator<Item = &CachedAmsUnit> {
+        self.ams_units.iter().filter(|u| u.kind == AmsKind::External)
     }
 }
 
@@ -164,54 +185,24 @@ impl Default for CachedPrinter {
 struct BackendManager {
     state: BackendState,
     server_url: String,
-    printers: [CachedPrinter; MAX_PRINTERS],
-    printer_count: usize,
-}
-
-const EMPTY_AMS_TRAY: CachedAmsTray = CachedAmsTray {
-    tray_type: [0; 16],
-    tray_color: 0,
-    remain: 0,
-};
-
-const EMPTY_AMS_UNIT: CachedAmsUnit = CachedAmsUnit {
-    id: 0,
-    humidity: -1,
-    temperature: -1,
-    extruder: -1,
-    tray_count: 0,
-    trays: [EMPTY_AMS_TRAY; 4],
-};
-
-const EMPTY_PRINTER: CachedPrinter = CachedPrinter {
-    name: [0; 32],
-    serial: [0; 20],
-    ip_address: [0; 20],
-    access_code: [0; 16],
-    connected: false,
-    gcode_state: [0; 16],
-    print_progress: 0,
-    subtask_name: [0; 64],
-    stg_cur_name: [0; 48],
-    remaining_time_min: 0,
-    stg_cur: -1,
-    ams_unit_count: 0,
-    ams_units: [EMPTY_AMS_UNIT; MAX_AMS_UNITS],
-    tray_now: -1,
-    tray_now_left: -1,
-    tray_now_right: -1,
-    active_extruder: -1,
-};
+    /// Address of `server_url` ([0; 4] if the host didn't resolve) and port
+    server_addr: ([u8; 4], u16),
+    printers: Vec<CachedPrinter>,
+}
 
 impl BackendManager {
     const fn new() -> Self {
         Self {
             state: BackendState::Disconnected,
             server_url: String::new(),
-            printers: [EMPTY_PRINTER; MAX_PRINTERS],
-            printer_count: 0,
+            server_addr: ([0; 4], 0),
+            printers: Vec::new(),
         }
     }
+
+    fn printer(&self, index: c_int) -> Option<&CachedPrinter> {
+        usize::try_from(index).ok().and_then(|i| self.printers.get(i))
+    }
 }
 
 // Global backend manager
@@ -223,39 +214,328 @@ static COVER_DATA: Mutex<Vec<u8>> = Mutex::new(Vec::new());
 static COVER_VALID: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
 static LAST_COVER_URL: Mutex<String> = Mutex::new(String::new());
 
+/// How long to listen for mDNS responses
+const DISCOVERY_TIMEOUT_MS: u64 = 3000;
+static DISCOVERY_RUNNING: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+// NVS partition for server URL persistence
+static NVS_PARTITION: Mutex<Option<EspDefaultNvsPartition>> = Mutex::new(None);
+
 /// Initialize the backend client
-pub fn init() {
+pub fn init(nvs: Option<EspDefaultNvsPartition>) {
+    *NVS_PARTITION.lock().unwrap() = nvs;
     info!("Backend client initialized");
 }
 
-/// Set the backend server URL manually
-pub fn set_server_url(url: &str) {
+/// Parsed backend server URL
+#[derive(Debug, Clone, PartialEq)]
+struct ServerUrl {
+    https: bool,
+    host: String,
+    port: u16,
+}
+
+/// Parse "http(s)://host[:port][/path]"
+/// Port defaults to 3000 for http (backend default) and 443 for https.
+fn parse_server_url(url: &str) -> Option<ServerUrl> {
+    let (https, rest) = if let Some(rest) = url.strip_prefix("http://") {
+        (false, rest)
+    } else if let Some(rest) = url.strip_prefix("https://") {
+        (true, rest)
+    } else {
+        return None;
+    };
+
+    let authority = rest.split('/').next()?;
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) => (host, port.parse::<u16>().ok().filter(|p| *p != 0)?),
+        None => (authority, if https { 443 } else { DEFAULT_HTTP_PORT }),
+    };
+
+    let valid_host = !host.is_empty()
+        && host.len() <= 253
+        && host.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '_');
+    if !valid_host {
+        return None;
+    }
+
+    Some(ServerUrl { https, host: host.to_string(), port })
+}
+
+/// Resolve a host to an IPv4 address (literal or DNS lookup)
+fn resolve_host(host: &str, port: u16) -> Option<[u8; 4]> {
+    use std::net::ToSocketAddrs;
+
+    if let Ok(ip) = host.parse::<std::net::Ipv4Addr>() {
+        return Some(ip.octets());
+    }
+
+    match (host, port).to_socket_addrs() {
+        Ok(addrs) => addrs.into_iter().find_map(|a| match a {
+            std::net::SocketAddr::V4(v4) => Some(v4.ip().octets()),
+            _ => None,
+        }),
+        Err(e) => {
+            warn!("Failed to resolve {}: {:?}", host, e);
+            None
+        }
+    }
+}
+
+/// Set the backend server URL (in memory only)
+/// Accepts http and https URLs with an IPv4 literal or hostname.
+/// Returns false if the URL is invalid; the current server is kept in that case.
+pub fn set_server_url(url: &str) -> bool {
+    let url = url.trim().trim_end_matches('/');
+    let Some(parsed) = parse_server_url(url) else {
+        warn!("Failed to parse server URL: {}", url);
+        return false;
+    };
+
+    // Resolve outside the lock (DNS lookups can block).
+    // An unresolved hostname is still used; the HTTP client resolves it per request.
+    // A pinned certificate stays pinned: https requests to a server presenting
+    // another one fail (see `tls_pin`).
+    let ip = resolve_host(&parsed.host, parsed.port).unwrap_or([0; 4]);
+
+    // Not connected until the server answers (see `note_auth_status`)
     let mut manager = BACKEND_MANAGER.lock().unwrap();
     manager.server_url = url.to_string();
+    manager.server_addr = (ip, parsed.port);
+    manager.state = BackendState::Discovering;
+    info!("Backend server set to: {} (https={})", url, parsed.https);
+    true
+}
+
+/// Set and persist the backend server URL
+/// An empty URL clears the stored value and falls back to mDNS discovery.
+/// Returns false if the URL is invalid.
+pub fn configure_server_url(url: &str) -> bool {
+    let url = url.trim().trim_end_matches('/');
+
+    if url.is_empty() {
+        save_url_to_nvs("");
+        {
+            let mut manager = BACKEND_MANAGER.lock().unwrap();
+            manager.server_url.clear();
+            manager.state = BackendState::Disconnected;
+        }
+        info!("Backend server URL cleared, starting discovery");
+        backend_discover_server();
+        return true;
+    }
+
+    if !set_server_url(url) {
+        return false;
+    }
+    save_url_to_nvs(url);
+    true
+}
+
+/// Apply the server URL saved in NVS, if any
+/// Returns true if a saved URL was applied.
+pub fn apply_saved_server_url() -> bool {
+    match load_url_from_nvs() {
+        Some(url) => {
+            info!("Loaded saved backend URL: {}", url);
+            set_server_url(&url)
+        }
+        None => false,
+    }
+}
+
+/// Load the configured server URL from NVS
+fn load_url_from_nvs() -> Option<String> {
+    let nvs_guard = NVS_PARTITION.lock().unwrap();
+    let nvs_partition = nvs_guard.as_ref()?;
+
+    let nvs = match EspNvs::new(nvs_partition.clone(), NVS_NAMESPACE, true) {
+        Ok(nvs) => nvs,
+        Err(e) => {
+            warn!("Failed to open NVS namespace for backend: {:?}", e);
+            return None;
+        }
+    };
+
+    let mut buf = [0u8; 256];
+    match nvs.get_str(NVS_KEY_URL, &mut buf) {
+        Ok(Some(s)) if !s.is_empty() => Some(s.to_string()),
+        Ok(_) => None,
+        Err(e) => {
+            warn!("Failed to read backend URL from NVS: {:?}", e);
+            None
+        }
+    }
+}
+
+/// Save the configured server URL to NVS (empty string removes it)
+fn save_url_to_nvs(url: &str) {
+    let nvs_guard = NVS_PARTITION.lock().unwrap();
+    let Some(nvs_partition) = nvs_guard.as_ref() else {
+        warn!("No NVS partition available for saving backend URL");
+        return;
+    };
+    let nvs_clone = nvs_partition.clone();
+    drop(nvs_guard); // Release lock before NVS operations
+
+    let Ok(nvs) = EspNvs::new(nvs_clone, NVS_NAMESPACE, true) else {
+        warn!("Failed to open NVS namespace for writing");
+        return;
+    };
+
+    let result = if url.is_empty() {
+        nvs.remove(NVS_KEY_URL).map(|_| ())
+    } else {
+        nvs.set_str(NVS_KEY_URL, url)
+    };
+
+    match result {
+        Ok(()) => info!("Backend URL saved to NVS"),
+        Err(e) => warn!("Failed to save backend URL to NVS: {:?}", e),
+    }
+}
 
-    // Parse IP from URL for status
-    if let Some(ip_str) = url.strip_prefix("http://") {
-        if let Some(ip_port) = ip_str.split('/').next() {
-            if let Some(ip_only) = ip_port.split(':').next() {
-                if let Ok(ip) = ip_only.parse::<std::net::Ipv4Addr>() {
-                    let octets = ip.octets();
-                    let port = ip_port.split(':').nth(1)
-                        .and_then(|p| p.parse().ok())
-                        .unwrap_or(3000);
-                    manager.state = BackendState::Connected { ip: octets, port };
-                    info!("Backend server set to: {}", url);
-                    return;
+/// Get the current backend server URL, if one is set
+pub fn get_server_url() -> Option<String> {
+    let manager = BACKEND_MANAGER.lock().unwrap();
+    if manager.server_url.is_empty() {
+        None
+    } else {
+        Some(manager.server_url.clone())
+    }
+}
+
+/// Track how the backend answered a request carrying the device API key
+/// The first successful (or 401/403) response from a newly set server leaves
+/// `Discovering`. 401 (missing or revoked key) and 403 (key lacks permission)
+/// enter `Unauthorized`, and a 401 also starts pairing for a new key. A
+/// successful response while unauthorized returns to `Connected`.
+pub fn note_auth_status(status: u16) {
+    let mut manager = BACKEND_MANAGER.lock().unwrap();
+    let answered = (200..300).contains(&status) || status == 401 || status == 403;
+    if manager.state == BackendState::Discovering
+        && answered
+        && !manager.server_url.is_empty()
+        && !DISCOVERY_RUNNING.load(std::sync::atomic::Ordering::Relaxed)
+    {
+        let (ip, port) = manager.server_addr;
+        info!("Backend server {} reachable", manager.server_url);
+        manager.state = BackendState::Connected { ip, port };
+    }
+    if let BackendState::CertificateMismatch { ip, port } = manager.state {
+        // Any response means the TLS handshake passed the pin again
+        info!("Backend certificate matches the pinned one again");
+        manager.state = BackendState::Connected { ip, port };
+    }
+    match manager.state.clone() {
+        BackendState::Connected { ip, port } | BackendState::Unauthorized { ip, port, .. }
+            if status == 401 || status == 403 =>
+        {
+            if matches!(manager.state, BackendState::Connected { .. }) {
+                warn!("Backend refused device API key (HTTP {})", status);
+            }
+            manager.state = BackendState::Unauthorized { ip, port, status };
+        }
+        BackendState::Unauthorized { ip, port, .. } if (200..300).contains(&status) => {
+            info!("Backend accepted device API key");
+            manager.state = BackendState::Connected { ip, port };
+        }
+        _ => {}
+    }
+    drop(manager);
+
+    if status == 401 {
+        device_auth::key_rejected();
+    }
+}
+
+/// Check whether a failed connection was refused because of the pinned certificate
+/// Enters `CertificateMismatch` when the server still answers without verification.
+fn check_certificate_mismatch(base_url: &str) {
+    if matches!(BACKEND_MANAGER.lock().unwrap().state, BackendState::CertificateMismatch { .. }) {
+        return;
+    }
+    if !tls_pin::is_pin_mismatch(base_url) {
+        return;
+    }
+
+    let mut manager = BACKEND_MANAGER.lock().unwrap();
+    let (ip, port) = match manager.state.clone() {
+        BackendState::Connected { ip, port } | BackendState::Unauthorized { ip, port, .. } => (ip, port),
+        BackendState::Discovering if !manager.server_url.is_empty() => manager.server_addr,
+        _ => return,
+    };
+    warn!(
+        "Backend certificate does not match the pinned certificate ({})",
+        tls_pin::fingerprint().unwrap_or_default()
+    );
+    manager.state = BackendState::CertificateMismatch { ip, port };
+}
+
+/// Browse for the backend via mDNS and use the best responder
+/// While a certificate is pinned, only the pinned server is taken.
+/// Blocks for up to DISCOVERY_TIMEOUT_MS. Returns true if a server was found.
+pub fn discover_server() -> bool {
+    use std::sync::atomic::Ordering;
+
+    if DISCOVERY_RUNNING.swap(true, Ordering::Relaxed) {
+        return false;
+    }
+
+    let previous_url = {
+        let mut manager = BACKEND_MANAGER.lock().unwrap();
+        manager.state = BackendState::Discovering;
+        manager.server_url.clone()
+    };
+
+    info!("Discovering backend server via mDNS...");
+    let found = match crate::mdns::discover(std::time::Duration::from_millis(DISCOVERY_TIMEOUT_MS)) {
+        Ok(instances) => {
+            // The pinned server wins, and no other is taken while one is pinned
+            let pinned = tls_pin::pinned_server();
+            let preferred = pinned.as_deref().or(Some(previous_url.as_str()).filter(|url| !url.is_empty()));
+            match crate::mdns::choose_instance(&instances, preferred).and_then(|i| i.url()) {
+                Some(url) if pinned.as_ref().is_some_and(|pinned| *pinned != url) => {
+                    warn!("Ignoring discovered backend {}: certificate pinned for another server", url);
+                    false
+                }
+                Some(url) => {
+                    info!("Discovered backend at {} ({} responder(s))", url, instances.len());
+                    set_server_url(&url);
+                    true
+                }
+                None => {
+                    warn!("No backend server found via mDNS");
+                    false
                 }
             }
         }
+        Err(e) => {
+            warn!("mDNS discovery failed: {}", e);
+            false
+        }
+    };
+
+    if !found {
+        // Fall back to the previous server, if any
+        if previous_url.is_empty() {
+            BACKEND_MANAGER.lock().unwrap().state = BackendState::Disconnected;
+        } else {
+            set_server_url(&previous_url);
+        }
     }
 
-    manager.state = BackendState::Disconnected;
-    warn!("Failed to parse server URL: {}", url);
+    DISCOVERY_RUNNING.store(false, Ordering::Relaxed);
+    found
 }
 
+/// Full printer fetch every N polls while the event stream is connected (~60 s)
+const PRINTER_RESYNC_POLLS: u32 = 30;
+static POLLS_SINCE_PRINTER_FETCH: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
+
 /// Poll the backend server for printer status and time
-/// Called from main loop every ~2 seconds
+/// Runs on the backend worker, queued by the main loop every ~2 seconds
+/// Printer data is skipped while the event stream delivers it.
 pub fn poll_backend() {
     let manager = BACKEND_MANAGER.lock().unwrap();
 
@@ -267,14 +547,39 @@ pub fn poll_backend() {
     let base_url = manager.server_url.clone();
     drop(manager); // Release lock before HTTP calls
 
+    // An https server's certificate is pinned before anything (the API key
+    // in the heartbeat first of all) is sent; requests fail until then
+    if !tls_pin::ensure_pinned(&base_url) {
+        return;
+    }
+
     // Send heartbeat to indicate display is connected
     send_heartbeat(&base_url);
 
+    // Obtain a device API key if there is none (or it was revoked)
+    if device_auth::needs_pairing() {
+        device_auth::poll_pairing(&base_url);
+    }
+
+    // Replay mutations recorded while the backend was unreachable
+    if outbox::len() > 0 {
+        flush_outbox(&base_url);
+    }
+
     // Send current scale weight to backend (so other clients can see it)
     let weight = crate::scale_manager::scale_get_weight();
     let stable = crate::scale_manager::scale_is_stable();
     send_device_state(None, weight, stable);
 
+    // Printers arrive on the event stream while it is up; still do a full
+    // fetch now and then to pick up changes the stream doesn't cover
+    let polls = POLLS_SINCE_PRINTER_FETCH.fetch_add(1, std::sync::atomic::Ordering::Relaxed) + 1;
+    if crate::event_stream::is_connected() && polls < PRINTER_RESYNC_POLLS {
+        fetch_and_set_time(&base_url);
+        return;
+    }
+    POLLS_SINCE_PRINTER_FETCH.store(0, std::sync::atomic::Ordering::Relaxed);
+
     // Fetch printers
     let printers_url = format!("{}/api/printers", base_url);
     let mut cover_url_to_fetch: Option<String> = None;
@@ -297,6 +602,8 @@ pub fn poll_backend() {
         fetch_cover_image(&url);
     }
 
+    feed_scale_temperature();
+
     // Fetch time from backend
     fetch_and_set_time(&base_url);
 }
@@ -347,17 +654,13 @@ fn get_wifi_params() -> String {
     params
 }
 
-// External C function to shutdown display before reboot
-extern "C" {
-    fn display_shutdown();
-}
+/// Maximum heartbeat response size (command list)
+const MAX_HEARTBEAT_RESPONSE: usize = 2048;
 
 /// Send heartbeat to backend to indicate display is connected
-/// Also checks for pending commands (e.g., reboot)
+/// Pending commands in the response are handed to the command worker.
 /// Includes WiFi status so backend always has current network info
 fn send_heartbeat(base_url: &str) {
-    use esp_idf_sys::esp_restart;
-
     let version = env!("CARGO_PKG_VERSION");
     let update_available = crate::ota_manager::is_update_available();
     let wifi_params = get_wifi_params();
@@ -366,10 +669,7 @@ fn send_heartbeat(base_url: &str) {
         base_url, version, update_available, wifi_params
     );
 
-    let config = HttpConfig {
-        timeout: Some(std::time::Duration::from_millis(2000)),
-        ..Default::default()
-    };
+    let config = tls_pin::http_config(std::time::Duration::from_millis(2000));
 
     let connection = match EspHttpConnection::new(&config) {
         Ok(c) => c,
@@ -378,72 +678,133 @@ fn send_heartbeat(base_url: &str) {
 
     let mut client = HttpClient::wrap(connection);
 
-    let request = match client.get(&url) {
+    let api_key = device_auth::api_key();
+    let headers = device_auth::with_api_key(&[], &api_key);
+    let request = match client.request(embedded_svc::http::Method::Get, &url, &headers) {
         Ok(r) => r,
         Err(_) => return,
     };
 
     let mut response = match request.submit() {
         Ok(r) => r,
-        Err(_) => return,
+        Err(_) => {
+            check_certificate_mismatch(base_url);
+            return;
+        }
     };
 
-    // Read response to check for commands
-    let mut buf = [0u8; 256];
-    if let Ok(n) = response.read(&mut buf) {
-        if n > 0 {
-            let body = String::from_utf8_lossy(&buf[..n]);
-            // Check for update command (triggers OTA)
-            if body.contains("\"command\":\"update\"") || body.contains("\"command\": \"update\"") {
-                log::info!("Received update command from backend - starting OTA");
-                if let Err(e) = crate::ota_manager::perform_update(base_url) {
-                    log::error!("OTA update failed: {}", e);
-                }
-                // perform_update reboots on success, so we only get here on failure
-            }
-            // Check for reboot command
-            else if body.contains("\"command\":\"reboot\"") || body.contains("\"command\": \"reboot\"") {
-                log::info!("Received reboot command from backend");
-                // Properly shutdown display before reboot to prevent display shift
-                unsafe { display_shutdown(); }
-                std::thread::sleep(std::time::Duration::from_millis(100));
-                unsafe { esp_restart(); }
-            }
-            // Check for scale tare command
-            else if body.contains("\"command\":\"scale_tare\"") || body.contains("\"command\": \"scale_tare\"") {
-                log::info!("Received scale_tare command from backend");
-                let result = crate::scale_manager::scale_tare();
-                log::info!("Scale tare result: {}", result);
-            }
-            // Check for scale calibrate command (e.g., "scale_calibrate:100.0")
-            else if body.contains("\"command\":\"scale_calibrate:") || body.contains("\"command\": \"scale_calibrate:") {
-                log::info!("Detected scale_calibrate command in response body");
-                // Extract the weight value from command
-                if let Some(start) = body.find("scale_calibrate:") {
-                    let after_cmd = &body[start + 16..];
-                    // Find end of weight value (quote or whitespace)
-                    let end = after_cmd.find(|c: char| c == '"' || c.is_whitespace()).unwrap_or(after_cmd.len());
-                    let weight_str = &after_cmd[..end];
-                    log::info!("Parsing weight value: '{}'", weight_str);
-                    if let Ok(known_weight) = weight_str.parse::<f32>() {
-                        log::info!("Received scale_calibrate command from backend: {}g", known_weight);
-                        let result = crate::scale_manager::scale_calibrate(known_weight);
-                        log::info!("Scale calibrate result: {} (0=success, -1=error)", result);
-                    } else {
-                        log::warn!("Failed to parse weight value: '{}'", weight_str);
-                    }
-                } else {
-                    log::warn!("Could not find scale_calibrate: in body");
+    let status = response.status();
+    note_auth_status(status);
+    if status != 200 {
+        return;
+    }
+
+    // Read full response (may contain several commands)
+    let mut buf = vec![0u8; MAX_HEARTBEAT_RESPONSE];
+    let mut total = 0;
+    loop {
+        match response.read(&mut buf[total..]) {
+            Ok(0) => break,
+            Ok(n) => {
+                total += n;
+                if total >= buf.len() {
+                    warn!("Heartbeat response truncated at {} bytes", total);
+                    break;
                 }
             }
-            // Check for scale reset command
-            else if body.contains("\"command\":\"scale_reset\"") || body.contains("\"command\": \"scale_reset\"") {
-                log::info!("Received scale_reset command from backend");
-                let result = crate::scale_manager::scale_reset_calibration();
-                log::info!("Scale reset result: {}", result);
-            }
+            Err(_) => break,
+        }
+    }
+
+    // Backend is reachable again - deliver results that failed earlier
+    crate::device_commands::flush_pending_results();
+
+    if total > 0 {
+        let body = String::from_utf8_lossy(&buf[..total]);
+        crate::device_commands::handle_heartbeat_response(&body);
+    }
+}
+
+/// Post a command result (JSON body) to the backend
+/// Returns true if the backend accepted it
+pub fn post_command_result(body: &str) -> bool {
+    let Some(base_url) = get_server_url() else {
+        return false;
+    };
+    let url = format!("{}/api/display/command-result", base_url);
+
+    let config = tls_pin::http_config(std::time::Duration::from_millis(HTTP_TIMEOUT_MS));
+
+    let connection = match EspHttpConnection::new(&config) {
+        Ok(c) => c,
+        Err(e) => {
+            warn!("Failed to create HTTP connection: {:?}", e);
+            return false;
         }
+    };
+
+    let mut client = HttpClient::wrap(connection);
+
+    let api_key = device_auth::api_key();
+    let content_length = body.len().to_string();
+    let headers = device_auth::with_api_key(&[
+        ("Content-Type", "application/json"),
+        ("Content-Length", content_length.as_str()),
+    ], &api_key);
+
+    let mut request = match client.request(embedded_svc::http::Method::Post, &url, &headers) {
+        Ok(r) => r,
+        Err(e) => {
+            warn!("Failed to create POST request: {:?}", e);
+            return false;
+        }
+    };
+
+    if let Err(e) = request.write(body.as_bytes()) {
+        warn!("Failed to write request body: {:?}", e);
+        return false;
+    }
+
+    if let Err(e) = request.flush() {
+        warn!("Failed to flush request: {:?}", e);
+        return false;
+    }
+
+    match request.submit() {
+        Ok(response) => {
+            note_auth_status(response.status());
+            response.status() == 200
+        }
+        Err(e) => {
+            warn!("Failed to submit command result: {:?}", e);
+            false
+        }
+    }
+}
+
+/// Get Bambu Lab tag details as URL query params (empty for other tags)
+fn get_tag_detail_params() -> String {
+    let tray_uid = crate::nfc_manager::get_tag_tray_uid();
+    let diameter = crate::nfc_manager::get_tag_filament_diameter();
+    if tray_uid.is_empty() && diameter <= 0.0 {
+        return String::new();
     }
+
+    let (drying_temp, drying_time) = crate::nfc_manager::get_tag_drying();
+    let (hotend_min, hotend_max) = crate::nfc_manager::get_tag_hotend_temps();
+    format!(
+        "&tag_diameter={:.2}&tag_drying_temp={}&tag_drying_time={}&tag_bed_temp={}&tag_hotend_min={}&tag_hotend_max={}&tag_spool_width={:.2}&tag_length={}&tag_production_date={}&tag_tray_uid={}",
+        diameter,
+        drying_temp,
+        drying_time,
+        crate::nfc_manager::get_tag_bed_temp(),
+        hotend_min,
+        hotend_max,
+        crate::nfc_manager::get_tag_spool_width(),
+        crate::nfc_manager::get_tag_filament_length(),
+        crate::nfc_manager::get_tag_production_date().replace(' ', "%20").replace('#', "%23"),
+        tray_uid
+    )
 }
 
 /// Send device state to backend (weight, tag, WiFi) and receive decoded tag data
@@ -461,21 +822,23 @@ pub fn send_device_state(tag_uid_hex: Option<&str>, weight: f32, stable: bool) -
 
     // Build URL with query params, including decoded tag data if available
     let url = if let Some(tag_id) = tag_uid_hex {
+        // Both tags of a Bambu spool report the same spool_id (its tray UID)
+        let spool_id = crate::spool_identity::spool_id(tag_id);
         // Get decoded tag data from NFC manager
-        let vendor = crate::nfc_bridge_manager::get_tag_vendor();
-        let material = crate::nfc_bridge_manager::get_tag_material();
-        let subtype = crate::nfc_bridge_manager::get_tag_subtype();
-        let color = crate::nfc_bridge_manager::get_tag_color_name();
-        let color_rgba = crate::nfc_bridge_manager::get_tag_color_rgba();
-        let spool_weight = crate::nfc_bridge_manager::get_tag_spool_weight();
-        let tag_type = crate::nfc_bridge_manager::get_tag_type();
+        let vendor = crate::nfc_manager::get_tag_vendor();
+        let material = crate::nfc_manager::get_tag_material();
+        let subtype = crate::nfc_manager::get_tag_subtype();
+        let color = crate::nfc_manager::get_tag_color_name();
+        let color_rgba = crate::nfc_manager::get_tag_color_rgba();
+        let spool_weight = crate::nfc_manager::get_tag_spool_weight();
+        let tag_type = crate::nfc_manager::get_tag_type();
 
         if !vendor.is_empty() {
             // Include decoded tag data (simple URL encoding - replace spaces with %20)
             let encode = |s: &str| s.replace(' ', "%20").replace('#', "%23");
             format!(
-                "{}/api/display/state?weight={:.1}&stable={}&tag_id={}&tag_vendor={}&tag_material={}&tag_subtype={}&tag_color={}&tag_color_rgba={}&tag_weight={}&tag_type={}{}",
-                base_url, weight, stable, tag_id,
+                "{}/api/display/state?weight={:.1}&stable={}&tag_id={}&spool_id={}&tag_vendor={}&tag_material={}&tag_subtype={}&tag_color={}&tag_color_rgba={}&tag_weight={}&tag_type={}{}{}",
+                base_url, weight, stable, tag_id, spool_id,
                 encode(&vendor),
                 encode(&material),
                 encode(&subtype),
@@ -483,13 +846,14 @@ pub fn send_device_state(tag_uid_hex: Option<&str>, weight: f32, stable: bool) -
                 color_rgba,
                 spool_weight,
                 encode(&tag_type),
+                get_tag_detail_params(),
                 wifi_params
             )
         } else {
             // Just send tag_id without decoded data
             format!(
-                "{}/api/display/state?weight={:.1}&stable={}&tag_id={}{}",
-                base_url, weight, stable, tag_id, wifi_params
+                "{}/api/display/state?weight={:.1}&stable={}&tag_id={}&spool_id={}{}",
+                base_url, weight, stable, tag_id, spool_id, wifi_params
             )
         }
     } else {
@@ -499,10 +863,7 @@ pub fn send_device_state(tag_uid_hex: Option<&str>, weight: f32, stable: bool) -
         )
     };
 
-    let config = HttpConfig {
-        timeout: Some(std::time::Duration::from_millis(3000)),
-        ..Default::default()
-    };
+    let config = tls_pin::http_config(std::time::Duration::from_millis(3000));
 
     let connection = match EspHttpConnection::new(&config) {
         Ok(c) => c,
@@ -512,7 +873,9 @@ pub fn send_device_state(tag_uid_hex: Option<&str>, weight: f32, stable: bool) -
     let mut client = HttpClient::wrap(connection);
 
     // POST request
-    let request = match client.post(&url, &[]) {
+    let api_key = device_auth::api_key();
+    let headers = device_auth::with_api_key(&[], &api_key);
+    let request = match client.post(&url, &headers) {
         Ok(r) => r,
         Err(_) => return false,
     };
@@ -539,10 +902,7 @@ pub fn send_device_state(tag_uid_hex: Option<&str>, weight: f32, stable: bool) -
 fn fetch_decoded_tag_data(base_url: &str) {
     let url = format!("{}/api/display/status", base_url);
 
-    let config = HttpConfig {
-        timeout: Some(std::time::Duration::from_millis(3000)),
-        ..Default::default()
-    };
+    let config = tls_pin::http_config(std::time::Duration::from_millis(3000));
 
     let connection = match EspHttpConnection::new(&config) {
         Ok(c) => c,
@@ -551,7 +911,9 @@ fn fetch_decoded_tag_data(base_url: &str) {
 
     let mut client = HttpClient::wrap(connection);
 
-    let request = match client.get(&url) {
+    let api_key = device_auth::api_key();
+    let headers = device_auth::with_api_key(&[], &api_key);
+    let request = match client.request(embedded_svc::http::Method::Get, &url, &headers) {
         Ok(r) => r,
         Err(_) => return,
     };
@@ -595,7 +957,7 @@ fn fetch_decoded_tag_data(base_url: &str) {
 
     if let Ok(status) = serde_json::from_slice::<DisplayStatus>(&body) {
         if let Some(tag_data) = status.tag_data {
-            crate::nfc_bridge_manager::set_decoded_tag_data(
+            crate::nfc_manager::set_decoded_tag_data(
                 tag_data.vendor.as_deref().unwrap_or(""),
                 tag_data.material.as_deref().unwrap_or(""),
                 tag_data.subtype.as_deref().unwrap_or(""),
@@ -616,6 +978,9 @@ pub fn fetch_and_set_time(base_url: &str) {
     match fetch_time(&time_url) {
         Ok(time) => {
             crate::time_manager::set_backend_time(time.hour, time.minute);
+            if let Some(ts) = time.timestamp {
+                crate::time_manager::set_backend_timestamp(ts);
+            }
         }
         Err(_) => {
             // Silently ignore time fetch errors
@@ -637,10 +1002,7 @@ pub fn sync_time() {
 /// Fetch printers from backend API
 fn fetch_printers(url: &str) -> Result<Vec<ApiPrinter>, String> {
     // Create HTTP client
-    let config = HttpConfig {
-        timeout: Some(std::time::Duration::from_millis(HTTP_TIMEOUT_MS)),
-        ..Default::default()
-    };
+    let config = tls_pin::http_config(std::time::Duration::from_millis(HTTP_TIMEOUT_MS));
 
     let connection = EspHttpConnection::new(&config)
         .map_err(|e| format!("HTTP connection failed: {:?}", e))?;
@@ -648,7 +1010,9 @@ fn fetch_printers(url: &str) -> Result<Vec<ApiPrinter>, String> {
     let mut client = HttpClient::wrap(connection);
 
     // Make GET request
-    let request = client.get(url)
+    let api_key = device_auth::api_key();
+    let headers = device_auth::with_api_key(&[], &api_key);
+    let request = client.request(embedded_svc::http::Method::Get, url, &headers)
         .map_err(|e| format!("GET request failed: {:?}", e))?;
 
     let mut response = request.submit()
@@ -680,17 +1044,16 @@ fn fetch_printers(url: &str) -> Result<Vec<ApiPrinter>, String> {
 
 /// Fetch time from backend API
 fn fetch_time(url: &str) -> Result<ApiTime, String> {
-    let config = HttpConfig {
-        timeout: Some(std::time::Duration::from_millis(2000)), // Short timeout for time
-        ..Default::default()
-    };
+    let config = tls_pin::http_config(std::time::Duration::from_millis(2000)); // Short timeout for time
 
     let connection = EspHttpConnection::new(&config)
         .map_err(|e| format!("HTTP connection failed: {:?}", e))?;
 
     let mut client = HttpClient::wrap(connection);
 
-    let request = client.get(url)
+    let api_key = device_auth::api_key();
+    let headers = device_auth::with_api_key(&[], &api_key);
+    let request = client.request(embedded_svc::http::Method::Get, url, &headers)
         .map_err(|e| format!("GET request failed: {:?}", e))?;
 
     let mut response = request.submit()
@@ -724,139 +1087,181 @@ fn parse_rgba_color(color: &str) -> u32 {
 }
 
 fn update_printer_cache(manager: &mut BackendManager, printers: &[ApiPrinter]) {
-    manager.printer_count = printers.len().min(MAX_PRINTERS);
-
     info!("Updating printer cache with {} printers", printers.len());
 
-    for (i, printer) in printers.iter().take(MAX_PRINTERS).enumerate() {
-        let cached = &mut manager.printers[i];
-
-        info!("Printer {}: serial={}, name={:?}, connected={}",
-              i, printer.serial, printer.name, printer.connected);
-
-        // Copy name
-        cached.name = [0; 32];
-        if let Some(ref name) = printer.name {
-            let bytes = name.as_bytes();
-            let len = bytes.len().min(31);
-            cached.name[..len].copy_from_slice(&bytes[..len]);
-        }
-
-        // Copy serial
-        cached.serial = [0; 20];
-        let serial_bytes = printer.serial.as_bytes();
-        let serial_len = serial_bytes.len().min(19);
-        cached.serial[..serial_len].copy_from_slice(&serial_bytes[..serial_len]);
-
-        // Copy IP address
-        cached.ip_address = [0; 20];
-        if let Some(ref ip) = printer.ip_address {
-            let bytes = ip.as_bytes();
-            let len = bytes.len().min(19);
-            cached.ip_address[..len].copy_from_slice(&bytes[..len]);
-        }
-
-        // Copy access code
-        cached.access_code = [0; 16];
-        if let Some(ref code) = printer.access_code {
-            let bytes = code.as_bytes();
-            let len = bytes.len().min(15);
-            cached.access_code[..len].copy_from_slice(&bytes[..len]);
-        }
-
-        // Copy state
-        cached.connected = printer.connected;
-        cached.gcode_state = [0; 16];
-        cached.print_progress = 0;
-        cached.subtask_name = [0; 64];
-        cached.remaining_time_min = 0;
-
-        if let Some(ref gcode) = printer.gcode_state {
-            let bytes = gcode.as_bytes();
-            let len = bytes.len().min(15);
-            cached.gcode_state[..len].copy_from_slice(&bytes[..len]);
-        }
-        if let Some(progress) = printer.print_progress {
-            cached.print_progress = progress;
-        }
-        if let Some(ref subtask) = printer.subtask_name {
-            let bytes = subtask.as_bytes();
-            let len = bytes.len().min(63);
-            cached.subtask_name[..len].copy_from_slice(&bytes[..len]);
-        }
-        if let Some(time) = printer.mc_remaining_time {
-            cached.remaining_time_min = time;
-        }
-
-        // Copy stage info
-        cached.stg_cur = printer.stg_cur.unwrap_or(-1);
-        cached.stg_cur_name = [0; 48];
-        if let Some(ref stg_name) = printer.stg_cur_name {
-            let bytes = stg_name.as_bytes();
-            let len = bytes.len().min(47);
-            cached.stg_cur_name[..len].copy_from_slice(&bytes[..len]);
-        }
-
-        // Copy active tray info
-        cached.tray_now = printer.tray_now.unwrap_or(-1);
-        cached.tray_now_left = printer.tray_now_left.unwrap_or(-1);
-        cached.tray_now_right = printer.tray_now_right.unwrap_or(-1);
-        cached.active_extruder = printer.active_extruder.unwrap_or(-1);
-
-        // Copy AMS units
-        cached.ams_unit_count = printer.ams_units.len().min(MAX_AMS_UNITS) as u8;
-        cached.ams_units = [EMPTY_AMS_UNIT; MAX_AMS_UNITS];
-
-        info!("Printer {} has {} AMS units, tray_now={}, active_extruder={}",
-              i, printer.ams_units.len(), cached.tray_now, cached.active_extruder);
-
-        for (j, ams) in printer.ams_units.iter().take(MAX_AMS_UNITS).enumerate() {
-            let cached_ams = &mut cached.ams_units[j];
-            cached_ams.id = ams.id;
-            cached_ams.humidity = ams.humidity.unwrap_or(-1);
-            cached_ams.temperature = ams.temperature.map(|t| (t * 10.0) as i16).unwrap_or(-1);
-            cached_ams.extruder = ams.extruder.map(|e| e as i8).unwrap_or(-1);
-            cached_ams.tray_count = ams.trays.len().min(4) as u8;
-
-            info!("  AMS[{}] id={} extruder={:?} trays={}", j, ams.id, ams.extruder, ams.trays.len());
-
-            for (k, tray) in ams.trays.iter().take(4).enumerate() {
-                let cached_tray = &mut cached_ams.trays[k];
-
-                // Copy tray type
-                cached_tray.tray_type = [0; 16];
-                if let Some(ref tray_type) = tray.tray_type {
-                    let bytes = tray_type.as_bytes();
-                    let len = bytes.len().min(15);
-                    cached_tray.tray_type[..len].copy_from_slice(&bytes[..len]);
-                }
+    manager.printers = printers
+        .iter()
+        .enumerate()
+        .map(|(i, printer)| cache_printer(i, printer))
+        .collect();
+}
 
-                // Parse color
-                cached_tray.tray_color = tray.tray_color
-                    .as_ref()
-                    .map(|c| parse_rgba_color(c))
-                    .unwrap_or(0);
+/// Apply a single changed printer (from the event stream) to the cache
+/// Returns the cache index of the printer.
+fn apply_printer_delta(manager: &mut BackendManager, printer: &ApiPrinter) -> usize {
+    let existing = manager.printers.iter().position(|p| p.serial == printer.serial);
 
-                // Remaining percentage (clamp negative to 0)
-                cached_tray.remain = tray.remain.unwrap_or(0).max(0) as u8;
-            }
+    match existing {
+        Some(i) => {
+            manager.printers[i] = cache_printer(i, printer);
+            i
+        }
+        None => {
+            let i = manager.printers.len();
+            manager.printers.push(cache_printer(i, printer));
+            i
         }
     }
-
 }
 
-/// Check if cover URL changed and return the new URL if so
-fn check_cover_url_changed(printers: &[ApiPrinter], base_url: &str) -> Option<String> {
-    if let Some(printer) = printers.first() {
-        if let Some(ref cover_url) = printer.cover_url {
-            let mut last_url = LAST_COVER_URL.lock().unwrap();
-            if *last_url != *cover_url {
-                // Cover URL changed
-                *last_url = cover_url.clone();
-                return Some(format!("{}{}", base_url, cover_url));
+/// Convert one API printer into its cache entry
+fn cache_printer(i: usize, printer: &ApiPrinter) -> CachedPrinter {
+    info!("Printer {}: serial={}, name={:?}, connected={}",
+          i, printer.serial, printer.name, printer.connected);
+
+    let model = printer.model.as_deref();
+    let mut ams_units: Vec<CachedAmsUnit> = printer.ams_units
+        .iter()
+        .map(|ams| CachedAmsUnit {
+            id: ams.id,
+            kind: AmsKind::of_unit(ams.id, model),
+            humidity: ams.humidity.unwrap_or(-1),
+            temperature: ams.temperature.map(|t| (t * 10.0) as i16).unwrap_or(-1),
+            extruder: ams.extruder.map(|e| e as i8).unwrap_or(-1),
+            trays: ams.trays.iter().map(cache_tray).collect(),
+        })
+        .collect();
+
+    // External spool holder, reported separately by the printer
+    if let Some(ref tray) = printer.vt_tray {
+        ams_units.push(CachedAmsUnit {
+            id: 255,
+            kind: AmsKind::External,
+            humidity: -1,
+            temperature: -1,
+            extruder: -1,
+            trays: vec![cache_tray(tray)],
+        });
+    }
+
+    let cached = CachedPrinter {
+        name: printer.name.clone().unwrap_or_default(),
+        serial: printer.serial.clone(),
+        ip_address: printer.ip_address.clone().unwrap_or_default(),
+        access_code: printer.access_code.clone().unwrap_or_default(),
+        connected: printer.connected,
+        gcode_state: printer.gcode_state.clone().unwrap_or_default(),
+        print_progress: printer.print_progress.unwrap_or(0),
+        subtask_name: printer.subtask_name.clone().unwrap_or_default(),
+        remaining_time_min: printer.mc_remaining_time.unwrap_or(0),
+        stg_cur: printer.stg_cur.unwrap_or(-1),
+        stg_cur_name: printer.stg_cur_name.clone().unwrap_or_default(),
+        ams_units,
+        tray_now: printer.tray_now.unwrap_or(-1),
+        tray_now_left: printer.tray_now_left.unwrap_or(-1),
+        tray_now_right: printer.tray_now_right.unwrap_or(-1),
+        active_extruder: printer.active_extruder.unwrap_or(-1),
+    };
+
+    info!("Printer {} has {} AMS units, tray_now={}, active_extruder={}",
+          i, cached.ams().count(), cached.tray_now, cached.active_extruder);
+    for unit in &cached.ams_units {
+        info!("  {:?} id={} extruder={} trays={}", unit.kind, unit.id, unit.extruder, unit.trays.len());
+    }
+
+    cached
+}
+
+fn cache_tray(tray: &ApiAmsTray) -> CachedAmsTray {
+    CachedAmsTray {
+        tray_type: tray.tray_type.clone().unwrap_or_default(),
+        tray_color: tray.tray_color
+            .as_ref()
+            .map(|c| parse_rgba_color(c))
+            .unwrap_or(0),
+        // Remaining percentage (clamp negative to 0)
+        remain: tray.remain.unwrap_or(0).clamp(0, 100) as u8,
+    }
+}
+
+/// Apply an event from the backend event stream
+/// "printers" carries the full list, "printer" a single changed printer.
+pub fn handle_stream_event(event: &str, data: &str) {
+    let base_url = match get_server_url() {
+        Some(url) => url,
+        None => return,
+    };
+
+    let cover_url_to_fetch = match event {
+        "printers" => match serde_json::from_str::<Vec<ApiPrinter>>(data) {
+            Ok(printers) => {
+                let cover_url = check_cover_url_changed(&printers, &base_url);
+                let mut manager = BACKEND_MANAGER.lock().unwrap();
+                update_printer_cache(&mut manager, &printers);
+                cover_url
             }
-        } else {
-            // No cover URL, invalidate cover
+            Err(e) => {
+                warn!("Invalid printers event: {:?}", e);
+                None
+            }
+        },
+        "printer" => match serde_json::from_str::<ApiPrinter>(data) {
+            Ok(printer) => {
+                let mut manager = BACKEND_MANAGER.lock().unwrap();
+                let index = apply_printer_delta(&mut manager, &printer);
+                drop(manager);
+                // Cover image follows the first printer
+                if index == 0 {
+                    check_cover_url_changed(std::slice::from_ref(&printer), &base_url)
+                } else {
+                    None
+                }
+            }
+            Err(e) => {
+                warn!("Invalid printer event: {:?}", e);
+                None
+            }
+        },
+        _ => None,
+    };
+
+    if let Some(url) = cover_url_to_fetch {
+        fetch_cover_image(&url);
+    }
+
+    feed_scale_temperature();
+}
+
+/// Pass the temperature of the first AMS reporting one (on a connected
+/// printer) to the scale, used for drift compensation when selected
+fn feed_scale_temperature() {
+    let manager = BACKEND_MANAGER.lock().unwrap();
+    let temperature = manager
+        .printers
+        .iter()
+        .filter(|p| p.connected)
+        .flat_map(|p| p.ams())
+        .find(|ams| ams.temperature >= 0)
+        .map(|ams| ams.temperature as f32 / 10.0);
+    drop(manager);
+
+    if let Some(temperature) = temperature {
+        crate::scale_manager::set_ams_temperature(temperature);
+    }
+}
+
+/// Check if cover URL changed and return the new URL if so
+fn check_cover_url_changed(printers: &[ApiPrinter], base_url: &str) -> Option<String> {
+    if let Some(printer) = printers.first() {
+        if let Some(ref cover_url) = printer.cover_url {
+            let mut last_url = LAST_COVER_URL.lock().unwrap();
+            if *last_url != *cover_url {
+                // Cover URL changed
+                *last_url = cover_url.clone();
+                return Some(format!("{}{}", base_url, cover_url));
+            }
+        } else {
+            // No cover URL, invalidate cover
             COVER_VALID.store(false, std::sync::atomic::Ordering::Relaxed);
             let mut last_url = LAST_COVER_URL.lock().unwrap();
             last_url.clear();
@@ -869,10 +1274,7 @@ fn check_cover_url_changed(printers: &[ApiPrinter], base_url: &str) -> Option<St
 fn fetch_cover_image(url: &str) {
     info!("Fetching cover image from: {}", url);
 
-    let config = HttpConfig {
-        timeout: Some(std::time::Duration::from_millis(10000)), // 10s timeout for image
-        ..Default::default()
-    };
+    let config = tls_pin::http_config(std::time::Duration::from_millis(10000)); // 10s timeout for image
 
     let connection = match EspHttpConnection::new(&config) {
         Ok(c) => c,
@@ -885,7 +1287,9 @@ fn fetch_cover_image(url: &str) {
 
     let mut client = HttpClient::wrap(connection);
 
-    let request = match client.get(url) {
+    let api_key = device_auth::api_key();
+    let headers = device_auth::with_api_key(&[], &api_key);
+    let request = match client.request(embedded_svc::http::Method::Get, url, &headers) {
         Ok(r) => r,
         Err(e) => {
             warn!("Cover fetch request failed: {:?}", e);
@@ -945,13 +1349,14 @@ fn fetch_cover_image(url: &str) {
 /// Backend status for C interface
 #[repr(C)]
 pub struct BackendStatus {
-    /// 0=Disconnected, 1=Discovering, 2=Connected, 3=Error
+    /// 0=Disconnected, 1=Discovering, 2=Connected, 3=Error, 4=Unauthorized,
+    /// 5=CertificateMismatch
     pub state: c_int,
-    /// Server IP address (valid when state=2)
+    /// Server IP address (valid when state=2, 4 or 5)
     pub server_ip: [u8; 4],
-    /// Server port (valid when state=2)
+    /// Server port (valid when state=2, 4 or 5)
     pub server_port: u16,
-    /// Number of printers cached
+    /// Number of printers cached (saturates at 255, see backend_get_printer_count)
     pub printer_count: u8,
 }
 
@@ -1003,77 +1408,91 @@ pub extern "C" fn backend_get_status(status: *mut BackendStatus) {
                 (*status).server_ip = [0; 4];
                 (*status).server_port = 0;
             }
+            BackendState::Unauthorized { ip, port, .. } => {
+                (*status).state = 4;
+                (*status).server_ip = *ip;
+                (*status).server_port = *port;
+            }
+            BackendState::CertificateMismatch { ip, port } => {
+                (*status).state = 5;
+                (*status).server_ip = *ip;
+                (*status).server_port = *port;
+            }
         }
-        (*status).printer_count = manager.printer_count as u8;
+        (*status).printer_count = manager.printers.len().min(u8::MAX as usize) as u8;
     }
 }
 
 /// Get printer info by index
+/// Strings longer than the fixed fields are cut at a character boundary;
+/// backend_get_printer_text returns them in full.
 /// Returns 0 on success, -1 if index out of range
 #[no_mangle]
 pub extern "C" fn backend_get_printer(index: c_int, info: *mut PrinterInfo) -> c_int {
-    if info.is_null() || index < 0 {
+    if info.is_null() {
         return -1;
     }
 
     let manager = BACKEND_MANAGER.lock().unwrap();
-    let idx = index as usize;
-
-    if idx >= manager.printer_count {
+    let Some(cached) = manager.printer(index) else {
         return -1;
-    }
-
-    let cached = &manager.printers[idx];
-
-    unsafe {
-        // Copy name (already null-terminated due to zero init)
-        for (i, &b) in cached.name.iter().enumerate() {
-            (*info).name[i] = b as c_char;
-        }
-
-        // Copy serial
-        for (i, &b) in cached.serial.iter().enumerate() {
-            (*info).serial[i] = b as c_char;
-        }
-
-        // Copy IP address
-        for (i, &b) in cached.ip_address.iter().enumerate() {
-            (*info).ip_address[i] = b as c_char;
-        }
-
-        // Copy access code
-        for (i, &b) in cached.access_code.iter().enumerate() {
-            (*info).access_code[i] = b as c_char;
-        }
+    };
 
-        // Copy state
-        for (i, &b) in cached.gcode_state.iter().enumerate() {
-            (*info).gcode_state[i] = b as c_char;
-        }
+    let out = unsafe { &mut *info };
+    copy_to_c_buf_signed(&cached.name, &mut out.name);
+    copy_to_c_buf_signed(&cached.serial, &mut out.serial);
+    copy_to_c_buf_signed(&cached.ip_address, &mut out.ip_address);
+    copy_to_c_buf_signed(&cached.access_code, &mut out.access_code);
+    copy_to_c_buf_signed(&cached.gcode_state, &mut out.gcode_state);
+    copy_to_c_buf_signed(&cached.subtask_name, &mut out.subtask_name);
+    copy_to_c_buf_signed(&cached.stg_cur_name, &mut out.stg_cur_name);
 
-        // Copy subtask name
-        for (i, &b) in cached.subtask_name.iter().enumerate() {
-            (*info).subtask_name[i] = b as c_char;
-        }
+    out.connected = cached.connected;
+    out.print_progress = cached.print_progress;
+    out.remaining_time_min = cached.remaining_time_min;
+    out.stg_cur = cached.stg_cur;
+    out._pad = [0; 3];
 
-        (*info).connected = cached.connected;
-        (*info).print_progress = cached.print_progress;
-        (*info).remaining_time_min = cached.remaining_time_min;
+    0
+}
 
-        // Copy stage info
-        (*info).stg_cur = cached.stg_cur;
-        for (i, &b) in cached.stg_cur_name.iter().enumerate() {
-            (*info).stg_cur_name[i] = b as c_char;
-        }
+/// Printer text fields for backend_get_printer_text
+const PRINTER_TEXT_NAME: c_int = 0;
+const PRINTER_TEXT_SERIAL: c_int = 1;
+const PRINTER_TEXT_GCODE_STATE: c_int = 2;
+const PRINTER_TEXT_SUBTASK_NAME: c_int = 3;
+const PRINTER_TEXT_STAGE_NAME: c_int = 4;
 
-        // Initialize padding
-        (*info)._pad = [0; 3];
+/// Get a printer text field without the PrinterInfo length limits
+/// Copies as much as fits (cut at a character boundary). Returns the full
+/// length in bytes like snprintf, or -1 on error.
+#[no_mangle]
+pub extern "C" fn backend_get_printer_text(index: c_int, field: c_int, buf: *mut c_char, buf_len: c_int) -> c_int {
+    if buf.is_null() || buf_len <= 0 {
+        return -1;
     }
 
-    0
+    let manager = BACKEND_MANAGER.lock().unwrap();
+    let Some(cached) = manager.printer(index) else {
+        return -1;
+    };
+    let text = match field {
+        PRINTER_TEXT_NAME => &cached.name,
+        PRINTER_TEXT_SERIAL => &cached.serial,
+        PRINTER_TEXT_GCODE_STATE => &cached.gcode_state,
+        PRINTER_TEXT_SUBTASK_NAME => &cached.subtask_name,
+        PRINTER_TEXT_STAGE_NAME => &cached.stg_cur_name,
+        _ => return -1,
+    };
+
+    let dest = unsafe { std::slice::from_raw_parts_mut(buf, buf_len as usize) };
+    copy_to_c_buf_signed(text, dest);
+    text.len() as c_int
 }
 
-/// Set backend server URL from C
+/// Set backend server URL from C (settings dialog) and save it to NVS
+/// Another server drops the certificate pinned for the old one. An empty
+/// string clears the saved URL and restarts discovery (keeping the pin).
 /// Returns 0 on success, -1 on error
 #[no_mangle]
 pub extern "C" fn backend_set_url(url: *const c_char) -> c_int {
@@ -1088,22 +1507,65 @@ pub extern "C" fn backend_set_url(url: *const c_char) -> c_int {
         }
     };
 
-    set_server_url(url_str);
+    if !configure_server_url(url_str) {
+        return -1;
+    }
+    // A server entered on the display itself replaces the pinned one
+    let url = url_str.trim().trim_end_matches('/');
+    if !url.is_empty() {
+        tls_pin::clear_for_other_server(url);
+    }
     0
 }
 
-/// Trigger mDNS discovery for backend server
-/// Returns 0 if discovery started, -1 on error
+/// Get the current backend server URL (configured or discovered)
+/// Returns string length, 0 if no server is set, -1 on error
 #[no_mangle]
-pub extern "C" fn backend_discover_server() -> c_int {
-    // TODO: Implement mDNS browsing for _spoolbuddy-server._tcp
-    // For now, this is a placeholder
-    info!("Backend server discovery requested (not yet implemented)");
+pub extern "C" fn backend_get_server_url(buf: *mut c_char, buf_len: c_int) -> c_int {
+    if buf.is_null() || buf_len <= 0 {
+        return -1;
+    }
+    let url = get_server_url().unwrap_or_default();
+    let bytes = url.as_bytes();
+    let copy_len = std::cmp::min(bytes.len(), (buf_len - 1) as usize);
+    unsafe {
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), buf as *mut u8, copy_len);
+        *buf.add(copy_len) = 0; // Null terminate
+    }
+    copy_len as c_int
+}
 
-    let mut manager = BACKEND_MANAGER.lock().unwrap();
-    manager.state = BackendState::Discovering;
+/// Get the pairing code to show while the device waits for an API key
+/// Returns string length, 0 if not pairing, -1 on error
+#[no_mangle]
+pub extern "C" fn backend_get_pairing_code(buf: *mut c_char, buf_len: c_int) -> c_int {
+    if buf.is_null() || buf_len <= 0 {
+        return -1;
+    }
+    let code = device_auth::pairing_code().unwrap_or_default();
+    let bytes = code.as_bytes();
+    let copy_len = std::cmp::min(bytes.len(), (buf_len - 1) as usize);
+    unsafe {
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), buf as *mut u8, copy_len);
+        *buf.add(copy_len) = 0; // Null terminate
+    }
+    copy_len as c_int
+}
 
-    0
+/// Trigger mDNS discovery for backend server (non-blocking, runs on the
+/// backend worker)
+/// Returns 0 if discovery was queued, -1 on error or if already running
+#[no_mangle]
+pub extern "C" fn backend_discover_server() -> c_int {
+    if DISCOVERY_RUNNING.load(std::sync::atomic::Ordering::Relaxed) {
+        return -1;
+    }
+
+    if crate::backend_worker::submit(crate::backend_worker::Job::Discover) {
+        0
+    } else {
+        -1
+    }
 }
 
 /// Check if backend is connected
@@ -1121,7 +1583,7 @@ pub extern "C" fn backend_is_connected() -> c_int {
 #[no_mangle]
 pub extern "C" fn backend_get_printer_count() -> c_int {
     let manager = BACKEND_MANAGER.lock().unwrap();
-    manager.printer_count as c_int
+    manager.printers.len() as c_int
 }
 
 /// Check if cover image is available
@@ -1181,18 +1643,43 @@ pub struct AmsUnitCInfo {
     pub humidity: c_int,          // -1 if not available
     pub temperature: i16,         // Celsius * 10, -1 if not available
     pub extruder: i8,             // -1 if not available, 0=right, 1=left
-    pub tray_count: u8,
+    pub tray_count: u8,           // Real tray count (trays[] holds the first 4)
+    pub kind: u8,                 // 0=AMS, 1=AMS Lite, 2=AMS HT, 3=External
     pub trays: [AmsTrayCInfo; 4],
 }
 
-/// Get number of AMS units for a printer
+fn fill_ams_unit_info(out: &mut AmsUnitCInfo, ams: &CachedAmsUnit) {
+    out.id = ams.id;
+    out.humidity = ams.humidity;
+    out.temperature = ams.temperature;
+    out.extruder = ams.extruder;
+    out.tray_count = ams.trays.len().min(u8::MAX as usize) as u8;
+    out.kind = ams.kind as u8;
+
+    for (i, out_tray) in out.trays.iter_mut().enumerate() {
+        match ams.trays.get(i) {
+            Some(tray) => {
+                copy_to_c_buf_signed(&tray.tray_type, &mut out_tray.tray_type);
+                out_tray.tray_color = tray.tray_color;
+                out_tray.remain = tray.remain;
+            }
+            None => {
+                out_tray.tray_type = [0; 16];
+                out_tray.tray_color = 0;
+                out_tray.remain = 0;
+            }
+        }
+    }
+}
+
+/// Get number of AMS units for a printer (without external spool holders)
 #[no_mangle]
 pub extern "C" fn backend_get_ams_count(printer_index: c_int) -> c_int {
     let manager = BACKEND_MANAGER.lock().unwrap();
-    if printer_index < 0 || printer_index as usize >= manager.printer_count {
+    let Some(printer) = manager.printer(printer_index) else {
         return 0;
-    }
-    let count = manager.printers[printer_index as usize].ams_unit_count as c_int;
+    };
+    let count = printer.ams().count() as c_int;
     info!("backend_get_ams_count({}) = {}", printer_index, count);
     count
 }
@@ -1210,35 +1697,45 @@ pub extern "C" fn backend_get_ams_unit(
     }
 
     let manager = BACKEND_MANAGER.lock().unwrap();
-    if printer_index < 0 || printer_index as usize >= manager.printer_count {
+    let Some(printer) = manager.printer(printer_index) else {
         return -1;
-    }
-
-    let printer = &manager.printers[printer_index as usize];
-    if ams_index < 0 || ams_index as usize >= printer.ams_unit_count as usize {
+    };
+    let Some(ams) = usize::try_from(ams_index).ok().and_then(|i| printer.ams().nth(i)) else {
         return -1;
-    }
+    };
+
+    fill_ams_unit_info(unsafe { &mut *info }, ams);
+    0
+}
 
-    let ams = &printer.ams_units[ams_index as usize];
+/// Get number of external spool holders reported by a printer
+#[no_mangle]
+pub extern "C" fn backend_get_external_count(printer_index: c_int) -> c_int {
+    let manager = BACKEND_MANAGER.lock().unwrap();
+    manager.printer(printer_index).map_or(0, |p| p.external().count() as c_int)
+}
 
-    unsafe {
-        let out = &mut *info;
-        out.id = ams.id;
-        out.humidity = ams.humidity;
-        out.temperature = ams.temperature;
-        out.extruder = ams.extruder;
-        out.tray_count = ams.tray_count;
-
-        for (i, tray) in ams.trays.iter().enumerate() {
-            out.trays[i].tray_type = [0; 16];
-            for (j, &byte) in tray.tray_type.iter().enumerate() {
-                out.trays[i].tray_type[j] = byte as c_char;
-            }
-            out.trays[i].tray_color = tray.tray_color;
-            out.trays[i].remain = tray.remain;
-        }
+/// Get an external spool holder (kind 3, one tray)
+/// Returns 0 on success, -1 on error
+#[no_mangle]
+pub extern "C" fn backend_get_external_unit(
+    printer_index: c_int,
+    external_index: c_int,
+    info: *mut AmsUnitCInfo,
+) -> c_int {
+    if info.is_null() {
+        return -1;
     }
 
+    let manager = BACKEND_MANAGER.lock().unwrap();
+    let Some(printer) = manager.printer(printer_index) else {
+        return -1;
+    };
+    let Some(external) = usize::try_from(external_index).ok().and_then(|i| printer.external().nth(i)) else {
+        return -1;
+    };
+
+    fill_ams_unit_info(unsafe { &mut *info }, external);
     0
 }
 
@@ -1251,6 +1748,7 @@ pub struct AmsTrayInfo {
 }
 
 /// Get AMS tray info with color as hex string
+/// Any tray of the unit can be read, not only the four in AmsUnitCInfo.
 /// Returns 0 on success, -1 on error
 #[no_mangle]
 pub extern "C" fn backend_get_ams_tray(
@@ -1264,39 +1762,21 @@ pub extern "C" fn backend_get_ams_tray(
     }
 
     let manager = BACKEND_MANAGER.lock().unwrap();
-    if printer_index < 0 || printer_index as usize >= manager.printer_count {
+    let Some(printer) = manager.printer(printer_index) else {
         return -1;
-    }
-
-    let printer = &manager.printers[printer_index as usize];
-    if ams_index < 0 || ams_index as usize >= printer.ams_unit_count as usize {
+    };
+    let Some(ams) = usize::try_from(ams_index).ok().and_then(|i| printer.ams().nth(i)) else {
         return -1;
-    }
-
-    let ams = &printer.ams_units[ams_index as usize];
-    if tray_index < 0 || tray_index as usize >= ams.tray_count as usize {
+    };
+    let Some(tray) = usize::try_from(tray_index).ok().and_then(|i| ams.trays.get(i)) else {
         return -1;
-    }
-
-    let tray = &ams.trays[tray_index as usize];
-
-    unsafe {
-        let out = &mut *info;
-
-        // Copy tray_type
-        out.tray_type = [0; 16];
-        for (j, &byte) in tray.tray_type.iter().enumerate() {
-            out.tray_type[j] = byte as c_char;
-        }
-
-        // Convert packed RGBA to hex string
-        out.tray_color = [0; 16];
-        let color = tray.tray_color;
-        let hex_str = format!("{:08X}", color);
-        copy_to_c_buf_signed(&hex_str, &mut out.tray_color);
+    };
 
-        out.remain = tray.remain;
-    }
+    let out = unsafe { &mut *info };
+    copy_to_c_buf_signed(&tray.tray_type, &mut out.tray_type);
+    // Convert packed RGBA to hex string
+    copy_to_c_buf_signed(&format!("{:08X}", tray.tray_color), &mut out.tray_color);
+    out.remain = tray.remain;
 
     0
 }
@@ -1306,10 +1786,7 @@ pub extern "C" fn backend_get_ams_tray(
 #[no_mangle]
 pub extern "C" fn backend_get_tray_now(printer_index: c_int) -> c_int {
     let manager = BACKEND_MANAGER.lock().unwrap();
-    if printer_index < 0 || printer_index as usize >= manager.printer_count {
-        return -1;
-    }
-    manager.printers[printer_index as usize].tray_now
+    manager.printer(printer_index).map_or(-1, |p| p.tray_now)
 }
 
 /// Get active tray for left nozzle (dual-nozzle printer)
@@ -1317,10 +1794,7 @@ pub extern "C" fn backend_get_tray_now(printer_index: c_int) -> c_int {
 #[no_mangle]
 pub extern "C" fn backend_get_tray_now_left(printer_index: c_int) -> c_int {
     let manager = BACKEND_MANAGER.lock().unwrap();
-    if printer_index < 0 || printer_index as usize >= manager.printer_count {
-        return -1;
-    }
-    manager.printers[printer_index as usize].tray_now_left
+    manager.printer(printer_index).map_or(-1, |p| p.tray_now_left)
 }
 
 /// Get active tray for right nozzle (dual-nozzle printer)
@@ -1328,10 +1802,7 @@ pub extern "C" fn backend_get_tray_now_left(printer_index: c_int) -> c_int {
 #[no_mangle]
 pub extern "C" fn backend_get_tray_now_right(printer_index: c_int) -> c_int {
     let manager = BACKEND_MANAGER.lock().unwrap();
-    if printer_index < 0 || printer_index as usize >= manager.printer_count {
-        return -1;
-    }
-    manager.printers[printer_index as usize].tray_now_right
+    manager.printer(printer_index).map_or(-1, |p| p.tray_now_right)
 }
 
 /// Get currently active extruder (dual-nozzle printer)
@@ -1339,10 +1810,7 @@ pub extern "C" fn backend_get_tray_now_right(printer_index: c_int) -> c_int {
 #[no_mangle]
 pub extern "C" fn backend_get_active_extruder(printer_index: c_int) -> c_int {
     let manager = BACKEND_MANAGER.lock().unwrap();
-    if printer_index < 0 || printer_index as usize >= manager.printer_count {
-        return -1;
-    }
-    manager.printers[printer_index as usize].active_extruder
+    manager.printer(printer_index).map_or(-1, |p| p.active_extruder)
 }
 
 /// Check if firmware update is available
@@ -1416,37 +1884,19 @@ pub extern "C" fn ota_get_progress() -> c_int {
     }
 }
 
-/// Trigger OTA update check (non-blocking, spawns thread)
+/// Trigger OTA update check (non-blocking, runs on the backend worker)
 /// Returns 0 on success, -1 on error
 #[no_mangle]
 pub extern "C" fn ota_check_for_update() -> c_int {
-    // Get backend URL
-    let manager = BACKEND_MANAGER.lock().unwrap();
-    let url = match &manager.state {
-        BackendState::Connected { ip, port } => {
-            format!("http://{}.{}.{}.{}:{}", ip[0], ip[1], ip[2], ip[3], port)
-        }
-        _ => return -1, // Not connected
-    };
-    drop(manager);
+    if backend_is_connected() == 0 {
+        return -1;
+    }
 
-    // Spawn thread with larger stack (HTTP client needs more stack space)
-    std::thread::Builder::new()
-        .name("ota_check".into())
-        .stack_size(8192)  // 8KB stack
-        .spawn(move || {
-            match crate::ota_manager::check_for_update(&url) {
-                Ok(info) => {
-                    crate::ota_manager::set_update_available(info.available, &info.version);
-                    info!("OTA check complete: available={}, version={}", info.available, info.version);
-                }
-                Err(e) => {
-                    warn!("OTA check failed: {}", e);
-                }
-            }
-        })
-        .ok();
-    0
+    if crate::backend_worker::submit(crate::backend_worker::Job::OtaCheck) {
+        0
+    } else {
+        -1
+    }
 }
 
 /// Trigger OTA update (non-blocking, spawns thread)
@@ -1456,9 +1906,7 @@ pub extern "C" fn ota_start_update() -> c_int {
     // Get backend URL
     let manager = BACKEND_MANAGER.lock().unwrap();
     let url = match &manager.state {
-        BackendState::Connected { ip, port } => {
-            format!("http://{}.{}.{}.{}:{}", ip[0], ip[1], ip[2], ip[3], port)
-        }
+        BackendState::Connected { .. } => manager.server_url.clone(),
         _ => return -1, // Not connected
     };
     drop(manager);
@@ -1502,8 +1950,8 @@ pub struct SpoolKProfileC {
     pub printer_serial: [u8; 32], // Printer serial this profile is for
 }
 
-/// API response for spool listing
-#[derive(Debug, Deserialize)]
+/// API response for a spool
+#[derive(Debug, Clone, Deserialize)]
 struct ApiSpool {
     id: String,
     tag_id: Option<String>,
@@ -1515,6 +1963,8 @@ struct ApiSpool {
     label_weight: Option<i32>,
     weight_current: Option<i32>,
     slicer_filament: Option<String>,
+    #[serde(default)]
+    updated_at: Option<i64>,
 }
 
 /// API response for K-profile
@@ -1553,309 +2003,321 @@ fn parse_rgba_hex(hex: &str) -> u32 {
     u32::from_str_radix(&padded, 16).unwrap_or(0)
 }
 
-/// Get spool info by NFC tag ID
-/// Returns true if found, fills info struct
-#[no_mangle]
-pub extern "C" fn spool_get_by_tag(tag_id: *const c_char, info: *mut SpoolInfoC) -> bool {
-    if tag_id.is_null() || info.is_null() {
-        return false;
-    }
+/// Spool lookup states reported to C (see `SpoolLookupState` in ui_internal.h)
+const SPOOL_LOOKUP_NONE: c_int = -1;
+const SPOOL_LOOKUP_PENDING: c_int = 0;
+const SPOOL_LOOKUP_FOUND: c_int = 1;
+const SPOOL_LOOKUP_NOT_FOUND: c_int = 2;
+const SPOOL_LOOKUP_FAILED: c_int = 3;
 
-    let tag_id_str = unsafe {
-        match std::ffi::CStr::from_ptr(tag_id).to_str() {
-            Ok(s) => s,
-            Err(_) => return false,
-        }
-    };
+/// Recent tag lookups kept for the UI
+const MAX_SPOOL_LOOKUPS: usize = 8;
 
-    // Get backend URL
-    let manager = BACKEND_MANAGER.lock().unwrap();
-    let base_url = manager.server_url.clone();
-    drop(manager);
+/// Result of a tag lookup done on the backend worker
+enum LookupState {
+    Pending,
+    Found(ApiSpool),
+    NotFound,
+    Failed,
+}
 
-    if base_url.is_empty() {
-        return false;
-    }
+struct SpoolLookup {
+    tag_id: String,
+    state: LookupState,
+}
 
-    // GET /api/spools to list all spools
-    let url = format!("{}/api/spools", base_url);
+static SPOOL_LOOKUPS: Mutex<Vec<SpoolLookup>> = Mutex::new(Vec::new());
 
-    let config = HttpConfig {
-        timeout: Some(std::time::Duration::from_millis(HTTP_TIMEOUT_MS)),
-        ..Default::default()
-    };
+/// Spools recently seen by tag UID (LRU), so a re-scan is shown at once
+const SPOOL_CACHE_SIZE: usize = 16;
 
-    let connection = match EspHttpConnection::new(&config) {
-        Ok(c) => c,
-        Err(_) => return false,
-    };
+/// How long a cached spool is still shown while the backend is unreachable
+const SPOOL_CACHE_OFFLINE_TTL: std::time::Duration = std::time::Duration::from_secs(600);
 
-    let mut client = HttpClient::wrap(connection);
-    let request = match client.get(&url) {
-        Ok(r) => r,
-        Err(_) => return false,
-    };
+struct CachedSpool {
+    tag_id: String,
+    spool: ApiSpool,
+    /// ETag of the response (changes with the spool's updated_at)
+    etag: Option<String>,
+    /// Last time the backend confirmed this entry
+    confirmed: std::time::Instant,
+}
 
-    let mut response = match request.submit() {
-        Ok(r) => r,
-        Err(_) => return false,
-    };
+/// Least recently used first
+static SPOOL_CACHE: Mutex<Vec<CachedSpool>> = Mutex::new(Vec::new());
 
-    // Read response body
-    let mut buf = vec![0u8; 8192];
-    let mut total = 0;
-    loop {
-        match response.read(&mut buf[total..]) {
-            Ok(0) => break,
-            Ok(n) => total += n,
-            Err(_) => break,
-        }
-        if total >= buf.len() {
-            break;
-        }
-    }
+/// Result of `GET /api/spools/by-tag`
+enum TagLookup {
+    Found { spool: ApiSpool, etag: Option<String> },
+    /// The cached spool is still current (HTTP 304)
+    NotModified,
+    NotFound,
+}
 
-    if total == 0 {
-        return false;
+/// Untagged spool count from the last lookup (-1 = unknown)
+static UNTAGGED_COUNT: std::sync::atomic::AtomicI32 = std::sync::atomic::AtomicI32::new(-1);
+
+fn set_lookup_state(tag_id: &str, state: LookupState) {
+    let mut lookups = SPOOL_LOOKUPS.lock().unwrap();
+    lookups.retain(|l| l.tag_id != tag_id);
+    if lookups.len() >= MAX_SPOOL_LOOKUPS {
+        lookups.remove(0);
     }
+    lookups.push(SpoolLookup { tag_id: tag_id.to_string(), state });
+}
 
-    // Parse JSON array of spools
-    let body = String::from_utf8_lossy(&buf[..total]);
-    let spools: Vec<ApiSpool> = match serde_json::from_str(&body) {
-        Ok(s) => s,
-        Err(_) => return false,
-    };
+/// Drop a cached lookup (the tag was just linked or added)
+fn invalidate_spool_lookup(tag_id: &str) {
+    SPOOL_LOOKUPS.lock().unwrap().retain(|l| l.tag_id != tag_id);
+    SPOOL_CACHE.lock().unwrap().retain(|c| c.tag_id != tag_id);
+}
 
-    // Find spool with matching tag_id
-    for spool in spools {
-        if let Some(ref tid) = spool.tag_id {
-            if tid == tag_id_str {
-                // Found - fill info struct
-                let info_ref = unsafe { &mut *info };
-                *info_ref = SpoolInfoC {
-                    id: [0; 64],
-                    tag_id: [0; 32],
-                    brand: [0; 32],
-                    material: [0; 16],
-                    subtype: [0; 32],
-                    color_name: [0; 32],
-                    color_rgba: 0,
-                    label_weight: 0,
-                    weight_current: 0,
-                    slicer_filament: [0; 32],
-                    valid: true,
-                };
-
-                copy_to_c_buf(&spool.id, &mut info_ref.id);
-                copy_to_c_buf(tid, &mut info_ref.tag_id);
-                if let Some(ref b) = spool.brand {
-                    copy_to_c_buf(b, &mut info_ref.brand);
-                }
-                if let Some(ref m) = spool.material {
-                    copy_to_c_buf(m, &mut info_ref.material);
-                }
-                if let Some(ref s) = spool.subtype {
-                    copy_to_c_buf(s, &mut info_ref.subtype);
-                }
-                if let Some(ref c) = spool.color_name {
-                    copy_to_c_buf(c, &mut info_ref.color_name);
-                }
-                if let Some(ref rgba) = spool.rgba {
-                    info_ref.color_rgba = parse_rgba_hex(rgba);
-                }
-                if let Some(w) = spool.label_weight {
-                    info_ref.label_weight = w;
-                }
-                if let Some(w) = spool.weight_current {
-                    info_ref.weight_current = w;
-                }
-                if let Some(ref sf) = spool.slicer_filament {
-                    copy_to_c_buf(sf, &mut info_ref.slicer_filament);
-                }
+/// Cached spool for a tag, marked as most recently used
+fn spool_cache_get(tag_id: &str) -> Option<(ApiSpool, Option<String>, std::time::Instant)> {
+    let mut cache = SPOOL_CACHE.lock().unwrap();
+    let index = cache.iter().position(|c| c.tag_id == tag_id)?;
+    let entry = cache.remove(index);
+    let result = (entry.spool.clone(), entry.etag.clone(), entry.confirmed);
+    cache.push(entry);
+    Some(result)
+}
 
-                info!("spool_get_by_tag: found spool {} for tag {}", spool.id, tag_id_str);
-                return true;
-            }
-        }
+/// Store a spool confirmed by the backend, evicting the least recently used
+fn spool_cache_put(tag_id: &str, spool: ApiSpool, etag: Option<String>) {
+    let mut cache = SPOOL_CACHE.lock().unwrap();
+    cache.retain(|c| c.tag_id != tag_id);
+    if cache.len() >= SPOOL_CACHE_SIZE {
+        cache.remove(0);
     }
+    cache.push(CachedSpool {
+        tag_id: tag_id.to_string(),
+        spool,
+        etag,
+        confirmed: std::time::Instant::now(),
+    });
+}
 
-    info!("spool_get_by_tag: no spool found for tag {}", tag_id_str);
-    false
+/// Whether the last lookup for a tag found its spool
+pub fn spool_lookup_found(tag_id: &str) -> bool {
+    SPOOL_LOOKUPS
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|l| l.tag_id == tag_id && matches!(l.state, LookupState::Found(_)))
 }
 
-/// Get K-profile for a spool on a specific printer
-/// Returns true if found, fills profile struct
-#[no_mangle]
-pub extern "C" fn spool_get_k_profile_for_printer(
-    spool_id: *const c_char,
-    printer_serial: *const c_char,
-    profile: *mut SpoolKProfileC,
-) -> bool {
-    if spool_id.is_null() || printer_serial.is_null() || profile.is_null() {
+/// Spool data for writing to a tag, from the last lookup or the cache
+pub fn spool_tag_info(tag_id: &str) -> Option<crate::nfc::i2c_bridge::DecodedTagInfo> {
+    let spool = SPOOL_LOOKUPS.lock().unwrap().iter().find_map(|l| match &l.state {
+        LookupState::Found(spool) if l.tag_id == tag_id => Some(spool.clone()),
+        _ => None,
+    });
+    let spool = spool.or_else(|| spool_cache_get(tag_id).map(|(spool, _, _)| spool))?;
+
+    Some(crate::nfc::i2c_bridge::DecodedTagInfo {
+        vendor: spool.brand.unwrap_or_default(),
+        material: spool.material.unwrap_or_default(),
+        material_subtype: spool.subtype.unwrap_or_default(),
+        color_name: spool.color_name.unwrap_or_default(),
+        color_rgba: spool.rgba.as_deref().map(parse_rgba_hex).unwrap_or(0),
+        spool_weight: spool.label_weight.unwrap_or(0),
+        tag_type_name: String::new(),
+        ..Default::default()
+    })
+}
+
+/// Start a fresh inventory lookup for a tag on the backend worker
+/// A cached spool is reported as found right away and revalidated in the background.
+pub fn start_spool_lookup(tag_id: &str) -> bool {
+    if tag_id.is_empty() {
+        return false;
+    }
+    let cached = spool_cache_get(tag_id);
+    if get_server_url().is_none() && cached.is_none() {
         return false;
     }
 
-    let spool_id_str = unsafe {
-        match std::ffi::CStr::from_ptr(spool_id).to_str() {
-            Ok(s) => s,
-            Err(_) => return false,
+    let has_cached = cached.is_some();
+    match cached {
+        Some((spool, _, _)) => set_lookup_state(tag_id, LookupState::Found(spool)),
+        None => set_lookup_state(tag_id, LookupState::Pending),
+    }
+    let submitted = crate::backend_worker::submit(crate::backend_worker::Job::SpoolLookup {
+        tag_id: tag_id.to_string(),
+    });
+    if !submitted && !has_cached {
+        set_lookup_state(tag_id, LookupState::Failed);
+    }
+    submitted || has_cached
+}
+
+/// Run a tag lookup (backend worker only - blocks on HTTP)
+pub fn run_spool_lookup(tag_id: &str) {
+    let cached = spool_cache_get(tag_id);
+    let base_url = get_server_url();
+    let result = match base_url.as_deref() {
+        Some(base_url) => {
+            let etag = cached.as_ref().and_then(|(_, etag, _)| etag.as_deref());
+            fetch_spool_by_spool_tags(base_url, tag_id, etag)
         }
+        None => Err("No backend server".to_string()),
     };
 
-    let printer_serial_str = unsafe {
-        match std::ffi::CStr::from_ptr(printer_serial).to_str() {
-            Ok(s) => s,
-            Err(_) => return false,
+    let state = match (result, cached) {
+        (Ok(TagLookup::Found { spool, etag }), _) => {
+            info!("Spool lookup: found spool {} for tag {}", spool.id, tag_id);
+            spool_cache_put(tag_id, spool.clone(), etag);
+            LookupState::Found(spool)
+        }
+        (Ok(TagLookup::NotModified), Some((spool, etag, _))) => {
+            spool_cache_put(tag_id, spool.clone(), etag);
+            LookupState::Found(spool)
+        }
+        (Ok(TagLookup::NotModified), None) => {
+            warn!("Spool lookup for tag {}: not modified, but nothing cached", tag_id);
+            LookupState::Failed
+        }
+        (Ok(TagLookup::NotFound), _) => {
+            info!("Spool lookup: no spool found for tag {}", tag_id);
+            SPOOL_CACHE.lock().unwrap().retain(|c| c.tag_id != tag_id);
+            LookupState::NotFound
+        }
+        // Backend unreachable - a recently confirmed spool is still good enough
+        (Err(e), Some((spool, _, confirmed))) if confirmed.elapsed() < SPOOL_CACHE_OFFLINE_TTL => {
+            warn!("Spool lookup for tag {} failed, showing cached spool: {}", tag_id, e);
+            LookupState::Found(spool)
+        }
+        (Err(e), _) => {
+            warn!("Spool lookup for tag {} failed: {}", tag_id, e);
+            LookupState::Failed
         }
     };
 
-    // Get backend URL
-    let manager = BACKEND_MANAGER.lock().unwrap();
-    let base_url = manager.server_url.clone();
-    drop(manager);
+    // Untagged count is shown next to the lookup result ("link to existing spool")
+    if let (LookupState::NotFound, Some(base_url)) = (&state, base_url.as_deref()) {
+        match fetch_untagged_count(base_url) {
+            Ok(count) => UNTAGGED_COUNT.store(count, std::sync::atomic::Ordering::Relaxed),
+            Err(e) => warn!("Untagged count fetch failed: {}", e),
+        }
+    }
 
-    if base_url.is_empty() {
-        return false;
+    // Ignore the result if the lookup was invalidated meanwhile
+    let still_wanted = SPOOL_LOOKUPS.lock().unwrap().iter().any(|l| l.tag_id == tag_id);
+    if still_wanted {
+        set_lookup_state(tag_id, state);
+    }
+}
+
+/// Look up the spool by the tag, then by the other tags of the same spool
+/// (the inventory spool may be linked by the tag on its other side)
+fn fetch_spool_by_spool_tags(base_url: &str, tag_id: &str, etag: Option<&str>) -> Result<TagLookup, String> {
+    for uid in crate::spool_identity::spool_tag_uids(tag_id) {
+        match fetch_spool_by_tag(base_url, &uid, etag)? {
+            TagLookup::NotFound => continue,
+            found => {
+                if uid != tag_id {
+                    info!("Spool lookup: tag {} found by its spool's other tag {}", tag_id, uid);
+                }
+                return Ok(found);
+            }
+        }
     }
+    Ok(TagLookup::NotFound)
+}
 
-    // GET /api/spools/{id}/k-profiles
-    let url = format!("{}/api/spools/{}/k-profiles", base_url, spool_id_str);
+/// GET /api/spools/by-tag for the spool with a matching tag_id
+/// With the ETag of a cached spool the backend answers 304 if it is unchanged.
+fn fetch_spool_by_tag(base_url: &str, tag_id: &str, etag: Option<&str>) -> Result<TagLookup, String> {
+    let url = format!("{}/api/spools/by-tag?tag_id={}", base_url, url_encode(tag_id));
 
-    let config = HttpConfig {
-        timeout: Some(std::time::Duration::from_millis(HTTP_TIMEOUT_MS)),
-        ..Default::default()
-    };
+    let config = tls_pin::http_config(std::time::Duration::from_millis(HTTP_TIMEOUT_MS));
 
-    let connection = match EspHttpConnection::new(&config) {
-        Ok(c) => c,
-        Err(_) => return false,
-    };
+    let connection = EspHttpConnection::new(&config)
+        .map_err(|e| format!("HTTP connection failed: {:?}", e))?;
 
     let mut client = HttpClient::wrap(connection);
-    let request = match client.get(&url) {
-        Ok(r) => r,
-        Err(_) => return false,
-    };
+    let api_key = device_auth::api_key();
+    let conditional: Vec<(&str, &str)> = etag.map(|e| ("If-None-Match", e)).into_iter().collect();
+    let headers = device_auth::with_api_key(&conditional, &api_key);
+    let request = client.request(embedded_svc::http::Method::Get, &url, &headers)
+        .map_err(|e| format!("GET request failed: {:?}", e))?;
 
-    let mut response = match request.submit() {
-        Ok(r) => r,
-        Err(_) => return false,
-    };
+    let mut response = request.submit()
+        .map_err(|e| format!("Request submit failed: {:?}", e))?;
 
-    // Read response body
+    let status = response.status();
+    note_auth_status(status);
+    match status {
+        200 => {}
+        304 => return Ok(TagLookup::NotModified),
+        404 => return Ok(TagLookup::NotFound),
+        _ => return Err(format!("HTTP error: {}", status)),
+    }
+
+    let etag = response.header("ETag").map(|e| e.to_string());
+
+    // Read response (a single spool)
     let mut buf = vec![0u8; 4096];
     let mut total = 0;
     loop {
         match response.read(&mut buf[total..]) {
             Ok(0) => break,
             Ok(n) => total += n,
-            Err(_) => break,
+            Err(e) => return Err(format!("Read error: {:?}", e)),
         }
         if total >= buf.len() {
             break;
         }
     }
 
-    if total == 0 {
-        return false;
-    }
-
-    // Parse JSON array of K-profiles
-    let body = String::from_utf8_lossy(&buf[..total]);
-    let profiles: Vec<ApiKProfile> = match serde_json::from_str(&body) {
-        Ok(p) => p,
-        Err(_) => return false,
-    };
-
-    // Find profile matching the printer serial
-    for p in profiles {
-        if let Some(ref serial) = p.printer_serial {
-            if serial == printer_serial_str {
-                // Found - fill profile struct
-                let profile_ref = unsafe { &mut *profile };
-                *profile_ref = SpoolKProfileC {
-                    cali_idx: p.cali_idx.unwrap_or(-1),
-                    k_value: [0; 16],
-                    name: [0; 64],
-                    printer_serial: [0; 32],
-                };
-
-                if let Some(ref kv) = p.k_value {
-                    copy_to_c_buf(kv, &mut profile_ref.k_value);
-                }
-                if let Some(ref n) = p.name {
-                    copy_to_c_buf(n, &mut profile_ref.name);
-                }
-                copy_to_c_buf(serial, &mut profile_ref.printer_serial);
-
-                info!("spool_get_k_profile: found profile for {} on {}", spool_id_str, printer_serial_str);
-                return true;
-            }
-        }
-    }
+    let spool: ApiSpool = serde_json::from_slice(&buf[..total])
+        .map_err(|e| format!("JSON parse error: {:?}", e))?;
 
-    info!("spool_get_k_profile: no profile for {} on {}", spool_id_str, printer_serial_str);
-    false
+    Ok(TagLookup::Found { spool, etag })
 }
 
-/// Check if a spool with given tag_id exists in inventory
-#[no_mangle]
-pub extern "C" fn spool_exists_by_tag(tag_id: *const c_char) -> bool {
-    if tag_id.is_null() {
-        return false;
-    }
-
-    let tag_id_str = unsafe {
-        match std::ffi::CStr::from_ptr(tag_id).to_str() {
-            Ok(s) => s,
-            Err(_) => return false,
+/// Percent-encode a query parameter value (tag IDs are base64)
+fn url_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for b in value.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(b as char),
+            _ => encoded.push_str(&format!("%{:02X}", b)),
         }
-    };
-
-    let manager = BACKEND_MANAGER.lock().unwrap();
-    let base_url = manager.server_url.clone();
-    drop(manager);
-
-    if base_url.is_empty() {
-        return false;
     }
+    encoded
+}
 
-    // GET /api/spools - get all spools and search for matching tag
-    let url = format!("{}/api/spools", base_url);
+/// GET /api/spools?untagged=true and count the results
+fn fetch_untagged_count(base_url: &str) -> Result<i32, String> {
+    let url = format!("{}/api/spools?untagged=true", base_url);
 
-    let config = HttpConfig {
-        timeout: Some(std::time::Duration::from_millis(HTTP_TIMEOUT_MS)),
-        ..Default::default()
-    };
+    let config = tls_pin::http_config(std::time::Duration::from_millis(HTTP_TIMEOUT_MS));
 
-    let connection = match EspHttpConnection::new(&config) {
-        Ok(c) => c,
-        Err(_) => return false,
-    };
+    let connection = EspHttpConnection::new(&config)
+        .map_err(|e| format!("HTTP connection failed: {:?}", e))?;
 
     let mut client = HttpClient::wrap(connection);
-    let request = match client.get(&url) {
-        Ok(r) => r,
-        Err(_) => return false,
-    };
+    let api_key = device_auth::api_key();
+    let headers = device_auth::with_api_key(&[], &api_key);
+    let request = client.request(embedded_svc::http::Method::Get, &url, &headers)
+        .map_err(|e| format!("GET request failed: {:?}", e))?;
 
-    let mut response = match request.submit() {
-        Ok(r) => r,
-        Err(_) => return false,
-    };
+    let mut response = request.submit()
+        .map_err(|e| format!("Request submit failed: {:?}", e))?;
 
-    if response.status() != 200 {
-        return false;
+    let status = response.status();
+    if status != 200 {
+        return Err(format!("HTTP error: {}", status));
     }
 
-    // Read response - use larger buffer for full spool list
-    let mut buf = vec![0u8; 16384];
+    let mut buf = vec![0u8; 8192];
     let mut total = 0;
     loop {
         match response.read(&mut buf[total..]) {
             Ok(0) => break,
             Ok(n) => total += n,
-            Err(_) => break,
+            Err(e) => return Err(format!("Read error: {:?}", e)),
         }
         if total >= buf.len() {
             break;
@@ -1863,314 +2325,455 @@ pub extern "C" fn spool_exists_by_tag(tag_id: *const c_char) -> bool {
     }
 
     if total == 0 {
-        return false;
+        return Ok(0);
     }
 
-    // Parse as JSON array of spools
-    let body = String::from_utf8_lossy(&buf[..total]);
-    let spools: Vec<ApiSpool> = match serde_json::from_str(&body) {
-        Ok(s) => s,
-        Err(e) => {
-            warn!("spool_exists_by_tag: JSON parse error: {:?}", e);
-            return false;
-        }
+    let spools: Vec<serde_json::Value> = serde_json::from_slice(&buf[..total])
+        .map_err(|e| format!("JSON parse error: {:?}", e))?;
+
+    Ok(spools.len() as i32)
+}
+
+/// Fill the C spool struct from an API spool
+fn fill_spool_info(info: &mut SpoolInfoC, spool: &ApiSpool) {
+    *info = SpoolInfoC {
+        id: [0; 64],
+        tag_id: [0; 32],
+        brand: [0; 32],
+        material: [0; 16],
+        subtype: [0; 32],
+        color_name: [0; 32],
+        color_rgba: 0,
+        label_weight: 0,
+        weight_current: 0,
+        slicer_filament: [0; 32],
+        valid: true,
     };
 
-    // Search for matching tag_id
-    for spool in spools {
-        if let Some(ref tid) = spool.tag_id {
-            if tid == tag_id_str {
-                info!("spool_exists_by_tag: found spool for tag {}", tag_id_str);
-                return true;
-            }
-        }
+    copy_to_c_buf(&spool.id, &mut info.id);
+    if let Some(ref tid) = spool.tag_id {
+        copy_to_c_buf(tid, &mut info.tag_id);
+    }
+    if let Some(ref b) = spool.brand {
+        copy_to_c_buf(b, &mut info.brand);
+    }
+    if let Some(ref m) = spool.material {
+        copy_to_c_buf(m, &mut info.material);
+    }
+    if let Some(ref s) = spool.subtype {
+        copy_to_c_buf(s, &mut info.subtype);
+    }
+    if let Some(ref c) = spool.color_name {
+        copy_to_c_buf(c, &mut info.color_name);
+    }
+    if let Some(ref rgba) = spool.rgba {
+        info.color_rgba = parse_rgba_hex(rgba);
+    }
+    if let Some(w) = spool.label_weight {
+        info.label_weight = w;
+    }
+    if let Some(w) = spool.weight_current {
+        info.weight_current = w;
+    }
+    if let Some(ref sf) = spool.slicer_filament {
+        copy_to_c_buf(sf, &mut info.slicer_filament);
     }
-
-    info!("spool_exists_by_tag: no spool found for tag {}", tag_id_str);
-    false
 }
 
-/// Add a new spool to inventory
+/// Start an inventory lookup for a tag (non-blocking)
+/// Returns 0 if submitted, -1 on invalid tag or no server
 #[no_mangle]
-pub extern "C" fn spool_add_to_inventory(
-    tag_id: *const c_char,
-    vendor: *const c_char,
-    material: *const c_char,
-    subtype: *const c_char,
-    color_name: *const c_char,
-    color_rgba: u32,
-    label_weight: c_int,
-    weight_current: c_int,
-    data_origin: *const c_char,
-    tag_type: *const c_char,
-    slicer_filament: *const c_char,
-) -> bool {
-    fn c_str_to_string(ptr: *const c_char) -> String {
-        if ptr.is_null() {
-            String::new()
-        } else {
-            unsafe {
-                std::ffi::CStr::from_ptr(ptr)
-                    .to_str()
-                    .unwrap_or("")
-                    .to_string()
-            }
-        }
+pub extern "C" fn spool_lookup_start(tag_id: *const c_char) -> c_int {
+    if tag_id.is_null() {
+        return -1;
     }
 
-    let tag_id_str = c_str_to_string(tag_id);
-    let vendor_str = c_str_to_string(vendor);
-    let material_str = c_str_to_string(material);
-    let subtype_str = c_str_to_string(subtype);
-    let color_name_str = c_str_to_string(color_name);
-    let data_origin_str = c_str_to_string(data_origin);
-    let tag_type_str = c_str_to_string(tag_type);
-    let slicer_filament_str = c_str_to_string(slicer_filament);
-
-    // Convert RGBA to hex string
-    let rgba_hex = format!("{:08X}", color_rgba);
+    let tag_id_str = unsafe {
+        match std::ffi::CStr::from_ptr(tag_id).to_str() {
+            Ok(s) => s,
+            Err(_) => return -1,
+        }
+    };
 
-    let manager = BACKEND_MANAGER.lock().unwrap();
-    let base_url = manager.server_url.clone();
-    drop(manager);
+    if start_spool_lookup(tag_id_str) { 0 } else { -1 }
+}
 
-    if base_url.is_empty() {
-        return false;
+/// Get the state of the lookup for a tag
+/// Returns -1 = none, 0 = pending, 1 = found, 2 = not found, 3 = failed
+#[no_mangle]
+pub extern "C" fn spool_lookup_poll(tag_id: *const c_char) -> c_int {
+    if tag_id.is_null() {
+        return SPOOL_LOOKUP_NONE;
     }
 
-    // POST /api/spools
-    let url = format!("{}/api/spools", base_url);
+    let tag_id_str = unsafe {
+        match std::ffi::CStr::from_ptr(tag_id).to_str() {
+            Ok(s) => s,
+            Err(_) => return SPOOL_LOOKUP_NONE,
+        }
+    };
 
-    let body = format!(
-        r#"{{"tag_id":"{}","brand":"{}","material":"{}","subtype":"{}","color_name":"{}","rgba":"{}","label_weight":{},"weight_current":{},"data_origin":"{}","tag_type":"{}","slicer_filament":"{}"}}"#,
-        tag_id_str, vendor_str, material_str, subtype_str, color_name_str,
-        rgba_hex, label_weight, weight_current, data_origin_str, tag_type_str, slicer_filament_str
-    );
+    let lookups = SPOOL_LOOKUPS.lock().unwrap();
+    match lookups.iter().find(|l| l.tag_id == tag_id_str).map(|l| &l.state) {
+        None => SPOOL_LOOKUP_NONE,
+        Some(LookupState::Pending) => SPOOL_LOOKUP_PENDING,
+        Some(LookupState::Found(_)) => SPOOL_LOOKUP_FOUND,
+        Some(LookupState::NotFound) => SPOOL_LOOKUP_NOT_FOUND,
+        Some(LookupState::Failed) => SPOOL_LOOKUP_FAILED,
+    }
+}
 
-    info!("spool_add_to_inventory: POST {} with {}", url, body);
+/// Get spool info by NFC tag ID (non-blocking)
+/// Returns true if a completed lookup found the spool, fills info struct.
+/// Starts a lookup if there is none for this tag yet.
+#[no_mangle]
+pub extern "C" fn spool_get_by_tag(tag_id: *const c_char, info: *mut SpoolInfoC) -> bool {
+    if tag_id.is_null() || info.is_null() {
+        return false;
+    }
 
-    let config = HttpConfig {
-        timeout: Some(std::time::Duration::from_millis(HTTP_TIMEOUT_MS)),
-        ..Default::default()
+    let tag_id_str = unsafe {
+        match std::ffi::CStr::from_ptr(tag_id).to_str() {
+            Ok(s) => s,
+            Err(_) => return false,
+        }
     };
 
-    let connection = match EspHttpConnection::new(&config) {
-        Ok(c) => c,
-        Err(e) => {
-            warn!("Failed to create HTTP connection: {:?}", e);
-            return false;
+    let lookups = SPOOL_LOOKUPS.lock().unwrap();
+    match lookups.iter().find(|l| l.tag_id == tag_id_str) {
+        Some(SpoolLookup { state: LookupState::Found(spool), .. }) => {
+            fill_spool_info(unsafe { &mut *info }, spool);
+            true
         }
-    };
+        Some(_) => false,
+        None => {
+            drop(lookups);
+            start_spool_lookup(tag_id_str);
+            false
+        }
+    }
+}
 
-    let mut client = HttpClient::wrap(connection);
+/// K-profile of a spool on a printer (None = the spool has none for it)
+static SPOOL_K_PROFILE: Mutex<Request<Option<ApiKProfile>>> = Mutex::new(Request::new());
 
-    let headers = [
-        ("Content-Type", "application/json"),
-        ("Content-Length", &body.len().to_string()),
-    ];
+/// Start looking up a spool's K-profile for a printer (non-blocking)
+/// Returns 0 if submitted, -1 on invalid arguments or if the worker isn't running
+#[no_mangle]
+pub extern "C" fn spool_k_profile_start(spool_id: *const c_char, printer_serial: *const c_char) -> c_int {
+    let spool_id = c_str_to_string(spool_id);
+    let printer_serial = c_str_to_string(printer_serial);
+    if spool_id.is_empty() || printer_serial.is_empty() {
+        return -1;
+    }
 
-    let mut request = match client.request(embedded_svc::http::Method::Post, &url, &headers) {
-        Ok(r) => r,
-        Err(e) => {
-            warn!("Failed to create POST request: {:?}", e);
-            return false;
+    start_request(&SPOOL_K_PROFILE, |seq| Job::SpoolKProfile { seq, spool_id, printer_serial })
+}
+
+/// Get the state of the spool K-profile lookup (`BackendRequestState`)
+/// When done, fills the profile; cali_idx is -1 and the strings are empty if
+/// the spool has no profile for the printer.
+#[no_mangle]
+pub extern "C" fn spool_k_profile_poll(profile: *mut SpoolKProfileC) -> c_int {
+    if profile.is_null() {
+        return REQUEST_NONE;
+    }
+
+    poll_request(&SPOOL_K_PROFILE, |found| {
+        let profile_ref = unsafe { &mut *profile };
+        *profile_ref = SpoolKProfileC {
+            cali_idx: -1,
+            k_value: [0; 16],
+            name: [0; 64],
+            printer_serial: [0; 32],
+        };
+
+        if let Some(p) = found {
+            profile_ref.cali_idx = p.cali_idx.unwrap_or(-1);
+            if let Some(ref kv) = p.k_value {
+                copy_to_c_buf(kv, &mut profile_ref.k_value);
+            }
+            if let Some(ref n) = p.name {
+                copy_to_c_buf(n, &mut profile_ref.name);
+            }
+            if let Some(ref serial) = p.printer_serial {
+                copy_to_c_buf(serial, &mut profile_ref.printer_serial);
+            }
         }
-    };
+    })
+}
 
-    if let Err(e) = request.write(body.as_bytes()) {
-        warn!("Failed to write request body: {:?}", e);
-        return false;
+/// Look up a spool's K-profile (backend worker only - blocks on HTTP)
+pub fn run_spool_k_profile(seq: u32, spool_id: &str, printer_serial: &str) {
+    let result = fetch_spool_k_profile(spool_id, printer_serial);
+    if let Ok(profile) = &result {
+        info!("Spool K-profile for {} on {}: {}", spool_id, printer_serial,
+              if profile.is_some() { "found" } else { "none" });
     }
+    finish_request(&SPOOL_K_PROFILE, seq, "Spool K-profile lookup", result);
+}
 
-    if let Err(e) = request.flush() {
-        warn!("Failed to flush request: {:?}", e);
-        return false;
+/// GET /api/spools/{id}/k-profiles and pick the profile for the printer
+fn fetch_spool_k_profile(spool_id: &str, printer_serial: &str) -> Result<Option<ApiKProfile>, String> {
+    let (status, body) = api_get(&format!("/api/spools/{}/k-profiles", spool_id), 4096)?;
+    if status != 200 {
+        return Err(format!("HTTP error: {}", status));
     }
 
-    let response = match request.submit() {
-        Ok(r) => r,
-        Err(e) => {
-            warn!("Failed to submit request: {:?}", e);
-            return false;
-        }
-    };
+    let profiles: Vec<ApiKProfile> = serde_json::from_slice(&body)
+        .map_err(|e| format!("JSON parse error: {:?}", e))?;
 
-    let status = response.status();
-    if status != 200 && status != 201 {
-        warn!("spool_add_to_inventory failed with status {}", status);
+    Ok(profiles.into_iter().find(|p| p.printer_serial.as_deref() == Some(printer_serial)))
+}
+
+/// Check if a spool with given tag_id exists in inventory (non-blocking)
+/// Reads the lookup result; starts a lookup if there is none for this tag yet.
+#[no_mangle]
+pub extern "C" fn spool_exists_by_tag(tag_id: *const c_char) -> bool {
+    if tag_id.is_null() {
         return false;
     }
 
-    info!("spool_add_to_inventory: success");
-    true
+    let tag_id_str = unsafe {
+        match std::ffi::CStr::from_ptr(tag_id).to_str() {
+            Ok(s) => s,
+            Err(_) => return false,
+        }
+    };
+
+    match spool_lookup_poll(tag_id) {
+        SPOOL_LOOKUP_FOUND => true,
+        SPOOL_LOOKUP_NONE => {
+            start_spool_lookup(tag_id_str);
+            false
+        }
+        _ => false,
+    }
 }
 
-/// Untagged spool info for FFI
-#[repr(C)]
-pub struct UntaggedSpoolInfo {
-    pub id: [c_char; 64],
-    pub brand: [c_char; 32],
-    pub material: [c_char; 32],
-    pub color_name: [c_char; 32],
-    pub color_rgba: u32,
-    pub label_weight: i32,
-    pub spool_number: i32,
-    pub valid: bool,
+// =============================================================================
+// UI requests on the backend worker
+// =============================================================================
+
+/// Request states reported to C (see `BackendRequestState` in ui_internal.h)
+const REQUEST_NONE: c_int = -1;
+const REQUEST_PENDING: c_int = 0;
+const REQUEST_DONE: c_int = 1;
+const REQUEST_FAILED: c_int = 2;
+
+enum RequestState<T> {
+    None,
+    Pending,
+    Done(T),
+    Failed,
 }
 
-/// API response for untagged spools
-#[derive(Debug, Clone, Deserialize, Default)]
-struct ApiUntaggedSpool {
-    id: String,
-    brand: Option<String>,
-    material: Option<String>,
-    color_name: Option<String>,
-    rgba: Option<String>,
-    label_weight: Option<i32>,
-    spool_number: Option<i32>,
+/// Latest UI request of one kind (started by a `*_start`, read by a `*_poll`)
+/// Starting another one supersedes it: the sequence number changes, so a late
+/// result of the old request is dropped.
+struct Request<T> {
+    seq: u32,
+    state: RequestState<T>,
 }
 
-/// Get list of spools without NFC tags assigned
-#[no_mangle]
-pub extern "C" fn spool_get_untagged_list(
-    spools: *mut UntaggedSpoolInfo,
-    max_count: c_int,
-) -> c_int {
-    if spools.is_null() || max_count <= 0 {
-        return -1;
+impl<T> Request<T> {
+    const fn new() -> Self {
+        Request { seq: 0, state: RequestState::None }
     }
+}
 
-    let manager = BACKEND_MANAGER.lock().unwrap();
-    let base_url = manager.server_url.clone();
-    drop(manager);
+/// Mark a new request pending and queue its job (built from the request's seq)
+/// Returns 0 if queued, -1 if the worker isn't running
+fn start_request<T>(request: &Mutex<Request<T>>, job: impl FnOnce(u32) -> Job) -> c_int {
+    let seq = {
+        let mut request = request.lock().unwrap();
+        request.seq = request.seq.wrapping_add(1);
+        request.state = RequestState::Pending;
+        request.seq
+    };
 
-    if base_url.is_empty() {
-        return -1;
+    if crate::backend_worker::submit(job(seq)) {
+        return 0;
     }
+    let mut request = request.lock().unwrap();
+    if request.seq == seq {
+        request.state = RequestState::Failed;
+    }
+    -1
+}
 
-    // GET /api/spools?untagged=true
-    let url = format!("{}/api/spools?untagged=true", base_url);
-
-    let config = HttpConfig {
-        timeout: Some(std::time::Duration::from_millis(HTTP_TIMEOUT_MS)),
-        ..Default::default()
+/// Publish the result of a request, unless a newer one replaced it
+fn finish_request<T>(request: &Mutex<Request<T>>, seq: u32, what: &str, result: Result<T, String>) {
+    let state = match result {
+        Ok(value) => RequestState::Done(value),
+        Err(e) => {
+            warn!("{} failed: {}", what, e);
+            RequestState::Failed
+        }
     };
 
-    let connection = match EspHttpConnection::new(&config) {
-        Ok(c) => c,
-        Err(_) => return -1,
-    };
+    let mut request = request.lock().unwrap();
+    if request.seq == seq {
+        request.state = state;
+    }
+}
+
+/// State of a request for C, handing a finished result to `fill`
+fn poll_request<T>(request: &Mutex<Request<T>>, fill: impl FnOnce(&T)) -> c_int {
+    let request = request.lock().unwrap();
+    match &request.state {
+        RequestState::None => REQUEST_NONE,
+        RequestState::Pending => REQUEST_PENDING,
+        RequestState::Done(value) => {
+            fill(value);
+            REQUEST_DONE
+        }
+        RequestState::Failed => REQUEST_FAILED,
+    }
+}
+
+/// Copy a C string argument (empty if NULL or not UTF-8)
+fn c_str_to_string(ptr: *const c_char) -> String {
+    if ptr.is_null() {
+        return String::new();
+    }
+    unsafe { std::ffi::CStr::from_ptr(ptr) }
+        .to_str()
+        .unwrap_or("")
+        .to_string()
+}
+
+/// GET a backend API path, reading at most `max_len` bytes of the body
+/// Returns the status and body; Err if no server is set or it can't be reached
+fn api_get(path: &str, max_len: usize) -> Result<(u16, Vec<u8>), String> {
+    let base_url = get_server_url().ok_or("No backend server")?;
+    let url = format!("{}{}", base_url, path);
+
+    let config = tls_pin::http_config(std::time::Duration::from_millis(HTTP_TIMEOUT_MS));
+
+    let connection = EspHttpConnection::new(&config)
+        .map_err(|e| format!("HTTP connection failed: {:?}", e))?;
 
     let mut client = HttpClient::wrap(connection);
-    let request = match client.get(&url) {
-        Ok(r) => r,
-        Err(_) => return -1,
-    };
+    let api_key = device_auth::api_key();
+    let headers = device_auth::with_api_key(&[], &api_key);
+    let request = client.request(embedded_svc::http::Method::Get, &url, &headers)
+        .map_err(|e| format!("GET request failed: {:?}", e))?;
 
-    let mut response = match request.submit() {
-        Ok(r) => r,
-        Err(_) => return -1,
-    };
+    let mut response = request.submit()
+        .map_err(|e| format!("Request submit failed: {:?}", e))?;
 
-    if response.status() != 200 {
-        return -1;
-    }
+    let status = response.status();
 
-    let mut buf = vec![0u8; 8192];
+    let mut buf = vec![0u8; max_len];
     let mut total = 0;
     loop {
         match response.read(&mut buf[total..]) {
             Ok(0) => break,
             Ok(n) => total += n,
-            Err(_) => break,
+            Err(e) => return Err(format!("Read error: {:?}", e)),
         }
         if total >= buf.len() {
             break;
         }
     }
+    buf.truncate(total);
 
-    if total == 0 {
-        return 0;
-    }
+    Ok((status, buf))
+}
 
-    let body = String::from_utf8_lossy(&buf[..total]);
-    let api_spools: Vec<ApiUntaggedSpool> = match serde_json::from_str(&body) {
-        Ok(s) => s,
-        Err(_) => return -1,
-    };
+/// POST a JSON body to a backend API path and return the status
+/// Err if no server is set or it can't be reached
+fn api_post(path: &str, body: &str) -> Result<u16, String> {
+    let base_url = get_server_url().ok_or("No backend server")?;
+    let url = format!("{}{}", base_url, path);
 
-    let count = api_spools.len().min(max_count as usize);
+    let config = tls_pin::http_config(std::time::Duration::from_millis(HTTP_TIMEOUT_MS));
 
-    for (i, spool) in api_spools.iter().take(count).enumerate() {
-        let spool_ref = unsafe { &mut *spools.add(i) };
+    let connection = EspHttpConnection::new(&config)
+        .map_err(|e| format!("HTTP connection failed: {:?}", e))?;
 
-        spool_ref.id = [0; 64];
-        spool_ref.brand = [0; 32];
-        spool_ref.material = [0; 32];
-        spool_ref.color_name = [0; 32];
-        spool_ref.color_rgba = 0;
-        spool_ref.label_weight = spool.label_weight.unwrap_or(0);
-        spool_ref.spool_number = spool.spool_number.unwrap_or(0);
-        spool_ref.valid = true;
+    let mut client = HttpClient::wrap(connection);
 
-        copy_to_c_buf_signed(&spool.id, &mut spool_ref.id);
-        if let Some(ref b) = spool.brand {
-            copy_to_c_buf_signed(b, &mut spool_ref.brand);
-        }
-        if let Some(ref m) = spool.material {
-            copy_to_c_buf_signed(m, &mut spool_ref.material);
-        }
-        if let Some(ref c) = spool.color_name {
-            copy_to_c_buf_signed(c, &mut spool_ref.color_name);
-        }
-        if let Some(ref rgba) = spool.rgba {
-            spool_ref.color_rgba = parse_rgba_hex(rgba);
-        }
+    let api_key = device_auth::api_key();
+    let content_length = body.len().to_string();
+    let headers = device_auth::with_api_key(&[
+        ("Content-Type", "application/json"),
+        ("Content-Length", content_length.as_str()),
+    ], &api_key);
+
+    let mut request = client.request(embedded_svc::http::Method::Post, &url, &headers)
+        .map_err(|e| format!("POST request failed: {:?}", e))?;
+
+    if !body.is_empty() {
+        request.write(body.as_bytes())
+            .map_err(|e| format!("Write failed: {:?}", e))?;
+        request.flush()
+            .map_err(|e| format!("Flush failed: {:?}", e))?;
     }
 
-    count as c_int
+    let response = request.submit()
+        .map_err(|e| format!("Submit failed: {:?}", e))?;
+
+    Ok(response.status())
 }
 
-/// Get count of spools without NFC tags
-#[no_mangle]
-pub extern "C" fn spool_get_untagged_count() -> c_int {
-    let manager = BACKEND_MANAGER.lock().unwrap();
-    let base_url = manager.server_url.clone();
-    drop(manager);
+// =============================================================================
+// Mutations with offline queue
+// =============================================================================
 
-    if base_url.is_empty() {
-        return -1;
-    }
+/// Outcome of a backend mutation
+enum MutationOutcome {
+    /// Backend answered (status code, response body)
+    Sent(u16, String),
+    /// Backend unreachable - recorded in the outbox for replay
+    Queued,
+}
 
-    // GET /api/spools?untagged=true (just count results)
-    let url = format!("{}/api/spools?untagged=true", base_url);
+/// Statuses that mean "try again later" rather than "rejected"
+/// 401/403 are kept too: they are resolved by pairing, not by the request.
+fn is_transient_status(status: u16) -> bool {
+    status >= 500 || status == 401 || status == 403 || status == 408 || status == 429
+}
 
-    let config = HttpConfig {
-        timeout: Some(std::time::Duration::from_millis(HTTP_TIMEOUT_MS)),
-        ..Default::default()
-    };
+/// Send an outbox entry with its idempotency key
+/// Returns Err only for transport failures (backend unreachable)
+fn send_outbox_entry(base_url: &str, entry: &OutboxEntry) -> Result<(u16, String), String> {
+    let url = format!("{}{}", base_url, entry.path);
 
-    let connection = match EspHttpConnection::new(&config) {
-        Ok(c) => c,
-        Err(_) => return -1,
-    };
+    let config = tls_pin::http_config(std::time::Duration::from_millis(HTTP_TIMEOUT_MS));
+
+    let connection = EspHttpConnection::new(&config)
+        .map_err(|e| format!("HTTP connection failed: {:?}", e))?;
 
     let mut client = HttpClient::wrap(connection);
-    let request = match client.get(&url) {
-        Ok(r) => r,
-        Err(_) => return -1,
-    };
 
-    let mut response = match request.submit() {
-        Ok(r) => r,
-        Err(_) => return -1,
-    };
+    let content_length = entry.body.len().to_string();
+    let created_at = entry.created_at.to_string();
+    let api_key = device_auth::api_key();
+    let headers = device_auth::with_api_key(&[
+        ("Content-Type", "application/json"),
+        ("Content-Length", content_length.as_str()),
+        ("Idempotency-Key", entry.key.as_str()),
+        ("X-Queued-At", created_at.as_str()),
+    ], &api_key);
 
-    if response.status() != 200 {
-        return -1;
-    }
+    let mut request = client.request(entry.method.to_http(), &url, &headers)
+        .map_err(|e| format!("Request failed: {:?}", e))?;
 
-    let mut buf = vec![0u8; 8192];
+    request.write(entry.body.as_bytes())
+        .map_err(|e| format!("Write failed: {:?}", e))?;
+    request.flush()
+        .map_err(|e| format!("Flush failed: {:?}", e))?;
+
+    let mut response = request.submit()
+        .map_err(|e| format!("Submit failed: {:?}", e))?;
+
+    let status = response.status();
+    note_auth_status(status);
+
+    // Read response body
+    let mut buf = vec![0u8; 2048];
     let mut total = 0;
     loop {
         match response.read(&mut buf[total..]) {
@@ -2183,355 +2786,473 @@ pub extern "C" fn spool_get_untagged_count() -> c_int {
         }
     }
 
-    if total == 0 {
-        return 0;
-    }
-
-    let body = String::from_utf8_lossy(&buf[..total]);
-    let spools: Vec<serde_json::Value> = match serde_json::from_str(&body) {
-        Ok(s) => s,
-        Err(_) => return -1,
-    };
-
-    spools.len() as c_int
+    Ok((status, String::from_utf8_lossy(&buf[..total]).into_owned()))
 }
 
-/// Link an NFC tag to an existing spool
-/// Returns: 0 = success, -1 = connection error, or HTTP status code on failure (e.g., 409 = already assigned)
-#[no_mangle]
-pub extern "C" fn spool_link_tag(
-    spool_id: *const c_char,
-    tag_id: *const c_char,
-    tag_type: *const c_char,
-) -> c_int {
-    if spool_id.is_null() || tag_id.is_null() {
-        return -1;
-    }
+/// Send a mutation, falling back to the outbox when the backend is unreachable
+/// While older mutations are still queued, new ones are queued behind them so
+/// the backend sees them in the order the user made them.
+fn submit_mutation(
+    kind: &str,
+    method: Method,
+    path: String,
+    body: String,
+    coalesce: Option<String>,
+) -> MutationOutcome {
+    let entry = outbox::new_entry(kind, method, path, body, coalesce);
 
-    fn c_str_to_string(ptr: *const c_char) -> String {
-        if ptr.is_null() {
-            String::new()
-        } else {
-            unsafe {
-                std::ffi::CStr::from_ptr(ptr)
-                    .to_str()
-                    .unwrap_or("")
-                    .to_string()
+    if let Some(base_url) = get_server_url() {
+        if outbox::len() == 0 {
+            match send_outbox_entry(&base_url, &entry) {
+                Ok((status, response)) if !is_transient_status(status) => {
+                    return MutationOutcome::Sent(status, response);
+                }
+                Ok((status, _)) => warn!("{}: server returned {}, queueing", kind, status),
+                Err(e) => warn!("{}: {}, queueing", kind, e),
             }
         }
     }
 
-    let spool_id_str = c_str_to_string(spool_id);
-    let tag_id_str = c_str_to_string(tag_id);
-    let tag_type_str = c_str_to_string(tag_type);
-
-    let manager = BACKEND_MANAGER.lock().unwrap();
-    let base_url = manager.server_url.clone();
-    drop(manager);
+    outbox::push(entry);
+    MutationOutcome::Queued
+}
 
-    if base_url.is_empty() {
-        return -1;
+/// Replay queued mutations in order
+/// Stops at the first transport failure; permanently rejected entries are dropped.
+pub fn flush_outbox(base_url: &str) {
+    while let Some(entry) = outbox::front() {
+        match send_outbox_entry(base_url, &entry) {
+            Ok((status, _)) if is_transient_status(status) => {
+                warn!("Outbox: {} got status {}, retrying later", entry.kind, status);
+                break;
+            }
+            Ok((status, _)) if (200..300).contains(&status) => {
+                info!("Outbox: replayed {} {} ({})", entry.kind, entry.path, entry.key);
+                outbox::remove(entry.seq);
+            }
+            Ok((status, response)) => {
+                warn!("Outbox: {} {} rejected with status {}, dropping: {}",
+                      entry.kind, entry.path, status, response);
+                outbox::remove(entry.seq);
+            }
+            Err(e) => {
+                warn!("Outbox: backend unreachable ({}), {} pending", e, outbox::len());
+                break;
+            }
+        }
     }
+}
 
-    // PATCH /api/spools/{spool_id}/link-tag
-    let url = format!("{}/api/spools/{}/link-tag", base_url, spool_id_str);
-
-    let body = format!(
-        r#"{{"tag_id":"{}","tag_type":"{}"}}"#,
-        tag_id_str, tag_type_str
-    );
-
-    info!("spool_link_tag: PATCH {} with {}", url, body);
+/// Get number of mutations waiting in the offline queue
+#[no_mangle]
+pub extern "C" fn backend_get_outbox_count() -> c_int {
+    outbox::len() as c_int
+}
 
-    let config = HttpConfig {
-        timeout: Some(std::time::Duration::from_millis(HTTP_TIMEOUT_MS)),
-        ..Default::default()
-    };
+/// Inventory mutations started from the UI (sent through `submit_mutation`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mutation {
+    AddSpool,
+    LinkTag,
+    SyncWeight,
+    AssignTray,
+}
 
-    let connection = match EspHttpConnection::new(&config) {
-        Ok(c) => c,
-        Err(e) => {
-            warn!("Failed to create HTTP connection: {:?}", e);
-            return -1;
+impl Mutation {
+    /// Outbox kind (shown in logs and replay)
+    fn kind(self) -> &'static str {
+        match self {
+            Mutation::AddSpool => "add_spool",
+            Mutation::LinkTag => "link_tag",
+            Mutation::SyncWeight => "sync_weight",
+            Mutation::AssignTray => "assign_tray",
         }
-    };
-
-    let mut client = HttpClient::wrap(connection);
-
-    let headers = [
-        ("Content-Type", "application/json"),
-        ("Content-Length", &body.len().to_string()),
-    ];
+    }
 
-    let mut request = match client.request(embedded_svc::http::Method::Patch, &url, &headers) {
-        Ok(r) => r,
-        Err(e) => {
-            warn!("Failed to create PATCH request: {:?}", e);
-            return -1;
+    fn method(self) -> Method {
+        match self {
+            Mutation::AddSpool | Mutation::AssignTray => Method::Post,
+            Mutation::LinkTag => Method::Patch,
+            Mutation::SyncWeight => Method::Put,
         }
-    };
-
-    if let Err(e) = request.write(body.as_bytes()) {
-        warn!("Failed to write request body: {:?}", e);
-        return -1;
     }
+}
 
-    if let Err(e) = request.flush() {
-        warn!("Failed to flush request: {:?}", e);
-        return -1;
+static ADD_SPOOL: Mutex<Request<()>> = Mutex::new(Request::new());
+/// 0 = linked, 1 = queued offline, or the HTTP status of the refusal
+static LINK_TAG: Mutex<Request<c_int>> = Mutex::new(Request::new());
+static SYNC_WEIGHT: Mutex<Request<()>> = Mutex::new(Request::new());
+/// AssignResult code
+static ASSIGN_TRAY: Mutex<Request<c_int>> = Mutex::new(Request::new());
+
+/// Send a mutation started from the UI (backend worker only - blocks on HTTP)
+/// Runs in order with the outbox replay, which also happens on the worker.
+pub fn run_mutation(seq: u32, mutation: Mutation, path: String, body: String, coalesce: Option<String>) {
+    let kind = mutation.kind();
+    let outcome = submit_mutation(kind, mutation.method(), path, body, coalesce);
+    match &outcome {
+        MutationOutcome::Sent(status, _) => info!("{}: status {}", kind, status),
+        MutationOutcome::Queued => info!("{}: queued for later", kind),
     }
 
-    let response = match request.submit() {
-        Ok(r) => r,
-        Err(e) => {
-            warn!("Failed to submit request: {:?}", e);
-            return -1;
+    match mutation {
+        Mutation::AddSpool => {
+            let result = match outcome {
+                MutationOutcome::Sent(200 | 201, _) | MutationOutcome::Queued => Ok(()),
+                MutationOutcome::Sent(status, _) => Err(format!("status {}", status)),
+            };
+            finish_request(&ADD_SPOOL, seq, kind, result);
         }
-    };
-
-    let status = response.status();
-    if status != 200 {
-        warn!("spool_link_tag failed with status {}", status);
-        return status as c_int;
+        Mutation::LinkTag => {
+            let result = match outcome {
+                MutationOutcome::Sent(200, _) => 0,
+                MutationOutcome::Sent(status, _) => status as c_int,
+                MutationOutcome::Queued => 1,
+            };
+            finish_request(&LINK_TAG, seq, kind, Ok(result));
+        }
+        Mutation::SyncWeight => {
+            let result = match outcome {
+                MutationOutcome::Sent(200, _) | MutationOutcome::Queued => Ok(()),
+                MutationOutcome::Sent(status, _) => Err(format!("status {}", status)),
+            };
+            finish_request(&SYNC_WEIGHT, seq, kind, result);
+        }
+        Mutation::AssignTray => finish_request(&ASSIGN_TRAY, seq, kind, Ok(assign_result(outcome))),
     }
-
-    info!("spool_link_tag: success");
-    0
 }
 
-/// Sync spool weight to backend
-#[no_mangle]
-pub extern "C" fn spool_sync_weight(
-    spool_id: *const c_char,
-    weight: c_int,
-) -> bool {
-    if spool_id.is_null() {
-        return false;
-    }
-
-    let spool_id_str = unsafe {
-        std::ffi::CStr::from_ptr(spool_id)
-            .to_str()
-            .unwrap_or("")
-            .to_string()
+/// AssignResult for the outcome of an assign request
+/// 0 = Error, 1 = Configured, 2 = Staged, 4 = Queued (offline)
+fn assign_result(outcome: MutationOutcome) -> c_int {
+    let (status, response_body) = match outcome {
+        MutationOutcome::Sent(status, response_body) => (status, response_body),
+        MutationOutcome::Queued => return 4,
     };
 
-    if spool_id_str.is_empty() {
-        return false;
+    if status != 200 && status != 201 {
+        warn!("Assign failed with status {}", status);
+        return 0;
     }
 
-    let manager = BACKEND_MANAGER.lock().unwrap();
-    let base_url = manager.server_url.clone();
-    drop(manager);
-
-    if base_url.is_empty() {
-        return false;
+    // Parse response
+    if let Ok(resp) = serde_json::from_str::<ApiAssignResponse>(&response_body) {
+        match resp.status.as_deref() {
+            Some("configured") => {
+                info!("Assign result: configured");
+                return 1;
+            }
+            Some("staged") => {
+                info!("Assign result: staged");
+                return 2;
+            }
+            _ => {}
+        }
     }
 
-    // PUT /api/spools/{spool_id}
-    let url = format!("{}/api/spools/{}", base_url, spool_id_str);
-
-    let body = format!(r#"{{"weight_current":{}}}"#, weight);
-
-    info!("spool_sync_weight: PUT {} with {}", url, body);
-
-    let config = HttpConfig {
-        timeout: Some(std::time::Duration::from_millis(HTTP_TIMEOUT_MS)),
-        ..Default::default()
-    };
+    // Default to configured if status was OK
+    info!("Assign result: assuming configured (status {})", status);
+    1
+}
 
-    let connection = match EspHttpConnection::new(&config) {
-        Ok(c) => c,
-        Err(e) => {
-            warn!("Failed to create HTTP connection: {:?}", e);
-            return false;
-        }
-    };
+/// Start adding a new spool to inventory (non-blocking)
+/// Returns 0 if submitted, -1 if the worker isn't running
+#[no_mangle]
+pub extern "C" fn spool_add_to_inventory_start(
+    tag_id: *const c_char,
+    vendor: *const c_char,
+    material: *const c_char,
+    subtype: *const c_char,
+    color_name: *const c_char,
+    color_rgba: u32,
+    label_weight: c_int,
+    weight_current: c_int,
+    data_origin: *const c_char,
+    tag_type: *const c_char,
+    slicer_filament: *const c_char,
+) -> c_int {
+    let tag_id_str = c_str_to_string(tag_id);
+    let vendor_str = c_str_to_string(vendor);
+    let material_str = c_str_to_string(material);
+    let subtype_str = c_str_to_string(subtype);
+    let color_name_str = c_str_to_string(color_name);
+    let data_origin_str = c_str_to_string(data_origin);
+    let tag_type_str = c_str_to_string(tag_type);
+    let slicer_filament_str = c_str_to_string(slicer_filament);
 
-    let mut client = HttpClient::wrap(connection);
+    // Convert RGBA to hex string
+    let rgba_hex = format!("{:08X}", color_rgba);
 
-    let headers = [
-        ("Content-Type", "application/json"),
-        ("Content-Length", &body.len().to_string()),
-    ];
+    // POST /api/spools
+    let body = format!(
+        r#"{{"tag_id":"{}","brand":"{}","material":"{}","subtype":"{}","color_name":"{}","rgba":"{}","label_weight":{},"weight_current":{},"data_origin":"{}","tag_type":"{}","slicer_filament":"{}"}}"#,
+        tag_id_str, vendor_str, material_str, subtype_str, color_name_str,
+        rgba_hex, label_weight, weight_current, data_origin_str, tag_type_str, slicer_filament_str
+    );
 
-    let mut request = match client.request(embedded_svc::http::Method::Put, &url, &headers) {
-        Ok(r) => r,
-        Err(e) => {
-            warn!("Failed to create PUT request: {:?}", e);
-            return false;
-        }
-    };
+    info!("spool_add_to_inventory: POST /api/spools with {}", body);
 
-    if let Err(e) = request.write(body.as_bytes()) {
-        warn!("Failed to write request body: {:?}", e);
-        return false;
-    }
+    // Tag now belongs to a spool - next lookup must not use the cached result
+    invalidate_spool_lookup(&tag_id_str);
 
-    if let Err(e) = request.flush() {
-        warn!("Failed to flush request: {:?}", e);
-        return false;
+    start_request(&ADD_SPOOL, |seq| Job::Mutation {
+        seq,
+        mutation: Mutation::AddSpool,
+        path: "/api/spools".to_string(),
+        body,
+        coalesce: None,
+    })
+}
+
+/// Get the state of the add spool request (`BackendRequestState`)
+/// Done once added, or queued offline for later replay
+#[no_mangle]
+pub extern "C" fn spool_add_to_inventory_poll() -> c_int {
+    poll_request(&ADD_SPOOL, |_| {})
+}
+
+/// Untagged spool info for FFI
+#[repr(C)]
+pub struct UntaggedSpoolInfo {
+    pub id: [c_char; 64],
+    pub brand: [c_char; 32],
+    pub material: [c_char; 32],
+    pub color_name: [c_char; 32],
+    pub color_rgba: u32,
+    pub label_weight: i32,
+    pub spool_number: i32,
+    pub valid: bool,
+}
+
+/// API response for untagged spools
+#[derive(Debug, Clone, Deserialize, Default)]
+struct ApiUntaggedSpool {
+    id: String,
+    brand: Option<String>,
+    material: Option<String>,
+    color_name: Option<String>,
+    rgba: Option<String>,
+    label_weight: Option<i32>,
+    spool_number: Option<i32>,
+}
+
+/// Spools without a tag, for linking a tag to one
+static UNTAGGED_SPOOLS: Mutex<Request<Vec<ApiUntaggedSpool>>> = Mutex::new(Request::new());
+
+/// Start fetching the spools without NFC tags assigned (non-blocking)
+/// Returns 0 if submitted, -1 if the worker isn't running
+#[no_mangle]
+pub extern "C" fn spool_untagged_list_start() -> c_int {
+    start_request(&UNTAGGED_SPOOLS, |seq| Job::UntaggedSpools { seq })
+}
+
+/// Get the state of the untagged spool list request (`BackendRequestState`)
+/// When done, fills up to max_count spools and sets count
+#[no_mangle]
+pub extern "C" fn spool_untagged_list_poll(
+    spools: *mut UntaggedSpoolInfo,
+    max_count: c_int,
+    count: *mut c_int,
+) -> c_int {
+    if spools.is_null() || count.is_null() || max_count <= 0 {
+        return REQUEST_NONE;
     }
 
-    let response = match request.submit() {
-        Ok(r) => r,
-        Err(e) => {
-            warn!("Failed to submit request: {:?}", e);
-            return false;
+    poll_request(&UNTAGGED_SPOOLS, |api_spools| {
+        let n = api_spools.len().min(max_count as usize);
+
+        for (i, spool) in api_spools.iter().take(n).enumerate() {
+            let spool_ref = unsafe { &mut *spools.add(i) };
+
+            spool_ref.id = [0; 64];
+            spool_ref.brand = [0; 32];
+            spool_ref.material = [0; 32];
+            spool_ref.color_name = [0; 32];
+            spool_ref.color_rgba = 0;
+            spool_ref.label_weight = spool.label_weight.unwrap_or(0);
+            spool_ref.spool_number = spool.spool_number.unwrap_or(0);
+            spool_ref.valid = true;
+
+            copy_to_c_buf_signed(&spool.id, &mut spool_ref.id);
+            if let Some(ref b) = spool.brand {
+                copy_to_c_buf_signed(b, &mut spool_ref.brand);
+            }
+            if let Some(ref m) = spool.material {
+                copy_to_c_buf_signed(m, &mut spool_ref.material);
+            }
+            if let Some(ref c) = spool.color_name {
+                copy_to_c_buf_signed(c, &mut spool_ref.color_name);
+            }
+            if let Some(ref rgba) = spool.rgba {
+                spool_ref.color_rgba = parse_rgba_hex(rgba);
+            }
         }
-    };
 
-    let status = response.status();
+        unsafe { *count = n as c_int };
+    })
+}
+
+/// Fetch the untagged spool list (backend worker only - blocks on HTTP)
+pub fn run_untagged_spools(seq: u32) {
+    let result = fetch_untagged_spools();
+    if let Ok(spools) = &result {
+        UNTAGGED_COUNT.store(spools.len() as i32, std::sync::atomic::Ordering::Relaxed);
+    }
+    finish_request(&UNTAGGED_SPOOLS, seq, "Untagged spool list", result);
+}
+
+/// GET /api/spools?untagged=true
+fn fetch_untagged_spools() -> Result<Vec<ApiUntaggedSpool>, String> {
+    let (status, body) = api_get("/api/spools?untagged=true", 8192)?;
     if status != 200 {
-        warn!("spool_sync_weight failed with status {}", status);
-        return false;
+        return Err(format!("HTTP error: {}", status));
+    }
+    if body.is_empty() {
+        return Ok(Vec::new());
     }
 
-    info!("spool_sync_weight: success");
-    true
+    serde_json::from_slice(&body).map_err(|e| format!("JSON parse error: {:?}", e))
 }
 
-/// Assign result enum (matches simulator)
-/// 0 = Error, 1 = Configured, 2 = Staged, 3 = StagedReplace
+/// Get count of spools without NFC tags (non-blocking)
+/// Returns the count fetched by the last tag lookup, -1 if unknown
 #[no_mangle]
-pub extern "C" fn backend_assign_spool_to_tray(
-    printer_serial: *const c_char,
-    ams_id: c_int,
-    tray_id: c_int,
+pub extern "C" fn spool_get_untagged_count() -> c_int {
+    UNTAGGED_COUNT.load(std::sync::atomic::Ordering::Relaxed) as c_int
+}
+
+/// Start linking an NFC tag to an existing spool (non-blocking)
+/// Returns 0 if submitted, -1 on invalid arguments or if the worker isn't running
+#[no_mangle]
+pub extern "C" fn spool_link_tag_start(
     spool_id: *const c_char,
+    tag_id: *const c_char,
+    tag_type: *const c_char,
 ) -> c_int {
-    if printer_serial.is_null() || spool_id.is_null() {
-        return 0; // Error
+    let spool_id_str = c_str_to_string(spool_id);
+    let tag_id_str = c_str_to_string(tag_id);
+    let tag_type_str = c_str_to_string(tag_type);
+
+    if spool_id_str.is_empty() || tag_id_str.is_empty() {
+        return -1;
     }
 
-    let printer_serial_str = unsafe {
-        match std::ffi::CStr::from_ptr(printer_serial).to_str() {
-            Ok(s) => s,
-            Err(_) => return 0,
-        }
-    };
+    // PATCH /api/spools/{spool_id}/link-tag
+    let path = format!("/api/spools/{}/link-tag", spool_id_str);
 
-    let spool_id_str = unsafe {
-        match std::ffi::CStr::from_ptr(spool_id).to_str() {
-            Ok(s) => s,
-            Err(_) => return 0,
-        }
-    };
+    let body = format!(
+        r#"{{"tag_id":"{}","tag_type":"{}"}}"#,
+        tag_id_str, tag_type_str
+    );
 
-    // Get backend URL
-    let manager = BACKEND_MANAGER.lock().unwrap();
-    let base_url = manager.server_url.clone();
-    drop(manager);
+    info!("spool_link_tag: PATCH {} with {}", path, body);
 
-    if base_url.is_empty() {
-        return 0;
-    }
+    invalidate_spool_lookup(&tag_id_str);
 
-    // POST /api/printers/{serial}/ams/{ams_id}/tray/{tray_id}/assign
-    let url = format!(
-        "{}/api/printers/{}/ams/{}/tray/{}/assign",
-        base_url, printer_serial_str, ams_id, tray_id
-    );
+    start_request(&LINK_TAG, |seq| Job::Mutation {
+        seq,
+        mutation: Mutation::LinkTag,
+        path,
+        body,
+        coalesce: None,
+    })
+}
 
-    // Build JSON body
-    let body = format!(r#"{{"spool_id":"{}"}}"#, spool_id_str);
+/// Get the state of the link tag request (`BackendRequestState`)
+/// When done, result is 0 = linked, 1 = queued offline, or the HTTP status
+/// code of the refusal (e.g., 409 = already assigned)
+#[no_mangle]
+pub extern "C" fn spool_link_tag_poll(result: *mut c_int) -> c_int {
+    if result.is_null() {
+        return REQUEST_NONE;
+    }
 
-    info!("backend_assign_spool_to_tray: POST {} with {}", url, body);
+    poll_request(&LINK_TAG, |code| unsafe { *result = *code })
+}
 
-    let config = HttpConfig {
-        timeout: Some(std::time::Duration::from_millis(HTTP_TIMEOUT_MS)),
-        ..Default::default()
-    };
+/// Start syncing a spool weight to the backend (non-blocking)
+/// Returns 0 if submitted, -1 on invalid arguments or if the worker isn't running
+#[no_mangle]
+pub extern "C" fn spool_sync_weight_start(
+    spool_id: *const c_char,
+    weight: c_int,
+) -> c_int {
+    let spool_id_str = c_str_to_string(spool_id);
+    if spool_id_str.is_empty() {
+        return -1;
+    }
 
-    let connection = match EspHttpConnection::new(&config) {
-        Ok(c) => c,
-        Err(e) => {
-            warn!("Failed to create HTTP connection: {:?}", e);
-            return 0;
-        }
-    };
+    // PUT /api/spools/{spool_id}
+    let path = format!("/api/spools/{}", spool_id_str);
 
-    let mut client = HttpClient::wrap(connection);
+    let body = format!(r#"{{"weight_current":{}}}"#, weight);
 
-    // POST request with JSON body
-    // Use request() method with headers that include Content-Length
-    let headers = [
-        ("Content-Type", "application/json"),
-        ("Content-Length", &body.len().to_string()),
-    ];
+    info!("spool_sync_weight: PUT {} with {}", path, body);
 
-    let mut request = match client.request(embedded_svc::http::Method::Post, &url, &headers) {
-        Ok(r) => r,
-        Err(e) => {
-            warn!("Failed to create POST request: {:?}", e);
-            return 0;
-        }
-    };
+    // Only the latest weight of a spool matters, so queued syncs replace each other
+    let coalesce = Some(format!("weight:{}", spool_id_str));
+    start_request(&SYNC_WEIGHT, |seq| Job::Mutation {
+        seq,
+        mutation: Mutation::SyncWeight,
+        path,
+        body,
+        coalesce,
+    })
+}
 
-    // Write body
-    if let Err(e) = request.write(body.as_bytes()) {
-        warn!("Failed to write request body: {:?}", e);
-        return 0;
-    }
+/// Get the state of the weight sync request (`BackendRequestState`)
+/// Done once synced, or queued offline for later replay
+#[no_mangle]
+pub extern "C" fn spool_sync_weight_poll() -> c_int {
+    poll_request(&SYNC_WEIGHT, |_| {})
+}
 
-    // Flush to ensure body is sent
-    if let Err(e) = request.flush() {
-        warn!("Failed to flush request: {:?}", e);
-        return 0;
+/// Start assigning a spool to an AMS tray (non-blocking)
+/// Returns 0 if submitted, -1 on invalid arguments or if the worker isn't running
+#[no_mangle]
+pub extern "C" fn backend_assign_spool_to_tray_start(
+    printer_serial: *const c_char,
+    ams_id: c_int,
+    tray_id: c_int,
+    spool_id: *const c_char,
+) -> c_int {
+    let printer_serial_str = c_str_to_string(printer_serial);
+    let spool_id_str = c_str_to_string(spool_id);
+
+    if printer_serial_str.is_empty() || spool_id_str.is_empty() {
+        return -1;
     }
 
-    let mut response = match request.submit() {
-        Ok(r) => r,
-        Err(e) => {
-            warn!("Failed to submit request: {:?}", e);
-            return 0;
-        }
-    };
+    // POST /api/printers/{serial}/ams/{ams_id}/tray/{tray_id}/assign
+    let path = format!(
+        "/api/printers/{}/ams/{}/tray/{}/assign",
+        printer_serial_str, ams_id, tray_id
+    );
 
-    let status = response.status();
+    // Build JSON body
+    let body = format!(r#"{{"spool_id":"{}"}}"#, spool_id_str);
 
-    // Read response body
-    let mut buf = vec![0u8; 1024];
-    let mut total = 0;
-    loop {
-        match response.read(&mut buf[total..]) {
-            Ok(0) => break,
-            Ok(n) => total += n,
-            Err(_) => break,
-        }
-        if total >= buf.len() {
-            break;
-        }
-    }
+    info!("backend_assign_spool_to_tray: POST {} with {}", path, body);
 
-    if status != 200 && status != 201 {
-        warn!("Assign failed with status {}", status);
-        return 0;
-    }
+    start_request(&ASSIGN_TRAY, |seq| Job::Mutation {
+        seq,
+        mutation: Mutation::AssignTray,
+        path,
+        body,
+        coalesce: None,
+    })
+}
 
-    // Parse response
-    if total > 0 {
-        let body = String::from_utf8_lossy(&buf[..total]);
-        if let Ok(resp) = serde_json::from_str::<ApiAssignResponse>(&body) {
-            if let Some(ref s) = resp.status {
-                match s.as_str() {
-                    "configured" => {
-                        info!("Assign result: configured");
-                        return 1;
-                    }
-                    "staged" => {
-                        info!("Assign result: staged");
-                        return 2;
-                    }
-                    _ => {}
-                }
-            }
-        }
+/// Get the state of the assign request (`BackendRequestState`)
+/// When done, result is the AssignResult (matches simulator):
+/// 0 = Error, 1 = Configured, 2 = Staged, 3 = StagedReplace, 4 = Queued (offline)
+#[no_mangle]
+pub extern "C" fn backend_assign_spool_to_tray_poll(result: *mut c_int) -> c_int {
+    if result.is_null() {
+        return REQUEST_NONE;
     }
 
-    // Default to configured if status was OK
-    info!("Assign result: assuming configured (status {})", status);
-    1
+    poll_request(&ASSIGN_TRAY, |code| unsafe { *result = *code })
 }
 
 // =============================================================================
@@ -2636,376 +3357,388 @@ pub struct ColorCatalogEntry {
     pub material: [c_char; 32],
 }
 
-/// Get slicer filament presets from Bambu Cloud (via backend)
-/// Returns number of presets found (up to max_count), -1 on error
+/// AMS slot commands sent from the Configure Slot modal
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SlotCommand {
+    Filament,
+    Calibration,
+    Reset,
+}
+
+impl SlotCommand {
+    fn name(self) -> &'static str {
+        match self {
+            SlotCommand::Filament => "set_slot_filament",
+            SlotCommand::Calibration => "set_slot_calibration",
+            SlotCommand::Reset => "reset_slot",
+        }
+    }
+}
+
+static SLICER_PRESETS: Mutex<Request<Vec<ApiSlicerPreset>>> = Mutex::new(Request::new());
+static PRESET_DETAIL: Mutex<Request<ApiPresetDetail>> = Mutex::new(Request::new());
+static K_PROFILES: Mutex<Request<Vec<ApiKProfileInfo>>> = Mutex::new(Request::new());
+static CATALOG_COLORS: Mutex<Request<Vec<ApiColorEntry>>> = Mutex::new(Request::new());
+static SLOT_FILAMENT: Mutex<Request<()>> = Mutex::new(Request::new());
+static SLOT_CALIBRATION: Mutex<Request<()>> = Mutex::new(Request::new());
+static SLOT_RESET: Mutex<Request<()>> = Mutex::new(Request::new());
+
+/// Start fetching the slicer filament presets from Bambu Cloud (via backend)
+/// Returns 0 if submitted, -1 if the worker isn't running
+#[no_mangle]
+pub extern "C" fn backend_slicer_presets_start() -> c_int {
+    start_request(&SLICER_PRESETS, |seq| Job::SlicerPresets { seq })
+}
+
+/// Get the state of the slicer presets request (`BackendRequestState`)
+/// When done, fills up to max_count presets and sets count (0 if the backend
+/// isn't connected or not signed in to the cloud)
 #[no_mangle]
-pub extern "C" fn backend_get_slicer_presets(
+pub extern "C" fn backend_slicer_presets_poll(
     presets: *mut SlicerPreset,
     max_count: c_int,
+    count: *mut c_int,
 ) -> c_int {
-    info!("backend_get_slicer_presets called");
-
-    if presets.is_null() || max_count <= 0 {
-        return -1;
+    if presets.is_null() || count.is_null() || max_count <= 0 {
+        return REQUEST_NONE;
     }
 
-    let manager = BACKEND_MANAGER.lock().unwrap();
-    let base_url = manager.server_url.clone();
-    let is_connected = matches!(manager.state, BackendState::Connected { .. });
-    drop(manager);
+    poll_request(&SLICER_PRESETS, |filaments| {
+        let n = filaments.len().min(max_count as usize);
 
-    if base_url.is_empty() {
-        info!("backend_get_slicer_presets: no server URL configured");
-        return 0;  // Return 0 presets instead of -1 if not configured
-    }
+        for (i, preset) in filaments.iter().take(n).enumerate() {
+            let preset_ref = unsafe { &mut *presets.add(i) };
 
-    if !is_connected {
-        info!("backend_get_slicer_presets: backend not connected, skipping");
-        return 0;  // Don't block UI if backend not connected
-    }
+            // Initialize with zeros
+            preset_ref.setting_id = [0; 64];
+            preset_ref.name = [0; 64];
+            preset_ref.preset_type = [0; 16];
+            preset_ref.is_custom = preset.is_custom.unwrap_or(false);
 
-    // GET /api/cloud/settings
-    let url = format!("{}/api/cloud/settings", base_url);
+            // Copy strings
+            copy_to_c_buf_signed(&preset.setting_id, &mut preset_ref.setting_id);
+            copy_to_c_buf_signed(&preset.name, &mut preset_ref.name);
+            if let Some(ref t) = preset.preset_type {
+                copy_to_c_buf_signed(t, &mut preset_ref.preset_type);
+            }
+        }
 
-    let config = HttpConfig {
-        timeout: Some(std::time::Duration::from_millis(HTTP_TIMEOUT_MS)),
-        ..Default::default()
-    };
+        unsafe { *count = n as c_int };
+    })
+}
 
-    let connection = match EspHttpConnection::new(&config) {
-        Ok(c) => c,
-        Err(_) => return -1,
-    };
+/// Fetch the slicer presets (backend worker only - blocks on HTTP)
+pub fn run_slicer_presets(seq: u32) {
+    let result = fetch_slicer_presets();
+    if let Ok(filaments) = &result {
+        info!("Slicer presets: {} filament presets", filaments.len());
+    }
+    finish_request(&SLICER_PRESETS, seq, "Slicer presets", result);
+}
 
-    let mut client = HttpClient::wrap(connection);
-    let request = match client.get(&url) {
-        Ok(r) => r,
-        Err(_) => return -1,
-    };
+/// GET /api/cloud/settings (filament presets only)
+fn fetch_slicer_presets() -> Result<Vec<ApiSlicerPreset>, String> {
+    if backend_is_connected() == 0 {
+        info!("Slicer presets: backend not connected, skipping");
+        return Ok(Vec::new());
+    }
 
-    let mut response = match request.submit() {
-        Ok(r) => r,
-        Err(_) => return -1,
-    };
+    // 256KB buffer for presets list
+    let (status, body) = api_get("/api/cloud/settings", 262144)?;
+    // 401 here means not signed in to the cloud - no presets
+    if status == 401 {
+        return Ok(Vec::new());
+    }
+    if status != 200 {
+        return Err(format!("HTTP error: {}", status));
+    }
+    if body.is_empty() {
+        return Ok(Vec::new());
+    }
 
-    if response.status() != 200 {
-        // Might be 401 (not authenticated) - return 0 presets
-        if response.status() == 401 {
-            return 0;
-        }
+    let settings: ApiSlicerSettingsResponse = serde_json::from_slice(&body)
+        .map_err(|e| format!("JSON parse error: {:?}", e))?;
+    Ok(settings.filament.unwrap_or_default())
+}
+
+/// Start fetching the detail (filament_id and base_id) of a preset (non-blocking)
+/// Returns 0 if submitted, -1 on invalid arguments or if the worker isn't running
+#[no_mangle]
+pub extern "C" fn backend_preset_detail_start(setting_id: *const c_char) -> c_int {
+    let setting_id = c_str_to_string(setting_id);
+    if setting_id.is_empty() {
         return -1;
     }
 
-    // Read response body
-    let mut buf = vec![0u8; 262144]; // 256KB buffer for presets list
-    let mut total = 0;
-    loop {
-        match response.read(&mut buf[total..]) {
-            Ok(0) => break,
-            Ok(n) => total += n,
-            Err(_) => break,
-        }
-        if total >= buf.len() {
-            break;
-        }
-    }
+    start_request(&PRESET_DETAIL, |seq| Job::PresetDetail { seq, setting_id })
+}
 
-    if total == 0 {
-        return 0;
+/// Get the state of the preset detail request (`BackendRequestState`)
+/// When done, fills detail
+#[no_mangle]
+pub extern "C" fn backend_preset_detail_poll(detail: *mut PresetDetail) -> c_int {
+    if detail.is_null() {
+        return REQUEST_NONE;
     }
 
-    // Parse response
-    let body = String::from_utf8_lossy(&buf[..total]);
-    let settings: ApiSlicerSettingsResponse = match serde_json::from_str(&body) {
-        Ok(s) => s,
-        Err(e) => {
-            warn!("Failed to parse slicer presets: {:?}", e);
-            return -1;
+    poll_request(&PRESET_DETAIL, |api_detail| {
+        let detail_ref = unsafe { &mut *detail };
+        detail_ref.filament_id = [0; 64];
+        detail_ref.base_id = [0; 64];
+        detail_ref.has_filament_id = false;
+        detail_ref.has_base_id = false;
+
+        // Check top-level first, then nested setting object
+        if let Some(ref fid) = api_detail.filament_id {
+            copy_to_c_buf_signed(fid, &mut detail_ref.filament_id);
+            detail_ref.has_filament_id = true;
+        } else if let Some(ref setting) = api_detail.setting {
+            if let Some(ref fid) = setting.filament_id {
+                copy_to_c_buf_signed(fid, &mut detail_ref.filament_id);
+                detail_ref.has_filament_id = true;
+            }
         }
-    };
 
-    // Extract filament presets only
-    let filaments = settings.filament.unwrap_or_default();
-    let count = filaments.len().min(max_count as usize);
+        if let Some(ref bid) = api_detail.base_id {
+            copy_to_c_buf_signed(bid, &mut detail_ref.base_id);
+            detail_ref.has_base_id = true;
+        } else if let Some(ref setting) = api_detail.setting {
+            if let Some(ref bid) = setting.base_id {
+                copy_to_c_buf_signed(bid, &mut detail_ref.base_id);
+                detail_ref.has_base_id = true;
+            }
+        }
+    })
+}
 
-    for (i, preset) in filaments.iter().take(count).enumerate() {
-        let preset_ref = unsafe { &mut *presets.add(i) };
+/// Fetch a preset detail (backend worker only - blocks on HTTP)
+pub fn run_preset_detail(seq: u32, setting_id: &str) {
+    let result = fetch_preset_detail(setting_id);
+    finish_request(&PRESET_DETAIL, seq, "Preset detail", result);
+}
 
-        // Initialize with zeros
-        preset_ref.setting_id = [0; 64];
-        preset_ref.name = [0; 64];
-        preset_ref.preset_type = [0; 16];
-        preset_ref.is_custom = preset.is_custom.unwrap_or(false);
+/// GET /api/cloud/settings/{setting_id}
+fn fetch_preset_detail(setting_id: &str) -> Result<ApiPresetDetail, String> {
+    if backend_is_connected() == 0 {
+        return Err("backend not connected".to_string());
+    }
 
-        // Copy strings
-        copy_to_c_buf_signed(&preset.setting_id, &mut preset_ref.setting_id);
-        copy_to_c_buf_signed(&preset.name, &mut preset_ref.name);
-        if let Some(ref t) = preset.preset_type {
-            copy_to_c_buf_signed(t, &mut preset_ref.preset_type);
-        }
+    let (status, body) = api_get(&format!("/api/cloud/settings/{}", setting_id), 4096)?;
+    if status != 200 {
+        return Err(format!("HTTP error: {}", status));
     }
 
-    info!("backend_get_slicer_presets: returning {} presets", count);
-    count as c_int
+    serde_json::from_slice(&body).map_err(|e| format!("JSON parse error: {:?}", e))
 }
 
-/// Get detailed preset info including filament_id and base_id
-/// Returns true on success, false on failure
+/// Start fetching the K-profiles (calibration profiles) of a printer (non-blocking)
+/// Returns 0 if submitted, -1 on invalid arguments or if the worker isn't running
 #[no_mangle]
-pub extern "C" fn backend_get_preset_detail(
-    setting_id: *const c_char,
-    detail: *mut PresetDetail,
-) -> bool {
-    if setting_id.is_null() || detail.is_null() {
-        return false;
+pub extern "C" fn backend_k_profiles_start(
+    printer_serial: *const c_char,
+    nozzle_diameter: *const c_char,
+) -> c_int {
+    let printer_serial = c_str_to_string(printer_serial);
+    if printer_serial.is_empty() {
+        return -1;
     }
 
-    let setting_id_str = unsafe {
-        match std::ffi::CStr::from_ptr(setting_id).to_str() {
-            Ok(s) => s,
-            Err(_) => return false,
-        }
-    };
+    let mut nozzle_diameter = c_str_to_string(nozzle_diameter);
+    if nozzle_diameter.is_empty() {
+        nozzle_diameter = "0.4".to_string();
+    }
 
-    let manager = BACKEND_MANAGER.lock().unwrap();
-    let base_url = manager.server_url.clone();
-    let is_connected = matches!(manager.state, BackendState::Connected { .. });
-    drop(manager);
+    start_request(&K_PROFILES, |seq| Job::KProfiles { seq, printer_serial, nozzle_diameter })
+}
 
-    if base_url.is_empty() || !is_connected {
-        info!("backend_get_preset_detail: backend not available, skipping");
-        return false;
+/// Get the state of the K-profiles request (`BackendRequestState`)
+/// When done, fills up to max_count profiles and sets count (0 if the backend
+/// isn't connected)
+#[no_mangle]
+pub extern "C" fn backend_k_profiles_poll(
+    profiles: *mut KProfileInfo,
+    max_count: c_int,
+    count: *mut c_int,
+) -> c_int {
+    if profiles.is_null() || count.is_null() || max_count <= 0 {
+        return REQUEST_NONE;
     }
 
-    // GET /api/cloud/settings/{setting_id}
-    let url = format!("{}/api/cloud/settings/{}", base_url, setting_id_str);
+    poll_request(&K_PROFILES, |api_profiles| {
+        let n = api_profiles.len().min(max_count as usize);
 
-    let config = HttpConfig {
-        timeout: Some(std::time::Duration::from_millis(HTTP_TIMEOUT_MS)),
-        ..Default::default()
-    };
+        for (i, prof) in api_profiles.iter().take(n).enumerate() {
+            let prof_ref = unsafe { &mut *profiles.add(i) };
 
-    let connection = match EspHttpConnection::new(&config) {
-        Ok(c) => c,
-        Err(_) => return false,
-    };
+            prof_ref.cali_idx = prof.cali_idx.unwrap_or(-1);
+            prof_ref.name = [0; 64];
+            prof_ref.k_value = [0; 16];
+            prof_ref.filament_id = [0; 32];
+            prof_ref.setting_id = [0; 64];
+            prof_ref.extruder_id = prof.extruder_id.unwrap_or(-1);
+            prof_ref.nozzle_temp = prof.nozzle_temp.unwrap_or(0);
 
-    let mut client = HttpClient::wrap(connection);
-    let request = match client.get(&url) {
-        Ok(r) => r,
-        Err(_) => return false,
-    };
+            if let Some(ref n) = prof.name {
+                copy_to_c_buf_signed(n, &mut prof_ref.name);
+            }
+            if let Some(k) = prof.k_value {
+                let k_str = format!("{:.3}", k);
+                copy_to_c_buf_signed(&k_str, &mut prof_ref.k_value);
+            }
+            if let Some(ref fid) = prof.filament_id {
+                copy_to_c_buf_signed(fid, &mut prof_ref.filament_id);
+            }
+            if let Some(ref sid) = prof.setting_id {
+                copy_to_c_buf_signed(sid, &mut prof_ref.setting_id);
+            }
+        }
 
-    let mut response = match request.submit() {
-        Ok(r) => r,
-        Err(_) => return false,
-    };
+        unsafe { *count = n as c_int };
+    })
+}
 
-    if response.status() != 200 {
-        return false;
+/// Fetch a printer's K-profiles (backend worker only - blocks on HTTP)
+pub fn run_k_profiles(seq: u32, printer_serial: &str, nozzle_diameter: &str) {
+    let result = fetch_k_profiles(printer_serial, nozzle_diameter);
+    if let Ok(profiles) = &result {
+        info!("K-profiles: {} profiles for {}", profiles.len(), printer_serial);
     }
+    finish_request(&K_PROFILES, seq, "K-profiles", result);
+}
 
-    // Read response body
-    let mut buf = vec![0u8; 4096];
-    let mut total = 0;
-    loop {
-        match response.read(&mut buf[total..]) {
-            Ok(0) => break,
-            Ok(n) => total += n,
-            Err(_) => break,
-        }
-        if total >= buf.len() {
-            break;
-        }
+/// GET /api/printers/{serial}/calibrations?nozzle_diameter=X
+fn fetch_k_profiles(printer_serial: &str, nozzle_diameter: &str) -> Result<Vec<ApiKProfileInfo>, String> {
+    if backend_is_connected() == 0 {
+        info!("K-profiles: backend not connected, skipping");
+        return Ok(Vec::new());
     }
 
-    if total == 0 {
-        return false;
+    let path = format!("/api/printers/{}/calibrations?nozzle_diameter={}", printer_serial, nozzle_diameter);
+    let (status, body) = api_get(&path, 8192)?;
+    if status != 200 {
+        return Err(format!("HTTP error: {}", status));
     }
-
-    // Parse response
-    let body = String::from_utf8_lossy(&buf[..total]);
-    let api_detail: ApiPresetDetail = match serde_json::from_str(&body) {
-        Ok(d) => d,
-        Err(_) => return false,
-    };
-
-    // Fill detail struct
-    let detail_ref = unsafe { &mut *detail };
-    detail_ref.filament_id = [0; 64];
-    detail_ref.base_id = [0; 64];
-    detail_ref.has_filament_id = false;
-    detail_ref.has_base_id = false;
-
-    // Check top-level first, then nested setting object
-    if let Some(ref fid) = api_detail.filament_id {
-        copy_to_c_buf_signed(fid, &mut detail_ref.filament_id);
-        detail_ref.has_filament_id = true;
-    } else if let Some(ref setting) = api_detail.setting {
-        if let Some(ref fid) = setting.filament_id {
-            copy_to_c_buf_signed(fid, &mut detail_ref.filament_id);
-            detail_ref.has_filament_id = true;
-        }
+    if body.is_empty() {
+        return Ok(Vec::new());
     }
 
-    if let Some(ref bid) = api_detail.base_id {
-        copy_to_c_buf_signed(bid, &mut detail_ref.base_id);
-        detail_ref.has_base_id = true;
-    } else if let Some(ref setting) = api_detail.setting {
-        if let Some(ref bid) = setting.base_id {
-            copy_to_c_buf_signed(bid, &mut detail_ref.base_id);
-            detail_ref.has_base_id = true;
-        }
-    }
+    serde_json::from_slice(&body).map_err(|e| format!("JSON parse error: {:?}", e))
+}
 
-    info!("backend_get_preset_detail: {} -> has_fid={}, has_bid={}",
-          setting_id_str, detail_ref.has_filament_id, detail_ref.has_base_id);
-    true
+/// Start searching the color catalog by manufacturer and/or material (non-blocking)
+/// Empty or NULL filters are left out. Returns 0 if submitted, -1 if the worker
+/// isn't running
+#[no_mangle]
+pub extern "C" fn backend_search_colors_start(
+    manufacturer: *const c_char,
+    material: *const c_char,
+) -> c_int {
+    let manufacturer = Some(c_str_to_string(manufacturer)).filter(|s| !s.is_empty());
+    let material = Some(c_str_to_string(material)).filter(|s| !s.is_empty());
+
+    start_request(&CATALOG_COLORS, |seq| Job::SearchColors { seq, manufacturer, material })
 }
 
-/// Get K-profiles (calibration profiles) for a printer
-/// Returns number of profiles found, -1 on error
+/// Get the state of the color search (`BackendRequestState`)
+/// When done, fills up to max_count colors and sets count
 #[no_mangle]
-pub extern "C" fn backend_get_k_profiles(
-    printer_serial: *const c_char,
-    nozzle_diameter: *const c_char,
-    profiles: *mut KProfileInfo,
+pub extern "C" fn backend_search_colors_poll(
+    colors: *mut ColorCatalogEntry,
     max_count: c_int,
+    count: *mut c_int,
 ) -> c_int {
-    info!("backend_get_k_profiles called");
-
-    if printer_serial.is_null() || profiles.is_null() || max_count <= 0 {
-        return -1;
+    if colors.is_null() || count.is_null() || max_count <= 0 {
+        return REQUEST_NONE;
     }
 
-    let serial_str = unsafe {
-        match std::ffi::CStr::from_ptr(printer_serial).to_str() {
-            Ok(s) => s,
-            Err(_) => return -1,
-        }
-    };
+    poll_request(&CATALOG_COLORS, |api_colors| {
+        let n = api_colors.len().min(max_count as usize);
 
-    let nozzle_str = if nozzle_diameter.is_null() {
-        "0.4"
-    } else {
-        unsafe {
-            match std::ffi::CStr::from_ptr(nozzle_diameter).to_str() {
-                Ok(s) => s,
-                Err(_) => "0.4",
+        for (i, color) in api_colors.iter().take(n).enumerate() {
+            let color_ref = unsafe { &mut *colors.add(i) };
+
+            color_ref.id = color.id.unwrap_or(0);
+            color_ref.manufacturer = [0; 64];
+            color_ref.color_name = [0; 64];
+            color_ref.hex_color = [0; 16];
+            color_ref.material = [0; 32];
+
+            if let Some(ref m) = color.manufacturer {
+                copy_to_c_buf_signed(m, &mut color_ref.manufacturer);
+            }
+            if let Some(ref c) = color.color_name {
+                copy_to_c_buf_signed(c, &mut color_ref.color_name);
+            }
+            if let Some(ref h) = color.hex_color {
+                copy_to_c_buf_signed(h, &mut color_ref.hex_color);
+            }
+            if let Some(ref m) = color.material {
+                copy_to_c_buf_signed(m, &mut color_ref.material);
             }
         }
-    };
-
-    let manager = BACKEND_MANAGER.lock().unwrap();
-    let base_url = manager.server_url.clone();
-    let is_connected = matches!(manager.state, BackendState::Connected { .. });
-    drop(manager);
 
-    if base_url.is_empty() {
-        info!("backend_get_k_profiles: no server URL configured");
-        return 0;  // Return 0 profiles instead of -1 if not configured
-    }
+        unsafe { *count = n as c_int };
+    })
+}
 
-    if !is_connected {
-        info!("backend_get_k_profiles: backend not connected, skipping");
-        return 0;  // Don't block UI if backend not connected
+/// Search the color catalog (backend worker only - blocks on HTTP)
+pub fn run_search_colors(seq: u32, manufacturer: Option<&str>, material: Option<&str>) {
+    let result = fetch_colors(manufacturer, material);
+    if let Ok(colors) = &result {
+        info!("Color search: {} colors", colors.len());
     }
+    finish_request(&CATALOG_COLORS, seq, "Color search", result);
+}
 
-    info!("backend_get_k_profiles: fetching from {}", base_url);
-
-    // GET /api/printers/{serial}/calibrations?nozzle_diameter=X
-    let url = format!("{}/api/printers/{}/calibrations?nozzle_diameter={}",
-                      base_url, serial_str, nozzle_str);
-
-    let config = HttpConfig {
-        timeout: Some(std::time::Duration::from_millis(HTTP_TIMEOUT_MS)),
-        ..Default::default()
-    };
-
-    let connection = match EspHttpConnection::new(&config) {
-        Ok(c) => c,
-        Err(_) => return -1,
-    };
-
-    let mut client = HttpClient::wrap(connection);
-    let request = match client.get(&url) {
-        Ok(r) => r,
-        Err(_) => return -1,
-    };
-
-    let mut response = match request.submit() {
-        Ok(r) => r,
-        Err(_) => return -1,
-    };
+/// GET /api/colors/search?manufacturer=X&material=Y
+fn fetch_colors(manufacturer: Option<&str>, material: Option<&str>) -> Result<Vec<ApiColorEntry>, String> {
+    let mut path = "/api/colors/search".to_string();
+    let mut has_param = false;
 
-    if response.status() != 200 {
-        return -1;
+    if let Some(m) = manufacturer {
+        path.push_str(&format!("?manufacturer={}", m.replace(' ', "%20")));
+        has_param = true;
     }
-
-    // Read response body
-    let mut buf = vec![0u8; 8192];
-    let mut total = 0;
-    loop {
-        match response.read(&mut buf[total..]) {
-            Ok(0) => break,
-            Ok(n) => total += n,
-            Err(_) => break,
-        }
-        if total >= buf.len() {
-            break;
-        }
+    if let Some(m) = material {
+        path.push_str(&format!("{}material={}", if has_param { "&" } else { "?" }, m));
     }
 
-    if total == 0 {
-        return 0;
+    // 64KB buffer for colors list
+    let (status, body) = api_get(&path, 65536)?;
+    if status != 200 {
+        return Err(format!("HTTP error: {}", status));
+    }
+    if body.is_empty() {
+        return Ok(Vec::new());
     }
 
-    // Parse response
-    let body = String::from_utf8_lossy(&buf[..total]);
-    let api_profiles: Vec<ApiKProfileInfo> = match serde_json::from_str(&body) {
-        Ok(p) => p,
-        Err(e) => {
-            warn!("Failed to parse K-profiles: {:?}", e);
-            return -1;
-        }
-    };
-
-    let count = api_profiles.len().min(max_count as usize);
-
-    for (i, prof) in api_profiles.iter().take(count).enumerate() {
-        let prof_ref = unsafe { &mut *profiles.add(i) };
+    serde_json::from_slice(&body).map_err(|e| format!("JSON parse error: {:?}", e))
+}
 
-        prof_ref.cali_idx = prof.cali_idx.unwrap_or(-1);
-        prof_ref.name = [0; 64];
-        prof_ref.k_value = [0; 16];
-        prof_ref.filament_id = [0; 32];
-        prof_ref.setting_id = [0; 64];
-        prof_ref.extruder_id = prof.extruder_id.unwrap_or(-1);
-        prof_ref.nozzle_temp = prof.nozzle_temp.unwrap_or(0);
+/// Send an AMS slot command (backend worker only - blocks on HTTP)
+pub fn run_slot_command(seq: u32, command: SlotCommand, path: &str, body: &str) {
+    info!("{}: POST {} with {}", command.name(), path, body);
 
-        if let Some(ref n) = prof.name {
-            copy_to_c_buf_signed(n, &mut prof_ref.name);
-        }
-        if let Some(k) = prof.k_value {
-            let k_str = format!("{:.3}", k);
-            copy_to_c_buf_signed(&k_str, &mut prof_ref.k_value);
-        }
-        if let Some(ref fid) = prof.filament_id {
-            copy_to_c_buf_signed(fid, &mut prof_ref.filament_id);
-        }
-        if let Some(ref sid) = prof.setting_id {
-            copy_to_c_buf_signed(sid, &mut prof_ref.setting_id);
-        }
+    let result = api_post(path, body).and_then(|status| match status {
+        200 | 204 => Ok(()),
+        status => Err(format!("status {}", status)),
+    });
+    if result.is_ok() {
+        info!("{}: success", command.name());
     }
 
-    info!("backend_get_k_profiles: returning {} profiles for {}", count, serial_str);
-    count as c_int
+    let request = match command {
+        SlotCommand::Filament => &SLOT_FILAMENT,
+        SlotCommand::Calibration => &SLOT_CALIBRATION,
+        SlotCommand::Reset => &SLOT_RESET,
+    };
+    finish_request(request, seq, command.name(), result);
 }
 
-/// Set filament in an AMS slot
-/// Returns true on success
+/// Start setting the filament of an AMS slot (non-blocking)
+/// Returns 0 if submitted, -1 on invalid arguments or if the worker isn't running
 #[no_mangle]
-pub extern "C" fn backend_set_slot_filament(
+pub extern "C" fn backend_set_slot_filament_start(
     printer_serial: *const c_char,
     ams_id: c_int,
     tray_id: c_int,
@@ -3016,30 +3749,10 @@ pub extern "C" fn backend_set_slot_filament(
     tray_color: *const c_char,
     nozzle_temp_min: c_int,
     nozzle_temp_max: c_int,
-) -> bool {
-    if printer_serial.is_null() {
-        return false;
-    }
-
-    let serial_str = unsafe {
-        match std::ffi::CStr::from_ptr(printer_serial).to_str() {
-            Ok(s) => s,
-            Err(_) => return false,
-        }
-    };
-
-    // Helper to convert C string to Rust string
-    fn c_str_to_string(ptr: *const c_char) -> String {
-        if ptr.is_null() {
-            String::new()
-        } else {
-            unsafe {
-                std::ffi::CStr::from_ptr(ptr)
-                    .to_str()
-                    .unwrap_or("")
-                    .to_string()
-            }
-        }
+) -> c_int {
+    let serial_str = c_str_to_string(printer_serial);
+    if serial_str.is_empty() {
+        return -1;
     }
 
     let tray_info_idx_str = c_str_to_string(tray_info_idx);
@@ -3048,17 +3761,8 @@ pub extern "C" fn backend_set_slot_filament(
     let tray_sub_brands_str = c_str_to_string(tray_sub_brands);
     let tray_color_str = c_str_to_string(tray_color);
 
-    let manager = BACKEND_MANAGER.lock().unwrap();
-    let base_url = manager.server_url.clone();
-    drop(manager);
-
-    if base_url.is_empty() {
-        return false;
-    }
-
     // POST /api/printers/{serial}/ams/{ams_id}/tray/{tray_id}/filament
-    let url = format!("{}/api/printers/{}/ams/{}/tray/{}/filament",
-                      base_url, serial_str, ams_id, tray_id);
+    let path = format!("/api/printers/{}/ams/{}/tray/{}/filament", serial_str, ams_id, tray_id);
 
     // Build JSON body
     let body = format!(
@@ -3067,68 +3771,24 @@ pub extern "C" fn backend_set_slot_filament(
         tray_color_str, nozzle_temp_min, nozzle_temp_max
     );
 
-    info!("backend_set_slot_filament: POST {} with {}", url, body);
-
-    let config = HttpConfig {
-        timeout: Some(std::time::Duration::from_millis(HTTP_TIMEOUT_MS)),
-        ..Default::default()
-    };
-
-    let connection = match EspHttpConnection::new(&config) {
-        Ok(c) => c,
-        Err(e) => {
-            warn!("Failed to create HTTP connection: {:?}", e);
-            return false;
-        }
-    };
-
-    let mut client = HttpClient::wrap(connection);
-
-    let headers = [
-        ("Content-Type", "application/json"),
-        ("Content-Length", &body.len().to_string()),
-    ];
-
-    let mut request = match client.request(embedded_svc::http::Method::Post, &url, &headers) {
-        Ok(r) => r,
-        Err(e) => {
-            warn!("Failed to create POST request: {:?}", e);
-            return false;
-        }
-    };
-
-    if let Err(e) = request.write(body.as_bytes()) {
-        warn!("Failed to write request body: {:?}", e);
-        return false;
-    }
-
-    if let Err(e) = request.flush() {
-        warn!("Failed to flush request: {:?}", e);
-        return false;
-    }
-
-    let response = match request.submit() {
-        Ok(r) => r,
-        Err(e) => {
-            warn!("Failed to submit request: {:?}", e);
-            return false;
-        }
-    };
-
-    let status = response.status();
-    if status != 200 && status != 204 {
-        warn!("set_slot_filament failed with status {}", status);
-        return false;
-    }
+    start_request(&SLOT_FILAMENT, |seq| Job::SlotCommand {
+        seq,
+        command: SlotCommand::Filament,
+        path,
+        body,
+    })
+}
 
-    info!("backend_set_slot_filament: success");
-    true
+/// Get the state of the set slot filament request (`BackendRequestState`)
+#[no_mangle]
+pub extern "C" fn backend_set_slot_filament_poll() -> c_int {
+    poll_request(&SLOT_FILAMENT, |_| {})
 }
 
-/// Set calibration (K-profile) for an AMS slot
-/// Returns true on success
+/// Start setting the calibration (K-profile) of an AMS slot (non-blocking)
+/// Returns 0 if submitted, -1 on invalid arguments or if the worker isn't running
 #[no_mangle]
-pub extern "C" fn backend_set_slot_calibration(
+pub extern "C" fn backend_set_slot_calibration_start(
     printer_serial: *const c_char,
     ams_id: c_int,
     tray_id: c_int,
@@ -3138,29 +3798,10 @@ pub extern "C" fn backend_set_slot_calibration(
     nozzle_diameter: *const c_char,
     k_value: f32,
     nozzle_temp: c_int,
-) -> bool {
-    if printer_serial.is_null() {
-        return false;
-    }
-
-    let serial_str = unsafe {
-        match std::ffi::CStr::from_ptr(printer_serial).to_str() {
-            Ok(s) => s,
-            Err(_) => return false,
-        }
-    };
-
-    fn c_str_to_string(ptr: *const c_char) -> String {
-        if ptr.is_null() {
-            String::new()
-        } else {
-            unsafe {
-                std::ffi::CStr::from_ptr(ptr)
-                    .to_str()
-                    .unwrap_or("")
-                    .to_string()
-            }
-        }
+) -> c_int {
+    let serial_str = c_str_to_string(printer_serial);
+    if serial_str.is_empty() {
+        return -1;
     }
 
     let filament_id_str = c_str_to_string(filament_id);
@@ -3171,17 +3812,8 @@ pub extern "C" fn backend_set_slot_calibration(
         c_str_to_string(nozzle_diameter)
     };
 
-    let manager = BACKEND_MANAGER.lock().unwrap();
-    let base_url = manager.server_url.clone();
-    drop(manager);
-
-    if base_url.is_empty() {
-        return false;
-    }
-
     // POST /api/printers/{serial}/ams/{ams_id}/tray/{tray_id}/calibration
-    let url = format!("{}/api/printers/{}/ams/{}/tray/{}/calibration",
-                      base_url, serial_str, ams_id, tray_id);
+    let path = format!("/api/printers/{}/ams/{}/tray/{}/calibration", serial_str, ams_id, tray_id);
 
     // Build JSON body
     let body = format!(
@@ -3189,280 +3821,58 @@ pub extern "C" fn backend_set_slot_calibration(
         cali_idx, filament_id_str, setting_id_str, nozzle_diameter_str, k_value, nozzle_temp
     );
 
-    info!("backend_set_slot_calibration: POST {} with {}", url, body);
-
-    let config = HttpConfig {
-        timeout: Some(std::time::Duration::from_millis(HTTP_TIMEOUT_MS)),
-        ..Default::default()
-    };
-
-    let connection = match EspHttpConnection::new(&config) {
-        Ok(c) => c,
-        Err(e) => {
-            warn!("Failed to create HTTP connection: {:?}", e);
-            return false;
-        }
-    };
-
-    let mut client = HttpClient::wrap(connection);
-
-    let headers = [
-        ("Content-Type", "application/json"),
-        ("Content-Length", &body.len().to_string()),
-    ];
-
-    let mut request = match client.request(embedded_svc::http::Method::Post, &url, &headers) {
-        Ok(r) => r,
-        Err(e) => {
-            warn!("Failed to create POST request: {:?}", e);
-            return false;
-        }
-    };
-
-    if let Err(e) = request.write(body.as_bytes()) {
-        warn!("Failed to write request body: {:?}", e);
-        return false;
-    }
-
-    if let Err(e) = request.flush() {
-        warn!("Failed to flush request: {:?}", e);
-        return false;
-    }
-
-    let response = match request.submit() {
-        Ok(r) => r,
-        Err(e) => {
-            warn!("Failed to submit request: {:?}", e);
-            return false;
-        }
-    };
-
-    let status = response.status();
-    if status != 200 && status != 204 {
-        warn!("set_slot_calibration failed with status {}", status);
-        return false;
-    }
+    start_request(&SLOT_CALIBRATION, |seq| Job::SlotCommand {
+        seq,
+        command: SlotCommand::Calibration,
+        path,
+        body,
+    })
+}
 
-    info!("backend_set_slot_calibration: success");
-    true
+/// Get the state of the set slot calibration request (`BackendRequestState`)
+#[no_mangle]
+pub extern "C" fn backend_set_slot_calibration_poll() -> c_int {
+    poll_request(&SLOT_CALIBRATION, |_| {})
 }
 
-/// Reset/clear an AMS slot (triggers RFID re-read)
-/// Returns true on success
+/// Start resetting/clearing an AMS slot (triggers RFID re-read, non-blocking)
+/// Returns 0 if submitted, -1 on invalid arguments or if the worker isn't running
 #[no_mangle]
-pub extern "C" fn backend_reset_slot(
+pub extern "C" fn backend_reset_slot_start(
     printer_serial: *const c_char,
     ams_id: c_int,
     tray_id: c_int,
-) -> bool {
-    if printer_serial.is_null() {
-        return false;
-    }
-
-    let serial_str = unsafe {
-        match std::ffi::CStr::from_ptr(printer_serial).to_str() {
-            Ok(s) => s,
-            Err(_) => return false,
-        }
-    };
-
-    let manager = BACKEND_MANAGER.lock().unwrap();
-    let base_url = manager.server_url.clone();
-    drop(manager);
-
-    if base_url.is_empty() {
-        return false;
-    }
-
-    // POST /api/printers/{serial}/ams/{ams_id}/tray/{tray_id}/reset
-    let url = format!("{}/api/printers/{}/ams/{}/tray/{}/reset",
-                      base_url, serial_str, ams_id, tray_id);
-
-    info!("backend_reset_slot: POST {}", url);
-
-    let config = HttpConfig {
-        timeout: Some(std::time::Duration::from_millis(HTTP_TIMEOUT_MS)),
-        ..Default::default()
-    };
-
-    let connection = match EspHttpConnection::new(&config) {
-        Ok(c) => c,
-        Err(e) => {
-            warn!("Failed to create HTTP connection: {:?}", e);
-            return false;
-        }
-    };
-
-    let mut client = HttpClient::wrap(connection);
-
-    // Empty body POST
-    let headers = [
-        ("Content-Type", "application/json"),
-        ("Content-Length", "0"),
-    ];
-
-    let request = match client.request(embedded_svc::http::Method::Post, &url, &headers) {
-        Ok(r) => r,
-        Err(e) => {
-            warn!("Failed to create POST request: {:?}", e);
-            return false;
-        }
-    };
-
-    let response = match request.submit() {
-        Ok(r) => r,
-        Err(e) => {
-            warn!("Failed to submit request: {:?}", e);
-            return false;
-        }
-    };
-
-    let status = response.status();
-    if status != 200 && status != 204 {
-        warn!("reset_slot failed with status {}", status);
-        return false;
-    }
-
-    info!("backend_reset_slot: success");
-    true
-}
-
-/// Search color catalog by manufacturer and/or material
-/// Returns number of colors found (up to max_count), -1 on error
-#[no_mangle]
-pub extern "C" fn backend_search_colors(
-    manufacturer: *const c_char,
-    material: *const c_char,
-    colors: *mut ColorCatalogEntry,
-    max_count: c_int,
 ) -> c_int {
-    if colors.is_null() || max_count <= 0 {
-        return -1;
-    }
-
-    fn c_str_to_option(ptr: *const c_char) -> Option<String> {
-        if ptr.is_null() {
-            None
-        } else {
-            let s = unsafe {
-                std::ffi::CStr::from_ptr(ptr)
-                    .to_str()
-                    .unwrap_or("")
-                    .to_string()
-            };
-            if s.is_empty() { None } else { Some(s) }
-        }
-    }
-
-    let manufacturer_opt = c_str_to_option(manufacturer);
-    let material_opt = c_str_to_option(material);
-
-    let manager = BACKEND_MANAGER.lock().unwrap();
-    let base_url = manager.server_url.clone();
-    drop(manager);
-
-    if base_url.is_empty() {
+    let serial_str = c_str_to_string(printer_serial);
+    if serial_str.is_empty() {
         return -1;
     }
 
-    // GET /api/colors/search?manufacturer=X&material=Y
-    let mut url = format!("{}/api/colors/search", base_url);
-    let mut has_param = false;
-
-    if let Some(ref m) = manufacturer_opt {
-        url.push_str(&format!("?manufacturer={}", m.replace(' ', "%20")));
-        has_param = true;
-    }
-    if let Some(ref m) = material_opt {
-        url.push_str(&format!("{}material={}", if has_param { "&" } else { "?" }, m));
-    }
-
-    let config = HttpConfig {
-        timeout: Some(std::time::Duration::from_millis(HTTP_TIMEOUT_MS)),
-        ..Default::default()
-    };
-
-    let connection = match EspHttpConnection::new(&config) {
-        Ok(c) => c,
-        Err(_) => return -1,
-    };
-
-    let mut client = HttpClient::wrap(connection);
-    let request = match client.get(&url) {
-        Ok(r) => r,
-        Err(_) => return -1,
-    };
-
-    let mut response = match request.submit() {
-        Ok(r) => r,
-        Err(_) => return -1,
-    };
-
-    if response.status() != 200 {
-        return -1;
-    }
-
-    // Read response body
-    let mut buf = vec![0u8; 65536]; // 64KB buffer for colors list
-    let mut total = 0;
-    loop {
-        match response.read(&mut buf[total..]) {
-            Ok(0) => break,
-            Ok(n) => total += n,
-            Err(_) => break,
-        }
-        if total >= buf.len() {
-            break;
-        }
-    }
+    // POST /api/printers/{serial}/ams/{ams_id}/tray/{tray_id}/reset (empty body)
+    let path = format!("/api/printers/{}/ams/{}/tray/{}/reset", serial_str, ams_id, tray_id);
 
-    if total == 0 {
-        return 0;
-    }
-
-    // Parse response
-    let body = String::from_utf8_lossy(&buf[..total]);
-    let api_colors: Vec<ApiColorEntry> = match serde_json::from_str(&body) {
-        Ok(c) => c,
-        Err(e) => {
-            warn!("Failed to parse color catalog: {:?}", e);
-            return -1;
-        }
-    };
-
-    let count = api_colors.len().min(max_count as usize);
-
-    for (i, color) in api_colors.iter().take(count).enumerate() {
-        let color_ref = unsafe { &mut *colors.add(i) };
-
-        color_ref.id = color.id.unwrap_or(0);
-        color_ref.manufacturer = [0; 64];
-        color_ref.color_name = [0; 64];
-        color_ref.hex_color = [0; 16];
-        color_ref.material = [0; 32];
-
-        if let Some(ref m) = color.manufacturer {
-            copy_to_c_buf_signed(m, &mut color_ref.manufacturer);
-        }
-        if let Some(ref c) = color.color_name {
-            copy_to_c_buf_signed(c, &mut color_ref.color_name);
-        }
-        if let Some(ref h) = color.hex_color {
-            copy_to_c_buf_signed(h, &mut color_ref.hex_color);
-        }
-        if let Some(ref m) = color.material {
-            copy_to_c_buf_signed(m, &mut color_ref.material);
-        }
-    }
+    start_request(&SLOT_RESET, |seq| Job::SlotCommand {
+        seq,
+        command: SlotCommand::Reset,
+        path,
+        body: String::new(),
+    })
+}
 
-    info!("backend_search_colors: returning {} colors", count);
-    count as c_int
+/// Get the state of the reset slot request (`BackendRequestState`)
+#[no_mangle]
+pub extern "C" fn backend_reset_slot_poll() -> c_int {
+    poll_request(&SLOT_RESET, |_| {})
 }
 
 /// Helper to copy string to c_char buffer (signed char)
 fn copy_to_c_buf_signed(src: &str, dest: &mut [c_char]) {
     let bytes = src.as_bytes();
-    let len = bytes.len().min(dest.len() - 1);
+    let mut len = bytes.len().min(dest.len() - 1);
+    // Don't cut a UTF-8 character in half
+    while !src.is_char_boundary(len) {
+        len -= 1;
+    }
     for i in 0..len {
         dest[i] = bytes[i] as c_char;
     }
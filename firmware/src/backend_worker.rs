@@ -0,0 +1,219 @@
+//! Background worker for backend I/O
+//!
+//! The main loop only renders the UI and polls sensors. All backend traffic
+//! runs here on a dedicated thread: the periodic and background requests
+//! (discovery, heartbeat/printer polling, device state, OTA check, spool
+//! lookups) and the requests made from the UI (presets, K-profiles, colours,
+//! untagged spools, AMS slot commands and inventory mutations). Results of
+//! the background requests are published into the shared caches that the FFI
+//! getters read; a UI request is started with its `*_start` FFI call and its
+//! result read with the matching `*_poll`, so C code never waits on the
+//! network. Mutations run in order with the outbox replay on this thread.
+
+use log::{info, warn};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::Mutex;
+
+/// Backend work item
+#[derive(Debug, Clone, PartialEq)]
+pub enum Job {
+    /// Post-WiFi setup: saved URL or mDNS discovery, time sync, first poll
+    Connect,
+    /// mDNS discovery of the backend (retry while no server is known)
+    Discover,
+    /// Heartbeat, outbox replay, printers and time
+    Poll,
+    /// Send the latest scale/tag state (see `submit_device_state`)
+    DeviceState,
+    /// Firmware update check, at startup or from the UI (doesn't install)
+    OtaCheck,
+    /// Look up the inventory spool for a scanned tag
+    SpoolLookup { tag_id: String },
+    /// Slicer filament presets from Bambu Cloud (via backend)
+    SlicerPresets { seq: u32 },
+    /// filament_id and base_id of a slicer preset
+    PresetDetail { seq: u32, setting_id: String },
+    /// K-profiles (calibrations) of a printer
+    KProfiles { seq: u32, printer_serial: String, nozzle_diameter: String },
+    /// K-profile of a spool on a printer
+    SpoolKProfile { seq: u32, spool_id: String, printer_serial: String },
+    /// Spools without an NFC tag
+    UntaggedSpools { seq: u32 },
+    /// Color catalog search
+    SearchColors { seq: u32, manufacturer: Option<String>, material: Option<String> },
+    /// AMS slot filament, calibration or reset command
+    SlotCommand {
+        seq: u32,
+        command: crate::backend_client::SlotCommand,
+        path: String,
+        body: String,
+    },
+    /// Inventory mutation (queued offline if the backend is unreachable)
+    Mutation {
+        seq: u32,
+        mutation: crate::backend_client::Mutation,
+        path: String,
+        body: String,
+        coalesce: Option<String>,
+    },
+}
+
+/// Latest device state waiting to be sent
+#[derive(Debug, Clone, PartialEq)]
+struct PendingDeviceState {
+    tag_uid: Option<String>,
+    weight: f32,
+    stable: bool,
+}
+
+static JOB_TX: Mutex<Option<Sender<Job>>> = Mutex::new(None);
+static POLL_QUEUED: AtomicBool = AtomicBool::new(false);
+static DISCOVER_QUEUED: AtomicBool = AtomicBool::new(false);
+static DEVICE_STATE: Mutex<Option<PendingDeviceState>> = Mutex::new(None);
+
+/// Start the worker thread
+pub fn init() {
+    let (tx, rx) = mpsc::channel::<Job>();
+
+    // Larger stack: JSON parsing of printer and spool lists runs here
+    let spawned = std::thread::Builder::new()
+        .name("backend_io".into())
+        .stack_size(16384)
+        .spawn(move || {
+            while let Ok(job) = rx.recv() {
+                run(job);
+            }
+        });
+
+    match spawned {
+        Ok(_) => {
+            *JOB_TX.lock().unwrap() = Some(tx);
+            info!("Backend worker started");
+        }
+        Err(e) => warn!("Failed to start backend worker: {:?}", e),
+    }
+}
+
+/// Flag for jobs that are only queued once at a time
+fn queued_flag(job: &Job) -> Option<&'static AtomicBool> {
+    match job {
+        Job::Poll => Some(&POLL_QUEUED),
+        Job::Discover => Some(&DISCOVER_QUEUED),
+        _ => None,
+    }
+}
+
+/// Queue a job; returns false if the worker isn't running
+/// A poll or discovery that is still waiting in the queue is not queued twice.
+pub fn submit(job: Job) -> bool {
+    let flag = queued_flag(&job);
+    if flag.is_some_and(|f| f.swap(true, Ordering::Relaxed)) {
+        return true;
+    }
+
+    let tx_guard = JOB_TX.lock().unwrap();
+    let sent = match tx_guard.as_ref() {
+        Some(tx) => tx.send(job.clone()).is_ok(),
+        None => false,
+    };
+
+    if !sent {
+        if let Some(f) = flag {
+            f.store(false, Ordering::Relaxed);
+        }
+        warn!("Backend worker not running, dropping {:?}", job);
+    }
+    sent
+}
+
+/// Queue a device state update
+/// Updates made while one is pending are merged, keeping the latest weight
+/// and the tag if either update carried one.
+pub fn submit_device_state(tag_uid: Option<&str>, weight: f32, stable: bool) {
+    let mut pending = DEVICE_STATE.lock().unwrap();
+    let queued = pending.is_some();
+    let tag_uid = tag_uid
+        .map(|t| t.to_string())
+        .or_else(|| pending.as_ref().and_then(|p| p.tag_uid.clone()));
+    *pending = Some(PendingDeviceState { tag_uid, weight, stable });
+    drop(pending);
+
+    if !queued && !submit(Job::DeviceState) {
+        DEVICE_STATE.lock().unwrap().take();
+    }
+}
+
+fn run(job: Job) {
+    match job {
+        Job::Connect => {
+            // Use saved backend URL, or find the server via mDNS
+            if !crate::backend_client::apply_saved_server_url() {
+                crate::backend_client::discover_server();
+            }
+            // Pin an https server's certificate before the first request
+            if let Some(url) = crate::backend_client::get_server_url() {
+                crate::tls_pin::ensure_pinned(&url);
+            }
+            // Sync time immediately from backend (faster than SNTP)
+            crate::backend_client::sync_time();
+            // Immediate first poll for printer data
+            crate::backend_client::poll_backend();
+        }
+        Job::Discover => {
+            DISCOVER_QUEUED.store(false, Ordering::Relaxed);
+            crate::backend_client::discover_server();
+        }
+        Job::Poll => {
+            POLL_QUEUED.store(false, Ordering::Relaxed);
+            crate::backend_client::poll_backend();
+        }
+        Job::DeviceState => {
+            let pending = DEVICE_STATE.lock().unwrap().take();
+            if let Some(state) = pending {
+                crate::backend_client::send_device_state(state.tag_uid.as_deref(), state.weight, state.stable);
+            }
+        }
+        Job::OtaCheck => {
+            let Some(server_url) = crate::backend_client::get_server_url() else {
+                return;
+            };
+            info!("Firmware version: v{}", crate::ota_manager::get_version());
+
+            // Check for updates and store result (don't auto-install)
+            match crate::ota_manager::check_for_update(&server_url) {
+                Ok(info) => {
+                    if info.available {
+                        info!("Firmware update available: v{}", info.version);
+                        crate::ota_manager::set_update_available(true, &info.version);
+                    } else {
+                        info!("Firmware is up to date");
+                        crate::ota_manager::set_update_available(false, "");
+                    }
+                }
+                Err(e) => {
+                    warn!("OTA check failed: {}", e);
+                }
+            }
+        }
+        Job::SpoolLookup { tag_id } => crate::backend_client::run_spool_lookup(&tag_id),
+        Job::SlicerPresets { seq } => crate::backend_client::run_slicer_presets(seq),
+        Job::PresetDetail { seq, setting_id } => crate::backend_client::run_preset_detail(seq, &setting_id),
+        Job::KProfiles { seq, printer_serial, nozzle_diameter } => {
+            crate::backend_client::run_k_profiles(seq, &printer_serial, &nozzle_diameter)
+        }
+        Job::SpoolKProfile { seq, spool_id, printer_serial } => {
+            crate::backend_client::run_spool_k_profile(seq, &spool_id, &printer_serial)
+        }
+        Job::UntaggedSpools { seq } => crate::backend_client::run_untagged_spools(seq),
+        Job::SearchColors { seq, manufacturer, material } => {
+            crate::backend_client::run_search_colors(seq, manufacturer.as_deref(), material.as_deref())
+        }
+        Job::SlotCommand { seq, command, path, body } => {
+            crate::backend_client::run_slot_command(seq, command, &path, &body)
+        }
+        Job::Mutation { seq, mutation, path, body, coalesce } => {
+            crate::backend_client::run_mutation(seq, mutation, path, body, coalesce)
+        }
+    }
+}
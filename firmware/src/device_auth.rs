@@ -0,0 +1,273 @@
+//! Device API key and pairing
+//!
+//! Every request to the backend carries the device API key in an `X-API-Key`
+//! header. The key is stored in NVS and obtained by pairing: the display asks
+//! `/api/device/pair` for a pairing code, shows it in the status bar and polls
+//! until the code is entered in the web UI, which hands out the key. The
+//! backend can later replace the key with a `rotate_api_key` command.
+
+use embedded_svc::http::client::Client as HttpClient;
+use esp_idf_svc::http::client::EspHttpConnection;
+use esp_idf_svc::nvs::{EspDefaultNvsPartition, EspNvs};
+use log::{info, warn};
+use serde::Deserialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Header carrying the key
+pub const API_KEY_HEADER: &str = "X-API-Key";
+
+/// NVS namespace (shared with the server URL) and key
+const NVS_NAMESPACE: &str = "backend";
+const NVS_KEY_API_KEY: &str = "api_key";
+
+/// Longest key accepted (backend keys are "sb_" plus 43 characters)
+const MAX_KEY_LEN: usize = 128;
+
+/// Wait before asking for a new pairing code after a failed request
+const PAIRING_RETRY: Duration = Duration::from_secs(30);
+
+/// HTTP timeout in milliseconds
+const HTTP_TIMEOUT_MS: u64 = 5000;
+
+/// Largest pairing response accepted
+const MAX_RESPONSE_SIZE: usize = 1024;
+
+/// API key received from the backend (parsed with the commands in firmware-core)
+pub use spoolbuddy_firmware_core::device_command::ApiKey;
+
+/// Pairing request waiting for approval
+#[derive(Debug, Clone)]
+struct Pairing {
+    id: String,
+    code: String,
+}
+
+struct PairingState {
+    current: Option<Pairing>,
+    retry_at: Option<Instant>,
+}
+
+/// Response to `POST /api/device/pair`
+#[derive(Debug, Deserialize)]
+struct PairResponse {
+    pairing_id: String,
+    code: String,
+}
+
+/// Response to `GET /api/device/pair/{id}`
+#[derive(Debug, Deserialize)]
+struct PairStatusResponse {
+    status: String,
+    #[serde(default)]
+    key: Option<ApiKey>,
+}
+
+static NVS_PARTITION: Mutex<Option<EspDefaultNvsPartition>> = Mutex::new(None);
+static API_KEY: Mutex<Option<String>> = Mutex::new(None);
+static PAIRING: Mutex<PairingState> = Mutex::new(PairingState { current: None, retry_at: None });
+
+/// Backend answered 401 to the stored key - pair again for a new one
+static KEY_REJECTED: AtomicBool = AtomicBool::new(false);
+
+/// Load the stored key
+pub fn init(nvs: Option<EspDefaultNvsPartition>) {
+    let key = nvs.as_ref().and_then(load_key);
+    if key.is_some() {
+        info!("Device API key loaded");
+    } else {
+        info!("No device API key stored, pairing required");
+    }
+    *API_KEY.lock().unwrap() = key;
+    *NVS_PARTITION.lock().unwrap() = nvs;
+}
+
+fn load_key(nvs_partition: &EspDefaultNvsPartition) -> Option<String> {
+    let nvs = match EspNvs::new(nvs_partition.clone(), NVS_NAMESPACE, true) {
+        Ok(nvs) => nvs,
+        Err(e) => {
+            warn!("Failed to open NVS namespace for API key: {:?}", e);
+            return None;
+        }
+    };
+
+    let mut buf = [0u8; MAX_KEY_LEN + 1];
+    match nvs.get_str(NVS_KEY_API_KEY, &mut buf) {
+        Ok(Some(s)) if !s.is_empty() => Some(s.to_string()),
+        Ok(_) => None,
+        Err(e) => {
+            warn!("Failed to read API key from NVS: {:?}", e);
+            None
+        }
+    }
+}
+
+fn save_key(key: &str) -> Result<(), String> {
+    let nvs_guard = NVS_PARTITION.lock().unwrap();
+    let Some(nvs_partition) = nvs_guard.as_ref() else {
+        return Err("No NVS partition available".to_string());
+    };
+    let nvs_clone = nvs_partition.clone();
+    drop(nvs_guard); // Release lock before NVS operations
+
+    let nvs = EspNvs::new(nvs_clone, NVS_NAMESPACE, true)
+        .map_err(|e| format!("Failed to open NVS namespace: {:?}", e))?;
+    nvs.set_str(NVS_KEY_API_KEY, key)
+        .map_err(|e| format!("Failed to save API key: {:?}", e))
+}
+
+/// Current device API key, if paired
+pub fn api_key() -> Option<String> {
+    API_KEY.lock().unwrap().clone()
+}
+
+/// Request headers plus the API key header when a key is stored
+pub fn with_api_key<'a>(headers: &[(&'a str, &'a str)], api_key: &'a Option<String>) -> Vec<(&'a str, &'a str)> {
+    let mut all = headers.to_vec();
+    if let Some(key) = api_key {
+        all.push((API_KEY_HEADER, key.as_str()));
+    }
+    all
+}
+
+/// Store a new key (from pairing or rotation)
+/// The key is only used once it was persisted, so a reboot can't bring back
+/// a key the backend has already revoked.
+pub fn set_api_key(key: &ApiKey) -> Result<(), String> {
+    let valid = !key.0.is_empty()
+        && key.0.len() <= MAX_KEY_LEN
+        && key.0.chars().all(|c| c.is_ascii_graphic());
+    if !valid {
+        return Err("Invalid API key".to_string());
+    }
+
+    save_key(&key.0)?;
+    *API_KEY.lock().unwrap() = Some(key.0.clone());
+    KEY_REJECTED.store(false, Ordering::Relaxed);
+    info!("Device API key updated ({}...)", key.prefix());
+    Ok(())
+}
+
+/// Record that the backend rejected the stored key (HTTP 401)
+pub fn key_rejected() {
+    if !KEY_REJECTED.swap(true, Ordering::Relaxed) {
+        warn!("Backend rejected device API key, pairing again");
+    }
+}
+
+/// Check whether a (new) key has to be obtained by pairing
+pub fn needs_pairing() -> bool {
+    API_KEY.lock().unwrap().is_none() || KEY_REJECTED.load(Ordering::Relaxed)
+}
+
+/// Pairing code to show on the display while pairing is in progress
+pub fn pairing_code() -> Option<String> {
+    let state = PAIRING.lock().unwrap();
+    state.current.as_ref().map(|p| p.code.clone())
+}
+
+/// Advance pairing: request a code, or check whether it was approved
+/// Runs on the backend worker as part of each poll while `needs_pairing()`.
+pub fn poll_pairing(base_url: &str) {
+    let current = {
+        let state = PAIRING.lock().unwrap();
+        if state.retry_at.is_some_and(|at| Instant::now() < at) {
+            return;
+        }
+        state.current.clone()
+    };
+
+    let Some(pairing) = current else {
+        match request_pairing(base_url) {
+            Ok(response) => {
+                info!("Pairing code {} - enter it in the SpoolBuddy web UI", response.code);
+                *PAIRING.lock().unwrap() = PairingState {
+                    current: Some(Pairing { id: response.pairing_id, code: response.code }),
+                    retry_at: None,
+                };
+            }
+            Err(e) => {
+                warn!("Pairing request failed: {}", e);
+                PAIRING.lock().unwrap().retry_at = Some(Instant::now() + PAIRING_RETRY);
+            }
+        }
+        return;
+    };
+
+    match check_pairing(base_url, &pairing.id) {
+        Ok(Some(PairStatusResponse { status, key: Some(key) })) if status == "approved" => {
+            match set_api_key(&key) {
+                Ok(()) => {
+                    info!("Paired with backend");
+                    PAIRING.lock().unwrap().current = None;
+                }
+                Err(e) => warn!("Pairing approved but key not stored: {}", e),
+            }
+        }
+        Ok(Some(response)) if response.status == "pending" => {}
+        Ok(_) => {
+            // Expired or unknown to the backend (e.g. restarted) - get a new code
+            info!("Pairing code {} expired", pairing.code);
+            PAIRING.lock().unwrap().current = None;
+        }
+        Err(e) => warn!("Pairing status check failed: {}", e),
+    }
+}
+
+/// Ask the backend for a pairing code
+fn request_pairing(base_url: &str) -> Result<PairResponse, String> {
+    let url = format!("{}/api/device/pair?device_id={}", base_url, crate::outbox::device_id());
+    let (status, body) = http_request(embedded_svc::http::Method::Post, &url, &[("Content-Length", "0")])?;
+    if status != 200 {
+        return Err(format!("HTTP error: {}", status));
+    }
+    serde_json::from_str(&body).map_err(|e| format!("Invalid pairing response: {}", e))
+}
+
+/// Get the state of a pairing request (None if the backend doesn't know it)
+fn check_pairing(base_url: &str, pairing_id: &str) -> Result<Option<PairStatusResponse>, String> {
+    let url = format!("{}/api/device/pair/{}", base_url, pairing_id);
+    let (status, body) = http_request(embedded_svc::http::Method::Get, &url, &[])?;
+    match status {
+        200 => serde_json::from_str(&body)
+            .map(Some)
+            .map_err(|e| format!("Invalid pairing status: {}", e)),
+        404 => Ok(None),
+        _ => Err(format!("HTTP error: {}", status)),
+    }
+}
+
+fn http_request(method: embedded_svc::http::Method, url: &str, headers: &[(&str, &str)]) -> Result<(u16, String), String> {
+    let config = crate::tls_pin::http_config(Duration::from_millis(HTTP_TIMEOUT_MS));
+
+    let connection = EspHttpConnection::new(&config)
+        .map_err(|e| format!("HTTP connection failed: {:?}", e))?;
+
+    let mut client = HttpClient::wrap(connection);
+
+    let request = client.request(method, url, headers)
+        .map_err(|e| format!("Request failed: {:?}", e))?;
+
+    let mut response = request.submit()
+        .map_err(|e| format!("Submit failed: {:?}", e))?;
+
+    let status = response.status();
+
+    let mut body = Vec::new();
+    let mut buf = [0u8; 256];
+    loop {
+        match response.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => {
+                body.extend_from_slice(&buf[..n]);
+                if body.len() > MAX_RESPONSE_SIZE {
+                    return Err("Response too large".to_string());
+                }
+            }
+            Err(e) => return Err(format!("Read error: {:?}", e)),
+        }
+    }
+
+    Ok((status, String::from_utf8_lossy(&body).into_owned()))
+}
@@ -0,0 +1,303 @@
+//! Typed device commands from the backend
+//!
+//! The heartbeat response carries a `commands` list; each entry has an `id`
+//! and a `type` plus type-specific parameters (parsed in firmware-core,
+//! `device_command`). Commands are queued to a worker thread so slow
+//! operations (calibration, OTA) never block the heartbeat or the UI loop.
+//! Every outcome, including rejection of unknown commands, is posted back to
+//! `/api/display/command-result`.
+
+use crate::scale::drift::{DriftConfig, TempSource};
+use crate::scale::filter::FilterConfig;
+use crate::scale::nau7802::AdcConfig;
+use log::{info, warn};
+use spoolbuddy_firmware_core::device_command::{
+    parse_heartbeat, CommandKind, CommandResult, DeviceCommand, ScaleSettings,
+};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Results kept for retry when the backend is unreachable
+const MAX_PENDING_RESULTS: usize = 16;
+
+/// Recently executed command ids (guards against redelivery)
+const MAX_RECENT_IDS: usize = 16;
+
+/// Attempts to report a reboot result before restarting anyway
+const REBOOT_REPORT_ATTEMPTS: u32 = 5;
+
+/// Delay between reboot result attempts
+const REBOOT_REPORT_RETRY: Duration = Duration::from_secs(2);
+
+// =============================================================================
+// Queue and worker
+// =============================================================================
+
+static COMMAND_TX: Mutex<Option<Sender<DeviceCommand>>> = Mutex::new(None);
+static RECENT_IDS: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+static PENDING_RESULTS: Mutex<Vec<CommandResult>> = Mutex::new(Vec::new());
+static REBOOT_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Start the command worker thread
+pub fn init() {
+    let (tx, rx) = mpsc::channel::<DeviceCommand>();
+
+    // Larger stack: OTA download and HTTP result reporting run here
+    let spawned = std::thread::Builder::new()
+        .name("device_cmd".into())
+        .stack_size(16384)
+        .spawn(move || {
+            while let Ok(cmd) = rx.recv() {
+                info!("Executing command {}: {:?}", cmd.id, cmd.kind);
+                let result = execute(&cmd);
+                if matches!(cmd.kind, CommandKind::Reboot) {
+                    // Only after reporting: the main loop restarts within a few
+                    // ms of the flag, and a queued result would be lost with it
+                    report_reboot_result(&result);
+                    REBOOT_REQUESTED.store(true, Ordering::Relaxed);
+                } else {
+                    report_result(result);
+                }
+            }
+        });
+
+    match spawned {
+        Ok(_) => {
+            *COMMAND_TX.lock().unwrap() = Some(tx);
+            info!("Device command worker started");
+        }
+        Err(e) => warn!("Failed to start device command worker: {:?}", e),
+    }
+}
+
+/// Queue a command for execution
+/// Commands seen recently (same id) are ignored.
+pub fn enqueue(cmd: DeviceCommand) {
+    {
+        let mut recent = RECENT_IDS.lock().unwrap();
+        if recent.iter().any(|id| *id == cmd.id) {
+            info!("Ignoring duplicate command {}", cmd.id);
+            return;
+        }
+        if recent.len() >= MAX_RECENT_IDS {
+            recent.pop_front();
+        }
+        recent.push_back(cmd.id.clone());
+    }
+
+    let tx_guard = COMMAND_TX.lock().unwrap();
+    let sent = match tx_guard.as_ref() {
+        Some(tx) => tx.send(cmd.clone()).is_ok(),
+        None => false,
+    };
+    drop(tx_guard);
+
+    if !sent {
+        report_result(CommandResult::error(&cmd.id, "Command worker not running".to_string()));
+    }
+}
+
+/// Handle a heartbeat response body: queue valid commands, reject the rest
+pub fn handle_heartbeat_response(body: &str) {
+    match parse_heartbeat(body) {
+        Ok((commands, rejected)) => {
+            for result in rejected {
+                warn!("Rejecting command {}: {}", result.id, result.error.as_deref().unwrap_or(""));
+                report_result(result);
+            }
+            for cmd in commands {
+                enqueue(cmd);
+            }
+        }
+        Err(e) => warn!("{}", e),
+    }
+}
+
+/// Check whether a reboot command was executed (handled by the main loop)
+pub fn reboot_requested() -> bool {
+    REBOOT_REQUESTED.load(Ordering::Relaxed)
+}
+
+/// Run a command and build its result
+fn execute(cmd: &DeviceCommand) -> CommandResult {
+    match &cmd.kind {
+        CommandKind::Update => {
+            let Some(base_url) = crate::backend_client::get_server_url() else {
+                return CommandResult::error(&cmd.id, "No backend server configured".to_string());
+            };
+            // perform_update reboots on success, so report the start first
+            report_result(CommandResult::ok(&cmd.id).with("message", "Update started"));
+            match crate::ota_manager::perform_update(&base_url) {
+                Ok(()) => CommandResult::ok(&cmd.id).with("message", "Update installed"),
+                Err(e) => CommandResult::error(&cmd.id, format!("OTA update failed: {}", e)),
+            }
+        }
+        // The worker flags the reboot for the main loop once this is reported
+        CommandKind::Reboot => CommandResult::ok(&cmd.id),
+        CommandKind::ScaleTare => match crate::scale_manager::tare() {
+            Ok(cal) => CommandResult::ok(&cmd.id)
+                .with("zero_offset", cal.zero_offset)
+                .with("cal_factor", cal.cal_factor),
+            Err(e) => CommandResult::error(&cmd.id, e),
+        },
+        CommandKind::ScaleCalibrate { known_weight } => match crate::scale_manager::calibrate(*known_weight) {
+            Ok(cal) => CommandResult::ok(&cmd.id)
+                .with("known_weight", *known_weight)
+                .with("zero_offset", cal.zero_offset)
+                .with("cal_factor", cal.cal_factor),
+            Err(e) => CommandResult::error(&cmd.id, e),
+        },
+        CommandKind::ScaleReset => match crate::scale_manager::reset_calibration() {
+            Ok(cal) => CommandResult::ok(&cmd.id)
+                .with("zero_offset", cal.zero_offset)
+                .with("cal_factor", cal.cal_factor),
+            Err(e) => CommandResult::error(&cmd.id, e),
+        },
+        CommandKind::ScaleConfig(settings) => match apply_scale_settings(settings) {
+            Ok((adc, filter, drift)) => CommandResult::ok(&cmd.id)
+                .with("sample_rate", adc.sample_rate.sps())
+                .with("gain", adc.gain.factor())
+                .with("ldo_mv", adc.ldo.millivolts())
+                .with("channel", adc.channel.number())
+                .with("median_window", filter.median_window)
+                .with("average_window", filter.average_window)
+                .with("ema_alpha", filter.ema_alpha)
+                .with("snap_threshold_g", filter.snap_threshold_g)
+                .with("stability_window", filter.stability_window)
+                .with("stability_std_dev_g", filter.stability_std_dev_g)
+                .with("auto_zero", drift.auto_zero)
+                .with("auto_zero_band_g", drift.auto_zero_band_g)
+                .with("auto_zero_rate_g", drift.auto_zero_rate_g)
+                .with("temp_source", drift.temp_source.name())
+                .with("zero_g_per_c", drift.zero_g_per_c)
+                .with("span_ppm_per_c", drift.span_ppm_per_c),
+            Err(e) => CommandResult::error(&cmd.id, e),
+        },
+        CommandKind::SetServerUrl { url } => {
+            if crate::backend_client::configure_server_url(url) {
+                CommandResult::ok(&cmd.id).with("url", url.as_str())
+            } else {
+                CommandResult::error(&cmd.id, format!("Invalid server URL: '{}'", url))
+            }
+        }
+        CommandKind::RotateApiKey { key } => match crate::device_auth::set_api_key(key) {
+            // The result is posted with the new key, which proves it works
+            Ok(()) => CommandResult::ok(&cmd.id).with("key_prefix", key.prefix()),
+            Err(e) => CommandResult::error(&cmd.id, e),
+        },
+    }
+}
+
+/// Apply the given scale settings over the active ones
+/// Returns the active ADC, filter and drift settings.
+fn apply_scale_settings(settings: &ScaleSettings) -> Result<(AdcConfig, FilterConfig, DriftConfig), String> {
+    let mut adc = crate::scale_manager::adc_config()?;
+    if settings.has_adc() {
+        let config = crate::scale_manager::parse_adc_config(
+            settings.sample_rate.unwrap_or(adc.sample_rate.sps() as i32),
+            settings.gain.unwrap_or(adc.gain.factor() as i32),
+            settings.ldo_mv.unwrap_or(adc.ldo.millivolts() as i32),
+            settings.channel.unwrap_or(adc.channel.number() as i32),
+        )?;
+        adc = crate::scale_manager::set_adc_config(config)?;
+    }
+
+    let mut filter = crate::scale_manager::filter_config()?;
+    if settings.has_filter() {
+        filter = FilterConfig {
+            median_window: settings.median_window.unwrap_or(filter.median_window),
+            average_window: settings.average_window.unwrap_or(filter.average_window),
+            ema_alpha: settings.ema_alpha.unwrap_or(filter.ema_alpha),
+            snap_threshold_g: settings.snap_threshold_g.unwrap_or(filter.snap_threshold_g),
+            stability_window: settings.stability_window.unwrap_or(filter.stability_window),
+            stability_std_dev_g: settings.stability_std_dev_g.unwrap_or(filter.stability_std_dev_g),
+        };
+        crate::scale_manager::set_filter_config(filter)?;
+    }
+
+    let mut drift = crate::scale_manager::drift_config()?;
+    if settings.has_drift() {
+        let temp_source = match settings.temp_source.as_deref() {
+            Some(name) => TempSource::from_name(name)
+                .ok_or_else(|| format!("Invalid temperature source: '{}'", name))?,
+            None => drift.temp_source,
+        };
+        drift = DriftConfig {
+            auto_zero: settings.auto_zero.unwrap_or(drift.auto_zero),
+            auto_zero_band_g: settings.auto_zero_band_g.unwrap_or(drift.auto_zero_band_g),
+            auto_zero_rate_g: settings.auto_zero_rate_g.unwrap_or(drift.auto_zero_rate_g),
+            temp_source,
+            zero_g_per_c: settings.zero_g_per_c.unwrap_or(drift.zero_g_per_c),
+            span_ppm_per_c: settings.span_ppm_per_c.unwrap_or(drift.span_ppm_per_c),
+            ..drift
+        };
+        crate::scale_manager::set_drift_config(drift)?;
+    }
+    Ok((adc, filter, drift))
+}
+
+/// Post a result to the backend; returns true if it was delivered
+fn post_result(result: &CommandResult) -> bool {
+    let body = match serde_json::to_string(result) {
+        Ok(b) => b,
+        Err(e) => {
+            warn!("Failed to serialize command result: {}", e);
+            return false;
+        }
+    };
+
+    let delivered = crate::backend_client::post_command_result(&body);
+    if delivered {
+        info!("Reported command {} result: {}", result.id, result.status);
+    }
+    delivered
+}
+
+/// Post a result to the backend, keeping it for retry on failure
+/// Returns true if the result was delivered now.
+fn report_result(result: CommandResult) -> bool {
+    if post_result(&result) {
+        return true;
+    }
+
+    let mut pending = PENDING_RESULTS.lock().unwrap();
+    if pending.len() >= MAX_PENDING_RESULTS {
+        pending.remove(0);
+    }
+    pending.push(result);
+    false
+}
+
+/// Post the result of a reboot command, retrying a few times
+/// A pending result wouldn't survive the restart, so it isn't kept.
+fn report_reboot_result(result: &CommandResult) {
+    for attempt in 1..=REBOOT_REPORT_ATTEMPTS {
+        if post_result(result) {
+            return;
+        }
+        if attempt < REBOOT_REPORT_ATTEMPTS {
+            std::thread::sleep(REBOOT_REPORT_RETRY);
+        }
+    }
+    warn!(
+        "Could not report reboot command {} after {} attempts, rebooting anyway",
+        result.id, REBOOT_REPORT_ATTEMPTS
+    );
+}
+
+/// Retry reporting results that could not be delivered earlier
+/// Called from the heartbeat path once the backend is reachable again.
+pub fn flush_pending_results() {
+    let pending: Vec<CommandResult> = std::mem::take(&mut *PENDING_RESULTS.lock().unwrap());
+    let mut results = pending.into_iter();
+    while let Some(result) = results.next() {
+        // Once one fails, keep the rest for the next heartbeat
+        if !report_result(result) {
+            PENDING_RESULTS.lock().unwrap().extend(results);
+            break;
+        }
+    }
+}
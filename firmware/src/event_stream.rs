@@ -0,0 +1,214 @@
+//! Push-based printer updates from the backend
+//!
+//! Keeps a Server-Sent Events subscription to `/api/display/events` open on a
+//! background thread. The backend sends a full `printers` snapshot when the
+//! stream opens and a `printer` event whenever one printer's state (including
+//! its AMS units) changes. While the stream is up, `poll_backend` skips the
+//! printer list fetch; if it drops, polling takes over until it reconnects.
+
+use embedded_svc::http::client::Client as HttpClient;
+use esp_idf_svc::http::client::EspHttpConnection;
+use log::{info, warn};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// Read timeout - the backend sends a keepalive comment every 15 seconds
+const STREAM_TIMEOUT_MS: u64 = 35000;
+
+/// Reconnect backoff bounds
+const RECONNECT_MIN_MS: u64 = 2000;
+const RECONNECT_MAX_MS: u64 = 30000;
+
+/// Largest event accepted (full printer list with AMS data)
+const MAX_EVENT_SIZE: usize = 32768;
+
+static CONNECTED: AtomicBool = AtomicBool::new(false);
+
+/// A parsed Server-Sent Event
+#[derive(Debug, Clone, PartialEq)]
+pub struct SseEvent {
+    pub event: String,
+    pub data: String,
+}
+
+/// Incremental Server-Sent Events parser
+/// Chunks can split lines (and UTF-8 sequences) anywhere.
+#[derive(Debug, Default)]
+pub struct SseParser {
+    line: Vec<u8>,
+    event: String,
+    data: String,
+    overflow: bool,
+}
+
+impl SseParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed received bytes, returning every event completed by them
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        let mut events = Vec::new();
+        for &b in chunk {
+            if b == b'\n' {
+                if let Some(event) = self.process_line() {
+                    events.push(event);
+                }
+                self.line.clear();
+            } else if self.line.len() < MAX_EVENT_SIZE {
+                self.line.push(b);
+            } else {
+                self.overflow = true;
+            }
+        }
+        events
+    }
+
+    fn process_line(&mut self) -> Option<SseEvent> {
+        if self.line.last() == Some(&b'\r') {
+            self.line.pop();
+        }
+
+        // Blank line dispatches the event
+        if self.line.is_empty() {
+            let event = std::mem::take(&mut self.event);
+            let data = std::mem::take(&mut self.data);
+            if std::mem::take(&mut self.overflow) {
+                warn!("Dropping oversized '{}' event", event);
+                return None;
+            }
+            if data.is_empty() {
+                return None;
+            }
+            let event = if event.is_empty() { String::from("message") } else { event };
+            return Some(SseEvent { event, data });
+        }
+
+        // Comment (keepalive)
+        if self.line[0] == b':' {
+            return None;
+        }
+
+        let line = String::from_utf8_lossy(&self.line);
+        let (field, value) = match line.find(':') {
+            Some(pos) => {
+                let value = &line[pos + 1..];
+                (&line[..pos], value.strip_prefix(' ').unwrap_or(value))
+            }
+            None => (&line[..], ""),
+        };
+
+        match field {
+            "event" => self.event = value.to_string(),
+            "data" => {
+                if !self.data.is_empty() {
+                    self.data.push('\n');
+                }
+                if self.data.len() + value.len() > MAX_EVENT_SIZE {
+                    self.overflow = true;
+                } else {
+                    self.data.push_str(value);
+                }
+            }
+            _ => {} // id / retry are not used
+        }
+        None
+    }
+}
+
+/// Check if the event stream is currently delivering updates
+pub fn is_connected() -> bool {
+    CONNECTED.load(Ordering::Relaxed)
+}
+
+/// Start the event stream thread
+pub fn init() {
+    let spawned = std::thread::Builder::new()
+        .name("backend_events".into())
+        .stack_size(12288)
+        .spawn(run);
+
+    if let Err(e) = spawned {
+        warn!("Failed to start backend event stream: {:?}", e);
+    }
+}
+
+fn run() {
+    let mut backoff_ms = RECONNECT_MIN_MS;
+
+    loop {
+        let base_url = match crate::backend_client::get_server_url() {
+            Some(url) if crate::wifi_manager::is_connected() => url,
+            _ => {
+                std::thread::sleep(Duration::from_millis(RECONNECT_MIN_MS));
+                continue;
+            }
+        };
+
+        match stream_events(&base_url) {
+            Ok(()) => {
+                info!("Backend event stream ended");
+                backoff_ms = RECONNECT_MIN_MS;
+            }
+            Err(e) => {
+                warn!("Backend event stream: {} (retry in {} ms)", e, backoff_ms);
+            }
+        }
+        CONNECTED.store(false, Ordering::Relaxed);
+
+        std::thread::sleep(Duration::from_millis(backoff_ms));
+        backoff_ms = (backoff_ms * 2).min(RECONNECT_MAX_MS);
+    }
+}
+
+/// Open the stream and apply events until it closes, fails or the server changes
+fn stream_events(base_url: &str) -> Result<(), String> {
+    let url = format!("{}/api/display/events", base_url);
+
+    let config = crate::tls_pin::http_config(Duration::from_millis(STREAM_TIMEOUT_MS));
+
+    let connection = EspHttpConnection::new(&config)
+        .map_err(|e| format!("HTTP connection failed: {:?}", e))?;
+
+    let mut client = HttpClient::wrap(connection);
+
+    let api_key = crate::device_auth::api_key();
+    let headers = crate::device_auth::with_api_key(&[("Accept", "text/event-stream")], &api_key);
+    let request = client.request(embedded_svc::http::Method::Get, &url, &headers)
+        .map_err(|e| format!("GET request failed: {:?}", e))?;
+
+    let mut response = request.submit()
+        .map_err(|e| format!("Request submit failed: {:?}", e))?;
+
+    let status = response.status();
+    crate::backend_client::note_auth_status(status);
+    if status != 200 {
+        return Err(format!("HTTP error: {}", status));
+    }
+
+    info!("Backend event stream connected");
+    let mut parser = SseParser::new();
+    let mut buf = [0u8; 512];
+
+    loop {
+        let n = response.read(&mut buf)
+            .map_err(|e| format!("Read error: {:?}", e))?;
+        if n == 0 {
+            return Ok(());
+        }
+
+        for event in parser.feed(&buf[..n]) {
+            if event.event == "printers" {
+                // Snapshot received - polling can stop
+                CONNECTED.store(true, Ordering::Relaxed);
+            }
+            crate::backend_client::handle_stream_event(&event.event, &event.data);
+        }
+
+        // Reconnect if the server URL was changed or cleared
+        if crate::backend_client::get_server_url().as_deref() != Some(base_url) {
+            info!("Backend server changed, closing event stream");
+            return Ok(());
+        }
+    }
+}
@@ -25,8 +25,8 @@ mod nfc;
 // Shared I2C bus for scale and NFC
 mod shared_i2c;
 
-// NFC bridge manager (Pico I2C bridge)
-mod nfc_bridge_manager;
+// NFC manager (Pico I2C bridge or PN5180 on SPI) with C-callable interface
+mod nfc_manager;
 
 // WiFi manager with C-callable interface
 mod wifi_manager;
@@ -34,13 +34,41 @@ mod wifi_manager;
 // Backend client for server communication
 mod backend_client;
 
+// mDNS / DNS-SD discovery of the backend server (host-tested in firmware-core)
+use spoolbuddy_firmware_core::mdns;
+
+// Device API key (sent with every backend request) and pairing
+mod device_auth;
+
+// TLS certificate pinning (trust on first use) for https backends
+mod tls_pin;
+
+// SHA-256, HMAC/HKDF and base64 (mbedTLS) for certificate fingerprints and tag keys (host-tested in firmware-core)
+use spoolbuddy_firmware_core::crypto;
+
+// Typed commands from the backend (executed on a worker thread)
+mod device_commands;
+
+// Offline queue for backend mutations (persisted in NVS)
+mod outbox;
+
+// Spool identity by tray UID (both tags of a Bambu spool, persisted in NVS)
+mod spool_identity;
+
+// Push-based printer updates (Server-Sent Events from the backend)
+mod event_stream;
+
+// Background worker that runs all backend HTTP off the UI loop
+mod backend_worker;
+
 // Time manager for NTP sync
 mod time_manager;
 
 // OTA update manager
 mod ota_manager;
 
-// Direct SPI NFC disabled - now using I2C bridge via Pico
+// Direct SPI PN5180 fallback when no Pico bridge is found. Off: its SPI pins
+// (J9 IO4/5/6) are used by the RGB LCD and UART0 on the CrowPanel
 const NFC_ENABLED: bool = false;
 
 // Display driver C functions (handles LVGL init and EEZ UI)
@@ -48,6 +76,7 @@ extern "C" {
     fn display_init() -> i32;
     fn display_tick();
     fn display_set_backlight_hw(brightness_percent: u8);
+    fn display_shutdown();
 }
 
 // =============================================================================
@@ -166,8 +195,13 @@ fn main() {
     let sysloop = EspSystemEventLoop::take().expect("Failed to take system event loop");
     let nvs = EspDefaultNvsPartition::take().ok();
 
-    // Clone NVS partition for scale calibration persistence
+    // Clone NVS partition for scale calibration, backend URL, API key, outbox and spool identity persistence
     let nvs_for_scale = nvs.clone();
+    let nvs_for_backend = nvs.clone();
+    let nvs_for_auth = nvs.clone();
+    let nvs_for_tls = nvs.clone();
+    let nvs_for_outbox = nvs.clone();
+    let nvs_for_spool_ids = nvs.clone();
 
     match wifi_manager::init_wifi_system(peripherals.modem, sysloop, nvs) {
         Ok(_) => info!("WiFi subsystem ready"),
@@ -178,7 +212,28 @@ fn main() {
     scale_manager::init_nvs(nvs_for_scale);
 
     // Initialize backend client (for server communication)
-    backend_client::init();
+    backend_client::init(nvs_for_backend);
+
+    // Load the device API key (pairing starts once the backend is reachable)
+    device_auth::init(nvs_for_auth);
+
+    // Install the pinned server certificate (https backends)
+    tls_pin::init(nvs_for_tls);
+
+    // Load mutations queued while the backend was unreachable
+    outbox::init(nvs_for_outbox);
+
+    // Load the chip UIDs known to belong to each spool
+    spool_identity::init(nvs_for_spool_ids);
+
+    // Start worker for backend I/O (polling, device state, lookups)
+    backend_worker::init();
+
+    // Start worker for backend commands (tare, calibrate, OTA, ...)
+    device_commands::init();
+
+    // Subscribe to printer updates (waits for WiFi and a server URL)
+    event_stream::init();
 
     // Initialize display, LVGL, and EEZ UI via C driver
     // Display uses I2C0 (GPIO15/16) for touch controller
@@ -236,7 +291,8 @@ fn main() {
             // Initialize scale if found
             if found_nau7802 {
                 let mut scale_state = scale::nau7802::Nau7802State::new();
-                match scale::nau7802::init(i2c_static, &mut scale_state) {
+                scale_state.adc_config = scale_manager::saved_adc_config();
+                match scale::nau7802::init(i2c_static, &mut FreeRtos, &mut scale_state) {
                     Ok(()) => {
                         info!("NAU7802 scale initialized");
                         scale_manager::init_scale_manager(scale_state);
@@ -249,12 +305,12 @@ fn main() {
             let i2c_owned = unsafe { Box::from_raw(i2c_static as *mut I2cDriver<'static>) };
             shared_i2c::init_shared_i2c(*i2c_owned);
 
-            // Initialize NFC bridge manager (uses shared I2C)
+            // Use the Pico NFC bridge as reader (uses shared I2C)
             if found_pico {
-                if nfc_bridge_manager::init_nfc_manager() {
-                    info!("NFC bridge manager initialized");
+                if nfc_manager::init_pico_reader() {
+                    info!("NFC manager initialized (Pico bridge)");
                 } else {
-                    warn!("NFC bridge manager init failed");
+                    warn!("NFC bridge init failed");
                 }
             }
         }
@@ -265,9 +321,9 @@ fn main() {
     info!("=== SHARED I2C DONE ===");
 
     // ==========================================================================
-    // Direct PN5180 SPI NFC - DISABLED (using I2C bridge via Pico instead)
+    // Direct PN5180 SPI NFC - only probed when no Pico bridge answered
     // ==========================================================================
-    if NFC_ENABLED {
+    if NFC_ENABLED && !nfc_manager::is_initialized() {
     // Working config from commit c27f680:
     // SPI pins on J9 header:
     //   - IO5 (J9 Pin 2) -> SCK
@@ -1069,9 +1125,9 @@ fn main() {
                         match nfc::pn5180::init_pn5180(spi_device, nss, None, None, &mut nfc_state) {
                             Ok(driver) => {
                                 info!("PN5180 NFC initialized successfully");
-                                // Store driver for later use in main loop
-                                // TODO: Add NFC manager to handle tag detection
-                                drop(driver);  // For now, just verify init works
+                                if nfc_manager::init_pn5180_reader(driver, nfc_state.firmware_version) {
+                                    info!("NFC manager initialized (PN5180)");
+                                }
                             }
                             Err(e) => warn!("PN5180 init failed: {:?}", e),
                         }
@@ -1108,23 +1164,22 @@ fn main() {
             if loop_count % 20 == 0 && wifi_manager::is_connected() {
                 // Initialize SNTP for time sync (may take time)
                 time_manager::init_sntp();
-                // Set backend server URL
-                backend_client::set_server_url("http://192.168.255.16:3000");
-                // Sync time immediately from backend (faster than SNTP)
-                backend_client::sync_time();
+                // Backend URL, time sync and first poll run on the worker
+                backend_worker::submit(backend_worker::Job::Connect);
                 WIFI_INIT_DONE.store(true, std::sync::atomic::Ordering::Relaxed);
-                info!("Post-WiFi init complete (SNTP + backend URL + time sync)");
-                // Immediate first poll for printer data
-                backend_client::poll_backend();
+                info!("Post-WiFi init complete (SNTP started, backend connect queued)");
             }
+        } else if loop_count % 2000 == 0 && backend_client::get_server_url().is_none() {
+            // No server yet - retry discovery every 10 seconds (runs in background)
+            backend_client::backend_discover_server();
         } else if loop_count % 400 == 0 {
-            // Regular polling every 2 seconds (full sync: printers, commands, etc.)
-            backend_client::poll_backend();
+            // Regular polling every 2 seconds (heartbeat, commands; printers unless streamed)
+            backend_worker::submit(backend_worker::Job::Poll);
         } else if loop_count % 100 == 0 {
             // Weight-only update every 500ms for faster UI feedback
             let weight = scale_manager::scale_get_weight();
             let stable = scale_manager::scale_is_stable();
-            backend_client::send_device_state(None, weight, stable);
+            backend_worker::submit_device_state(None, weight, stable);
         }
 
         // OTA check on startup (once, after WiFi init) - check but don't auto-install
@@ -1132,29 +1187,25 @@ fn main() {
         if WIFI_INIT_DONE.load(std::sync::atomic::Ordering::Relaxed)
             && !OTA_CHECK_DONE.load(std::sync::atomic::Ordering::Relaxed)
         {
-            OTA_CHECK_DONE.store(true, std::sync::atomic::Ordering::Relaxed);
-            info!("Firmware version: v{}", ota_manager::get_version());
-
-            // Check for updates and store result (don't auto-install)
-            match ota_manager::check_for_update("http://192.168.255.16:3000") {
-                Ok(info) => {
-                    if info.available {
-                        info!("Firmware update available: v{}", info.version);
-                        ota_manager::set_update_available(true, &info.version);
-                    } else {
-                        info!("Firmware is up to date");
-                        ota_manager::set_update_available(false, "");
-                    }
-                }
-                Err(e) => {
-                    warn!("OTA check failed: {}", e);
-                }
+            if backend_client::get_server_url().is_some() {
+                OTA_CHECK_DONE.store(true, std::sync::atomic::Ordering::Relaxed);
+                backend_worker::submit(backend_worker::Job::OtaCheck);
             }
         }
 
-        // Poll NFC bridge every 100 iterations (~500ms at 5ms delay)
-        if loop_count % 100 == 0 {
-            nfc_bridge_manager::poll_nfc();
+        // Step the NFC state machine every 4 iterations (~20ms at 5ms delay);
+        // each step is one short I2C transfer, scans are paced inside it
+        if loop_count % 4 == 0 {
+            nfc_manager::poll_nfc();
+        }
+
+        // Reboot requested by backend command (result already reported)
+        if device_commands::reboot_requested() {
+            info!("Rebooting on backend command");
+            // Properly shutdown display before reboot to prevent display shift
+            unsafe { display_shutdown(); }
+            FreeRtos::delay_ms(100);
+            unsafe { esp_idf_sys::esp_restart(); }
         }
 
         FreeRtos::delay_ms(5);
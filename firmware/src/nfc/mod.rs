@@ -20,12 +20,15 @@
 #[allow(dead_code)]
 pub mod pn5180;
 
-/// I2C bridge to Pico for NFC (recommended - more reliable than direct SPI)
-pub mod i2c_bridge;
+// Hardware-independent NFC modules (host-tested in firmware-core)
+pub use spoolbuddy_firmware_core::nfc::{
+    bridge_protocol, i2c_bridge, iso14443a, mifare_classic, pico_reader, pn5180_protocol, pn5180_reader, reader,
+    tag_formats, tag_poller,
+};
 
 // Re-exports will be used when NFC functionality is integrated
 #[allow(unused_imports)]
-pub use pn5180::{Pn5180State, Pn5180Error, Iso14443aCard, MifareKeyType, BAMBULAB_KEY};
+pub use pn5180::{Pn5180State, Pn5180Error, Iso14443aCard, MifareKeyType};
 #[allow(unused_imports)]
 pub use pn5180::{init_stub, detect_tag_stub, rf_field_on_stub, rf_field_off_stub};
 
@@ -42,6 +42,15 @@ use esp_idf_hal::delay::FreeRtos;
 use embedded_hal::spi::SpiDevice;
 use log::{info, warn};
 
+use super::i2c_bridge::{decode_bambu_tag, DecodedTagInfo};
+use super::iso14443a;
+use super::mifare_classic::{self, BambuKeys, BAMBU_BLOCKS};
+use super::pn5180_protocol::{self, Pn5180Io};
+
+pub use super::iso14443a::Iso14443aCard;
+pub use super::mifare_classic::MifareKeyType;
+pub use super::pn5180_protocol::{commands, registers, Pn5180Error};
+
 // =============================================================================
 // GPIO Pin Definitions for CrowPanel Advance 7.0"
 // =============================================================================
@@ -66,47 +75,6 @@ pub const PIN_BUSY: u8 = 2;  // IO2
 /// Hardware reset pin (J11 Pin 3) - active low
 pub const PIN_RST: u8 = 15;  // IO15
 
-/// PN5180 command codes
-#[allow(dead_code)]
-pub mod commands {
-    pub const WRITE_REGISTER: u8 = 0x00;
-    pub const WRITE_REGISTER_OR_MASK: u8 = 0x01;
-    pub const WRITE_REGISTER_AND_MASK: u8 = 0x02;
-    pub const READ_REGISTER: u8 = 0x04;
-    pub const WRITE_EEPROM: u8 = 0x06;
-    pub const READ_EEPROM: u8 = 0x07;
-    pub const SEND_DATA: u8 = 0x09;
-    pub const READ_DATA: u8 = 0x0A;
-    pub const SWITCH_MODE: u8 = 0x0B;
-    pub const MIFARE_AUTHENTICATE: u8 = 0x0C;
-    pub const EPC_INVENTORY: u8 = 0x0D;
-    pub const EPC_RESUME_INVENTORY: u8 = 0x0E;
-    pub const EPC_RETRIEVE_INVENTORY_RESULT_SIZE: u8 = 0x0F;
-    pub const EPC_RETRIEVE_INVENTORY_RESULT: u8 = 0x10;
-    pub const LOAD_RF_CONFIG: u8 = 0x11;
-    pub const UPDATE_RF_CONFIG: u8 = 0x12;
-    pub const RETRIEVE_RF_CONFIG_SIZE: u8 = 0x13;
-    pub const RETRIEVE_RF_CONFIG: u8 = 0x14;
-    pub const RF_ON: u8 = 0x16;
-    pub const RF_OFF: u8 = 0x17;
-}
-
-/// PN5180 register addresses
-#[allow(dead_code)]
-pub mod registers {
-    pub const SYSTEM_CONFIG: u8 = 0x00;
-    pub const IRQ_ENABLE: u8 = 0x01;
-    pub const IRQ_STATUS: u8 = 0x02;
-    pub const IRQ_CLEAR: u8 = 0x03;
-    pub const TRANSCEIVE_CONTROL: u8 = 0x04;
-    pub const TIMER1_CONFIG: u8 = 0x0F;
-    pub const TIMER1_RELOAD: u8 = 0x10;
-    pub const TIMER1_VALUE: u8 = 0x11;
-    pub const TX_DATA_NUM: u8 = 0x14;
-    pub const RX_STATUS: u8 = 0x15;
-    pub const RF_STATUS: u8 = 0x1D;
-}
-
 /// RF configuration protocols
 #[allow(dead_code)]
 pub mod rf_config {
@@ -120,17 +88,6 @@ pub mod rf_config {
     pub const ISO_14443A_848_RX: u8 = 0x83;
 }
 
-/// MIFARE authentication key type
-#[derive(Debug, Clone, Copy)]
-pub enum MifareKeyType {
-    KeyA,
-    KeyB,
-}
-
-/// Bambu Lab MIFARE key (Crypto-1)
-/// Note: This is the known key for reading Bambu Lab tags
-pub const BAMBULAB_KEY: [u8; 6] = [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]; // Placeholder - actual key needed
-
 /// PN5180 driver state (without hardware - for init tracking)
 pub struct Pn5180State {
     /// Whether the PN5180 has been initialized
@@ -209,49 +166,6 @@ pub fn rf_field_off_stub(state: &mut Pn5180State) -> Result<(), Pn5180Error> {
     Ok(())
 }
 
-/// ISO 14443A card info
-#[derive(Debug, Clone)]
-pub struct Iso14443aCard {
-    /// UID (4, 7, or 10 bytes)
-    pub uid: [u8; 10],
-    /// UID length (4, 7, or 10)
-    pub uid_len: u8,
-    /// ATQA (2 bytes)
-    pub atqa: [u8; 2],
-    /// SAK byte
-    pub sak: u8,
-}
-
-impl Iso14443aCard {
-    /// Check if this is an NTAG (based on SAK)
-    pub fn is_ntag(&self) -> bool {
-        self.sak == 0x00
-    }
-
-    /// Check if this is a MIFARE Classic 1K (based on SAK)
-    pub fn is_mifare_classic_1k(&self) -> bool {
-        self.sak == 0x08
-    }
-
-    /// Check if this is a MIFARE Classic 4K (based on SAK)
-    pub fn is_mifare_classic_4k(&self) -> bool {
-        self.sak == 0x18
-    }
-}
-
-/// PN5180 errors
-#[derive(Debug, Clone, Copy)]
-pub enum Pn5180Error {
-    SpiError,
-    GpioError,
-    Timeout,
-    NoCard,
-    AuthFailed,
-    ReadFailed,
-    WriteFailed,
-    InvalidResponse,
-}
-
 // =============================================================================
 // REAL IMPLEMENTATION - PN5180 Driver
 // =============================================================================
@@ -762,18 +676,12 @@ where
 
     /// Read register (32-bit) - single read with delay
     pub fn read_register(&mut self, reg: u8) -> Result<u32, Pn5180Error> {
-        let cmd = [commands::READ_REGISTER, reg];
-        let mut response = [0u8; 4];
-        self.send_command_read(&cmd, &mut response)?;
-        let value = u32::from_le_bytes(response);
-        Ok(value)
+        pn5180_protocol::read_register(self, reg)
     }
 
     /// Write register (32-bit)
     pub fn write_register(&mut self, reg: u8, value: u32) -> Result<(), Pn5180Error> {
-        let bytes = value.to_le_bytes();
-        let cmd = [commands::WRITE_REGISTER, reg, bytes[0], bytes[1], bytes[2], bytes[3]];
-        self.send_command(&cmd)
+        pn5180_protocol::write_register(self, reg, value)
     }
 
     /// Get firmware version
@@ -853,77 +761,66 @@ where
         self.send_command(&cmd)
     }
 
-    /// Send ISO14443A REQA/WUPA and get ATQA
+    /// Activate a card in the field: WUPA, anticollision and SELECT on every
+    /// cascade level. WUPA also wakes cards left halted by the last poll.
     pub fn iso14443a_activate(&mut self) -> Result<Option<Iso14443aCard>, Pn5180Error> {
-        // Check RF status first
-        let rf_status = self.read_register(registers::RF_STATUS)?;
-        info!("  RF_STATUS: 0x{:08X}", rf_status);
-
-        // Clear IRQ status
-        self.write_register(registers::IRQ_CLEAR, 0xFFFFFFFF)?;
-
-        // Configure TX_DATA_NUM for REQA short frame (7 bits)
-        // Register format:
-        //   Bits 15:0  = number of bytes to transmit (1 for REQA)
-        //   Bits 18:16 = number of valid bits in last byte (7 for REQA)
-        let tx_data_num = (7u32 << 16) | 1u32;  // 7 valid bits, 1 byte
-        self.write_register(registers::TX_DATA_NUM, tx_data_num)?;
-
-        // Send REQA (0x26) using SEND_DATA command
-        // The SEND_DATA command sends the bytes following the command
-        let send_cmd = [
-            commands::SEND_DATA,
-            0x26,  // REQA command byte
-        ];
-        self.send_command(&send_cmd)?;
-
-        FreeRtos::delay_ms(10);  // Wait for card response
+        // A card left authenticated would not answer in plain
+        pn5180_protocol::crypto1_off(self)?;
 
-        // Check IRQ status
-        let irq_status = self.read_register(registers::IRQ_STATUS)?;
-        info!("  IRQ_STATUS: 0x{:08X}", irq_status);
-
-        // Check RX status
-        let rx_status = self.read_register(registers::RX_STATUS)?;
-        info!("  RX_STATUS: 0x{:08X}", rx_status);
+        let card = iso14443a::activate(self, iso14443a::WUPA)?;
+        if let Some(ref card) = card {
+            info!("  ATQA: {:02X} {:02X}, SAK: {:02X}, UID length: {}",
+                  card.atqa[0], card.atqa[1], card.sak, card.uid_len);
+        }
+        Ok(card)
+    }
 
-        let rx_len = (rx_status & 0x1FF) as usize;
-        let rx_collision = (rx_status >> 17) & 0x01;  // Bit 17 is collision flag
+    /// Halt the selected card (it then ignores REQA until it leaves the field)
+    pub fn iso14443a_halt(&mut self) -> Result<(), Pn5180Error> {
+        iso14443a::halt(self)
+    }
 
-        info!("  RX bytes: {}, collision: {}", rx_len, rx_collision);
+    /// Authenticate the MIFARE Classic sector holding `block` of the selected card
+    pub fn mifare_authenticate(
+        &mut self,
+        block: u8,
+        key_type: MifareKeyType,
+        key: &[u8; 6],
+        uid: &[u8],
+    ) -> Result<(), Pn5180Error> {
+        pn5180_protocol::mifare_authenticate(self, block, key_type, key, uid)
+    }
 
-        // Validate RX status - 0xFFFFFFFF or large values indicate SPI error
-        if rx_status == 0xFFFFFFFF || rx_len > 64 {
-            info!("  Invalid RX_STATUS (SPI error)");
-            return Ok(None);
-        }
+    /// Read a 16 byte block of the authenticated sector
+    pub fn mifare_read_block(&mut self, block: u8) -> Result<[u8; 16], Pn5180Error> {
+        mifare_classic::read_block(self, block)
+    }
 
-        if rx_len < 2 {
-            return Ok(None); // No card
-        }
+    /// Read the Bambu Lab filament blocks of a selected MIFARE Classic card
+    /// (the same blocks the Pico bridge reads) and decode them
+    pub fn read_bambu_tag(&mut self, card: &Iso14443aCard) -> Result<DecodedTagInfo, Pn5180Error> {
+        let data = mifare_classic::read_blocks(self, card, &BambuKeys, &BAMBU_BLOCKS)?;
+        let decoded = decode_bambu_tag(&data);
+        info!("  Bambu Lab tag: {} {}", decoded.material, decoded.material_subtype);
+        Ok(decoded)
+    }
+}
 
-        // Read ATQA response from RX buffer
-        // READ_DATA command is just the command byte, then read the response
-        let mut atqa = [0u8; 2];
-        let read_cmd = [commands::READ_DATA];  // No extra bytes needed
-        self.send_command_read(&read_cmd, &mut atqa)?;
-        info!("  ATQA: {:02X} {:02X}", atqa[0], atqa[1]);
-
-        // Validate ATQA - 0x0000 or 0xFFFF are invalid
-        if atqa == [0x00, 0x00] || atqa == [0xFF, 0xFF] {
-            info!("  Invalid ATQA (SPI error or no card)");
-            return Ok(None);
-        }
+/// PN5180 command frames over SPI, with NSS and BUSY handled by the driver
+impl<SPI> Pn5180Io for Pn5180Driver<'_, SPI>
+where
+    SPI: SpiDevice,
+{
+    fn command(&mut self, cmd: &[u8]) -> Result<(), Pn5180Error> {
+        self.send_command(cmd)
+    }
 
-        // TODO: Full anti-collision sequence to get UID
-        // For now, just return what we have
+    fn command_read(&mut self, cmd: &[u8], response: &mut [u8]) -> Result<(), Pn5180Error> {
+        self.send_command_read(cmd, response)
+    }
 
-        Ok(Some(Iso14443aCard {
-            uid: [0; 10],
-            uid_len: 0,
-            atqa,
-            sak: 0,
-        }))
+    fn delay_ms(&mut self, ms: u32) {
+        FreeRtos::delay_ms(ms);
     }
 }
 
@@ -1,332 +1,852 @@
 //! NFC Manager with C-callable interface
 //!
-//! Provides FFI functions for the C UI code to access NFC data.
-//! Manages the PN5180 driver and handles card detection/reading.
+//! Provides FFI functions for the C UI code to access NFC tag data.
+//! The reader is picked at boot: the Pico bridge if it answers on I2C
+//! address 0x55, otherwise a PN5180 on SPI. Either way it is driven through
+//! [`NfcReader`] by the tag state machine.
 
-use esp_idf_hal::gpio::{Input, Output, PinDriver};
 use embedded_hal::spi::SpiDevice;
 use log::{info, warn};
-use std::sync::Mutex;
-
-use crate::nfc::pn5180::{self, Iso14443aCard, Pn5180Driver, Pn5180Error, Pn5180State};
-
-/// Global NFC state protected by mutex
-static NFC_STATE: Mutex<Option<NfcManagerState>> = Mutex::new(None);
-
-/// NFC manager state (without driver - driver is type-erased)
-struct NfcManagerState {
-    /// PN5180 state
-    state: Pn5180State,
-    /// Last detected card info
-    last_card: Option<CardInfo>,
-    /// Whether RF field is on
-    rf_on: bool,
-    /// Card present flag
-    card_present: bool,
-    /// Poll counter
-    poll_count: u32,
+use std::ffi::{c_char, c_int};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+use crate::nfc::bridge_protocol::BridgeTransport;
+use crate::nfc::i2c_bridge::{DecodedTagInfo, PICO_NFC_ADDR};
+use crate::nfc::pico_reader::PicoReader;
+use crate::nfc::pn5180::Pn5180Driver;
+use crate::nfc::pn5180_reader::Pn5180Reader;
+use crate::nfc::reader::{DetectedTag, NfcReader, TagKind};
+use crate::nfc::tag_formats::{self, TagWriteFormat};
+use crate::nfc::tag_poller::{NfcEvent, TagPoller};
+use crate::shared_i2c;
+
+/// The reader found at boot (locked before NFC_POLLER)
+static NFC_READER: Mutex<Option<Box<dyn NfcReader + Send>>> = Mutex::new(None);
+
+/// Scan/read/write state machine (locked after NFC_READER, before TAG_WRITE)
+static NFC_POLLER: Mutex<TagPoller> = Mutex::new(TagPoller::new());
+
+/// Reader and tag as seen by the FFI (never held with the other locks)
+static NFC_STATE: Mutex<Option<NfcState>> = Mutex::new(None);
+
+/// Time base for the state machine's timeouts and scan interval
+static NFC_EPOCH: OnceLock<Instant> = OnceLock::new();
+
+/// Tag write states reported to C (TAG_WRITE_* in ui_nfc_card.c)
+const TAG_WRITE_IDLE: c_int = 0;
+const TAG_WRITE_PENDING: c_int = 1;
+const TAG_WRITE_OK: c_int = 2;
+const TAG_WRITE_FAILED: c_int = 3;
+
+struct NfcState {
+    firmware_version: (u8, u8, u8),
+    /// Tag on the reader
+    tag: Option<DetectedTag>,
 }
 
-/// Card information for FFI
-#[repr(C)]
-#[derive(Debug, Clone)]
-pub struct CardInfo {
-    /// UID bytes (up to 10)
-    pub uid: [u8; 10],
-    /// UID length (4, 7, or 10)
-    pub uid_len: u8,
-    /// ATQA bytes
-    pub atqa: [u8; 2],
-    /// SAK byte
-    pub sak: u8,
-    /// Card type (0=unknown, 1=NTAG, 2=MIFARE Classic 1K, 3=MIFARE Classic 4K)
-    pub card_type: u8,
-}
-
-impl From<&Iso14443aCard> for CardInfo {
-    fn from(card: &Iso14443aCard) -> Self {
-        let card_type = if card.is_ntag() {
-            1
-        } else if card.is_mifare_classic_1k() {
-            2
-        } else if card.is_mifare_classic_4k() {
-            3
-        } else {
-            0
-        };
+/// A tag write requested by the UI, handed to the state machine by `poll_nfc`
+struct TagWriteRequest {
+    tag: DetectedTag,
+    format: TagWriteFormat,
+    info: DecodedTagInfo,
+}
 
-        CardInfo {
-            uid: card.uid,
-            uid_len: card.uid_len,
-            atqa: card.atqa,
-            sak: card.sak,
-            card_type,
-        }
-    }
+struct TagWrite {
+    status: c_int,
+    message: String,
+    request: Option<TagWriteRequest>,
 }
 
+static TAG_WRITE: Mutex<TagWrite> = Mutex::new(TagWrite {
+    status: TAG_WRITE_IDLE,
+    message: String::new(),
+    request: None,
+});
+
 /// NFC status for C code
 #[repr(C)]
 pub struct NfcStatus {
     pub initialized: bool,
-    pub rf_on: bool,
-    pub card_present: bool,
-    pub firmware_major: u8,
-    pub firmware_minor: u8,
-    pub firmware_patch: u8,
-}
-
-/// Initialize the NFC manager (called after driver init)
-pub fn init_nfc_manager(state: Pn5180State) {
-    let mut guard = NFC_STATE.lock().unwrap();
-    *guard = Some(NfcManagerState {
-        state,
-        last_card: None,
-        rf_on: false,
-        card_present: false,
-        poll_count: 0,
-    });
-    info!("NFC manager initialized");
+    pub tag_present: bool,
+    pub uid_len: u8,
+    pub uid: [u8; 10],
 }
 
-/// Store driver reference for polling
-/// Note: We use a separate static for the driver due to type complexity
-static NFC_DRIVER: Mutex<Option<NfcDriverHolder>> = Mutex::new(None);
+/// The shared I2C bus as transport to the Pico. The bus is taken for each
+/// transfer only, so the scale can use it while the Pico works on a tag.
+struct SharedI2cBus;
 
-/// Type-erased driver holder
-/// Note: Not Send because driver uses raw pointers internally
-struct NfcDriverHolder {
-    /// Function pointer to poll for cards
-    poll_fn: Box<dyn FnMut() -> Result<Option<Iso14443aCard>, Pn5180Error>>,
-    /// Function pointer to turn RF on
-    rf_on_fn: Box<dyn FnMut() -> Result<(), Pn5180Error>>,
-    /// Function pointer to turn RF off
-    rf_off_fn: Box<dyn FnMut() -> Result<(), Pn5180Error>>,
-}
-
-/// Initialize NFC driver holder with the actual driver
-pub fn init_nfc_driver<'a, SPI>(
-    mut driver: Pn5180Driver<'a, SPI>,
-) where
-    SPI: SpiDevice + Send + 'static,
-{
-    // We need to leak the driver to get 'static lifetime
-    let driver = Box::leak(Box::new(driver));
+impl BridgeTransport for SharedI2cBus {
+    fn write(&mut self, data: &[u8]) -> Result<(), &'static str> {
+        shared_i2c::with_i2c(|i2c| i2c.write(PICO_NFC_ADDR, data, 100).map_err(|_| "I2C write failed"))
+            .unwrap_or(Err("I2C bus not initialized"))
+    }
 
-    // Create closures that capture the driver
-    let driver_ptr = driver as *mut Pn5180Driver<'static, SPI>;
+    fn read(&mut self, buf: &mut [u8]) -> Result<(), &'static str> {
+        shared_i2c::with_i2c(|i2c| i2c.read(PICO_NFC_ADDR, buf, 100).map_err(|_| "I2C read failed"))
+            .unwrap_or(Err("I2C bus not initialized"))
+    }
 
-    let poll_fn = Box::new(move || {
-        let driver = unsafe { &mut *driver_ptr };
-        driver.iso14443a_activate()
-    });
+    fn delay_ms(&mut self, ms: u32) {
+        std::thread::sleep(std::time::Duration::from_millis(ms as u64));
+    }
+}
 
-    let driver_ptr2 = driver_ptr;
-    let rf_on_fn = Box::new(move || {
-        let driver = unsafe { &mut *driver_ptr2 };
-        driver.rf_on()
-    });
+/// Use the Pico bridge on the shared I2C bus as the reader
+pub fn init_pico_reader() -> bool {
+    match PicoReader::init(SharedI2cBus) {
+        Ok(reader) => {
+            install_reader(Box::new(reader));
+            true
+        }
+        Err(e) => {
+            warn!("NFC bridge init failed: {}", e);
+            false
+        }
+    }
+}
 
-    let driver_ptr3 = driver_ptr;
-    let rf_off_fn = Box::new(move || {
-        let driver = unsafe { &mut *driver_ptr3 };
-        driver.rf_off()
-    });
+/// Use a PN5180 on SPI (initialized, ISO14443A configuration loaded) as the reader
+pub fn init_pn5180_reader<SPI>(mut driver: Pn5180Driver<'static, SPI>, firmware_version: (u8, u8, u8)) -> bool
+where
+    SPI: SpiDevice + Send + 'static,
+    Pn5180Driver<'static, SPI>: Send,
+{
+    if let Err(e) = driver.rf_on() {
+        warn!("Failed to enable RF field: {:?}", e);
+        return false;
+    }
+    install_reader(Box::new(Pn5180Reader::new(driver, firmware_version)));
+    true
+}
 
-    let mut guard = NFC_DRIVER.lock().unwrap();
-    *guard = Some(NfcDriverHolder {
-        poll_fn,
-        rf_on_fn,
-        rf_off_fn,
+fn install_reader(reader: Box<dyn NfcReader + Send>) {
+    let (major, minor, patch) = reader.firmware_version();
+    info!("NFC reader: {} (firmware {}.{}.{})", reader.name(), major, minor, patch);
+    *NFC_STATE.lock().unwrap() = Some(NfcState {
+        firmware_version: reader.firmware_version(),
+        tag: None,
     });
+    *NFC_READER.lock().unwrap() = Some(reader);
+}
 
-    info!("NFC driver holder initialized");
+/// Whether a reader was found at boot
+pub fn is_initialized() -> bool {
+    NFC_STATE.lock().unwrap().is_some()
 }
 
-/// Poll for NFC cards (call from main loop)
+/// Poll the NFC reader (call from main loop)
+/// Advances the tag state machine by one step: with the Pico bridge each call
+/// holds the I2C bus for a single short transfer, so call it often.
 pub fn poll_nfc() {
-    let mut driver_guard = NFC_DRIVER.lock().unwrap();
-    let mut state_guard = NFC_STATE.lock().unwrap();
+    let now_ms = NFC_EPOCH.get_or_init(Instant::now).elapsed().as_millis() as u64;
 
-    if let (Some(ref mut driver), Some(ref mut state)) = (&mut *driver_guard, &mut *state_guard) {
-        state.poll_count += 1;
-
-        // Only poll every 10th call to reduce overhead
-        if state.poll_count % 10 != 0 {
+    // Step the state machine, then release locks before queueing backend updates
+    let (events, tag) = {
+        let mut reader = NFC_READER.lock().unwrap();
+        let Some(reader) = reader.as_mut() else {
             return;
-        }
+        };
+        let mut poller = NFC_POLLER.lock().unwrap();
 
-        // Ensure RF is on
-        if !state.rf_on {
-            match (driver.rf_on_fn)() {
-                Ok(()) => {
-                    state.rf_on = true;
-                    info!("NFC RF field enabled");
-                }
-                Err(e) => {
-                    warn!("Failed to enable RF field: {:?}", e);
-                    return;
-                }
-            }
+        // Hand a write the UI queued to the state machine
+        let request = TAG_WRITE.lock().unwrap().request.take();
+        if let Some(request) = request {
+            info!("Starting {} tag write", request.format.name());
+            poller.queue_write(request.tag, tag_formats::encode_ntag(request.format, &request.info));
         }
 
-        // Try to detect a card
-        match (driver.poll_fn)() {
-            Ok(Some(card)) => {
-                if !state.card_present {
-                    info!("NFC card detected! ATQA: {:02X}{:02X}, SAK: {:02X}",
-                          card.atqa[0], card.atqa[1], card.sak);
-                    if card.uid_len > 0 {
-                        let uid_str: String = card.uid[..card.uid_len as usize]
-                            .iter()
-                            .map(|b| format!("{:02X}", b))
-                            .collect::<Vec<_>>()
-                            .join(":");
-                        info!("  UID: {}", uid_str);
-                    }
-                }
-                state.card_present = true;
-                state.last_card = Some(CardInfo::from(&card));
+        let events = poller.step(reader.as_mut(), now_ms);
+        (events, poller.present().cloned())
+    }; // Release NFC_READER and NFC_POLLER here
+
+    if let Some(state) = NFC_STATE.lock().unwrap().as_mut() {
+        state.tag = tag;
+    }
+
+    for event in events {
+        handle_event(event);
+    }
+}
+
+/// Act on a state machine event (FFI tag data, write status, backend updates)
+fn handle_event(event: NfcEvent) {
+    // Hand backend updates to the worker (no HTTP on this thread)
+    let weight = crate::scale_manager::scale_get_weight();
+    let stable = crate::scale_manager::scale_is_stable();
+
+    match event {
+        NfcEvent::TagDetected { tag } => {
+            let uid_hex = tag.uid_hex();
+            // Prefetch the inventory spool so the tag popup can open without waiting
+            crate::backend_client::start_spool_lookup(&uid_hex);
+            crate::backend_worker::submit_device_state(Some(&uid_hex), weight, stable);
+        }
+        NfcEvent::TagDecoded { uid_hex, info } => {
+            set_decoded_info(&info);
+            info!("Tag decoded: {} {} {} ({}g)", info.vendor, info.material, info.color_name, info.spool_weight);
+            // The first read of this side of a spool: its other tags may be linked in the
+            // inventory, unless the lookup on detection already found the spool
+            if crate::spool_identity::record(&uid_hex, &info.tray_uid)
+                && !crate::backend_client::spool_lookup_found(&uid_hex)
+            {
+                crate::backend_client::start_spool_lookup(&uid_hex);
             }
-            Ok(None) => {
-                if state.card_present {
-                    info!("NFC card removed");
+            crate::backend_worker::submit_device_state(Some(&uid_hex), weight, stable);
+        }
+        NfcEvent::TagRemoved => {
+            clear_decoded_tag_data();
+            crate::backend_worker::submit_device_state(None, weight, stable);
+        }
+        NfcEvent::WriteFinished { uid_hex, result } => match result {
+            Ok(info) => {
+                set_decoded_info(&info);
+                {
+                    let mut write = TAG_WRITE.lock().unwrap();
+                    write.status = TAG_WRITE_OK;
+                    write.message = format!("{} data written", info.tag_type_name);
                 }
-                state.card_present = false;
+                crate::backend_worker::submit_device_state(Some(&uid_hex), weight, stable);
             }
             Err(e) => {
-                // Timeout is normal when no card present
-                if !matches!(e, Pn5180Error::Timeout) {
-                    warn!("NFC poll error: {:?}", e);
-                }
-                state.card_present = false;
+                let mut write = TAG_WRITE.lock().unwrap();
+                write.status = TAG_WRITE_FAILED;
+                write.message = e;
             }
-        }
+        },
     }
 }
 
+/// Copy decoded data to FFI storage
+fn set_decoded_info(info: &DecodedTagInfo) {
+    set_decoded_tag_data(
+        &info.vendor,
+        &info.material,
+        &info.material_subtype,
+        &info.color_name,
+        info.color_rgba,
+        info.spool_weight,
+        &info.tag_type_name,
+    );
+    set_decoded_tag_details(info);
+}
+
+/// UID of the tag on the reader
+fn present_uid() -> Option<Vec<u8>> {
+    NFC_STATE.lock().unwrap().as_ref().and_then(|state| state.tag.as_ref()).map(|tag| tag.uid.clone())
+}
+
 // =============================================================================
 // C-callable FFI functions
 // =============================================================================
 
-/// Get NFC status
+/// Get current NFC status
 #[no_mangle]
 pub extern "C" fn nfc_get_status(status: *mut NfcStatus) {
     if status.is_null() {
         return;
     }
 
-    let guard = NFC_STATE.lock().unwrap();
+    let initialized = is_initialized();
+    let uid = present_uid();
     let status = unsafe { &mut *status };
 
-    if let Some(ref manager) = *guard {
-        status.initialized = manager.state.initialized;
-        status.rf_on = manager.rf_on;
-        status.card_present = manager.card_present;
-        status.firmware_major = manager.state.firmware_version.0;
-        status.firmware_minor = manager.state.firmware_version.1;
-        status.firmware_patch = manager.state.firmware_version.2;
-    } else {
-        status.initialized = false;
-        status.rf_on = false;
-        status.card_present = false;
-        status.firmware_major = 0;
-        status.firmware_minor = 0;
-        status.firmware_patch = 0;
+    status.initialized = initialized;
+    status.tag_present = uid.is_some();
+    status.uid = [0; 10];
+    status.uid_len = 0;
+    if let Some(uid) = uid {
+        let len = uid.len().min(status.uid.len());
+        status.uid[..len].copy_from_slice(&uid[..len]);
+        status.uid_len = len as u8;
     }
 }
 
 /// Check if NFC is initialized
 #[no_mangle]
 pub extern "C" fn nfc_is_initialized() -> bool {
-    let guard = NFC_STATE.lock().unwrap();
-    if let Some(ref manager) = *guard {
-        manager.state.initialized
-    } else {
-        false
-    }
+    is_initialized()
 }
 
-/// Check if a card is present
+/// Check if a tag is present
 #[no_mangle]
-pub extern "C" fn nfc_card_present() -> bool {
-    let guard = NFC_STATE.lock().unwrap();
-    if let Some(ref manager) = *guard {
-        manager.card_present
-    } else {
-        false
-    }
+pub extern "C" fn nfc_tag_present() -> bool {
+    present_uid().is_some()
 }
 
-/// Get last detected card info
-/// Returns true if card info is available, false otherwise
+/// Get tag UID length (0 if no tag)
 #[no_mangle]
-pub extern "C" fn nfc_get_card_info(info: *mut CardInfo) -> bool {
-    if info.is_null() {
-        return false;
+pub extern "C" fn nfc_get_uid_len() -> u8 {
+    present_uid().map_or(0, |uid| uid.len() as u8)
+}
+
+/// Copy tag UID to buffer (returns actual length copied)
+#[no_mangle]
+pub extern "C" fn nfc_get_uid(buf: *mut u8, buf_len: u8) -> u8 {
+    if buf.is_null() || buf_len == 0 {
+        return 0;
     }
 
-    let guard = NFC_STATE.lock().unwrap();
-    if let Some(ref manager) = *guard {
-        if let Some(ref card) = manager.last_card {
+    match present_uid() {
+        Some(uid) if !uid.is_empty() => {
+            let copy_len = std::cmp::min(uid.len(), buf_len as usize);
             unsafe {
-                *info = card.clone();
+                std::ptr::copy_nonoverlapping(uid.as_ptr(), buf, copy_len);
             }
-            return true;
+            copy_len as u8
         }
+        _ => 0,
     }
-    false
 }
 
-/// Get card UID as hex string (returns length, 0 if no card)
+/// Get UID as hex string (for display)
+/// Writes to buf, returns length written (not including null terminator)
 #[no_mangle]
-pub extern "C" fn nfc_get_uid_hex(buf: *mut u8, buf_len: usize) -> usize {
+pub extern "C" fn nfc_get_uid_hex(buf: *mut u8, buf_len: u8) -> u8 {
     if buf.is_null() || buf_len < 3 {
         return 0;
     }
 
-    let guard = NFC_STATE.lock().unwrap();
-    if let Some(ref manager) = *guard {
-        if let Some(ref card) = manager.last_card {
-            let uid_len = card.uid_len as usize;
-            // Each byte becomes 2 hex chars + colons between
-            let needed = uid_len * 2 + uid_len.saturating_sub(1);
-            if needed > buf_len {
-                return 0;
-            }
+    let uid = match present_uid() {
+        Some(uid) if !uid.is_empty() => uid,
+        _ => return 0,
+    };
 
-            let mut pos = 0;
-            for (i, byte) in card.uid[..uid_len].iter().enumerate() {
-                if i > 0 {
-                    unsafe { *buf.add(pos) = b':'; }
-                    pos += 1;
-                }
-                let hex = format!("{:02X}", byte);
-                unsafe {
-                    *buf.add(pos) = hex.as_bytes()[0];
-                    *buf.add(pos + 1) = hex.as_bytes()[1];
-                }
-                pos += 2;
+    // Format: "XX:XX:XX:XX" - each byte is 2 chars + separator
+    let max_bytes = ((buf_len as usize) + 1) / 3;  // Account for : separators
+    let uid_len = std::cmp::min(uid.len(), max_bytes);
+
+    let mut pos = 0usize;
+    for (i, &byte) in uid[..uid_len].iter().enumerate() {
+        if pos + 2 > buf_len as usize {
+            break;
+        }
+        let hex_chars: [u8; 16] = *b"0123456789ABCDEF";
+        unsafe {
+            *buf.add(pos) = hex_chars[(byte >> 4) as usize];
+            *buf.add(pos + 1) = hex_chars[(byte & 0x0F) as usize];
+        }
+        pos += 2;
+
+        // Add separator if not last byte
+        if i < uid_len - 1 && pos < buf_len as usize {
+            unsafe {
+                *buf.add(pos) = b':';
             }
-            return pos;
+            pos += 1;
         }
     }
-    0
+
+    pos as u8
+}
+
+/// Get the spool identity of the tag on the reader: the tray UID of a Bambu
+/// Lab tag (shared by both tags of the spool), else the UID as hex
+/// Writes a null-terminated string to buf, returns length written
+#[no_mangle]
+pub extern "C" fn nfc_get_spool_id(buf: *mut u8, buf_len: u8) -> u8 {
+    if buf.is_null() || buf_len == 0 {
+        return 0;
+    }
+
+    let tag_uid = {
+        let state = NFC_STATE.lock().unwrap();
+        match state.as_ref().and_then(|state| state.tag.as_ref()) {
+            Some(tag) if !tag.uid.is_empty() => tag.uid_hex(),
+            _ => return 0,
+        }
+    };
+    let spool_id = crate::spool_identity::spool_id(&tag_uid);
+
+    let len = spool_id.len().min(buf_len as usize - 1);
+    unsafe {
+        std::ptr::copy_nonoverlapping(spool_id.as_ptr(), buf, len);
+        *buf.add(len) = 0;
+    }
+    len as u8
 }
 
-/// Get firmware version string
+/// Get the reader's firmware version (0.0.0 if no reader)
 #[no_mangle]
 pub extern "C" fn nfc_get_firmware_version(major: *mut u8, minor: *mut u8, patch: *mut u8) {
-    let guard = NFC_STATE.lock().unwrap();
-    if let Some(ref manager) = *guard {
-        if !major.is_null() {
-            unsafe { *major = manager.state.firmware_version.0; }
+    let version = NFC_STATE.lock().unwrap().as_ref().map_or((0, 0, 0), |state| state.firmware_version);
+    for (ptr, value) in [(major, version.0), (minor, version.1), (patch, version.2)] {
+        if !ptr.is_null() {
+            unsafe { *ptr = value; }
+        }
+    }
+}
+
+/// Queue writing a spool's inventory data to the tag on the reader
+/// format: 0 = OpenSpool, 1 = OpenPrintTag
+/// Returns 0 if queued, -1 if no tag, unknown spool or a write is already running
+#[no_mangle]
+pub extern "C" fn nfc_write_spool_tag(tag_id: *const c_char, format: c_int) -> c_int {
+    if tag_id.is_null() {
+        return -1;
+    }
+    let tag_id = match unsafe { std::ffi::CStr::from_ptr(tag_id) }.to_str() {
+        Ok(s) => s.to_string(),
+        Err(_) => return -1,
+    };
+
+    let format = match TagWriteFormat::from_c(format) {
+        Some(format) => format,
+        None => return -1,
+    };
+
+    // Not nested in TAG_WRITE (see NFC_STATE)
+    let tag = NFC_STATE.lock().unwrap().as_ref().and_then(|state| state.tag.clone());
+
+    let mut write = TAG_WRITE.lock().unwrap();
+    if write.status == TAG_WRITE_PENDING {
+        return -1;
+    }
+
+    let tag = match tag {
+        Some(tag) if tag.uid_hex() == tag_id => tag,
+        Some(_) => {
+            write.status = TAG_WRITE_FAILED;
+            write.message = "A different tag is on the reader".to_string();
+            return -1;
         }
-        if !minor.is_null() {
-            unsafe { *minor = manager.state.firmware_version.1; }
+        None => {
+            write.status = TAG_WRITE_FAILED;
+            write.message = "No tag present".to_string();
+            return -1;
         }
-        if !patch.is_null() {
-            unsafe { *patch = manager.state.firmware_version.2; }
+    };
+
+    // Only NTAG tags can be written; Bambu MIFARE tags are signed and read-only
+    if tag.kind != TagKind::Ntag {
+        write.status = TAG_WRITE_FAILED;
+        write.message = "Only NTAG tags can be written".to_string();
+        return -1;
+    }
+
+    let info = match crate::backend_client::spool_tag_info(&tag_id) {
+        Some(info) => info,
+        None => {
+            write.status = TAG_WRITE_FAILED;
+            write.message = "Spool not found in inventory".to_string();
+            return -1;
         }
+    };
+
+    info!("Queueing {} tag write", format.name());
+    write.status = TAG_WRITE_PENDING;
+    write.message = "Writing tag...".to_string();
+    write.request = Some(TagWriteRequest { tag, format, info });
+    0
+}
+
+/// Get the tag write state: 0 = idle, 1 = pending, 2 = written, 3 = failed
+#[no_mangle]
+pub extern "C" fn nfc_get_write_status() -> c_int {
+    TAG_WRITE.lock().unwrap().status
+}
+
+/// Copy the tag write result message to buf (returns length, or -1)
+#[no_mangle]
+pub extern "C" fn nfc_get_write_message(buf: *mut c_char, buf_len: c_int) -> c_int {
+    if buf.is_null() || buf_len <= 0 {
+        return -1;
+    }
+    let write = TAG_WRITE.lock().unwrap();
+    let dst = unsafe { std::slice::from_raw_parts_mut(buf as *mut u8, buf_len as usize) };
+    copy_str_to_buf(&write.message, dst);
+    write.message.len().min(buf_len as usize - 1) as c_int
+}
+
+/// Reset the write state once the UI has shown the result (cancels a queued write)
+#[no_mangle]
+pub extern "C" fn nfc_clear_write_status() {
+    {
+        let mut write = TAG_WRITE.lock().unwrap();
+        write.status = TAG_WRITE_IDLE;
+        write.message.clear();
+        write.request = None;
+    }
+    // Not nested in TAG_WRITE: poll_nfc takes TAG_WRITE while holding NFC_POLLER
+    NFC_POLLER.lock().unwrap().cancel_write();
+}
+
+// =============================================================================
+// Decoded Tag Data Storage
+// =============================================================================
+
+/// Decoded tag data (populated by backend or local decoding)
+struct DecodedTagData {
+    vendor: [u8; 32],
+    material: [u8; 32],
+    material_subtype: [u8; 32],
+    color_name: [u8; 32],
+    color_rgba: u32,
+    spool_weight: i32,
+    tag_type: [u8; 32],
+    // Bambu Lab details (see DecodedTagInfo)
+    filament_diameter: f32,
+    drying_temp: i32,
+    drying_time: i32,
+    bed_temp: i32,
+    hotend_min_temp: i32,
+    hotend_max_temp: i32,
+    spool_width: f32,
+    filament_length: i32,
+    production_date: [u8; 32],
+    tray_uid: [u8; 33],
+}
+
+impl Default for DecodedTagData {
+    fn default() -> Self {
+        Self {
+            vendor: [0; 32],
+            material: [0; 32],
+            material_subtype: [0; 32],
+            color_name: [0; 32],
+            color_rgba: 0,
+            spool_weight: 0,
+            tag_type: [0; 32],
+            filament_diameter: 0.0,
+            drying_temp: 0,
+            drying_time: 0,
+            bed_temp: 0,
+            hotend_min_temp: 0,
+            hotend_max_temp: 0,
+            spool_width: 0.0,
+            filament_length: 0,
+            production_date: [0; 32],
+            tray_uid: [0; 33],
+        }
+    }
+}
+
+static DECODED_TAG: Mutex<DecodedTagData> = Mutex::new(DecodedTagData {
+    vendor: [0; 32],
+    material: [0; 32],
+    material_subtype: [0; 32],
+    color_name: [0; 32],
+    color_rgba: 0,
+    spool_weight: 0,
+    tag_type: [0; 32],
+    filament_diameter: 0.0,
+    drying_temp: 0,
+    drying_time: 0,
+    bed_temp: 0,
+    hotend_min_temp: 0,
+    hotend_max_temp: 0,
+    spool_width: 0.0,
+    filament_length: 0,
+    production_date: [0; 32],
+    tray_uid: [0; 33],
+});
+
+/// Helper to copy string to fixed buffer
+fn copy_str_to_buf(src: &str, dst: &mut [u8]) {
+    let bytes = src.as_bytes();
+    let len = bytes.len().min(dst.len() - 1);
+    dst[..len].copy_from_slice(&bytes[..len]);
+    dst[len] = 0;
+}
+
+/// Set decoded tag data (called from backend response parsing)
+pub fn set_decoded_tag_data(
+    vendor: &str,
+    material: &str,
+    subtype: &str,
+    color_name: &str,
+    color_rgba: u32,
+    spool_weight: i32,
+    tag_type: &str,
+) {
+    let mut data = DECODED_TAG.lock().unwrap();
+    copy_str_to_buf(vendor, &mut data.vendor);
+    copy_str_to_buf(material, &mut data.material);
+    copy_str_to_buf(subtype, &mut data.material_subtype);
+    copy_str_to_buf(color_name, &mut data.color_name);
+    data.color_rgba = color_rgba;
+    data.spool_weight = spool_weight;
+    copy_str_to_buf(tag_type, &mut data.tag_type);
+    info!("Decoded tag data set: {} {} {}", vendor, material, color_name);
+}
+
+/// Set the Bambu Lab details of a locally decoded tag
+fn set_decoded_tag_details(info: &DecodedTagInfo) {
+    let mut data = DECODED_TAG.lock().unwrap();
+    data.filament_diameter = info.filament_diameter;
+    data.drying_temp = info.drying_temp;
+    data.drying_time = info.drying_time;
+    data.bed_temp = info.bed_temp;
+    data.hotend_min_temp = info.hotend_min_temp;
+    data.hotend_max_temp = info.hotend_max_temp;
+    data.spool_width = info.spool_width;
+    data.filament_length = info.filament_length;
+    copy_str_to_buf(&info.production_date, &mut data.production_date);
+    copy_str_to_buf(&info.tray_uid, &mut data.tray_uid);
+}
+
+/// Clear decoded tag data (when tag removed)
+pub fn clear_decoded_tag_data() {
+    let mut data = DECODED_TAG.lock().unwrap();
+    *data = DecodedTagData::default();
+}
+
+// =============================================================================
+// Rust-callable getters for sending to backend
+// =============================================================================
+
+/// Get tag vendor as String
+pub fn get_tag_vendor() -> String {
+    let data = DECODED_TAG.lock().unwrap();
+    let end = data.vendor.iter().position(|&b| b == 0).unwrap_or(data.vendor.len());
+    String::from_utf8_lossy(&data.vendor[..end]).to_string()
+}
+
+/// Get tag material as String
+pub fn get_tag_material() -> String {
+    let data = DECODED_TAG.lock().unwrap();
+    let end = data.material.iter().position(|&b| b == 0).unwrap_or(data.material.len());
+    String::from_utf8_lossy(&data.material[..end]).to_string()
+}
+
+/// Get tag material subtype as String
+pub fn get_tag_subtype() -> String {
+    let data = DECODED_TAG.lock().unwrap();
+    let end = data.material_subtype.iter().position(|&b| b == 0).unwrap_or(data.material_subtype.len());
+    String::from_utf8_lossy(&data.material_subtype[..end]).to_string()
+}
+
+/// Get tag color name as String
+pub fn get_tag_color_name() -> String {
+    let data = DECODED_TAG.lock().unwrap();
+    let end = data.color_name.iter().position(|&b| b == 0).unwrap_or(data.color_name.len());
+    String::from_utf8_lossy(&data.color_name[..end]).to_string()
+}
+
+/// Get tag color RGBA
+pub fn get_tag_color_rgba() -> u32 {
+    let data = DECODED_TAG.lock().unwrap();
+    data.color_rgba
+}
+
+/// Get tag spool weight
+pub fn get_tag_spool_weight() -> i32 {
+    let data = DECODED_TAG.lock().unwrap();
+    data.spool_weight
+}
+
+/// Get tag type as String
+pub fn get_tag_type() -> String {
+    let data = DECODED_TAG.lock().unwrap();
+    let end = data.tag_type.iter().position(|&b| b == 0).unwrap_or(data.tag_type.len());
+    String::from_utf8_lossy(&data.tag_type[..end]).to_string()
+}
+
+/// Get filament diameter (mm, 0 if unknown)
+pub fn get_tag_filament_diameter() -> f32 {
+    let data = DECODED_TAG.lock().unwrap();
+    data.filament_diameter
+}
+
+/// Get drying temperature (°C) and time (hours)
+pub fn get_tag_drying() -> (i32, i32) {
+    let data = DECODED_TAG.lock().unwrap();
+    (data.drying_temp, data.drying_time)
+}
+
+/// Get bed temperature (°C)
+pub fn get_tag_bed_temp() -> i32 {
+    let data = DECODED_TAG.lock().unwrap();
+    data.bed_temp
+}
+
+/// Get hotend temperature range (°C, min and max)
+pub fn get_tag_hotend_temps() -> (i32, i32) {
+    let data = DECODED_TAG.lock().unwrap();
+    (data.hotend_min_temp, data.hotend_max_temp)
+}
+
+/// Get spool width (mm)
+pub fn get_tag_spool_width() -> f32 {
+    let data = DECODED_TAG.lock().unwrap();
+    data.spool_width
+}
+
+/// Get filament length (m)
+pub fn get_tag_filament_length() -> i32 {
+    let data = DECODED_TAG.lock().unwrap();
+    data.filament_length
+}
+
+/// Get production date as String
+pub fn get_tag_production_date() -> String {
+    let data = DECODED_TAG.lock().unwrap();
+    let end = data.production_date.iter().position(|&b| b == 0).unwrap_or(data.production_date.len());
+    String::from_utf8_lossy(&data.production_date[..end]).to_string()
+}
+
+/// Get tray UID (hex) as String
+pub fn get_tag_tray_uid() -> String {
+    let data = DECODED_TAG.lock().unwrap();
+    let end = data.tray_uid.iter().position(|&b| b == 0).unwrap_or(data.tray_uid.len());
+    String::from_utf8_lossy(&data.tray_uid[..end]).to_string()
+}
+
+// =============================================================================
+// Decoded Tag Data FFI Functions
+// =============================================================================
+
+/// Get tag vendor (returns pointer to static string, valid until next call)
+#[no_mangle]
+#[allow(static_mut_refs)]
+pub extern "C" fn nfc_get_tag_vendor() -> *const std::ffi::c_char {
+    static mut VENDOR_BUF: [u8; 32] = [0; 32];
+    let data = DECODED_TAG.lock().unwrap();
+    unsafe {
+        VENDOR_BUF.copy_from_slice(&data.vendor);
+        VENDOR_BUF.as_ptr() as *const std::ffi::c_char
+    }
+}
+
+/// Get tag material type
+#[no_mangle]
+#[allow(static_mut_refs)]
+pub extern "C" fn nfc_get_tag_material() -> *const std::ffi::c_char {
+    static mut MATERIAL_BUF: [u8; 32] = [0; 32];
+    let data = DECODED_TAG.lock().unwrap();
+    unsafe {
+        MATERIAL_BUF.copy_from_slice(&data.material);
+        MATERIAL_BUF.as_ptr() as *const std::ffi::c_char
+    }
+}
+
+/// Get tag material subtype
+#[no_mangle]
+#[allow(static_mut_refs)]
+pub extern "C" fn nfc_get_tag_material_subtype() -> *const std::ffi::c_char {
+    static mut SUBTYPE_BUF: [u8; 32] = [0; 32];
+    let data = DECODED_TAG.lock().unwrap();
+    unsafe {
+        SUBTYPE_BUF.copy_from_slice(&data.material_subtype);
+        SUBTYPE_BUF.as_ptr() as *const std::ffi::c_char
+    }
+}
+
+/// Get tag color name
+#[no_mangle]
+#[allow(static_mut_refs)]
+pub extern "C" fn nfc_get_tag_color_name() -> *const std::ffi::c_char {
+    static mut COLOR_BUF: [u8; 32] = [0; 32];
+    let data = DECODED_TAG.lock().unwrap();
+    unsafe {
+        COLOR_BUF.copy_from_slice(&data.color_name);
+        COLOR_BUF.as_ptr() as *const std::ffi::c_char
+    }
+}
+
+/// Get tag color as RGBA (0xRRGGBBAA)
+#[no_mangle]
+pub extern "C" fn nfc_get_tag_color_rgba() -> u32 {
+    let data = DECODED_TAG.lock().unwrap();
+    data.color_rgba
+}
+
+/// Get spool weight from tag (grams)
+#[no_mangle]
+pub extern "C" fn nfc_get_tag_spool_weight() -> i32 {
+    let data = DECODED_TAG.lock().unwrap();
+    data.spool_weight
+}
+
+/// Get tag type (e.g., "bambu", "spoolease", "generic")
+#[no_mangle]
+#[allow(static_mut_refs)]
+pub extern "C" fn nfc_get_tag_type() -> *const std::ffi::c_char {
+    static mut TYPE_BUF: [u8; 32] = [0; 32];
+    let data = DECODED_TAG.lock().unwrap();
+    unsafe {
+        TYPE_BUF.copy_from_slice(&data.tag_type);
+        TYPE_BUF.as_ptr() as *const std::ffi::c_char
+    }
+}
+
+/// Get filament diameter (mm, 0 if unknown)
+#[no_mangle]
+pub extern "C" fn nfc_get_tag_filament_diameter() -> f32 {
+    let data = DECODED_TAG.lock().unwrap();
+    data.filament_diameter
+}
+
+/// Get drying temperature (°C)
+#[no_mangle]
+pub extern "C" fn nfc_get_tag_drying_temp() -> i32 {
+    let data = DECODED_TAG.lock().unwrap();
+    data.drying_temp
+}
+
+/// Get drying time (hours)
+#[no_mangle]
+pub extern "C" fn nfc_get_tag_drying_time() -> i32 {
+    let data = DECODED_TAG.lock().unwrap();
+    data.drying_time
+}
+
+/// Get bed temperature (°C)
+#[no_mangle]
+pub extern "C" fn nfc_get_tag_bed_temp() -> i32 {
+    let data = DECODED_TAG.lock().unwrap();
+    data.bed_temp
+}
+
+/// Get minimum hotend temperature (°C)
+#[no_mangle]
+pub extern "C" fn nfc_get_tag_hotend_min_temp() -> i32 {
+    let data = DECODED_TAG.lock().unwrap();
+    data.hotend_min_temp
+}
+
+/// Get maximum hotend temperature (°C)
+#[no_mangle]
+pub extern "C" fn nfc_get_tag_hotend_max_temp() -> i32 {
+    let data = DECODED_TAG.lock().unwrap();
+    data.hotend_max_temp
+}
+
+/// Get spool width (mm)
+#[no_mangle]
+pub extern "C" fn nfc_get_tag_spool_width() -> f32 {
+    let data = DECODED_TAG.lock().unwrap();
+    data.spool_width
+}
+
+/// Get filament length (m)
+#[no_mangle]
+pub extern "C" fn nfc_get_tag_filament_length() -> i32 {
+    let data = DECODED_TAG.lock().unwrap();
+    data.filament_length
+}
+
+/// Get production date (e.g., "2024_03_18_13_38", empty if unknown)
+#[no_mangle]
+#[allow(static_mut_refs)]
+pub extern "C" fn nfc_get_tag_production_date() -> *const std::ffi::c_char {
+    static mut DATE_BUF: [u8; 32] = [0; 32];
+    let data = DECODED_TAG.lock().unwrap();
+    unsafe {
+        DATE_BUF.copy_from_slice(&data.production_date);
+        DATE_BUF.as_ptr() as *const std::ffi::c_char
+    }
+}
+
+/// Get tray UID (32 hex digits, empty if unknown)
+#[no_mangle]
+#[allow(static_mut_refs)]
+pub extern "C" fn nfc_get_tag_tray_uid() -> *const std::ffi::c_char {
+    static mut TRAY_UID_BUF: [u8; 33] = [0; 33];
+    let data = DECODED_TAG.lock().unwrap();
+    unsafe {
+        TRAY_UID_BUF.copy_from_slice(&data.tray_uid);
+        TRAY_UID_BUF.as_ptr() as *const std::ffi::c_char
     }
 }
@@ -11,7 +11,7 @@
 
 #![allow(dead_code)]
 
-use esp_idf_svc::http::client::{Configuration as HttpConfig, EspHttpConnection};
+use esp_idf_svc::http::client::EspHttpConnection;
 use esp_idf_sys::{
     esp_partition_find, esp_partition_erase_range, esp_partition_write,
     esp_partition_t, esp_partition_type_t_ESP_PARTITION_TYPE_APP,
@@ -94,21 +94,24 @@ pub fn check_for_update(server_url: &str) -> Result<UpdateInfo, String> {
     let url = format!("{}/api/firmware/check?current_version={}", server_url, CURRENT_VERSION);
     info!("Checking for updates: {}", url);
 
-    let config = HttpConfig {
-        timeout: Some(std::time::Duration::from_secs(10)),
-        ..Default::default()
-    };
+    let config = crate::tls_pin::http_config(std::time::Duration::from_secs(10));
 
     let connection = EspHttpConnection::new(&config)
         .map_err(|e| format!("HTTP connection failed: {:?}", e))?;
     let mut client = HttpClient::wrap(connection);
 
-    let request = client.get(&url)
+    let api_key = crate::device_auth::api_key();
+    let headers = crate::device_auth::with_api_key(&[], &api_key);
+    let request = client.request(embedded_svc::http::Method::Get, &url, &headers)
         .map_err(|e| format!("HTTP request failed: {:?}", e))?;
     let mut response = request.submit()
-        .map_err(|e| format!("HTTP submit failed: {:?}", e))?;
+        .map_err(|e| {
+            set_state(OtaState::Idle);
+            submit_error(server_url, e)
+        })?;
 
     let status = response.status();
+    crate::backend_client::note_auth_status(status);
     if status != 200 {
         set_state(OtaState::Idle);
         return Err(format!("HTTP error: {}", status));
@@ -189,21 +192,25 @@ fn download_firmware(server_url: &str) -> Result<Vec<u8>, String> {
     let url = format!("{}/api/firmware/ota", server_url);
     info!("Downloading firmware from: {}", url);
 
-    let config = HttpConfig {
-        timeout: Some(std::time::Duration::from_secs(120)), // 2 min for large file
-        ..Default::default()
-    };
+    let config = crate::tls_pin::http_config(std::time::Duration::from_secs(120)); // 2 min for large file
 
     let connection = EspHttpConnection::new(&config)
         .map_err(|e| format!("HTTP connection failed: {:?}", e))?;
     let mut client = HttpClient::wrap(connection);
 
-    let request = client.get(&url)
+    let api_key = crate::device_auth::api_key();
+    let headers = crate::device_auth::with_api_key(&[], &api_key);
+    let request = client.request(embedded_svc::http::Method::Get, &url, &headers)
         .map_err(|e| format!("HTTP request failed: {:?}", e))?;
     let mut response = request.submit()
-        .map_err(|e| format!("HTTP submit failed: {:?}", e))?;
+        .map_err(|e| {
+            let error = submit_error(server_url, e);
+            set_state(OtaState::Error(error.clone()));
+            error
+        })?;
 
     let status = response.status();
+    crate::backend_client::note_auth_status(status);
     if status != 200 {
         set_state(OtaState::Error(format!("HTTP {}", status)));
         return Err(format!("HTTP error: {}", status));
@@ -247,6 +254,17 @@ fn download_firmware(server_url: &str) -> Result<Vec<u8>, String> {
     Ok(firmware_data)
 }
 
+/// Error message for a request that could not be sent
+/// A server that fails the pinned certificate is reported as such, not as
+/// unreachable.
+fn submit_error(server_url: &str, e: impl std::fmt::Debug) -> String {
+    if crate::tls_pin::is_pin_mismatch(server_url) {
+        "Server certificate does not match the pinned certificate".to_string()
+    } else {
+        format!("HTTP submit failed: {:?}", e)
+    }
+}
+
 /// Validate firmware binary
 fn validate_firmware(data: &[u8]) -> Result<(), String> {
     info!("Validating firmware ({} bytes)", data.len());
@@ -0,0 +1,264 @@
+//! Offline outbox for backend mutations
+//!
+//! Mutations made on the display (weight sync, tag link, add spool, tray
+//! assignment) are recorded here when the backend cannot be reached. The
+//! queue is persisted to NVS and replayed in order once the backend is back.
+//! Every entry carries an idempotency key so a request that reached the
+//! backend but whose response was lost is not applied twice.
+
+use esp_idf_svc::nvs::{EspDefaultNvsPartition, EspNvs};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// NVS namespace and keys for the queue blob and the sequence reservation
+const NVS_NAMESPACE: &str = "outbox";
+const NVS_KEY_QUEUE: &str = "queue";
+const NVS_KEY_SEQ: &str = "seq";
+
+/// Sequence numbers reserved per NVS write; a reboot skips the unused rest
+const SEQ_BLOCK: u32 = 64;
+
+/// Maximum queued mutations (oldest are dropped beyond this)
+const MAX_ENTRIES: usize = 32;
+
+/// HTTP method of a queued mutation
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Method {
+    Post,
+    Put,
+    Patch,
+}
+
+impl Method {
+    pub fn to_http(self) -> embedded_svc::http::Method {
+        match self {
+            Method::Post => embedded_svc::http::Method::Post,
+            Method::Put => embedded_svc::http::Method::Put,
+            Method::Patch => embedded_svc::http::Method::Patch,
+        }
+    }
+}
+
+/// A recorded backend mutation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutboxEntry {
+    pub seq: u32,
+    /// Idempotency key sent with every attempt
+    pub key: String,
+    /// Unix time when the user made the change (0 if time was unknown)
+    pub created_at: u64,
+    /// Short label for logs (e.g. "sync_weight")
+    pub kind: String,
+    pub method: Method,
+    /// Path relative to the server URL (e.g. "/api/spools/abc")
+    pub path: String,
+    pub body: String,
+    /// Entries with the same coalesce key replace each other
+    /// (only the latest absolute weight of a spool matters)
+    #[serde(default)]
+    pub coalesce: Option<String>,
+}
+
+/// Persisted queue state
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Queue {
+    /// Next sequence number (kept with the queue for older firmware; the
+    /// reservation under `NVS_KEY_SEQ` is what keeps keys unique)
+    next_seq: u32,
+    entries: VecDeque<OutboxEntry>,
+}
+
+impl Queue {
+    /// Append an entry, replacing an older one with the same coalesce key
+    fn push(&mut self, entry: OutboxEntry) {
+        if let Some(ref key) = entry.coalesce {
+            self.entries.retain(|e| e.coalesce.as_ref() != Some(key));
+        }
+        while self.entries.len() >= MAX_ENTRIES {
+            if let Some(dropped) = self.entries.pop_front() {
+                warn!("Outbox full, dropping {} {}", dropped.kind, dropped.key);
+            }
+        }
+        self.entries.push_back(entry);
+    }
+}
+
+struct Outbox {
+    nvs: Option<EspDefaultNvsPartition>,
+    device_id: String,
+    queue: Queue,
+    /// Sequence numbers below this are reserved in NVS
+    seq_reserved: u32,
+}
+
+static OUTBOX: Mutex<Option<Outbox>> = Mutex::new(None);
+
+/// Load the persisted queue
+pub fn init(nvs: Option<EspDefaultNvsPartition>) {
+    let mut queue = nvs.as_ref().and_then(load_queue).unwrap_or_default();
+    if !queue.entries.is_empty() {
+        info!("Outbox: {} queued mutation(s) from previous session", queue.entries.len());
+    }
+
+    // Continue after everything reserved before the reboot
+    let reserved = nvs.as_ref().and_then(load_seq_reserved).unwrap_or(0);
+    queue.next_seq = queue.next_seq.max(reserved);
+
+    *OUTBOX.lock().unwrap() = Some(Outbox {
+        nvs,
+        device_id: device_id(),
+        seq_reserved: queue.next_seq,
+        queue,
+    });
+}
+
+/// Device identifier used as idempotency key prefix (factory MAC)
+pub fn device_id() -> String {
+    let mut mac = [0u8; 6];
+    unsafe {
+        esp_idf_sys::esp_efuse_mac_get_default(mac.as_mut_ptr());
+    }
+    mac.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn load_queue(nvs_partition: &EspDefaultNvsPartition) -> Option<Queue> {
+    let nvs = match EspNvs::new(nvs_partition.clone(), NVS_NAMESPACE, true) {
+        Ok(nvs) => nvs,
+        Err(e) => {
+            warn!("Failed to open NVS namespace for outbox: {:?}", e);
+            return None;
+        }
+    };
+
+    let len = match nvs.blob_len(NVS_KEY_QUEUE) {
+        Ok(Some(len)) => len,
+        _ => return None,
+    };
+
+    let mut buf = vec![0u8; len];
+    match nvs.get_blob(NVS_KEY_QUEUE, &mut buf) {
+        Ok(Some(data)) => match serde_json::from_slice(data) {
+            Ok(queue) => Some(queue),
+            Err(e) => {
+                warn!("Discarding corrupt outbox: {}", e);
+                None
+            }
+        },
+        _ => None,
+    }
+}
+
+fn load_seq_reserved(nvs_partition: &EspDefaultNvsPartition) -> Option<u32> {
+    let nvs = EspNvs::new(nvs_partition.clone(), NVS_NAMESPACE, true).ok()?;
+    nvs.get_u32(NVS_KEY_SEQ).ok().flatten()
+}
+
+/// Reserve the next block of sequence numbers in NVS
+fn save_seq_reserved(outbox: &Outbox) {
+    let Some(ref nvs_partition) = outbox.nvs else {
+        return;
+    };
+
+    let result = EspNvs::new(nvs_partition.clone(), NVS_NAMESPACE, true)
+        .and_then(|mut nvs| nvs.set_u32(NVS_KEY_SEQ, outbox.seq_reserved));
+    if let Err(e) = result {
+        warn!("Failed to save outbox sequence to NVS: {:?}", e);
+    }
+}
+
+fn save_queue(outbox: &Outbox) {
+    let Some(ref nvs_partition) = outbox.nvs else {
+        return;
+    };
+
+    let nvs = match EspNvs::new(nvs_partition.clone(), NVS_NAMESPACE, true) {
+        Ok(nvs) => nvs,
+        Err(e) => {
+            warn!("Failed to open NVS namespace for outbox: {:?}", e);
+            return;
+        }
+    };
+
+    let data = match serde_json::to_vec(&outbox.queue) {
+        Ok(d) => d,
+        Err(e) => {
+            warn!("Failed to serialize outbox: {}", e);
+            return;
+        }
+    };
+
+    if let Err(e) = nvs.set_blob(NVS_KEY_QUEUE, &data) {
+        warn!("Failed to save outbox to NVS: {:?}", e);
+    }
+}
+
+/// Create a new entry with a fresh sequence number and idempotency key
+/// The entry is not queued yet - it is sent directly first.
+pub fn new_entry(kind: &str, method: Method, path: String, body: String, coalesce: Option<String>) -> OutboxEntry {
+    let mut guard = OUTBOX.lock().unwrap();
+    let (seq, device_id) = match guard.as_mut() {
+        Some(outbox) => {
+            let seq = outbox.queue.next_seq;
+            outbox.queue.next_seq = seq.wrapping_add(1);
+            // Keys must stay unique across reboots: reserve numbers in
+            // blocks instead of writing the counter for every mutation
+            if seq >= outbox.seq_reserved {
+                outbox.seq_reserved = seq.saturating_add(SEQ_BLOCK);
+                save_seq_reserved(outbox);
+            }
+            (seq, outbox.device_id.clone())
+        }
+        None => (0, String::from("unknown")),
+    };
+
+    OutboxEntry {
+        seq,
+        key: format!("sb-{}-{}", device_id, seq),
+        created_at: crate::time_manager::unix_time().unwrap_or(0),
+        kind: kind.to_string(),
+        method,
+        path,
+        body,
+        coalesce,
+    }
+}
+
+/// Queue an entry for later replay
+pub fn push(entry: OutboxEntry) {
+    let mut guard = OUTBOX.lock().unwrap();
+    let Some(outbox) = guard.as_mut() else {
+        warn!("Outbox not initialized, dropping {}", entry.kind);
+        return;
+    };
+
+    info!("Outbox: queued {} {} ({})", entry.kind, entry.path, entry.key);
+    outbox.queue.push(entry);
+    save_queue(outbox);
+}
+
+/// Oldest queued entry
+pub fn front() -> Option<OutboxEntry> {
+    let guard = OUTBOX.lock().unwrap();
+    guard.as_ref().and_then(|o| o.queue.entries.front().cloned())
+}
+
+/// Remove an entry once it was delivered (or permanently rejected)
+pub fn remove(seq: u32) {
+    let mut guard = OUTBOX.lock().unwrap();
+    let Some(outbox) = guard.as_mut() else {
+        return;
+    };
+    let before = outbox.queue.entries.len();
+    outbox.queue.entries.retain(|e| e.seq != seq);
+    if outbox.queue.entries.len() != before {
+        save_queue(outbox);
+    }
+}
+
+/// Number of queued mutations
+pub fn len() -> usize {
+    let guard = OUTBOX.lock().unwrap();
+    guard.as_ref().map(|o| o.queue.entries.len()).unwrap_or(0)
+}
@@ -11,7 +11,5 @@
 //! - 3V3  (I2C-OUT Pin 1) -> VCC
 //! - GND  (I2C-OUT Pin 4) -> GND
 
-#![allow(dead_code)]
-#![allow(unused)]
-
-pub mod nau7802;
+// Hardware-independent scale modules (host-tested in firmware-core)
+pub use spoolbuddy_firmware_core::scale::{calibration, drift, filter, nau7802};
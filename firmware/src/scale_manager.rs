@@ -3,17 +3,41 @@
 //! Provides FFI functions for the C UI code to access scale data.
 //! Uses shared I2C bus.
 //! Calibration data is persisted to NVS flash.
+//!
+//! Multi-point calibration: tare, then add reference weights one by one
+//! (`scale_cal_add_point`), then fit the curve (`scale_cal_finish`). The
+//! residuals of the last fit are kept for the calibration screen.
+//!
+//! Zero corrections made by auto-zero tracking are not saved: after a restart
+//! the zero of the last tare is tracked again.
+//!
+//! The ADC settings (sample rate, gain, LDO, channel) are persisted as well
+//! and read before the NAU7802 is initialized (`saved_adc_config`). So are
+//! the filter/stability and the zero tracking/drift compensation settings,
+//! restored with the calibration.
+//!
+//! Tare, calibration and ADC changes take seconds (AFE calibration, settling,
+//! averaging). They take the shared I2C bus per transfer only, so NFC polling
+//! goes on meanwhile.
 
+use embedded_hal::i2c::{Error as _, ErrorKind, ErrorType, I2c, Operation};
+use esp_idf_hal::delay::FreeRtos;
 use esp_idf_svc::nvs::{EspDefaultNvsPartition, EspNvs};
 use log::{info, warn};
 use std::sync::Mutex;
 
-use crate::scale::nau7802::{self, Calibration, Nau7802State};
+use crate::scale::calibration::{self, CalPoint, CurveKind, FitReport, MAX_POINTS};
+use crate::scale::drift::{DriftConfig, TempSource};
+use crate::scale::filter::FilterConfig;
+use crate::scale::nau7802::{self, AdcConfig, Calibration, Channel, Gain, LdoVoltage, Nau7802State, SampleRate};
 use crate::shared_i2c;
 
 /// NVS namespace for scale calibration
 const NVS_NAMESPACE: &str = "scale";
 const NVS_KEY_CALIBRATION: &str = "cal";
+const NVS_KEY_ADC_CONFIG: &str = "adc";
+const NVS_KEY_FILTER_CONFIG: &str = "filter";
+const NVS_KEY_DRIFT_CONFIG: &str = "drift";
 
 /// Global scale state protected by mutex
 static SCALE_STATE: Mutex<Option<Nau7802State>> = Mutex::new(None);
@@ -21,6 +45,29 @@ static SCALE_STATE: Mutex<Option<Nau7802State>> = Mutex::new(None);
 /// Global NVS partition for calibration persistence
 static NVS_PARTITION: Mutex<Option<EspDefaultNvsPartition>> = Mutex::new(None);
 
+/// Reference weights measured since the last tare
+static CAL_POINTS: Mutex<Vec<CalPoint>> = Mutex::new(Vec::new());
+
+/// Result of the last calibration fit
+static LAST_FIT: Mutex<Option<FitReport>> = Mutex::new(None);
+
+/// The shared I2C bus for the slow scale operations. The bus is taken for
+/// each transfer only, not for the waits in between.
+struct SharedI2cBus;
+
+impl ErrorType for SharedI2cBus {
+    type Error = ErrorKind;
+}
+
+impl I2c for SharedI2cBus {
+    fn transaction(&mut self, address: u8, operations: &mut [Operation<'_>]) -> Result<(), Self::Error> {
+        match shared_i2c::with_i2c(|i2c| I2c::transaction(i2c, address, operations)) {
+            Some(result) => result.map_err(|e| e.kind()),
+            None => Err(ErrorKind::Other),
+        }
+    }
+}
+
 /// Scale status for C code
 #[repr(C)]
 pub struct ScaleStatus {
@@ -30,6 +77,27 @@ pub struct ScaleStatus {
     pub stable: bool,
     pub tare_offset: i32,
     pub cal_factor: f32,
+    /// Whether `temperature_c` is known
+    pub temperature_valid: bool,
+    /// Temperature used for drift compensation (°C)
+    pub temperature_c: f32,
+    /// Temperature correction of the weight (grams)
+    pub drift_correction_g: f32,
+    /// Zero moved by auto-zero tracking since the last tare (grams)
+    pub auto_zero_g: f32,
+}
+
+/// ADC settings for C code
+#[repr(C)]
+pub struct ScaleAdcConfig {
+    /// Samples per second (10, 20, 40, 80 or 320)
+    pub sample_rate_sps: i32,
+    /// PGA gain (1-128)
+    pub gain: i32,
+    /// LDO voltage in millivolts (2400-4500 in 300mV steps)
+    pub ldo_mv: i32,
+    /// Input channel (1 or 2)
+    pub channel: i32,
 }
 
 /// Initialize NVS for scale calibration persistence
@@ -42,21 +110,34 @@ pub fn init_nvs(nvs: Option<EspDefaultNvsPartition>) {
 /// Initialize the scale manager with state (uses shared I2C)
 pub fn init_scale_manager(mut state: Nau7802State) {
     // Try to load saved calibration from NVS
-    if let Some(calibration) = load_calibration_from_nvs() {
-        info!("Loaded saved calibration: zero_offset={}, cal_factor={}",
-              calibration.zero_offset, calibration.cal_factor);
+    if let Some((calibration, migrated)) = load_calibration_from_nvs() {
+        info!("Loaded saved calibration: zero_offset={}, cal_factor={}, quadratic={:e}",
+              calibration.zero_offset, calibration.cal_factor, calibration.quadratic);
         state.calibration = calibration;
+        if migrated {
+            info!("Migrating saved calibration to the current format");
+            save_calibration_to_nvs(&calibration);
+        }
     } else {
         info!("No saved calibration found, using defaults");
     }
+    if let Some(config) = load_filter_config_from_nvs() {
+        info!("Loaded saved filter settings: {:?}", config);
+        state.filter.set_config(config);
+    }
+    if let Some(config) = load_drift_config_from_nvs() {
+        info!("Loaded saved drift settings: {:?}", config);
+        state.drift.set_config(config);
+    }
 
     let mut guard = SCALE_STATE.lock().unwrap();
     *guard = Some(state);
     info!("Scale manager initialized");
 }
 
-/// Load calibration data from NVS (8 bytes: i32 zero_offset + i32 cal_factor_x1000)
-fn load_calibration_from_nvs() -> Option<Calibration> {
+/// Load calibration data from NVS (see `scale::calibration` for the format)
+/// Returns the calibration and whether it is in the old format
+fn load_calibration_from_nvs() -> Option<(Calibration, bool)> {
     let nvs_guard = NVS_PARTITION.lock().unwrap();
     let nvs_partition = nvs_guard.as_ref()?;
 
@@ -68,20 +149,16 @@ fn load_calibration_from_nvs() -> Option<Calibration> {
         }
     };
 
-    // Read calibration blob
-    let mut buf = [0u8; 8];
+    // Read calibration blob (large enough for any version)
+    let mut buf = [0u8; 64];
     match nvs.get_blob(NVS_KEY_CALIBRATION, &mut buf) {
-        Ok(Some(_)) => {
-            // Parse the calibration data
-            let zero_offset = i32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]);
-            let cal_factor_x1000 = i32::from_le_bytes([buf[4], buf[5], buf[6], buf[7]]);
-            let cal_factor = cal_factor_x1000 as f32 / 1000.0;
-
-            Some(Calibration {
-                zero_offset,
-                cal_factor,
-            })
-        }
+        Ok(Some(data)) => match Calibration::from_blob(data) {
+            Ok(loaded) => Some(loaded),
+            Err(e) => {
+                warn!("Discarding saved calibration: {}", e);
+                None
+            }
+        },
         Ok(None) => None, // No saved calibration
         Err(e) => {
             warn!("Failed to read calibration from NVS: {:?}", e);
@@ -90,6 +167,165 @@ fn load_calibration_from_nvs() -> Option<Calibration> {
     }
 }
 
+/// Saved ADC settings, or the defaults
+/// (call after `init_nvs`, before initializing the NAU7802)
+pub fn saved_adc_config() -> AdcConfig {
+    let nvs_guard = NVS_PARTITION.lock().unwrap();
+    let Some(nvs_partition) = nvs_guard.as_ref() else {
+        return AdcConfig::default();
+    };
+
+    let nvs = match EspNvs::new(nvs_partition.clone(), NVS_NAMESPACE, true) {
+        Ok(nvs) => nvs,
+        Err(e) => {
+            warn!("Failed to open NVS namespace for scale: {:?}", e);
+            return AdcConfig::default();
+        }
+    };
+
+    let mut buf = [0u8; 16];
+    match nvs.get_blob(NVS_KEY_ADC_CONFIG, &mut buf) {
+        Ok(Some(data)) => AdcConfig::from_blob(data).unwrap_or_else(|e| {
+            warn!("Discarding saved ADC settings: {}", e);
+            AdcConfig::default()
+        }),
+        Ok(None) => AdcConfig::default(),
+        Err(e) => {
+            warn!("Failed to read ADC settings from NVS: {:?}", e);
+            AdcConfig::default()
+        }
+    }
+}
+
+/// Save ADC settings to NVS
+fn save_adc_config_to_nvs(config: &AdcConfig) -> bool {
+    let nvs_guard = NVS_PARTITION.lock().unwrap();
+    let Some(nvs_partition) = nvs_guard.as_ref() else {
+        warn!("No NVS partition available for saving ADC settings");
+        return false;
+    };
+
+    let nvs = match EspNvs::new(nvs_partition.clone(), NVS_NAMESPACE, true) {
+        Ok(nvs) => nvs,
+        Err(e) => {
+            warn!("Failed to open NVS namespace for scale: {:?}", e);
+            return false;
+        }
+    };
+
+    if let Err(e) = nvs.set_blob(NVS_KEY_ADC_CONFIG, &config.to_blob()) {
+        warn!("Failed to save ADC settings to NVS: {:?}", e);
+        return false;
+    }
+    true
+}
+
+/// Load the filter and stability settings from NVS
+fn load_filter_config_from_nvs() -> Option<FilterConfig> {
+    let nvs_guard = NVS_PARTITION.lock().unwrap();
+    let nvs_partition = nvs_guard.as_ref()?;
+
+    let nvs = match EspNvs::new(nvs_partition.clone(), NVS_NAMESPACE, true) {
+        Ok(nvs) => nvs,
+        Err(e) => {
+            warn!("Failed to open NVS namespace for scale: {:?}", e);
+            return None;
+        }
+    };
+
+    let mut buf = [0u8; 32];
+    match nvs.get_blob(NVS_KEY_FILTER_CONFIG, &mut buf) {
+        Ok(Some(data)) => match FilterConfig::from_blob(data) {
+            Ok(config) => Some(config),
+            Err(e) => {
+                warn!("Discarding saved filter settings: {}", e);
+                None
+            }
+        },
+        Ok(None) => None,
+        Err(e) => {
+            warn!("Failed to read filter settings from NVS: {:?}", e);
+            None
+        }
+    }
+}
+
+/// Save the filter and stability settings to NVS
+fn save_filter_config_to_nvs(config: &FilterConfig) -> bool {
+    let nvs_guard = NVS_PARTITION.lock().unwrap();
+    let Some(nvs_partition) = nvs_guard.as_ref() else {
+        warn!("No NVS partition available for saving filter settings");
+        return false;
+    };
+
+    let nvs = match EspNvs::new(nvs_partition.clone(), NVS_NAMESPACE, true) {
+        Ok(nvs) => nvs,
+        Err(e) => {
+            warn!("Failed to open NVS namespace for scale: {:?}", e);
+            return false;
+        }
+    };
+
+    if let Err(e) = nvs.set_blob(NVS_KEY_FILTER_CONFIG, &config.to_blob()) {
+        warn!("Failed to save filter settings to NVS: {:?}", e);
+        return false;
+    }
+    true
+}
+
+/// Load the zero tracking and drift compensation settings from NVS
+fn load_drift_config_from_nvs() -> Option<DriftConfig> {
+    let nvs_guard = NVS_PARTITION.lock().unwrap();
+    let nvs_partition = nvs_guard.as_ref()?;
+
+    let nvs = match EspNvs::new(nvs_partition.clone(), NVS_NAMESPACE, true) {
+        Ok(nvs) => nvs,
+        Err(e) => {
+            warn!("Failed to open NVS namespace for scale: {:?}", e);
+            return None;
+        }
+    };
+
+    let mut buf = [0u8; 32];
+    match nvs.get_blob(NVS_KEY_DRIFT_CONFIG, &mut buf) {
+        Ok(Some(data)) => match DriftConfig::from_blob(data) {
+            Ok(config) => Some(config),
+            Err(e) => {
+                warn!("Discarding saved drift settings: {}", e);
+                None
+            }
+        },
+        Ok(None) => None,
+        Err(e) => {
+            warn!("Failed to read drift settings from NVS: {:?}", e);
+            None
+        }
+    }
+}
+
+/// Save the zero tracking and drift compensation settings to NVS
+fn save_drift_config_to_nvs(config: &DriftConfig) -> bool {
+    let nvs_guard = NVS_PARTITION.lock().unwrap();
+    let Some(nvs_partition) = nvs_guard.as_ref() else {
+        warn!("No NVS partition available for saving drift settings");
+        return false;
+    };
+
+    let nvs = match EspNvs::new(nvs_partition.clone(), NVS_NAMESPACE, true) {
+        Ok(nvs) => nvs,
+        Err(e) => {
+            warn!("Failed to open NVS namespace for scale: {:?}", e);
+            return false;
+        }
+    };
+
+    if let Err(e) = nvs.set_blob(NVS_KEY_DRIFT_CONFIG, &config.to_blob()) {
+        warn!("Failed to save drift settings to NVS: {:?}", e);
+        return false;
+    }
+    true
+}
+
 /// Save calibration data to NVS
 fn save_calibration_to_nvs(calibration: &Calibration) -> bool {
     let nvs_guard = NVS_PARTITION.lock().unwrap();
@@ -106,20 +342,14 @@ fn save_calibration_to_nvs(calibration: &Calibration) -> bool {
         }
     };
 
-    // Pack calibration data into bytes
-    let cal_factor_x1000 = (calibration.cal_factor * 1000.0) as i32;
-    let mut buf = [0u8; 8];
-    buf[0..4].copy_from_slice(&calibration.zero_offset.to_le_bytes());
-    buf[4..8].copy_from_slice(&cal_factor_x1000.to_le_bytes());
-
-    // Save as blob
-    if let Err(e) = nvs.set_blob(NVS_KEY_CALIBRATION, &buf) {
+    // Save as versioned blob
+    if let Err(e) = nvs.set_blob(NVS_KEY_CALIBRATION, &calibration.to_blob()) {
         warn!("Failed to save calibration to NVS: {:?}", e);
         return false;
     }
 
-    info!("Calibration saved to NVS: zero_offset={}, cal_factor={}",
-          calibration.zero_offset, calibration.cal_factor);
+    info!("Calibration saved to NVS: zero_offset={}, cal_factor={}, quadratic={:e}",
+          calibration.zero_offset, calibration.cal_factor, calibration.quadratic);
     true
 }
 
@@ -181,6 +411,10 @@ pub extern "C" fn scale_get_status(status: *mut ScaleStatus) {
         status.stable = state.stable;
         status.tare_offset = state.calibration.zero_offset;
         status.cal_factor = state.calibration.cal_factor;
+        status.temperature_valid = state.drift.temperature().is_some();
+        status.temperature_c = state.drift.temperature().unwrap_or(0.0);
+        status.drift_correction_g = state.drift.correction_g();
+        status.auto_zero_g = state.drift.tracked_g();
     } else {
         status.initialized = false;
         status.weight_grams = 0.0;
@@ -188,6 +422,10 @@ pub extern "C" fn scale_get_status(status: *mut ScaleStatus) {
         status.stable = false;
         status.tare_offset = 0;
         status.cal_factor = 1.0;
+        status.temperature_valid = false;
+        status.temperature_c = 0.0;
+        status.drift_correction_g = 0.0;
+        status.auto_zero_g = 0.0;
     }
 }
 
@@ -236,74 +474,41 @@ pub extern "C" fn scale_is_stable() -> bool {
 }
 
 /// Tare the scale (set current weight as zero)
+/// Returns 0 on success, -1 on error
 #[no_mangle]
 pub extern "C" fn scale_tare() -> i32 {
-    let mut guard = SCALE_STATE.lock().unwrap();
-    if let Some(ref mut state) = *guard {
-        let result = shared_i2c::with_i2c(|i2c| {
-            nau7802::tare(i2c, state)
-        });
-        match result {
-            Some(Ok(())) => {
-                // Save calibration (includes tare offset) to NVS
-                save_calibration_to_nvs(&state.calibration);
-                0
-            }
-            _ => -1,
+    match tare() {
+        Ok(_) => 0,
+        Err(e) => {
+            warn!("{}", e);
+            -1
         }
-    } else {
-        -1
     }
 }
 
 /// Calibrate with a known weight (in grams)
+/// Returns 0 on success, -1 on error
 #[no_mangle]
 pub extern "C" fn scale_calibrate(known_weight_grams: f32) -> i32 {
-    let mut guard = SCALE_STATE.lock().unwrap();
-    if let Some(ref mut state) = *guard {
-        let result = shared_i2c::with_i2c(|i2c| {
-            nau7802::calibrate(i2c, state, known_weight_grams)
-        });
-        match result {
-            Some(Ok(())) => {
-                // Save calibration to NVS for persistence across restarts
-                save_calibration_to_nvs(&state.calibration);
-                0
-            }
-            _ => -1,
+    match calibrate(known_weight_grams) {
+        Ok(_) => 0,
+        Err(e) => {
+            warn!("{}", e);
+            -1
         }
-    } else {
-        -1
     }
 }
 
 /// Reset calibration to defaults
+/// Returns 0 on success, -1 on error
 #[no_mangle]
 pub extern "C" fn scale_reset_calibration() -> i32 {
-    info!("Resetting scale calibration to defaults...");
-    let mut guard = SCALE_STATE.lock().unwrap();
-    if let Some(ref mut state) = *guard {
-        // Reset to default calibration
-        state.calibration = Calibration::default();
-        state.weight_grams = 0.0;
-        state.stable = false;
-        state.stable_count = 0;
-
-        // Clear saved calibration from NVS
-        let nvs_guard = NVS_PARTITION.lock().unwrap();
-        if let Some(ref nvs_partition) = *nvs_guard {
-            if let Ok(nvs) = EspNvs::new(nvs_partition.clone(), NVS_NAMESPACE, true) {
-                let _ = nvs.remove(NVS_KEY_CALIBRATION);
-            }
+    match reset_calibration() {
+        Ok(_) => 0,
+        Err(e) => {
+            warn!("{}", e);
+            -1
         }
-        drop(nvs_guard);
-
-        info!("Scale calibration reset: zero_offset={}, cal_factor={}",
-              state.calibration.zero_offset, state.calibration.cal_factor);
-        0
-    } else {
-        warn!("Scale reset failed: no state");
-        -1
     }
 }
 
@@ -317,3 +522,487 @@ pub extern "C" fn scale_get_tare_offset() -> i32 {
         0
     }
 }
+
+/// Standard deviation (grams) of the recent readings the stability is
+/// judged on, -1 until enough readings were taken
+#[no_mangle]
+pub extern "C" fn scale_get_std_dev() -> f32 {
+    let guard = SCALE_STATE.lock().unwrap();
+    match *guard {
+        Some(ref state) if state.filter.std_dev().is_finite() => state.filter.std_dev(),
+        _ => -1.0,
+    }
+}
+
+/// Set the stability window (readings) and the largest standard deviation
+/// (grams) of a stable weight
+/// Returns 0 on success, -1 on error
+#[no_mangle]
+pub extern "C" fn scale_set_stability(window: i32, max_std_dev_g: f32) -> i32 {
+    let result = filter_config().and_then(|config| {
+        set_filter_config(FilterConfig {
+            stability_window: usize::try_from(window).unwrap_or(0),
+            stability_std_dev_g: max_std_dev_g,
+            ..config
+        })
+    });
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            warn!("{}", e);
+            -1
+        }
+    }
+}
+
+/// Set the filter pipeline: median and moving average windows (1 = off),
+/// EMA alpha (1 = off) and the step (grams) on which the EMA snaps
+/// Returns 0 on success, -1 on error
+#[no_mangle]
+pub extern "C" fn scale_set_filter(median_window: i32, average_window: i32, ema_alpha: f32, snap_threshold_g: f32) -> i32 {
+    let result = filter_config().and_then(|config| {
+        set_filter_config(FilterConfig {
+            median_window: usize::try_from(median_window).unwrap_or(0),
+            average_window: usize::try_from(average_window).unwrap_or(0),
+            ema_alpha,
+            snap_threshold_g,
+            ..config
+        })
+    });
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            warn!("{}", e);
+            -1
+        }
+    }
+}
+
+/// Log every raw reading (`SCALE_TRACE <raw>`) for recording filter traces
+#[no_mangle]
+pub extern "C" fn scale_set_trace_logging(enabled: bool) {
+    let mut guard = SCALE_STATE.lock().unwrap();
+    if let Some(ref mut state) = *guard {
+        state.trace_logging = enabled;
+    }
+}
+
+/// Set auto-zero tracking: on/off, the band (grams) taken as an empty
+/// platform and the largest correction per reading (grams)
+/// Returns 0 on success, -1 on error
+#[no_mangle]
+pub extern "C" fn scale_set_auto_zero(enabled: bool, band_g: f32, rate_g: f32) -> i32 {
+    let result = drift_config().and_then(|config| {
+        set_drift_config(DriftConfig {
+            auto_zero: enabled,
+            auto_zero_band_g: band_g,
+            auto_zero_rate_g: rate_g,
+            ..config
+        })
+    });
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            warn!("{}", e);
+            -1
+        }
+    }
+}
+
+/// Set temperature drift compensation: source (0 = off, 1 = NAU7802
+/// internal sensor, 2 = AMS temperature), zero drift (grams per °C) and
+/// span drift (ppm per °C)
+/// Returns 0 on success, -1 on error
+#[no_mangle]
+pub extern "C" fn scale_set_temp_compensation(source: i32, zero_g_per_c: f32, span_ppm_per_c: f32) -> i32 {
+    let Some(temp_source) = TempSource::from_i32(source) else {
+        warn!("Invalid temperature source: {}", source);
+        return -1;
+    };
+    let result = drift_config().and_then(|config| {
+        set_drift_config(DriftConfig { temp_source, zero_g_per_c, span_ppm_per_c, ..config })
+    });
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            warn!("{}", e);
+            -1
+        }
+    }
+}
+
+/// Feed the AMS temperature (°C) used when it is the compensation source
+#[no_mangle]
+pub extern "C" fn scale_set_temperature(temperature_c: f32) {
+    set_ams_temperature(temperature_c);
+}
+
+/// Get the active ADC settings
+/// Returns 0 on success, -1 if the scale is not initialized
+#[no_mangle]
+pub extern "C" fn scale_get_adc_config(config: *mut ScaleAdcConfig) -> i32 {
+    if config.is_null() {
+        return -1;
+    }
+    let Ok(active) = adc_config() else {
+        return -1;
+    };
+
+    let config = unsafe { &mut *config };
+    config.sample_rate_sps = active.sample_rate.sps() as i32;
+    config.gain = active.gain.factor() as i32;
+    config.ldo_mv = active.ldo.millivolts() as i32;
+    config.channel = active.channel.number() as i32;
+    0
+}
+
+/// Change and persist the ADC settings (see `ScaleAdcConfig` for the values)
+/// Returns 0 on success, -1 on error
+#[no_mangle]
+pub extern "C" fn scale_set_adc_config(sample_rate_sps: i32, gain: i32, ldo_mv: i32, channel: i32) -> i32 {
+    let result = parse_adc_config(sample_rate_sps, gain, ldo_mv, channel).and_then(set_adc_config);
+    match result {
+        Ok(_) => 0,
+        Err(e) => {
+            warn!("{}", e);
+            -1
+        }
+    }
+}
+
+/// Discard the reference weights measured so far
+#[no_mangle]
+pub extern "C" fn scale_cal_clear_points() {
+    CAL_POINTS.lock().unwrap().clear();
+}
+
+/// Measure a known weight (in grams) as the next calibration point
+/// Returns the number of points so far, -1 on error
+#[no_mangle]
+pub extern "C" fn scale_cal_add_point(known_weight_grams: f32) -> i32 {
+    match add_calibration_point(known_weight_grams) {
+        Ok(count) => count as i32,
+        Err(e) => {
+            warn!("{}", e);
+            -1
+        }
+    }
+}
+
+/// Number of calibration points measured since the last tare
+#[no_mangle]
+pub extern "C" fn scale_cal_point_count() -> i32 {
+    CAL_POINTS.lock().unwrap().len() as i32
+}
+
+/// Fit the calibration curve to the measured points and persist it
+/// Returns 0 on success, -1 on error
+#[no_mangle]
+pub extern "C" fn scale_cal_finish() -> i32 {
+    match finish_calibration() {
+        Ok(_) => 0,
+        Err(e) => {
+            warn!("{}", e);
+            -1
+        }
+    }
+}
+
+/// Whether the last fit was quadratic (else linear)
+#[no_mangle]
+pub extern "C" fn scale_cal_is_quadratic() -> bool {
+    let guard = LAST_FIT.lock().unwrap();
+    guard.as_ref().is_some_and(|fit| fit.kind == CurveKind::Quadratic)
+}
+
+/// Largest absolute residual of the last fit (grams)
+#[no_mangle]
+pub extern "C" fn scale_cal_get_max_error() -> f32 {
+    let guard = LAST_FIT.lock().unwrap();
+    guard.as_ref().map_or(0.0, |fit| fit.max_error)
+}
+
+/// RMS residual of the last fit (grams)
+#[no_mangle]
+pub extern "C" fn scale_cal_get_rms_error() -> f32 {
+    let guard = LAST_FIT.lock().unwrap();
+    guard.as_ref().map_or(0.0, |fit| fit.rms_error)
+}
+
+/// Residual (fitted minus known grams) of point `index` of the last fit
+#[no_mangle]
+pub extern "C" fn scale_cal_get_residual(index: i32) -> f32 {
+    let guard = LAST_FIT.lock().unwrap();
+    guard
+        .as_ref()
+        .and_then(|fit| fit.residuals.get(usize::try_from(index).ok()?).copied())
+        .unwrap_or(0.0)
+}
+
+// =============================================================================
+// Rust API (used by FFI wrappers and backend commands)
+// =============================================================================
+
+/// Tare the scale and persist the new zero offset
+/// Measured calibration points are discarded (they are relative to the old zero)
+/// Returns the updated calibration
+pub fn tare() -> Result<Calibration, String> {
+    let mut guard = SCALE_STATE.lock().unwrap();
+    let Some(ref mut state) = *guard else {
+        return Err("Scale not initialized".to_string());
+    };
+
+    if !shared_i2c::is_initialized() {
+        return Err("I2C bus not available".to_string());
+    }
+
+    match nau7802::tare(&mut SharedI2cBus, &mut FreeRtos, state) {
+        Ok(()) => {
+            CAL_POINTS.lock().unwrap().clear();
+            // Save calibration (includes tare offset) to NVS
+            save_calibration_to_nvs(&state.calibration);
+            Ok(state.calibration)
+        }
+        Err(e) => Err(format!("Tare failed: {:?}", e)),
+    }
+}
+
+/// Calibrate with a known weight (in grams) and persist the result
+/// Returns the updated calibration
+pub fn calibrate(known_weight_grams: f32) -> Result<Calibration, String> {
+    if known_weight_grams.is_nan() || known_weight_grams <= 0.0 {
+        return Err(format!("Invalid calibration weight: {}g", known_weight_grams));
+    }
+
+    let mut guard = SCALE_STATE.lock().unwrap();
+    let Some(ref mut state) = *guard else {
+        return Err("Scale not initialized".to_string());
+    };
+
+    if !shared_i2c::is_initialized() {
+        return Err("I2C bus not available".to_string());
+    }
+
+    match nau7802::calibrate(&mut SharedI2cBus, &mut FreeRtos, state, known_weight_grams) {
+        Ok(()) => {
+            // Save calibration to NVS for persistence across restarts
+            save_calibration_to_nvs(&state.calibration);
+            Ok(state.calibration)
+        }
+        Err(e) => Err(format!("Calibration failed: {:?}", e)),
+    }
+}
+
+/// Measure a known weight (in grams) as the next calibration point
+/// Returns the number of points so far
+pub fn add_calibration_point(known_weight_grams: f32) -> Result<usize, String> {
+    if known_weight_grams.is_nan() || known_weight_grams <= 0.0 {
+        return Err(format!("Invalid calibration weight: {}g", known_weight_grams));
+    }
+    if CAL_POINTS.lock().unwrap().len() >= MAX_POINTS {
+        return Err(format!("At most {} calibration points", MAX_POINTS));
+    }
+
+    let mut guard = SCALE_STATE.lock().unwrap();
+    let Some(ref mut state) = *guard else {
+        return Err("Scale not initialized".to_string());
+    };
+
+    if !shared_i2c::is_initialized() {
+        return Err("I2C bus not available".to_string());
+    }
+
+    match nau7802::measure_point(&mut SharedI2cBus, &mut FreeRtos, state, known_weight_grams) {
+        Ok(point) => {
+            let mut points = CAL_POINTS.lock().unwrap();
+            points.push(point);
+            info!("Calibration point {}: {}g -> delta {}", points.len(), point.grams, point.delta);
+            Ok(points.len())
+        }
+        Err(e) => Err(format!("Calibration point failed: {:?}", e)),
+    }
+}
+
+/// Fit the calibration curve to the measured points (quadratic from three
+/// points on) and persist the result
+/// Returns the updated calibration and the residual report
+pub fn finish_calibration() -> Result<(Calibration, FitReport), String> {
+    let points = CAL_POINTS.lock().unwrap().clone();
+
+    let mut guard = SCALE_STATE.lock().unwrap();
+    let Some(ref mut state) = *guard else {
+        return Err("Scale not initialized".to_string());
+    };
+
+    let mut cal = state.calibration;
+    let kind = CurveKind::for_points(points.len());
+    let report = calibration::fit(&mut cal, &points, kind).map_err(|e| format!("Calibration failed: {}", e))?;
+
+    info!("{:?} fit over {} points: max error {:.2}g, rms {:.2}g",
+          report.kind, points.len(), report.max_error, report.rms_error);
+    for (point, residual) in points.iter().zip(&report.residuals) {
+        info!("  {:>8.1}g: {:+.2}g", point.grams, residual);
+    }
+
+    // The last reference weight is still on the scale
+    let weight = points.last().map_or(0.0, |p| p.grams);
+    nau7802::apply_calibration(state, cal, weight);
+    save_calibration_to_nvs(&state.calibration);
+    *LAST_FIT.lock().unwrap() = Some(report.clone());
+    Ok((state.calibration, report))
+}
+
+/// Current filter and stability settings
+pub fn filter_config() -> Result<FilterConfig, String> {
+    let guard = SCALE_STATE.lock().unwrap();
+    match *guard {
+        Some(ref state) => Ok(state.filter.config()),
+        None => Err("Scale not initialized".to_string()),
+    }
+}
+
+/// Change and persist the filter and stability settings (takes effect
+/// immediately)
+pub fn set_filter_config(config: FilterConfig) -> Result<(), String> {
+    config.validate().map_err(|e| format!("Invalid filter settings: {}", e))?;
+
+    let mut guard = SCALE_STATE.lock().unwrap();
+    let Some(ref mut state) = *guard else {
+        return Err("Scale not initialized".to_string());
+    };
+    if config == state.filter.config() {
+        return Ok(());
+    }
+    state.filter.set_config(config);
+    state.stable = false;
+    info!("Scale filter settings: {:?}", config);
+    save_filter_config_to_nvs(&config);
+    Ok(())
+}
+
+/// Current zero tracking and drift compensation settings
+pub fn drift_config() -> Result<DriftConfig, String> {
+    let guard = SCALE_STATE.lock().unwrap();
+    match *guard {
+        Some(ref state) => Ok(state.drift.config()),
+        None => Err("Scale not initialized".to_string()),
+    }
+}
+
+/// Change and persist the zero tracking and drift compensation settings
+pub fn set_drift_config(config: DriftConfig) -> Result<(), String> {
+    config.validate().map_err(|e| format!("Invalid drift settings: {}", e))?;
+
+    let mut guard = SCALE_STATE.lock().unwrap();
+    let Some(ref mut state) = *guard else {
+        return Err("Scale not initialized".to_string());
+    };
+    if config == state.drift.config() {
+        return Ok(());
+    }
+    state.drift.set_config(config);
+    info!("Scale drift settings: {:?}", config);
+    save_drift_config_to_nvs(&config);
+    Ok(())
+}
+
+/// AMS temperature (°C) from the backend; ignored unless the AMS is the
+/// compensation source
+pub fn set_ams_temperature(temperature_c: f32) {
+    let mut guard = SCALE_STATE.lock().unwrap();
+    if let Some(ref mut state) = *guard {
+        if state.drift.config().temp_source == TempSource::Ams {
+            state.drift.set_temperature(temperature_c);
+        }
+    }
+}
+
+/// Active ADC settings
+pub fn adc_config() -> Result<AdcConfig, String> {
+    let guard = SCALE_STATE.lock().unwrap();
+    match *guard {
+        Some(ref state) => Ok(state.adc_config),
+        None => Err("Scale not initialized".to_string()),
+    }
+}
+
+/// ADC settings from plain values (samples per second, gain factor, LDO
+/// millivolts, channel number)
+pub fn parse_adc_config(sample_rate_sps: i32, gain: i32, ldo_mv: i32, channel: i32) -> Result<AdcConfig, String> {
+    Ok(AdcConfig {
+        sample_rate: u16::try_from(sample_rate_sps)
+            .ok()
+            .and_then(SampleRate::from_sps)
+            .ok_or_else(|| format!("Invalid sample rate: {} SPS", sample_rate_sps))?,
+        gain: u8::try_from(gain).ok().and_then(Gain::from_factor).ok_or_else(|| format!("Invalid gain: {}", gain))?,
+        ldo: u16::try_from(ldo_mv)
+            .ok()
+            .and_then(LdoVoltage::from_millivolts)
+            .ok_or_else(|| format!("Invalid LDO voltage: {}mV", ldo_mv))?,
+        channel: u8::try_from(channel)
+            .ok()
+            .and_then(Channel::from_number)
+            .ok_or_else(|| format!("Invalid channel: {}", channel))?,
+    })
+}
+
+/// Apply new ADC settings (with AFE calibration) and persist them, along
+/// with the calibration rescaled for a gain change
+/// Returns the active settings
+pub fn set_adc_config(config: AdcConfig) -> Result<AdcConfig, String> {
+    let mut guard = SCALE_STATE.lock().unwrap();
+    let Some(ref mut state) = *guard else {
+        return Err("Scale not initialized".to_string());
+    };
+    if config == state.adc_config {
+        return Ok(config);
+    }
+
+    if !shared_i2c::is_initialized() {
+        return Err("I2C bus not available".to_string());
+    }
+
+    // On failure the old settings stay active (and nothing is saved)
+    let gain_changed = config.gain != state.adc_config.gain;
+    match nau7802::apply_adc_config(&mut SharedI2cBus, &mut FreeRtos, state, config) {
+        Ok(()) => {
+            info!("Scale ADC settings: {:?}", config);
+            save_adc_config_to_nvs(&config);
+            if gain_changed {
+                save_calibration_to_nvs(&state.calibration);
+            }
+            Ok(config)
+        }
+        Err(e) => Err(format!("ADC configuration failed: {:?}", e)),
+    }
+}
+
+/// Reset calibration to defaults and clear it from NVS
+/// Returns the default calibration
+pub fn reset_calibration() -> Result<Calibration, String> {
+    info!("Resetting scale calibration to defaults...");
+    let mut guard = SCALE_STATE.lock().unwrap();
+    let Some(ref mut state) = *guard else {
+        return Err("Scale reset failed: no state".to_string());
+    };
+
+    // Reset to default calibration
+    state.calibration = Calibration::default();
+    state.drift.reset();
+    CAL_POINTS.lock().unwrap().clear();
+    *LAST_FIT.lock().unwrap() = None;
+    state.reset_weight(0.0);
+
+    // Clear saved calibration from NVS
+    let nvs_guard = NVS_PARTITION.lock().unwrap();
+    if let Some(ref nvs_partition) = *nvs_guard {
+        if let Ok(nvs) = EspNvs::new(nvs_partition.clone(), NVS_NAMESPACE, true) {
+            let _ = nvs.remove(NVS_KEY_CALIBRATION);
+        }
+    }
+    drop(nvs_guard);
+
+    info!("Scale calibration reset: zero_offset={}, cal_factor={}",
+          state.calibration.zero_offset, state.calibration.cal_factor);
+    Ok(state.calibration)
+}
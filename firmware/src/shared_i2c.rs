@@ -25,7 +25,6 @@ where
 }
 
 /// Check if I2C is initialized
-#[allow(dead_code)]
 pub fn is_initialized() -> bool {
     let guard = SHARED_I2C.lock().unwrap();
     guard.is_some()
@@ -0,0 +1,126 @@
+//! Spool identity of the tags on the reader
+//!
+//! Bambu Lab spools carry a tag on each side. The chip UIDs differ, but both
+//! tags hold the same tray UID, so the tray UID identifies the spool. The
+//! chip UIDs seen with each tray UID are remembered (and persisted to NVS):
+//! the other side of a known spool is recognised as soon as it is detected,
+//! and an inventory spool linked by one side is found from the other. Tags
+//! without a tray UID are identified by their chip UID. The registry itself
+//! is host-tested in firmware-core (`nfc::spool_identity`).
+
+use esp_idf_svc::nvs::{EspDefaultNvsPartition, EspNvs};
+use log::{info, warn};
+use spoolbuddy_firmware_core::nfc::spool_identity::Registry;
+use std::sync::Mutex;
+
+/// NVS namespace and key for the spool list blob
+const NVS_NAMESPACE: &str = "spool_ids";
+const NVS_KEY_SPOOLS: &str = "spools";
+
+struct SpoolIds {
+    nvs: Option<EspDefaultNvsPartition>,
+    registry: Registry,
+}
+
+static SPOOL_IDS: Mutex<Option<SpoolIds>> = Mutex::new(None);
+
+/// Load the persisted spool list
+pub fn init(nvs: Option<EspDefaultNvsPartition>) {
+    let registry = nvs.as_ref().and_then(load_registry).unwrap_or_default();
+    if !registry.spools.is_empty() {
+        info!("Spool identity: {} known spool(s)", registry.spools.len());
+    }
+    *SPOOL_IDS.lock().unwrap() = Some(SpoolIds { nvs, registry });
+}
+
+fn load_registry(nvs_partition: &EspDefaultNvsPartition) -> Option<Registry> {
+    let nvs = match EspNvs::new(nvs_partition.clone(), NVS_NAMESPACE, true) {
+        Ok(nvs) => nvs,
+        Err(e) => {
+            warn!("Failed to open NVS namespace for spool identities: {:?}", e);
+            return None;
+        }
+    };
+
+    let len = match nvs.blob_len(NVS_KEY_SPOOLS) {
+        Ok(Some(len)) => len,
+        _ => return None,
+    };
+
+    let mut buf = vec![0u8; len];
+    match nvs.get_blob(NVS_KEY_SPOOLS, &mut buf) {
+        Ok(Some(data)) => match serde_json::from_slice(data) {
+            Ok(registry) => Some(registry),
+            Err(e) => {
+                warn!("Discarding corrupt spool identities: {}", e);
+                None
+            }
+        },
+        _ => None,
+    }
+}
+
+fn save_registry(ids: &SpoolIds) {
+    let Some(ref nvs_partition) = ids.nvs else {
+        return;
+    };
+
+    let nvs = match EspNvs::new(nvs_partition.clone(), NVS_NAMESPACE, true) {
+        Ok(nvs) => nvs,
+        Err(e) => {
+            warn!("Failed to open NVS namespace for spool identities: {:?}", e);
+            return;
+        }
+    };
+
+    let data = match serde_json::to_vec(&ids.registry) {
+        Ok(d) => d,
+        Err(e) => {
+            warn!("Failed to serialize spool identities: {}", e);
+            return;
+        }
+    };
+
+    if let Err(e) = nvs.set_blob(NVS_KEY_SPOOLS, &data) {
+        warn!("Failed to save spool identities to NVS: {:?}", e);
+    }
+}
+
+/// Remember the tray UID decoded from a tag.
+/// Returns true if the tag was not known to hold it (its spool's other tags
+/// are only known from now on).
+pub fn record(tag_uid: &str, tray_uid: &str) -> bool {
+    if tag_uid.is_empty() || tray_uid.is_empty() {
+        return false;
+    }
+    let mut guard = SPOOL_IDS.lock().unwrap();
+    let Some(ids) = guard.as_mut() else {
+        return false;
+    };
+
+    let new = ids.registry.record(tag_uid, tray_uid);
+    if new {
+        info!("Spool identity: tag {} is spool {}", tag_uid, tray_uid);
+        save_registry(ids);
+    }
+    new
+}
+
+/// Spool identity of a tag: its tray UID if known, else the chip UID
+pub fn spool_id(tag_uid: &str) -> String {
+    let guard = SPOOL_IDS.lock().unwrap();
+    match guard.as_ref() {
+        Some(ids) => ids.registry.spool_id(tag_uid),
+        None => tag_uid.to_string(),
+    }
+}
+
+/// Chip UIDs of the spool a tag belongs to, starting with the tag itself
+/// (the others most recently seen first)
+pub fn spool_tag_uids(tag_uid: &str) -> Vec<String> {
+    let guard = SPOOL_IDS.lock().unwrap();
+    match guard.as_ref() {
+        Some(ids) => ids.registry.spool_tag_uids(tag_uid),
+        None => vec![tag_uid.to_string()],
+    }
+}
@@ -7,7 +7,7 @@ use esp_idf_svc::sntp::{EspSntp, SyncStatus, SntpConf};
 use log::{info, warn};
 use std::ffi::c_int;
 use std::sync::Mutex;
-use std::time::SystemTime;
+use std::time::{Instant, SystemTime};
 
 /// Time sync state
 static TIME_SYNCED: Mutex<bool> = Mutex::new(false);
@@ -16,6 +16,9 @@ static SNTP_HANDLE: Mutex<Option<EspSntp<'static>>> = Mutex::new(None);
 /// Backend time (hour, minute) - used when SNTP isn't available
 static BACKEND_TIME: Mutex<Option<(u8, u8)>> = Mutex::new(None);
 
+/// Backend unix timestamp and the moment it was received
+static BACKEND_TIMESTAMP: Mutex<Option<(u64, Instant)>> = Mutex::new(None);
+
 /// Initialize SNTP time synchronization
 /// Call this after WiFi is connected
 pub fn init_sntp() {
@@ -63,6 +66,25 @@ pub fn set_backend_time(hour: u8, minute: u8) {
     *backend_time = Some((hour, minute));
 }
 
+/// Set unix timestamp from backend server
+pub fn set_backend_timestamp(timestamp: u64) {
+    let mut backend_ts = BACKEND_TIMESTAMP.lock().unwrap();
+    *backend_ts = Some((timestamp, Instant::now()));
+}
+
+/// Get current unix time (UTC seconds)
+/// Uses SNTP when synced, otherwise the last backend timestamp plus elapsed time
+pub fn unix_time() -> Option<u64> {
+    if is_time_synced() {
+        if let Ok(duration) = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+            return Some(duration.as_secs());
+        }
+    }
+
+    let backend_ts = BACKEND_TIMESTAMP.lock().unwrap();
+    backend_ts.map(|(ts, received)| ts + received.elapsed().as_secs())
+}
+
 // Timezone offset in seconds (CET = UTC+1 = 3600, CEST = UTC+2 = 7200)
 // TODO: Make this configurable via backend
 const TIMEZONE_OFFSET_SECS: u64 = 3600; // CET (UTC+1)
@@ -0,0 +1,311 @@
+//! TLS certificate pinning for https backends
+//!
+//! The backend's certificate (or the CA that issued it) is trusted on first
+//! use: at the first contact with an https server, before anything else is
+//! sent to it, the display fetches it from `/api/device/tls-certificate`,
+//! checks that a connection verified against it succeeds, and stores the PEM,
+//! its SHA-256 fingerprint and the server URL in NVS. From then on it is the
+//! only trust anchor for https connections (ESP-TLS global CA store), so a
+//! server presenting another certificate is refused - firmware downloads
+//! included. The pin is only dropped when the server URL is changed on the
+//! display itself (settings dialog); the new server is then pinned at its
+//! first contact. Discovery prefers the pinned server and a URL set by a
+//! backend command keeps the pin, so another host can't take over by
+//! advertising itself or by being named in a command.
+//!
+//! Backend requests always verify against the global CA store, so they fail
+//! while nothing is pinned. Only the capture itself and the mismatch probe
+//! connect unverified (CONFIG_ESP_TLS_SKIP_SERVER_CERT_VERIFY); neither sends
+//! an API key or data.
+
+use embedded_svc::http::client::Client as HttpClient;
+use esp_idf_svc::http::client::{Configuration as HttpConfig, EspHttpConnection};
+use esp_idf_svc::nvs::{EspDefaultNvsPartition, EspNvs};
+use log::{info, warn};
+use serde::Deserialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// NVS namespace (shared with the server URL) and keys
+const NVS_NAMESPACE: &str = "backend";
+const NVS_KEY_CERT: &str = "tls_cert";
+const NVS_KEY_FINGERPRINT: &str = "tls_fp";
+const NVS_KEY_SERVER: &str = "tls_url";
+
+/// Largest certificate PEM accepted (leaf plus a short chain)
+const MAX_CERT_SIZE: usize = 6144;
+
+/// Wait before retrying a failed capture
+const CAPTURE_RETRY: Duration = Duration::from_secs(30);
+
+/// HTTP timeout in milliseconds
+const HTTP_TIMEOUT_MS: u64 = 5000;
+
+/// Response to `GET /api/device/tls-certificate`
+#[derive(Debug, Deserialize)]
+struct CertificateResponse {
+    certificate: String,
+    fingerprint: String,
+}
+
+static NVS_PARTITION: Mutex<Option<EspDefaultNvsPartition>> = Mutex::new(None);
+/// SHA-256 fingerprint (hex) of the pinned certificate
+static FINGERPRINT: Mutex<Option<String>> = Mutex::new(None);
+/// Server URL the certificate was pinned for
+static SERVER: Mutex<Option<String>> = Mutex::new(None);
+static PINNED: AtomicBool = AtomicBool::new(false);
+static CAPTURE_RETRY_AT: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Load the pinned certificate and install it as the trust anchor
+pub fn init(nvs: Option<EspDefaultNvsPartition>) {
+    if let Some((pem, fingerprint, server)) = nvs.as_ref().and_then(load_pin) {
+        match install_ca_store(&pem) {
+            Ok(()) => {
+                info!("TLS pin loaded for {} ({})", server, fingerprint);
+                *FINGERPRINT.lock().unwrap() = Some(fingerprint);
+                *SERVER.lock().unwrap() = Some(server);
+                PINNED.store(true, Ordering::Relaxed);
+            }
+            Err(e) => warn!("Failed to install pinned certificate: {}", e),
+        }
+    }
+    *NVS_PARTITION.lock().unwrap() = nvs;
+}
+
+/// Pinned PEM, fingerprint and server
+/// Pins saved without a server (older firmware) are not used; the server is
+/// pinned again at its next contact.
+fn load_pin(nvs_partition: &EspDefaultNvsPartition) -> Option<(String, String, String)> {
+    let nvs = match EspNvs::new(nvs_partition.clone(), NVS_NAMESPACE, true) {
+        Ok(nvs) => nvs,
+        Err(e) => {
+            warn!("Failed to open NVS namespace for TLS pin: {:?}", e);
+            return None;
+        }
+    };
+
+    let mut cert_buf = vec![0u8; MAX_CERT_SIZE];
+    let pem = match nvs.get_blob(NVS_KEY_CERT, &mut cert_buf) {
+        Ok(Some(data)) => String::from_utf8(data.to_vec()).ok()?,
+        _ => return None,
+    };
+
+    let mut fp_buf = [0u8; 65];
+    let fingerprint = match nvs.get_str(NVS_KEY_FINGERPRINT, &mut fp_buf) {
+        Ok(Some(s)) => s.to_string(),
+        _ => return None,
+    };
+
+    let mut server_buf = [0u8; 256];
+    let server = match nvs.get_str(NVS_KEY_SERVER, &mut server_buf) {
+        Ok(Some(s)) if !s.is_empty() => s.to_string(),
+        _ => return None,
+    };
+
+    Some((pem, fingerprint, server))
+}
+
+fn save_pin(pem: &str, fingerprint: &str, server: &str) -> Result<(), String> {
+    let nvs_guard = NVS_PARTITION.lock().unwrap();
+    let Some(nvs_partition) = nvs_guard.as_ref() else {
+        return Err("No NVS partition available".to_string());
+    };
+    let nvs_clone = nvs_partition.clone();
+    drop(nvs_guard); // Release lock before NVS operations
+
+    let nvs = EspNvs::new(nvs_clone, NVS_NAMESPACE, true)
+        .map_err(|e| format!("Failed to open NVS namespace: {:?}", e))?;
+    nvs.set_blob(NVS_KEY_CERT, pem.as_bytes())
+        .map_err(|e| format!("Failed to save certificate: {:?}", e))?;
+    nvs.set_str(NVS_KEY_FINGERPRINT, fingerprint)
+        .map_err(|e| format!("Failed to save fingerprint: {:?}", e))?;
+    nvs.set_str(NVS_KEY_SERVER, server)
+        .map_err(|e| format!("Failed to save pinned server: {:?}", e))
+}
+
+/// Set the ESP-TLS global CA store to the given PEM
+fn install_ca_store(pem: &str) -> Result<(), String> {
+    // mbedTLS wants the PEM NUL-terminated, with the terminator counted
+    let mut buf = pem.as_bytes().to_vec();
+    buf.push(0);
+    let err = unsafe { esp_idf_sys::esp_tls_set_global_ca_store(buf.as_ptr(), buf.len() as u32) };
+    esp_idf_sys::esp!(err).map_err(|e| format!("esp_tls_set_global_ca_store: {:?}", e))
+}
+
+/// HTTP client configuration for backend requests
+/// https connections are verified against the pinned certificate, and fail
+/// while none is pinned (see `ensure_pinned`).
+pub fn http_config(timeout: Duration) -> HttpConfig {
+    HttpConfig {
+        timeout: Some(timeout),
+        use_global_ca_store: true,
+        ..Default::default()
+    }
+}
+
+/// Check whether a certificate is pinned
+pub fn is_pinned() -> bool {
+    PINNED.load(Ordering::Relaxed)
+}
+
+/// Fingerprint of the pinned certificate
+pub fn fingerprint() -> Option<String> {
+    FINGERPRINT.lock().unwrap().clone()
+}
+
+/// Server URL the certificate is pinned for
+pub fn pinned_server() -> Option<String> {
+    SERVER.lock().unwrap().clone()
+}
+
+/// Drop a pin taken for another server than `base_url`
+/// Only for a server change made on the display itself (settings dialog).
+pub fn clear_for_other_server(base_url: &str) {
+    if pinned_server().is_some_and(|server| server != base_url) {
+        clear();
+    }
+}
+
+/// Make sure an https server is pinned, capturing its certificate on first use
+/// Call before the first request to a server. Returns true when requests to
+/// `base_url` can be made (always for plain http).
+pub fn ensure_pinned(base_url: &str) -> bool {
+    if !base_url.starts_with("https://") || is_pinned() {
+        return true;
+    }
+
+    {
+        let retry_at = CAPTURE_RETRY_AT.lock().unwrap();
+        if retry_at.is_some_and(|at| Instant::now() < at) {
+            return false;
+        }
+    }
+
+    match capture(base_url) {
+        Ok(fingerprint) => {
+            info!("Pinned backend certificate {}", fingerprint);
+            true
+        }
+        Err(e) => {
+            warn!("Failed to pin backend certificate: {}", e);
+            *CAPTURE_RETRY_AT.lock().unwrap() = Some(Instant::now() + CAPTURE_RETRY);
+            false
+        }
+    }
+}
+
+/// Fetch, check and store the server's certificate
+fn capture(base_url: &str) -> Result<String, String> {
+    // Nothing is pinned yet, so this connection is unverified (trust on first use)
+    let url = format!("{}/api/device/tls-certificate", base_url);
+    let (status, body) = http_get(&url, false)?;
+    if status != 200 {
+        return Err(format!("HTTP error: {}", status));
+    }
+
+    let response: CertificateResponse = serde_json::from_str(&body)
+        .map_err(|e| format!("Invalid certificate response: {}", e))?;
+
+    let der = pem_to_der(&response.certificate).ok_or("Invalid certificate PEM")?;
+    let fingerprint = crate::crypto::to_hex(&crate::crypto::sha256(&der));
+    if !fingerprint.eq_ignore_ascii_case(&response.fingerprint) {
+        return Err(format!("Fingerprint {} does not match certificate", response.fingerprint));
+    }
+
+    // The server must present a certificate that verifies against the pin
+    install_ca_store(&response.certificate)?;
+    if let Err(e) = http_get(&format!("{}/api/time", base_url), true) {
+        unsafe { esp_idf_sys::esp_tls_free_global_ca_store() };
+        return Err(format!("Server certificate does not verify against the offered one: {}", e));
+    }
+
+    save_pin(&response.certificate, &fingerprint, base_url)?;
+    *FINGERPRINT.lock().unwrap() = Some(fingerprint.clone());
+    *SERVER.lock().unwrap() = Some(base_url.to_string());
+    PINNED.store(true, Ordering::Relaxed);
+    Ok(fingerprint)
+}
+
+/// Check whether a failed https connection was caused by the server's certificate
+/// Connects once without verification; no API key or data is sent on it.
+pub fn is_pin_mismatch(base_url: &str) -> bool {
+    if !base_url.starts_with("https://") || !is_pinned() {
+        return false;
+    }
+    http_get(&format!("{}/api/time", base_url), false).is_ok()
+}
+
+/// Forget the pinned certificate (another server was set on the display)
+pub fn clear() {
+    *CAPTURE_RETRY_AT.lock().unwrap() = None;
+    if !PINNED.swap(false, Ordering::Relaxed) {
+        return;
+    }
+    unsafe { esp_idf_sys::esp_tls_free_global_ca_store() };
+    *FINGERPRINT.lock().unwrap() = None;
+    *SERVER.lock().unwrap() = None;
+
+    let nvs_guard = NVS_PARTITION.lock().unwrap();
+    let Some(nvs_partition) = nvs_guard.as_ref() else {
+        return;
+    };
+    let nvs_clone = nvs_partition.clone();
+    drop(nvs_guard);
+
+    if let Ok(nvs) = EspNvs::new(nvs_clone, NVS_NAMESPACE, true) {
+        let _ = nvs.remove(NVS_KEY_CERT);
+        let _ = nvs.remove(NVS_KEY_FINGERPRINT);
+        let _ = nvs.remove(NVS_KEY_SERVER);
+    }
+    info!("TLS pin cleared");
+}
+
+/// Decode the first certificate of a PEM bundle to DER
+pub fn pem_to_der(pem: &str) -> Option<Vec<u8>> {
+    const BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+    const END: &str = "-----END CERTIFICATE-----";
+
+    let start = pem.find(BEGIN)? + BEGIN.len();
+    let end = start + pem[start..].find(END)?;
+    crate::crypto::base64_decode(&pem[start..end])
+}
+
+/// Plain GET, verified against the pin or not at all
+fn http_get(url: &str, verify: bool) -> Result<(u16, String), String> {
+    let config = HttpConfig {
+        timeout: Some(Duration::from_millis(HTTP_TIMEOUT_MS)),
+        use_global_ca_store: verify,
+        ..Default::default()
+    };
+
+    let connection = EspHttpConnection::new(&config)
+        .map_err(|e| format!("HTTP connection failed: {:?}", e))?;
+
+    let mut client = HttpClient::wrap(connection);
+
+    let request = client.get(url)
+        .map_err(|e| format!("Request failed: {:?}", e))?;
+
+    let mut response = request.submit()
+        .map_err(|e| format!("Submit failed: {:?}", e))?;
+
+    let status = response.status();
+
+    let mut body = Vec::new();
+    let mut buf = [0u8; 512];
+    loop {
+        match response.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => {
+                body.extend_from_slice(&buf[..n]);
+                if body.len() > 2 * MAX_CERT_SIZE {
+                    return Err("Response too large".to_string());
+                }
+            }
+            Err(e) => return Err(format!("Read error: {:?}", e)),
+        }
+    }
+
+    Ok((status, String::from_utf8_lossy(&body).into_owned()))
+}
@@ -646,6 +646,42 @@ describe('API Client', () => {
       })
     })
 
+    describe('waitForDeviceCommand', () => {
+      it('should poll until the device reports a result', async () => {
+        let calls = 0
+        server.use(
+          http.get('/api/device/commands/abc123', () => {
+            calls += 1
+            if (calls < 3) {
+              return HttpResponse.json({ id: 'abc123', type: 'scale_calibrate', status: 'sent' })
+            }
+            return HttpResponse.json({
+              id: 'abc123',
+              type: 'scale_calibrate',
+              status: 'error',
+              error: 'Calibration failed: CalibrationFailed',
+            })
+          })
+        )
+
+        const result = await api.waitForDeviceCommand('abc123', 5000, 1)
+        expect(result.status).toBe('error')
+        expect(result.error).toContain('CalibrationFailed')
+        expect(calls).toBe(3)
+      })
+
+      it('should return the last status on timeout', async () => {
+        server.use(
+          http.get('/api/device/commands/slow', () => {
+            return HttpResponse.json({ id: 'slow', type: 'scale_tare', status: 'sent' })
+          })
+        )
+
+        const result = await api.waitForDeviceCommand('slow', 0, 1)
+        expect(result.status).toBe('sent')
+      })
+    })
+
     describe('resetScaleCalibration', () => {
       it('should reset scale calibration', async () => {
         server.use(
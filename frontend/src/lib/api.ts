@@ -138,6 +138,35 @@ export interface DeviceStatus {
   current_tag_id: string | null;
 }
 
+// Command queued for the display; the device reports the outcome later
+export interface DeviceCommandQueued {
+  success: boolean;
+  message: string;
+  command_id: string;
+}
+
+export interface DeviceCommandResult {
+  id: string;
+  type: string | null;
+  status: "pending" | "sent" | "ok" | "error" | "rejected";
+  error?: string | null;
+  data?: Record<string, unknown>;
+}
+
+// Display pairing state (the display's API key)
+export interface DeviceAuthStatus {
+  paired: boolean;
+  key_prefix: string | null;
+  last_used: number | null;
+  pending_pairings: number;
+}
+
+export interface DevicePairingApproved {
+  success: boolean;
+  message: string;
+  key_prefix: string;
+}
+
 // Cloud API types
 export interface CloudAuthStatus {
   is_authenticated: boolean;
@@ -627,16 +656,56 @@ class ApiClient {
     return this.request<DeviceStatus>("/device/status");
   }
 
-  async tareScale(): Promise<void> {
-    return this.request<void>("/device/scale/tare", { method: "POST" });
+  async tareScale(): Promise<DeviceCommandQueued | undefined> {
+    return this.request<DeviceCommandQueued | undefined>("/device/scale/tare", { method: "POST" });
+  }
+
+  async calibrateScale(knownWeight: number): Promise<DeviceCommandQueued | undefined> {
+    return this.request<DeviceCommandQueued | undefined>(`/device/scale/calibrate?known_weight=${knownWeight}`, { method: "POST" });
   }
 
-  async calibrateScale(knownWeight: number): Promise<void> {
-    return this.request<void>(`/device/scale/calibrate?known_weight=${knownWeight}`, { method: "POST" });
+  async resetScaleCalibration(): Promise<DeviceCommandQueued | undefined> {
+    return this.request<DeviceCommandQueued | undefined>("/device/scale/reset", { method: "POST" });
   }
 
-  async resetScaleCalibration(): Promise<void> {
-    return this.request<void>("/device/scale/reset", { method: "POST" });
+  async getDeviceCommand(commandId: string): Promise<DeviceCommandResult> {
+    return this.request<DeviceCommandResult>(`/device/commands/${commandId}`);
+  }
+
+  // Poll until the device reports the command outcome (or timeout)
+  async waitForDeviceCommand(commandId: string, timeoutMs = 20000, intervalMs = 500): Promise<DeviceCommandResult> {
+    const deadline = Date.now() + timeoutMs;
+    let result = await this.getDeviceCommand(commandId);
+    while ((result.status === "pending" || result.status === "sent") && Date.now() < deadline) {
+      await new Promise(resolve => setTimeout(resolve, intervalMs));
+      result = await this.getDeviceCommand(commandId);
+    }
+    return result;
+  }
+
+  async getDeviceAuth(): Promise<DeviceAuthStatus> {
+    return this.request<DeviceAuthStatus>("/device/auth");
+  }
+
+  async approveDevicePairing(code: string, apiKey: string): Promise<DevicePairingApproved> {
+    return this.request<DevicePairingApproved>(`/device/pair/approve?code=${encodeURIComponent(code)}`, {
+      method: "POST",
+      headers: { "X-API-Key": apiKey },
+    });
+  }
+
+  async rotateDeviceKey(apiKey: string): Promise<DeviceCommandQueued> {
+    return this.request<DeviceCommandQueued>("/device/rotate-key", {
+      method: "POST",
+      headers: { "X-API-Key": apiKey },
+    });
+  }
+
+  async unpairDevice(apiKey: string): Promise<void> {
+    return this.request<void>("/device/auth", {
+      method: "DELETE",
+      headers: { "X-API-Key": apiKey },
+    });
   }
 
   async writeTag(spoolId: string): Promise<void> {
@@ -1,7 +1,7 @@
 import { useState, useEffect, useCallback } from "preact/hooks";
 import * as preact from "preact";
 import { useWebSocket } from "../lib/websocket";
-import { api, CloudAuthStatus, VersionInfo, UpdateCheck, UpdateStatus, FirmwareCheck, AMSThresholds, DebugLoggingState, LogEntry, SystemInfo, APIKey, APIKeyCreate } from "../lib/api";
+import { api, CloudAuthStatus, VersionInfo, UpdateCheck, UpdateStatus, FirmwareCheck, AMSThresholds, DebugLoggingState, LogEntry, SystemInfo, APIKey, APIKeyCreate, DeviceAuthStatus } from "../lib/api";
 import { Cloud, CloudOff, LogOut, Loader2, Mail, Lock, Key, Download, RefreshCw, CheckCircle, AlertCircle, GitBranch, ExternalLink, Wifi, WifiOff, Cpu, Usb, RotateCcw, Upload, HardDrive, Palette, Sun, Moon, LayoutDashboard, Settings2, Package, Monitor, Scale, X, ChevronRight, Droplets, Thermometer, LifeBuoy, Bug, Trash2, FileText, Server, Database, Activity, HelpCircle, Play, Square, Copy, Globe, Plus } from "lucide-preact";
 import { useToast } from "../lib/toast";
 import { SerialTerminal } from "../components/SerialTerminal";
@@ -781,6 +781,87 @@ export function Settings() {
     setCalibrationWeight(500);
   };
 
+  // Display pairing (API key held by the display)
+  const [deviceAuth, setDeviceAuth] = useState<DeviceAuthStatus | null>(null);
+  const [pairingCode, setPairingCode] = useState('');
+  const [pairingApiKey, setPairingApiKey] = useState('');
+  const [pairing, setPairing] = useState(false);
+  const [rotatingKey, setRotatingKey] = useState(false);
+
+  const loadDeviceAuth = useCallback(async () => {
+    try {
+      setDeviceAuth(await api.getDeviceAuth());
+    } catch (e) {
+      console.error("Failed to load display pairing:", e);
+    }
+  }, []);
+
+  useEffect(() => {
+    if (activeTab === 'system') loadDeviceAuth();
+  }, [activeTab, loadDeviceAuth]);
+
+  const handleApprovePairing = async () => {
+    const code = pairingCode.trim();
+    if (!/^\d{6}$/.test(code)) {
+      showToast('error', 'Enter the 6-digit code shown on the display');
+      return;
+    }
+    if (!pairingApiKey.trim()) {
+      showToast('error', 'Enter an API key with control permission');
+      return;
+    }
+    setPairing(true);
+    try {
+      await api.approveDevicePairing(code, pairingApiKey.trim());
+      setPairingCode('');
+      setPairingApiKey('');
+      showToast('success', 'Display paired');
+      await loadDeviceAuth();
+    } catch (e) {
+      showToast('error', e instanceof Error ? e.message : 'Failed to pair display');
+    } finally {
+      setPairing(false);
+    }
+  };
+
+  const handleRotateDeviceKey = async () => {
+    if (!pairingApiKey.trim()) {
+      showToast('error', 'Enter an API key with control permission');
+      return;
+    }
+    setRotatingKey(true);
+    try {
+      const queued = await api.rotateDeviceKey(pairingApiKey.trim());
+      const result = await api.waitForDeviceCommand(queued.command_id);
+      if (result.status === 'ok') {
+        showToast('success', 'Display API key rotated');
+      } else if (result.status === 'pending' || result.status === 'sent') {
+        showToast('info', 'Display has not confirmed the new key yet');
+      } else {
+        showToast('error', result.error || 'Display could not store the new key');
+      }
+      await loadDeviceAuth();
+    } catch (e) {
+      showToast('error', e instanceof Error ? e.message : 'Failed to rotate key');
+    } finally {
+      setRotatingKey(false);
+    }
+  };
+
+  const handleUnpairDevice = async () => {
+    if (!pairingApiKey.trim()) {
+      showToast('error', 'Enter an API key with control permission');
+      return;
+    }
+    try {
+      await api.unpairDevice(pairingApiKey.trim());
+      showToast('success', 'Display unpaired');
+      await loadDeviceAuth();
+    } catch (e) {
+      showToast('error', e instanceof Error ? e.message : 'Failed to unpair display');
+    }
+  };
+
   // Load API keys when tab becomes active
   useEffect(() => {
     if (activeTab !== 'api') return;
@@ -1029,8 +1110,13 @@ export function Settings() {
       // Tare the scale (set zero point while empty)
       setCalibrating(true);
       try {
-        await api.tareScale();
-        setCalibrationStep('weight');
+        const queued = await api.tareScale();
+        const result = queued?.command_id ? await api.waitForDeviceCommand(queued.command_id) : null;
+        if (result && result.status !== 'ok') {
+          showToast('error', `Failed to set zero point: ${result.error ?? 'no response from device'}`);
+        } else {
+          setCalibrationStep('weight');
+        }
       } catch {
         showToast('error', 'Failed to set zero point');
       } finally {
@@ -1040,7 +1126,14 @@ export function Settings() {
       // Perform calibration with known weight
       setCalibrating(true);
       try {
-        await api.calibrateScale(calibrationWeight);
+        const queued = await api.calibrateScale(calibrationWeight);
+        const result = queued?.command_id ? await api.waitForDeviceCommand(queued.command_id) : null;
+        if (result && result.status !== 'ok') {
+          // Device reported the failure (e.g. no weight change detected)
+          showToast('error', `Calibration failed: ${result.error ?? 'no response from device'}`);
+          setCalibrationStep('idle');
+          return;
+        }
         // Wait a moment for the scale to update
         await new Promise(resolve => setTimeout(resolve, 1500));
         // Check if calibration actually worked by comparing current weight to target
@@ -2304,6 +2397,75 @@ export function Settings() {
                   </div>
                 </div>
 
+                {/* Pairing */}
+                <div class="p-4 rounded-xl bg-[var(--bg-tertiary)]/50 border border-[var(--border-color)]">
+                  <div class="flex items-center gap-2 mb-4">
+                    <Key class="w-4 h-4 text-[var(--accent)]" />
+                    <h3 class="text-sm font-semibold text-[var(--text-primary)]">Pairing</h3>
+                  </div>
+                  {deviceAuth?.paired ? (
+                    <>
+                      <div class="flex items-center justify-between">
+                        <div>
+                          <p class="text-sm text-[var(--text-primary)]">
+                            Paired <span class="font-mono text-[var(--text-muted)]">({deviceAuth.key_prefix}...)</span>
+                          </p>
+                          <p class="text-xs text-[var(--text-muted)]">
+                            {deviceAuth.last_used
+                              ? `Key last used ${new Date(deviceAuth.last_used * 1000).toLocaleString()}`
+                              : 'Key not used yet'}
+                          </p>
+                        </div>
+                        <div class="flex gap-2">
+                          <button onClick={handleRotateDeviceKey} disabled={!deviceConnected || rotatingKey} class="btn btn-ghost flex items-center gap-2">
+                            {rotatingKey ? <Loader2 class="w-4 h-4 animate-spin" /> : <RefreshCw class="w-4 h-4" />}
+                            <span class="hidden sm:inline">Rotate Key</span>
+                          </button>
+                          <button onClick={handleUnpairDevice} class="btn btn-ghost text-red-500">
+                            Unpair
+                          </button>
+                        </div>
+                      </div>
+                      <input
+                        type="password"
+                        placeholder="API key (control) to pair, rotate or unpair"
+                        value={pairingApiKey}
+                        onInput={(e) => setPairingApiKey((e.target as HTMLInputElement).value)}
+                        class="input w-full font-mono mt-3"
+                      />
+                    </>
+                  ) : (
+                    <p class="text-xs text-[var(--text-muted)] mb-3">
+                      The display shows a pairing code in its status bar. Enter it here, with an API key that has control permission, to give the display its API key.
+                    </p>
+                  )}
+                  {(!deviceAuth?.paired || (deviceAuth?.pending_pairings ?? 0) > 0) && (
+                    <div class={`flex gap-2 ${deviceAuth?.paired ? 'mt-4 pt-4 border-t border-[var(--border-color)]' : ''}`}>
+                      <input
+                        type="text"
+                        inputMode="numeric"
+                        maxLength={6}
+                        placeholder="Pairing code"
+                        value={pairingCode}
+                        onInput={(e) => setPairingCode((e.target as HTMLInputElement).value)}
+                        class="input flex-1 font-mono"
+                      />
+                      {!deviceAuth?.paired && (
+                        <input
+                          type="password"
+                          placeholder="API key (control)"
+                          value={pairingApiKey}
+                          onInput={(e) => setPairingApiKey((e.target as HTMLInputElement).value)}
+                          class="input flex-1 font-mono"
+                        />
+                      )}
+                      <button onClick={handleApprovePairing} disabled={pairing} class="btn btn-primary">
+                        {pairing ? <Loader2 class="w-4 h-4 animate-spin" /> : 'Pair'}
+                      </button>
+                    </div>
+                  )}
+                </div>
+
                 {/* USB Serial Terminal */}
                 <details class="group">
                   <summary class="flex items-center gap-2 text-sm text-[var(--text-muted)] hover:text-[var(--text-primary)] cursor-pointer list-none">
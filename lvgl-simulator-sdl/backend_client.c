@@ -783,6 +783,7 @@ int backend_get_ams_unit(int printer_index, int ams_index, AmsUnitCInfo *info) {
     info->temperature = src->temperature * 10;  // Firmware uses Celsius * 10
     info->extruder = src->extruder;
     info->tray_count = src->tray_count;
+    info->kind = (src->id >= 128 && src->id <= 135) ? AMS_KIND_HT : AMS_KIND_AMS;
 
     for (int i = 0; i < src->tray_count && i < 4; i++) {
         strncpy(info->trays[i].tray_type, src->trays[i].tray_type, sizeof(info->trays[i].tray_type) - 1);
@@ -793,6 +794,19 @@ int backend_get_ams_unit(int printer_index, int ams_index, AmsUnitCInfo *info) {
     return 0;
 }
 
+// The simulator state has no external spool holder data
+int backend_get_external_count(int printer_index) {
+    (void)printer_index;
+    return 0;
+}
+
+int backend_get_external_unit(int printer_index, int external_index, AmsUnitCInfo *info) {
+    (void)printer_index;
+    (void)external_index;
+    (void)info;
+    return -1;
+}
+
 int backend_get_tray_now(int printer_index) {
     if (printer_index < 0 || printer_index >= g_state.printer_count) {
         return -1;
@@ -2489,6 +2503,34 @@ void nfc_clear_spool_just_added(void) {
     g_just_added_material[0] = '\0';
 }
 
+// =============================================================================
+// Tag Writing (no NFC hardware in the simulator)
+// =============================================================================
+
+static int g_tag_write_status = 0;  // TAG_WRITE_IDLE
+
+int nfc_write_spool_tag(const char *tag_id, int format) {
+    printf("[sim] Tag write requested for %s (format %d) - not supported\n", tag_id ? tag_id : "", format);
+    g_tag_write_status = 3;  // TAG_WRITE_FAILED
+    return -1;
+}
+
+int nfc_get_write_status(void) {
+    return g_tag_write_status;
+}
+
+int nfc_get_write_message(char *buf, int buf_len) {
+    if (!buf || buf_len <= 0) return -1;
+    const char *msg = g_tag_write_status == 3 ? "Tag writing needs the device" : "";
+    strncpy(buf, msg, buf_len - 1);
+    buf[buf_len - 1] = '\0';
+    return (int)strlen(buf);
+}
+
+void nfc_clear_write_status(void) {
+    g_tag_write_status = 0;
+}
+
 // =============================================================================
 // WiFi Status (synced from real device via backend)
 // =============================================================================
@@ -3058,3 +3100,232 @@ int backend_search_colors(const char *manufacturer, const char *material,
            count, manufacturer ? manufacturer : "", material ? material : "");
     return count;
 }
+
+// =============================================================================
+// UI requests (firmware start/poll API)
+// The firmware runs these on its backend worker. The simulator makes the
+// request right away in *_start and keeps the result for *_poll.
+// =============================================================================
+
+#define SIM_MAX_REQUEST_RESULTS 100
+
+static int request_state(bool ok) {
+    return ok ? BACKEND_REQUEST_DONE : BACKEND_REQUEST_FAILED;
+}
+
+static SpoolKProfileC g_spool_k_profile;
+static int g_spool_k_profile_state = BACKEND_REQUEST_NONE;
+
+int spool_k_profile_start(const char *spool_id, const char *printer_serial) {
+    if (!spool_id || !spool_id[0] || !printer_serial || !printer_serial[0]) return -1;
+    // Not found is a result too: cali_idx -1 and empty strings
+    if (!spool_get_k_profile_for_printer(spool_id, printer_serial, &g_spool_k_profile)) {
+        g_spool_k_profile.cali_idx = -1;
+    }
+    g_spool_k_profile_state = BACKEND_REQUEST_DONE;
+    return 0;
+}
+
+int spool_k_profile_poll(SpoolKProfileC *profile) {
+    if (!profile) return BACKEND_REQUEST_NONE;
+    if (g_spool_k_profile_state == BACKEND_REQUEST_DONE) *profile = g_spool_k_profile;
+    return g_spool_k_profile_state;
+}
+
+static int g_assign_result;
+static int g_assign_state = BACKEND_REQUEST_NONE;
+
+int backend_assign_spool_to_tray_start(const char *printer_serial, int ams_id, int tray_id, const char *spool_id) {
+    if (!printer_serial || !printer_serial[0] || !spool_id || !spool_id[0]) return -1;
+    g_assign_result = backend_assign_spool_to_tray(printer_serial, ams_id, tray_id, spool_id);
+    g_assign_state = BACKEND_REQUEST_DONE;
+    return 0;
+}
+
+int backend_assign_spool_to_tray_poll(int *result) {
+    if (!result) return BACKEND_REQUEST_NONE;
+    if (g_assign_state == BACKEND_REQUEST_DONE) *result = g_assign_result;
+    return g_assign_state;
+}
+
+static int g_sync_weight_state = BACKEND_REQUEST_NONE;
+
+int spool_sync_weight_start(const char *spool_id, int weight) {
+    if (!spool_id || !spool_id[0]) return -1;
+    g_sync_weight_state = request_state(spool_sync_weight(spool_id, weight));
+    return 0;
+}
+
+int spool_sync_weight_poll(void) {
+    return g_sync_weight_state;
+}
+
+static int g_add_spool_state = BACKEND_REQUEST_NONE;
+
+int spool_add_to_inventory_start(const char *tag_id, const char *vendor, const char *material,
+                                 const char *subtype, const char *color_name, uint32_t color_rgba,
+                                 int label_weight, int weight_current, const char *data_origin,
+                                 const char *tag_type, const char *slicer_filament) {
+    g_add_spool_state = request_state(spool_add_to_inventory(tag_id, vendor, material, subtype, color_name,
+                                                             color_rgba, label_weight, weight_current,
+                                                             data_origin, tag_type, slicer_filament));
+    return 0;
+}
+
+int spool_add_to_inventory_poll(void) {
+    return g_add_spool_state;
+}
+
+static UntaggedSpoolInfo g_untagged_spools[SIM_MAX_REQUEST_RESULTS];
+static int g_untagged_count;
+static int g_untagged_state = BACKEND_REQUEST_NONE;
+
+int spool_untagged_list_start(void) {
+    g_untagged_count = spool_get_untagged_list(g_untagged_spools, SIM_MAX_REQUEST_RESULTS);
+    g_untagged_state = request_state(g_untagged_count >= 0);
+    return 0;
+}
+
+int spool_untagged_list_poll(UntaggedSpoolInfo *spools, int max_count, int *count) {
+    if (!spools || !count || max_count <= 0) return BACKEND_REQUEST_NONE;
+    if (g_untagged_state == BACKEND_REQUEST_DONE) {
+        *count = g_untagged_count < max_count ? g_untagged_count : max_count;
+        memcpy(spools, g_untagged_spools, *count * sizeof(UntaggedSpoolInfo));
+    }
+    return g_untagged_state;
+}
+
+static int g_link_tag_state = BACKEND_REQUEST_NONE;
+
+int spool_link_tag_start(const char *spool_id, const char *tag_id, const char *tag_type) {
+    if (!spool_id || !spool_id[0] || !tag_id || !tag_id[0]) return -1;
+    g_link_tag_state = request_state(spool_link_tag(spool_id, tag_id, tag_type));
+    return 0;
+}
+
+int spool_link_tag_poll(int *result) {
+    if (!result) return BACKEND_REQUEST_NONE;
+    if (g_link_tag_state == BACKEND_REQUEST_DONE) *result = 0;
+    return g_link_tag_state;
+}
+
+static SlicerPreset g_slicer_presets[SIM_MAX_REQUEST_RESULTS];
+static int g_slicer_preset_count;
+static int g_slicer_presets_state = BACKEND_REQUEST_NONE;
+
+int backend_slicer_presets_start(void) {
+    g_slicer_preset_count = backend_get_slicer_presets(g_slicer_presets, SIM_MAX_REQUEST_RESULTS);
+    g_slicer_presets_state = request_state(g_slicer_preset_count >= 0);
+    return 0;
+}
+
+int backend_slicer_presets_poll(SlicerPreset *presets, int max_count, int *count) {
+    if (!presets || !count || max_count <= 0) return BACKEND_REQUEST_NONE;
+    if (g_slicer_presets_state == BACKEND_REQUEST_DONE) {
+        *count = g_slicer_preset_count < max_count ? g_slicer_preset_count : max_count;
+        memcpy(presets, g_slicer_presets, *count * sizeof(SlicerPreset));
+    }
+    return g_slicer_presets_state;
+}
+
+static PresetDetail g_preset_detail;
+static int g_preset_detail_state = BACKEND_REQUEST_NONE;
+
+int backend_preset_detail_start(const char *setting_id) {
+    if (!setting_id || !setting_id[0]) return -1;
+    g_preset_detail_state = request_state(backend_get_preset_detail(setting_id, &g_preset_detail));
+    return 0;
+}
+
+int backend_preset_detail_poll(PresetDetail *detail) {
+    if (!detail) return BACKEND_REQUEST_NONE;
+    if (g_preset_detail_state == BACKEND_REQUEST_DONE) *detail = g_preset_detail;
+    return g_preset_detail_state;
+}
+
+static KProfileInfo g_k_profiles[SIM_MAX_REQUEST_RESULTS];
+static int g_k_profile_count;
+static int g_k_profiles_state = BACKEND_REQUEST_NONE;
+
+int backend_k_profiles_start(const char *printer_serial, const char *nozzle_diameter) {
+    if (!printer_serial || !printer_serial[0]) return -1;
+    g_k_profile_count = backend_get_k_profiles(printer_serial,
+                                               nozzle_diameter && nozzle_diameter[0] ? nozzle_diameter : "0.4",
+                                               g_k_profiles, SIM_MAX_REQUEST_RESULTS);
+    g_k_profiles_state = request_state(g_k_profile_count >= 0);
+    return 0;
+}
+
+int backend_k_profiles_poll(KProfileInfo *profiles, int max_count, int *count) {
+    if (!profiles || !count || max_count <= 0) return BACKEND_REQUEST_NONE;
+    if (g_k_profiles_state == BACKEND_REQUEST_DONE) {
+        *count = g_k_profile_count < max_count ? g_k_profile_count : max_count;
+        memcpy(profiles, g_k_profiles, *count * sizeof(KProfileInfo));
+    }
+    return g_k_profiles_state;
+}
+
+static int g_slot_filament_state = BACKEND_REQUEST_NONE;
+
+int backend_set_slot_filament_start(const char *printer_serial, int ams_id, int tray_id,
+                                    const char *tray_info_idx, const char *setting_id,
+                                    const char *tray_type, const char *tray_sub_brands,
+                                    const char *tray_color, int nozzle_temp_min, int nozzle_temp_max) {
+    if (!printer_serial || !printer_serial[0]) return -1;
+    g_slot_filament_state = request_state(backend_set_slot_filament(printer_serial, ams_id, tray_id,
+                                                                    tray_info_idx, setting_id, tray_type,
+                                                                    tray_sub_brands, tray_color,
+                                                                    nozzle_temp_min, nozzle_temp_max));
+    return 0;
+}
+
+int backend_set_slot_filament_poll(void) {
+    return g_slot_filament_state;
+}
+
+static int g_slot_calibration_state = BACKEND_REQUEST_NONE;
+
+int backend_set_slot_calibration_start(const char *printer_serial, int ams_id, int tray_id,
+                                       int cali_idx, const char *filament_id, const char *setting_id,
+                                       const char *nozzle_diameter, float k_value, int nozzle_temp) {
+    if (!printer_serial || !printer_serial[0]) return -1;
+    g_slot_calibration_state = request_state(backend_set_slot_calibration(printer_serial, ams_id, tray_id,
+                                                                          cali_idx, filament_id, setting_id,
+                                                                          nozzle_diameter, k_value, nozzle_temp));
+    return 0;
+}
+
+int backend_set_slot_calibration_poll(void) {
+    return g_slot_calibration_state;
+}
+
+static int g_reset_slot_state = BACKEND_REQUEST_NONE;
+
+int backend_reset_slot_start(const char *printer_serial, int ams_id, int tray_id) {
+    if (!printer_serial || !printer_serial[0]) return -1;
+    g_reset_slot_state = request_state(backend_reset_slot(printer_serial, ams_id, tray_id));
+    return 0;
+}
+
+int backend_reset_slot_poll(void) {
+    return g_reset_slot_state;
+}
+
+static ColorCatalogEntry g_catalog_colors[SIM_MAX_REQUEST_RESULTS];
+static int g_catalog_color_count;
+static int g_catalog_colors_state = BACKEND_REQUEST_NONE;
+
+int backend_search_colors_start(const char *manufacturer, const char *material) {
+    g_catalog_color_count = backend_search_colors(manufacturer, material, g_catalog_colors, SIM_MAX_REQUEST_RESULTS);
+    g_catalog_colors_state = request_state(g_catalog_color_count >= 0);
+    return 0;
+}
+
+int backend_search_colors_poll(ColorCatalogEntry *colors, int max_count, int *count) {
+    if (!colors || !count || max_count <= 0) return BACKEND_REQUEST_NONE;
+    if (g_catalog_colors_state == BACKEND_REQUEST_DONE) {
+        *count = g_catalog_color_count < max_count ? g_catalog_color_count : max_count;
+        memcpy(colors, g_catalog_colors, *count * sizeof(ColorCatalogEntry));
+    }
+    return g_catalog_colors_state;
+}
@@ -135,9 +135,9 @@ const char *backend_fetch_cover_image(const char *serial);
 
 // Backend connection status (matches firmware BackendStatus)
 typedef struct {
-    int state;              // 0=Disconnected, 1=Discovering, 2=Connected, 3=Error
-    uint8_t server_ip[4];   // Server IP address (valid when state=2)
-    uint16_t server_port;   // Server port (valid when state=2)
+    int state;              // 0=Disconnected, 1=Discovering, 2=Connected, 3=Error, 4=Unauthorized, 5=CertificateMismatch
+    uint8_t server_ip[4];   // Server IP address (valid when state=2, 4 or 5)
+    uint16_t server_port;   // Server port (valid when state=2, 4 or 5)
     uint8_t printer_count;  // Number of printers cached
 } BackendStatus;
 
@@ -164,13 +164,20 @@ typedef struct {
     uint8_t remain;         // 0-100 percentage
 } AmsTrayCInfo;
 
+// AMS unit kinds (matches firmware AMS_KIND_*)
+#define AMS_KIND_AMS       0
+#define AMS_KIND_LITE      1
+#define AMS_KIND_HT        2
+#define AMS_KIND_EXTERNAL  3
+
 // AMS unit info (matches firmware AmsUnitCInfo)
 typedef struct {
     int id;                 // AMS unit ID
     int humidity;           // -1 if not available
     int16_t temperature;    // Celsius * 10, -1 if not available
     int8_t extruder;        // -1=unknown, 0=right, 1=left
-    uint8_t tray_count;     // Number of trays (1-4)
+    uint8_t tray_count;     // Number of trays (trays[] holds the first 4)
+    uint8_t kind;           // AMS_KIND_*
     AmsTrayCInfo trays[4];  // Tray data
 } AmsUnitCInfo;
 
@@ -179,6 +186,8 @@ void backend_get_status(BackendStatus *status);
 int backend_get_printer(int index, BackendPrinterInfo *info);  // Firmware-compatible
 int backend_get_ams_count(int printer_index);
 int backend_get_ams_unit(int printer_index, int ams_index, AmsUnitCInfo *info);
+int backend_get_external_count(int printer_index);
+int backend_get_external_unit(int printer_index, int external_index, AmsUnitCInfo *info);
 int backend_get_tray_now(int printer_index);
 int backend_get_tray_now_left(int printer_index);
 int backend_get_tray_now_right(int printer_index);
@@ -378,6 +387,12 @@ const char* nfc_get_just_added_vendor(void);
 const char* nfc_get_just_added_material(void);
 void nfc_clear_spool_just_added(void);
 
+// Tag writing (not supported in the simulator, always fails)
+int nfc_write_spool_tag(const char *tag_id, int format);
+int nfc_get_write_status(void);
+int nfc_get_write_message(char *buf, int buf_len);
+void nfc_clear_write_status(void);
+
 // =============================================================================
 // AMS Slot Assignment functions (configure printer AMS with filament/calibration)
 // =============================================================================
@@ -388,6 +403,7 @@ typedef enum {
     ASSIGN_RESULT_CONFIGURED = 1, // Slot configured immediately (spool was present)
     ASSIGN_RESULT_STAGED = 2,     // Assignment staged - waiting for spool insertion
     ASSIGN_RESULT_STAGED_REPLACE = 3,  // Assignment staged - wrong spool in slot, needs replacement
+    ASSIGN_RESULT_QUEUED = 4,     // Server offline - queued on the device (firmware only)
 } AssignResult;
 
 // Assign a spool to an AMS tray (sends filament settings to printer)
@@ -551,6 +567,71 @@ typedef struct {
 int backend_search_colors(const char *manufacturer, const char *material,
                           ColorCatalogEntry *colors, int max_count);
 
+// =============================================================================
+// UI requests (firmware start/poll API, used by the shared UI code)
+// =============================================================================
+
+// State of a request started from the UI (matches firmware ui_internal.h)
+// *_start returns 0 if submitted, -1 on invalid arguments; *_poll returns the
+// state and fills its outputs once DONE. The simulator completes each request
+// inside *_start; the firmware runs it on its backend worker.
+typedef enum {
+    BACKEND_REQUEST_NONE = -1,
+    BACKEND_REQUEST_PENDING = 0,
+    BACKEND_REQUEST_DONE = 1,
+    BACKEND_REQUEST_FAILED = 2,
+} BackendRequestState;
+
+// K-profile of a spool on a printer (cali_idx -1 if the spool has none)
+int spool_k_profile_start(const char *spool_id, const char *printer_serial);
+int spool_k_profile_poll(SpoolKProfileC *profile);
+
+// Assign a spool to an AMS tray (result is an AssignResult)
+int backend_assign_spool_to_tray_start(const char *printer_serial, int ams_id, int tray_id, const char *spool_id);
+int backend_assign_spool_to_tray_poll(int *result);
+
+int spool_sync_weight_start(const char *spool_id, int weight);
+int spool_sync_weight_poll(void);
+
+int spool_add_to_inventory_start(const char *tag_id, const char *vendor, const char *material,
+                                 const char *subtype, const char *color_name, uint32_t color_rgba,
+                                 int label_weight, int weight_current, const char *data_origin,
+                                 const char *tag_type, const char *slicer_filament);
+int spool_add_to_inventory_poll(void);
+
+int spool_untagged_list_start(void);
+int spool_untagged_list_poll(UntaggedSpoolInfo *spools, int max_count, int *count);
+
+// Link an NFC tag to a spool (result 0 = success; failures are FAILED)
+int spool_link_tag_start(const char *spool_id, const char *tag_id, const char *tag_type);
+int spool_link_tag_poll(int *result);
+
+int backend_slicer_presets_start(void);
+int backend_slicer_presets_poll(SlicerPreset *presets, int max_count, int *count);
+
+int backend_preset_detail_start(const char *setting_id);
+int backend_preset_detail_poll(PresetDetail *detail);
+
+int backend_k_profiles_start(const char *printer_serial, const char *nozzle_diameter);
+int backend_k_profiles_poll(KProfileInfo *profiles, int max_count, int *count);
+
+int backend_set_slot_filament_start(const char *printer_serial, int ams_id, int tray_id,
+                                    const char *tray_info_idx, const char *setting_id,
+                                    const char *tray_type, const char *tray_sub_brands,
+                                    const char *tray_color, int nozzle_temp_min, int nozzle_temp_max);
+int backend_set_slot_filament_poll(void);
+
+int backend_set_slot_calibration_start(const char *printer_serial, int ams_id, int tray_id,
+                                       int cali_idx, const char *filament_id, const char *setting_id,
+                                       const char *nozzle_diameter, float k_value, int nozzle_temp);
+int backend_set_slot_calibration_poll(void);
+
+int backend_reset_slot_start(const char *printer_serial, int ams_id, int tray_id);
+int backend_reset_slot_poll(void);
+
+int backend_search_colors_start(const char *manufacturer, const char *material);
+int backend_search_colors_poll(ColorCatalogEntry *colors, int max_count, int *count);
+
 // =============================================================================
 // Printer Management API
 // =============================================================================
@@ -4,7 +4,7 @@
  *
  * Supports:
  * - MIFARE Classic 1K (Bambu Lab tags) with HKDF key derivation
- * - NTAG (SpoolEase/OpenPrintTag with NDEF)
+ * - NTAG (SpoolEase/OpenPrintTag with NDEF), read and write
  */
 
 #include <SPI.h>
@@ -24,6 +24,35 @@
 #define CMD_GET_PRODUCT_VERSION 0x01
 #define CMD_SCAN_TAG            0x10
 #define CMD_READ_TAG_DATA       0x20  // New: Read tag blocks/pages
+#define CMD_WRITE_TAG           0x30  // Write NTAG pages
+
+// CMD_WRITE_TAG response status
+#define WRITE_OK                0
+#define WRITE_NO_TAG            1
+#define WRITE_FAILED            2
+#define WRITE_NOT_NTAG          3
+#define WRITE_READ_ONLY         4
+#define WRITE_OUT_OF_RANGE      5
+#define WRITE_BAD_REQUEST       6
+#define WRITE_NOT_NDEF          7
+
+// Framed protocol (v2)
+// Request:  [SOF, version, cmd, seq, len, payload[len], crc_hi, crc_lo]
+// Response: [SOF, version, seq, state, len, payload[len], crc_hi, crc_lo]
+// CRC-16/CCITT-FALSE over everything before the CRC. The response stays
+// readable until the next command, and reads BUSY while it is processed.
+// Requests without the SOF byte get the old unframed response.
+#define FRAME_SOF               0xB5
+#define PROTOCOL_VERSION        2
+#define FRAME_HEADER_LEN        5
+#define FRAME_OVERHEAD          (FRAME_HEADER_LEN + 2)
+#define FRAME_STATE_READY       0
+#define FRAME_STATE_BUSY        1
+#define FRAME_STATE_BAD_FRAME   2   // Request rejected (CRC, length or version)
+
+// Capabilities reported by CMD_GET_PRODUCT_VERSION
+#define CAP_WRITE_TAG           0x01
+#define BRIDGE_CAPS             (CAP_WRITE_TAG)
 
 // Tag types (from SAK byte)
 #define TAG_TYPE_UNKNOWN        0
@@ -38,6 +67,17 @@ volatile uint8_t cmdBuffer[64];
 volatile uint8_t cmdLength = 0;
 volatile bool cmdReady = false;
 
+// Payload of the current command (after [cmd, seq] or the frame header)
+uint8_t cmdPayloadOffset = 2;
+uint8_t cmdPayloadLength = 0;
+
+// Framed response state
+volatile bool respFramed = false;
+volatile bool respReady = true;
+volatile uint8_t respSeq = 0;
+uint8_t frameBuffer[200 + FRAME_OVERHEAD];
+uint8_t frameLength = 0;
+
 // Tag state
 uint8_t tagUid[10];
 uint8_t tagUidLen = 0;
@@ -70,8 +110,14 @@ const char BAMBU_CONTEXT[] = "RFID-A";
 uint8_t bambuKeys[96];
 bool keysGenerated = false;
 
+// Bambu tag data blocks, in the order they are sent in READ_TAG_DATA:
+// 1, 2, 4, 5 (material, type, color, weight), 6 (temperatures, drying),
+// 9 (tray UID), 10 (spool width), 12 (production date), 14 (length)
+const uint8_t BAMBU_BLOCKS[] = {1, 2, 4, 5, 6, 9, 10, 12, 14};
+const int BAMBU_BLOCK_COUNT = sizeof(BAMBU_BLOCKS);
+
 // Tag data storage
-uint8_t tagBlocks[5][16];  // Blocks 1, 2, 4, 5, 16 for Bambu
+uint8_t tagBlocks[BAMBU_BLOCK_COUNT][16];
 bool tagDataValid = false;
 
 // Command processing flag - prevents background scan interference
@@ -737,7 +783,7 @@ bool reactivateCard() {
     return true;
 }
 
-// Read Bambu tag blocks (1, 2, 4, 5)
+// Read Bambu tag blocks (BAMBU_BLOCKS)
 bool readBambuTagData() {
     if (!keysGenerated) {
         logSeq("Keys not generated!");
@@ -757,7 +803,6 @@ bool readBambuTagData() {
     pn5180_writeRegisterAndMask(0x00, 0xFFFFFFBF);  // Clear MFC_CRYPTO1_ON
     pn5180_writeRegister(0x03, 0xFFFFFFFF);  // Clear IRQs
 
-    const uint8_t blocksToRead[] = {1, 2, 4, 5};
     int currentSector = -1;
 
     tagDataValid = false;
@@ -785,8 +830,8 @@ bool readBambuTagData() {
         return false;
     }
 
-    for (int i = 0; i < 4; i++) {
-        uint8_t block = blocksToRead[i];
+    for (int i = 0; i < BAMBU_BLOCK_COUNT; i++) {
+        uint8_t block = BAMBU_BLOCKS[i];
         uint8_t sector = block / 4;
 
         // Authenticate if sector changed
@@ -892,6 +937,102 @@ bool ntag_readPages(uint8_t startPage, uint8_t* buf, uint8_t numPages) {
     return true;
 }
 
+// Write one NTAG page (WRITE 0xA2 + page + 4 bytes, tag answers with a 4-bit ACK)
+bool ntag_writePage(uint8_t page, const uint8_t* data) {
+    pn5180_writeRegister(0x03, 0xFFFFFFFF);
+    pn5180_setTransceiveMode();
+
+    // Command needs CRC; the ACK/NAK is 4 bits without CRC
+    pn5180_writeRegisterOrMask(0x19, 0x01);         // TX CRC on
+    pn5180_writeRegisterAndMask(0x12, 0xFFFFFFFE);  // RX CRC off
+
+    uint8_t writeCmd[6] = {0xA2, page, data[0], data[1], data[2], data[3]};
+    pn5180_sendData(writeCmd, 6, 0x00);
+    delay(10);  // EEPROM programming time (~4ms)
+
+    uint32_t rxStatus = pn5180_readRegister(0x13);
+    uint16_t rxLen = rxStatus & 0x1FF;
+    if (rxLen < 1) {
+        Serial.print("NTAG write: no ACK for page ");
+        Serial.println(page);
+        return false;
+    }
+
+    uint8_t ack = 0;
+    pn5180_readData(&ack, 1);
+    if ((ack & 0x0F) != 0x0A) {
+        Serial.print("NTAG write: NAK 0x");
+        Serial.print(ack, HEX);
+        Serial.print(" for page ");
+        Serial.println(page);
+        return false;
+    }
+    return true;
+}
+
+// Handle CMD_WRITE_TAG
+// Request payload: [start_page, page_count, data (page_count * 4 bytes)]
+// Response: [status, data area size / 8, CC access byte]
+// A page_count of 0 only checks the tag (capability container, page 3).
+void handleWriteTag() {
+    respLength = 3;
+    respBuffer[1] = 0;
+    respBuffer[2] = 0;
+
+    if (!tagPresent) {
+        respBuffer[0] = WRITE_NO_TAG;
+        return;
+    }
+    if (tagType != TAG_TYPE_NTAG) {
+        respBuffer[0] = WRITE_NOT_NTAG;
+        return;
+    }
+
+    // Only accepted framed: a corrupted write must never reach the tag
+    const volatile uint8_t *payload = &cmdBuffer[cmdPayloadOffset];
+    uint8_t startPage = (cmdPayloadLength >= 1) ? payload[0] : 0;
+    uint8_t pageCount = (cmdPayloadLength >= 2) ? payload[1] : 0;
+    if (!respFramed || cmdPayloadLength < 2 || cmdPayloadLength != 2 + pageCount * 4) {
+        respBuffer[0] = WRITE_BAD_REQUEST;
+        return;
+    }
+
+    // Capability container: E1 <version> <data area size / 8> <access>
+    uint8_t cc[4];
+    if (!ntag_readPages(3, cc, 1)) {
+        respBuffer[0] = WRITE_FAILED;
+        return;
+    }
+    respBuffer[1] = cc[2];
+    respBuffer[2] = cc[3];
+    if (cc[0] != 0xE1) {
+        respBuffer[0] = WRITE_NOT_NDEF;
+        return;
+    }
+    // Low nibble is write access (0x0 = writable, 0xF = read-only)
+    if ((cc[3] & 0x0F) != 0x00) {
+        respBuffer[0] = WRITE_READ_ONLY;
+        return;
+    }
+
+    // Data area starts at page 4
+    uint16_t lastPage = 4 + (cc[2] * 8) / 4;
+    if (pageCount > 0 && (startPage < 4 || startPage + pageCount > lastPage)) {
+        respBuffer[0] = WRITE_OUT_OF_RANGE;
+        return;
+    }
+
+    for (uint8_t i = 0; i < pageCount; i++) {
+        if (!ntag_writePage(startPage + i, (const uint8_t*)&payload[2 + i * 4])) {
+            // A NAK on an unlocked CC usually means the page is locked
+            respBuffer[0] = WRITE_FAILED;
+            return;
+        }
+    }
+
+    respBuffer[0] = WRITE_OK;
+}
+
 // ============================================================================
 // Tag Activation (with SAK detection)
 // ============================================================================
@@ -1132,14 +1273,70 @@ bool scanTag() {
 // I2C Command Processing
 // ============================================================================
 
+// CRC-16/CCITT-FALSE (poly 0x1021, init 0xFFFF)
+uint16_t crc16(uint16_t crc, const volatile uint8_t *data, uint16_t len) {
+    for (uint16_t i = 0; i < len; i++) {
+        crc ^= (uint16_t)data[i] << 8;
+        for (uint8_t bit = 0; bit < 8; bit++) {
+            crc = (crc & 0x8000) ? (crc << 1) ^ 0x1021 : crc << 1;
+        }
+    }
+    return crc;
+}
+
+// Check a framed request: version, length and CRC
+bool validateFrame() {
+    if (cmdLength < FRAME_OVERHEAD || cmdBuffer[1] != PROTOCOL_VERSION) return false;
+    uint8_t len = cmdBuffer[4];
+    if (cmdLength != FRAME_OVERHEAD + len) return false;
+    uint16_t crc = crc16(0xFFFF, cmdBuffer, FRAME_HEADER_LEN + len);
+    return cmdBuffer[FRAME_HEADER_LEN + len] == (crc >> 8) &&
+           cmdBuffer[FRAME_HEADER_LEN + len + 1] == (crc & 0xFF);
+}
+
+// Build the response frame from respBuffer and mark it ready
+void finishFrame(uint8_t seq, uint8_t state) {
+    frameBuffer[0] = FRAME_SOF;
+    frameBuffer[1] = PROTOCOL_VERSION;
+    frameBuffer[2] = seq;
+    frameBuffer[3] = state;
+    frameBuffer[4] = respLength;
+    memcpy(&frameBuffer[FRAME_HEADER_LEN], (const void*)respBuffer, respLength);
+    uint16_t crc = crc16(0xFFFF, frameBuffer, FRAME_HEADER_LEN + respLength);
+    frameBuffer[FRAME_HEADER_LEN + respLength] = crc >> 8;
+    frameBuffer[FRAME_HEADER_LEN + respLength + 1] = crc & 0xFF;
+    frameLength = FRAME_OVERHEAD + respLength;
+    // A newer command arrived meanwhile: it stays BUSY until processed
+    if (respSeq == seq) {
+        respReady = true;
+    }
+}
+
 void processCommand() {
     if (cmdLength == 0) return;
 
     processingCommand = true;  // Prevent background scan interference
 
-    uint8_t cmd = cmdBuffer[0];
-    // Extract sequence number if present (2nd byte)
-    cmdSeq = (cmdLength >= 2) ? cmdBuffer[1] : 0;
+    uint8_t cmd;
+    if (respFramed) {
+        if (!validateFrame()) {
+            Serial.println("Bad request frame");
+            respLength = 0;
+            finishFrame(respSeq, FRAME_STATE_BAD_FRAME);
+            processingCommand = false;
+            return;
+        }
+        cmd = cmdBuffer[2];
+        cmdSeq = cmdBuffer[3];
+        cmdPayloadOffset = FRAME_HEADER_LEN;
+        cmdPayloadLength = cmdBuffer[4];
+    } else {
+        cmd = cmdBuffer[0];
+        // Extract sequence number if present (2nd byte)
+        cmdSeq = (cmdLength >= 2) ? cmdBuffer[1] : 0;
+        cmdPayloadOffset = 2;
+        cmdPayloadLength = (cmdLength > 2) ? cmdLength - 2 : 0;
+    }
 
     Serial.print("[#");
     Serial.print(cmdSeq);
@@ -1154,10 +1351,14 @@ void processCommand() {
             break;
 
         case CMD_GET_PRODUCT_VERSION:
+            // [status, major, minor, protocol version, capabilities]
+            // (unframed readers only take the first 3 bytes)
             respBuffer[0] = 0;
             respBuffer[1] = cachedVersion[0];
             respBuffer[2] = cachedVersion[1];
-            respLength = 3;
+            respBuffer[3] = PROTOCOL_VERSION;
+            respBuffer[4] = BRIDGE_CAPS;
+            respLength = 5;
             break;
 
         case CMD_SCAN_TAG:
@@ -1200,24 +1401,24 @@ void processCommand() {
                 // [2] = uid length
                 // [3..3+uidLen] = uid
                 // Then for each block: 16 bytes
-                // Blocks: 1, 2, 4, 5 = 64 bytes total
+                // Blocks: BAMBU_BLOCKS = 144 bytes total (older bridges sent 1, 2, 4, 5 only)
                 respBuffer[0] = 0;  // Success
                 respBuffer[1] = tagType;
                 respBuffer[2] = tagUidLen;
                 memcpy((void*)&respBuffer[3], tagUid, tagUidLen);
                 int offset = 3 + tagUidLen;
-                memcpy((void*)&respBuffer[offset], tagBlocks[0], 16); offset += 16;  // Block 1
-                memcpy((void*)&respBuffer[offset], tagBlocks[1], 16); offset += 16;  // Block 2
-                memcpy((void*)&respBuffer[offset], tagBlocks[2], 16); offset += 16;  // Block 4
-                memcpy((void*)&respBuffer[offset], tagBlocks[3], 16); offset += 16;  // Block 5
+                for (int i = 0; i < BAMBU_BLOCK_COUNT; i++) {
+                    memcpy((void*)&respBuffer[offset], tagBlocks[i], 16);
+                    offset += 16;
+                }
                 respLength = offset;
                 Serial.print("Sending ");
                 Serial.print(respLength);
                 Serial.println(" bytes of tag data");
             } else if (tagType == TAG_TYPE_NTAG) {
-                // Read NTAG pages 4-20 (NDEF data area)
-                uint8_t ntagData[68];  // 17 pages * 4 bytes
-                if (!ntag_readPages(4, ntagData, 17)) {
+                // Read NTAG pages 4-39 (NDEF data area, all of NTAG213 user memory)
+                uint8_t ntagData[144];  // 36 pages * 4 bytes
+                if (!ntag_readPages(4, ntagData, 36)) {
                     respBuffer[0] = 2;  // Read error
                     respLength = 1;
                     break;
@@ -1227,8 +1428,8 @@ void processCommand() {
                 respBuffer[2] = tagUidLen;
                 memcpy((void*)&respBuffer[3], tagUid, tagUidLen);
                 int offset = 3 + tagUidLen;
-                memcpy((void*)&respBuffer[offset], ntagData, 68);
-                respLength = offset + 68;
+                memcpy((void*)&respBuffer[offset], ntagData, 144);
+                respLength = offset + 144;
                 Serial.print("Sending ");
                 Serial.print(respLength);
                 Serial.println(" bytes of NTAG data");
@@ -1238,11 +1439,21 @@ void processCommand() {
             }
             break;
 
+        case CMD_WRITE_TAG:
+            handleWriteTag();
+            // Keep the card selected for the next chunk / read-back
+            scanProtectionUntil = millis() + 2000;
+            break;
+
         default:
             respBuffer[0] = 0xFF;
             respLength = 1;
     }
 
+    if (respFramed) {
+        finishFrame(cmdSeq, FRAME_STATE_READY);
+    }
+
     processingCommand = false;  // Allow background scans again
 }
 
@@ -1255,6 +1466,12 @@ void i2cReceive(int n) {
     while (Wire.available() && cmdLength < 64) {
         cmdBuffer[cmdLength++] = Wire.read();
     }
+    // A framed command reads BUSY (with its seq) until processed
+    respFramed = (cmdLength > 0 && cmdBuffer[0] == FRAME_SOF);
+    if (respFramed) {
+        respSeq = (cmdLength >= 4) ? cmdBuffer[3] : 0;
+        respReady = false;
+    }
     for (int i = 0; i < cmdLength; i++) {
         Serial.print(cmdBuffer[i], HEX);
         Serial.print(" ");
@@ -1265,7 +1482,19 @@ void i2cReceive(int n) {
 
 void i2cRequest() {
     Serial.print("I2C REQ: ");
-    if (respLength > 0) {
+    if (respFramed) {
+        if (respReady) {
+            // Kept until the next command so the ESP32 can re-read it
+            Wire.write(frameBuffer, frameLength);
+        } else {
+            uint8_t busy[FRAME_OVERHEAD] = {FRAME_SOF, PROTOCOL_VERSION, respSeq, FRAME_STATE_BUSY, 0};
+            uint16_t crc = crc16(0xFFFF, busy, FRAME_HEADER_LEN);
+            busy[FRAME_HEADER_LEN] = crc >> 8;
+            busy[FRAME_HEADER_LEN + 1] = crc & 0xFF;
+            Wire.write(busy, FRAME_OVERHEAD);
+        }
+        Serial.println(respReady ? "frame" : "busy");
+    } else if (respLength > 0) {
         Serial.print(respLength);
         Serial.println(" bytes");
         Wire.write((uint8_t*)respBuffer, respLength);
@@ -1388,7 +1617,8 @@ void loop() {
     }
 
     if (cmdReady) {
-        processCommand();
+        // Cleared first so a command arriving during processing is not lost
         cmdReady = false;
+        processCommand();
     }
 }
@@ -1,3 +1,3 @@
 #!/bin/bash
 
-./test_frontend.sh && ./test_backend.sh --full && ./test_docker.sh
+./test_frontend.sh && ./test_backend.sh --full && ./test_firmware.sh && ./test_docker.sh
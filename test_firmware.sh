@@ -0,0 +1,6 @@
+#!/bin/sh
+
+cd firmware-core
+cargo clippy --all-targets --all-features -- -D warnings
+cargo test
+cd ..
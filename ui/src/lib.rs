@@ -9,6 +9,7 @@
 
 #![allow(dead_code)]
 
+pub use spoolbuddy_colors as color_names;
 pub mod theme;
 pub mod screens;
 pub mod widgets;
@@ -2,7 +2,7 @@
 //!
 //! Step 1: Empty Scale
 //! ┌──────────────────────────┐
-//! │ Scale Calibration (1/2)  │
+//! │ Scale Calibration: Zero  │
 //! ├──────────────────────────┤
 //! │                          │
 //! │  Remove everything from  │
@@ -13,9 +13,9 @@
 //! │  [NEXT]        [CANCEL]  │
 //! └──────────────────────────┘
 //!
-//! Step 2: Place Weight
+//! Step 2..n: Place Weights (repeated per reference weight)
 //! ┌──────────────────────────┐
-//! │ Scale Calibration (2/2)  │
+//! │ Scale Calibration: 2     │
 //! ├──────────────────────────┤
 //! │                          │
 //! │  Place 500g calibration  │
@@ -23,9 +23,12 @@
 //! │                          │
 //! │  Current: 498.2g         │
 //! │  Target:  500.0g         │
+//! │  Points:  200g           │
 //! │                          │
-//! │  [CALIBRATE]   [CANCEL]  │
+//! │ [ADD] [FINISH] [CANCEL]  │
 //! └──────────────────────────┘
+//!
+//! Three or more points give a quadratic fit, fewer a linear one.
 
 use crate::theme::{self, spacing};
 use crate::widgets::Button;
@@ -49,6 +52,9 @@ pub enum CalibrationStep {
     Complete,
 }
 
+/// Reference weights per calibration
+pub const MAX_CAL_POINTS: usize = 8;
+
 /// Calibration state
 #[derive(Clone, Default)]
 pub struct CalibrationState {
@@ -56,6 +62,12 @@ pub struct CalibrationState {
     pub target_weight: f32,
     pub zero_offset: i32,
     pub ready_to_advance: bool,
+    /// Reference weights measured so far (grams)
+    pub points: heapless::Vec<f32, MAX_CAL_POINTS>,
+    /// Whether the fitted curve is quadratic (else linear)
+    pub quadratic: bool,
+    /// Largest residual of the fit (grams)
+    pub max_error: f32,
 }
 
 impl CalibrationState {
@@ -65,6 +77,9 @@ impl CalibrationState {
             target_weight: 500.0, // Default 500g calibration weight
             zero_offset: 0,
             ready_to_advance: false,
+            points: heapless::Vec::new(),
+            quadratic: false,
+            max_error: 0.0,
         }
     }
 }
@@ -112,15 +127,19 @@ impl CalibrationScreen {
             .into_styled(PrimitiveStyle::with_fill(theme.status_bar_bg))
             .draw(display)?;
 
-        // Title with step number
-        let title = match cal_state.step {
-            CalibrationStep::EmptyScale => "Scale Calibration (1/2)",
-            CalibrationStep::PlaceWeight => "Scale Calibration (2/2)",
-            CalibrationStep::Complete => "Calibration Complete",
+        // Title with point number
+        let mut title: heapless::String<32> = heapless::String::new();
+        let _ = match cal_state.step {
+            CalibrationStep::EmptyScale => core::fmt::write(&mut title, format_args!("Scale Calibration: Zero")),
+            CalibrationStep::PlaceWeight => core::fmt::write(
+                &mut title,
+                format_args!("Scale Calibration: {}", cal_state.points.len() + 1),
+            ),
+            CalibrationStep::Complete => core::fmt::write(&mut title, format_args!("Calibration Complete")),
         };
 
         let title_style = MonoTextStyle::new(&FONT_10X20, theme.text_primary);
-        Text::new(title, Point::new(spacing::MD, 32), title_style).draw(display)?;
+        Text::new(&title, Point::new(spacing::MD, 32), title_style).draw(display)?;
 
         // Main content card
         let card_margin = 40;
@@ -150,7 +169,7 @@ impl CalibrationScreen {
                 Self::render_place_weight_step(display, state, cal_state, content_x, content_y)?;
             }
             CalibrationStep::Complete => {
-                Self::render_complete_step(display, content_x, content_y)?;
+                Self::render_complete_step(display, cal_state, content_x, content_y)?;
             }
         }
 
@@ -185,15 +204,30 @@ impl CalibrationScreen {
                 cancel_button.draw(display)?;
             }
             CalibrationStep::PlaceWeight => {
-                // Calibrate button
-                let cal_button = Button::new(
+                // Add point button
+                let add_button = Button::new(
                     Point::new(card_x + spacing::MD, button_y),
                     Size::new(button_width, button_height),
-                    "CALIBRATE",
+                    "ADD POINT",
                 )
                 .with_style(ButtonStyle::Primary)
                 .with_large_font();
-                cal_button.draw(display)?;
+                add_button.draw(display)?;
+
+                // Finish button (once there is a point to fit)
+                let finish_style = if cal_state.points.is_empty() {
+                    ButtonStyle::Secondary
+                } else {
+                    ButtonStyle::Primary
+                };
+                let finish_button = Button::new(
+                    Point::new((DISPLAY_WIDTH as i32 - button_width as i32) / 2, button_y),
+                    Size::new(button_width, button_height),
+                    "FINISH",
+                )
+                .with_style(finish_style)
+                .with_large_font();
+                finish_button.draw(display)?;
 
                 // Cancel button
                 let cancel_button = Button::new(
@@ -314,11 +348,26 @@ impl CalibrationScreen {
         let _ = core::fmt::write(&mut diff_text, format_args!("Difference: {:.1}g", diff));
         Text::new(&diff_text, Point::new(x, y + 190), diff_style).draw(display)?;
 
+        // Reference weights measured so far
+        if !cal_state.points.is_empty() {
+            let mut points_text: heapless::String<64> = heapless::String::new();
+            let _ = points_text.push_str("Points:");
+            for weight in &cal_state.points {
+                let _ = core::fmt::write(&mut points_text, format_args!(" {:.0}g", weight));
+            }
+            Text::new(&points_text, Point::new(x, y + 215), detail_style).draw(display)?;
+        }
+
         Ok(())
     }
 
     /// Render completion step
-    fn render_complete_step<D>(display: &mut D, x: i32, y: i32) -> Result<(), D::Error>
+    fn render_complete_step<D>(
+        display: &mut D,
+        cal_state: &CalibrationState,
+        x: i32,
+        y: i32,
+    ) -> Result<(), D::Error>
     where
         D: DrawTarget<Color = Rgb565>,
     {
@@ -340,6 +389,19 @@ impl CalibrationScreen {
         )
         .draw(display)?;
 
+        // Fit report
+        let mut fit_text: heapless::String<64> = heapless::String::new();
+        let _ = core::fmt::write(
+            &mut fit_text,
+            format_args!(
+                "{} fit, {} points, max error {:.1}g",
+                if cal_state.quadratic { "Quadratic" } else { "Linear" },
+                cal_state.points.len(),
+                cal_state.max_error
+            ),
+        );
+        Text::new(&fit_text, Point::new(x, y + 170), detail_style).draw(display)?;
+
         Ok(())
     }
 
@@ -352,4 +414,19 @@ impl CalibrationScreen {
             CalibrationStep::Complete => CalibrationStep::Complete,
         };
     }
+
+    /// Record a measured reference weight and stay on the weight step.
+    /// Returns false when no more points fit.
+    pub fn add_point(weight_grams: f32) -> bool {
+        let state = Self::get_state();
+        state.points.push(weight_grams).is_ok()
+    }
+
+    /// Show the fit result of the measured points
+    pub fn finish(quadratic: bool, max_error: f32) {
+        let state = Self::get_state();
+        state.quadratic = quadratic;
+        state.max_error = max_error;
+        state.step = CalibrationStep::Complete;
+    }
 }